@@ -34,7 +34,7 @@ impl ToolRegistry {
                             "paths": {"type": "string", "description": "File path or glob pattern (e.g., \"src/**/*.rs\")"},
                             "node_type": {
                                 "type": "string",
-                                "enum": ["struct-literal", "match-arm", "enum-usage", "function-call", "method-call", "macro-call", "identifier", "type-ref", "struct", "enum", "function", "impl-method", "trait", "trait-impl", "const", "static", "type-alias", "mod"],
+                                "enum": ["struct-literal", "match-arm", "enum-usage", "function-call", "method-call", "macro-call", "identifier", "type-ref", "lifetime", "generic-param", "struct", "enum", "function", "impl-method", "trait-method", "trait", "trait-impl", "const", "static", "type-alias", "mod"],
                                 "description": "Type of AST node to inspect. Omit to search ALL types with grouped output. v0.5.5: 'trait-impl' lists implementors of a trait (filter via --name)."
                             },
                             "name": {"type": "string", "description": "Optional name filter (e.g., \"Shadow\", \"Operator::Error\", \"unwrap\", \"View::Rectangle\"). v0.5.3: Use '*::StructName' wildcard to match all qualified paths."},
@@ -44,7 +44,22 @@ impl ToolRegistry {
                             "include_comments": {"type": "boolean", "default": true, "description": "Include preceding comments (doc and regular) in output"},
                             "format": {"type": "string", "enum": ["snippets", "locations", "json"], "default": "snippets"},
                             "limit": {"type": "integer", "description": "Limit number of results (like 'head -N')"},
-                            "context": {"type": "integer", "description": "v0.5.5: prepend N raw lines before each snippet match, like 'grep -B N'"}
+                            "context": {"type": "integer", "description": "v0.5.5: prepend N raw lines before each snippet match, like 'grep -B N'"},
+                            "arity": {"type": "integer", "description": "Only match functions/methods with exactly N parameters"},
+                            "min_arity": {"type": "integer", "description": "Only match functions/methods with at least N parameters"},
+                            "max_arity": {"type": "integer", "description": "Only match functions/methods with at most N parameters"},
+                            "exclude_self": {"type": "boolean", "default": false, "description": "Don't count a self receiver toward arity"},
+                            "required_only": {"type": "boolean", "default": false, "description": "Only trait methods without a default body"},
+                            "default_only": {"type": "boolean", "default": false, "description": "Only trait methods that provide a default body"},
+                            "exclude_name": {"type": "array", "items": {"type": "string"}, "description": "Skip matches whose own name matches one of these globs (e.g. \"test_*\")"},
+                            "exclude_in": {"type": "array", "items": {"type": "string"}, "description": "Skip matches inside a function/type/module whose name matches one of these globs"},
+                            "duplicate_variants": {"type": "boolean", "default": false, "description": "Report enum variants with identical fields (use name to pick the enum)"},
+                            "field_method_collisions": {"type": "boolean", "default": false, "description": "Report struct fields named like a method in an impl of the same type (use name to pick the struct)"},
+                            "in_macro_call": {"type": "string", "description": "Scan the token streams of this macro's invocations (e.g. \"bitflags\") for identifiers; results are token-based (node_type \"macro-token\"), not AST matches"},
+                            "name_regex": {"type": "string", "description": "Regex the names must match, instead of name (unanchored)"},
+                            "callers": {"type": "string", "description": "List calls to this function or method, grouped by the enclosing function"},
+                            "unused": {"type": "boolean", "description": "Report private fns, structs and consts never referenced outside their own definition (dead-code candidates)"},
+                            "duplicates": {"type": "boolean", "description": "Report functions and methods with identical bodies, ignoring whitespace, comments and local names (node_type limits it to function, impl-method or trait-method)"}
                         },
                         "required": ["paths"]
                     }),
@@ -144,7 +159,7 @@ impl ToolRegistry {
                             "node_type": {"type": "string", "enum": ["function-call", "identifier", "enum-variant", "type-ref"], "description": "Granular AST node type. Mutually exclusive with --kind."},
                             "enum_path": {"type": "string", "description": "Qualified enum path for variant renames (e.g., 'types::Status')"},
                             "function_path": {"type": "string", "description": "Module path for function (optional)"},
-                            "edit_mode": {"type": "string", "enum": ["surgical", "reformat"], "default": "surgical", "description": "Edit mode: surgical (preserves formatting, default) or reformat"},
+                            "edit_mode": {"type": "string", "enum": ["surgical", "reformat", "rustfmt"], "default": "surgical", "description": "Edit mode: surgical (preserves formatting, default), reformat (prettyplease) or rustfmt (surgical, then the project's rustfmt)"},
                             "validate": {"type": "boolean", "default": true, "description": "Validate with cargo check"},
                             "apply": {"type": "boolean", "default": false, "description": "Apply changes (default is dry-run)"}
                         },
@@ -156,16 +171,16 @@ impl ToolRegistry {
                 // ============================================================
                 Tool {
                     name: "transform",
-                    description: "Generic transformation tool - comment, remove, or replace any AST nodes",
+                    description: "Generic transformation tool - comment, remove, replace, or wrap any AST nodes",
                     input_schema: json!({
                         "type": "object",
                         "properties": {
                             "paths": {"type": "string"},
                             "node_type": {"type": "string", "enum": ["macro-call", "method-call", "function-call", "enum-usage", "struct-literal", "match-arm", "identifier", "type-ref"]},
-                            "action": {"type": "string", "enum": ["comment", "remove", "replace"]},
+                            "action": {"type": "string", "enum": ["comment", "remove", "replace", "wrap"]},
                             "name": {"type": "string"},
                             "content_filter": {"type": "string"},
-                            "with": {"type": "string", "description": "Replacement code (required if action=replace)"},
+                            "with": {"type": "string", "description": "Replacement code (required if action=replace), or a template with {} for the original code (action=wrap)"},
                             "apply": {"type": "boolean", "default": false}
                         },
                         "required": ["paths", "node_type", "action"]
@@ -281,6 +296,37 @@ impl ToolRegistry {
                         "required": ["path"]
                     }),
                 },
+                // ============================================================
+                // NAVIGATION (2)
+                // One-call answers to "what is this type" and "who calls this",
+                // run in-process and returned as structured JSON.
+                // ============================================================
+                Tool {
+                    name: "describe_type",
+                    description: "Everything about one type in a single call: its definition with fields or variants, every impl block (inherent and trait) with method signatures and call counts, and reference counts by category (struct literals, fields, signatures, impls, paths, imports, type refs). Matched by name (last path segment). Returns JSON.",
+                    input_schema: json!({
+                        "type": "object",
+                        "properties": {
+                            "type": {"type": "string", "description": "Type name (e.g., \"Config\" or \"crate::app::Config\")"},
+                            "paths": {"type": "string", "description": "File path or glob pattern (e.g., \"src/**/*.rs\")"},
+                            "exclude": {"type": "array", "items": {"type": "string"}, "description": "Glob patterns of paths to leave out"}
+                        },
+                        "required": ["type", "paths"]
+                    }),
+                },
+                Tool {
+                    name: "find_callers",
+                    description: "Who calls a function: call sites grouped by enclosing function (`main`, `Type::method`), most calls first. An unqualified name matches `name(..)`, `Path::name(..)` and `.name(..)`; `Type::method` matches that type's path calls plus `Self::method`/`self.method()` inside its impls. Same as `find --callers`. Returns JSON.",
+                    input_schema: json!({
+                        "type": "object",
+                        "properties": {
+                            "function": {"type": "string", "description": "Function or method name (e.g., \"process\" or \"Worker::process\")"},
+                            "paths": {"type": "string", "description": "File path or glob pattern (e.g., \"src/**/*.rs\")"},
+                            "exclude": {"type": "array", "items": {"type": "string"}, "description": "Glob patterns of paths to leave out"}
+                        },
+                        "required": ["function", "paths"]
+                    }),
+                },
             ],
         }
     }
@@ -294,8 +340,11 @@ impl ToolRegistry {
 
         // In-process dispatch for tools backed by the rs-hack lib API.
         // Returns serialized JSON; bypasses argv → CLI → stdout marshalling.
-        if name == "find" {
-            return Self::call_find_inproc(&arguments);
+        match name {
+            "find" => return Self::call_find_inproc(&arguments),
+            "describe_type" => return Self::call_describe_type_inproc(&arguments),
+            "find_callers" => return Self::call_find_callers_inproc(&arguments),
+            _ => {}
         }
 
         // Map tool name to rs-hack command and build arguments
@@ -352,6 +401,26 @@ impl ToolRegistry {
         let str_arg = |k: &str| -> Option<String> {
             arguments.get(k).and_then(|v| v.as_str()).map(String::from)
         };
+        let usize_arg = |k: &str| -> Option<usize> {
+            arguments
+                .get(k)
+                .and_then(|v| v.as_u64())
+                .map(|n| n as usize)
+        };
+        let bool_arg =
+            |k: &str| -> bool { arguments.get(k).and_then(|v| v.as_bool()).unwrap_or(false) };
+        let strs_arg = |k: &str| -> Vec<String> {
+            arguments
+                .get(k)
+                .and_then(|v| v.as_array())
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|v| v.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
 
         let paths: Vec<PathBuf> = arguments
             .get("paths")
@@ -367,6 +436,10 @@ impl ToolRegistry {
         let args = FindArgs {
             paths,
             exclude: Vec::new(),
+            collect: Default::default(),
+            verbose: Default::default(),
+            strict: false,
+            scope: Default::default(),
             kind: str_arg("kind"),
             node_type: str_arg("node_type"),
             name: str_arg("name"),
@@ -374,16 +447,86 @@ impl ToolRegistry {
             content_filter: str_arg("content_filter"),
             field_name: str_arg("field_name"),
             include_comments,
-            context: arguments
-                .get("context")
-                .and_then(|v| v.as_u64())
-                .map(|n| n as usize),
+            context: usize_arg("context"),
+            arity: usize_arg("arity"),
+            min_arity: usize_arg("min_arity"),
+            max_arity: usize_arg("max_arity"),
+            exclude_self: bool_arg("exclude_self"),
+            required_only: bool_arg("required_only"),
+            default_only: bool_arg("default_only"),
+            exclude_names: strs_arg("exclude_name"),
+            exclude_in: strs_arg("exclude_in"),
+            duplicate_variants: bool_arg("duplicate_variants"),
+            field_method_collisions: bool_arg("field_method_collisions"),
+            in_macro_call: str_arg("in_macro_call"),
+            name_regex: str_arg("name_regex"),
+            callers: str_arg("callers"),
+            unused: bool_arg("unused"),
+            duplicates: bool_arg("duplicates"),
         };
 
         let result = run(&args)?;
         Ok(serde_json::to_string_pretty(&result)?)
     }
 
+    /// In-process `describe_type`: the type's definitions, impls with methods and call
+    /// counts, and reference counts, as JSON.
+    fn call_describe_type_inproc(arguments: &Value) -> Result<String> {
+        let type_name = arguments
+            .get("type")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("describe_type: 'type' is required"))?;
+        let description = rs_hack::commands::describe::run(
+            &Self::paths_arg(arguments, "describe_type")?,
+            type_name,
+            &Self::exclude_arg(arguments),
+            &rs_hack::files::CollectOptions::default(),
+        )?;
+        Ok(serde_json::to_string_pretty(&description)?)
+    }
+
+    /// In-process `find_callers`: `find --callers` through the lib, returning the caller
+    /// groups as JSON.
+    fn call_find_callers_inproc(arguments: &Value) -> Result<String> {
+        use rs_hack::commands::find::{FindArgs, FindResult, run};
+
+        let function = arguments
+            .get("function")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("find_callers: 'function' is required"))?;
+        let args = FindArgs {
+            paths: Self::paths_arg(arguments, "find_callers")?,
+            exclude: Self::exclude_arg(arguments),
+            callers: Some(function.to_string()),
+            ..Default::default()
+        };
+        let FindResult::Callers { groups } = run(&args)? else {
+            return Err(anyhow!("find_callers: unexpected result kind"));
+        };
+        Ok(serde_json::to_string_pretty(&groups)?)
+    }
+
+    fn paths_arg(arguments: &Value, tool: &str) -> Result<Vec<std::path::PathBuf>> {
+        arguments
+            .get("paths")
+            .and_then(|v| v.as_str())
+            .map(|s| vec![std::path::PathBuf::from(s)])
+            .ok_or_else(|| anyhow!("{}: 'paths' is required", tool))
+    }
+
+    fn exclude_arg(arguments: &Value) -> Vec<String> {
+        arguments
+            .get("exclude")
+            .and_then(|v| v.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     fn build_command(&self, tool_name: &str, arguments: &Value) -> Result<(String, Vec<String>)> {
         let mut args = Vec::new();
 
@@ -0,0 +1,518 @@
+//! `#[cfg(...)]` evaluation for `--cfg`.
+//!
+//! rs-hack parses every item whatever its `#[cfg]`. Given `--cfg` options, an item whose
+//! `#[cfg]` predicates are false under them is treated as compiled out: the editor's `Scope`
+//! prunes it from the syntax tree operations and `find` work on, so they never see it. Without
+//! `--cfg`, edits inside any `#[cfg]`-gated item are counted so the CLI can say to double-check
+//! them.
+//!
+//! `--skip-tests` and `--skip-attr` work the same way for code marked by an attribute
+//! (`#[cfg(test)]`, `#[test]`, ...), whatever `--cfg` says, and the matches left out there are
+//! counted. `--in-module` is the other way around: everything outside the given inline module
+//! is left out, and counted the same.
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+use anyhow::Result;
+use quote::ToTokens;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+use syn::visit_mut::VisitMut;
+use syn::{Attribute, Meta};
+
+/// The attributes `--skip-tests` stands for.
+pub const TEST_ATTRS: &[&str] = &["cfg(test)", "test"];
+
+fn compact(text: &str) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// Whether `attr` is one of `skip`: the whole attribute (`cfg(test)`), or its path alone so
+/// `tokio::test` also matches `#[tokio::test(flavor = "multi_thread")]`.
+fn skips(attr: &Attribute, skip: &[String]) -> bool {
+    let meta = compact(&attr.meta.to_token_stream().to_string());
+    let path = compact(&attr.path().to_token_stream().to_string());
+    skip.iter().any(|s| *s == meta || *s == path)
+}
+
+/// The set cfg options: names (`unix`, `test`) and key-value pairs (`feature = "serde"`).
+/// Like rustc, anything not in the set is false.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CfgSet {
+    options: HashSet<(String, Option<String>)>,
+}
+
+impl CfgSet {
+    /// Parse `--cfg` values: `unix`, `feature=serde` or `feature="serde"`.
+    pub fn parse<'a>(flags: impl IntoIterator<Item = &'a str>) -> Result<Self> {
+        let mut options = HashSet::new();
+        for flag in flags {
+            let (key, value) = match flag.split_once('=') {
+                Some((key, value)) => (key.trim(), Some(value.trim().trim_matches('"'))),
+                None => (flag.trim(), None),
+            };
+            if syn::parse_str::<syn::Ident>(key).is_err() {
+                anyhow::bail!("Invalid --cfg `{}`: expected NAME or KEY=VALUE", flag);
+            }
+            options.insert((key.to_string(), value.map(str::to_string)));
+        }
+        Ok(Self { options })
+    }
+
+    /// Whether every `#[cfg]` in `attrs` holds. A predicate that can't be read counts as
+    /// true, so unfamiliar syntax never hides code.
+    pub fn is_active(&self, attrs: &[Attribute]) -> bool {
+        attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("cfg"))
+            .all(|attr| {
+                attr.parse_args::<Meta>()
+                    .ok()
+                    .and_then(|meta| self.evaluate(&meta))
+                    .unwrap_or(true)
+            })
+    }
+
+    /// Evaluate a cfg predicate: an option, `key = "value"`, or `all`/`any`/`not` of
+    /// predicates. `None` when it isn't one.
+    pub fn evaluate(&self, meta: &Meta) -> Option<bool> {
+        match meta {
+            Meta::Path(path) => {
+                let name = path.get_ident()?.to_string();
+                Some(self.options.contains(&(name, None)))
+            }
+            Meta::NameValue(nv) => {
+                let key = nv.path.get_ident()?.to_string();
+                let syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(value),
+                    ..
+                }) = &nv.value
+                else {
+                    return None;
+                };
+                Some(self.options.contains(&(key, Some(value.value()))))
+            }
+            Meta::List(list) => {
+                let args = list
+                    .parse_args_with(Punctuated::<Meta, syn::Token![,]>::parse_terminated)
+                    .ok()?;
+                let values = args
+                    .iter()
+                    .map(|arg| self.evaluate(arg))
+                    .collect::<Option<Vec<bool>>>()?;
+                match list.path.get_ident()?.to_string().as_str() {
+                    "all" => Some(values.iter().all(|v| *v)),
+                    "any" => Some(values.iter().any(|v| *v)),
+                    "not" if values.len() == 1 => Some(!values[0]),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+/// The code an operation or `find` works on: the whole file, or the `--in-module` module,
+/// less what `--cfg` compiles out and the code `--skip-tests`/`--skip-attr` skip.
+///
+/// The editor prunes out-of-scope code from its syntax tree, so lookups and visitors never
+/// see it, whatever it is called.
+#[derive(Debug, Clone, Default)]
+pub struct Scope {
+    cfg: Option<CfgSet>,
+    /// Skipped attributes, whitespace removed: `cfg(test)`, `test`
+    skip_attrs: Vec<String>,
+    /// Inline module path within the file (`a::b` for `mod a { mod b { .. } }`), empty for
+    /// the whole file
+    module: Vec<String>,
+}
+
+impl Scope {
+    /// Leave out the code the `--cfg` options compile out (nothing without them).
+    pub fn with_cfg(mut self, cfg: Option<CfgSet>) -> Self {
+        self.cfg = cfg;
+        self
+    }
+
+    /// Leave out the code carrying one of `attrs`. `#[cfg(test)]` and `cfg(test)` are the
+    /// same; whitespace doesn't matter.
+    pub fn with_skip_attrs<S: AsRef<str>>(mut self, attrs: impl IntoIterator<Item = S>) -> Self {
+        self.skip_attrs = attrs
+            .into_iter()
+            .map(|attr| {
+                let attr = compact(attr.as_ref());
+                attr.strip_prefix("#[")
+                    .and_then(|attr| attr.strip_suffix(']'))
+                    .map_or_else(|| attr.clone(), str::to_string)
+            })
+            .collect();
+        self
+    }
+
+    /// Leave out everything outside the inline module `module`. A leading `crate::` or
+    /// `self::` is ignored.
+    pub fn with_in_module(mut self, module: Option<&str>) -> Self {
+        self.module = module.map_or_else(Vec::new, |module| {
+            let module = compact(module);
+            let module = module
+                .strip_prefix("crate::")
+                .or_else(|| module.strip_prefix("self::"))
+                .unwrap_or(&module);
+            module
+                .split("::")
+                .filter(|segment| !segment.is_empty())
+                .map(str::to_string)
+                .collect()
+        });
+        self
+    }
+
+    pub const fn cfg(&self) -> Option<&CfgSet> {
+        self.cfg.as_ref()
+    }
+
+    /// The `--in-module` path, empty when unset.
+    pub fn module(&self) -> &[String] {
+        &self.module
+    }
+
+    /// Whether the scope is the whole file.
+    pub const fn is_empty(&self) -> bool {
+        self.cfg.is_none() && !self.skips_code()
+    }
+
+    /// Whether code is left out for other reasons than `--cfg`. Matches there are counted
+    /// (see `unskipped`), so the user hears about them.
+    pub const fn skips_code(&self) -> bool {
+        !self.skip_attrs.is_empty() || !self.module.is_empty()
+    }
+
+    /// The scope without what `skips_code` is about: `--cfg` alone.
+    pub fn unskipped(&self) -> Self {
+        Self::default().with_cfg(self.cfg.clone())
+    }
+
+    /// Whether a node with these attributes is out of scope.
+    fn leaves_out(&self, attrs: &[Attribute]) -> bool {
+        self.cfg.as_ref().is_some_and(|cfg| !cfg.is_active(attrs))
+            || attrs.iter().any(|attr| skips(attr, &self.skip_attrs))
+    }
+
+    /// Remove the out-of-scope items, fields, variants, arms and statements from `file`.
+    /// Returns the 0-based, end-exclusive line ranges they spanned. The nodes left keep
+    /// their spans, so positions in the original text still apply.
+    pub fn prune(&self, file: &mut syn::File) -> Vec<Range<usize>> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        let mut pruner = Pruner {
+            scope: self,
+            lines: Vec::new(),
+        };
+        pruner.prune_to_module(&mut file.items, &self.module);
+        pruner.visit_file_mut(file);
+        pruner.lines
+    }
+}
+
+struct Pruner<'a> {
+    scope: &'a Scope,
+    lines: Vec<Range<usize>>,
+}
+
+impl Pruner<'_> {
+    /// Keep only the inline module at `path` in `items`, down to the last segment.
+    fn prune_to_module(&mut self, items: &mut Vec<syn::Item>, path: &[String]) {
+        let Some((name, rest)) = path.split_first() else {
+            return;
+        };
+        items.retain(|item| {
+            let on_path = matches!(
+                item,
+                syn::Item::Mod(module) if module.ident == name && module.content.is_some()
+            );
+            if !on_path {
+                self.record(item);
+            }
+            on_path
+        });
+        if let Some(syn::Item::Mod(syn::ItemMod {
+            content: Some((_, inner)),
+            ..
+        })) = items.first_mut()
+        {
+            self.prune_to_module(inner, rest);
+        }
+    }
+
+    /// Record the lines of a pruned node.
+    fn record(&mut self, node: &impl Spanned) {
+        let span = node.span();
+        self.lines
+            .push(span.start().line.saturating_sub(1)..span.end().line);
+    }
+
+    /// Whether to keep `node`; records its lines when it goes.
+    fn keep(&mut self, attrs: &[Attribute], node: &impl Spanned) -> bool {
+        let keep = !self.scope.leaves_out(attrs);
+        if !keep {
+            self.record(node);
+        }
+        keep
+    }
+
+    fn keep_pairs<T: Spanned, P>(
+        &mut self,
+        list: &mut Punctuated<T, P>,
+        attrs: impl Fn(&T) -> &[Attribute],
+    ) {
+        *list = std::mem::take(list)
+            .into_pairs()
+            .filter(|pair| self.keep(attrs(pair.value()), pair.value()))
+            .collect();
+    }
+}
+
+impl VisitMut for Pruner<'_> {
+    fn visit_file_mut(&mut self, node: &mut syn::File) {
+        node.items.retain(|item| self.keep(item_attrs(item), item));
+        syn::visit_mut::visit_file_mut(self, node);
+    }
+
+    fn visit_item_mod_mut(&mut self, node: &mut syn::ItemMod) {
+        if let Some((_, items)) = &mut node.content {
+            items.retain(|item| self.keep(item_attrs(item), item));
+        }
+        syn::visit_mut::visit_item_mod_mut(self, node);
+    }
+
+    fn visit_item_impl_mut(&mut self, node: &mut syn::ItemImpl) {
+        node.items
+            .retain(|item| self.keep(impl_item_attrs(item), item));
+        syn::visit_mut::visit_item_impl_mut(self, node);
+    }
+
+    fn visit_item_trait_mut(&mut self, node: &mut syn::ItemTrait) {
+        node.items
+            .retain(|item| self.keep(trait_item_attrs(item), item));
+        syn::visit_mut::visit_item_trait_mut(self, node);
+    }
+
+    fn visit_block_mut(&mut self, node: &mut syn::Block) {
+        node.stmts.retain(|stmt| match stmt {
+            syn::Stmt::Item(item) => self.keep(item_attrs(item), item),
+            syn::Stmt::Local(local) => self.keep(&local.attrs, local),
+            syn::Stmt::Macro(mac) => self.keep(&mac.attrs, mac),
+            syn::Stmt::Expr(..) => true,
+        });
+        syn::visit_mut::visit_block_mut(self, node);
+    }
+
+    fn visit_fields_named_mut(&mut self, node: &mut syn::FieldsNamed) {
+        self.keep_pairs(&mut node.named, |field| &field.attrs);
+        syn::visit_mut::visit_fields_named_mut(self, node);
+    }
+
+    fn visit_fields_unnamed_mut(&mut self, node: &mut syn::FieldsUnnamed) {
+        self.keep_pairs(&mut node.unnamed, |field| &field.attrs);
+        syn::visit_mut::visit_fields_unnamed_mut(self, node);
+    }
+
+    fn visit_item_enum_mut(&mut self, node: &mut syn::ItemEnum) {
+        self.keep_pairs(&mut node.variants, |variant| &variant.attrs);
+        syn::visit_mut::visit_item_enum_mut(self, node);
+    }
+
+    fn visit_expr_match_mut(&mut self, node: &mut syn::ExprMatch) {
+        node.arms.retain(|arm| self.keep(&arm.attrs, arm));
+        syn::visit_mut::visit_expr_match_mut(self, node);
+    }
+
+    fn visit_expr_struct_mut(&mut self, node: &mut syn::ExprStruct) {
+        self.keep_pairs(&mut node.fields, |field| &field.attrs);
+        syn::visit_mut::visit_expr_struct_mut(self, node);
+    }
+}
+
+/// 0-based, end-exclusive line ranges of the outermost `#[cfg]`-gated items, fields,
+/// variants, arms and statements in `file`: with `cfg`, only those it compiles out;
+/// without, all of them.
+pub fn gated_lines(file: &syn::File, cfg: Option<&CfgSet>) -> Vec<Range<usize>> {
+    marked_lines(file, &|attrs| {
+        cfg.map_or_else(
+            || attrs.iter().any(|attr| attr.path().is_ident("cfg")),
+            |cfg| !cfg.is_active(attrs),
+        )
+    })
+}
+
+/// Line ranges of the outermost nodes whose attributes `marked` picks out.
+fn marked_lines(file: &syn::File, marked: &dyn Fn(&[Attribute]) -> bool) -> Vec<Range<usize>> {
+    struct Gated<'a> {
+        marked: &'a dyn Fn(&[Attribute]) -> bool,
+        lines: Vec<Range<usize>>,
+    }
+
+    impl Gated<'_> {
+        /// Record `node` if its attributes gate it; returns whether it did.
+        fn check(&mut self, attrs: &[Attribute], node: &impl Spanned) -> bool {
+            let gated = (self.marked)(attrs);
+            if gated {
+                let span = node.span();
+                self.lines
+                    .push(span.start().line.saturating_sub(1)..span.end().line);
+            }
+            gated
+        }
+    }
+
+    impl<'ast> Visit<'ast> for Gated<'_> {
+        fn visit_item(&mut self, node: &'ast syn::Item) {
+            if !self.check(item_attrs(node), node) {
+                syn::visit::visit_item(self, node);
+            }
+        }
+
+        fn visit_impl_item(&mut self, node: &'ast syn::ImplItem) {
+            if !self.check(impl_item_attrs(node), node) {
+                syn::visit::visit_impl_item(self, node);
+            }
+        }
+
+        fn visit_trait_item(&mut self, node: &'ast syn::TraitItem) {
+            if !self.check(trait_item_attrs(node), node) {
+                syn::visit::visit_trait_item(self, node);
+            }
+        }
+
+        fn visit_field(&mut self, node: &'ast syn::Field) {
+            if !self.check(&node.attrs, node) {
+                syn::visit::visit_field(self, node);
+            }
+        }
+
+        fn visit_variant(&mut self, node: &'ast syn::Variant) {
+            if !self.check(&node.attrs, node) {
+                syn::visit::visit_variant(self, node);
+            }
+        }
+
+        fn visit_arm(&mut self, node: &'ast syn::Arm) {
+            if !self.check(&node.attrs, node) {
+                syn::visit::visit_arm(self, node);
+            }
+        }
+
+        fn visit_field_value(&mut self, node: &'ast syn::FieldValue) {
+            if !self.check(&node.attrs, node) {
+                syn::visit::visit_field_value(self, node);
+            }
+        }
+
+        fn visit_local(&mut self, node: &'ast syn::Local) {
+            if !self.check(&node.attrs, node) {
+                syn::visit::visit_local(self, node);
+            }
+        }
+
+        fn visit_stmt_macro(&mut self, node: &'ast syn::StmtMacro) {
+            if !self.check(&node.attrs, node) {
+                syn::visit::visit_stmt_macro(self, node);
+            }
+        }
+    }
+
+    let mut gated = Gated {
+        marked,
+        lines: Vec::new(),
+    };
+    gated.visit_file(file);
+    gated.lines
+}
+
+fn item_attrs(item: &syn::Item) -> &[Attribute] {
+    match item {
+        syn::Item::Const(i) => &i.attrs,
+        syn::Item::Enum(i) => &i.attrs,
+        syn::Item::ExternCrate(i) => &i.attrs,
+        syn::Item::Fn(i) => &i.attrs,
+        syn::Item::ForeignMod(i) => &i.attrs,
+        syn::Item::Impl(i) => &i.attrs,
+        syn::Item::Macro(i) => &i.attrs,
+        syn::Item::Mod(i) => &i.attrs,
+        syn::Item::Static(i) => &i.attrs,
+        syn::Item::Struct(i) => &i.attrs,
+        syn::Item::Trait(i) => &i.attrs,
+        syn::Item::TraitAlias(i) => &i.attrs,
+        syn::Item::Type(i) => &i.attrs,
+        syn::Item::Union(i) => &i.attrs,
+        syn::Item::Use(i) => &i.attrs,
+        _ => &[],
+    }
+}
+
+fn impl_item_attrs(item: &syn::ImplItem) -> &[Attribute] {
+    match item {
+        syn::ImplItem::Const(i) => &i.attrs,
+        syn::ImplItem::Fn(i) => &i.attrs,
+        syn::ImplItem::Type(i) => &i.attrs,
+        syn::ImplItem::Macro(i) => &i.attrs,
+        _ => &[],
+    }
+}
+
+fn trait_item_attrs(item: &syn::TraitItem) -> &[Attribute] {
+    match item {
+        syn::TraitItem::Const(i) => &i.attrs,
+        syn::TraitItem::Fn(i) => &i.attrs,
+        syn::TraitItem::Type(i) => &i.attrs,
+        syn::TraitItem::Macro(i) => &i.attrs,
+        _ => &[],
+    }
+}
+
+/// How many `#[cfg]`-gated items the edit from `old_content` to `new_content` touches, for
+/// runs without `--cfg` to say to double-check them.
+pub fn gated_items_touched(old_content: &str, new_content: &str) -> usize {
+    let Ok(file) = syn::parse_file(old_content) else {
+        return 0;
+    };
+    let gated = gated_lines(&file, None);
+    if gated.is_empty() {
+        return 0;
+    }
+    let regions = changed_regions(old_content, new_content);
+    gated
+        .iter()
+        .filter(|gate| {
+            regions
+                .iter()
+                .any(|(old, _)| within(old, gate) || (old.start < gate.end && gate.start < old.end))
+        })
+        .count()
+}
+
+/// (old, new) line ranges of each changed region between the two texts.
+pub fn changed_regions(old_content: &str, new_content: &str) -> Vec<(Range<usize>, Range<usize>)> {
+    similar::TextDiff::from_lines(old_content, new_content)
+        .grouped_ops(0)
+        .iter()
+        .filter_map(|group| {
+            let (first, last) = (group.first()?, group.last()?);
+            Some((
+                first.old_range().start..last.old_range().end,
+                first.new_range().start..last.new_range().end,
+            ))
+        })
+        .collect()
+}
+
+/// An insertion (empty range) is inside when it lands between a gated item's lines.
+fn within(old: &Range<usize>, gate: &Range<usize>) -> bool {
+    if old.is_empty() {
+        (gate.start + 1..gate.end).contains(&old.start)
+    } else {
+        gate.start <= old.start && old.end <= gate.end
+    }
+}
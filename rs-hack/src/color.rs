@@ -0,0 +1,68 @@
+//! ANSI colors for human-readable output, and the `--color auto|always|never` choice.
+//!
+//! Only the text printers (diffs, `find` snippets) color anything; JSON, JSONL, locations,
+//! SARIF and GitHub output never do.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// When to write ANSI colors (`--color`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// When stdout is a terminal and `NO_COLOR` isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl std::str::FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(format!(
+                "Invalid color choice: {}. Valid values are 'auto', 'always' or 'never'",
+                s
+            )),
+        }
+    }
+}
+
+static CHOICE: AtomicU8 = AtomicU8::new(ColorChoice::Auto as u8);
+
+pub fn set_color_choice(choice: ColorChoice) {
+    CHOICE.store(choice as u8, Ordering::Relaxed);
+}
+
+/// Whether ANSI colors may be written to stdout. An explicit `--color always` or `never`
+/// wins over `NO_COLOR`, as the convention asks.
+pub fn enabled() -> bool {
+    use std::io::IsTerminal;
+    match CHOICE.load(Ordering::Relaxed) {
+        c if c == ColorChoice::Always as u8 => true,
+        c if c == ColorChoice::Never as u8 => false,
+        _ => {
+            std::io::stdout().is_terminal()
+                && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+        }
+    }
+}
+
+pub const BOLD: &str = "\x1b[1m";
+pub const DIM: &str = "\x1b[2m";
+pub const RED: &str = "\x1b[31m";
+pub const GREEN: &str = "\x1b[32m";
+pub const CYAN: &str = "\x1b[36m";
+pub const RESET: &str = "\x1b[0m";
+
+/// `text` wrapped in `style` when colors are enabled, as is otherwise.
+pub fn paint(style: &str, text: &str) -> String {
+    if enabled() {
+        format!("{}{}{}", style, text, RESET)
+    } else {
+        text.to_string()
+    }
+}
@@ -0,0 +1,242 @@
+//! `complexity` command: cyclomatic-complexity-style score per function, reporting the
+//! functions above a threshold.
+//!
+//! Each function starts at 1 and gains a point for every `if` (including `if let` and
+//! `else if`) and `let ... else`, every match arm after the first, every match guard, every
+//! `&&` / `||`, and every `for`, `while`, or `loop`. Closures count toward the function that
+//! contains them; nested `fn` items are scored on their own.
+
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+use serde::Serialize;
+use syn::visit::Visit;
+
+use crate::files::{CollectOptions, collect_rust_files_with_options};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FunctionComplexity {
+    pub file_path: String,
+    pub line: usize,
+    /// `name` for free functions, `Type::name` / `Trait::name` for methods
+    pub name: String,
+    pub score: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ComplexityReport {
+    pub threshold: usize,
+    pub total_functions: usize,
+    /// Functions scoring above `threshold`, in the requested order
+    pub functions: Vec<FunctionComplexity>,
+}
+
+pub fn run(
+    paths: &[PathBuf],
+    threshold: usize,
+    sort: &str,
+    exclude: &[String],
+    options: &CollectOptions,
+) -> Result<ComplexityReport> {
+    if !matches!(sort, "score" | "name" | "location") {
+        bail!(
+            "Unknown sort '{}'. Use \"score\", \"name\", or \"location\"",
+            sort
+        );
+    }
+
+    let files = collect_rust_files_with_options(paths, exclude, options)?;
+    let mut all: Vec<FunctionComplexity> = Vec::new();
+
+    for file in &files {
+        let content = match std::fs::read_to_string(file) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("⚠️  Skipping {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        let syntax = match syn::parse_file(&content) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("⚠️  Skipping {} (parse error): {}", file.display(), e);
+                continue;
+            }
+        };
+
+        let mut visitor = ComplexityVisitor {
+            file_path: file.to_string_lossy().to_string(),
+            owner: Vec::new(),
+            stack: Vec::new(),
+            done: Vec::new(),
+        };
+        visitor.visit_file(&syntax);
+        all.extend(visitor.done);
+    }
+
+    let total_functions = all.len();
+    let mut functions: Vec<FunctionComplexity> =
+        all.into_iter().filter(|f| f.score > threshold).collect();
+    match sort {
+        "name" => functions.sort_by(|a, b| a.name.cmp(&b.name)),
+        "location" => {
+            functions.sort_by(|a, b| a.file_path.cmp(&b.file_path).then(a.line.cmp(&b.line)));
+        }
+        _ => functions.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then(a.file_path.cmp(&b.file_path))
+                .then(a.line.cmp(&b.line))
+        }),
+    }
+
+    Ok(ComplexityReport {
+        threshold,
+        total_functions,
+        functions,
+    })
+}
+
+pub fn render(report: &ComplexityReport) {
+    println!(
+        "Functions above complexity {}: {} of {}",
+        report.threshold,
+        report.functions.len(),
+        report.total_functions
+    );
+    if report.functions.is_empty() {
+        return;
+    }
+    println!();
+    for f in &report.functions {
+        println!("  {:>4}  {}:{}  {}", f.score, f.file_path, f.line, f.name);
+    }
+}
+
+/// Score of a single function body, as the command computes it.
+pub fn score_block(block: &syn::Block) -> usize {
+    let mut visitor = ComplexityVisitor {
+        file_path: String::new(),
+        owner: Vec::new(),
+        stack: vec![(String::new(), 0, 1)],
+        done: Vec::new(),
+    };
+    visitor.visit_block(block);
+    visitor.stack[0].2
+}
+
+// ---- helpers ----------------------------------------------------------------
+
+struct ComplexityVisitor {
+    file_path: String,
+    /// Enclosing impl self type / trait name, for qualifying method names
+    owner: Vec<Option<String>>,
+    /// Functions being scored: (name, line, score)
+    stack: Vec<(String, usize, usize)>,
+    done: Vec<FunctionComplexity>,
+}
+
+impl ComplexityVisitor {
+    fn add(&mut self, points: usize) {
+        if let Some(top) = self.stack.last_mut() {
+            top.2 += points;
+        }
+    }
+
+    fn score_fn(&mut self, ident: &syn::Ident, block: &syn::Block) {
+        let name = match self.owner.last() {
+            Some(Some(owner)) => format!("{}::{}", owner, ident),
+            _ => ident.to_string(),
+        };
+        self.stack.push((name, ident.span().start().line, 1));
+        // Items nested in the body are scored on their own, without the outer owner
+        self.owner.push(None);
+        self.visit_block(block);
+        self.owner.pop();
+        if let Some((name, line, score)) = self.stack.pop() {
+            self.done.push(FunctionComplexity {
+                file_path: self.file_path.clone(),
+                line,
+                name,
+                score,
+            });
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for ComplexityVisitor {
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        self.score_fn(&node.sig.ident, &node.block);
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        let owner = match node.self_ty.as_ref() {
+            syn::Type::Path(p) => p.path.segments.last().map(|seg| seg.ident.to_string()),
+            other => Some(quote::quote!(#other).to_string()),
+        };
+        self.owner.push(owner);
+        syn::visit::visit_item_impl(self, node);
+        self.owner.pop();
+    }
+
+    fn visit_item_trait(&mut self, node: &'ast syn::ItemTrait) {
+        self.owner.push(Some(node.ident.to_string()));
+        syn::visit::visit_item_trait(self, node);
+        self.owner.pop();
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        self.score_fn(&node.sig.ident, &node.block);
+    }
+
+    fn visit_trait_item_fn(&mut self, node: &'ast syn::TraitItemFn) {
+        if let Some(block) = &node.default {
+            self.score_fn(&node.sig.ident, block);
+        }
+    }
+
+    fn visit_expr_if(&mut self, node: &'ast syn::ExprIf) {
+        self.add(1);
+        syn::visit::visit_expr_if(self, node);
+    }
+
+    fn visit_expr_match(&mut self, node: &'ast syn::ExprMatch) {
+        self.add(node.arms.len().saturating_sub(1));
+        self.add(node.arms.iter().filter(|arm| arm.guard.is_some()).count());
+        syn::visit::visit_expr_match(self, node);
+    }
+
+    fn visit_expr_binary(&mut self, node: &'ast syn::ExprBinary) {
+        if matches!(node.op, syn::BinOp::And(_) | syn::BinOp::Or(_)) {
+            self.add(1);
+        }
+        syn::visit::visit_expr_binary(self, node);
+    }
+
+    fn visit_expr_for_loop(&mut self, node: &'ast syn::ExprForLoop) {
+        self.add(1);
+        syn::visit::visit_expr_for_loop(self, node);
+    }
+
+    fn visit_expr_while(&mut self, node: &'ast syn::ExprWhile) {
+        self.add(1);
+        syn::visit::visit_expr_while(self, node);
+    }
+
+    fn visit_expr_loop(&mut self, node: &'ast syn::ExprLoop) {
+        self.add(1);
+        syn::visit::visit_expr_loop(self, node);
+    }
+
+    fn visit_local(&mut self, node: &'ast syn::Local) {
+        // `let ... else` branches like an `if`
+        if node
+            .init
+            .as_ref()
+            .is_some_and(|init| init.diverge.is_some())
+        {
+            self.add(1);
+        }
+        syn::visit::visit_local(self, node);
+    }
+}
@@ -0,0 +1,266 @@
+//! `debug` command: the syntax tree around one line of a file.
+//!
+//! Lists every node whose span covers the line, outermost first, with its syn type
+//! (`Item::Impl`, `Expr::MethodCall`, ...) and the `--node-type` that matches it, so batch
+//! specs and `find`/`transform` runs can name the right node type without guessing.
+
+use std::fmt::Debug;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+use crate::surgical::Source;
+
+#[derive(Debug, Clone)]
+pub struct DebugArgs {
+    pub file: PathBuf,
+    /// 1-based line to explain
+    pub line: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AstNode {
+    /// Nesting below the outermost node listed (0)
+    pub depth: usize,
+    /// The syn type, with the enum variant when there is one, e.g. "Expr::MethodCall"
+    pub syn_type: String,
+    /// The `--node-type` that finds this node, if any
+    pub node_type: Option<&'static str>,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// The node's source on one line, shortened
+    pub snippet: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DebugReport {
+    pub file: PathBuf,
+    pub line: usize,
+    pub nodes: Vec<AstNode>,
+}
+
+/// Longest snippet shown, in chars.
+const SNIPPET_CHARS: usize = 60;
+
+pub fn run(args: &DebugArgs) -> Result<DebugReport> {
+    let text = std::fs::read_to_string(&args.file)
+        .with_context(|| format!("Failed to read file: {:?}", args.file))?;
+    let syntax =
+        syn::parse_file(&text).with_context(|| format!("Failed to parse file: {:?}", args.file))?;
+    let lines = text.lines().count();
+    if args.line == 0 || args.line > lines {
+        anyhow::bail!(
+            "--line {} is outside {} ({} lines)",
+            args.line,
+            args.file.display(),
+            lines
+        );
+    }
+
+    let src = Source::new(&text);
+    let mut visitor = Spanning {
+        src: &src,
+        line: args.line,
+        depth: 0,
+        nodes: Vec::new(),
+    };
+    visitor.visit_file(&syntax);
+    Ok(DebugReport {
+        file: args.file.clone(),
+        line: args.line,
+        nodes: visitor.nodes,
+    })
+}
+
+pub fn render(report: &DebugReport) {
+    println!("{}:{}", report.file.display(), report.line);
+    if report.nodes.is_empty() {
+        println!("  No syntax node covers this line (blank, comment or inner attribute)");
+        return;
+    }
+    let width = report
+        .nodes
+        .iter()
+        .map(|node| node.depth * 2 + node.syn_type.len())
+        .max()
+        .unwrap_or(0);
+    for node in &report.nodes {
+        let lines = if node.start_line == node.end_line {
+            format!("line {}", node.start_line)
+        } else {
+            format!("lines {}-{}", node.start_line, node.end_line)
+        };
+        println!(
+            "  {:indent$}{:<width$} {:<14} {:<13} {}",
+            "",
+            node.syn_type,
+            node.node_type.unwrap_or("-"),
+            lines,
+            node.snippet,
+            indent = node.depth * 2,
+            width = width - node.depth * 2,
+        );
+    }
+}
+
+/// Collects the nodes whose span covers `line`, skipping subtrees that don't.
+struct Spanning<'a> {
+    src: &'a Source<'a>,
+    line: usize,
+    depth: usize,
+    nodes: Vec<AstNode>,
+}
+
+impl Spanning<'_> {
+    /// Record `node` and walk into it with `visit` when it covers the line.
+    fn descend<T: Spanned + Debug>(
+        &mut self,
+        node: &T,
+        node_type: Option<&'static str>,
+        visit: impl FnOnce(&mut Self),
+    ) {
+        let span = node.span();
+        if span.start().line > self.line || span.end().line < self.line {
+            return;
+        }
+        let (start, end) = self.src.range(node);
+        let mut snippet = self.src.text[start..end]
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if snippet.chars().count() > SNIPPET_CHARS {
+            snippet = snippet.chars().take(SNIPPET_CHARS - 3).collect::<String>() + "...";
+        }
+        self.nodes.push(AstNode {
+            depth: self.depth,
+            syn_type: syn_type(node),
+            node_type,
+            start_line: span.start().line,
+            end_line: span.end().line,
+            snippet,
+        });
+        self.depth += 1;
+        visit(self);
+        self.depth -= 1;
+    }
+}
+
+/// `Expr::MethodCall`, `Item::Struct`, `Arm`: the head of syn's `Debug` output.
+fn syn_type(node: &impl Debug) -> String {
+    let debug = format!("{:?}", node);
+    debug
+        .split([' ', '(', '{'])
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+impl<'ast> Visit<'ast> for Spanning<'_> {
+    fn visit_item(&mut self, node: &'ast syn::Item) {
+        let node_type = match node {
+            syn::Item::Struct(_) => Some("struct"),
+            syn::Item::Union(_) => Some("union"),
+            syn::Item::Enum(_) => Some("enum"),
+            syn::Item::Fn(_) => Some("function"),
+            syn::Item::Trait(_) => Some("trait"),
+            syn::Item::Impl(imp) if imp.trait_.is_some() => Some("trait-impl"),
+            syn::Item::Impl(_) => Some("impl"),
+            syn::Item::Const(_) => Some("const"),
+            syn::Item::Static(_) => Some("static"),
+            syn::Item::Type(_) => Some("type-alias"),
+            syn::Item::Mod(_) => Some("mod"),
+            syn::Item::Macro(_) => Some("macro-call"),
+            _ => None,
+        };
+        self.descend(node, node_type, |v| syn::visit::visit_item(v, node));
+    }
+
+    fn visit_impl_item(&mut self, node: &'ast syn::ImplItem) {
+        let node_type = matches!(node, syn::ImplItem::Fn(_)).then_some("impl-method");
+        self.descend(node, node_type, |v| syn::visit::visit_impl_item(v, node));
+    }
+
+    fn visit_trait_item(&mut self, node: &'ast syn::TraitItem) {
+        let node_type = matches!(node, syn::TraitItem::Fn(_)).then_some("trait-method");
+        self.descend(node, node_type, |v| syn::visit::visit_trait_item(v, node));
+    }
+
+    fn visit_foreign_item(&mut self, node: &'ast syn::ForeignItem) {
+        let node_type = matches!(node, syn::ForeignItem::Fn(_)).then_some("extern-fn");
+        self.descend(node, node_type, |v| syn::visit::visit_foreign_item(v, node));
+    }
+
+    fn visit_field(&mut self, node: &'ast syn::Field) {
+        self.descend(node, None, |v| syn::visit::visit_field(v, node));
+    }
+
+    fn visit_variant(&mut self, node: &'ast syn::Variant) {
+        self.descend(node, None, |v| syn::visit::visit_variant(v, node));
+    }
+
+    fn visit_attribute(&mut self, node: &'ast syn::Attribute) {
+        let node_type = if node.path().is_ident("derive") {
+            "derive"
+        } else {
+            "attribute"
+        };
+        self.descend(node, Some(node_type), |v| {
+            syn::visit::visit_attribute(v, node)
+        });
+    }
+
+    fn visit_generic_param(&mut self, node: &'ast syn::GenericParam) {
+        let node_type = match node {
+            syn::GenericParam::Lifetime(_) => "lifetime",
+            _ => "generic-param",
+        };
+        self.descend(node, Some(node_type), |v| {
+            syn::visit::visit_generic_param(v, node)
+        });
+    }
+
+    fn visit_local(&mut self, node: &'ast syn::Local) {
+        self.descend(node, None, |v| syn::visit::visit_local(v, node));
+    }
+
+    fn visit_stmt(&mut self, node: &'ast syn::Stmt) {
+        // Other statements are listed as the local, item or expression they hold
+        if matches!(node, syn::Stmt::Macro(_)) {
+            self.descend(node, Some("macro-call"), |v| {
+                syn::visit::visit_stmt(v, node)
+            });
+        } else {
+            syn::visit::visit_stmt(self, node);
+        }
+    }
+
+    fn visit_expr(&mut self, node: &'ast syn::Expr) {
+        let node_type = match node {
+            syn::Expr::Struct(_) => Some("struct-literal"),
+            syn::Expr::Call(_) => Some("function-call"),
+            syn::Expr::MethodCall(_) => Some("method-call"),
+            syn::Expr::Macro(_) => Some("macro-call"),
+            syn::Expr::Closure(_) => Some("closure"),
+            syn::Expr::Path(p) if p.path.segments.len() == 1 => Some("identifier"),
+            syn::Expr::Path(_) => Some("enum-usage"),
+            _ => None,
+        };
+        self.descend(node, node_type, |v| syn::visit::visit_expr(v, node));
+    }
+
+    fn visit_arm(&mut self, node: &'ast syn::Arm) {
+        self.descend(node, Some("match-arm"), |v| syn::visit::visit_arm(v, node));
+    }
+
+    fn visit_pat(&mut self, node: &'ast syn::Pat) {
+        self.descend(node, None, |v| syn::visit::visit_pat(v, node));
+    }
+
+    fn visit_type(&mut self, node: &'ast syn::Type) {
+        let node_type = matches!(node, syn::Type::Path(_)).then_some("type-ref");
+        self.descend(node, node_type, |v| syn::visit::visit_type(v, node));
+    }
+}
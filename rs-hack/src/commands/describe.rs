@@ -0,0 +1,289 @@
+//! `describe_type` (MCP tool): one type's shape and use in a single call.
+//!
+//! Puts together what otherwise takes several `find` runs: where the type is defined with
+//! its fields or variants, every impl block for it with its methods and how often each is
+//! called, and the reference counts `impact` reports. Read-only. Like `impact`, the type is
+//! matched by name (the last path segment), so same-named types in different modules are
+//! described together.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde::Serialize;
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+use crate::commands::find::callers_in;
+use crate::files::{CollectOptions, collect_rust_files_with_options};
+use crate::surgical::Source;
+
+#[derive(Debug, Serialize)]
+pub struct TypeDescription {
+    pub type_name: String,
+    /// Structs, enums, unions and type aliases of that name
+    pub definitions: Vec<TypeDefinition>,
+    /// Inherent and trait impls whose self type is the type
+    pub impls: Vec<ImplBlock>,
+    /// References by `impact` category (`struct-literal`, `field`, ...), empty ones left out
+    pub references: Vec<ReferenceCount>,
+    /// Total of `references`
+    pub blast_radius: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TypeDefinition {
+    /// `struct`, `enum`, `union` or `type`
+    pub kind: &'static str,
+    pub file_path: String,
+    pub line: usize,
+    /// `name: Type` per field (`0: Type` for tuple fields), or one entry per variant with
+    /// its fields (`Moved { x: i32 }`); the aliased type for a type alias
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImplBlock {
+    /// The trait, for a trait impl (`Display`, `From<u32>`)
+    pub trait_name: Option<String>,
+    pub file_path: String,
+    pub line: usize,
+    pub methods: Vec<MethodInfo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MethodInfo {
+    pub name: String,
+    pub line: usize,
+    /// The signature on one line, e.g. `pub fn len(&self) -> usize`
+    pub signature: String,
+    /// `Type::name(..)` calls, plus `Self::name(..)` and `self.name(..)` inside impls of
+    /// the type
+    pub qualified_calls: usize,
+    /// `.name(..)` method calls on any receiver: the receiver's type isn't checked, so
+    /// same-named methods of other types are counted too
+    pub method_calls: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReferenceCount {
+    pub category: &'static str,
+    pub count: usize,
+}
+
+pub fn run(
+    paths: &[PathBuf],
+    type_name: &str,
+    exclude: &[String],
+    options: &CollectOptions,
+) -> Result<TypeDescription> {
+    let name = type_name.rsplit("::").next().unwrap_or(type_name);
+    let files = collect_rust_files_with_options(paths, exclude, options)?;
+
+    let mut definitions = Vec::new();
+    let mut impls = Vec::new();
+    let mut sources = Vec::new();
+    for file in &files {
+        let content = match std::fs::read_to_string(file) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("⚠️  Skipping {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        let syntax = match syn::parse_file(&content) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("⚠️  Skipping {} (parse error): {}", file.display(), e);
+                continue;
+            }
+        };
+        let src = Source::new(&content);
+        let mut visitor = Describer {
+            name,
+            file_path: file.to_string_lossy().to_string(),
+            src: &src,
+            definitions: Vec::new(),
+            impls: Vec::new(),
+        };
+        visitor.visit_file(&syntax);
+        definitions.append(&mut visitor.definitions);
+        impls.append(&mut visitor.impls);
+        sources.push((syntax, content));
+    }
+
+    // Call counts need every file, so they're added once all the methods are known
+    for method in impls.iter_mut().flat_map(|imp| imp.methods.iter_mut()) {
+        let qualified = format!("{}::{}", name, method.name);
+        for (syntax, content) in &sources {
+            method.qualified_calls += callers_in(syntax, content, &qualified)
+                .iter()
+                .map(|group| group.count)
+                .sum::<usize>();
+            let mut counter = MethodCallCounter {
+                method: &method.name,
+                count: 0,
+            };
+            counter.visit_file(syntax);
+            method.method_calls += counter.count;
+        }
+    }
+
+    let impact = crate::commands::impact::run(paths, name, exclude, options)?;
+    let references = impact
+        .categories
+        .iter()
+        .filter(|category| !category.sites.is_empty())
+        .map(|category| ReferenceCount {
+            category: category.category,
+            count: category.sites.len(),
+        })
+        .collect();
+
+    Ok(TypeDescription {
+        type_name: name.to_string(),
+        definitions,
+        impls,
+        references,
+        blast_radius: impact.blast_radius,
+    })
+}
+
+// ---- helpers ----------------------------------------------------------------
+
+struct Describer<'a> {
+    name: &'a str,
+    file_path: String,
+    src: &'a Source<'a>,
+    definitions: Vec<TypeDefinition>,
+    impls: Vec<ImplBlock>,
+}
+
+impl Describer<'_> {
+    /// Source text of `node`, whitespace collapsed to single spaces.
+    fn text(&self, node: &impl Spanned) -> String {
+        let (start, end) = self.src.range(node);
+        self.src.text[start..end]
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn fields(&self, fields: &syn::Fields) -> Vec<String> {
+        fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let ty = self.text(&field.ty);
+                field.ident.as_ref().map_or_else(
+                    || format!("{}: {}", i, ty),
+                    |ident| format!("{}: {}", ident, ty),
+                )
+            })
+            .collect()
+    }
+
+    /// `pub fn len(&self) -> usize`: the signature with its visibility, if any.
+    fn signature(&self, vis: &syn::Visibility, sig: &syn::Signature) -> String {
+        match vis {
+            syn::Visibility::Inherited => self.text(sig),
+            vis => format!("{} {}", self.text(vis), self.text(sig)),
+        }
+    }
+
+    /// Record the definition if `ident` is the type; `fields` lists its contents.
+    fn define(
+        &mut self,
+        kind: &'static str,
+        ident: &syn::Ident,
+        fields: impl FnOnce(&Self) -> Vec<String>,
+    ) {
+        if ident == self.name {
+            let fields = fields(self);
+            self.definitions.push(TypeDefinition {
+                kind,
+                file_path: self.file_path.clone(),
+                line: ident.span().start().line,
+                fields,
+            });
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for Describer<'_> {
+    fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
+        self.define("struct", &node.ident, |d| d.fields(&node.fields));
+        syn::visit::visit_item_struct(self, node);
+    }
+
+    fn visit_item_enum(&mut self, node: &'ast syn::ItemEnum) {
+        self.define("enum", &node.ident, |d| {
+            node.variants
+                .iter()
+                .map(|variant| match &variant.fields {
+                    syn::Fields::Unit => variant.ident.to_string(),
+                    syn::Fields::Named(fields) => format!("{} {}", variant.ident, d.text(fields)),
+                    syn::Fields::Unnamed(fields) => format!("{}{}", variant.ident, d.text(fields)),
+                })
+                .collect()
+        });
+        syn::visit::visit_item_enum(self, node);
+    }
+
+    fn visit_item_union(&mut self, node: &'ast syn::ItemUnion) {
+        self.define("union", &node.ident, |d| {
+            d.fields(&syn::Fields::Named(node.fields.clone()))
+        });
+        syn::visit::visit_item_union(self, node);
+    }
+
+    fn visit_item_type(&mut self, node: &'ast syn::ItemType) {
+        self.define("type", &node.ident, |d| vec![d.text(&node.ty)]);
+        syn::visit::visit_item_type(self, node);
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        let is_target = matches!(
+            node.self_ty.as_ref(),
+            syn::Type::Path(type_path)
+                if type_path.path.segments.last().is_some_and(|seg| seg.ident == self.name)
+        );
+        if is_target {
+            let methods = node
+                .items
+                .iter()
+                .filter_map(|item| match item {
+                    syn::ImplItem::Fn(method) => Some(MethodInfo {
+                        name: method.sig.ident.to_string(),
+                        line: method.sig.ident.span().start().line,
+                        signature: self.signature(&method.vis, &method.sig),
+                        qualified_calls: 0,
+                        method_calls: 0,
+                    }),
+                    _ => None,
+                })
+                .collect();
+            self.impls.push(ImplBlock {
+                trait_name: node.trait_.as_ref().map(|(_, path, _)| self.text(path)),
+                file_path: self.file_path.clone(),
+                line: node.impl_token.span.start().line,
+                methods,
+            });
+        }
+        syn::visit::visit_item_impl(self, node);
+    }
+}
+
+/// Counts `.method(..)` calls by name, whatever the receiver.
+struct MethodCallCounter<'a> {
+    method: &'a str,
+    count: usize,
+}
+
+impl<'ast> Visit<'ast> for MethodCallCounter<'_> {
+    fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
+        if node.method == self.method {
+            self.count += 1;
+        }
+        syn::visit::visit_expr_method_call(self, node);
+    }
+}
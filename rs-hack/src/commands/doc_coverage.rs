@@ -5,7 +5,7 @@ use std::path::PathBuf;
 use anyhow::Result;
 use syn::spanned::Spanned;
 
-use crate::files::collect_rust_files_with_exclusions;
+use crate::files::{CollectOptions, collect_rust_files_with_options};
 
 #[derive(Debug)]
 pub struct DocCoverageReport {
@@ -23,8 +23,13 @@ pub struct MissingDoc {
     pub label: String, // e.g. "AppState (struct)", "RenderedCell::width (field)"
 }
 
-pub fn run(paths: &[PathBuf], check_fields: bool, exclude: &[String]) -> Result<DocCoverageReport> {
-    let files = collect_rust_files_with_exclusions(paths, exclude)?;
+pub fn run(
+    paths: &[PathBuf],
+    check_fields: bool,
+    exclude: &[String],
+    options: &CollectOptions,
+) -> Result<DocCoverageReport> {
+    let files = collect_rust_files_with_options(paths, exclude, options)?;
 
     let mut missing_items: Vec<MissingDoc> = Vec::new();
     let mut total_items = 0usize;
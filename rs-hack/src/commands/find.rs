@@ -1,19 +1,33 @@
 //! `find` command as a lib API. Returns structured matches; rendering (text,
 //! snippets, hints) is the caller's job — see `main.rs` for the CLI renderer.
 
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
+use crate::cfg::Scope;
+use crate::commands::node_types::{KINDS, expand_kind_to_node_types};
 use crate::editor::RustEditor;
-use crate::files::{collect_rust_files_with_exclusions, expand_kind_to_node_types};
-use crate::operations::{FieldLocation, InspectResult};
+use crate::files::{CollectOptions, collect_rust_files_with_options};
+use crate::operations::{FieldLocation, InspectResult, compile_name_regex, name_matches};
+use crate::verbose::Verbose;
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FindArgs {
     pub paths: Vec<PathBuf>,
     pub exclude: Vec<String>,
+    #[serde(skip)]
+    pub collect: CollectOptions,
+    #[serde(skip)]
+    pub verbose: Verbose,
+    /// The code searched (`--cfg`, `--skip-tests`, `--skip-attr`, `--in-module`)
+    #[serde(skip)]
+    pub scope: Scope,
+    /// A file that doesn't parse fails the search instead of being skipped
+    #[serde(default)]
+    pub strict: bool,
     pub kind: Option<String>,
     pub node_type: Option<String>,
     pub name: Option<String>,
@@ -21,42 +35,325 @@ pub struct FindArgs {
     pub content_filter: Option<String>,
     pub field_name: Option<String>,
     pub include_comments: bool,
-    /// Number of raw source lines to show before each snippet match (like grep -B N)
+    /// Number of raw source lines to show before and after each snippet match (like grep -C N)
     #[serde(default)]
     pub context: Option<usize>,
+    /// Only match functions/methods with exactly this many parameters
+    #[serde(default)]
+    pub arity: Option<usize>,
+    /// Only match functions/methods with at least this many parameters
+    #[serde(default)]
+    pub min_arity: Option<usize>,
+    /// Only match functions/methods with at most this many parameters
+    #[serde(default)]
+    pub max_arity: Option<usize>,
+    /// Don't count a `self` receiver toward arity
+    #[serde(default)]
+    pub exclude_self: bool,
+    /// Only trait methods without a default body
+    #[serde(default)]
+    pub required_only: bool,
+    /// Only trait methods that provide a default body
+    #[serde(default)]
+    pub default_only: bool,
+    /// Skip matches whose own name matches one of these globs
+    #[serde(default)]
+    pub exclude_names: Vec<String>,
+    /// Skip matches inside a function/type/module whose name matches one of these globs
+    #[serde(default)]
+    pub exclude_in: Vec<String>,
+    /// Report variants with identical fields within each enum (`name` picks the enum)
+    #[serde(default)]
+    pub duplicate_variants: bool,
+    /// Report struct fields named like a method in an impl of the same type (`name` picks
+    /// the type)
+    #[serde(default)]
+    pub field_method_collisions: bool,
+    /// Search the token streams of this macro's invocations instead of the AST
+    #[serde(default)]
+    pub in_macro_call: Option<String>,
+    /// Regex the match's name must match, instead of an exact `name` (see `name_matches`)
+    #[serde(default)]
+    pub name_regex: Option<String>,
+    /// List the calls to this function or method, grouped by the function they're in
+    #[serde(default)]
+    pub callers: Option<String>,
+    /// Report private fns, structs and consts whose name never appears outside their own
+    /// definition (dead-code candidates)
+    #[serde(default)]
+    pub unused: bool,
+    /// Report functions and methods whose bodies are identical once whitespace, comments
+    /// and local names are normalized (`node_type` limits it to `function`, `impl-method`
+    /// or `trait-method`)
+    #[serde(default)]
+    pub duplicates: bool,
+}
+
+impl FindArgs {
+    const fn has_arity_filter(&self) -> bool {
+        self.arity.is_some() || self.min_arity.is_some() || self.max_arity.is_some()
+    }
+
+    fn matches_arity(&self, result: &InspectResult) -> bool {
+        let Some(n) = result.arity.map(|arity| arity.count(self.exclude_self)) else {
+            return false;
+        };
+        self.arity.is_none_or(|a| n == a)
+            && self.min_arity.is_none_or(|min| n >= min)
+            && self.max_arity.is_none_or(|max| n <= max)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "kind", rename_all = "snake_case")]
 pub enum FindResult {
-    Field { matches: Vec<FieldLocation> },
-    Nodes { matches: Vec<InspectResult> },
+    Field {
+        matches: Vec<FieldLocation>,
+    },
+    Nodes {
+        matches: Vec<InspectResult>,
+        /// Matches left out inside code `--skip-tests`/`--skip-attr` skips, or outside the
+        /// `--in-module` module
+        #[serde(default)]
+        skipped: usize,
+    },
+    DuplicateVariants {
+        groups: Vec<DuplicateVariants>,
+    },
+    FieldMethodCollisions {
+        collisions: Vec<FieldMethodCollision>,
+    },
+    Callers {
+        groups: Vec<CallerGroup>,
+    },
+    Unused {
+        candidates: Vec<UnusedCandidate>,
+    },
+    DuplicateFunctions {
+        groups: Vec<DuplicateFunctions>,
+    },
 }
 
 impl FindResult {
     pub const fn is_empty(&self) -> bool {
         match self {
             Self::Field { matches } => matches.is_empty(),
-            Self::Nodes { matches } => matches.is_empty(),
+            Self::Nodes { matches, .. } => matches.is_empty(),
+            Self::DuplicateVariants { groups } => groups.is_empty(),
+            Self::FieldMethodCollisions { collisions } => collisions.is_empty(),
+            Self::Callers { groups } => groups.is_empty(),
+            Self::Unused { candidates } => candidates.is_empty(),
+            Self::DuplicateFunctions { groups } => groups.is_empty(),
         }
     }
 }
 
+/// A struct field sharing its name with methods in impls of the same type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldMethodCollision {
+    pub type_name: String,
+    pub field: String,
+    pub file_path: String,
+    pub line: usize,
+    pub methods: Vec<MethodSite>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MethodSite {
+    pub file_path: String,
+    pub line: usize,
+    /// Trait the method implements, for methods in trait impls
+    pub trait_name: Option<String>,
+    /// The body just returns the field (`self.x`, `&self.x`, `self.x.clone()`)
+    pub is_getter: bool,
+}
+
+/// The calls to one function made from one enclosing function (`find --callers`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallerGroup {
+    /// Enclosing function: `main`, `AppState::update` for a method, `Trait::method` for a
+    /// default method body, or `<top level>` for consts and statics
+    pub caller: String,
+    pub file_path: String,
+    pub count: usize,
+    pub calls: Vec<CallSite>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallSite {
+    pub line: usize,
+    pub column: usize,
+    /// The call as written, on one line
+    pub code: String,
+}
+
+/// A private item whose name is never used outside its own definition (`find --unused`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnusedCandidate {
+    /// `fn` (free function or inherent method), `struct` or `const`
+    pub item_kind: String,
+    /// The name; `Type::method` for methods
+    pub name: String,
+    pub file_path: String,
+    pub line: usize,
+}
+
+/// Functions and methods with the same normalized body (`find --duplicates`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateFunctions {
+    /// Length of the shared body, in tokens
+    pub tokens: usize,
+    pub functions: Vec<FunctionSite>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionSite {
+    /// `name`, or `Type::method`/`Trait::method` for methods
+    pub name: String,
+    /// `function`, `impl-method` or `trait-method`
+    pub node_type: String,
+    pub file_path: String,
+    pub line: usize,
+}
+
+/// Match counts without the matches themselves (`find --format json --summary`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FindSummary {
+    /// Matches per node type
+    pub by_type: BTreeMap<String, usize>,
+    /// Matches per file, for files with at least one
+    pub by_file: BTreeMap<String, usize>,
+    pub total: usize,
+}
+
+impl FindSummary {
+    pub fn from_results(results: &[InspectResult]) -> Self {
+        let mut summary = Self {
+            total: results.len(),
+            ..Self::default()
+        };
+        for result in results {
+            *summary.by_type.entry(result.node_type.clone()).or_default() += 1;
+            *summary.by_file.entry(result.file_path.clone()).or_default() += 1;
+        }
+        summary
+    }
+
+    /// `by_type`, most matches first (`find --format count-by-type`).
+    pub fn types_by_count(&self) -> Vec<(&str, usize)> {
+        let mut types: Vec<(&str, usize)> = self
+            .by_type
+            .iter()
+            .map(|(node_type, n)| (node_type.as_str(), *n))
+            .collect();
+        types.sort_by_key(|&(_, n)| std::cmp::Reverse(n));
+        types
+    }
+}
+
+/// Variants of one enum whose fields are token-for-token identical.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateVariants {
+    pub file_path: String,
+    pub enum_name: String,
+    /// The shared field list, e.g. `{ id : u32 }` or `(String)`
+    pub fields: String,
+    pub variants: Vec<VariantSite>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VariantSite {
+    pub name: String,
+    pub line: usize,
+}
+
 pub fn run(args: &FindArgs) -> Result<FindResult> {
-    let files = collect_rust_files_with_exclusions(&args.paths, &args.exclude)?;
+    let files = collect_rust_files_with_options(&args.paths, &args.exclude, &args.collect)?;
 
     if let Some(field) = &args.field_name {
         return Ok(FindResult::Field {
-            matches: find_field(&files, field)?,
+            matches: find_field(&files, field, &args.scope)?,
+        });
+    }
+
+    if args.duplicate_variants {
+        return Ok(FindResult::DuplicateVariants {
+            groups: find_duplicate_variants(&files, args.name.as_deref())?,
+        });
+    }
+
+    if args.field_method_collisions {
+        return Ok(FindResult::FieldMethodCollisions {
+            collisions: find_field_method_collisions(&files, args.name.as_deref())?,
+        });
+    }
+
+    if let Some(callee) = &args.callers {
+        return Ok(FindResult::Callers {
+            groups: find_callers(&files, callee)?,
+        });
+    }
+
+    if args.unused {
+        return Ok(FindResult::Unused {
+            candidates: find_unused(&files)?,
         });
     }
 
+    if args.duplicates {
+        return Ok(FindResult::DuplicateFunctions {
+            groups: find_duplicate_functions(&files, args.node_type.as_deref(), &args.scope)?,
+        });
+    }
+
+    let mut all_results: Vec<InspectResult> = Vec::new();
+    let skipped = search_nodes(&files, args, &mut |results| {
+        all_results.extend(results);
+        Ok(())
+    })?;
+
+    Ok(FindResult::Nodes {
+        matches: all_results,
+        skipped,
+    })
+}
+
+/// Node-mode search that hands over each file's matches as soon as the file is done.
+///
+/// Used by `find --format jsonl` to print matches before the scan ends. Files are visited
+/// in the same order as `run`; `on_file` is called for every file searched, with an empty
+/// `Vec` when nothing in it matched. Returns how many matches `--skip-tests`/`--skip-attr`
+/// left out.
+pub fn stream_nodes(
+    args: &FindArgs,
+    mut on_file: impl FnMut(Vec<InspectResult>) -> Result<()>,
+) -> Result<usize> {
+    let files = collect_rust_files_with_options(&args.paths, &args.exclude, &args.collect)?;
+    search_nodes(&files, args, &mut on_file)
+}
+
+/// Returns how many matches `--skip-tests`/`--skip-attr`/`--in-module` left out.
+fn search_nodes(
+    files: &[PathBuf],
+    args: &FindArgs,
+    on_file: &mut dyn FnMut(Vec<InspectResult>) -> Result<()>,
+) -> Result<usize> {
+    let name_regex = args
+        .name_regex
+        .as_deref()
+        .map(compile_name_regex)
+        .transpose()?;
+    if let Some(macro_name) = &args.in_macro_call {
+        return find_macro_tokens(files, macro_name, args, name_regex.as_ref(), on_file);
+    }
+
     let node_types_to_search: Vec<Option<&str>> = if let Some(k) = &args.kind {
         let expanded = expand_kind_to_node_types(k);
         if expanded.is_empty() {
             anyhow::bail!(
-                "Unknown kind '{}'. Valid kinds: struct, function, enum, match, identifier, type, macro, const, trait, mod, use",
-                k
+                "Unknown kind '{}'. Valid kinds: {} (see: rs-hack node-types)",
+                k,
+                KINDS.join(", ")
             );
         }
         expanded.into_iter().map(Some).collect()
@@ -66,57 +363,111 @@ pub fn run(args: &FindArgs) -> Result<FindResult> {
         vec![None]
     };
 
-    let mut all_results: Vec<InspectResult> = Vec::new();
-
-    for file in &files {
+    let mut skipped = 0;
+    let mut parsed = 0;
+    for file in files {
+        let started = std::time::Instant::now();
         let content = std::fs::read_to_string(file)
             .with_context(|| format!("Failed to read file: {:?}", file))?;
 
-        let editor = match RustEditor::new(&content) {
+        let editor = match RustEditor::with_scope(&content, args.scope.clone()) {
             Ok(e) => e,
+            Err(e) if args.strict => {
+                return Err(e).with_context(|| format!("Failed to parse {}", file.display()));
+            }
             Err(e) => {
                 eprintln!("⚠️  Skipping {}: {}", file.display(), e);
                 continue;
             }
         };
+        parsed += 1;
 
-        for node_type_to_search in &node_types_to_search {
-            let mut results = editor.inspect(
-                *node_type_to_search,
-                args.name.as_deref(),
-                args.variant.as_deref(),
-                args.include_comments,
-            )?;
+        let search = |editor: &RustEditor| {
+            matches_in(
+                editor,
+                file,
+                &node_types_to_search,
+                args,
+                name_regex.as_ref(),
+            )
+        };
+        let file_results = search(&editor)?;
+        if args.scope.skips_code() {
+            let unskipped = RustEditor::with_scope(&content, args.scope.unskipped())?;
+            skipped += search(&unskipped)?.len().saturating_sub(file_results.len());
+        }
 
-            for result in &mut results {
-                result.file_path = file.to_string_lossy().to_string();
-            }
+        args.verbose
+            .file_done(file, file_results.len(), started.elapsed());
+        on_file(file_results)?;
+    }
 
-            if let Some(filter) = &args.content_filter {
-                results.retain(|r| r.snippet.contains(filter));
-            }
+    if parsed == 0 && !files.is_empty() {
+        anyhow::bail!(
+            "None of the {} file(s) could be parsed; nothing was searched",
+            files.len()
+        );
+    }
+    Ok(skipped)
+}
 
-            all_results.extend(results);
+/// The matches for `args` in one file's `editor`, of each of `node_types`.
+fn matches_in(
+    editor: &RustEditor,
+    file: &std::path::Path,
+    node_types: &[Option<&str>],
+    args: &FindArgs,
+    name_regex: Option<&regex::Regex>,
+) -> Result<Vec<InspectResult>> {
+    let mut file_results = Vec::new();
+    for node_type in node_types {
+        let mut results = editor.inspect(
+            *node_type,
+            args.name.as_deref(),
+            args.variant.as_deref(),
+            args.include_comments,
+        )?;
+
+        for result in &mut results {
+            result.file_path = file.to_string_lossy().to_string();
         }
-    }
 
-    Ok(FindResult::Nodes {
-        matches: all_results,
-    })
+        if let Some(filter) = &args.content_filter {
+            results.retain(|r| r.snippet.contains(filter));
+        }
+
+        if let Some(regex) = name_regex {
+            results.retain(|r| name_matches(regex, &r.identifier));
+        }
+
+        if args.has_arity_filter() {
+            results.retain(|r| args.matches_arity(r));
+        }
+
+        if args.required_only || args.default_only {
+            results.retain(|r| {
+                trait_method_has_default(&r.node_type, &r.snippet) == Some(args.default_only)
+            });
+        }
+
+        editor.retain_not_excluded(&mut results, &args.exclude_names, &args.exclude_in);
+        file_results.extend(results);
+    }
+    Ok(file_results)
 }
 
 /// Re-search across all node types — used by the CLI to suggest near-misses
 /// when a typed search returns nothing. Exposed so embedders can offer the
 /// same hint UX.
 pub fn run_unfiltered_by_node_type(args: &FindArgs) -> Result<Vec<InspectResult>> {
-    let files = collect_rust_files_with_exclusions(&args.paths, &args.exclude)?;
+    let files = collect_rust_files_with_options(&args.paths, &args.exclude, &args.collect)?;
     let mut hint_results: Vec<InspectResult> = Vec::new();
 
     for file in &files {
         let content = std::fs::read_to_string(file)
             .with_context(|| format!("Failed to read file: {:?}", file))?;
 
-        let editor = match RustEditor::new(&content) {
+        let editor = match RustEditor::with_scope(&content, args.scope.clone()) {
             Ok(e) => e,
             Err(_) => continue,
         };
@@ -137,14 +488,92 @@ pub fn run_unfiltered_by_node_type(args: &FindArgs) -> Result<Vec<InspectResult>
     Ok(hint_results)
 }
 
-fn find_field(files: &[PathBuf], field: &str) -> Result<Vec<FieldLocation>> {
+/// Source lines with their 1-based numbers.
+pub type NumberedLines<'a> = Vec<(usize, &'a str)>;
+
+/// `--context N` for a match spanning lines `first..=last` (1-based): up to `n` numbered
+/// source lines above it and up to `n` below, clipped to the file.
+pub fn context_lines(
+    content: &str,
+    first: usize,
+    last: usize,
+    n: usize,
+) -> (NumberedLines<'_>, NumberedLines<'_>) {
+    let numbered = || content.lines().enumerate().map(|(i, line)| (i + 1, line));
+    let before = numbered()
+        .skip(first.saturating_sub(n + 1))
+        .take_while(|&(number, _)| number < first)
+        .collect();
+    let after = numbered().skip(last).take(n).collect();
+    (before, after)
+}
+
+/// Whether a `trait-method` match provides a default body (`Some(false)` for required
+/// methods). Returns `None` for other node types.
+pub fn trait_method_has_default(node_type: &str, snippet: &str) -> Option<bool> {
+    if node_type != "trait-method" {
+        return None;
+    }
+    Some(
+        syn::parse_str::<syn::TraitItemFn>(snippet)
+            .ok()?
+            .default
+            .is_some(),
+    )
+}
+
+/// Wrap each whole-word occurrence of `name` in `snippet` in bold yellow ANSI codes, for
+/// `find --highlight`.
+pub fn highlight_name(snippet: &str, name: &str) -> String {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    if name.is_empty() {
+        return snippet.to_string();
+    }
+    let mut out = String::with_capacity(snippet.len());
+    let mut rest = snippet;
+    while let Some(at) = rest.find(name) {
+        let end = at + name.len();
+        let bounded = !rest[..at].chars().next_back().is_some_and(is_ident)
+            && !rest[end..].chars().next().is_some_and(is_ident);
+        out.push_str(&rest[..at]);
+        if bounded {
+            out.push_str("\x1b[1;33m");
+            out.push_str(name);
+            out.push_str("\x1b[0m");
+        } else {
+            out.push_str(name);
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The word `--highlight` marks in a match: the last segment of `--name` when it's a plain
+/// name, else of the match's own identifier (`Trait for Type` → `Type`).
+pub fn highlight_target<'a>(identifier: &'a str, name: Option<&'a str>) -> &'a str {
+    let last = |s: &'a str| {
+        s.rsplit("::")
+            .next()
+            .unwrap_or(s)
+            .rsplit(' ')
+            .next()
+            .unwrap_or(s)
+    };
+    match name {
+        Some(name) if !name.contains(['*', '?', '[']) => last(name),
+        _ => last(identifier),
+    }
+}
+
+fn find_field(files: &[PathBuf], field: &str, scope: &Scope) -> Result<Vec<FieldLocation>> {
     let mut all_locations: Vec<FieldLocation> = Vec::new();
 
     for file in files {
         let content = std::fs::read_to_string(file)
             .with_context(|| format!("Failed to read file: {:?}", file))?;
 
-        let editor = match RustEditor::new(&content) {
+        let editor = match RustEditor::with_scope(&content, scope.clone()) {
             Ok(e) => e,
             Err(e) => {
                 eprintln!("⚠️  Skipping {}: {}", file.display(), e);
@@ -160,3 +589,1069 @@ fn find_field(files: &[PathBuf], field: &str) -> Result<Vec<FieldLocation>> {
 
     Ok(all_locations)
 }
+
+/// Group each enum's variants by their field tokens and keep the groups with more than one
+/// variant. Unit variants are all alike, so they're never reported.
+pub fn duplicate_variants_in(
+    syntax: &syn::File,
+    enum_name: Option<&str>,
+) -> Vec<DuplicateVariants> {
+    use quote::ToTokens;
+    use syn::visit::Visit;
+
+    struct EnumVisitor<'a> {
+        enum_name: Option<&'a str>,
+        groups: Vec<DuplicateVariants>,
+    }
+
+    impl<'ast> Visit<'ast> for EnumVisitor<'_> {
+        fn visit_item_enum(&mut self, node: &'ast syn::ItemEnum) {
+            if self.enum_name.is_none_or(|name| node.ident == name) {
+                let mut by_fields: Vec<DuplicateVariants> = Vec::new();
+                for variant in &node.variants {
+                    if matches!(variant.fields, syn::Fields::Unit) {
+                        continue;
+                    }
+                    let fields = variant.fields.to_token_stream().to_string();
+                    let site = VariantSite {
+                        name: variant.ident.to_string(),
+                        line: variant.ident.span().start().line,
+                    };
+                    match by_fields.iter_mut().find(|group| group.fields == fields) {
+                        Some(group) => group.variants.push(site),
+                        None => by_fields.push(DuplicateVariants {
+                            file_path: String::new(),
+                            enum_name: node.ident.to_string(),
+                            fields,
+                            variants: vec![site],
+                        }),
+                    }
+                }
+                self.groups.extend(
+                    by_fields
+                        .into_iter()
+                        .filter(|group| group.variants.len() > 1),
+                );
+            }
+            syn::visit::visit_item_enum(self, node);
+        }
+    }
+
+    let mut visitor = EnumVisitor {
+        enum_name,
+        groups: Vec::new(),
+    };
+    visitor.visit_file(syntax);
+    visitor.groups
+}
+
+fn find_duplicate_variants(
+    files: &[PathBuf],
+    enum_name: Option<&str>,
+) -> Result<Vec<DuplicateVariants>> {
+    let mut all_groups = Vec::new();
+
+    for file in files {
+        let content = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read file: {:?}", file))?;
+
+        let syntax = match syn::parse_file(&content) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("⚠️  Skipping {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        let mut groups = duplicate_variants_in(&syntax, enum_name);
+        for group in &mut groups {
+            group.file_path = file.to_string_lossy().to_string();
+        }
+        all_groups.extend(groups);
+    }
+
+    Ok(all_groups)
+}
+
+/// Fields whose name matches a method in an impl (inherent or trait) of the same type.
+///
+/// Types are matched by name across all of `sources` (`(file_path, syntax)` pairs), since
+/// impls often live in another file than the struct; same-named types in different modules
+/// are treated as one. `type_name` limits the check to one struct.
+pub fn field_method_collisions_in(
+    sources: &[(String, syn::File)],
+    type_name: Option<&str>,
+) -> Vec<FieldMethodCollision> {
+    use syn::visit::Visit;
+
+    struct MemberCollector<'a> {
+        file_path: &'a str,
+        /// (type, field, line, file)
+        fields: Vec<(String, String, usize, String)>,
+        /// (type, method, site)
+        methods: Vec<(String, String, MethodSite)>,
+    }
+
+    /// `self.x`, `&self.x`, or `self.x.clone()` as the whole body, with `x` the method name
+    fn is_getter(method: &syn::ImplItemFn) -> bool {
+        let [syn::Stmt::Expr(expr, None)] = method.block.stmts.as_slice() else {
+            return false;
+        };
+        let expr = match expr {
+            syn::Expr::Reference(r) => r.expr.as_ref(),
+            syn::Expr::MethodCall(call) if call.method == "clone" && call.args.is_empty() => {
+                call.receiver.as_ref()
+            }
+            other => other,
+        };
+        matches!(expr, syn::Expr::Field(field)
+            if matches!(&field.member, syn::Member::Named(ident) if *ident == method.sig.ident)
+                && matches!(field.base.as_ref(), syn::Expr::Path(p) if p.path.is_ident("self")))
+    }
+
+    impl<'ast> Visit<'ast> for MemberCollector<'_> {
+        fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
+            if let syn::Fields::Named(fields) = &node.fields {
+                for field in &fields.named {
+                    if let Some(ident) = &field.ident {
+                        self.fields.push((
+                            node.ident.to_string(),
+                            ident.to_string(),
+                            ident.span().start().line,
+                            self.file_path.to_string(),
+                        ));
+                    }
+                }
+            }
+            syn::visit::visit_item_struct(self, node);
+        }
+
+        fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+            if let syn::Type::Path(type_path) = node.self_ty.as_ref()
+                && let Some(seg) = type_path.path.segments.last()
+            {
+                let trait_name = node
+                    .trait_
+                    .as_ref()
+                    .and_then(|(_, path, _)| path.segments.last().map(|seg| seg.ident.to_string()));
+                for item in &node.items {
+                    if let syn::ImplItem::Fn(method) = item {
+                        self.methods.push((
+                            seg.ident.to_string(),
+                            method.sig.ident.to_string(),
+                            MethodSite {
+                                file_path: self.file_path.to_string(),
+                                line: method.sig.ident.span().start().line,
+                                trait_name: trait_name.clone(),
+                                is_getter: is_getter(method),
+                            },
+                        ));
+                    }
+                }
+            }
+            syn::visit::visit_item_impl(self, node);
+        }
+    }
+
+    let mut collector = MemberCollector {
+        file_path: "",
+        fields: Vec::new(),
+        methods: Vec::new(),
+    };
+    for (file_path, syntax) in sources {
+        collector.file_path = file_path;
+        collector.visit_file(syntax);
+    }
+
+    collector
+        .fields
+        .into_iter()
+        .filter(|(ty, ..)| type_name.is_none_or(|name| ty == name))
+        .filter_map(|(ty, field, line, file_path)| {
+            let methods: Vec<MethodSite> = collector
+                .methods
+                .iter()
+                .filter(|(method_ty, method, _)| *method_ty == ty && *method == field)
+                .map(|(_, _, site)| site.clone())
+                .collect();
+            (!methods.is_empty()).then_some(FieldMethodCollision {
+                type_name: ty,
+                field,
+                file_path,
+                line,
+                methods,
+            })
+        })
+        .collect()
+}
+
+fn find_field_method_collisions(
+    files: &[PathBuf],
+    type_name: Option<&str>,
+) -> Result<Vec<FieldMethodCollision>> {
+    let mut sources = Vec::new();
+
+    for file in files {
+        let content = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read file: {:?}", file))?;
+
+        match syn::parse_file(&content) {
+            Ok(syntax) => sources.push((file.to_string_lossy().to_string(), syntax)),
+            Err(e) => eprintln!("⚠️  Skipping {}: {}", file.display(), e),
+        }
+    }
+
+    Ok(field_method_collisions_in(&sources, type_name))
+}
+
+/// Calls to `callee` in one file, grouped by the function they're made from, in order of
+/// first call.
+///
+/// An unqualified name matches path calls by their last segment (`process(..)`,
+/// `Worker::process(..)`) and method calls by method name (`x.process()`); the receiver's
+/// type isn't checked. A qualified name (`Worker::process`) matches path calls ending in
+/// those segments, plus `Self::process(..)` and `self.process()` inside impls of `Worker`.
+/// Closures count toward the function they're written in. Calls inside macro invocations
+/// aren't seen.
+pub fn callers_in(syntax: &syn::File, content: &str, callee: &str) -> Vec<CallerGroup> {
+    use syn::spanned::Spanned;
+    use syn::visit::Visit;
+
+    struct CallerVisitor<'a> {
+        callee: Vec<&'a str>,
+        content: &'a str,
+        line_starts: Vec<usize>,
+        /// Self type of the impl (or trait) being visited
+        owner: Option<String>,
+        /// Function whose body is being visited
+        current_fn: Option<String>,
+        groups: Vec<CallerGroup>,
+    }
+
+    impl CallerVisitor<'_> {
+        fn record(&mut self, span: proc_macro2::Span) {
+            let offset = |lc: proc_macro2::LineColumn| {
+                let start = self.line_starts.get(lc.line - 1).copied().unwrap_or(0);
+                self.content[start..]
+                    .char_indices()
+                    .nth(lc.column)
+                    .map_or(self.content.len(), |(i, _)| start + i)
+            };
+            let code = self
+                .content
+                .get(offset(span.start())..offset(span.end()))
+                .unwrap_or_default()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            let site = CallSite {
+                line: span.start().line,
+                column: span.start().column,
+                code,
+            };
+            let caller = self
+                .current_fn
+                .clone()
+                .unwrap_or_else(|| "<top level>".to_string());
+            match self.groups.iter_mut().find(|g| g.caller == caller) {
+                Some(group) => {
+                    group.count += 1;
+                    group.calls.push(site);
+                }
+                None => self.groups.push(CallerGroup {
+                    caller,
+                    file_path: String::new(),
+                    count: 1,
+                    calls: vec![site],
+                }),
+            }
+        }
+
+        fn enter_fn(&mut self, name: &syn::Ident) -> Option<String> {
+            let qualified = self
+                .owner
+                .as_ref()
+                .map_or_else(|| name.to_string(), |owner| format!("{}::{}", owner, name));
+            self.current_fn.replace(qualified)
+        }
+    }
+
+    impl<'ast> Visit<'ast> for CallerVisitor<'_> {
+        fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+            // A nested fn item is its own caller, with no impl around it
+            let outer_owner = self.owner.take();
+            let outer = self.enter_fn(&node.sig.ident);
+            syn::visit::visit_item_fn(self, node);
+            self.current_fn = outer;
+            self.owner = outer_owner;
+        }
+
+        fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+            let owner = match node.self_ty.as_ref() {
+                syn::Type::Path(type_path) => type_path
+                    .path
+                    .segments
+                    .last()
+                    .map(|seg| seg.ident.to_string()),
+                _ => None,
+            };
+            let outer = std::mem::replace(&mut self.owner, owner);
+            syn::visit::visit_item_impl(self, node);
+            self.owner = outer;
+        }
+
+        fn visit_item_trait(&mut self, node: &'ast syn::ItemTrait) {
+            let outer = self.owner.replace(node.ident.to_string());
+            syn::visit::visit_item_trait(self, node);
+            self.owner = outer;
+        }
+
+        fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+            let outer = self.enter_fn(&node.sig.ident);
+            syn::visit::visit_impl_item_fn(self, node);
+            self.current_fn = outer;
+        }
+
+        fn visit_trait_item_fn(&mut self, node: &'ast syn::TraitItemFn) {
+            let outer = self.enter_fn(&node.sig.ident);
+            syn::visit::visit_trait_item_fn(self, node);
+            self.current_fn = outer;
+        }
+
+        fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+            if let syn::Expr::Path(path) = node.func.as_ref() {
+                let segments = &path.path.segments;
+                let skip = segments.len().checked_sub(self.callee.len());
+                // `Self::process()` inside `impl Worker` is `Worker::process()`
+                let owner = self.owner.as_deref();
+                if skip.is_some_and(|skip| {
+                    segments
+                        .iter()
+                        .skip(skip)
+                        .zip(&self.callee)
+                        .all(|(seg, name)| {
+                            seg.ident == name || (seg.ident == "Self" && owner == Some(*name))
+                        })
+                }) {
+                    self.record(node.span());
+                }
+            }
+            syn::visit::visit_expr_call(self, node);
+        }
+
+        fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
+            let on_self =
+                matches!(node.receiver.as_ref(), syn::Expr::Path(p) if p.path.is_ident("self"));
+            let matches = match self.callee.as_slice() {
+                [name] => node.method == name,
+                // `self.process()` inside `impl Worker` for `Worker::process`
+                [.., ty, name] => {
+                    node.method == name && on_self && self.owner.as_deref() == Some(*ty)
+                }
+                [] => false,
+            };
+            if matches {
+                self.record(node.span());
+            }
+            syn::visit::visit_expr_method_call(self, node);
+        }
+    }
+
+    let mut visitor = CallerVisitor {
+        callee: callee.split("::").map(str::trim).collect(),
+        content,
+        line_starts: std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+            .collect(),
+        owner: None,
+        current_fn: None,
+        groups: Vec::new(),
+    };
+    visitor.visit_file(syntax);
+    visitor.groups
+}
+
+/// `callers_in` over every file, busiest callers first (ties keep file order).
+fn find_callers(files: &[PathBuf], callee: &str) -> Result<Vec<CallerGroup>> {
+    let mut all_groups = Vec::new();
+
+    for file in files {
+        let content = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read file: {:?}", file))?;
+
+        let syntax = match syn::parse_file(&content) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("⚠️  Skipping {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        let mut groups = callers_in(&syntax, &content, callee);
+        for group in &mut groups {
+            group.file_path = file.to_string_lossy().to_string();
+        }
+        all_groups.extend(groups);
+    }
+
+    all_groups.sort_by_key(|group| std::cmp::Reverse(group.count));
+    Ok(all_groups)
+}
+
+/// Private items across `sources` whose name appears nowhere outside the item itself, in
+/// file order.
+///
+/// A heuristic, kept conservative: any identifier with the same name counts as a use (a
+/// field, a local, a token inside a macro invocation), so a reported item is "defined once,
+/// referenced never" by name. It still can't see uses through macro expansion, trait
+/// dispatch, FFI or other crates. Only items without a visibility qualifier are considered:
+/// free functions, methods of inherent impls, structs and consts. `main`, tests and items
+/// under `#[allow(dead_code)]` (on the item or an enclosing module or impl), `#[no_mangle]`
+/// or `#[export_name]` are left out.
+pub fn unused_in(sources: &[(String, syn::File)]) -> Vec<UnusedCandidate> {
+    use std::collections::{HashMap, HashSet};
+
+    use proc_macro2::{LineColumn, Span, TokenTree};
+    use syn::spanned::Spanned;
+    use syn::visit::Visit;
+
+    /// A candidate and the span of its definition, as (line, column) pairs.
+    type Definition = (UnusedCandidate, String, (usize, usize), (usize, usize));
+
+    fn exempt(attrs: &[syn::Attribute]) -> bool {
+        attrs.iter().any(|attr| {
+            let path = attr.path();
+            path.is_ident("test")
+                || path.is_ident("no_mangle")
+                || path.is_ident("export_name")
+                || (path.is_ident("allow")
+                    && attr
+                        .meta
+                        .require_list()
+                        .is_ok_and(|list| list.tokens.to_string().contains("dead_code")))
+        })
+    }
+
+    const fn at(lc: LineColumn) -> (usize, usize) {
+        (lc.line, lc.column)
+    }
+
+    struct DefinitionCollector<'a> {
+        file_path: &'a str,
+        /// Inside an `#[allow(dead_code)]` module or impl
+        allowed: bool,
+        definitions: Vec<Definition>,
+    }
+
+    impl DefinitionCollector<'_> {
+        fn define(&mut self, item_kind: &str, ident: &syn::Ident, name: String, span: Span) {
+            self.definitions.push((
+                UnusedCandidate {
+                    item_kind: item_kind.to_string(),
+                    name,
+                    file_path: self.file_path.to_string(),
+                    line: ident.span().start().line,
+                },
+                ident.to_string(),
+                at(span.start()),
+                at(span.end()),
+            ));
+        }
+    }
+
+    impl<'ast> Visit<'ast> for DefinitionCollector<'_> {
+        fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+            if matches!(node.vis, syn::Visibility::Inherited)
+                && !self.allowed
+                && !exempt(&node.attrs)
+                && node.sig.ident != "main"
+            {
+                self.define(
+                    "fn",
+                    &node.sig.ident,
+                    node.sig.ident.to_string(),
+                    node.span(),
+                );
+            }
+            syn::visit::visit_item_fn(self, node);
+        }
+
+        fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
+            if matches!(node.vis, syn::Visibility::Inherited)
+                && !self.allowed
+                && !exempt(&node.attrs)
+            {
+                self.define("struct", &node.ident, node.ident.to_string(), node.span());
+            }
+            syn::visit::visit_item_struct(self, node);
+        }
+
+        fn visit_item_const(&mut self, node: &'ast syn::ItemConst) {
+            if matches!(node.vis, syn::Visibility::Inherited)
+                && !self.allowed
+                && !exempt(&node.attrs)
+                && node.ident != "_"
+            {
+                self.define("const", &node.ident, node.ident.to_string(), node.span());
+            }
+            syn::visit::visit_item_const(self, node);
+        }
+
+        fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+            let outer = self.allowed;
+            self.allowed |= exempt(&node.attrs);
+            syn::visit::visit_item_mod(self, node);
+            self.allowed = outer;
+        }
+
+        fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+            let outer = self.allowed;
+            self.allowed |= exempt(&node.attrs);
+            // Trait impl methods are called through the trait
+            if node.trait_.is_none()
+                && !self.allowed
+                && let syn::Type::Path(type_path) = node.self_ty.as_ref()
+                && let Some(ty) = type_path.path.segments.last()
+            {
+                for item in &node.items {
+                    if let syn::ImplItem::Fn(method) = item
+                        && matches!(method.vis, syn::Visibility::Inherited)
+                        && !exempt(&method.attrs)
+                    {
+                        let name = format!("{}::{}", ty.ident, method.sig.ident);
+                        self.define("fn", &method.sig.ident, name, method.span());
+                    }
+                }
+            }
+            syn::visit::visit_item_impl(self, node);
+            self.allowed = outer;
+        }
+    }
+
+    /// Where each defined name appears, including inside macro invocations.
+    struct UseCollector<'a> {
+        names: &'a HashSet<String>,
+        found: HashMap<String, Vec<(usize, usize)>>,
+    }
+
+    impl UseCollector<'_> {
+        fn record(&mut self, ident: &proc_macro2::Ident) {
+            self.record_name(ident.to_string(), ident.span());
+        }
+
+        fn record_name(&mut self, name: String, span: Span) {
+            if self.names.contains(&name) {
+                self.found.entry(name).or_default().push(at(span.start()));
+            }
+        }
+
+        fn record_tokens(&mut self, tokens: proc_macro2::TokenStream) {
+            for token in tokens {
+                match token {
+                    TokenTree::Ident(ident) => self.record(&ident),
+                    TokenTree::Group(group) => self.record_tokens(group.stream()),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    impl<'ast> Visit<'ast> for UseCollector<'_> {
+        fn visit_ident(&mut self, ident: &'ast proc_macro2::Ident) {
+            self.record(ident);
+        }
+
+        fn visit_macro(&mut self, node: &'ast syn::Macro) {
+            self.record_tokens(node.tokens.clone());
+            syn::visit::visit_macro(self, node);
+        }
+
+        fn visit_attribute(&mut self, node: &'ast syn::Attribute) {
+            // `#[serde(default = "make_default")]` and the like name functions in strings
+            if let syn::Meta::List(list) = &node.meta {
+                for token in list.tokens.clone() {
+                    if let TokenTree::Literal(lit) = token
+                        && let Some(last) = lit.to_string().trim_matches('"').rsplit("::").next()
+                    {
+                        self.record_name(last.to_string(), lit.span());
+                    }
+                }
+            }
+            syn::visit::visit_attribute(self, node);
+        }
+    }
+
+    let mut definitions = Vec::new();
+    for (file_path, syntax) in sources {
+        let mut collector = DefinitionCollector {
+            file_path,
+            allowed: false,
+            definitions: Vec::new(),
+        };
+        collector.visit_file(syntax);
+        definitions.extend(collector.definitions);
+    }
+
+    let names: HashSet<String> = definitions
+        .iter()
+        .map(|(_, name, ..)| name.clone())
+        .collect();
+    let uses: Vec<HashMap<String, Vec<(usize, usize)>>> = sources
+        .iter()
+        .map(|(_, syntax)| {
+            let mut collector = UseCollector {
+                names: &names,
+                found: HashMap::new(),
+            };
+            collector.visit_file(syntax);
+            collector.found
+        })
+        .collect();
+
+    definitions
+        .into_iter()
+        .filter(|(candidate, name, start, end)| {
+            let used = sources.iter().zip(&uses).any(|((file_path, _), found)| {
+                let in_definition = |pos: &(usize, usize)| {
+                    *file_path == candidate.file_path && start <= pos && pos < end
+                };
+                found
+                    .get(name)
+                    .is_some_and(|positions| positions.iter().any(|pos| !in_definition(pos)))
+            });
+            !used
+        })
+        .map(|(candidate, ..)| candidate)
+        .collect()
+}
+
+fn find_unused(files: &[PathBuf]) -> Result<Vec<UnusedCandidate>> {
+    let mut sources = Vec::new();
+
+    for file in files {
+        let content = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read file: {:?}", file))?;
+
+        match syn::parse_file(&content) {
+            Ok(syntax) => sources.push((file.to_string_lossy().to_string(), syntax)),
+            Err(e) => eprintln!("⚠️  Skipping {}: {}", file.display(), e),
+        }
+    }
+
+    Ok(unused_in(&sources))
+}
+
+/// Node types `find --duplicates` compares.
+pub const DUPLICATE_NODE_TYPES: &[&str] = &["function", "impl-method", "trait-method"];
+
+/// Bodies shorter than this many tokens (`{ self.x }`, `{ Self::default() }`) are too
+/// common to be worth reporting as duplicates.
+const MIN_DUPLICATE_TOKENS: usize = 10;
+
+/// Groups of functions and methods across `sources` whose bodies are the same token for
+/// token, biggest bodies first.
+///
+/// Bodies are compared as token streams, so whitespace and comments don't count. Parameters
+/// and bindings introduced in the body (`let`, closure, `for` and `match` patterns) are
+/// renamed by order of first use, so `fn a(x: u32) { x + 1 }` and `fn b(y: u32) { y + 1 }`
+/// match; everything else, including field and method names, must be spelled the same.
+/// `node_type` limits the comparison to one of `DUPLICATE_NODE_TYPES`.
+pub fn duplicate_functions_in(
+    sources: &[(String, syn::File)],
+    node_type: Option<&str>,
+) -> Vec<DuplicateFunctions> {
+    group_duplicate_bodies(
+        sources
+            .iter()
+            .flat_map(|(file_path, syntax)| function_bodies(file_path, syntax, node_type))
+            .collect(),
+    )
+}
+
+fn find_duplicate_functions(
+    files: &[PathBuf],
+    node_type: Option<&str>,
+    scope: &Scope,
+) -> Result<Vec<DuplicateFunctions>> {
+    if let Some(node_type) = node_type
+        && !DUPLICATE_NODE_TYPES.contains(&node_type)
+    {
+        anyhow::bail!(
+            "--duplicates compares function bodies; --node-type must be one of: {}",
+            DUPLICATE_NODE_TYPES.join(", ")
+        );
+    }
+    let mut bodies = Vec::new();
+
+    for file in files {
+        let content = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read file: {:?}", file))?;
+
+        let mut syntax = match syn::parse_file(&content) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("⚠️  Skipping {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        scope.prune(&mut syntax);
+        bodies.extend(function_bodies(&file.to_string_lossy(), &syntax, node_type));
+    }
+
+    Ok(group_duplicate_bodies(bodies))
+}
+
+fn group_duplicate_bodies(bodies: Vec<(String, usize, FunctionSite)>) -> Vec<DuplicateFunctions> {
+    let mut groups: HashMap<String, DuplicateFunctions> = HashMap::new();
+    for (body, tokens, site) in bodies {
+        groups
+            .entry(body)
+            .or_insert_with(|| DuplicateFunctions {
+                tokens,
+                functions: Vec::new(),
+            })
+            .functions
+            .push(site);
+    }
+    let mut groups: Vec<DuplicateFunctions> = groups
+        .into_values()
+        .filter(|group| group.functions.len() > 1)
+        .collect();
+    // Biggest first; ties by where their first function is
+    groups.sort_by(|a, b| {
+        let first = |group: &DuplicateFunctions| {
+            let site = &group.functions[0];
+            (site.file_path.clone(), site.line)
+        };
+        b.tokens
+            .cmp(&a.tokens)
+            .then_with(|| first(a).cmp(&first(b)))
+    });
+    groups
+}
+
+/// The normalized body, its length in tokens and its site, for each function in `syntax`
+/// with a body of at least `MIN_DUPLICATE_TOKENS` tokens.
+fn function_bodies(
+    file_path: &str,
+    syntax: &syn::File,
+    node_type: Option<&str>,
+) -> Vec<(String, usize, FunctionSite)> {
+    use syn::visit::Visit;
+
+    struct BodyCollector<'a> {
+        file_path: &'a str,
+        node_type: Option<&'a str>,
+        bodies: Vec<(String, usize, FunctionSite)>,
+    }
+
+    impl BodyCollector<'_> {
+        fn collect(
+            &mut self,
+            node_type: &str,
+            name: String,
+            sig: &syn::Signature,
+            block: &syn::Block,
+        ) {
+            if self.node_type.is_some_and(|wanted| wanted != node_type) {
+                return;
+            }
+            let (body, tokens) = normalized_body(sig, block);
+            if tokens >= MIN_DUPLICATE_TOKENS {
+                self.bodies.push((
+                    body,
+                    tokens,
+                    FunctionSite {
+                        name,
+                        node_type: node_type.to_string(),
+                        file_path: self.file_path.to_string(),
+                        line: sig.ident.span().start().line,
+                    },
+                ));
+            }
+        }
+    }
+
+    impl<'ast> Visit<'ast> for BodyCollector<'_> {
+        fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+            self.collect(
+                "function",
+                node.sig.ident.to_string(),
+                &node.sig,
+                &node.block,
+            );
+            syn::visit::visit_item_fn(self, node);
+        }
+
+        fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+            // `Type::` for the methods of an impl of a named type
+            let prefix = match node.self_ty.as_ref() {
+                syn::Type::Path(type_path) => type_path
+                    .path
+                    .segments
+                    .last()
+                    .map(|segment| format!("{}::", segment.ident))
+                    .unwrap_or_default(),
+                _ => String::new(),
+            };
+            for item in &node.items {
+                if let syn::ImplItem::Fn(method) = item {
+                    let name = format!("{}{}", prefix, method.sig.ident);
+                    self.collect("impl-method", name, &method.sig, &method.block);
+                }
+            }
+            syn::visit::visit_item_impl(self, node);
+        }
+
+        fn visit_item_trait(&mut self, node: &'ast syn::ItemTrait) {
+            for item in &node.items {
+                if let syn::TraitItem::Fn(method) = item
+                    && let Some(block) = &method.default
+                {
+                    let name = format!("{}::{}", node.ident, method.sig.ident);
+                    self.collect("trait-method", name, &method.sig, block);
+                }
+            }
+            syn::visit::visit_item_trait(self, node);
+        }
+    }
+
+    let mut collector = BodyCollector {
+        file_path,
+        node_type,
+        bodies: Vec::new(),
+    };
+    collector.visit_file(syntax);
+    collector.bodies
+}
+
+/// `block` as a string of tokens with parameters and local bindings renamed `_0`, `_1`, ...
+/// in order of first appearance, and its length in tokens.
+fn normalized_body(sig: &syn::Signature, block: &syn::Block) -> (String, usize) {
+    use std::collections::{HashMap, HashSet};
+
+    use proc_macro2::{Delimiter, TokenStream, TokenTree};
+    use quote::ToTokens;
+    use syn::visit::Visit;
+
+    struct Bindings(HashSet<String>);
+
+    impl<'ast> Visit<'ast> for Bindings {
+        fn visit_pat_ident(&mut self, node: &'ast syn::PatIdent) {
+            // `None` and other unit variants in patterns parse as identifiers too
+            let name = node.ident.to_string();
+            if !name.starts_with(char::is_uppercase) {
+                self.0.insert(name);
+            }
+            syn::visit::visit_pat_ident(self, node);
+        }
+
+        fn visit_item(&mut self, _: &'ast syn::Item) {
+            // Nested items are compared as part of the body, but their bindings are their own
+        }
+    }
+
+    struct Normalizer {
+        bindings: HashSet<String>,
+        renamed: HashMap<String, usize>,
+        out: String,
+        tokens: usize,
+    }
+
+    impl Normalizer {
+        fn push(&mut self, text: &str) {
+            if !self.out.is_empty() {
+                self.out.push(' ');
+            }
+            self.out.push_str(text);
+        }
+
+        fn walk(&mut self, stream: TokenStream) {
+            for token in stream {
+                match token {
+                    TokenTree::Group(group) => {
+                        let (open, close) = match group.delimiter() {
+                            Delimiter::Parenthesis => ("(", ")"),
+                            Delimiter::Brace => ("{", "}"),
+                            Delimiter::Bracket => ("[", "]"),
+                            Delimiter::None => ("", ""),
+                        };
+                        self.push(open);
+                        self.walk(group.stream());
+                        self.push(close);
+                    }
+                    TokenTree::Ident(ident) => {
+                        let name = ident.to_string();
+                        if self.bindings.contains(&name) {
+                            let next = self.renamed.len();
+                            let n = *self.renamed.entry(name).or_insert(next);
+                            self.push(&format!("_{n}"));
+                        } else {
+                            self.push(&name);
+                        }
+                        self.tokens += 1;
+                    }
+                    TokenTree::Punct(punct) => {
+                        self.push(&punct.as_char().to_string());
+                        self.tokens += 1;
+                    }
+                    TokenTree::Literal(lit) => {
+                        self.push(&lit.to_string());
+                        self.tokens += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut bindings = Bindings(HashSet::new());
+    for input in &sig.inputs {
+        if let syn::FnArg::Typed(arg) = input {
+            bindings.visit_pat(&arg.pat);
+        }
+    }
+    bindings.visit_block(block);
+
+    let mut normalizer = Normalizer {
+        bindings: bindings.0,
+        renamed: HashMap::new(),
+        out: String::new(),
+        tokens: 0,
+    };
+    normalizer.walk(block.to_token_stream());
+    (normalizer.out, normalizer.tokens)
+}
+
+/// Identifiers in the token streams of `macro_name!` invocations (by last path segment),
+/// reported as `macro-token` matches.
+///
+/// syn leaves macro input unparsed, so this is a plain
+/// token scan: it sees the names a `bitflags!` or DSL invocation spells out, not what the
+/// macro expands to. The snippet is the source line the identifier is on.
+pub fn macro_tokens_in(
+    syntax: &syn::File,
+    content: &str,
+    macro_name: &str,
+    matches_name: &dyn Fn(&str) -> bool,
+) -> Vec<InspectResult> {
+    use syn::ext::IdentExt;
+    use syn::visit::Visit;
+
+    use crate::operations::NodeLocation;
+
+    struct MacroTokenVisitor<'a> {
+        macro_name: &'a str,
+        matches_name: &'a dyn Fn(&str) -> bool,
+        lines: Vec<&'a str>,
+        results: Vec<InspectResult>,
+    }
+
+    impl MacroTokenVisitor<'_> {
+        fn scan(&mut self, tokens: proc_macro2::TokenStream) {
+            for tt in tokens {
+                match tt {
+                    proc_macro2::TokenTree::Ident(ident) => {
+                        let name = ident.unraw().to_string();
+                        if !(self.matches_name)(&name) {
+                            continue;
+                        }
+                        let (start, end) = (ident.span().start(), ident.span().end());
+                        let snippet = self
+                            .lines
+                            .get(start.line.saturating_sub(1))
+                            .map_or_else(String::new, |line| line.trim().to_string());
+                        self.results.push(InspectResult {
+                            file_path: String::new(),
+                            node_type: "macro-token".to_string(),
+                            identifier: name,
+                            location: NodeLocation {
+                                line: start.line,
+                                column: start.column,
+                                end_line: end.line,
+                                end_column: end.column,
+                            },
+                            snippet,
+                            preceding_comment: None,
+                            arity: None,
+                        });
+                    }
+                    proc_macro2::TokenTree::Group(group) => self.scan(group.stream()),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    impl<'ast> Visit<'ast> for MacroTokenVisitor<'_> {
+        fn visit_macro(&mut self, node: &'ast syn::Macro) {
+            if node
+                .path
+                .segments
+                .last()
+                .is_some_and(|seg| seg.ident == self.macro_name)
+            {
+                self.scan(node.tokens.clone());
+            }
+        }
+    }
+
+    let mut visitor = MacroTokenVisitor {
+        macro_name: macro_name.trim_end_matches('!'),
+        matches_name,
+        lines: content.lines().collect(),
+        results: Vec::new(),
+    };
+    visitor.visit_file(syntax);
+    visitor.results
+}
+
+fn find_macro_tokens(
+    files: &[PathBuf],
+    macro_name: &str,
+    args: &FindArgs,
+    regex: Option<&regex::Regex>,
+    on_file: &mut dyn FnMut(Vec<InspectResult>) -> Result<()>,
+) -> Result<usize> {
+    let matches_name = |ident: &str| {
+        args.name.as_deref().is_none_or(|name| name == ident)
+            && regex.is_none_or(|re| name_matches(re, ident))
+    };
+
+    let mut skipped = 0;
+    for file in files {
+        let content = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read file: {:?}", file))?;
+
+        let mut syntax = match syn::parse_file(&content) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("⚠️  Skipping {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        let matches = |syntax: &syn::File| -> Result<Vec<InspectResult>> {
+            let mut results = macro_tokens_in(syntax, &content, macro_name, &matches_name);
+            for result in &mut results {
+                result.file_path = file.to_string_lossy().to_string();
+            }
+            if let Some(filter) = &args.content_filter {
+                results.retain(|r| r.snippet.contains(filter));
+            }
+            if !args.exclude_names.is_empty() || !args.exclude_in.is_empty() {
+                RustEditor::new(&content)?.retain_not_excluded(
+                    &mut results,
+                    &args.exclude_names,
+                    &args.exclude_in,
+                );
+            }
+            Ok(results)
+        };
+        // What `--skip-tests`/`--skip-attr`/`--in-module` leave out is pruned second, to count it
+        args.scope.unskipped().prune(&mut syntax);
+        let unskipped = matches(&syntax)?.len();
+        args.scope.prune(&mut syntax);
+        let results = matches(&syntax)?;
+        skipped += unskipped - results.len();
+        on_file(results)?;
+    }
+
+    Ok(skipped)
+}
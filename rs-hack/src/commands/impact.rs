@@ -0,0 +1,372 @@
+//! `impact` command: everything that references a type, grouped by how it's used, to size
+//! up what removing or reshaping the type would break.
+//!
+//! Read-only. The type is matched by name (the last path segment), so same-named types in
+//! different modules are counted together. Each site is counted once, under the most
+//! specific category: a field of type `Vec<Config>` is a field, not also a type reference.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use proc_macro2::Span;
+use serde::Serialize;
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+use crate::files::{CollectOptions, collect_rust_files_with_options};
+
+/// Categories in report order: (key, heading).
+pub const CATEGORIES: &[(&str, &str)] = &[
+    ("struct-literal", "Struct literals"),
+    ("field", "Fields embedding it"),
+    ("signature", "Function signatures"),
+    ("impl", "Impl blocks"),
+    ("path", "Associated items and constructors"),
+    ("use", "Imports"),
+    ("type-ref", "Other type references"),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImpactSite {
+    pub file_path: String,
+    pub line: usize,
+    pub column: usize,
+    /// The referencing code, on one line (e.g. `Server::config: Arc<Config>`)
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImpactCategory {
+    pub category: &'static str,
+    pub sites: Vec<ImpactSite>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImpactReport {
+    pub type_name: String,
+    /// Where a struct, enum, union or type alias of that name is defined
+    pub definitions: Vec<ImpactSite>,
+    /// Every category, in `CATEGORIES` order (empty ones included)
+    pub categories: Vec<ImpactCategory>,
+    /// Number of referencing sites across all categories
+    pub blast_radius: usize,
+    /// Files with at least one reference
+    pub files: usize,
+}
+
+pub fn run(
+    paths: &[PathBuf],
+    type_name: &str,
+    exclude: &[String],
+    options: &CollectOptions,
+) -> Result<ImpactReport> {
+    let name = type_name.rsplit("::").next().unwrap_or(type_name);
+    let files = collect_rust_files_with_options(paths, exclude, options)?;
+
+    let mut report = ImpactReport {
+        type_name: name.to_string(),
+        definitions: Vec::new(),
+        categories: CATEGORIES
+            .iter()
+            .map(|&(category, _)| ImpactCategory {
+                category,
+                sites: Vec::new(),
+            })
+            .collect(),
+        blast_radius: 0,
+        files: 0,
+    };
+
+    for file in &files {
+        let content = match std::fs::read_to_string(file) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("⚠️  Skipping {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        let syntax = match syn::parse_file(&content) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("⚠️  Skipping {} (parse error): {}", file.display(), e);
+                continue;
+            }
+        };
+
+        let mut visitor = ImpactVisitor {
+            name,
+            file_path: file.to_string_lossy().to_string(),
+            content: &content,
+            line_starts: std::iter::once(0)
+                .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+                .collect(),
+            owner: None,
+            definitions: Vec::new(),
+            sites: Vec::new(),
+        };
+        visitor.visit_file(&syntax);
+
+        report.definitions.append(&mut visitor.definitions);
+        if !visitor.sites.is_empty() {
+            report.files += 1;
+        }
+        for (category, site) in visitor.sites {
+            if let Some(entry) = report
+                .categories
+                .iter_mut()
+                .find(|c| c.category == category)
+            {
+                entry.sites.push(site);
+                report.blast_radius += 1;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+pub fn render(report: &ImpactReport) {
+    println!(
+        "Blast radius of `{}`: {} reference{} in {} file{}",
+        report.type_name,
+        report.blast_radius,
+        if report.blast_radius == 1 { "" } else { "s" },
+        report.files,
+        if report.files == 1 { "" } else { "s" }
+    );
+    if report.definitions.is_empty() {
+        println!("Definition: not found in the given paths");
+    }
+    for def in &report.definitions {
+        println!("Defined at {}:{}  {}", def.file_path, def.line, def.code);
+    }
+
+    for category in &report.categories {
+        if category.sites.is_empty() {
+            continue;
+        }
+        let heading = CATEGORIES
+            .iter()
+            .find(|(key, _)| *key == category.category)
+            .map_or(category.category, |(_, heading)| heading);
+        println!("\n{} ({})", heading, category.sites.len());
+        for site in &category.sites {
+            println!("  {}:{}  {}", site.file_path, site.line, site.code);
+        }
+    }
+}
+
+// ---- helpers ----------------------------------------------------------------
+
+struct ImpactVisitor<'a> {
+    name: &'a str,
+    file_path: String,
+    content: &'a str,
+    line_starts: Vec<usize>,
+    /// Type or enum whose fields are being visited, for labelling field sites
+    owner: Option<String>,
+    definitions: Vec<ImpactSite>,
+    sites: Vec<(&'static str, ImpactSite)>,
+}
+
+impl ImpactVisitor<'_> {
+    /// Whether the type is named anywhere inside a node (`visit` walks it).
+    fn mentions(&self, visit: impl FnOnce(&mut MentionFinder<'_>)) -> bool {
+        let mut finder = MentionFinder {
+            name: self.name,
+            found: false,
+        };
+        visit(&mut finder);
+        finder.found
+    }
+
+    /// Source text of `span`, whitespace collapsed to single spaces.
+    fn text(&self, span: Span) -> String {
+        let offset = |lc: proc_macro2::LineColumn| {
+            let start = self.line_starts.get(lc.line - 1).copied().unwrap_or(0);
+            self.content[start..]
+                .char_indices()
+                .nth(lc.column)
+                .map_or(self.content.len(), |(i, _)| start + i)
+        };
+        let (start, end) = (offset(span.start()), offset(span.end()));
+        self.content
+            .get(start..end)
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn site(&self, span: Span, code: String) -> ImpactSite {
+        ImpactSite {
+            file_path: self.file_path.clone(),
+            line: span.start().line,
+            column: span.start().column,
+            code,
+        }
+    }
+
+    fn push(&mut self, category: &'static str, span: Span, code: String) {
+        let site = self.site(span, code);
+        self.sites.push((category, site));
+    }
+
+    fn define(&mut self, ident: &syn::Ident, kind: &str) {
+        if ident == self.name {
+            let site = self.site(ident.span(), format!("{} {}", kind, ident));
+            self.definitions.push(site);
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for ImpactVisitor<'_> {
+    fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
+        self.define(&node.ident, "struct");
+        let outer = self.owner.replace(node.ident.to_string());
+        syn::visit::visit_item_struct(self, node);
+        self.owner = outer;
+    }
+
+    fn visit_item_enum(&mut self, node: &'ast syn::ItemEnum) {
+        self.define(&node.ident, "enum");
+        let outer = self.owner.replace(node.ident.to_string());
+        syn::visit::visit_item_enum(self, node);
+        self.owner = outer;
+    }
+
+    fn visit_item_union(&mut self, node: &'ast syn::ItemUnion) {
+        self.define(&node.ident, "union");
+        let outer = self.owner.replace(node.ident.to_string());
+        syn::visit::visit_item_union(self, node);
+        self.owner = outer;
+    }
+
+    fn visit_item_type(&mut self, node: &'ast syn::ItemType) {
+        self.define(&node.ident, "type");
+        syn::visit::visit_item_type(self, node);
+    }
+
+    fn visit_variant(&mut self, node: &'ast syn::Variant) {
+        // Fields of a variant are labelled `Enum::Variant`
+        let outer = self.owner.clone();
+        self.owner = Some(format!(
+            "{}::{}",
+            outer.as_deref().unwrap_or_default(),
+            node.ident
+        ));
+        syn::visit::visit_variant(self, node);
+        self.owner = outer;
+    }
+
+    fn visit_field(&mut self, node: &'ast syn::Field) {
+        if self.mentions(|f| f.visit_type(&node.ty)) {
+            let owner = self.owner.as_deref().unwrap_or_default();
+            let ty = self.text(node.ty.span());
+            let code = node.ident.as_ref().map_or_else(
+                || format!("{}({})", owner, ty),
+                |ident| format!("{}::{}: {}", owner, ident, ty),
+            );
+            self.push("field", node.span(), code);
+        }
+    }
+
+    fn visit_signature(&mut self, node: &'ast syn::Signature) {
+        if self.mentions(|f| f.visit_signature(node)) {
+            let code = self.text(node.span());
+            self.push("signature", node.span(), code);
+        }
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        let header_mentions = self.mentions(|f| {
+            f.visit_type(&node.self_ty);
+            if let Some((_, path, _)) = &node.trait_ {
+                f.visit_path(path);
+            }
+        });
+        if header_mentions {
+            let header = match &node.trait_ {
+                Some((_, path, _)) => format!(
+                    "impl {} for {}",
+                    self.text(path.span()),
+                    self.text(node.self_ty.span())
+                ),
+                None => format!("impl {}", self.text(node.self_ty.span())),
+            };
+            self.push("impl", node.impl_token.span, header);
+        }
+        // Methods are visited on their own; the header was counted above
+        for item in &node.items {
+            self.visit_impl_item(item);
+        }
+    }
+
+    fn visit_expr_struct(&mut self, node: &'ast syn::ExprStruct) {
+        if node
+            .path
+            .segments
+            .last()
+            .is_some_and(|seg| seg.ident == self.name)
+        {
+            let code = format!("{} {{ .. }}", self.text(node.path.span()));
+            self.push("struct-literal", node.span(), code);
+        }
+        for field in &node.fields {
+            self.visit_expr(&field.expr);
+        }
+        if let Some(rest) = &node.rest {
+            self.visit_expr(rest);
+        }
+    }
+
+    fn visit_expr_path(&mut self, node: &'ast syn::ExprPath) {
+        if node.path.segments.iter().any(|seg| seg.ident == self.name) {
+            let code = self.text(node.span());
+            self.push("path", node.span(), code);
+        } else {
+            syn::visit::visit_expr_path(self, node);
+        }
+    }
+
+    fn visit_item_use(&mut self, node: &'ast syn::ItemUse) {
+        if self.mentions(|f| f.visit_item_use(node)) {
+            let code = self.text(node.span());
+            self.push("use", node.span(), code);
+        }
+    }
+
+    fn visit_type_path(&mut self, node: &'ast syn::TypePath) {
+        if self.mentions(|f| f.visit_type_path(node)) {
+            let code = self.text(node.span());
+            self.push("type-ref", node.span(), code);
+        }
+    }
+}
+
+/// Looks for the type's name in types, paths and `use` trees.
+struct MentionFinder<'a> {
+    name: &'a str,
+    found: bool,
+}
+
+impl<'ast> Visit<'ast> for MentionFinder<'_> {
+    fn visit_path_segment(&mut self, node: &'ast syn::PathSegment) {
+        if node.ident == self.name {
+            self.found = true;
+        }
+        syn::visit::visit_path_segment(self, node);
+    }
+
+    fn visit_use_name(&mut self, node: &'ast syn::UseName) {
+        if node.ident == self.name {
+            self.found = true;
+        }
+    }
+
+    fn visit_use_rename(&mut self, node: &'ast syn::UseRename) {
+        if node.ident == self.name {
+            self.found = true;
+        }
+    }
+}
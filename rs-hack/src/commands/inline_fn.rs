@@ -0,0 +1,976 @@
+//! `inline-function` command: replace the one call of a free function with its body and
+//! delete the definition.
+//!
+//! Arguments are mapped to parameters textually. An argument is written in place of each use
+//! of its parameter when that can't change what runs (a path or literal, or a single,
+//! unconditional use in a one-expression body); otherwise it is bound with `let` at the top
+//! of an inlined block. Functions that don't inline cleanly (several callers, an early
+//! `return` or `?`, type parameters, recursion, use as a value) are refused with the reason.
+//! Only computes the new file contents; the CLI writes them with `execute::write_changes`.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+use syn::{Expr, FnArg, Item, ItemFn, Pat, Stmt, UseTree};
+
+use crate::commands::move_item::Edit;
+use crate::execute::FileChange;
+use crate::operations::{BackupNode, NodeLocation};
+use crate::surgical::Source;
+
+#[derive(Debug, Clone)]
+pub struct InlineArgs {
+    /// Name of the function to inline
+    pub name: String,
+    /// Files searched for the definition, its call site and imports of it
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+pub struct InlineResult {
+    /// Where the function was defined, e.g. "src/util.rs:12"
+    pub definition: String,
+    /// Where its one call was, e.g. "src/main.rs:40"
+    pub call_site: String,
+    /// New contents for every file that changes
+    pub changes: Vec<FileChange>,
+    /// Things the inlining couldn't check and that may need a hand
+    pub warnings: Vec<String>,
+}
+
+/// A scanned file.
+struct Parsed {
+    path: PathBuf,
+    text: String,
+    file: syn::File,
+}
+
+/// A plain `name: Type` parameter of the inlined function.
+struct Param {
+    name: String,
+    mutable: bool,
+    ty: syn::Type,
+}
+
+pub fn run(args: &InlineArgs) -> Result<InlineResult> {
+    let name = args.name.as_str();
+    let mut parsed = Vec::new();
+    for path in &args.files {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let file = syn::parse_file(&text)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        parsed.push(Parsed {
+            path: path.clone(),
+            text,
+            file,
+        });
+    }
+
+    let mut defs = Vec::new();
+    for (index, p) in parsed.iter().enumerate() {
+        let mut found = Vec::new();
+        collect_fns(&p.file.items, name, &mut found);
+        defs.extend(found.into_iter().map(|func| (index, func)));
+    }
+    let (def_index, func) = match defs.as_slice() {
+        [] => anyhow::bail!("No function `{}` found in the scanned files", name),
+        [def] => *def,
+        _ => anyhow::bail!(
+            "Found {} functions named `{}` ({}); inline-function needs a unique name",
+            defs.len(),
+            name,
+            defs.iter()
+                .map(|(i, f)| location(&parsed[*i].path, f.sig.ident.span().start().line))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let params = check_inlinable(func)?;
+    let def = &parsed[def_index];
+    let def_src = Source::new(&def.text);
+    let def_range = def_src.range(func);
+    let def_line = func.sig.ident.span().start().line;
+
+    // Every mention of the function across the scanned files
+    let mut calls = Vec::new();
+    let mut values = Vec::new();
+    let mut macros = Vec::new();
+    let mut imports = Vec::new();
+    for (index, p) in parsed.iter().enumerate() {
+        let src = Source::new(&p.text);
+        let mut scan = Scan::new(&src, [name.to_string()].into(), true);
+        scan.visit_file(&p.file);
+        let inside_def =
+            |start: usize| index == def_index && (def_range.0..def_range.1).contains(&start);
+        if let Some(hit) = scan
+            .hits
+            .iter()
+            .chain(scan.macro_refs.iter())
+            .find(|hit| inside_def(hit.start))
+        {
+            anyhow::bail!(
+                "`{}` calls itself ({}); a recursive function can't be inlined",
+                name,
+                location(&p.path, hit.line)
+            );
+        }
+        for hit in scan.hits {
+            match hit.kind {
+                HitKind::Call(_) => calls.push((index, hit)),
+                HitKind::Value | HitKind::Shorthand => {
+                    values.push(location(&p.path, hit.line));
+                }
+            }
+        }
+        macros.extend(
+            scan.macro_refs
+                .iter()
+                .map(|hit| location(&p.path, hit.line)),
+        );
+        imports.extend(scan.imports.into_iter().map(|import| (index, import)));
+    }
+    if !values.is_empty() {
+        anyhow::bail!(
+            "`{}` is used as a value, not just called ({}); only direct calls can be inlined",
+            name,
+            values.join(", ")
+        );
+    }
+    if !macros.is_empty() {
+        anyhow::bail!(
+            "`{}` appears inside a macro whose arguments aren't expressions ({}); inline it by hand",
+            name,
+            macros.join(", ")
+        );
+    }
+    if let Some((index, import)) = imports.iter().find(|(_, import)| import.renamed) {
+        anyhow::bail!(
+            "`{}` is imported under another name ({}); calls through the alias aren't followed",
+            name,
+            location(&parsed[*index].path, import.line)
+        );
+    }
+    let (call_index, call) = match calls.as_slice() {
+        [] => anyhow::bail!(
+            "`{}` has no call sites in the scanned files; there is nothing to inline it into",
+            name
+        ),
+        [call] => call,
+        _ => anyhow::bail!(
+            "`{}` has {} call sites ({}); inline-function only inlines a function with exactly one caller",
+            name,
+            calls.len(),
+            calls
+                .iter()
+                .map(|(i, hit)| location(&parsed[*i].path, hit.line))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let call_file = &parsed[*call_index];
+    let HitKind::Call(call_args) = &call.kind else {
+        unreachable!("only calls are collected as call sites");
+    };
+    if call_args.len() != params.len() {
+        anyhow::bail!(
+            "The call at {} passes {} argument(s) but `{}` takes {}",
+            location(&call_file.path, call.line),
+            call_args.len(),
+            name,
+            params.len()
+        );
+    }
+
+    let call_src = Source::new(&call_file.text);
+    let replacement = inline_body(func, &params, &def_src, call_args, &call_src, call)?;
+
+    let mut result = InlineResult {
+        definition: location(&def.path, def_line),
+        call_site: location(&call_file.path, call.line),
+        ..InlineResult::default()
+    };
+    if !matches!(func.vis, syn::Visibility::Inherited) {
+        result.warnings.push(format!(
+            "`{}` was public; callers outside the scanned files will no longer compile",
+            name
+        ));
+    }
+    if *call_index != def_index {
+        result.warnings.push(format!(
+            "The body of `{}` moved from {} to {}; check that the items it uses are in scope there",
+            name,
+            def.path.display(),
+            call_file.path.display()
+        ));
+    }
+
+    let mut edits: Vec<Vec<Edit>> = parsed.iter().map(|_| Vec::new()).collect();
+    let (start, end) = removal_lines(&def_src, def_range);
+    edits[def_index].push(Edit {
+        start,
+        end,
+        text: String::new(),
+    });
+    edits[*call_index].push(Edit {
+        start: call.start,
+        end: call.end,
+        text: replacement,
+    });
+    for (index, import) in &imports {
+        if import.sole {
+            let src = Source::new(&parsed[*index].text);
+            let (start, end) = removal_lines(&src, (import.start, import.end));
+            edits[*index].push(Edit {
+                start,
+                end,
+                text: String::new(),
+            });
+        } else {
+            result.warnings.push(format!(
+                "Remove `{}` from the `use` at {}",
+                name,
+                location(&parsed[*index].path, import.line)
+            ));
+        }
+    }
+
+    let node = BackupNode {
+        node_type: "inlined-fn".to_string(),
+        identifier: name.to_string(),
+        original_content: String::new(),
+        location: NodeLocation {
+            line: func.span().start().line,
+            column: func.span().start().column,
+            end_line: func.span().end().line,
+            end_column: func.span().end().column,
+        },
+    };
+    for (p, edits) in parsed.iter().zip(edits) {
+        if edits.is_empty() {
+            continue;
+        }
+        let new_text = apply_edits(&p.text, edits);
+        syn::parse_file(&new_text).with_context(|| {
+            format!(
+                "Inlining would leave {} unparseable; nothing was changed",
+                p.path.display()
+            )
+        })?;
+        result.changes.push(FileChange {
+            path: p.path.clone(),
+            old_content: p.text.clone(),
+            new_content: new_text,
+            modified_nodes: vec![node.clone()],
+        });
+    }
+    Ok(result)
+}
+
+/// Free functions named `name` at module level, including inline `mod` blocks.
+fn collect_fns<'a>(items: &'a [Item], name: &str, found: &mut Vec<&'a ItemFn>) {
+    for item in items {
+        match item {
+            Item::Fn(func) if func.sig.ident == name => found.push(func),
+            Item::Mod(module) => {
+                if let Some((_, items)) = &module.content {
+                    collect_fns(items, name, found);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The function's parameters, or why its body can't simply take the place of its call.
+fn check_inlinable(func: &ItemFn) -> Result<Vec<Param>> {
+    let name = &func.sig.ident;
+    if func.sig.asyncness.is_some() {
+        anyhow::bail!(
+            "`{}` is async; its body can't replace the call and its `.await`",
+            name
+        );
+    }
+    if func.sig.unsafety.is_some() {
+        anyhow::bail!(
+            "`{}` is an `unsafe fn`; its body would lose the unsafe context",
+            name
+        );
+    }
+    if func.sig.variadic.is_some() {
+        anyhow::bail!("`{}` is variadic", name);
+    }
+    if func
+        .sig
+        .generics
+        .params
+        .iter()
+        .any(|p| !matches!(p, syn::GenericParam::Lifetime(_)))
+    {
+        anyhow::bail!(
+            "`{}` has type or const parameters; textual substitution can't carry them",
+            name
+        );
+    }
+    if let Some(attr) = func
+        .attrs
+        .iter()
+        .find(|a| a.path().is_ident("cfg") || a.path().is_ident("test"))
+    {
+        anyhow::bail!(
+            "`{}` is marked #[{}]; inlining would drop the condition",
+            name,
+            attr.path()
+                .get_ident()
+                .map(ToString::to_string)
+                .unwrap_or_default()
+        );
+    }
+
+    let mut params = Vec::new();
+    for input in &func.sig.inputs {
+        let FnArg::Typed(typed) = input else {
+            anyhow::bail!("`{}` takes `self`", name);
+        };
+        match &*typed.pat {
+            Pat::Ident(ident) if ident.by_ref.is_none() && ident.subpat.is_none() => {
+                params.push(Param {
+                    name: ident.ident.to_string(),
+                    mutable: ident.mutability.is_some(),
+                    ty: (*typed.ty).clone(),
+                });
+            }
+            pat => anyhow::bail!(
+                "`{}` destructures a parameter (line {}); only plain `name: Type` parameters map to arguments",
+                name,
+                pat.span().start().line
+            ),
+        }
+    }
+
+    let mut exits = EarlyExits::default();
+    exits.visit_block(&func.block);
+    if let Some((what, line)) = exits.found {
+        anyhow::bail!(
+            "`{}` has {} (line {}); only bodies without early exits can be inlined",
+            name,
+            what,
+            line
+        );
+    }
+    Ok(params)
+}
+
+/// The text that replaces the call.
+fn inline_body(
+    func: &ItemFn,
+    params: &[Param],
+    def_src: &Source,
+    call_args: &[(usize, usize)],
+    call_src: &Source,
+    call: &Hit,
+) -> Result<String> {
+    let block = &func.block;
+    let single_expr = match block.stmts.as_slice() {
+        [Stmt::Expr(expr, None)] => Some(expr),
+        _ => None,
+    };
+
+    let mut scan = Scan::new(
+        def_src,
+        params.iter().map(|p| p.name.clone()).collect(),
+        false,
+    );
+    scan.visit_block(block);
+    let args: Vec<(&str, Expr)> = call_args
+        .iter()
+        .map(|&(start, end)| {
+            let text = &call_src.text[start..end];
+            syn::parse_str::<Expr>(text).map(|expr| (text, expr))
+        })
+        .collect::<syn::Result<_>>()
+        .context("Failed to re-read the call's arguments")?;
+    let costly = args.iter().filter(|(_, expr)| !is_trivial(expr)).count();
+
+    let mut lets = Vec::new();
+    let mut edits = Vec::new();
+    for (param, (arg_text, arg)) in params.iter().zip(&args) {
+        let uses: Vec<&Hit> = scan.hits.iter().filter(|h| h.name == param.name).collect();
+        // An argument naming a local of the body would be captured by it
+        let captured =
+            arg.to_token_stream().into_iter().flat_map(flatten).any(
+                |tree| matches!(tree, TokenTree::Ident(i) if scan.bound.contains(&i.to_string())),
+            );
+        let substitute = !param.mutable
+            && !captured
+            && !scan.rebound.contains(&param.name)
+            && (is_trivial(arg)
+                || (single_expr.is_some()
+                    && costly == 1
+                    && matches!(uses.as_slice(), [hit] if !hit.deferred)));
+        if !substitute {
+            lets.push(let_binding(param, arg_text, def_src));
+            continue;
+        }
+        for hit in uses {
+            let text = if hit.bare_ok || is_atomic(arg) {
+                (*arg_text).to_string()
+            } else {
+                format!("({})", arg_text)
+            };
+            edits.push(Edit {
+                start: hit.start,
+                end: hit.end,
+                text: match hit.kind {
+                    HitKind::Shorthand => format!("{}: {}", param.name, text),
+                    _ => text,
+                },
+            });
+        }
+    }
+
+    let call_indent = line_indent(call_src.text, call.start);
+    if let (Some(expr), true) = (single_expr, lets.is_empty()) {
+        let (start, end) = def_src.range(expr);
+        let text = substitute_in(def_src.text, start, end, edits);
+        let from = line_indent(def_src.text, start).len();
+        let text = reindent(&text, from, call_indent, true);
+        return Ok(if call.bare_ok || is_atomic(expr) {
+            text
+        } else {
+            format!("({})", text)
+        });
+    }
+
+    let start = def_src.offset(block.brace_token.span.open().end());
+    let end = def_src.offset(block.brace_token.span.close().start());
+    let body = substitute_in(def_src.text, start, end, edits);
+    let body = body.trim_matches(|c| c == '\n' || c == '\r');
+    let body = body.trim_end();
+    let from = body
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let inner_indent = format!("{}    ", call_indent);
+    let mut out = String::from("{\n");
+    for binding in lets {
+        out.push_str(&format!("{}{}\n", inner_indent, binding));
+    }
+    if !body.trim().is_empty() {
+        out.push_str(&reindent(body, from, &inner_indent, false));
+        out.push('\n');
+    }
+    out.push_str(call_indent);
+    out.push('}');
+    Ok(out)
+}
+
+/// `let p: Ty = arg;`, leaving out types that can't be written in a `let`.
+fn let_binding(param: &Param, arg_text: &str, def_src: &Source) -> String {
+    let mutability = if param.mutable { "mut " } else { "" };
+    let mut unnameable = Unnameable::default();
+    unnameable.visit_type(&param.ty);
+    if unnameable.found {
+        return format!("let {}{} = {};", mutability, param.name, arg_text);
+    }
+    let (start, end) = def_src.range(&param.ty);
+    format!(
+        "let {}{}: {} = {};",
+        mutability,
+        param.name,
+        &def_src.text[start..end],
+        arg_text
+    )
+}
+
+/// `text[start..end]` with `edits` (offsets into `text`) applied.
+fn substitute_in(text: &str, start: usize, end: usize, mut edits: Vec<Edit>) -> String {
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.start));
+    let mut out = text[start..end].to_string();
+    for edit in edits {
+        out.replace_range(edit.start - start..edit.end - start, &edit.text);
+    }
+    out
+}
+
+/// Lines of `text` with up to `from` columns of leading whitespace swapped for `to`.
+fn reindent(text: &str, from: usize, to: &str, skip_first: bool) -> String {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            if (skip_first && i == 0) || line.trim().is_empty() {
+                return if line.trim().is_empty() {
+                    String::new()
+                } else {
+                    line.to_string()
+                };
+            }
+            let strip = line
+                .char_indices()
+                .take(from)
+                .take_while(|(_, c)| c.is_whitespace())
+                .count();
+            format!("{}{}", to, &line[strip..])
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Leading whitespace of the line containing `offset`.
+fn line_indent(text: &str, offset: usize) -> &str {
+    let start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line = &text[start..];
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Whole lines of an item, plus one of the blank lines around it when it sat between two
+/// (or the blank line above it at the end of the file).
+fn removal_lines(src: &Source, range: (usize, usize)) -> (usize, usize) {
+    let (start, end) = src.item_lines(range.0, range.1);
+    let before_blank = start == 0 || src.text[..start].ends_with("\n\n");
+    if before_blank && src.text[end..].starts_with('\n') {
+        (start, end + 1)
+    } else if end == src.text.len() && src.text[..start].ends_with("\n\n") {
+        (start - 1, end)
+    } else {
+        (start, end)
+    }
+}
+
+/// Apply non-overlapping `edits`, last first.
+fn apply_edits(text: &str, mut edits: Vec<Edit>) -> String {
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.start));
+    let mut out = text.to_string();
+    for edit in edits {
+        out.replace_range(edit.start..edit.end, &edit.text);
+    }
+    out
+}
+
+fn flatten(tree: TokenTree) -> Vec<TokenTree> {
+    match tree {
+        TokenTree::Group(group) => group.stream().into_iter().flat_map(flatten).collect(),
+        tree => vec![tree],
+    }
+}
+
+fn location(path: &Path, line: usize) -> String {
+    format!("{}:{}", path.display(), line)
+}
+
+/// Side-effect free and cheap, so writing it at every use is the same as evaluating it once.
+fn is_trivial(expr: &Expr) -> bool {
+    match expr {
+        Expr::Path(_) | Expr::Lit(_) => true,
+        Expr::Reference(r) => r.mutability.is_none() && is_trivial(&r.expr),
+        Expr::Field(f) => is_trivial(&f.base),
+        Expr::Paren(p) => is_trivial(&p.expr),
+        _ => false,
+    }
+}
+
+/// Binds tighter than any operator around it, so it never needs parentheses.
+const fn is_atomic(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Path(_)
+            | Expr::Lit(_)
+            | Expr::Call(_)
+            | Expr::MethodCall(_)
+            | Expr::Field(_)
+            | Expr::Index(_)
+            | Expr::Paren(_)
+            | Expr::Tuple(_)
+            | Expr::Array(_)
+            | Expr::Macro(_)
+            | Expr::Try(_)
+    )
+}
+
+/// `path` names the function: `name`, or `module::name` (lowercase segments, so not an
+/// associated function of a type).
+fn names_fn(path: &syn::Path, name: &str, qualified: bool) -> bool {
+    let segments: Vec<_> = path.segments.iter().collect();
+    let Some((last, prefix)) = segments.split_last() else {
+        return false;
+    };
+    if last.ident != name || !last.arguments.is_none() {
+        return false;
+    }
+    if !qualified {
+        return prefix.is_empty() && path.leading_colon.is_none();
+    }
+    prefix.iter().all(|segment| {
+        segment
+            .ident
+            .to_string()
+            .starts_with(|c: char| c.is_lowercase() || c == '_')
+    })
+}
+
+#[derive(Debug)]
+enum HitKind {
+    /// A call, with the byte ranges of its arguments
+    Call(Vec<(usize, usize)>),
+    /// Any other use of the name as an expression
+    Value,
+    /// `Point { name }`
+    Shorthand,
+}
+
+#[derive(Debug)]
+struct Hit {
+    name: String,
+    kind: HitKind,
+    start: usize,
+    end: usize,
+    line: usize,
+    /// Sits where any expression fits without parentheses (a `let` initializer, an
+    /// argument, a statement, ...)
+    bare_ok: bool,
+    /// Inside a closure, loop, branch or short-circuit operand, so it may run zero or many
+    /// times
+    deferred: bool,
+}
+
+/// A `use` that imports the function.
+struct Import {
+    start: usize,
+    end: usize,
+    line: usize,
+    /// The whole `use` item imports nothing else, so it can be removed
+    sole: bool,
+    /// `use ...::name as other;`
+    renamed: bool,
+}
+
+/// Finds uses of `names` as expressions: the inlined function across the files (`fn_mode`,
+/// where calls are told apart and module paths count) or its parameters in its body.
+struct Scan<'a, 's> {
+    src: &'a Source<'s>,
+    names: HashSet<String>,
+    fn_mode: bool,
+    hits: Vec<Hit>,
+    /// Mentions inside macros that don't parse as expressions
+    macro_refs: Vec<Hit>,
+    /// Names re-bound by a pattern (or read by a format string) in the scanned code
+    rebound: HashSet<String>,
+    /// Every name a pattern binds in the scanned code
+    bound: HashSet<String>,
+    imports: Vec<Import>,
+    bare_ok: bool,
+    deferred: usize,
+}
+
+impl<'a, 's> Scan<'a, 's> {
+    fn new(src: &'a Source<'s>, names: HashSet<String>, fn_mode: bool) -> Self {
+        Self {
+            src,
+            names,
+            fn_mode,
+            hits: Vec::new(),
+            macro_refs: Vec::new(),
+            rebound: HashSet::new(),
+            bound: HashSet::new(),
+            imports: Vec::new(),
+            bare_ok: false,
+            deferred: 0,
+        }
+    }
+
+    fn matching(&self, expr: &Expr) -> Option<String> {
+        match expr {
+            Expr::Path(p) if p.qself.is_none() => self
+                .names
+                .iter()
+                .find(|name| names_fn(&p.path, name, self.fn_mode))
+                .cloned(),
+            _ => None,
+        }
+    }
+
+    fn hit(&self, name: String, kind: HitKind, node: &impl Spanned, bare_ok: bool) -> Hit {
+        let (start, end) = self.src.range(node);
+        Hit {
+            name,
+            kind,
+            start,
+            end,
+            line: node.span().start().line,
+            bare_ok,
+            deferred: self.deferred > 0,
+        }
+    }
+
+    /// Visit an expression sitting where it needs no parentheses.
+    fn visit_slot(&mut self, expr: &Expr) {
+        self.bare_ok = true;
+        self.visit_expr(expr);
+    }
+
+    fn visit_deferred(&mut self, visit: impl FnOnce(&mut Self)) {
+        self.deferred += 1;
+        visit(self);
+        self.deferred -= 1;
+    }
+
+    /// Walk macro tokens that aren't expressions, noting any mention of the names (and
+    /// `{name}` in format strings).
+    fn scan_tokens(&mut self, tokens: TokenStream, parsed: bool) {
+        for tree in tokens {
+            match tree {
+                TokenTree::Group(group) => self.scan_tokens(group.stream(), parsed),
+                TokenTree::Ident(ident) if !parsed && self.names.contains(&ident.to_string()) => {
+                    let line = ident.span().start().line;
+                    self.macro_refs.push(Hit {
+                        name: ident.to_string(),
+                        kind: HitKind::Value,
+                        start: self.src.offset(ident.span().start()),
+                        end: self.src.offset(ident.span().end()),
+                        line,
+                        bare_ok: false,
+                        deferred: true,
+                    });
+                    self.rebound.insert(ident.to_string());
+                }
+                TokenTree::Literal(literal) => {
+                    if let syn::Lit::Str(s) = syn::Lit::new(literal) {
+                        let value = s.value();
+                        for name in &self.names {
+                            if value.contains(&format!("{{{}}}", name))
+                                || value.contains(&format!("{{{}:", name))
+                            {
+                                self.rebound.insert(name.clone());
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for Scan<'_, '_> {
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        let bare_ok = std::mem::replace(&mut self.bare_ok, false);
+        if let Expr::Call(call) = expr
+            && let Some(name) = self.fn_mode.then(|| self.matching(&call.func)).flatten()
+        {
+            let args = call.args.iter().map(|arg| self.src.range(arg)).collect();
+            let hit = self.hit(name, HitKind::Call(args), call, bare_ok);
+            self.hits.push(hit);
+            for arg in &call.args {
+                self.visit_slot(arg);
+            }
+            return;
+        }
+        if let Some(name) = self.matching(expr) {
+            let hit = self.hit(name, HitKind::Value, expr, bare_ok);
+            self.hits.push(hit);
+            return;
+        }
+        syn::visit::visit_expr(self, expr);
+    }
+
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        self.visit_pat(&local.pat);
+        if let Some(init) = &local.init {
+            self.visit_slot(&init.expr);
+            if let Some((_, diverge)) = &init.diverge {
+                self.visit_expr(diverge);
+            }
+        }
+    }
+
+    fn visit_stmt(&mut self, stmt: &'ast Stmt) {
+        match stmt {
+            Stmt::Expr(expr, _) => self.visit_slot(expr),
+            _ => syn::visit::visit_stmt(self, stmt),
+        }
+    }
+
+    fn visit_expr_call(&mut self, call: &'ast syn::ExprCall) {
+        self.visit_expr(&call.func);
+        for arg in &call.args {
+            self.visit_slot(arg);
+        }
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        self.visit_expr(&call.receiver);
+        for arg in &call.args {
+            self.visit_slot(arg);
+        }
+    }
+
+    fn visit_field_value(&mut self, field: &'ast syn::FieldValue) {
+        if field.colon_token.is_none()
+            && let Some(name) = self.matching(&field.expr)
+        {
+            let hit = self.hit(name, HitKind::Shorthand, field, true);
+            self.hits.push(hit);
+            return;
+        }
+        self.visit_slot(&field.expr);
+    }
+
+    fn visit_expr_array(&mut self, array: &'ast syn::ExprArray) {
+        for elem in &array.elems {
+            self.visit_slot(elem);
+        }
+    }
+
+    fn visit_expr_tuple(&mut self, tuple: &'ast syn::ExprTuple) {
+        for elem in &tuple.elems {
+            self.visit_slot(elem);
+        }
+    }
+
+    fn visit_arm(&mut self, arm: &'ast syn::Arm) {
+        self.visit_deferred(|this| {
+            this.visit_pat(&arm.pat);
+            if let Some((_, guard)) = &arm.guard {
+                this.visit_expr(guard);
+            }
+            this.visit_slot(&arm.body);
+        });
+    }
+
+    fn visit_expr_closure(&mut self, closure: &'ast syn::ExprClosure) {
+        self.visit_deferred(|this| syn::visit::visit_expr_closure(this, closure));
+    }
+
+    fn visit_expr_loop(&mut self, node: &'ast syn::ExprLoop) {
+        self.visit_deferred(|this| syn::visit::visit_expr_loop(this, node));
+    }
+
+    fn visit_expr_while(&mut self, node: &'ast syn::ExprWhile) {
+        self.visit_deferred(|this| syn::visit::visit_expr_while(this, node));
+    }
+
+    fn visit_expr_for_loop(&mut self, node: &'ast syn::ExprForLoop) {
+        self.visit_deferred(|this| syn::visit::visit_expr_for_loop(this, node));
+    }
+
+    fn visit_expr_if(&mut self, node: &'ast syn::ExprIf) {
+        self.visit_deferred(|this| syn::visit::visit_expr_if(this, node));
+    }
+
+    fn visit_expr_binary(&mut self, node: &'ast syn::ExprBinary) {
+        if matches!(node.op, syn::BinOp::And(_) | syn::BinOp::Or(_)) {
+            self.visit_deferred(|this| syn::visit::visit_expr_binary(this, node));
+        } else {
+            syn::visit::visit_expr_binary(self, node);
+        }
+    }
+
+    fn visit_pat_ident(&mut self, pat: &'ast syn::PatIdent) {
+        self.bound.insert(pat.ident.to_string());
+        if self.names.contains(&pat.ident.to_string()) {
+            self.rebound.insert(pat.ident.to_string());
+        }
+        syn::visit::visit_pat_ident(self, pat);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        match mac.parse_body_with(Punctuated::<Expr, syn::Token![,]>::parse_terminated) {
+            Ok(args) => {
+                for arg in &args {
+                    self.visit_slot(arg);
+                }
+                self.scan_tokens(mac.tokens.clone(), true);
+            }
+            Err(_) => self.scan_tokens(mac.tokens.clone(), false),
+        }
+    }
+
+    fn visit_item_use(&mut self, item: &'ast syn::ItemUse) {
+        if !self.fn_mode {
+            return;
+        }
+        let mut leaves = Vec::new();
+        use_leaves(&item.tree, &mut leaves);
+        let mentions: Vec<_> = leaves
+            .iter()
+            .filter(|(ident, _)| self.names.contains(&ident.to_string()))
+            .collect();
+        if mentions.is_empty() {
+            return;
+        }
+        let (start, end) = self.src.range(item);
+        self.imports.push(Import {
+            start,
+            end,
+            line: item.span().start().line,
+            sole: leaves.len() == 1 && mentions[0].1.is_none(),
+            renamed: mentions.iter().any(|(_, rename)| rename.is_some()),
+        });
+    }
+}
+
+/// The names a `use` tree brings in, with what they are renamed to.
+fn use_leaves<'a>(tree: &'a UseTree, out: &mut Vec<(&'a syn::Ident, Option<&'a syn::Ident>)>) {
+    match tree {
+        UseTree::Path(path) => use_leaves(&path.tree, out),
+        UseTree::Name(name) => out.push((&name.ident, None)),
+        UseTree::Rename(rename) => out.push((&rename.ident, Some(&rename.rename))),
+        UseTree::Glob(_) => {}
+        UseTree::Group(group) => {
+            for tree in &group.items {
+                use_leaves(tree, out);
+            }
+        }
+    }
+}
+
+/// The first `return` or `?` that would exit the function (not a closure or nested item
+/// inside it).
+#[derive(Default)]
+struct EarlyExits {
+    found: Option<(&'static str, usize)>,
+}
+
+impl<'ast> Visit<'ast> for EarlyExits {
+    fn visit_expr_return(&mut self, node: &'ast syn::ExprReturn) {
+        self.found
+            .get_or_insert_with(|| ("an early `return`", node.span().start().line));
+    }
+
+    fn visit_expr_try(&mut self, node: &'ast syn::ExprTry) {
+        self.found
+            .get_or_insert_with(|| ("a `?`", node.question_token.span.start().line));
+    }
+
+    fn visit_expr_closure(&mut self, _: &'ast syn::ExprClosure) {}
+
+    fn visit_expr_async(&mut self, _: &'ast syn::ExprAsync) {}
+
+    fn visit_item(&mut self, _: &'ast Item) {}
+}
+
+/// A type that can't be spelled in a `let`: `impl Trait` or a named lifetime.
+#[derive(Default)]
+struct Unnameable {
+    found: bool,
+}
+
+impl<'ast> Visit<'ast> for Unnameable {
+    fn visit_type_impl_trait(&mut self, _: &'ast syn::TypeImplTrait) {
+        self.found = true;
+    }
+
+    fn visit_lifetime(&mut self, lifetime: &'ast syn::Lifetime) {
+        if lifetime.ident != "static" && lifetime.ident != "_" {
+            self.found = true;
+        }
+    }
+}
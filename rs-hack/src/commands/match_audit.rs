@@ -5,7 +5,7 @@ use std::path::PathBuf;
 use anyhow::{Result, bail};
 use syn::visit::Visit;
 
-use crate::files::collect_rust_files_with_exclusions;
+use crate::files::{CollectOptions, collect_rust_files_with_options};
 
 #[derive(Debug)]
 pub struct MatchReport {
@@ -20,11 +20,18 @@ pub struct MatchSite {
     pub file_path: String,
     pub line: usize,
     pub missing_variants: Vec<String>,
+    /// Variants referenced by an arm that no longer exist on the enum
+    pub stale_variants: Vec<String>,
     pub has_wildcard: bool,
 }
 
-pub fn run(paths: &[PathBuf], enum_name: &str, exclude: &[String]) -> Result<MatchReport> {
-    let files = collect_rust_files_with_exclusions(paths, exclude)?;
+pub fn run(
+    paths: &[PathBuf],
+    enum_name: &str,
+    exclude: &[String],
+    options: &CollectOptions,
+) -> Result<MatchReport> {
+    let files = collect_rust_files_with_options(paths, exclude, options)?;
 
     // Pass 1: find the enum definition and collect its variants
     let mut all_variants: Vec<String> = Vec::new();
@@ -199,6 +206,12 @@ impl<'ast, 'a> Visit<'ast> for MatchAuditVisitor<'a> {
                     .collect()
             };
 
+            let stale: Vec<String> = variants_seen
+                .iter()
+                .filter(|v| !self.all_variants.contains(v))
+                .cloned()
+                .collect();
+
             // Approximate line number via proc_macro2 span
             let line = node.match_token.span.start().line;
 
@@ -207,6 +220,7 @@ impl<'ast, 'a> Visit<'ast> for MatchAuditVisitor<'a> {
                 file_path: self.file_path.clone(),
                 line,
                 missing_variants: missing,
+                stale_variants: stale,
                 has_wildcard,
             });
         }
@@ -216,7 +230,7 @@ impl<'ast, 'a> Visit<'ast> for MatchAuditVisitor<'a> {
 }
 
 /// Return true if this pattern is a wildcard (`_`) or an ident that acts as a catch-all.
-fn arm_has_wildcard(pat: &syn::Pat) -> bool {
+pub(crate) fn arm_has_wildcard(pat: &syn::Pat) -> bool {
     match pat {
         syn::Pat::Wild(_) => true,
         syn::Pat::Ident(pi) if pi.ident == "_" => true,
@@ -227,7 +241,11 @@ fn arm_has_wildcard(pat: &syn::Pat) -> bool {
 
 /// Walk a pattern and push enum variant names into `out` when the second-to-last
 /// path segment equals `enum_name`.
-fn collect_enum_variants_from_pat(pat: &syn::Pat, enum_name: &str, out: &mut Vec<String>) {
+pub(crate) fn collect_enum_variants_from_pat(
+    pat: &syn::Pat,
+    enum_name: &str,
+    out: &mut Vec<String>,
+) {
     match pat {
         syn::Pat::Path(pp) => {
             check_path(&pp.path, enum_name, out);
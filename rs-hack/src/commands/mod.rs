@@ -4,8 +4,22 @@
 //! yah, tests) can drive the same logic without shelling out. The CLI in `main.rs` is a thin
 //! clap → struct → `run()` translator that adds rendering on top.
 
+pub mod complexity;
+pub mod debug;
+pub mod describe;
 pub mod doc_coverage;
 pub mod find;
+pub mod impact;
+pub mod inline_fn;
 pub mod match_audit;
+pub mod move_item;
 pub mod neighbors;
+pub mod node_types;
+pub mod rename_mod;
+pub mod rename_text;
+pub mod risk;
+pub mod stats;
+pub mod stdin_batch;
 pub mod summary;
+pub mod sync_match;
+pub mod unused_imports;
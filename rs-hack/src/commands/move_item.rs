@@ -0,0 +1,916 @@
+//! `move` command: relocate an item from one module file to another.
+//!
+//! The item travels with the impl blocks of a moved type and with the `use` items it relies
+//! on; `use` declarations and `crate::`/`self::`/`super::` paths that named it at the old
+//! module are pointed at the new one across the scanned files. Only computes the new file
+//! contents; the CLI writes them with `execute::write_changes`.
+
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+use syn::{Item, UseTree};
+
+use crate::execute::FileChange;
+use crate::files::module_path;
+use crate::operations::{BackupNode, NodeLocation};
+use crate::surgical::Source;
+
+#[derive(Debug, Clone)]
+pub struct MoveArgs {
+    /// Name of the item to move
+    pub name: String,
+    pub from: PathBuf,
+    pub to: PathBuf,
+    /// Files whose `use` declarations and paths are updated (`from` and `to` are always
+    /// included)
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+pub struct MoveResult {
+    pub from_module: String,
+    pub to_module: String,
+    /// What travelled, e.g. "struct Config", "impl Display for Config"
+    pub moved: Vec<String>,
+    /// New contents for every file that changes, `from` and `to` first
+    pub changes: Vec<FileChange>,
+    /// Things the move couldn't fix and that will likely need a hand
+    pub warnings: Vec<String>,
+}
+
+/// A text edit on one file: replace `start..end` with `text`.
+pub(crate) struct Edit {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) text: String,
+}
+
+/// Where new `use` lines go in `file`: after the last top-level `use`, or before the first
+/// item.
+fn use_insertion_point(src: &Source<'_>, file: &syn::File) -> usize {
+    let last_use = file.items.iter().rev().find(|i| matches!(i, Item::Use(_)));
+    match (last_use, file.items.first()) {
+        (Some(item), _) => src.item_lines(src.range(item).0, src.range(item).1).1,
+        (None, Some(first)) => src.item_lines(src.range(first).0, src.range(first).1).0,
+        (None, None) => src.text.len(),
+    }
+}
+
+pub fn run(args: &MoveArgs) -> Result<MoveResult> {
+    let name = args.name.as_str();
+    if same_file(&args.from, &args.to) {
+        anyhow::bail!("--from and --to are the same file");
+    }
+    if !args.to.is_file() {
+        anyhow::bail!(
+            "{} doesn't exist; create it and declare its module (`mod ...;`) first",
+            args.to.display()
+        );
+    }
+    let from_module = module_path(&args.from)
+        .with_context(|| format!("Can't tell the module path of {}", args.from.display()))?;
+    let to_module = module_path(&args.to)
+        .with_context(|| format!("Can't tell the module path of {}", args.to.display()))?;
+
+    let from_text = std::fs::read_to_string(&args.from)
+        .with_context(|| format!("Failed to read {}", args.from.display()))?;
+    let to_text = std::fs::read_to_string(&args.to)
+        .with_context(|| format!("Failed to read {}", args.to.display()))?;
+    let from_file = syn::parse_file(&from_text)
+        .with_context(|| format!("Failed to parse {}", args.from.display()))?;
+    let to_file = syn::parse_file(&to_text)
+        .with_context(|| format!("Failed to parse {}", args.to.display()))?;
+    let from_src = Source::new(&from_text);
+    let to_src = Source::new(&to_text);
+
+    let mut result = MoveResult {
+        from_module: from_module.join("::"),
+        to_module: to_module.join("::"),
+        ..MoveResult::default()
+    };
+
+    // The item itself, plus impl blocks when it's a type
+    let primary: Vec<&Item> = from_file
+        .items
+        .iter()
+        .filter(|item| item_name(item).as_deref() == Some(name))
+        .collect();
+    if primary.is_empty() {
+        anyhow::bail!(
+            "No top-level item named '{}' in {}",
+            name,
+            args.from.display()
+        );
+    }
+    if to_file
+        .items
+        .iter()
+        .any(|item| item_name(item).as_deref() == Some(name))
+    {
+        anyhow::bail!("{} already defines '{}'", args.to.display(), name);
+    }
+    let is_type = primary.iter().any(|item| {
+        matches!(
+            item,
+            Item::Struct(_) | Item::Enum(_) | Item::Union(_) | Item::Type(_)
+        )
+    });
+    let moved: Vec<&Item> = from_file
+        .items
+        .iter()
+        .filter(|item| {
+            primary.iter().any(|p| std::ptr::eq(*p, *item))
+                || (is_type
+                    && matches!(item, Item::Impl(imp) if type_name(&imp.self_ty).as_deref() == Some(name)))
+        })
+        .collect();
+    let is_moved = |item: &Item| moved.iter().any(|m| std::ptr::eq(*m, item));
+    result.moved = moved.iter().map(|item| describe(item)).collect();
+
+    let moved_ranges: Vec<(usize, usize)> = moved
+        .iter()
+        .map(|item| {
+            let (start, end) = from_src.range(*item);
+            from_src.item_lines(start, end)
+        })
+        .collect();
+    let moved_text = moved_ranges
+        .iter()
+        .map(|&(start, end)| from_text[start..end].trim_matches('\n'))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    // What the moved code refers to, and what the code staying behind refers to
+    let mut moved_refs = References::default();
+    for item in &moved {
+        moved_refs.visit_item(item);
+    }
+    let mut staying_refs = References::default();
+    for item in from_file
+        .items
+        .iter()
+        .filter(|item| !is_moved(item) && !matches!(item, Item::Use(_)))
+    {
+        staying_refs.visit_item(item);
+    }
+    if moved_refs.relative_paths {
+        result.warnings.push(format!(
+            "The moved code uses `self::`/`super::` paths, which now resolve from {}; check them",
+            result.to_module
+        ));
+    }
+
+    // `use` lines the destination needs: imports the moved code relied on, and items it
+    // uses that stay in the old module
+    let to_names = imported_or_defined(&to_file);
+    let mut to_uses: BTreeSet<String> = BTreeSet::new();
+    for item in &from_file.items {
+        let Item::Use(item_use) = item else { continue };
+        for leaf in use_leaves(&item_use.tree, &from_module, &mut Vec::new()) {
+            if moved_refs.names.contains(&leaf.binding) && !to_names.contains(&leaf.binding) {
+                to_uses.insert(leaf.use_line());
+            }
+        }
+    }
+    for item in &from_file.items {
+        if is_moved(item) || matches!(item, Item::Use(_) | Item::Impl(_)) {
+            continue;
+        }
+        let Some(item_ident) = item_name(item) else {
+            continue;
+        };
+        if moved_refs.names.contains(&item_ident) && !to_names.contains(&item_ident) {
+            to_uses.insert(format!("use {}::{};", result.from_module, item_ident));
+            if matches!(item_visibility(item), Some(syn::Visibility::Inherited)) {
+                result.warnings.push(format!(
+                    "`{}` stays in {} and is private there; the moved code can't reach it until it's pub(crate)",
+                    item_ident, result.from_module
+                ));
+            }
+        }
+    }
+
+    // Source file: cut the items, import the moved name back if the rest still uses it
+    let mut from_edits: Vec<Edit> = moved_ranges
+        .iter()
+        .map(|&(start, end)| Edit {
+            start,
+            end,
+            text: String::new(),
+        })
+        .collect();
+    if staying_refs.names.contains(name) {
+        let at = use_insertion_point(&from_src, &from_file);
+        from_edits.push(Edit {
+            start: at,
+            end: at,
+            text: format!("use {}::{};\n", result.to_module, name),
+        });
+    }
+    // Imports only the moved code used go with it
+    let carried: HashSet<String> = moved_refs
+        .names
+        .iter()
+        .filter(|binding| !staying_refs.names.contains(*binding))
+        .cloned()
+        .collect();
+    from_edits.extend(remove_imports(&from_file, &from_src, &carried));
+    // Paths inside the moved items go with them
+    from_edits.extend(
+        repoint_paths(
+            &from_file,
+            &from_src,
+            &from_module,
+            &from_module,
+            &to_module,
+            name,
+            false,
+        )
+        .into_iter()
+        .filter(|edit| {
+            !moved_ranges
+                .iter()
+                .any(|&(start, end)| start <= edit.start && edit.end <= end)
+        }),
+    );
+
+    // Destination file: new imports after its own, the items at the end
+    let mut to_edits: Vec<Edit> = Vec::new();
+    if !to_uses.is_empty() {
+        let at = use_insertion_point(&to_src, &to_file);
+        let lines: String = to_uses.iter().map(|line| format!("{}\n", line)).collect();
+        to_edits.push(Edit {
+            start: at,
+            end: at,
+            text: lines,
+        });
+    }
+    let separator = if to_text.trim().is_empty() {
+        ""
+    } else if to_text.ends_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    to_edits.push(Edit {
+        start: to_text.len(),
+        end: to_text.len(),
+        text: format!("{}{}\n", separator, moved_text),
+    });
+    to_edits.extend(repoint_paths(
+        &to_file,
+        &to_src,
+        &to_module,
+        &from_module,
+        &to_module,
+        name,
+        true,
+    ));
+
+    let moved_nodes: Vec<BackupNode> = moved
+        .iter()
+        .map(|item| BackupNode {
+            node_type: "moved-item".to_string(),
+            identifier: describe(item),
+            original_content: String::new(),
+            location: NodeLocation {
+                line: item.span().start().line,
+                column: item.span().start().column,
+                end_line: item.span().end().line,
+                end_column: item.span().end().column,
+            },
+        })
+        .collect();
+    push_change(
+        &mut result,
+        &args.from,
+        &from_text,
+        from_edits,
+        moved_nodes.clone(),
+    )?;
+    push_change(&mut result, &args.to, &to_text, to_edits, moved_nodes)?;
+
+    // Everyone else: repoint `use` declarations and qualified paths
+    for file in &args.files {
+        if same_file(file, &args.from) || same_file(file, &args.to) {
+            continue;
+        }
+        let Ok(text) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        let Ok(parsed) = syn::parse_file(&text) else {
+            result
+                .warnings
+                .push(format!("Skipped {}: it doesn't parse", file.display()));
+            continue;
+        };
+        let Some(module) = module_path(file) else {
+            continue;
+        };
+        let src = Source::new(&text);
+        let edits = repoint_paths(
+            &parsed,
+            &src,
+            &module,
+            &from_module,
+            &to_module,
+            name,
+            false,
+        );
+        if !edits.is_empty() {
+            push_change(&mut result, file, &text, edits, Vec::new())?;
+        }
+    }
+
+    Ok(result)
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Apply `edits` (last first, skipping any that overlap an edit already applied) and record
+/// the change. The result must still parse.
+fn push_change(
+    result: &mut MoveResult,
+    path: &Path,
+    text: &str,
+    mut edits: Vec<Edit>,
+    modified_nodes: Vec<BackupNode>,
+) -> Result<()> {
+    edits.sort_by_key(|edit| std::cmp::Reverse((edit.start, edit.end)));
+    let mut new_text = text.to_string();
+    let mut applied_from = usize::MAX;
+    for edit in edits {
+        // Trimming trailing blank lines can leave the text shorter than `applied_from`
+        if edit.end > applied_from.min(new_text.len()) {
+            continue;
+        }
+        new_text.replace_range(edit.start..edit.end, &edit.text);
+        if edit.text.is_empty() {
+            close_gap(&mut new_text, edit.start);
+        }
+        applied_from = edit.start;
+    }
+    simplify_use_groups(&mut new_text, text);
+    if new_text == text {
+        return Ok(());
+    }
+    syn::parse_file(&new_text).with_context(|| {
+        format!(
+            "Moving would leave {} unparseable; nothing was changed",
+            path.display()
+        )
+    })?;
+    result.changes.push(FileChange {
+        path: path.to_path_buf(),
+        old_content: text.to_string(),
+        new_content: new_text,
+        modified_nodes,
+    });
+    Ok(())
+}
+
+/// After whole lines were cut at `at`, drop the blank lines that leaves stacked: the ones
+/// right after a blank line, and any at the start or end of the file.
+fn close_gap(text: &mut String, at: usize) {
+    if at > 0 && !text[..at].ends_with('\n') {
+        return;
+    }
+    let is_blank = |line: &str| line.trim().is_empty();
+    let mut end = at;
+    while let Some(len) = text[end..].find('\n').map(|i| i + 1)
+        && is_blank(&text[end..end + len])
+    {
+        end += len;
+    }
+    if end == text.len() || is_blank(&text[end..]) {
+        // The rest of the file is blank: end it right after the last line kept
+        let keep = text[..at].trim_end().len();
+        text.truncate(keep);
+        if keep > 0 {
+            text.push('\n');
+        }
+        return;
+    }
+    let prev_blank =
+        at == 0 || is_blank(&text[text[..at - 1].rfind('\n').map_or(0, |i| i + 1)..at]);
+    if prev_blank {
+        text.replace_range(at..end, "");
+    } else if end > at {
+        // Keep one blank line between what was on either side
+        let first_len = text[at..].find('\n').map_or(0, |i| i + 1);
+        text.replace_range(at + first_len..end, "");
+    }
+}
+
+/// Rewrite `use a::{b};` left behind by removing the other members of a group as
+/// `use a::b;`. Only `use` items the move edited are touched.
+fn simplify_use_groups(text: &mut String, original: &str) {
+    let Ok(file) = syn::parse_file(text) else {
+        return;
+    };
+    let Ok(original_file) = syn::parse_file(original) else {
+        return;
+    };
+    let original_src = Source::new(original);
+    let untouched: HashSet<&str> = original_file
+        .items
+        .iter()
+        .filter(|item| matches!(item, Item::Use(_)))
+        .map(|item| {
+            let (start, end) = original_src.range(item);
+            &original[start..end]
+        })
+        .collect();
+
+    fn singleton_groups(tree: &UseTree, src: &Source<'_>, edits: &mut Vec<Edit>) {
+        match tree {
+            UseTree::Path(p) => singleton_groups(&p.tree, src, edits),
+            UseTree::Group(g) if g.items.len() == 1 => {
+                let child = &g.items[0];
+                // `{self}` can't lose its braces
+                if matches!(child, UseTree::Name(n) if n.ident == "self") {
+                    return;
+                }
+                let (start, end) = src.range(g);
+                let (child_start, child_end) = src.range(child);
+                edits.push(Edit {
+                    start,
+                    end,
+                    text: src.text[child_start..child_end].to_string(),
+                });
+            }
+            UseTree::Group(g) => {
+                for child in &g.items {
+                    singleton_groups(child, src, edits);
+                }
+            }
+            UseTree::Name(_) | UseTree::Rename(_) | UseTree::Glob(_) => {}
+        }
+    }
+
+    let src = Source::new(text);
+    let mut edits = Vec::new();
+    for item in &file.items {
+        let Item::Use(item_use) = item else { continue };
+        let (start, end) = src.range(item_use);
+        if !untouched.contains(&text[start..end]) {
+            singleton_groups(&item_use.tree, &src, &mut edits);
+        }
+    }
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.start));
+    let mut simplified = text.clone();
+    for edit in edits {
+        simplified.replace_range(edit.start..edit.end, &edit.text);
+    }
+    *text = simplified;
+}
+
+/// The name an item is declared under (None for impls, uses, extern blocks, ...).
+fn item_name(item: &Item) -> Option<String> {
+    Some(match item {
+        Item::Struct(i) => i.ident.to_string(),
+        Item::Enum(i) => i.ident.to_string(),
+        Item::Union(i) => i.ident.to_string(),
+        Item::Fn(i) => i.sig.ident.to_string(),
+        Item::Trait(i) => i.ident.to_string(),
+        Item::TraitAlias(i) => i.ident.to_string(),
+        Item::Type(i) => i.ident.to_string(),
+        Item::Const(i) => i.ident.to_string(),
+        Item::Static(i) => i.ident.to_string(),
+        Item::Macro(i) => i.ident.as_ref()?.to_string(),
+        _ => return None,
+    })
+}
+
+const fn item_visibility(item: &Item) -> Option<&syn::Visibility> {
+    Some(match item {
+        Item::Struct(i) => &i.vis,
+        Item::Enum(i) => &i.vis,
+        Item::Union(i) => &i.vis,
+        Item::Fn(i) => &i.vis,
+        Item::Trait(i) => &i.vis,
+        Item::TraitAlias(i) => &i.vis,
+        Item::Type(i) => &i.vis,
+        Item::Const(i) => &i.vis,
+        Item::Static(i) => &i.vis,
+        _ => return None,
+    })
+}
+
+fn type_name(ty: &syn::Type) -> Option<String> {
+    match ty {
+        syn::Type::Path(tp) => tp.path.segments.last().map(|seg| seg.ident.to_string()),
+        _ => None,
+    }
+}
+
+/// "struct Config", "impl Display for Config", ...
+fn describe(item: &Item) -> String {
+    let kind = match item {
+        Item::Struct(_) => "struct",
+        Item::Enum(_) => "enum",
+        Item::Union(_) => "union",
+        Item::Fn(_) => "fn",
+        Item::Trait(_) | Item::TraitAlias(_) => "trait",
+        Item::Type(_) => "type",
+        Item::Const(_) => "const",
+        Item::Static(_) => "static",
+        Item::Macro(_) => "macro",
+        Item::Impl(imp) => {
+            let ty = type_name(&imp.self_ty).unwrap_or_default();
+            return match &imp.trait_ {
+                Some((_, path, _)) => {
+                    let trait_name = path
+                        .segments
+                        .last()
+                        .map(|seg| seg.ident.to_string())
+                        .unwrap_or_default();
+                    format!("impl {} for {}", trait_name, ty)
+                }
+                None => format!("impl {}", ty),
+            };
+        }
+        _ => "item",
+    };
+    format!("{} {}", kind, item_name(item).unwrap_or_default())
+}
+
+/// Names a piece of code refers to: first segments of paths (types, calls, macros) and the
+/// traits named in `#[derive(...)]`.
+#[derive(Default)]
+struct References {
+    names: HashSet<String>,
+    /// Whether any path starts with `self::` or `super::`
+    relative_paths: bool,
+}
+
+impl<'ast> Visit<'ast> for References {
+    fn visit_path(&mut self, path: &'ast syn::Path) {
+        if let Some(first) = path.segments.first() {
+            if first.ident == "self" || first.ident == "super" {
+                self.relative_paths |= path.segments.len() > 1;
+            }
+            self.names.insert(first.ident.to_string());
+        }
+        syn::visit::visit_path(self, path);
+    }
+
+    fn visit_attribute(&mut self, attr: &'ast syn::Attribute) {
+        if attr.path().is_ident("derive") {
+            let _ = attr.parse_nested_meta(|meta| {
+                self.visit_path(&meta.path);
+                Ok(())
+            });
+        }
+        syn::visit::visit_attribute(self, attr);
+    }
+}
+
+/// Names a file already has in scope at the top level, by import or definition.
+fn imported_or_defined(file: &syn::File) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for item in &file.items {
+        match item {
+            Item::Use(item_use) => {
+                for leaf in use_leaves(&item_use.tree, &[], &mut Vec::new()) {
+                    names.insert(leaf.binding);
+                }
+            }
+            other => names.extend(item_name(other)),
+        }
+    }
+    names
+}
+
+/// One imported name of a `use` tree, with its path made absolute where it started with
+/// `self`/`super`/`crate`.
+struct UseLeaf {
+    path: Vec<String>,
+    /// The name it's bound to in scope (the alias for `x as y`)
+    binding: String,
+    rename: Option<String>,
+}
+
+impl UseLeaf {
+    fn use_line(&self) -> String {
+        let path = self.path.join("::");
+        self.rename.as_ref().map_or_else(
+            || format!("use {};", path),
+            |alias| format!("use {} as {};", path, alias),
+        )
+    }
+}
+
+/// Leaves of a `use` tree, with paths that start with `crate`/`self`/`super` made absolute
+/// against `module` (globs are skipped: they bind no single name).
+fn use_leaves(tree: &UseTree, module: &[String], prefix: &mut Vec<String>) -> Vec<UseLeaf> {
+    let leaf = |prefix: &[String], binding: String, rename: Option<String>| {
+        let path = resolve(prefix, module, false).unwrap_or_else(|| prefix.to_vec());
+        vec![UseLeaf {
+            path,
+            binding,
+            rename,
+        }]
+    };
+    match tree {
+        UseTree::Path(p) => {
+            prefix.push(p.ident.to_string());
+            let leaves = use_leaves(&p.tree, module, prefix);
+            prefix.pop();
+            leaves
+        }
+        // `use a::b::{self}` binds `b`
+        UseTree::Name(n) if n.ident == "self" => {
+            let binding = prefix.last().cloned().unwrap_or_default();
+            leaf(prefix, binding, None)
+        }
+        UseTree::Name(n) => {
+            let mut path = prefix.clone();
+            path.push(n.ident.to_string());
+            leaf(&path, n.ident.to_string(), None)
+        }
+        UseTree::Rename(r) => {
+            let mut path = prefix.clone();
+            path.push(r.ident.to_string());
+            leaf(&path, r.rename.to_string(), Some(r.rename.to_string()))
+        }
+        UseTree::Glob(_) => Vec::new(),
+        UseTree::Group(g) => g
+            .items
+            .iter()
+            .flat_map(|item| use_leaves(item, module, prefix))
+            .collect(),
+    }
+}
+
+/// Resolve the leading `crate`/`self`/`super` segments of a path written in `module`.
+/// `relative_ok` also treats other first segments as children of `module` (edition-2018
+/// `use` paths); otherwise such paths give `None`.
+pub(crate) fn resolve(
+    segments: &[String],
+    module: &[String],
+    relative_ok: bool,
+) -> Option<Vec<String>> {
+    let first = segments.first()?;
+    let mut resolved: Vec<String> = match first.as_str() {
+        "crate" => vec!["crate".to_string()],
+        "self" => module.to_vec(),
+        "super" => module[..module.len().saturating_sub(1)].to_vec(),
+        _ if relative_ok => {
+            let mut path = module.to_vec();
+            path.push(first.clone());
+            path
+        }
+        _ => return None,
+    };
+    for segment in &segments[1..] {
+        if segment == "super" {
+            resolved.pop();
+        } else {
+            resolved.push(segment.clone());
+        }
+    }
+    Some(resolved)
+}
+
+/// One imported name of a `use` item, located in the source.
+struct UseSite {
+    /// Path as written, ending with the imported name
+    segments: Vec<String>,
+    /// The name it's bound to in scope (the alias for `x as y`)
+    binding: String,
+    /// `Name` or `Name as Alias`
+    leaf_text: String,
+    /// The child of the innermost group with more than one member that leads to this name;
+    /// None when the name is all the item imports
+    element: Option<(usize, usize)>,
+}
+
+/// Flatten a `use` item into its imported names (globs and `self` leaves are skipped).
+fn use_sites(item: &syn::ItemUse, src: &Source<'_>) -> Vec<UseSite> {
+    fn walk(
+        tree: &UseTree,
+        src: &Source<'_>,
+        element: Option<(usize, usize)>,
+        prefix: &mut Vec<String>,
+        sites: &mut Vec<UseSite>,
+    ) {
+        match tree {
+            UseTree::Path(p) => {
+                prefix.push(p.ident.to_string());
+                walk(&p.tree, src, element, prefix, sites);
+                prefix.pop();
+            }
+            UseTree::Group(g) => {
+                for child in &g.items {
+                    let element = if g.items.len() > 1 {
+                        Some(src.range(child))
+                    } else {
+                        element
+                    };
+                    walk(child, src, element, prefix, sites);
+                }
+            }
+            UseTree::Name(n) if n.ident != "self" => {
+                let mut segments = prefix.clone();
+                segments.push(n.ident.to_string());
+                sites.push(UseSite {
+                    segments,
+                    binding: n.ident.to_string(),
+                    leaf_text: n.ident.to_string(),
+                    element,
+                });
+            }
+            UseTree::Rename(r) => {
+                let mut segments = prefix.clone();
+                segments.push(r.ident.to_string());
+                sites.push(UseSite {
+                    segments,
+                    binding: r.rename.to_string(),
+                    leaf_text: format!("{} as {}", r.ident, r.rename),
+                    element,
+                });
+            }
+            UseTree::Name(_) | UseTree::Glob(_) => {}
+        }
+    }
+
+    let mut sites = Vec::new();
+    walk(&item.tree, src, None, &mut Vec::new(), &mut sites);
+    sites
+}
+
+/// The edit that drops `site` from `item`: the whole `use` line when it's the only name,
+/// otherwise its group member and one adjoining comma.
+fn remove_use_site(item: &syn::ItemUse, site: &UseSite, src: &Source<'_>) -> Edit {
+    let text = src.text;
+    let (start, end) = match site.element {
+        None => {
+            let (start, end) = src.range(item);
+            src.item_lines(start, end)
+        }
+        Some((start, end)) => {
+            let after = &text[end..];
+            let trimmed = after.trim_start();
+            if trimmed.starts_with(',') {
+                let comma = end + (after.len() - trimmed.len());
+                let rest = &text[comma + 1..];
+                (start, comma + 1 + (rest.len() - rest.trim_start().len()))
+            } else {
+                let before = text[..start].trim_end();
+                (before.strip_suffix(',').map_or(start, str::len), end)
+            }
+        }
+    };
+    Edit {
+        start,
+        end,
+        text: String::new(),
+    }
+}
+
+/// Edits that drop the top-level imports of `bindings` (they went with the moved code).
+fn remove_imports(file: &syn::File, src: &Source<'_>, bindings: &HashSet<String>) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    for item in &file.items {
+        let Item::Use(item_use) = item else { continue };
+        let sites = use_sites(item_use, src);
+        let dropped: Vec<&UseSite> = sites
+            .iter()
+            .filter(|site| bindings.contains(&site.binding))
+            .collect();
+        if dropped.is_empty() {
+            continue;
+        }
+        if dropped.len() == sites.len() {
+            let (start, end) = src.range(item_use);
+            let (start, end) = src.item_lines(start, end);
+            edits.push(Edit {
+                start,
+                end,
+                text: String::new(),
+            });
+        } else {
+            edits.extend(
+                dropped
+                    .iter()
+                    .map(|site| remove_use_site(item_use, site, src)),
+            );
+        }
+    }
+    edits
+}
+
+/// Edits that point `use` declarations and qualified paths naming `from::name` at
+/// `to::name`. In the destination file itself (`is_destination`) such imports are dropped
+/// instead, since the item is now local.
+fn repoint_paths(
+    file: &syn::File,
+    src: &Source<'_>,
+    module: &[String],
+    from: &[String],
+    to: &[String],
+    name: &str,
+    is_destination: bool,
+) -> Vec<Edit> {
+    let mut target = from.to_vec();
+    target.push(name.to_string());
+
+    struct Repointer<'a, 'b> {
+        src: &'a Source<'b>,
+        module: &'a [String],
+        target: &'a [String],
+        to: String,
+        name: &'a str,
+        is_destination: bool,
+        edits: Vec<Edit>,
+    }
+
+    impl<'ast> Visit<'ast> for Repointer<'_, '_> {
+        fn visit_item_use(&mut self, item: &'ast syn::ItemUse) {
+            let text = self.src.text;
+            for site in use_sites(item, self.src) {
+                if site.segments.last().map(String::as_str) != Some(self.name)
+                    || resolve(&site.segments, self.module, true).as_deref() != Some(self.target)
+                {
+                    continue;
+                }
+                let new_path = format!("{}::{}", self.to, site.leaf_text);
+                if site.element.is_none() && !self.is_destination {
+                    let (start, end) = self.src.range(&item.tree);
+                    self.edits.push(Edit {
+                        start,
+                        end,
+                        text: new_path,
+                    });
+                    continue;
+                }
+                self.edits.push(remove_use_site(item, &site, self.src));
+                if !self.is_destination {
+                    // A separate `use` line carries the name to the new module
+                    let (item_start, item_end) = self.src.range(item);
+                    let line_start = text[..item_start].rfind('\n').map_or(0, |i| i + 1);
+                    let indent: String = text[line_start..item_start]
+                        .chars()
+                        .take_while(|c| c.is_whitespace())
+                        .collect();
+                    let vis = match &item.vis {
+                        syn::Visibility::Inherited => String::new(),
+                        vis => {
+                            let (start, end) = self.src.range(vis);
+                            format!("{} ", &text[start..end])
+                        }
+                    };
+                    self.edits.push(Edit {
+                        start: item_end,
+                        end: item_end,
+                        text: format!("\n{}{}use {};", indent, vis, new_path),
+                    });
+                }
+            }
+        }
+
+        fn visit_path(&mut self, path: &'ast syn::Path) {
+            let segments: Vec<String> = path
+                .segments
+                .iter()
+                .map(|seg| seg.ident.to_string())
+                .collect();
+            // `crate::old::Config::new` → `crate::new::Config::new`
+            let at = segments.iter().position(|seg| seg == self.name);
+            if let Some(at) = at
+                && at > 0
+                && resolve(&segments[..=at], self.module, false).as_deref() == Some(self.target)
+            {
+                let start = self.src.range(&path.segments[0]).0;
+                let end = self.src.range(&path.segments[at].ident).0;
+                self.edits.push(Edit {
+                    start,
+                    end,
+                    text: format!("{}::", self.to),
+                });
+            }
+            syn::visit::visit_path(self, path);
+        }
+    }
+
+    let mut repointer = Repointer {
+        src,
+        module,
+        target: &target,
+        to: to.join("::"),
+        name,
+        is_destination,
+        edits: Vec::new(),
+    };
+    repointer.visit_file(file);
+    repointer.edits
+}
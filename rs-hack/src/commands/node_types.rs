@@ -0,0 +1,353 @@
+//! `node-types` command: list the node-type and kind vocabulary accepted by the
+//! inspect-backed commands, straight from the tables the dispatch code uses.
+//!
+//! `NODE_TYPES` is the one list of node types: `--kind` expansion and the search
+//! `inspect` runs without a node type are both read from it.
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeTypeInfo {
+    pub name: &'static str,
+    /// "expression" or "definition"
+    pub level: &'static str,
+    pub matches: &'static str,
+    pub commands: &'static [&'static str],
+    /// The `--kind` this node type belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<&'static str>,
+    /// Whether `inspect` searches it when no node type is given. Off for the node types
+    /// that report matches another one already covers, like `derive` or `attribute`.
+    #[serde(skip)]
+    pub searched_by_default: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KindInfo {
+    pub name: &'static str,
+    pub node_types: Vec<&'static str>,
+    pub commands: &'static [&'static str],
+}
+
+#[derive(Debug, Serialize)]
+pub struct NodeTypesReport {
+    pub node_types: Vec<NodeTypeInfo>,
+    pub kinds: Vec<KindInfo>,
+}
+
+/// Commands that take `--node-type` and resolve it through `RustEditor::inspect`.
+const NODE_TYPE_COMMANDS: &[&str] = &["find", "transform"];
+
+/// Node types whose matches are single tokens reported with their whole line, for
+/// discovery rather than rewriting.
+const FIND_ONLY: &[&str] = &["find"];
+
+/// Commands that take `--kind` and expand it with `expand_kind_to_node_types`.
+const KIND_COMMANDS: &[&str] = &["find", "rename", "add", "remove", "update"];
+
+/// Every node type handled by `RustEditor::inspect`, in display order.
+pub const NODE_TYPES: &[NodeTypeInfo] = &[
+    NodeTypeInfo {
+        name: "struct-literal",
+        level: "expression",
+        matches: "Struct initialization (e.g., Config { field: value })",
+        commands: NODE_TYPE_COMMANDS,
+        kind: Some("struct"),
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "match-arm",
+        level: "expression",
+        matches: "Match arm pattern and body",
+        commands: NODE_TYPE_COMMANDS,
+        kind: Some("match"),
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "enum-usage",
+        level: "expression",
+        matches: "Enum variant usage (e.g., Status::Active)",
+        commands: NODE_TYPE_COMMANDS,
+        kind: Some("enum"),
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "function-call",
+        level: "expression",
+        matches: "Function call (e.g., process_data())",
+        commands: NODE_TYPE_COMMANDS,
+        kind: Some("function"),
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "method-call",
+        level: "expression",
+        matches: "Method call (e.g., value.unwrap())",
+        commands: NODE_TYPE_COMMANDS,
+        kind: Some("function"),
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "macro-call",
+        level: "expression",
+        matches: "Macro invocation (e.g., println!(), vec![])",
+        commands: NODE_TYPE_COMMANDS,
+        kind: Some("macro"),
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "closure",
+        level: "expression",
+        matches: "Closure (e.g., |x| x + offset; --name filters by the first captured variable)",
+        commands: NODE_TYPE_COMMANDS,
+        kind: None,
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "identifier",
+        level: "expression",
+        matches: "Variable or type identifier",
+        commands: NODE_TYPE_COMMANDS,
+        kind: Some("identifier"),
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "type-ref",
+        level: "expression",
+        matches: "Type reference in annotations",
+        commands: NODE_TYPE_COMMANDS,
+        kind: Some("type"),
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "lifetime",
+        level: "expression",
+        matches: "Lifetime, where declared and where used (--name 'a or a)",
+        commands: FIND_ONLY,
+        kind: None,
+        searched_by_default: false,
+    },
+    NodeTypeInfo {
+        name: "generic-param",
+        level: "expression",
+        matches: "Type or const generic parameter, where declared and where used in its item",
+        commands: FIND_ONLY,
+        kind: None,
+        searched_by_default: false,
+    },
+    NodeTypeInfo {
+        name: "struct",
+        level: "definition",
+        matches: "Struct definition",
+        commands: NODE_TYPE_COMMANDS,
+        kind: Some("struct"),
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "union",
+        level: "definition",
+        matches: "Union definition",
+        commands: NODE_TYPE_COMMANDS,
+        kind: None,
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "enum",
+        level: "definition",
+        matches: "Enum definition",
+        commands: NODE_TYPE_COMMANDS,
+        kind: Some("enum"),
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "function",
+        level: "definition",
+        matches: "Free function definition",
+        commands: NODE_TYPE_COMMANDS,
+        kind: Some("function"),
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "impl-method",
+        level: "definition",
+        matches: "Method in an impl block",
+        commands: NODE_TYPE_COMMANDS,
+        kind: Some("function"),
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "extern-fn",
+        level: "definition",
+        matches: "Function declared in an extern block (FFI signature)",
+        commands: NODE_TYPE_COMMANDS,
+        kind: Some("function"),
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "trait-method",
+        level: "definition",
+        matches: "Method declared in a trait",
+        commands: NODE_TYPE_COMMANDS,
+        kind: Some("function"),
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "trait",
+        level: "definition",
+        matches: "Trait definition",
+        commands: NODE_TYPE_COMMANDS,
+        kind: Some("trait"),
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "trait-impl",
+        level: "definition",
+        matches: "Trait implementation (impl Trait for Type)",
+        commands: NODE_TYPE_COMMANDS,
+        kind: Some("trait"),
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "impl",
+        level: "definition",
+        matches: "Impl block for a type, with its method names (--name filters by the type)",
+        commands: NODE_TYPE_COMMANDS,
+        kind: None,
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "derive",
+        level: "definition",
+        matches: "Struct/enum deriving a trait (--name filters by the derived trait)",
+        commands: NODE_TYPE_COMMANDS,
+        kind: None,
+        searched_by_default: false,
+    },
+    NodeTypeInfo {
+        name: "attribute",
+        level: "definition",
+        matches: "Attribute on an item, method, field or variant (--name filters by its path, e.g. deprecated)",
+        commands: FIND_ONLY,
+        kind: None,
+        searched_by_default: false,
+    },
+    NodeTypeInfo {
+        name: "where-clause",
+        level: "definition",
+        matches: "Fn, impl, trait or type with a trait bound on its generics, inline or in a where clause (--name filters by the bound, e.g. Send)",
+        commands: FIND_ONLY,
+        kind: None,
+        searched_by_default: false,
+    },
+    NodeTypeInfo {
+        name: "const",
+        level: "definition",
+        matches: "Const item",
+        commands: NODE_TYPE_COMMANDS,
+        kind: Some("const"),
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "static",
+        level: "definition",
+        matches: "Static item",
+        commands: NODE_TYPE_COMMANDS,
+        kind: Some("const"),
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "type-alias",
+        level: "definition",
+        matches: "Type alias",
+        commands: NODE_TYPE_COMMANDS,
+        kind: Some("type"),
+        searched_by_default: true,
+    },
+    NodeTypeInfo {
+        name: "mod",
+        level: "definition",
+        matches: "Module definition",
+        commands: NODE_TYPE_COMMANDS,
+        kind: Some("mod"),
+        searched_by_default: true,
+    },
+];
+
+/// Every kind understood by `expand_kind_to_node_types`, in display order.
+pub const KINDS: &[&str] = &[
+    "struct",
+    "function",
+    "enum",
+    "match",
+    "identifier",
+    "type",
+    "macro",
+    "const",
+    "trait",
+    "mod",
+];
+
+/// The node types a `--kind` covers; empty for an unknown kind.
+pub fn expand_kind_to_node_types(kind: &str) -> Vec<&'static str> {
+    NODE_TYPES
+        .iter()
+        .filter(|info| info.kind == Some(kind))
+        .map(|info| info.name)
+        .collect()
+}
+
+/// The node types `inspect` searches when it is given none.
+pub fn default_node_types() -> impl Iterator<Item = &'static str> {
+    NODE_TYPES
+        .iter()
+        .filter(|info| info.searched_by_default)
+        .map(|info| info.name)
+}
+
+pub fn run() -> NodeTypesReport {
+    let kinds = KINDS
+        .iter()
+        .map(|&name| KindInfo {
+            name,
+            node_types: expand_kind_to_node_types(name),
+            commands: KIND_COMMANDS,
+        })
+        .collect();
+
+    NodeTypesReport {
+        node_types: NODE_TYPES.to_vec(),
+        kinds,
+    }
+}
+
+pub fn render(report: &NodeTypesReport) {
+    for level in ["expression", "definition"] {
+        let types: Vec<&NodeTypeInfo> = report
+            .node_types
+            .iter()
+            .filter(|t| t.level == level)
+            .collect();
+        let heading = if level == "expression" {
+            "Expression-level"
+        } else {
+            "Definition-level"
+        };
+        println!("{} node types ({}):", heading, types.len());
+        for t in types {
+            println!("  {:<16} {}", t.name, t.matches);
+        }
+        println!();
+    }
+    println!(
+        "  Accepted by --node-type on: {}",
+        NODE_TYPE_COMMANDS.join(", ")
+    );
+    println!();
+
+    println!("Kinds ({}):", report.kinds.len());
+    for k in &report.kinds {
+        println!("  {:<16} {}", k.name, k.node_types.join(", "));
+    }
+    println!();
+    println!("  Accepted by --kind on: {}", KIND_COMMANDS.join(", "));
+}
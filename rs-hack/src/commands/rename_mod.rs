@@ -0,0 +1,483 @@
+//! `rename --kind mod`: rename a module and the paths that name it.
+//!
+//! The `mod` item is renamed where it's declared, inline (`mod x { }`) or file-based
+//! (`mod x;`), and `use` declarations and paths that resolve to it through `crate::`,
+//! `self::`, `super::` or its bare name are rewritten across the scanned files. A
+//! file-based module's `x.rs`, `x/mod.rs` or `x/` directory is reported for renaming too.
+//! Only computes the new contents; the CLI writes them with `execute::write_changes` and
+//! then renames the files.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use proc_macro2::TokenTree;
+use syn::UseTree;
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+use crate::commands::move_item::{Edit, resolve};
+use crate::execute::FileChange;
+use crate::files::module_path;
+use crate::operations::{BackupNode, NodeLocation};
+use crate::surgical::Source;
+
+#[derive(Debug, Clone)]
+pub struct RenameModArgs {
+    /// Module to rename: its name, optionally qualified (`net::old`, `crate::net::old`)
+    pub name: String,
+    pub to: String,
+    /// Files whose declarations, `use` items and paths are updated
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+pub struct RenameModResult {
+    /// The module's path before the rename, e.g. "crate::net::old"
+    pub module: String,
+    /// New contents for every file that changes, at their current paths
+    pub changes: Vec<FileChange>,
+    /// Files and directories to rename once `changes` are written: (from, to)
+    pub renames: Vec<(PathBuf, PathBuf)>,
+    /// Things the rename couldn't fix and that will likely need a hand
+    pub warnings: Vec<String>,
+}
+
+/// A `mod <old>` item found in the scanned files.
+struct Declaration {
+    file: PathBuf,
+    /// Path of the module it's declared in
+    parent: Vec<String>,
+    /// `mod x;` rather than `mod x { }`
+    file_based: bool,
+    /// Has a `#[path = "..."]` attribute
+    path_attr: bool,
+    line: usize,
+}
+
+struct Parsed {
+    path: PathBuf,
+    text: String,
+    syntax: syn::File,
+    module: Vec<String>,
+}
+
+pub fn run(args: &RenameModArgs) -> Result<RenameModResult> {
+    let segments: Vec<&str> = args.name.split("::").collect();
+    let (old, qualifier) = segments.split_last().context("--name is empty")?;
+    let old = *old;
+    if syn::parse_str::<syn::Ident>(&args.to).is_err() || args.to.contains("::") {
+        anyhow::bail!("'{}' isn't a valid module name", args.to);
+    }
+    if old == args.to {
+        anyhow::bail!("--name and --to are the same");
+    }
+
+    let mut result = RenameModResult::default();
+    let mut sources = Vec::new();
+    for file in &args.files {
+        let Ok(text) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        let Ok(syntax) = syn::parse_file(&text) else {
+            result
+                .warnings
+                .push(format!("Skipped {}: it doesn't parse", file.display()));
+            continue;
+        };
+        // Paths can only be resolved from files under a crate's src/
+        let Some(module) = module_path(file) else {
+            continue;
+        };
+        sources.push(Parsed {
+            path: file.clone(),
+            text,
+            syntax,
+            module,
+        });
+    }
+
+    // Which module is meant, and whether the new name is free next to it
+    let mut declarations = Vec::new();
+    let mut taken = Vec::new();
+    for source in &sources {
+        let mut finder = DeclarationFinder {
+            file: &source.path,
+            module: source.module.clone(),
+            old,
+            new: &args.to,
+            found: &mut declarations,
+            taken: &mut taken,
+        };
+        finder.visit_file(&source.syntax);
+    }
+    let qualifier: Vec<String> = qualifier.iter().map(|s| s.to_string()).collect();
+    declarations.retain(|decl| {
+        if qualifier.first().map(String::as_str) == Some("crate") {
+            decl.parent == qualifier
+        } else {
+            decl.parent.ends_with(&qualifier)
+        }
+    });
+    let Some(first) = declarations.first() else {
+        anyhow::bail!(
+            "No `mod {}` declared in the scanned files{}",
+            old,
+            if qualifier.is_empty() {
+                String::new()
+            } else {
+                format!(" under {}", qualifier.join("::"))
+            }
+        );
+    };
+    let parent = first.parent.clone();
+    let mut parents: Vec<String> = declarations
+        .iter()
+        .map(|decl| format!("{}::{}", decl.parent.join("::"), old))
+        .collect();
+    parents.sort();
+    parents.dedup();
+    if parents.len() > 1 {
+        anyhow::bail!(
+            "`mod {}` is declared in several modules ({}); qualify --name, e.g. --name {}",
+            old,
+            parents.join(", "),
+            parents[0].trim_start_matches("crate::")
+        );
+    }
+    if taken.contains(&parent) {
+        anyhow::bail!(
+            "{} already has a module named '{}'",
+            parent.join("::"),
+            args.to
+        );
+    }
+    let mut target = parent.clone();
+    target.push(old.to_string());
+    result.module = target.join("::");
+
+    // Module files to rename
+    for decl in declarations.iter().filter(|decl| decl.file_based) {
+        if decl.path_attr {
+            result.warnings.push(format!(
+                "{}:{}: `mod {}` has a #[path] attribute; its file keeps its name",
+                decl.file.display(),
+                decl.line,
+                old
+            ));
+            continue;
+        }
+        let Some(dir) = module_dir(&decl.file, &decl.parent) else {
+            continue;
+        };
+        let file = dir.join(format!("{}.rs", old));
+        let sub_dir = dir.join(old);
+        let mut renames = Vec::new();
+        if file.is_file() {
+            renames.push((file, dir.join(format!("{}.rs", args.to))));
+        }
+        if sub_dir.is_dir() {
+            renames.push((sub_dir, dir.join(&args.to)));
+        }
+        if renames.is_empty() {
+            result.warnings.push(format!(
+                "{}:{}: no {}.rs or {}/mod.rs found for `mod {}`",
+                decl.file.display(),
+                decl.line,
+                old,
+                old,
+                old
+            ));
+        }
+        for (from, to) in renames {
+            if to.exists() {
+                anyhow::bail!("{} already exists; nothing was changed", to.display());
+            }
+            if !result.renames.iter().any(|(f, _)| *f == from) {
+                result.renames.push((from, to));
+            }
+        }
+    }
+    if let Some((from, to)) = result.renames.first() {
+        result.warnings.push(format!(
+            "`rs-hack revert` restores file contents, not names; move {} back to {} before reverting",
+            to.display(),
+            from.display()
+        ));
+    }
+
+    for source in &sources {
+        let src = Source::new(&source.text);
+        let mut renamer = Renamer {
+            src: &src,
+            module: source.module.clone(),
+            parent: &parent,
+            target: &target,
+            old,
+            new: &args.to,
+            imported_in: HashSet::new(),
+            bare: Vec::new(),
+            edits: Vec::new(),
+            declared_at: None,
+            macro_lines: Vec::new(),
+        };
+        renamer.visit_file(&source.syntax);
+        for line in &renamer.macro_lines {
+            result.warnings.push(format!(
+                "{}:{}: `{}` appears inside a macro call; check it by hand",
+                source.path.display(),
+                line,
+                old
+            ));
+        }
+        let Renamer {
+            imported_in,
+            bare,
+            mut edits,
+            declared_at,
+            ..
+        } = renamer;
+        edits.extend(
+            bare.into_iter()
+                .filter(|(module, _)| imported_in.contains(module))
+                .map(|(_, edit)| edit),
+        );
+        if edits.is_empty() {
+            continue;
+        }
+        let modified_nodes = declared_at
+            .into_iter()
+            .map(|location| BackupNode {
+                node_type: "mod".to_string(),
+                identifier: old.to_string(),
+                original_content: String::new(),
+                location,
+            })
+            .collect();
+        push_change(&mut result, source, edits, modified_nodes)?;
+    }
+
+    Ok(result)
+}
+
+/// Directory holding the files of modules declared in `parent` by `file`: next to a crate
+/// root or `mod.rs`, in a directory named after any other file, then down through the
+/// inline modules between the file's own module and `parent`.
+fn module_dir(file: &Path, parent: &[String]) -> Option<PathBuf> {
+    let own = module_path(file)?;
+    let mut dir = file.parent()?.to_path_buf();
+    let stem = file.file_stem()?.to_string_lossy();
+    if stem != "mod" && own.len() > 1 {
+        dir.push(stem.as_ref());
+    }
+    for inline in parent.get(own.len()..)? {
+        dir.push(inline);
+    }
+    Some(dir)
+}
+
+fn push_change(
+    result: &mut RenameModResult,
+    source: &Parsed,
+    mut edits: Vec<Edit>,
+    modified_nodes: Vec<BackupNode>,
+) -> Result<()> {
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.start));
+    edits.dedup_by_key(|edit| edit.start);
+    let mut new_text = source.text.clone();
+    for edit in edits {
+        new_text.replace_range(edit.start..edit.end, &edit.text);
+    }
+    syn::parse_file(&new_text).with_context(|| {
+        format!(
+            "Renaming would leave {} unparseable; nothing was changed",
+            source.path.display()
+        )
+    })?;
+    result.changes.push(FileChange {
+        path: source.path.clone(),
+        old_content: source.text.clone(),
+        new_content: new_text,
+        modified_nodes,
+    });
+    Ok(())
+}
+
+/// Collects `mod <old>` items, and the modules that already have a `mod <new>`.
+struct DeclarationFinder<'a> {
+    file: &'a Path,
+    module: Vec<String>,
+    old: &'a str,
+    new: &'a str,
+    found: &'a mut Vec<Declaration>,
+    taken: &'a mut Vec<Vec<String>>,
+}
+
+impl<'ast> Visit<'ast> for DeclarationFinder<'_> {
+    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+        if node.ident == self.old {
+            self.found.push(Declaration {
+                file: self.file.to_path_buf(),
+                parent: self.module.clone(),
+                file_based: node.content.is_none(),
+                path_attr: node.attrs.iter().any(|attr| attr.path().is_ident("path")),
+                line: node.ident.span().start().line,
+            });
+        } else if node.ident == self.new {
+            self.taken.push(self.module.clone());
+        }
+        self.module.push(node.ident.to_string());
+        syn::visit::visit_item_mod(self, node);
+        self.module.pop();
+    }
+}
+
+/// Edits one file: the declaration, `use` trees and paths that resolve to the module.
+struct Renamer<'a> {
+    src: &'a Source<'a>,
+    /// Module the visitor is in, inline modules included
+    module: Vec<String>,
+    parent: &'a [String],
+    target: &'a [String],
+    old: &'a str,
+    new: &'a str,
+    /// Modules where a `use` (or a glob of the parent) brings the module in by its name
+    imported_in: HashSet<Vec<String>>,
+    /// Bare `old::...` paths that only name the module where it's imported, by module
+    bare: Vec<(Vec<String>, Edit)>,
+    edits: Vec<Edit>,
+    declared_at: Option<NodeLocation>,
+    macro_lines: Vec<usize>,
+}
+
+impl Renamer<'_> {
+    fn edit(&self, ident: &syn::Ident) -> Edit {
+        let (start, end) = self.src.range(ident);
+        Edit {
+            start,
+            end,
+            text: self.new.to_string(),
+        }
+    }
+
+    fn names_target(&self, segments: &[String]) -> bool {
+        resolve(segments, &self.module, true).as_deref() == Some(self.target)
+    }
+
+    fn rename_in_use(&mut self, tree: &UseTree, prefix: &mut Vec<String>) {
+        match tree {
+            UseTree::Path(p) => {
+                prefix.push(p.ident.to_string());
+                if p.ident == self.old && self.names_target(prefix) {
+                    self.edits.push(self.edit(&p.ident));
+                }
+                self.rename_in_use(&p.tree, prefix);
+                prefix.pop();
+            }
+            UseTree::Name(n) => {
+                // `use crate::old;` and `use crate::old::{self, ..};` bind the name `old`
+                if n.ident == "self" {
+                    if prefix.last().is_some_and(|seg| seg == self.old) && self.names_target(prefix)
+                    {
+                        self.imported_in.insert(self.module.clone());
+                    }
+                    return;
+                }
+                prefix.push(n.ident.to_string());
+                if n.ident == self.old && self.names_target(prefix) {
+                    self.edits.push(self.edit(&n.ident));
+                    self.imported_in.insert(self.module.clone());
+                }
+                prefix.pop();
+            }
+            UseTree::Rename(r) => {
+                prefix.push(r.ident.to_string());
+                if r.ident == self.old && self.names_target(prefix) {
+                    self.edits.push(self.edit(&r.ident));
+                }
+                prefix.pop();
+            }
+            // `use super::*;` in a sibling's child brings in the parent's modules
+            UseTree::Glob(_) => {
+                if resolve(prefix, &self.module, true).as_deref() == Some(self.parent) {
+                    self.imported_in.insert(self.module.clone());
+                }
+            }
+            UseTree::Group(g) => {
+                for item in &g.items {
+                    self.rename_in_use(item, prefix);
+                }
+            }
+        }
+    }
+
+    fn mentions_old(&self, tokens: proc_macro2::TokenStream) -> bool {
+        tokens.into_iter().any(|token| match token {
+            TokenTree::Ident(ident) => ident == self.old,
+            TokenTree::Group(group) => self.mentions_old(group.stream()),
+            _ => false,
+        })
+    }
+}
+
+impl<'ast> Visit<'ast> for Renamer<'_> {
+    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+        let in_parent = self.module == self.parent;
+        if in_parent && node.ident == self.old {
+            self.edits.push(self.edit(&node.ident));
+            let span = node.span();
+            self.declared_at = Some(NodeLocation {
+                line: span.start().line,
+                column: span.start().column,
+                end_line: span.end().line,
+                end_column: span.end().column,
+            });
+        }
+        for attr in &node.attrs {
+            self.visit_attribute(attr);
+        }
+        self.module.push(node.ident.to_string());
+        if let Some((_, items)) = &node.content {
+            for item in items {
+                self.visit_item(item);
+            }
+        }
+        self.module.pop();
+    }
+
+    fn visit_item_use(&mut self, node: &'ast syn::ItemUse) {
+        // `use ::old::..` names an extern crate
+        if node.leading_colon.is_none() {
+            self.rename_in_use(&node.tree, &mut Vec::new());
+        }
+    }
+
+    fn visit_path(&mut self, path: &'ast syn::Path) {
+        if path.leading_colon.is_none() {
+            let segments: Vec<String> = path
+                .segments
+                .iter()
+                .map(|seg| seg.ident.to_string())
+                .collect();
+            for (at, seg) in path.segments.iter().enumerate() {
+                // A lone `old` is a value or type, never the module
+                if seg.ident != self.old || (at == 0 && segments.len() == 1) {
+                    continue;
+                }
+                if self.names_target(&segments[..=at]) {
+                    self.edits.push(self.edit(&seg.ident));
+                } else if at == 0 {
+                    self.bare.push((self.module.clone(), self.edit(&seg.ident)));
+                }
+            }
+        }
+        syn::visit::visit_path(self, path);
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        if self.mentions_old(node.tokens.clone()) {
+            self.macro_lines.push(node.span().start().line);
+        }
+        syn::visit::visit_macro(self, node);
+    }
+}
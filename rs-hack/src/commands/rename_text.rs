@@ -0,0 +1,211 @@
+//! `rename --include-strings` / `--include-comments-text`: the old name where the AST rename
+//! can't see it.
+//!
+//! The name is replaced as a whole word (not touching another identifier character) inside
+//! string literals and/or comments, doc comments included. Code tokens are never touched,
+//! and a qualified name (`Status::Draft`) is only replaced written out in full. The changes
+//! are kept apart from the AST rename so the CLI can show them as their own diff to review.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use proc_macro2::{Span, TokenStream, TokenTree};
+
+use crate::execute::FileChange;
+use crate::operations::{BackupNode, NodeLocation};
+use crate::surgical::Source;
+
+#[derive(Debug, Clone)]
+pub struct RenameTextArgs {
+    /// The name being renamed, as given to `rename --name`
+    pub name: String,
+    /// The new name (the last segment, for a qualified `name`)
+    pub to: String,
+    pub files: Vec<PathBuf>,
+    /// Replace inside string literals
+    pub strings: bool,
+    /// Replace inside comments
+    pub comments: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct RenameTextResult {
+    pub changes: Vec<FileChange>,
+    /// Mentions replaced inside string literals
+    pub in_strings: usize,
+    /// Mentions replaced inside comments
+    pub in_comments: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Region {
+    Code,
+    String,
+    Comment,
+}
+
+pub fn run(args: &RenameTextArgs) -> Result<RenameTextResult> {
+    let new = match args.name.rsplit_once("::") {
+        Some((qualifier, _)) => format!("{}::{}", qualifier, args.to),
+        None => args.to.clone(),
+    };
+
+    let mut result = RenameTextResult::default();
+    for file in &args.files {
+        let Ok(text) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        // Files that don't tokenize are left alone: there's no telling code from text
+        let Ok(tokens) = text.parse::<TokenStream>() else {
+            continue;
+        };
+
+        let mut new_text = String::with_capacity(text.len());
+        let mut modified_nodes = Vec::new();
+        let mut pos = 0;
+        for (start, end, region) in regions(&text, tokens) {
+            // Only the text between the quotes of a string literal (`r#"..."#`, `b"..."`)
+            let token = &text[start..end];
+            let (start, end) = match region {
+                Region::String if args.strings => match (token.find('"'), token.rfind('"')) {
+                    (Some(open), Some(close)) if open < close => (start + open + 1, start + close),
+                    _ => continue,
+                },
+                Region::Comment if args.comments => (start, end),
+                _ => continue,
+            };
+            let (replaced, count) = replace_word(&text[start..end], &args.name, &new);
+            if count == 0 {
+                continue;
+            }
+            match region {
+                Region::String => result.in_strings += count,
+                _ => result.in_comments += count,
+            }
+            new_text.push_str(&text[pos..start]);
+            new_text.push_str(&replaced);
+            pos = end;
+            modified_nodes.push(BackupNode {
+                node_type: if region == Region::String {
+                    "string"
+                } else {
+                    "comment"
+                }
+                .to_string(),
+                identifier: args.name.clone(),
+                original_content: text[start..end].to_string(),
+                location: location(&text, start, end),
+            });
+        }
+        if modified_nodes.is_empty() {
+            continue;
+        }
+        new_text.push_str(&text[pos..]);
+        result.changes.push(FileChange {
+            path: file.clone(),
+            old_content: text,
+            new_content: new_text,
+            modified_nodes,
+        });
+    }
+    Ok(result)
+}
+
+/// The file split into byte ranges of code, string literals and comments, in order. Text
+/// between tokens is whitespace and plain comments; doc comments come out of the lexer as
+/// `#[doc = "..."]` tokens spanning the comment.
+fn regions(text: &str, tokens: TokenStream) -> Vec<(usize, usize, Region)> {
+    fn span(src: &Source, span: Span, literal: bool) -> (usize, usize, bool) {
+        (src.offset(span.start()), src.offset(span.end()), literal)
+    }
+
+    fn collect(stream: TokenStream, src: &Source, out: &mut Vec<(usize, usize, bool)>) {
+        for tree in stream {
+            match tree {
+                TokenTree::Group(group) => {
+                    out.push(span(src, group.span_open(), false));
+                    collect(group.stream(), src, out);
+                    out.push(span(src, group.span_close(), false));
+                }
+                TokenTree::Literal(literal) => out.push(span(src, literal.span(), true)),
+                other => out.push(span(src, other.span(), false)),
+            }
+        }
+    }
+
+    let src = Source::new(text);
+    let mut spans = Vec::new();
+    collect(tokens, &src, &mut spans);
+    // A doc comment gives all its tokens the comment's span (and its brackets a byte of
+    // it): keep the outermost range at each position
+    spans.sort_by_key(|&(start, end, _)| (start, std::cmp::Reverse(end)));
+
+    let mut regions = Vec::new();
+    let mut pos = 0;
+    for (start, end, literal) in spans {
+        if start < pos {
+            continue;
+        }
+        if pos < start {
+            regions.push((pos, start, Region::Comment));
+        }
+        let token = &text[start..end];
+        let region = if token.starts_with("//") || token.starts_with("/*") {
+            Region::Comment
+        } else if literal && token.contains('"') && !token.starts_with('\'') {
+            Region::String
+        } else {
+            Region::Code
+        };
+        regions.push((start, end, region));
+        pos = end;
+    }
+    if pos < text.len() {
+        regions.push((pos, text.len(), Region::Comment));
+    }
+    regions
+}
+
+/// `text` with every `old` that doesn't touch another identifier character replaced by
+/// `new`, and how many were.
+fn replace_word(text: &str, old: &str, new: &str) -> (String, usize) {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let mut out = String::with_capacity(text.len());
+    let mut count = 0;
+    let mut pos = 0;
+    for (i, _) in text.match_indices(old) {
+        if i < pos {
+            continue;
+        }
+        let before = text[..i].chars().next_back();
+        let after = text[i + old.len()..].chars().next();
+        if before.is_some_and(is_ident) || after.is_some_and(is_ident) {
+            continue;
+        }
+        out.push_str(&text[pos..i]);
+        out.push_str(new);
+        pos = i + old.len();
+        count += 1;
+    }
+    out.push_str(&text[pos..]);
+    (out, count)
+}
+
+/// Line and column (1-based line, 0-based char column) of a byte range.
+fn location(text: &str, start: usize, end: usize) -> NodeLocation {
+    let line_col = |offset: usize| {
+        let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+        (
+            text[..offset].matches('\n').count() + 1,
+            text[line_start..offset].chars().count(),
+        )
+    };
+    let (line, column) = line_col(start);
+    let (end_line, end_column) = line_col(end);
+    NodeLocation {
+        line,
+        column,
+        end_line,
+        end_column,
+    }
+}
@@ -0,0 +1,234 @@
+//! `--flag-risky`: score each match a rename or transform dry run would touch and explain
+//! why it might be a false positive, so ambiguous matches get reviewed before `--apply`.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde::Serialize;
+use syn::visit::Visit;
+
+use crate::editor::RustEditor;
+use crate::verbose::Verbose;
+
+/// Matches scoring at least this much are reported as risky.
+pub const RISK_THRESHOLD: u32 = 2;
+
+/// Names common enough that a bare match is likely to hit something unrelated.
+const COMMON_NAMES: &[&str] = &[
+    "apply", "build", "call", "clear", "clone", "close", "config", "data", "default", "find",
+    "from", "get", "handle", "id", "init", "insert", "into", "is_empty", "len", "load", "map",
+    "name", "new", "next", "open", "parse", "process", "push", "read", "remove", "reset", "run",
+    "save", "send", "set", "start", "state", "stop", "update", "value", "write",
+];
+
+#[derive(Debug, Clone)]
+pub struct RiskArgs {
+    pub files: Vec<PathBuf>,
+    /// Node types the operation matches (inspect vocabulary, e.g. "function-call")
+    pub node_types: Vec<String>,
+    pub name: Option<String>,
+    pub content_filter: Option<String>,
+    pub exclude_names: Vec<String>,
+    pub exclude_in: Vec<String>,
+    pub verbose: Verbose,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RiskyMatch {
+    pub file_path: String,
+    pub line: usize,
+    pub column: usize,
+    pub node_type: String,
+    pub identifier: String,
+    pub snippet: String,
+    pub score: u32,
+    pub reasons: Vec<String>,
+}
+
+/// Macro bodies that mention the target name; syn doesn't parse them, so nothing in
+/// them is matched or rewritten.
+#[derive(Debug, Clone, Serialize)]
+pub struct MacroMentions {
+    pub file_path: String,
+    pub lines: Vec<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RiskReport {
+    pub total_matches: usize,
+    pub risky: Vec<RiskyMatch>,
+    pub macro_mentions: Vec<MacroMentions>,
+}
+
+pub fn run(args: &RiskArgs) -> Result<RiskReport> {
+    let mut total_matches = 0;
+    let mut risky = Vec::new();
+    let mut macro_mentions = Vec::new();
+
+    for file in &args.files {
+        let Ok(content) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        let editor = match RustEditor::new(&content) {
+            Ok(editor) => editor,
+            Err(e) => {
+                args.verbose.skipped(file, &e);
+                continue;
+            }
+        };
+        let file_path = file.display().to_string();
+
+        for node_type in &args.node_types {
+            let mut matches = editor.inspect(Some(node_type), args.name.as_deref(), None, false)?;
+            if let Some(filter) = &args.content_filter {
+                matches.retain(|m| m.snippet.contains(filter));
+            }
+            editor.retain_not_excluded(&mut matches, &args.exclude_names, &args.exclude_in);
+            total_matches += matches.len();
+
+            for m in matches {
+                let (score, reasons) = score_match(&m.node_type, &m.identifier, &m.snippet);
+                if score >= RISK_THRESHOLD {
+                    risky.push(RiskyMatch {
+                        file_path: file_path.clone(),
+                        line: m.location.line,
+                        column: m.location.column,
+                        node_type: m.node_type,
+                        identifier: m.identifier,
+                        snippet: m.snippet,
+                        score,
+                        reasons,
+                    });
+                }
+            }
+        }
+
+        if let Some(name) = &args.name {
+            let lines = macro_mention_lines(&content, last_segment(name));
+            if !lines.is_empty() {
+                macro_mentions.push(MacroMentions { file_path, lines });
+            }
+        }
+    }
+
+    Ok(RiskReport {
+        total_matches,
+        risky,
+        macro_mentions,
+    })
+}
+
+fn last_segment(name: &str) -> &str {
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+/// Score one match; each reason adds its weight.
+pub(crate) fn score_match(node_type: &str, identifier: &str, snippet: &str) -> (u32, Vec<String>) {
+    let mut score = 0;
+    let mut reasons = Vec::new();
+
+    let name = last_segment(identifier);
+    if COMMON_NAMES.contains(&name) {
+        score += 2;
+        reasons.push(format!("common name `{}`", name));
+    }
+
+    match node_type {
+        "identifier" => {
+            score += 1;
+            reasons.push("bare identifier (could be a local, field, or unrelated item)".into());
+        }
+        "method-call" => {
+            score += 1;
+            reasons.push("method call; receiver type not checked".into());
+        }
+        "function-call" | "enum-usage" | "type-ref" => {
+            let head = snippet
+                .split(['(', '{', '<', ' '])
+                .next()
+                .unwrap_or(snippet);
+            if !head.contains("::") {
+                score += 1;
+                reasons.push("unqualified path".into());
+            }
+        }
+        _ => {}
+    }
+
+    (score, reasons)
+}
+
+/// Lines of macro invocations whose token stream contains `name` as an identifier.
+pub(crate) fn macro_mention_lines(content: &str, name: &str) -> Vec<usize> {
+    struct MacroVisitor<'a> {
+        name: &'a str,
+        lines: Vec<usize>,
+    }
+
+    fn mentions(tokens: proc_macro2::TokenStream, name: &str) -> bool {
+        tokens.into_iter().any(|tt| match tt {
+            proc_macro2::TokenTree::Ident(ident) => ident == name,
+            proc_macro2::TokenTree::Group(group) => mentions(group.stream(), name),
+            _ => false,
+        })
+    }
+
+    impl<'ast> Visit<'ast> for MacroVisitor<'_> {
+        fn visit_macro(&mut self, node: &'ast syn::Macro) {
+            if mentions(node.tokens.clone(), self.name) {
+                let line = node
+                    .path
+                    .segments
+                    .first()
+                    .map_or(0, |seg| seg.ident.span().start().line);
+                self.lines.push(line);
+            }
+        }
+    }
+
+    let Ok(syntax) = syn::parse_file(content) else {
+        return Vec::new();
+    };
+    let mut visitor = MacroVisitor {
+        name,
+        lines: Vec::new(),
+    };
+    visitor.visit_file(&syntax);
+    visitor.lines
+}
+
+pub fn render(report: &RiskReport) {
+    if report.risky.is_empty() {
+        eprintln!(
+            "Risk review: none of {} match(es) flagged as risky",
+            report.total_matches
+        );
+    } else {
+        eprintln!(
+            "Risk review: {} of {} match(es) flagged as risky",
+            report.risky.len(),
+            report.total_matches
+        );
+        for m in &report.risky {
+            eprintln!(
+                "  ⚠ risky {}:{}:{} {} `{}` (score {}): {}",
+                m.file_path,
+                m.line,
+                m.column,
+                m.node_type,
+                m.identifier,
+                m.score,
+                m.reasons.join("; ")
+            );
+        }
+    }
+    for mention in &report.macro_mentions {
+        let lines: Vec<String> = mention.lines.iter().map(ToString::to_string).collect();
+        eprintln!(
+            "  ⚠ {}: name also appears inside macro invocation(s) at line(s) {}; these are not matched",
+            mention.file_path,
+            lines.join(", ")
+        );
+    }
+    eprintln!();
+}
@@ -0,0 +1,228 @@
+//! `stats` command: a size-and-shape summary of a codebase — item counts, `.unwrap()`s and
+//! `todo!()`s per file, plus the largest functions.
+//!
+//! Read-only. Each file is parsed once and every node `inspect` knows about is bucketed by
+//! type, so the counts agree with what `find --node-type` reports.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::editor::RustEditor;
+use crate::files::{CollectOptions, collect_rust_files_with_options};
+
+/// How many functions the "largest functions" section lists.
+pub const LARGEST_FUNCTIONS: usize = 10;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Counts {
+    /// Lines in the file
+    pub lines: usize,
+    /// Lines that are neither blank nor only a comment
+    pub code_lines: usize,
+    pub structs: usize,
+    pub enums: usize,
+    /// Free functions and methods in impl blocks
+    pub functions: usize,
+    /// Impl blocks, inherent and trait
+    pub impls: usize,
+    pub traits: usize,
+    pub macro_calls: usize,
+    pub unwraps: usize,
+    /// `todo!()` and `unimplemented!()`
+    pub todos: usize,
+}
+
+impl Counts {
+    const fn add(&mut self, other: &Self) {
+        self.lines += other.lines;
+        self.code_lines += other.code_lines;
+        self.structs += other.structs;
+        self.enums += other.enums;
+        self.functions += other.functions;
+        self.impls += other.impls;
+        self.traits += other.traits;
+        self.macro_calls += other.macro_calls;
+        self.unwraps += other.unwraps;
+        self.todos += other.todos;
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileStats {
+    pub file_path: String,
+    #[serde(flatten)]
+    pub counts: Counts,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FunctionSize {
+    /// `name` or `Type::method`
+    pub name: String,
+    pub file_path: String,
+    pub line: usize,
+    /// Lines spanned, signature to closing brace
+    pub lines: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatsReport {
+    pub files: Vec<FileStats>,
+    pub totals: Counts,
+    /// The `LARGEST_FUNCTIONS` longest functions, longest first
+    pub largest_functions: Vec<FunctionSize>,
+}
+
+pub fn run(paths: &[PathBuf], exclude: &[String], options: &CollectOptions) -> Result<StatsReport> {
+    let files = collect_rust_files_with_options(paths, exclude, options)?;
+
+    let mut report = StatsReport {
+        files: Vec::new(),
+        totals: Counts::default(),
+        largest_functions: Vec::new(),
+    };
+    let mut functions = Vec::new();
+
+    for file in &files {
+        let content = match std::fs::read_to_string(file) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("⚠️  Skipping {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        let editor = match RustEditor::new(&content) {
+            Ok(editor) => editor,
+            Err(e) => {
+                eprintln!("⚠️  Skipping {} (parse error): {}", file.display(), e);
+                continue;
+            }
+        };
+        let file_path = file.to_string_lossy().to_string();
+
+        let mut counts = Counts {
+            lines: content.lines().count(),
+            code_lines: content.lines().filter(|line| is_code(line)).count(),
+            ..Counts::default()
+        };
+        for node in editor.inspect(None, None, None, false)? {
+            match (node.node_type.as_str(), node.identifier.as_str()) {
+                ("struct", _) => counts.structs += 1,
+                ("enum", _) => counts.enums += 1,
+                ("function" | "impl-method", name) => {
+                    counts.functions += 1;
+                    functions.push(FunctionSize {
+                        name: name.to_string(),
+                        file_path: file_path.clone(),
+                        line: node.location.line,
+                        lines: node.location.end_line - node.location.line + 1,
+                    });
+                }
+                ("impl", _) => counts.impls += 1,
+                ("trait", _) => counts.traits += 1,
+                ("macro-call", name) => {
+                    counts.macro_calls += 1;
+                    if matches!(name, "todo" | "unimplemented") {
+                        counts.todos += 1;
+                    }
+                }
+                ("method-call", "unwrap") => counts.unwraps += 1,
+                _ => {}
+            }
+        }
+
+        report.totals.add(&counts);
+        report.files.push(FileStats { file_path, counts });
+    }
+
+    // Stable sort: equal sizes keep file order
+    functions.sort_by_key(|f| std::cmp::Reverse(f.lines));
+    functions.truncate(LARGEST_FUNCTIONS);
+    report.largest_functions = functions;
+
+    Ok(report)
+}
+
+pub fn render(report: &StatsReport) {
+    let headers = [
+        "file", "lines", "code", "structs", "enums", "fns", "impls", "traits", "macros", "unwraps",
+        "todos",
+    ];
+    let row = |label: &str, c: &Counts| {
+        let mut cells = vec![label.to_string()];
+        cells.extend(
+            [
+                c.lines,
+                c.code_lines,
+                c.structs,
+                c.enums,
+                c.functions,
+                c.impls,
+                c.traits,
+                c.macro_calls,
+                c.unwraps,
+                c.todos,
+            ]
+            .iter()
+            .map(ToString::to_string),
+        );
+        cells
+    };
+
+    let mut rows: Vec<Vec<String>> = report
+        .files
+        .iter()
+        .map(|f| row(&f.file_path, &f.counts))
+        .collect();
+    rows.push(row(
+        &format!("total ({} files)", report.files.len()),
+        &report.totals,
+    ));
+
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|i| {
+            rows.iter()
+                .map(|r| r[i].len())
+                .chain(std::iter::once(headers[i].len()))
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    let print_row = |cells: &[String]| {
+        let mut line = format!("{:<w$}", cells[0], w = widths[0]);
+        for (cell, width) in cells.iter().zip(&widths).skip(1) {
+            line.push_str(&format!("  {:>w$}", cell, w = width));
+        }
+        println!("{}", line.trim_end());
+    };
+
+    print_row(&headers.map(str::to_string));
+    for (i, cells) in rows.iter().enumerate() {
+        if i == rows.len() - 1 {
+            println!(
+                "{}",
+                "-".repeat(widths.iter().sum::<usize>() + 2 * (widths.len() - 1))
+            );
+        }
+        print_row(cells);
+    }
+
+    if !report.largest_functions.is_empty() {
+        println!("\nLargest functions:");
+        for f in &report.largest_functions {
+            println!(
+                "  {:>5} lines  {} ({}:{})",
+                f.lines, f.name, f.file_path, f.line
+            );
+        }
+    }
+}
+
+// ---- helpers ----------------------------------------------------------------
+
+/// Whether a line has code on it: not blank and not just a `//` comment.
+fn is_code(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && !line.starts_with("//")
+}
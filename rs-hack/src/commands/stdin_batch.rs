@@ -0,0 +1,184 @@
+//! `batch --stdin-batch`: a JSON-lines request loop over stdio.
+//!
+//! Each input line is one request naming an `Operation` (same shape as in batch specs) and
+//! the paths to run it on; each request gets exactly one response line, in order. The
+//! process stays up until stdin closes, so a tool issuing hundreds of edits pays the startup
+//! cost once. Errors are reported in the response and don't end the loop.
+
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::diff::generate_unified_diff;
+use crate::execute::{ExecuteOpts, ExecuteResult, execute, execute_with_state};
+use crate::files::{CollectOptions, collect_rust_files_with_options};
+use crate::operations::Operation;
+
+#[derive(Debug, Deserialize)]
+pub struct BatchRequest {
+    /// Echoed back in the response so callers can pipeline requests
+    #[serde(default)]
+    pub id: Option<serde_json::Value>,
+    pub paths: Vec<PathBuf>,
+    pub operation: Operation,
+    /// Write the changes (default is a dry run)
+    #[serde(default)]
+    pub apply: bool,
+    /// Stop after this many modifications (falls back to the server's `--limit`)
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchResponse {
+    pub id: Option<serde_json::Value>,
+    pub ok: bool,
+    /// Why the request failed; on success, the last per-file error of a multi-file run
+    /// (e.g. the target wasn't found in some of the files)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Set when changes were applied and recorded (`rs-hack revert <run_id>` undoes them)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    pub total_modifications: usize,
+    pub files: Vec<ChangedFile>,
+    /// Files that failed to parse, or that the operation would have changed in more places
+    /// than `--max-matches-per-file` allows, and were left alone
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<SkippedFile>,
+    pub limit_hit: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChangedFile {
+    pub path: PathBuf,
+    pub modified_nodes: usize,
+    /// Unified diff of the change (what would be written, on a dry run)
+    pub diff: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SkippedFile {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/// Settings shared by every request in the session.
+#[derive(Debug, Clone, Default)]
+pub struct ServeOpts {
+    pub exclude: Vec<String>,
+    pub collect: CollectOptions,
+    pub local_state: bool,
+    /// Apply without recording backups or run metadata
+    pub no_state: bool,
+    pub limit: Option<usize>,
+    pub max_matches_per_file: Option<usize>,
+}
+
+/// Answer requests from `input` until it closes. Returns the number of requests handled.
+pub fn serve(input: impl BufRead, mut output: impl Write, opts: &ServeOpts) -> Result<usize> {
+    let mut handled = 0;
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        // Parse in two steps so a malformed operation still gets its id echoed back
+        let response = match serde_json::from_str::<serde_json::Value>(&line) {
+            Ok(value) => {
+                let id = value.get("id").cloned();
+                match serde_json::from_value::<BatchRequest>(value) {
+                    Ok(request) => handle(&request, opts).unwrap_or_else(|e| failure(id, &e)),
+                    Err(e) => failure(id, &anyhow::anyhow!("Invalid request: {}", e)),
+                }
+            }
+            Err(e) => failure(None, &anyhow::anyhow!("Invalid request: {}", e)),
+        };
+        serde_json::to_writer(&mut output, &response)?;
+        output.write_all(b"\n")?;
+        output.flush()?;
+        handled += 1;
+    }
+    Ok(handled)
+}
+
+fn handle(request: &BatchRequest, opts: &ServeOpts) -> Result<BatchResponse> {
+    let files = collect_rust_files_with_options(&request.paths, &opts.exclude, &opts.collect)?;
+    let execute_opts = ExecuteOpts {
+        apply: request.apply,
+        output: None,
+        limit: request.limit.or(opts.limit),
+        max_per_file: opts.max_matches_per_file,
+        ..ExecuteOpts::default()
+    };
+    let result = if opts.no_state {
+        execute(&files, &request.operation, &execute_opts)?
+    } else {
+        execute_with_state(
+            &files,
+            &request.operation,
+            &execute_opts,
+            opts.local_state,
+            format!(
+                "rs-hack batch --stdin-batch ({})",
+                request.operation.kind_name()
+            ),
+        )?
+    };
+    Ok(response(request.id.clone(), result))
+}
+
+fn response(id: Option<serde_json::Value>, result: ExecuteResult) -> BatchResponse {
+    BatchResponse {
+        id,
+        ok: true,
+        error: result.last_error,
+        run_id: result.run_id,
+        total_modifications: result.total_modifications,
+        files: result
+            .changes
+            .into_iter()
+            .map(|change| ChangedFile {
+                diff: generate_unified_diff(
+                    &change.path,
+                    &change.old_content,
+                    &change.new_content,
+                    3,
+                )
+                .0,
+                path: change.path,
+                modified_nodes: change.modified_nodes.len(),
+            })
+            .collect(),
+        skipped: result
+            .parse_errors
+            .into_iter()
+            .map(|(path, reason)| SkippedFile { path, reason })
+            .chain(
+                result
+                    .capped_files
+                    .into_iter()
+                    .map(|(path, count)| SkippedFile {
+                        path,
+                        reason: format!("{} matches, over --max-matches-per-file", count),
+                    }),
+            )
+            .collect(),
+        limit_hit: result.limit_hit,
+    }
+}
+
+fn failure(id: Option<serde_json::Value>, error: &anyhow::Error) -> BatchResponse {
+    BatchResponse {
+        id,
+        ok: false,
+        error: Some(format!("{:#}", error)),
+        run_id: None,
+        total_modifications: 0,
+        files: Vec::new(),
+        skipped: Vec::new(),
+        limit_hit: false,
+    }
+}
@@ -0,0 +1,92 @@
+//! `sync-match` command: two-way reconciliation between an enum definition and the match
+//! expressions over it. Reporting lives here; the edit itself is `Operation::SyncMatchArms`.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use super::match_audit::{self, MatchReport};
+use crate::files::CollectOptions;
+use crate::operations::{Operation, SyncMatchArmsOp};
+
+pub fn run(
+    paths: &[PathBuf],
+    enum_name: &str,
+    function_name: Option<&str>,
+    exclude: &[String],
+    options: &CollectOptions,
+) -> Result<MatchReport> {
+    let mut report = match_audit::run(paths, enum_name, exclude, options)?;
+    if let Some(function_name) = function_name {
+        report
+            .match_sites
+            .retain(|site| site.fn_name == function_name);
+    }
+    Ok(report)
+}
+
+/// Build the edit for a report. The variant list comes from the report so match sites in files
+/// that don't define the enum can still be synchronized.
+pub fn operation(
+    report: &MatchReport,
+    function_name: Option<String>,
+    body: String,
+    prune: bool,
+) -> Operation {
+    Operation::SyncMatchArms(SyncMatchArmsOp {
+        enum_name: report.enum_name.clone(),
+        body,
+        function_name,
+        variants: report.all_variants.clone(),
+        prune,
+    })
+}
+
+/// Whether applying `operation` for this report would change anything.
+pub fn has_changes(report: &MatchReport, prune: bool) -> bool {
+    report
+        .match_sites
+        .iter()
+        .any(|site| !site.missing_variants.is_empty() || (prune && !site.stale_variants.is_empty()))
+}
+
+pub fn render(report: &MatchReport, prune: bool) {
+    println!("Match sync for enum {}:", report.enum_name);
+    println!("  Known variants: {}", report.all_variants.join(", "));
+    println!();
+
+    if report.match_sites.is_empty() {
+        println!(
+            "  No match expressions found for enum {}.",
+            report.enum_name
+        );
+        return;
+    }
+
+    let mut any_stale = false;
+    for site in &report.match_sites {
+        if site.missing_variants.is_empty() && site.stale_variants.is_empty() {
+            println!(
+                "  {} ({}:{}): in sync",
+                site.fn_name, site.file_path, site.line
+            );
+            continue;
+        }
+
+        println!("  {} ({}:{}):", site.fn_name, site.file_path, site.line);
+        if !site.missing_variants.is_empty() {
+            println!("    + add: {}", site.missing_variants.join(", "));
+        }
+        if !site.stale_variants.is_empty() {
+            any_stale = true;
+            let verb = if prune { "- remove" } else { "! stale" };
+            println!("    {}: {}", verb, site.stale_variants.join(", "));
+        }
+    }
+
+    if any_stale && !prune {
+        println!(
+            "\n💡 Stale arms reference variants that no longer exist. Re-run with --prune to remove them."
+        );
+    }
+}
@@ -0,0 +1,392 @@
+//! `remove --unused-imports`: `use` imports a file never refers to. Reporting lives here; the
+//! edit itself is `Operation::RemoveUnusedImports`.
+//!
+//! An import counts as used when its name appears anywhere in the file outside `use` items.
+//! syn can't see everything the compiler can, so some imports are left in place with a
+//! warning rather than guessed at: globs, names that only appear inside macro input or
+//! attributes, and names that may be traits brought into scope for their methods.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use proc_macro2::TokenTree;
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+use crate::operations::{Operation, RemoveUnusedImportsOp};
+
+/// Traits commonly imported only so their methods resolve. An unreferenced import of one of
+/// these (or of any `*Ext` name, or a trait the scanned files define) is kept.
+const METHOD_TRAITS: &[&str] = &[
+    "Any",
+    "Args",
+    "AsMut",
+    "AsRef",
+    "AsyncBufReadExt",
+    "AsyncRead",
+    "AsyncReadExt",
+    "AsyncWrite",
+    "AsyncWriteExt",
+    "Borrow",
+    "BorrowMut",
+    "Buf",
+    "BufMut",
+    "BufRead",
+    "CommandFactory",
+    "Context",
+    "Deref",
+    "DerefMut",
+    "Digest",
+    "DoubleEndedIterator",
+    "Error",
+    "ExactSizeIterator",
+    "Extend",
+    "Float",
+    "FromArgMatches",
+    "FromIterator",
+    "FromPrimitive",
+    "FromStr",
+    "Future",
+    "Hash",
+    "Hasher",
+    "IdentExt",
+    "IndexedParallelIterator",
+    "IntoIterator",
+    "IntoParallelIterator",
+    "IntoParallelRefIterator",
+    "IsTerminal",
+    "Iterator",
+    "Itertools",
+    "Num",
+    "One",
+    "ParallelIterator",
+    "Parser",
+    "Read",
+    "Rng",
+    "SeedableRng",
+    "Seek",
+    "Sink",
+    "Spanned",
+    "Stream",
+    "Subcommand",
+    "ToPrimitive",
+    "ToTokens",
+    "TryFrom",
+    "TryInto",
+    "ValueEnum",
+    "Visit",
+    "VisitMut",
+    "Watcher",
+    "Write",
+    "Zero",
+];
+
+/// Why an unreferenced-looking import was left in place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// `use x::*`: what it brings in isn't known
+    Glob,
+    /// The name only appears in macro input or attribute arguments
+    MacroOnly,
+    /// The name may be a trait in scope for its methods
+    MaybeTrait,
+}
+
+impl SkipReason {
+    pub const fn describe(self) -> &'static str {
+        match self {
+            Self::Glob => "glob imports aren't checked",
+            Self::MacroOnly => "only used inside a macro or attribute",
+            Self::MaybeTrait => "may be a trait used for its methods",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedImport {
+    pub file_path: String,
+    pub line: usize,
+    /// The imported path, e.g. `super::*` or `std::io::Write`
+    pub import: String,
+    pub reason: SkipReason,
+}
+
+#[derive(Debug, Default)]
+pub struct UnusedImportsReport {
+    /// Traits the scanned files define
+    pub traits: Vec<String>,
+    pub skipped: Vec<SkippedImport>,
+}
+
+/// One file's imports: the names safe to remove, and those left alone.
+#[derive(Debug, Default)]
+pub struct FileImports {
+    pub unused: HashSet<String>,
+    /// (line, imported path, reason)
+    pub skipped: Vec<(usize, String, SkipReason)>,
+}
+
+pub fn run(files: &[PathBuf]) -> Result<UnusedImportsReport> {
+    let mut sources = Vec::new();
+    for file in files {
+        let content = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read file: {:?}", file))?;
+        // Unparseable files are reported when the operation runs
+        if let Ok(syntax) = syn::parse_file(&content) {
+            sources.push((file.to_string_lossy().to_string(), syntax));
+        }
+    }
+
+    let mut report = UnusedImportsReport::default();
+    for (_, syntax) in &sources {
+        report.traits.extend(trait_names(syntax));
+    }
+    report.traits.sort();
+    report.traits.dedup();
+
+    for (file_path, syntax) in &sources {
+        for (line, import, reason) in analyze(syntax, &report.traits).skipped {
+            report.skipped.push(SkippedImport {
+                file_path: file_path.clone(),
+                line,
+                import,
+                reason,
+            });
+        }
+    }
+    Ok(report)
+}
+
+pub fn operation(report: &UnusedImportsReport) -> Operation {
+    Operation::RemoveUnusedImports(RemoveUnusedImportsOp {
+        traits: report.traits.clone(),
+    })
+}
+
+pub fn render_skipped(report: &UnusedImportsReport) {
+    for skipped in &report.skipped {
+        eprintln!(
+            "⚠️  {}:{}: left `use {}` in place: {}",
+            skipped.file_path,
+            skipped.line,
+            skipped.import,
+            skipped.reason.describe()
+        );
+    }
+}
+
+/// Sort `syntax`'s imports into unused and skipped. `traits` are trait names known to be
+/// defined elsewhere (see [`UnusedImportsReport::traits`]).
+pub fn analyze(syntax: &syn::File, traits: &[String]) -> FileImports {
+    let mut refs = References::default();
+    refs.visit_file(syntax);
+
+    let mut imports = FileImports::default();
+    for item in checked_use_items(syntax) {
+        let mut leaves = Vec::new();
+        use_leaves(&item.tree, &mut Vec::new(), None, &mut leaves);
+        for leaf in leaves {
+            let line = leaf.span.start().line;
+            let Some(name) = leaf.name else {
+                if leaf.glob {
+                    imports.skipped.push((line, leaf.path, SkipReason::Glob));
+                }
+                continue;
+            };
+            if refs.code.contains(&name) {
+                continue;
+            }
+            let reason = if refs.macros.contains(&name) {
+                Some(SkipReason::MacroOnly)
+            } else if METHOD_TRAITS.contains(&name.as_str())
+                || name.ends_with("Ext")
+                || traits.contains(&name)
+            {
+                Some(SkipReason::MaybeTrait)
+            } else {
+                None
+            };
+            match reason {
+                Some(reason) => imports.skipped.push((line, leaf.path, reason)),
+                None => {
+                    imports.unused.insert(name);
+                }
+            }
+        }
+    }
+    imports
+}
+
+/// Private `use` items anywhere in `syntax` (modules and function bodies included) that
+/// aren't marked `#[allow(unused_imports)]`. `pub use` re-exports are for other files.
+pub(crate) fn checked_use_items(syntax: &syn::File) -> Vec<&syn::ItemUse> {
+    struct Uses<'ast> {
+        items: Vec<&'ast syn::ItemUse>,
+    }
+
+    impl<'ast> Visit<'ast> for Uses<'ast> {
+        fn visit_item_use(&mut self, node: &'ast syn::ItemUse) {
+            let allowed = node.attrs.iter().any(|attr| {
+                attr.path().is_ident("allow")
+                    && attr
+                        .meta
+                        .require_list()
+                        .is_ok_and(|list| list.tokens.to_string().contains("unused_imports"))
+            });
+            if matches!(node.vis, syn::Visibility::Inherited) && !allowed {
+                self.items.push(node);
+            }
+        }
+    }
+
+    let mut uses = Uses { items: Vec::new() };
+    uses.visit_file(syntax);
+    uses.items
+}
+
+/// One name a `use` tree brings in.
+#[derive(Debug)]
+pub(crate) struct UseLeaf {
+    /// The name it binds: `None` for globs and `as _`
+    pub name: Option<String>,
+    pub path: String,
+    pub glob: bool,
+    pub span: proc_macro2::Span,
+}
+
+/// The leaves of `tree` under `prefix`; `parent` is the segment a `self` in a group stands for.
+pub(crate) fn use_leaves(
+    tree: &syn::UseTree,
+    prefix: &mut Vec<String>,
+    parent: Option<&syn::Ident>,
+    leaves: &mut Vec<UseLeaf>,
+) {
+    let path = |prefix: &[String], last: &str| {
+        prefix
+            .iter()
+            .map(String::as_str)
+            .chain(std::iter::once(last))
+            .collect::<Vec<_>>()
+            .join("::")
+    };
+    match tree {
+        syn::UseTree::Path(p) => {
+            prefix.push(p.ident.to_string());
+            use_leaves(&p.tree, prefix, Some(&p.ident), leaves);
+            prefix.pop();
+        }
+        syn::UseTree::Name(n) => {
+            let name = if n.ident == "self" {
+                parent.map(ToString::to_string)
+            } else {
+                Some(n.ident.to_string())
+            };
+            leaves.push(UseLeaf {
+                name,
+                path: path(prefix, &n.ident.to_string()),
+                glob: false,
+                span: tree.span(),
+            });
+        }
+        syn::UseTree::Rename(r) => leaves.push(UseLeaf {
+            name: (r.rename != "_").then(|| r.rename.to_string()),
+            path: path(prefix, &format!("{} as {}", r.ident, r.rename)),
+            glob: false,
+            span: tree.span(),
+        }),
+        syn::UseTree::Glob(_) => leaves.push(UseLeaf {
+            name: None,
+            path: path(prefix, "*"),
+            glob: true,
+            span: tree.span(),
+        }),
+        syn::UseTree::Group(g) => {
+            for item in &g.items {
+                use_leaves(item, prefix, parent, leaves);
+            }
+        }
+    }
+}
+
+/// Names referred to outside `use` items: in code (paths, types, derives, macro names) and
+/// in macro input or attribute arguments, which syn leaves as tokens.
+#[derive(Default)]
+struct References {
+    code: HashSet<String>,
+    macros: HashSet<String>,
+}
+
+impl References {
+    fn record_tokens(&mut self, tokens: proc_macro2::TokenStream) {
+        for token in tokens {
+            match token {
+                TokenTree::Ident(ident) => {
+                    self.macros.insert(ident.to_string());
+                }
+                TokenTree::Group(group) => self.record_tokens(group.stream()),
+                // `#[serde(with = "module")]` and the like name paths in strings
+                TokenTree::Literal(lit) => {
+                    let text = lit.to_string();
+                    if let Some(path) = text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+                        self.macros.extend(path.split("::").map(str::to_string));
+                    }
+                }
+                TokenTree::Punct(_) => {}
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for References {
+    fn visit_ident(&mut self, ident: &'ast proc_macro2::Ident) {
+        self.code.insert(ident.to_string());
+    }
+
+    fn visit_item_use(&mut self, node: &'ast syn::ItemUse) {
+        // `use io::Write;` refers to an imported `io`
+        if let syn::UseTree::Path(p) = &node.tree {
+            self.code.insert(p.ident.to_string());
+        }
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        self.record_tokens(node.tokens.clone());
+        syn::visit::visit_macro(self, node);
+    }
+
+    fn visit_attribute(&mut self, node: &'ast syn::Attribute) {
+        if let syn::Meta::List(list) = &node.meta {
+            let derives = node.path().is_ident("derive").then(|| {
+                list.parse_args_with(
+                    syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated,
+                )
+            });
+            match derives {
+                Some(Ok(paths)) => {
+                    for path in &paths {
+                        self.visit_path(path);
+                    }
+                }
+                _ => self.record_tokens(list.tokens.clone()),
+            }
+        }
+        syn::visit::visit_attribute(self, node);
+    }
+}
+
+/// Names of the traits `syntax` defines, at any depth.
+fn trait_names(syntax: &syn::File) -> Vec<String> {
+    struct Traits(Vec<String>);
+
+    impl<'ast> Visit<'ast> for Traits {
+        fn visit_item_trait(&mut self, node: &'ast syn::ItemTrait) {
+            self.0.push(node.ident.to_string());
+            syn::visit::visit_item_trait(self, node);
+        }
+    }
+
+    let mut traits = Traits(Vec::new());
+    traits.visit_file(syntax);
+    traits.0
+}
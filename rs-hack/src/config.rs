@@ -0,0 +1,75 @@
+//! `.rs-hack.toml`: per-project defaults for CLI flags, taken from the current directory or
+//! the closest one above it.
+//!
+//! Only the top-level keys below are accepted; anything else is reported as an error.
+//!
+//! ```toml
+//! paths = ["src"]
+//! exclude = ["target", "vendor"]
+//! format = "diff"
+//! edit_mode = "rustfmt"
+//! local_state = true
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::operations::EditMode;
+
+/// File name of the per-project config.
+pub const CONFIG_FILE: &str = ".rs-hack.toml";
+
+/// Defaults read from a `.rs-hack.toml`. Explicit flags override every one of them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Used when a command is run without `--paths`; relative to the config's directory
+    pub paths: Vec<PathBuf>,
+    /// Used when no `--exclude` is given
+    pub exclude: Vec<String>,
+    /// The global `--format`
+    pub format: Option<String>,
+    /// `--edit-mode` for the commands that take one
+    pub edit_mode: Option<EditMode>,
+    /// `--local-state`
+    pub local_state: bool,
+    /// The file the config came from (for messages)
+    #[serde(skip)]
+    pub source: PathBuf,
+}
+
+impl Config {
+    /// Parse the contents of a config file.
+    pub fn parse(text: &str) -> Result<Self> {
+        Ok(toml::from_str(text)?)
+    }
+
+    /// The config in `start` or the closest directory above it. Its `paths` are resolved
+    /// against the directory holding it, and shown relative to `start` when they're below it.
+    pub fn find(start: &Path) -> Result<Option<Self>> {
+        let Some(dir) = start
+            .ancestors()
+            .find(|dir| dir.join(CONFIG_FILE).is_file())
+        else {
+            return Ok(None);
+        };
+        let path = dir.join(CONFIG_FILE);
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut config =
+            Self::parse(&text).with_context(|| format!("Invalid {}", path.display()))?;
+        for p in &mut config.paths {
+            let resolved = dir.join(&*p);
+            *p = resolved
+                .strip_prefix(start)
+                .map_or_else(|_| resolved.clone(), Path::to_path_buf);
+            if p.as_os_str().is_empty() {
+                *p = PathBuf::from(".");
+            }
+        }
+        config.source = path;
+        Ok(Some(config))
+    }
+}
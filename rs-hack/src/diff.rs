@@ -77,6 +77,25 @@ pub fn generate_unified_diff(
     (output, stats)
 }
 
+/// One file's section of a patch for `git apply -p1`.
+///
+/// `diff --git` and `a/`/`b/` headers, then the hunks; empty when the contents are the
+/// same. `path` is relative to where the patch will be applied, with `/` separators.
+pub fn git_file_patch(path: &str, original: &str, modified: &str) -> String {
+    let hunks = TextDiff::from_lines(original, modified)
+        .unified_diff()
+        .context_radius(3)
+        .to_string();
+    if hunks.is_empty() {
+        return String::new();
+    }
+    format!(
+        "diff --git a/{path} b/{path}\n--- a/{path}\n+++ b/{path}\n{hunks}",
+        path = path,
+        hunks = hunks
+    )
+}
+
 /// Print a unified diff to stdout
 ///
 /// This is a convenience function that generates and prints a diff.
@@ -92,12 +111,52 @@ pub fn print_diff(path: &Path, original: &str, modified: &str) -> DiffStats {
 
     // Only print if there are actual changes
     if stats.files_changed > 0 {
-        print!("{}", diff_output);
+        if crate::color::enabled() {
+            print!("{}", colorize_diff(&diff_output));
+        } else {
+            print!("{}", diff_output);
+        }
     }
 
     stats
 }
 
+/// Add ANSI colors to a unified diff from `generate_unified_diff`: bold file headers, cyan
+/// hunk headers, red deletions and green additions.
+pub fn colorize_diff(diff: &str) -> String {
+    use crate::color::{BOLD, CYAN, GREEN, RED, RESET};
+
+    let mut out = String::with_capacity(diff.len());
+    let mut in_header = true;
+    for line in diff.split_inclusive('\n') {
+        let (text, newline) = line
+            .strip_suffix('\n')
+            .map_or((line, ""), |text| (text, "\n"));
+        // Only the leading `---`/`+++` pair is a header; inside a hunk the same prefix is a
+        // changed line that happens to start with `--` or `++`
+        let style = if in_header && (text.starts_with("--- ") || text.starts_with("+++ ")) {
+            BOLD
+        } else {
+            in_header = false;
+            match text.as_bytes().first() {
+                Some(b'@') if text.starts_with("@@") => CYAN,
+                Some(b'-') => RED,
+                Some(b'+') => GREEN,
+                _ => "",
+            }
+        };
+        if style.is_empty() {
+            out.push_str(line);
+        } else {
+            out.push_str(style);
+            out.push_str(text);
+            out.push_str(RESET);
+            out.push_str(newline);
+        }
+    }
+    out
+}
+
 /// Print a summary of changes (only changed lines with minimal context)
 ///
 /// This shows a more focused view than a full unified diff, displaying only
@@ -146,7 +205,20 @@ pub fn print_summary_diff(path: &Path, original: &str, modified: &str) -> DiffSt
             let (line_num, tag, content) = &changes[i];
 
             // Print the change
-            print!("{:>5} | {}{}", line_num, tag, content);
+            let style = if *tag == '-' {
+                crate::color::RED
+            } else {
+                crate::color::GREEN
+            };
+            let line = format!("{}{}", tag, content);
+            print!(
+                "{:>5} | {}",
+                line_num,
+                crate::color::paint(style, line.trim_end_matches('\n'))
+            );
+            if line.ends_with('\n') {
+                println!();
+            }
 
             i += 1;
         }
@@ -240,6 +312,35 @@ mod tests {
         assert_eq!(stats1.lines_removed, 3);
     }
 
+    #[test]
+    fn test_colorize_diff() {
+        let original = "a\n-- b\nc\n";
+        let modified = "a\nc\nd\n";
+        let (diff, _) = generate_unified_diff(&PathBuf::from("f.rs"), original, modified, 3);
+
+        let colored = colorize_diff(&diff);
+        assert!(colored.starts_with("\x1b[1m--- f.rs\x1b[0m\n\x1b[1m+++ f.rs\x1b[0m\n"));
+        assert!(colored.contains("\x1b[36m@@ -1,3 +1,3 @@\x1b[0m\n"));
+        // A deleted line starting with `--` is still a deletion
+        assert!(colored.contains("\x1b[31m--- b\x1b[0m\n"));
+        assert!(colored.contains("\x1b[32m+d\x1b[0m\n"));
+        assert!(colored.contains("\n a\n"));
+    }
+
+    #[test]
+    fn test_git_file_patch() {
+        let original = "fn a() {}\nfn b() {}\n";
+        let modified = "fn a() {}\nfn c() {}\n";
+
+        let patch = git_file_patch("src/lib.rs", original, modified);
+        assert!(patch.starts_with(
+            "diff --git a/src/lib.rs b/src/lib.rs\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,2 +1,2 @@\n"
+        ));
+        assert!(patch.ends_with(" fn a() {}\n-fn b() {}\n+fn c() {}\n"));
+
+        assert_eq!(git_file_patch("src/lib.rs", original, original), "");
+    }
+
     #[test]
     fn test_print_diff_returns_stats() {
         let original = "line1\nline2\n";
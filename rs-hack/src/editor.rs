@@ -13,18 +13,63 @@ use syn::{
     Arm, Expr, ExprMatch, ExprStruct, Field, Fields, File, Item, ItemEnum, ItemStruct, parse_str,
 };
 
+use crate::cfg::Scope;
 use crate::operations::*;
 use crate::path_resolver::PathResolver;
 
+/// Node types the `wrap` transform action applies to.
+pub const WRAP_NODE_TYPES: &[&str] = &["function-call", "method-call", "struct-literal"];
+
+/// Node types the `rename-signature` transform action applies to, each with the keyword
+/// its name follows.
+pub const RENAME_SIGNATURE_NODE_TYPES: &[(&str, &str)] = &[
+    ("function", "fn"),
+    ("impl-method", "fn"),
+    ("trait-method", "fn"),
+    ("extern-fn", "fn"),
+    ("struct", "struct"),
+    ("union", "union"),
+    ("enum", "enum"),
+    ("trait", "trait"),
+    ("const", "const"),
+    ("static", "static"),
+    ("type-alias", "type"),
+    ("mod", "mod"),
+];
+
+/// Node types `update --visibility` applies to.
+pub const VISIBILITY_NODE_TYPES: &[&str] = &[
+    "struct",
+    "union",
+    "enum",
+    "function",
+    "const",
+    "static",
+    "trait",
+    "type-alias",
+    "mod",
+];
+
+/// A type definition, the function whose body declares it, and the inline module path it
+/// is in.
+type TypeDef = (Item, Option<String>, Vec<String>);
+
 pub struct RustEditor {
     content: String,
     syntax_tree: File,
     line_offsets: Vec<usize>, // Byte offset for each line start
+    /// The code operations and `inspect` work on; the rest is pruned from `syntax_tree`
+    scope: Scope,
 }
 
 impl RustEditor {
     pub fn new(content: &str) -> Result<Self> {
-        let syntax_tree: File = syn::parse_str(content).map_err(|e| {
+        Self::with_scope(content, Scope::default())
+    }
+
+    /// An editor that only sees the code in `scope` (see `crate::cfg::Scope`).
+    pub fn with_scope(content: &str, scope: Scope) -> Result<Self> {
+        let mut syntax_tree: File = syn::parse_str(content).map_err(|e| {
             anyhow::anyhow!(
                 "Failed to parse Rust code at line {}, column {}: {}",
                 e.span().start().line,
@@ -32,6 +77,7 @@ impl RustEditor {
                 e
             )
         })?;
+        scope.prune(&mut syntax_tree);
 
         let line_offsets = Self::compute_line_offsets(content);
 
@@ -39,9 +85,53 @@ impl RustEditor {
             content: content.to_string(),
             syntax_tree,
             line_offsets,
+            scope,
         })
     }
 
+    /// Parse `content` as it is now, pruned to the editor's scope.
+    fn parse_content(&self) -> syn::Result<File> {
+        let mut file: File = syn::parse_str(&self.content)?;
+        self.scope.prune(&mut file);
+        Ok(file)
+    }
+
+    /// The whole syntax tree printed by prettyplease. Under a scope the tree lacks the code
+    /// pruned from it, so printing it would drop that code.
+    fn unparse_tree(&self) -> Result<String> {
+        if !self.scope.is_empty() {
+            anyhow::bail!(
+                "This edit re-prints the whole file, which would drop the code left out by \
+                 --cfg/--skip-tests/--skip-attr/--in-module; use --edit-mode surgical"
+            );
+        }
+        Ok(prettyplease::unparse(&self.syntax_tree))
+    }
+
+    /// Fail when pruned code sits inside `span`: re-printing the node there from the
+    /// syntax tree would drop it.
+    fn ensure_nothing_pruned_in(&self, what: &str, span: Span) -> Result<()> {
+        if self.scope.is_empty() {
+            return Ok(());
+        }
+        let mut file: File = syn::parse_str(&self.content)?;
+        let lines = span.start().line.saturating_sub(1)..span.end().line;
+        if let Some(pruned) = self
+            .scope
+            .prune(&mut file)
+            .into_iter()
+            .find(|pruned| lines.start <= pruned.start && pruned.end <= lines.end)
+        {
+            anyhow::bail!(
+                "Can't re-print {}: the code in it left out by \
+                 --cfg/--skip-tests/--skip-attr/--in-module (line {}) would be dropped",
+                what,
+                pruned.start + 1
+            );
+        }
+        Ok(())
+    }
+
     /// Format a field without extra spaces (e.g., "pub name: String" not "pub name : String")
     fn format_field(field: &Field) -> String {
         let mut result = String::new();
@@ -67,6 +157,499 @@ impl RustEditor {
         result
     }
 
+    /// Split leading outer attributes off a field snippet, e.g.
+    /// `#[cfg(feature = "x")] extra: bool` → (`["#[cfg(feature = \"x\")]"]`, `"extra: bool"`)
+    fn split_leading_attrs(def: &str) -> (Vec<String>, &str) {
+        let mut attrs = Vec::new();
+        let mut rest = def.trim_start();
+
+        while rest.starts_with("#[") {
+            let mut depth = 0usize;
+            let mut in_str = false;
+            let mut end = None;
+            let mut prev = '\0';
+            for (i, c) in rest.char_indices() {
+                match c {
+                    '"' if prev != '\\' => in_str = !in_str,
+                    '[' if !in_str => depth += 1,
+                    ']' if !in_str => {
+                        depth -= 1;
+                        if depth == 0 {
+                            end = Some(i + 1);
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+                prev = c;
+            }
+            let Some(end) = end else { break };
+            attrs.push(rest[..end].to_string());
+            rest = rest[end..].trim_start();
+        }
+
+        (attrs, rest)
+    }
+
+    /// `{field}` references in a struct literal field snippet such as `area: {width} * 2`,
+    /// with their byte ranges: a lone identifier in braces with nothing else inside, not
+    /// even spaces. Other braces, and any inside string literals, are left as written.
+    fn field_value_refs(field: &str) -> Vec<(std::ops::Range<usize>, String)> {
+        use proc_macro2::{Delimiter, TokenStream, TokenTree};
+
+        fn walk(
+            field: &str,
+            tokens: TokenStream,
+            refs: &mut Vec<(std::ops::Range<usize>, String)>,
+        ) {
+            for token in tokens {
+                let TokenTree::Group(group) = token else {
+                    continue;
+                };
+                let inner: Vec<TokenTree> = group.stream().into_iter().collect();
+                let range = group.span().byte_range();
+                if group.delimiter() == Delimiter::Brace
+                    && let [TokenTree::Ident(ident)] = inner.as_slice()
+                    && field.get(range.clone()) == Some(&format!("{{{}}}", ident))
+                {
+                    refs.push((range, ident.to_string()));
+                } else {
+                    walk(field, group.stream(), refs);
+                }
+            }
+        }
+
+        let mut refs = Vec::new();
+        if let Ok(tokens) = field.parse::<TokenStream>() {
+            walk(field, tokens, &mut refs);
+        }
+        refs
+    }
+
+    /// `#[cfg(...)]` attributes on `field_name` in the definition of `struct_name`
+    /// (a struct, or an `Enum::Variant` with named fields) if it is defined in this file.
+    fn definition_field_cfg_attrs(&self, struct_name: &str, field_name: &str) -> Vec<String> {
+        let segments: Vec<&str> = struct_name.trim_start_matches("*::").split("::").collect();
+        let fields = match segments.as_slice() {
+            [.., enum_name, variant_name] => self.scoped_items().into_iter().find_map(|item| {
+                if let Item::Enum(e) = item
+                    && e.ident == enum_name
+                {
+                    e.variants
+                        .iter()
+                        .find(|v| v.ident == variant_name)
+                        .map(|v| v.fields.clone())
+                } else {
+                    None
+                }
+            }),
+            [name] => self.scoped_items().into_iter().find_map(|item| {
+                if let Item::Struct(s) = item
+                    && s.ident == name
+                {
+                    Some(s.fields.clone())
+                } else {
+                    None
+                }
+            }),
+            [] => None,
+        };
+
+        let Some(Fields::Named(fields)) = fields else {
+            return Vec::new();
+        };
+        let Some(field) = fields
+            .named
+            .iter()
+            .find(|f| f.ident.as_ref().is_some_and(|ident| ident == field_name))
+        else {
+            return Vec::new();
+        };
+
+        field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("cfg"))
+            .map(|attr| {
+                let start = self.span_to_byte_offset(attr.span().start());
+                let end = self.span_to_byte_offset(attr.span().end());
+                self.content[start..end].to_string()
+            })
+            .collect()
+    }
+
+    /// Every struct/enum/union definition named `name`, paired with the function whose body
+    /// declares it (`None` for items outside any function body) and the inline module it is
+    /// in (empty at the top of the file).
+    fn type_defs_named(&self, name: &str) -> Vec<TypeDef> {
+        use syn::visit::Visit;
+
+        struct TypeDefCollector<'a> {
+            name: &'a str,
+            fn_stack: Vec<String>,
+            mod_stack: Vec<String>,
+            found: Vec<TypeDef>,
+        }
+
+        impl TypeDefCollector<'_> {
+            fn push(&mut self, item: Item) {
+                self.found
+                    .push((item, self.fn_stack.last().cloned(), self.mod_stack.clone()));
+            }
+        }
+
+        impl<'ast> Visit<'ast> for TypeDefCollector<'_> {
+            fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+                self.fn_stack.push(node.sig.ident.to_string());
+                syn::visit::visit_item_fn(self, node);
+                self.fn_stack.pop();
+            }
+
+            fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+                self.fn_stack.push(node.sig.ident.to_string());
+                syn::visit::visit_impl_item_fn(self, node);
+                self.fn_stack.pop();
+            }
+
+            fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+                // Modules declared inside a function body aren't reachable by path
+                if !self.fn_stack.is_empty() {
+                    return;
+                }
+                self.mod_stack.push(node.ident.to_string());
+                syn::visit::visit_item_mod(self, node);
+                self.mod_stack.pop();
+            }
+
+            fn visit_item_struct(&mut self, node: &'ast ItemStruct) {
+                if node.ident == self.name {
+                    self.push(Item::Struct(node.clone()));
+                }
+            }
+
+            fn visit_item_enum(&mut self, node: &'ast ItemEnum) {
+                if node.ident == self.name {
+                    self.push(Item::Enum(node.clone()));
+                }
+            }
+
+            fn visit_item_union(&mut self, node: &'ast syn::ItemUnion) {
+                if node.ident == self.name {
+                    self.push(Item::Union(node.clone()));
+                }
+            }
+        }
+
+        let mut collector = TypeDefCollector {
+            name,
+            fn_stack: Vec::new(),
+            mod_stack: Vec::new(),
+            found: Vec::new(),
+        };
+        collector.visit_file(&self.syntax_tree);
+        collector.found
+    }
+
+    /// The definitions of `name` in the `--in-module` module (see `in_scope`).
+    fn type_defs_in_scope(&self, kind: &str, name: &str) -> Result<Vec<(Item, Option<String>)>> {
+        let defs = self
+            .type_defs_named(name)
+            .into_iter()
+            .map(|(item, owner, module)| ((item, owner), module))
+            .collect();
+        self.in_scope(kind, name, defs)
+    }
+
+    /// Narrow `candidates`, each with the inline module it is in, down to one module: the
+    /// `--in-module` one; without it, the top of the file if anything there matches, else
+    /// the one module where something does.
+    fn in_scope<T>(
+        &self,
+        kind: &str,
+        name: &str,
+        candidates: Vec<(T, Vec<String>)>,
+    ) -> Result<Vec<T>> {
+        let module = self.scope.module().to_vec();
+        let module = if !module.is_empty() || candidates.iter().any(|(_, m)| m.is_empty()) {
+            module
+        } else {
+            let mut modules: Vec<String> = candidates.iter().map(|(_, m)| m.join("::")).collect();
+            modules.sort();
+            modules.dedup();
+            match modules.as_slice() {
+                [] | [_] => candidates
+                    .first()
+                    .map(|(_, m)| m.clone())
+                    .unwrap_or_default(),
+                _ => anyhow::bail!(
+                    "{} '{}' is defined in several modules ({}); use --in-module to pick one",
+                    kind,
+                    name,
+                    modules.join(", ")
+                ),
+            }
+        };
+        if !module.is_empty() && !candidates.iter().any(|(_, m)| *m == module) {
+            anyhow::bail!(
+                "{} '{}' not found in module '{}'",
+                kind,
+                name,
+                module.join("::")
+            );
+        }
+
+        Ok(candidates
+            .into_iter()
+            .filter(|(_, m)| *m == module)
+            .map(|(candidate, _)| candidate)
+            .collect())
+    }
+
+    /// Every item of the file outside function bodies, the items of inline modules included,
+    /// with its index path (`[2, 0]`: the first item of the inline module that is the third
+    /// item of the file) and the path of the module it is in.
+    fn item_paths(&self) -> Vec<(Vec<usize>, Vec<String>, &Item)> {
+        Self::item_paths_in(&self.syntax_tree.items)
+    }
+
+    /// `item_paths` of a syntax tree's items.
+    fn item_paths_in(items: &[Item]) -> Vec<(Vec<usize>, Vec<String>, &Item)> {
+        type Found<'a> = Vec<(Vec<usize>, Vec<String>, &'a Item)>;
+
+        fn collect<'a>(
+            items: &'a [Item],
+            index: &mut Vec<usize>,
+            module: &mut Vec<String>,
+            out: &mut Found<'a>,
+        ) {
+            for (i, item) in items.iter().enumerate() {
+                index.push(i);
+                out.push((index.clone(), module.clone(), item));
+                if let Item::Mod(syn::ItemMod {
+                    ident,
+                    content: Some((_, items)),
+                    ..
+                }) = item
+                {
+                    module.push(ident.to_string());
+                    collect(items, index, module, out);
+                    module.pop();
+                }
+                index.pop();
+            }
+        }
+
+        let mut out = Vec::new();
+        collect(items, &mut Vec::new(), &mut Vec::new(), &mut out);
+        out
+    }
+
+    /// The item at an index path from `item_paths`.
+    fn item_at(&self, path: &[usize]) -> &Item {
+        let (last, modules) = path.split_last().expect("empty item path");
+        let mut items = &self.syntax_tree.items;
+        for &i in modules {
+            let Item::Mod(syn::ItemMod {
+                content: Some((_, inner)),
+                ..
+            }) = &items[i]
+            else {
+                unreachable!("item path through a non-module item");
+            };
+            items = inner;
+        }
+        &items[*last]
+    }
+
+    fn item_at_mut(&mut self, path: &[usize]) -> &mut Item {
+        let (last, modules) = path.split_last().expect("empty item path");
+        let mut items = &mut self.syntax_tree.items;
+        for &i in modules {
+            let Item::Mod(syn::ItemMod {
+                content: Some((_, inner)),
+                ..
+            }) = &mut items[i]
+            else {
+                unreachable!("item path through a non-module item");
+            };
+            items = inner;
+        }
+        &mut items[*last]
+    }
+
+    /// Index path of the `kind` item named `name` that `matches` picks out, in the module
+    /// `in_scope` settles on.
+    fn locate_item(
+        &self,
+        kind: &str,
+        name: &str,
+        matches: impl Fn(&Item) -> bool,
+    ) -> Result<Vec<usize>> {
+        let candidates = self
+            .item_paths()
+            .into_iter()
+            .filter(|(_, _, item)| matches(item))
+            .map(|(path, module, _)| (path, module))
+            .collect();
+        self.in_scope(kind, name, candidates)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("{} '{}' not found", kind, name))
+    }
+
+    /// Every item of the file outside function bodies, the items of inline modules included
+    /// (only the `--in-module` module's when one is set, the rest being pruned).
+    fn scoped_items(&self) -> Vec<&Item> {
+        self.item_paths()
+            .into_iter()
+            .map(|(_, _, item)| item)
+            .collect()
+    }
+
+    /// Pick one definition out of `candidates`. With `in_fn`, only a type declared in that
+    /// function's body qualifies; otherwise a top-level definition wins, then a function-local
+    /// one as long as only one function declares it.
+    fn select_type_def<T: Clone>(
+        kind: &str,
+        name: &str,
+        candidates: &[(T, Option<String>)],
+        in_fn: Option<&str>,
+    ) -> Result<T> {
+        if let Some(func) = in_fn {
+            return candidates
+                .iter()
+                .find(|(_, owner)| owner.as_deref() == Some(func))
+                .map(|(def, _)| def.clone())
+                .ok_or_else(|| {
+                    anyhow::anyhow!("{} '{}' not found in function '{}'", kind, name, func)
+                });
+        }
+
+        if let Some((def, _)) = candidates.iter().find(|(_, owner)| owner.is_none()) {
+            return Ok(def.clone());
+        }
+
+        match candidates {
+            [] => anyhow::bail!("{} '{}' not found", kind, name),
+            [(def, _)] => Ok(def.clone()),
+            _ => {
+                let owners: Vec<&str> = candidates
+                    .iter()
+                    .filter_map(|(_, owner)| owner.as_deref())
+                    .collect();
+                anyhow::bail!(
+                    "{} '{}' is defined locally in several functions ({}); use --in-fn to pick one",
+                    kind,
+                    name,
+                    owners.join(", ")
+                )
+            }
+        }
+    }
+
+    /// Find a struct definition, descending into function bodies (see `select_type_def`).
+    ///
+    /// A union counts too: it comes back as a struct with the same named fields and spans, so
+    /// the field operations work on it unchanged (`is_union` tells the two apart).
+    fn find_struct_def(&self, name: &str, in_fn: Option<&str>) -> Result<ItemStruct> {
+        let defs = self.type_defs_in_scope("Struct", name)?;
+        let candidates: Vec<(ItemStruct, Option<String>)> = defs
+            .into_iter()
+            .filter_map(|(item, owner)| match item {
+                Item::Struct(s) => Some((s, owner)),
+                Item::Union(u) => Some((
+                    ItemStruct {
+                        attrs: u.attrs,
+                        vis: u.vis,
+                        struct_token: syn::token::Struct(u.union_token.span),
+                        ident: u.ident,
+                        generics: u.generics,
+                        fields: Fields::Named(u.fields),
+                        semi_token: None,
+                    },
+                    owner,
+                )),
+                _ => None,
+            })
+            .collect();
+        Self::select_type_def("Struct", name, &candidates, in_fn)
+    }
+
+    /// Whether a definition returned by `find_struct_def` is really a union.
+    fn is_union(&self, item_struct: &ItemStruct) -> bool {
+        let keyword = self.span_to_byte_offset(item_struct.struct_token.span.start());
+        self.content[keyword..].starts_with("union")
+    }
+
+    /// Backup of a definition returned by `find_struct_def`, taken before changing it.
+    fn struct_def_backup(&self, item_struct: &ItemStruct, identifier: &str) -> BackupNode {
+        let (node_type, item) = match (&item_struct.fields, self.is_union(item_struct)) {
+            (Fields::Named(fields), true) => (
+                "union",
+                Item::Union(syn::ItemUnion {
+                    attrs: item_struct.attrs.clone(),
+                    vis: item_struct.vis.clone(),
+                    union_token: syn::token::Union(item_struct.struct_token.span),
+                    ident: item_struct.ident.clone(),
+                    generics: item_struct.generics.clone(),
+                    fields: fields.clone(),
+                }),
+            ),
+            _ => ("struct", Item::Struct(item_struct.clone())),
+        };
+        BackupNode {
+            node_type: node_type.to_string(),
+            identifier: identifier.to_string(),
+            original_content: self.backup_item(&item),
+            location: self.span_to_location(item_struct.span()),
+        }
+    }
+
+    /// Find an enum definition, descending into function bodies (see `select_type_def`).
+    fn find_enum_def(&self, name: &str, in_fn: Option<&str>) -> Result<ItemEnum> {
+        let defs = self.type_defs_in_scope("Enum", name)?;
+        let candidates: Vec<(ItemEnum, Option<String>)> = defs
+            .into_iter()
+            .filter_map(|(item, owner)| match item {
+                Item::Enum(e) => Some((e, owner)),
+                _ => None,
+            })
+            .collect();
+        Self::select_type_def("Enum", name, &candidates, in_fn)
+    }
+
+    /// Remove the whole lines in `line_start..end` and tidy the blank lines the removal leaves
+    /// behind: two blank lines meeting become one, and a blank line left right after the
+    /// opening `{` or right before the closing `}` is dropped. Other spacing is kept as is.
+    fn remove_lines(&mut self, line_start: usize, end: usize) {
+        self.content.replace_range(line_start..end, "");
+
+        let pos = line_start;
+        let next_end = self.content[pos..]
+            .find('\n')
+            .map_or(self.content.len(), |i| pos + i + 1);
+        let next_line = &self.content[pos..next_end];
+        let prev_start = self.content[..pos.saturating_sub(1)]
+            .rfind('\n')
+            .map_or(0, |i| i + 1);
+        let prev_line = if pos == 0 {
+            None
+        } else {
+            Some(&self.content[prev_start..pos - 1])
+        };
+
+        let next_blank = next_line.ends_with('\n') && next_line.trim().is_empty();
+        let prev_blank = prev_line.is_some_and(|line| line.trim().is_empty());
+        let prev_opens = prev_line.is_some_and(|line| line.trim_end().ends_with('{'));
+        let next_closes = next_line.trim_start().starts_with('}');
+
+        if next_blank && (prev_blank || prev_opens) {
+            self.content.replace_range(pos..next_end, "");
+        } else if prev_blank && next_closes {
+            self.content.replace_range(prev_start..pos, "");
+        }
+    }
+
     fn compute_line_offsets(content: &str) -> Vec<usize> {
         let mut offsets = vec![0];
         for (i, ch) in content.char_indices() {
@@ -98,6 +681,7 @@ impl RustEditor {
     pub fn apply_operation(&mut self, op: &Operation) -> Result<ModificationResult> {
         match op {
             Operation::AddStructField(op) => self.add_struct_field(op),
+            Operation::AddTupleField(op) => self.add_tuple_field(op),
             Operation::UpdateStructField(op) => self.update_struct_field(op),
             Operation::RemoveStructField(op) => self.remove_struct_field(op),
             Operation::AddStructLiteralField(op) => self.add_struct_literal_field(op),
@@ -108,11 +692,14 @@ impl RustEditor {
             Operation::UpdateMatchArm(op) => self.update_match_arm(op),
             Operation::RemoveMatchArm(op) => self.remove_match_arm(op),
             Operation::AddImplMethod(op) => self.add_impl_method(op),
+            Operation::RemoveImplMethod(op) => self.remove_impl_method(op),
             Operation::AddUseStatement(op) => self.add_use_statement(op),
             Operation::AddDerive(op) => self.add_derive(op),
+            Operation::UpdateDerive(op) => self.update_derive(op),
             Operation::Transform(op) => self.transform(op),
             Operation::RenameEnumVariant(op) => self.rename_enum_variant(op),
             Operation::RenameFunction(op) => self.rename_function(op),
+            Operation::RenameStructField(op) => self.rename_struct_field(op),
             Operation::AddDocComment(op) => {
                 self.add_doc_comment_surgical(&op.target_type, &op.name, &op.doc_comment, &op.style)
             }
@@ -129,6 +716,11 @@ impl RustEditor {
             Operation::AddCallArg(op) => self.add_call_arg(op),
             Operation::UpdateCallArg(op) => self.update_call_arg(op),
             Operation::RemoveCallArg(op) => self.remove_call_arg(op),
+            Operation::SyncMatchArms(op) => self.sync_match_arms(op),
+            Operation::MultiTransform(op) => self.multi_transform(op),
+            Operation::AlignStructFields(op) => self.align_struct_fields(op),
+            Operation::RemoveUnusedImports(op) => self.remove_unused_imports(op),
+            Operation::UpdateVisibility(op) => self.update_visibility(op),
         }
     }
 
@@ -140,7 +732,11 @@ impl RustEditor {
 
         // Check if this is literal-only mode (has literal_default but field_def has no type)
         // This allows operating on imported structs without needing the struct definition
-        let has_type = op.field_def.contains(':');
+        // Attributes such as `#[cfg(feature = "x")]` ride along to the literals so a
+        // cfg-gated field is only initialized where it exists
+        let (field_attrs, field_body) = Self::split_leading_attrs(&op.field_def);
+        let attr_prefix: String = field_attrs.iter().map(|a| format!("{} ", a)).collect();
+        let has_type = field_body.contains(':');
         let is_literal_only = op.literal_default.is_some() && !has_type;
 
         // For enum variant literals OR literal-only operations, operate on struct literals only
@@ -155,14 +751,13 @@ impl RustEditor {
 
             let final_field_def = if let Some(literal_default) = &op.literal_default {
                 // literal_default provided - use it
-                let field_name = op
-                    .field_def
+                let field_name = field_body
                     .split(':')
                     .next()
                     .map(|s| s.trim().to_string())
                     .context("Failed to extract field name")?;
-                format!("{}: {}", field_name, literal_default)
-            } else if op.field_def.contains(':') {
+                format!("{}{}: {}", attr_prefix, field_name, literal_default)
+            } else if has_type {
                 // field_def already contains a value (e.g., "layer: None")
                 op.field_def.clone()
             } else {
@@ -178,6 +773,7 @@ impl RustEditor {
                 field_def: final_field_def,
                 position: op.position.clone(),
                 struct_path: None,
+                field_path: Vec::new(),
             };
 
             // Update all struct literals
@@ -189,19 +785,7 @@ impl RustEditor {
         }
 
         // Find the struct and clone it to avoid borrowing issues
-        let item_struct = self
-            .syntax_tree
-            .items
-            .iter()
-            .find_map(|item| {
-                if let Item::Struct(s) = item
-                    && s.ident == op.struct_name
-                {
-                    return Some(s.clone());
-                }
-                None
-            })
-            .ok_or_else(|| anyhow::anyhow!("Struct '{}' not found", op.struct_name))?;
+        let item_struct = self.find_struct_def(&op.struct_name, op.in_fn.as_deref())?;
 
         // Check if the struct matches the where filter (if specified)
         if let Some(ref where_filter) = op.where_filter
@@ -215,15 +799,18 @@ impl RustEditor {
             });
         }
 
+        if op.literal_default.is_some() && self.is_union(&item_struct) {
+            anyhow::bail!(
+                "'{}' is a union: its literals initialize exactly one field, so there's no default to add to them.\n\
+                 Leave out --field-value to add the field to the definition only",
+                op.struct_name
+            );
+        }
+
         // If literal_default is NOT provided, only modify the definition
         if op.literal_default.is_none() {
             // Create backup of original struct before modification
-            let backup_node = BackupNode {
-                node_type: "struct".to_string(),
-                identifier: op.struct_name.clone(),
-                original_content: self.unparse_item(&Item::Struct(item_struct.clone())),
-                location: self.span_to_location(item_struct.span()),
-            };
+            let backup_node = self.struct_def_backup(&item_struct, &op.struct_name);
 
             // Insert the field into the struct definition
             let modified = self
@@ -251,18 +838,10 @@ impl RustEditor {
         // 2. Always update literals
         let literal_default = op.literal_default.as_ref().unwrap();
 
-        // Check if field_def contains a type (has ':')
-        // If it doesn't, skip definition modification (literals-only mode)
-        let has_type = op.field_def.contains(':');
-
+        // If field_def has no type (no ':'), skip definition modification (literals-only mode)
         if has_type {
             // Create backup before any modifications
-            let backup_node = BackupNode {
-                node_type: "struct".to_string(),
-                identifier: op.struct_name.clone(),
-                original_content: self.unparse_item(&Item::Struct(item_struct.clone())),
-                location: self.span_to_location(item_struct.span()),
-            };
+            let backup_node = self.struct_def_backup(&item_struct, &op.struct_name);
 
             // Try to insert field into definition (idempotent - returns false if already exists)
             let def_modified = self
@@ -272,7 +851,8 @@ impl RustEditor {
             if def_modified {
                 modified_nodes.push(backup_node);
                 // Re-parse the content to update syntax_tree with the struct field changes
-                self.syntax_tree = syn::parse_str(&self.content)
+                self.syntax_tree = self
+                    .parse_content()
                     .context("Failed to re-parse content after adding struct field")?;
                 self.line_offsets = Self::compute_line_offsets(&self.content);
             }
@@ -281,8 +861,7 @@ impl RustEditor {
         // Always update literals when literal_default is provided
         // Extract field name from field_def (e.g., "return_type: Option<Type>" -> "return_type" or
         // just "return_type")
-        let field_name = op
-            .field_def
+        let field_name = field_body
             .split(':')
             .next()
             .map(|s| s.trim().to_string())
@@ -291,9 +870,10 @@ impl RustEditor {
         // Create the AddStructLiteralFieldOp
         let literal_op = AddStructLiteralFieldOp {
             struct_name: op.struct_name.clone(),
-            field_def: format!("{}: {}", field_name, literal_default),
+            field_def: format!("{}{}: {}", attr_prefix, field_name, literal_default),
             position: op.position.clone(),
             struct_path: None, // Path resolution not available from struct field operations
+            field_path: Vec::new(),
         };
 
         // Update all struct literals
@@ -391,71 +971,330 @@ impl RustEditor {
                 }
             };
 
-            // Format the new field
+            // Format the new field, keeping its attributes (e.g. `#[cfg(...)]`) on their own lines
             let indent = self.get_indentation(insert_pos);
             let field_str = Self::format_field(&new_field);
-            let insert_text = format!("\n{}{},", indent, field_str);
+            let (field_attrs, _) = Self::split_leading_attrs(&op.field_def);
+            let mut insert_text = String::new();
+            for attr in &field_attrs {
+                insert_text.push_str(&format!("\n{}{}", indent, attr));
+            }
+            insert_text.push_str(&format!("\n{}{},", indent, field_str));
 
             self.content.insert_str(insert_pos, &insert_text);
             return Ok(true);
         }
 
+        if let Fields::Unnamed(_) = item_struct.fields {
+            anyhow::bail!(
+                "Struct '{}' is a tuple struct: leave out --field-name to add a field by position",
+                op.struct_name
+            );
+        }
         anyhow::bail!("Struct '{}' does not have named fields", op.struct_name)
     }
 
-    pub(crate) fn update_struct_field(
-        &mut self,
-        op: &UpdateStructFieldOp,
-    ) -> Result<ModificationResult> {
-        // Check if this is an enum variant struct literal (contains ::)
-        let is_enum_variant = op.struct_name.contains("::");
+    /// Number of fields of the tuple struct `name`, or `None` if it has named fields.
+    pub fn tuple_field_count(&self, name: &str, in_fn: Option<&str>) -> Result<Option<usize>> {
+        let item_struct = self.find_struct_def(name, in_fn)?;
+        Ok(match item_struct.fields {
+            Fields::Unnamed(fields) => Some(fields.unnamed.len()),
+            Fields::Unit => Some(0),
+            Fields::Named(_) => None,
+        })
+    }
 
-        // For enum variant literals, we can't update the enum variant definition
-        // (would need full enum context), so bail with helpful message
-        if is_enum_variant {
-            anyhow::bail!(
-                "Cannot update field in enum variant definition '{}'.\n\
-                 To update fields in enum variant struct literals, use the transform command:\n\
-                 rs-hack transform --node-type struct-literal --name {} --action replace --with <new_pattern> --paths ... --apply",
+    /// Add a field to a tuple struct and, with `literal_default`, a value to each of its
+    /// `Name(..)` literals (`Self(..)` too, inside its impls). Patterns aren't touched.
+    ///
+    /// Only a definition and literals with `op.arity` fields change; without an arity, the
+    /// definition in this file gives it. The file is backed up whole, since literals have
+    /// no names to find them by on revert.
+    pub(crate) fn add_tuple_field(&mut self, op: &AddTupleFieldOp) -> Result<ModificationResult> {
+        let unchanged = ModificationResult {
+            changed: false,
+            modified_nodes: vec![],
+            unmatched_qualified_paths: None,
+        };
+
+        let dummy: ItemStruct = parse_str(&format!("struct Dummy({});", op.field_type))
+            .with_context(|| format!("Failed to parse tuple field type '{}'", op.field_type))?;
+        if dummy.fields.len() != 1 {
+            anyhow::bail!("Expected one tuple field type, got '{}'", op.field_type);
+        }
+        if let Some(value) = &op.literal_default {
+            parse_str::<Expr>(value)
+                .with_context(|| format!("Failed to parse field value '{}'", value))?;
+        }
+
+        // Literals can be updated without the definition when the arity is known
+        let item_struct = if op.arity.is_none() || op.where_filter.is_some() {
+            Some(self.find_struct_def(&op.struct_name, op.in_fn.as_deref())?)
+        } else {
+            self.find_struct_def(&op.struct_name, op.in_fn.as_deref())
+                .ok()
+        };
+        let fields = match item_struct.as_ref().map(|s| &s.fields) {
+            Some(Fields::Named(_)) => anyhow::bail!(
+                "Struct '{}' has named fields: use --field-name to add one",
+                op.struct_name
+            ),
+            Some(Fields::Unnamed(fields)) => Some(fields),
+            // A unit struct has no parentheses to add to
+            Some(Fields::Unit) => anyhow::bail!(
+                "Struct '{}' is a unit struct: rewrite it as `struct {}();` first",
                 op.struct_name,
                 op.struct_name
-            );
+            ),
+            None => None,
+        };
+        if let (Some(item_struct), Some(where_filter)) = (&item_struct, &op.where_filter)
+            && !self.matches_where_filter(&item_struct.attrs, where_filter)?
+        {
+            return Ok(unchanged);
         }
 
-        // Find the struct and clone it to avoid borrowing issues
-        let item_struct = self
-            .syntax_tree
-            .items
-            .iter()
-            .find_map(|item| {
-                if let Item::Struct(s) = item
-                    && s.ident == op.struct_name
-                {
-                    return Some(s.clone());
-                }
-                None
-            })
-            .ok_or_else(|| anyhow::anyhow!("Struct '{}' not found", op.struct_name))?;
+        let arity = op
+            .arity
+            .or_else(|| fields.map(|f| f.unnamed.len()))
+            .context("Tuple struct arity unknown")?;
+        let index = Self::tuple_field_index(&op.position, arity)?;
 
-        // Check if the struct matches the where filter (if specified)
-        if let Some(ref where_filter) = op.where_filter
-            && !self.matches_where_filter(&item_struct.attrs, where_filter)?
+        let mut insertions = Vec::new();
+        let mut modified_nodes = Vec::new();
+        if let (Some(item_struct), Some(fields)) = (&item_struct, fields)
+            && fields.unnamed.len() == arity
         {
-            // Struct doesn't match filter - skip without error
-            return Ok(ModificationResult {
-                changed: false,
-                modified_nodes: vec![],
-                unmatched_qualified_paths: None,
-            });
+            let open = self.span_to_byte_offset(fields.paren_token.span.open().start());
+            let elems: Vec<_> = fields
+                .unnamed
+                .iter()
+                .map(|f| {
+                    self.span_to_byte_offset(f.span().start())
+                        ..self.span_to_byte_offset(f.span().end())
+                })
+                .collect();
+            insertions.push(self.list_insertion(open, &elems, index, op.field_type.trim()));
+            modified_nodes.push(self.struct_def_backup(item_struct, &op.struct_name));
         }
 
-        // Create backup of original struct before modification
-        let backup_node = BackupNode {
-            node_type: "struct".to_string(),
-            identifier: op.struct_name.clone(),
-            original_content: self.unparse_item(&Item::Struct(item_struct.clone())),
-            location: self.span_to_location(item_struct.span()),
+        if let Some(value) = &op.literal_default {
+            for call in self.tuple_literals(&op.struct_name) {
+                if call.args.len() != arity {
+                    continue;
+                }
+                let open = self.span_to_byte_offset(call.paren_token.span.open().start());
+                let elems: Vec<_> = call
+                    .args
+                    .iter()
+                    .map(|a| {
+                        self.span_to_byte_offset(a.span().start())
+                            ..self.span_to_byte_offset(a.span().end())
+                    })
+                    .collect();
+                insertions.push(self.list_insertion(open, &elems, index, value.trim()));
+                let start = self.span_to_byte_offset(call.span().start());
+                let end = self.span_to_byte_offset(call.span().end());
+                modified_nodes.push(BackupNode {
+                    node_type: "tuple-struct-literal".to_string(),
+                    identifier: op.struct_name.clone(),
+                    original_content: self.content[start..end].to_string(),
+                    location: self.span_to_location(call.span()),
+                });
+            }
+        }
+
+        if insertions.is_empty() {
+            return Ok(unchanged);
+        }
+        modified_nodes.insert(
+            0,
+            BackupNode {
+                node_type: "file".to_string(),
+                identifier: op.struct_name.clone(),
+                original_content: self.content.clone(),
+                location: NodeLocation {
+                    line: 0,
+                    column: 0,
+                    end_line: 0,
+                    end_column: 0,
+                },
+            },
+        );
+
+        insertions.sort_by_key(|(pos, _)| std::cmp::Reverse(*pos));
+        for (pos, text) in insertions {
+            self.content.insert_str(pos, &text);
+        }
+        self.line_offsets = Self::compute_line_offsets(&self.content);
+
+        Ok(ModificationResult {
+            changed: true,
+            modified_nodes,
+            unmatched_qualified_paths: None,
+        })
+    }
+
+    /// Index a new tuple field goes at, for a struct with `arity` fields: `after:`/`before:`
+    /// take a field index.
+    fn tuple_field_index(position: &InsertPosition, arity: usize) -> Result<usize> {
+        let parse = |index: &str| -> Result<usize> {
+            let index: usize = index.trim().parse().with_context(|| {
+                format!(
+                    "Tuple struct positions take a field index (e.g. after:0), got '{}'",
+                    index
+                )
+            })?;
+            if index >= arity {
+                anyhow::bail!(
+                    "Field index {} is out of range: the struct has {} fields",
+                    index,
+                    arity
+                );
+            }
+            Ok(index)
+        };
+        Ok(match position {
+            InsertPosition::First => 0,
+            InsertPosition::Last => arity,
+            InsertPosition::After(index) => parse(index)? + 1,
+            InsertPosition::Before(index) => parse(index)?,
+        })
+    }
+
+    /// Where to insert, and what, so `text` becomes element `index` of the comma-separated
+    /// list whose `(` is at byte `open` and whose elements span `elems`. A list written on
+    /// one line stays on one line; in a multi-line list `text` gets its own line.
+    fn list_insertion(
+        &self,
+        open: usize,
+        elems: &[std::ops::Range<usize>],
+        index: usize,
+        text: &str,
+    ) -> (usize, String) {
+        let (Some(first), Some(last)) = (elems.first(), elems.last()) else {
+            return (open + 1, text.to_string());
+        };
+        let multiline = self.content[open..first.start].contains('\n');
+        let indent = self.get_indentation(first.start);
+        if let Some(elem) = elems.get(index) {
+            let text = if multiline {
+                format!("{},\n{}", text, indent)
+            } else {
+                format!("{}, ", text)
+            };
+            return (elem.start, text);
+        }
+        if !multiline {
+            return (last.end, format!(", {}", text));
+        }
+        let rest = &self.content[last.end..];
+        let gap = rest.len() - rest.trim_start().len();
+        if rest[gap..].starts_with(',') {
+            (last.end + gap + 1, format!("\n{}{},", indent, text))
+        } else {
+            (last.end, format!(",\n{}{}", indent, text))
+        }
+    }
+
+    /// `name(..)` calls in the file, macro arguments included: the path may lead through
+    /// modules (`geo::Point(..)`), and `Self(..)` counts inside an impl of `name`.
+    fn tuple_literals(&self, name: &str) -> Vec<syn::ExprCall> {
+        use syn::visit::Visit;
+
+        struct Collector<'a> {
+            name: &'a str,
+            self_is_target: Vec<bool>,
+            found: Vec<syn::ExprCall>,
+        }
+
+        impl<'ast> Visit<'ast> for Collector<'_> {
+            fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+                let is_target = matches!(&*node.self_ty, syn::Type::Path(tp)
+                    if tp.path.segments.last().is_some_and(|s| s.ident == self.name));
+                self.self_is_target.push(is_target);
+                syn::visit::visit_item_impl(self, node);
+                self.self_is_target.pop();
+            }
+
+            fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+                if let Expr::Path(func) = &*node.func
+                    && func.qself.is_none()
+                    && let Some((last, modules)) =
+                        func.path.segments.iter().collect::<Vec<_>>().split_last()
+                {
+                    let through_modules = modules
+                        .iter()
+                        .all(|s| s.ident.to_string().starts_with(|c: char| c.is_lowercase()));
+                    let is_self = modules.is_empty()
+                        && last.ident == "Self"
+                        && self.self_is_target.last() == Some(&true);
+                    if (last.ident == self.name && through_modules) || is_self {
+                        self.found.push(node.clone());
+                    }
+                }
+                syn::visit::visit_expr_call(self, node);
+            }
+
+            fn visit_macro(&mut self, node: &'ast syn::Macro) {
+                use syn::parse::Parser;
+
+                if let Ok(exprs) =
+                    syn::punctuated::Punctuated::<Expr, syn::Token![,]>::parse_terminated
+                        .parse2(node.tokens.clone())
+                {
+                    for expr in &exprs {
+                        self.visit_expr(expr);
+                    }
+                }
+            }
+        }
+
+        let mut collector = Collector {
+            name,
+            self_is_target: Vec::new(),
+            found: Vec::new(),
         };
+        collector.visit_file(&self.syntax_tree);
+        collector.found
+    }
+
+    pub(crate) fn update_struct_field(
+        &mut self,
+        op: &UpdateStructFieldOp,
+    ) -> Result<ModificationResult> {
+        // Check if this is an enum variant struct literal (contains ::)
+        let is_enum_variant = op.struct_name.contains("::");
+
+        // For enum variant literals, we can't update the enum variant definition
+        // (would need full enum context), so bail with helpful message
+        if is_enum_variant {
+            anyhow::bail!(
+                "Cannot update field in enum variant definition '{}'.\n\
+                 To update fields in enum variant struct literals, use the transform command:\n\
+                 rs-hack transform --node-type struct-literal --name {} --action replace --with <new_pattern> --paths ... --apply",
+                op.struct_name,
+                op.struct_name
+            );
+        }
+
+        // Find the struct and clone it to avoid borrowing issues
+        let item_struct = self.find_struct_def(&op.struct_name, op.in_fn.as_deref())?;
+
+        // Check if the struct matches the where filter (if specified)
+        if let Some(ref where_filter) = op.where_filter
+            && !self.matches_where_filter(&item_struct.attrs, where_filter)?
+        {
+            // Struct doesn't match filter - skip without error
+            return Ok(ModificationResult {
+                changed: false,
+                modified_nodes: vec![],
+                unmatched_qualified_paths: None,
+            });
+        }
+
+        // Create backup of original struct before modification
+        let backup_node = self.struct_def_backup(&item_struct, &op.struct_name);
 
         let modified = self.replace_struct_field(&item_struct, op)?;
 
@@ -466,6 +1305,84 @@ impl RustEditor {
         })
     }
 
+    /// Line up the types of a struct's named fields: the gap after each `:` is padded so
+    /// every type starts one column past the longest `name:`.
+    ///
+    /// Attributes, doc comments, and trailing comments stay where they are. Fields that share
+    /// a line with another field, or whose type doesn't follow the colon on the same line
+    /// after plain spaces, are left alone and don't count toward the column.
+    pub(crate) fn align_struct_fields(
+        &mut self,
+        op: &AlignStructFieldsOp,
+    ) -> Result<ModificationResult> {
+        let item_struct = self.find_struct_def(&op.struct_name, op.in_fn.as_deref())?;
+        let Fields::Named(fields) = &item_struct.fields else {
+            anyhow::bail!("Struct '{}' has no named fields to align", op.struct_name);
+        };
+
+        // (colon end offset, type start offset, width of the line up to the colon)
+        let mut gaps: Vec<(usize, usize, usize)> = Vec::new();
+        for field in &fields.named {
+            let Some(colon) = field.colon_token else {
+                continue;
+            };
+            let colon_end = colon.span.end();
+            let ty_start = field.ty.span().start();
+            let shares_line = fields.named.iter().any(|other| {
+                !std::ptr::eq(other, field)
+                    && other
+                        .colon_token
+                        .is_some_and(|c| c.span.start().line == colon_end.line)
+            });
+            if shares_line || ty_start.line != colon_end.line {
+                continue;
+            }
+            let colon_offset = self.line_column_to_byte_offset(colon_end.line, colon_end.column)?;
+            let ty_offset = self.line_column_to_byte_offset(ty_start.line, ty_start.column)?;
+            if !self.content[colon_offset..ty_offset]
+                .chars()
+                .all(|c| c == ' ')
+            {
+                continue;
+            }
+            let line_start = self.line_offsets[colon_end.line - 1];
+            let width = self.content[line_start..colon_offset].chars().count();
+            gaps.push((colon_offset, ty_offset, width));
+        }
+
+        let Some(column) = gaps.iter().map(|(_, _, width)| width + 1).max() else {
+            return Ok(ModificationResult {
+                changed: false,
+                modified_nodes: vec![],
+                unmatched_qualified_paths: None,
+            });
+        };
+        if gaps
+            .iter()
+            .all(|(colon, ty, width)| ty - colon == column - width)
+        {
+            return Ok(ModificationResult {
+                changed: false,
+                modified_nodes: vec![],
+                unmatched_qualified_paths: None,
+            });
+        }
+
+        let backup_node = self.struct_def_backup(&item_struct, &op.struct_name);
+
+        for (colon, ty, width) in gaps.into_iter().rev() {
+            self.content
+                .replace_range(colon..ty, &" ".repeat(column - width));
+        }
+        self.line_offsets = Self::compute_line_offsets(&self.content);
+
+        Ok(ModificationResult {
+            changed: true,
+            modified_nodes: vec![backup_node],
+            unmatched_qualified_paths: None,
+        })
+    }
+
     fn replace_struct_field(
         &mut self,
         item_struct: &ItemStruct,
@@ -535,24 +1452,15 @@ impl RustEditor {
         // For enum variant literals, automatically operate on literals only
         // (since we can't modify the enum variant definition without the full enum context)
         let effective_literal_only = op.literal_only || is_enum_variant;
+        // A union literal sets exactly one field; dropping that field would leave `U {}`
+        let mut is_union = false;
 
         // Step 1: Remove from struct definition (unless literal_only is true or it's an enum
         // variant)
         if !effective_literal_only {
             // Find the struct and clone it to avoid borrowing issues
-            let item_struct = self
-                .syntax_tree
-                .items
-                .iter()
-                .find_map(|item| {
-                    if let Item::Struct(s) = item
-                        && s.ident == op.struct_name
-                    {
-                        return Some(s.clone());
-                    }
-                    None
-                })
-                .ok_or_else(|| anyhow::anyhow!("Struct '{}' not found", op.struct_name))?;
+            let item_struct = self.find_struct_def(&op.struct_name, op.in_fn.as_deref())?;
+            is_union = self.is_union(&item_struct);
 
             // Check if the struct matches the where filter (if specified)
             if let Some(ref where_filter) = op.where_filter
@@ -567,12 +1475,7 @@ impl RustEditor {
             }
 
             // Create backup of original struct before modification
-            let backup_node = BackupNode {
-                node_type: "struct".to_string(),
-                identifier: op.struct_name.clone(),
-                original_content: self.unparse_item(&Item::Struct(item_struct.clone())),
-                location: self.span_to_location(item_struct.span()),
-            };
+            let backup_node = self.struct_def_backup(&item_struct, &op.struct_name);
 
             if let Fields::Named(ref fields) = item_struct.fields {
                 // Find the field to remove
@@ -644,7 +1547,7 @@ impl RustEditor {
                 let before_field = &self.content[line_start..start];
                 if before_field.trim().is_empty() {
                     // Remove the whole line
-                    self.content.replace_range(line_start..end, "");
+                    self.remove_lines(line_start, end);
                 } else {
                     // Just remove the field and comma
                     self.content.replace_range(start..end, "");
@@ -654,12 +1557,21 @@ impl RustEditor {
                 changed = true;
 
                 // Re-parse the syntax tree after surgical edit
-                self.syntax_tree = syn::parse_str(&self.content)?;
+                self.syntax_tree = self.parse_content()?;
             } else {
                 anyhow::bail!("Struct '{}' does not have named fields", op.struct_name)
             }
         }
 
+        // Literals that set the removed union field have to be rewritten by hand
+        if is_union {
+            return Ok(ModificationResult {
+                changed,
+                modified_nodes,
+                unmatched_qualified_paths: None,
+            });
+        }
+
         // Step 2: Remove from all struct literal expressions using surgical editing
         // Collect backups of struct literals before modification
         let literal_backups = self.collect_struct_literal_backups(&op.struct_name, None);
@@ -812,7 +1724,8 @@ impl RustEditor {
             }
 
             // Re-parse to update syntax_tree
-            self.syntax_tree = syn::parse_str(&self.content)
+            self.syntax_tree = self
+                .parse_content()
                 .context("Failed to re-parse after removing struct literal fields")?;
             self.line_offsets = Self::compute_line_offsets(&self.content);
 
@@ -832,13 +1745,20 @@ impl RustEditor {
         op: &AddStructLiteralFieldOp,
     ) -> Result<ModificationResult> {
         // Parse the field name from field_def (e.g., "return_type: None" -> "return_type")
-        let field_name = op
-            .field_def
+        let (mut field_attrs, field_body) = Self::split_leading_attrs(&op.field_def);
+        let field_name = field_body
             .split(':')
             .next()
             .map(|s| s.trim().to_string())
             .context("Field definition must contain ':'")?;
 
+        // A field that only exists under some cfg must only be initialized under the same cfg,
+        // otherwise literals stop compiling when the cfg is off. (Nested literals can be of any
+        // type, so there's no single definition to consult.)
+        if field_attrs.is_empty() && op.field_path.is_empty() {
+            field_attrs = self.definition_field_cfg_attrs(&op.struct_name, &field_name);
+        }
+
         // Create a path resolver if a canonical path was provided
         let path_resolver = if let Some(struct_path) = &op.struct_path {
             let mut resolver = PathResolver::new(struct_path)
@@ -861,11 +1781,18 @@ impl RustEditor {
         struct LiteralFieldInserter<'a> {
             struct_name: String,
             field_name: String,
+            field_path: &'a [String],
             path_resolver: Option<&'a PathResolver>,
-            insertion_points: Vec<(usize, usize)>, // (byte_offset, indentation_spaces)
+            // The field to add, with `{sibling}` references to fill in from each literal
+            field_body: &'a str,
+            field_refs: &'a [(std::ops::Range<usize>, String)],
+            // (byte_offset, indentation_spaces, field)
+            insertion_points: Vec<(usize, usize, String)>,
+            // Literals missing a referenced field: (line, field)
+            missing_refs: Vec<(usize, String)>,
             unmatched_paths: std::collections::HashMap<String, usize>, /* Collect qualified
-                                                    * paths that didn't
-                                                    * match */
+                                                                        * paths that didn't
+                                                                        * match */
             editor: &'a RustEditor,
         }
 
@@ -941,7 +1868,24 @@ impl RustEditor {
                     }
                 };
 
-                if is_match {
+                // Descend through `field_path` to the nested literal that gets the field
+                let target = if is_match {
+                    self.field_path.iter().try_fold(node, |literal, name| {
+                        literal.fields.iter().find_map(|fv| {
+                            if fv.member.to_token_stream().to_string() != *name {
+                                return None;
+                            }
+                            match &fv.expr {
+                                syn::Expr::Struct(nested) => Some(nested),
+                                _ => None,
+                            }
+                        })
+                    })
+                } else {
+                    None
+                };
+
+                if let Some(node) = target {
                     // Check if field already exists
                     let field_exists = node
                         .fields
@@ -972,7 +1916,46 @@ impl RustEditor {
                             self.editor.get_indentation(struct_start).len() + 4
                         };
 
-                        self.insertion_points.push((insert_offset, indent));
+                        let mut field = self.field_body.to_string();
+                        for (range, sibling) in self.field_refs.iter().rev() {
+                            let Some(value) = node
+                                .fields
+                                .iter()
+                                .find(|fv| fv.member.to_token_stream().to_string() == *sibling)
+                            else {
+                                self.missing_refs
+                                    .push((node.span().start().line, sibling.clone()));
+                                continue;
+                            };
+                            let start = self.editor.span_to_byte_offset(value.expr.span().start());
+                            let end = self.editor.span_to_byte_offset(value.expr.span().end());
+                            let text = &self.editor.content[start..end];
+                            // `{a} * 2` with `a: x + 1` must stay `(x + 1) * 2`
+                            let whole_value = self
+                                .field_body
+                                .split_once(':')
+                                .is_some_and(|(_, v)| v.trim() == &self.field_body[range.clone()]);
+                            let atomic = matches!(
+                                value.expr,
+                                Expr::Lit(_)
+                                    | Expr::Path(_)
+                                    | Expr::Field(_)
+                                    | Expr::MethodCall(_)
+                                    | Expr::Call(_)
+                                    | Expr::Macro(_)
+                                    | Expr::Paren(_)
+                                    | Expr::Index(_)
+                                    | Expr::Tuple(_)
+                                    | Expr::Array(_)
+                                    | Expr::Struct(_)
+                            );
+                            if whole_value || atomic {
+                                field.replace_range(range.clone(), text);
+                            } else {
+                                field.replace_range(range.clone(), &format!("({})", text));
+                            }
+                        }
+                        self.insertion_points.push((insert_offset, indent, field));
                     }
                 }
 
@@ -980,17 +1963,32 @@ impl RustEditor {
             }
         }
 
+        let field_refs = Self::field_value_refs(field_body);
         let mut inserter = LiteralFieldInserter {
             struct_name: op.struct_name.clone(),
             field_name,
+            field_path: &op.field_path,
             path_resolver: path_resolver.as_ref(),
+            field_body,
+            field_refs: &field_refs,
             insertion_points: Vec::new(),
+            missing_refs: Vec::new(),
             unmatched_paths: std::collections::HashMap::new(),
             editor: self,
         };
 
         inserter.visit_file(&self.syntax_tree);
 
+        if let Some((line, sibling)) = inserter.missing_refs.first() {
+            anyhow::bail!(
+                "The {} literal at line {} has no `{}` field to copy into `{{{}}}`",
+                op.struct_name,
+                line,
+                sibling,
+                sibling
+            );
+        }
+
         // Prepare unmatched paths hint (only if using simple name and found unmatched paths)
         let unmatched_hint =
             if !op.struct_name.contains("::") && !inserter.unmatched_paths.is_empty() {
@@ -1010,17 +2008,22 @@ impl RustEditor {
         // Sort insertion points in reverse order so we can insert from end to beginning
         // (this way earlier offsets don't get invalidated by later insertions)
         let mut points = inserter.insertion_points;
-        points.sort_by_key(|(offset, _)| std::cmp::Reverse(*offset));
+        points.sort_by_key(|(offset, _, _)| std::cmp::Reverse(*offset));
 
         // Perform surgical insertions
-        for (insert_offset, indent_spaces) in points {
+        for (insert_offset, indent_spaces, field) in points {
             let indent = " ".repeat(indent_spaces);
-            let field_str = format!(",\n{}{}", indent, op.field_def);
+            let mut field_str = String::from(",");
+            for attr in &field_attrs {
+                field_str.push_str(&format!("\n{}{}", indent, attr));
+            }
+            field_str.push_str(&format!("\n{}{}", indent, field));
             self.content.insert_str(insert_offset, &field_str);
         }
 
         // Re-parse to update syntax_tree
-        self.syntax_tree = syn::parse_str(&self.content)
+        self.syntax_tree = self
+            .parse_content()
             .context("Failed to re-parse after adding struct literal fields")?;
         self.line_offsets = Self::compute_line_offsets(&self.content);
 
@@ -1247,7 +2250,8 @@ impl RustEditor {
         }
 
         // Re-parse to update syntax_tree
-        self.syntax_tree = syn::parse_str(&self.content)
+        self.syntax_tree = self
+            .parse_content()
             .context("Failed to re-parse after adding struct literal base")?;
         self.line_offsets = Self::compute_line_offsets(&self.content);
 
@@ -1376,19 +2380,7 @@ impl RustEditor {
 
     pub(crate) fn add_enum_variant(&mut self, op: &AddEnumVariantOp) -> Result<ModificationResult> {
         // Find the enum and clone it to avoid borrowing issues
-        let item_enum = self
-            .syntax_tree
-            .items
-            .iter()
-            .find_map(|item| {
-                if let Item::Enum(e) = item
-                    && e.ident == op.enum_name
-                {
-                    return Some(e.clone());
-                }
-                None
-            })
-            .ok_or_else(|| anyhow::anyhow!("Enum '{}' not found", op.enum_name))?;
+        let item_enum = self.find_enum_def(&op.enum_name, op.in_fn.as_deref())?;
 
         // Check if the enum matches the where filter (if specified)
         if let Some(ref where_filter) = op.where_filter
@@ -1406,7 +2398,7 @@ impl RustEditor {
         let backup_node = BackupNode {
             node_type: "enum".to_string(),
             identifier: op.enum_name.clone(),
-            original_content: self.unparse_item(&Item::Enum(item_enum.clone())),
+            original_content: self.backup_item(&Item::Enum(item_enum.clone())),
             location: self.span_to_location(item_enum.span()),
         };
 
@@ -1488,19 +2480,7 @@ impl RustEditor {
 
     fn update_enum_variant(&mut self, op: &UpdateEnumVariantOp) -> Result<ModificationResult> {
         // Find the enum and clone it
-        let item_enum = self
-            .syntax_tree
-            .items
-            .iter()
-            .find_map(|item| {
-                if let Item::Enum(e) = item
-                    && e.ident == op.enum_name
-                {
-                    return Some(e.clone());
-                }
-                None
-            })
-            .ok_or_else(|| anyhow::anyhow!("Enum '{}' not found", op.enum_name))?;
+        let item_enum = self.find_enum_def(&op.enum_name, op.in_fn.as_deref())?;
 
         // Check if the enum matches the where filter (if specified)
         if let Some(ref where_filter) = op.where_filter
@@ -1518,7 +2498,7 @@ impl RustEditor {
         let backup_node = BackupNode {
             node_type: "enum".to_string(),
             identifier: op.enum_name.clone(),
-            original_content: self.unparse_item(&Item::Enum(item_enum.clone())),
+            original_content: self.backup_item(&Item::Enum(item_enum.clone())),
             location: self.span_to_location(item_enum.span()),
         };
 
@@ -1568,19 +2548,7 @@ impl RustEditor {
         op: &RemoveEnumVariantOp,
     ) -> Result<ModificationResult> {
         // Find the enum
-        let item_enum = self
-            .syntax_tree
-            .items
-            .iter()
-            .find_map(|item| {
-                if let Item::Enum(e) = item
-                    && e.ident == op.enum_name
-                {
-                    return Some(e.clone());
-                }
-                None
-            })
-            .ok_or_else(|| anyhow::anyhow!("Enum '{}' not found", op.enum_name))?;
+        let item_enum = self.find_enum_def(&op.enum_name, op.in_fn.as_deref())?;
 
         // Check if the enum matches the where filter (if specified)
         if let Some(ref where_filter) = op.where_filter
@@ -1598,7 +2566,7 @@ impl RustEditor {
         let backup_node = BackupNode {
             node_type: "enum".to_string(),
             identifier: op.enum_name.clone(),
-            original_content: self.unparse_item(&Item::Enum(item_enum.clone())),
+            original_content: self.backup_item(&Item::Enum(item_enum.clone())),
             location: self.span_to_location(item_enum.span()),
         };
 
@@ -1646,7 +2614,7 @@ impl RustEditor {
 
         let before_variant = &self.content[line_start..start];
         if before_variant.trim().is_empty() {
-            self.content.replace_range(line_start..end, "");
+            self.remove_lines(line_start, end);
         } else {
             self.content.replace_range(start..end, "");
         }
@@ -1746,6 +2714,43 @@ impl RustEditor {
         prettyplease::unparse(&temp_file).trim().to_string()
     }
 
+    /// `unparse_item` for a backup of `item` as it is in the file: under a scope, code in it
+    /// may be pruned from the syntax tree, and the backup needs that too.
+    fn backup_item(&self, item: &Item) -> String {
+        use syn::visit::Visit;
+
+        struct ItemAt<'ast> {
+            start: LineColumn,
+            found: Option<&'ast Item>,
+        }
+
+        impl<'ast> Visit<'ast> for ItemAt<'ast> {
+            fn visit_item(&mut self, node: &'ast Item) {
+                if self.found.is_some() {
+                    return;
+                }
+                if node.span().start() == self.start {
+                    self.found = Some(node);
+                } else {
+                    syn::visit::visit_item(self, node);
+                }
+            }
+        }
+
+        if self.scope.is_empty() {
+            return self.unparse_item(item);
+        }
+        let Ok(file) = syn::parse_str::<File>(&self.content) else {
+            return self.unparse_item(item);
+        };
+        let mut at = ItemAt {
+            start: item.span().start(),
+            found: None,
+        };
+        at.visit_file(&file);
+        self.unparse_item(at.found.unwrap_or(item))
+    }
+
     /// Isolated prettyplease: Reformat only a specific item, preserving the rest of the file
     /// This finds an item in the original syntax tree, formats it with prettyplease, and surgically
     /// replaces it
@@ -1755,8 +2760,9 @@ impl RustEditor {
         F: Fn(&Item) -> bool,
     {
         // Find the item index in the ORIGINAL syntax tree (before mutations)
-        let original_syntax_tree: syn::File =
-            syn::parse_str(&self.content).context("Failed to parse original content")?;
+        let original_syntax_tree: syn::File = self
+            .parse_content()
+            .context("Failed to parse original content")?;
 
         let (item_index, original_item) = original_syntax_tree
             .items
@@ -1782,7 +2788,8 @@ impl RustEditor {
         self.content.replace_range(start..end, &formatted_item);
 
         // Re-parse to update syntax_tree
-        self.syntax_tree = syn::parse_str(&self.content)
+        self.syntax_tree = self
+            .parse_content()
             .context("Failed to re-parse after isolated prettyplease")?;
         self.line_offsets = Self::compute_line_offsets(&self.content);
 
@@ -1791,14 +2798,14 @@ impl RustEditor {
 
     /// Get backup of a function before modification
     fn get_function_backup(&self, fn_name: &str) -> Result<BackupNode> {
-        for item in &self.syntax_tree.items {
+        for item in self.scoped_items() {
             if let Item::Fn(f) = item
                 && f.sig.ident == fn_name
             {
                 return Ok(BackupNode {
                     node_type: "function".to_string(),
                     identifier: fn_name.to_string(),
-                    original_content: self.unparse_item(&Item::Fn(f.clone())),
+                    original_content: self.backup_item(&Item::Fn(f.clone())),
                     location: self.span_to_location(f.span()),
                 });
             }
@@ -1915,7 +2922,7 @@ impl RustEditor {
 
     fn find_enum_variants(&self, enum_name: &str) -> Result<Vec<String>> {
         // Find the enum in the syntax tree
-        for item in &self.syntax_tree.items {
+        for item in self.scoped_items() {
             if let Item::Enum(e) = item
                 && e.ident == enum_name
             {
@@ -2062,49 +3069,275 @@ impl RustEditor {
         }
     }
 
-    pub(crate) fn add_impl_method(&mut self, op: &AddImplMethodOp) -> Result<ModificationResult> {
-        // Parse the method definition
-        let method_code = format!("impl Dummy {{ {} }}", op.method_def);
-        let dummy: syn::ItemImpl =
-            parse_str(&method_code).context("Failed to parse method definition")?;
+    /// Reconcile every match over `op.enum_name` with the enum's current variants. Arms for
+    /// missing variants are appended surgically; arms whose patterns only reference variants
+    /// that no longer exist are removed when `op.prune` is set.
+    pub(crate) fn sync_match_arms(&mut self, op: &SyncMatchArmsOp) -> Result<ModificationResult> {
+        use syn::visit::Visit;
 
-        let new_method = dummy
-            .items
-            .first()
-            .context("No method found in definition")?
-            .clone();
+        use crate::commands::match_audit::{arm_has_wildcard, collect_enum_variants_from_pat};
 
-        // Get the method name for idempotency check
-        let method_name = match &new_method {
-            syn::ImplItem::Fn(f) => f.sig.ident.to_string(),
-            _ => anyhow::bail!("Only method definitions are supported"),
+        let variants = if op.variants.is_empty() {
+            self.find_enum_variants(&op.enum_name)?
+        } else {
+            op.variants.clone()
         };
 
-        // Find the impl block
-        let impl_index = self
-            .syntax_tree
-            .items
-            .iter()
-            .position(|item| {
-                if let Item::Impl(impl_block) = item {
-                    // Check if this is the right impl block
-                    if let syn::Type::Path(type_path) = &*impl_block.self_ty
-                        && let Some(segment) = type_path.path.segments.last()
-                    {
-                        return segment.ident == op.target;
-                    }
-                }
-                false
-            })
-            .ok_or_else(|| anyhow::anyhow!("impl block for '{}' not found", op.target))?;
+        if variants.is_empty() {
+            anyhow::bail!("Enum '{}' not found or has no variants", op.enum_name);
+        }
 
-        // Check if method already exists (idempotent)
-        let impl_block = match &self.syntax_tree.items[impl_index] {
-            Item::Impl(i) => i,
-            _ => unreachable!(),
-        };
+        // Validate the body up front so a bad expression doesn't produce broken code
+        parse_str::<Expr>(&op.body)
+            .with_context(|| format!("Failed to parse match arm body: {}", op.body))?;
 
-        let method_exists = impl_block.items.iter().any(|item| {
+        struct SyncCollector<'a> {
+            op: &'a SyncMatchArmsOp,
+            variants: &'a [String],
+            editor: &'a RustEditor,
+            fn_stack: Vec<String>,
+            top_level_fn: Option<String>,
+            // (start, end, replacement) - insertions have start == end
+            edits: Vec<(usize, usize, String)>,
+            touched_fns: Vec<String>,
+        }
+
+        impl SyncCollector<'_> {
+            fn arm_removal_range(&self, arm: &Arm) -> (usize, usize) {
+                let content = self.editor.content.as_bytes();
+                let start = self.editor.span_to_byte_offset(arm.span().start());
+                let mut end = self.editor.span_to_byte_offset(arm.span().end());
+
+                // Trailing comma is part of the arm span only when syn saw it
+                while end < content.len() && matches!(content[end], b' ' | b'\t') {
+                    end += 1;
+                }
+                if end < content.len() && content[end] == b',' {
+                    end += 1;
+                }
+
+                let mut line_start = start;
+                while line_start > 0 && matches!(content[line_start - 1], b' ' | b'\t') {
+                    line_start -= 1;
+                }
+                let mut line_end = end;
+                while line_end < content.len() && matches!(content[line_end], b' ' | b'\t') {
+                    line_end += 1;
+                }
+
+                // Remove the whole line when the arm sits on its own line
+                if (line_start == 0 || content[line_start - 1] == b'\n')
+                    && line_end < content.len()
+                    && content[line_end] == b'\n'
+                {
+                    (line_start, line_end + 1)
+                } else {
+                    (start, end)
+                }
+            }
+        }
+
+        impl<'ast> Visit<'ast> for SyncCollector<'_> {
+            fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+                let is_top_level = self.fn_stack.is_empty();
+                if is_top_level {
+                    self.top_level_fn = Some(node.sig.ident.to_string());
+                }
+                self.fn_stack.push(node.sig.ident.to_string());
+                syn::visit::visit_item_fn(self, node);
+                self.fn_stack.pop();
+                if is_top_level {
+                    self.top_level_fn = None;
+                }
+            }
+
+            fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+                self.fn_stack.push(node.sig.ident.to_string());
+                syn::visit::visit_impl_item_fn(self, node);
+                self.fn_stack.pop();
+            }
+
+            fn visit_expr_match(&mut self, node: &'ast ExprMatch) {
+                syn::visit::visit_expr_match(self, node);
+
+                if let Some(ref target) = self.op.function_name
+                    && self.fn_stack.last() != Some(target)
+                {
+                    return;
+                }
+
+                let mut seen = Vec::new();
+                let mut has_wildcard = false;
+                let mut stale_arms = Vec::new();
+
+                for (idx, arm) in node.arms.iter().enumerate() {
+                    has_wildcard |= arm_has_wildcard(&arm.pat);
+
+                    let mut referenced = Vec::new();
+                    collect_enum_variants_from_pat(&arm.pat, &self.op.enum_name, &mut referenced);
+                    if !referenced.is_empty()
+                        && referenced.iter().all(|v| !self.variants.contains(v))
+                    {
+                        stale_arms.push(idx);
+                    }
+                    seen.extend(referenced);
+                }
+
+                // Not a match over our enum
+                if seen.is_empty() {
+                    return;
+                }
+
+                let missing: Vec<&String> = if has_wildcard {
+                    Vec::new()
+                } else {
+                    self.variants.iter().filter(|v| !seen.contains(v)).collect()
+                };
+
+                let removed: Vec<usize> = if self.op.prune {
+                    stale_arms
+                } else {
+                    Vec::new()
+                };
+
+                if missing.is_empty() && removed.is_empty() {
+                    return;
+                }
+
+                if !missing.is_empty() {
+                    let anchor = node
+                        .arms
+                        .iter()
+                        .enumerate()
+                        .rev()
+                        .find(|(idx, _)| !removed.contains(idx))
+                        .map(|(_, arm)| arm);
+
+                    let (offset, indent, needs_comma) = if let Some(arm) = anchor {
+                        let end = self.editor.span_to_byte_offset(arm.span().end());
+                        let start = self.editor.span_to_byte_offset(arm.span().start());
+                        let needs_comma = arm.comma.is_none()
+                            && !matches!(*arm.body, Expr::Block(_))
+                            && !self.editor.content[end..].trim_start().starts_with(',');
+                        (end, self.editor.get_indentation(start), needs_comma)
+                    } else {
+                        let open = self
+                            .editor
+                            .span_to_byte_offset(node.brace_token.span.join().start());
+                        let match_start = self
+                            .editor
+                            .span_to_byte_offset(node.match_token.span.start());
+                        let indent = format!("{}    ", self.editor.get_indentation(match_start));
+                        (open + 1, indent, false)
+                    };
+
+                    let mut text = String::new();
+                    if needs_comma {
+                        text.push(',');
+                    }
+                    for variant in missing {
+                        text.push_str(&format!(
+                            "\n{}{}::{} => {},",
+                            indent, self.op.enum_name, variant, self.op.body
+                        ));
+                    }
+                    self.edits.push((offset, offset, text));
+                }
+
+                for idx in removed {
+                    let (start, end) = self.arm_removal_range(&node.arms[idx]);
+                    self.edits.push((start, end, String::new()));
+                }
+
+                if let Some(ref name) = self.top_level_fn
+                    && !self.touched_fns.contains(name)
+                {
+                    self.touched_fns.push(name.clone());
+                }
+            }
+        }
+
+        let mut collector = SyncCollector {
+            op,
+            variants: &variants,
+            editor: self,
+            fn_stack: Vec::new(),
+            top_level_fn: None,
+            edits: Vec::new(),
+            touched_fns: Vec::new(),
+        };
+        collector.visit_file(&self.syntax_tree);
+
+        if collector.edits.is_empty() {
+            return Ok(ModificationResult {
+                changed: false,
+                modified_nodes: vec![],
+                unmatched_qualified_paths: None,
+            });
+        }
+
+        let modified_nodes = collector
+            .touched_fns
+            .iter()
+            .filter_map(|name| self.get_function_backup(name).ok())
+            .collect();
+
+        // Apply from the end of the file backwards so earlier offsets stay valid
+        let mut edits = collector.edits;
+        edits.sort_by_key(|(start, _, _)| std::cmp::Reverse(*start));
+        for (start, end, text) in edits {
+            self.content.replace_range(start..end, &text);
+        }
+
+        self.syntax_tree = self
+            .parse_content()
+            .context("Failed to re-parse after synchronizing match arms")?;
+        self.line_offsets = Self::compute_line_offsets(&self.content);
+
+        Ok(ModificationResult {
+            changed: true,
+            modified_nodes,
+            unmatched_qualified_paths: None,
+        })
+    }
+
+    pub(crate) fn add_impl_method(&mut self, op: &AddImplMethodOp) -> Result<ModificationResult> {
+        // Parse the method definition
+        let method_code = format!("impl Dummy {{ {} }}", op.method_def);
+        let dummy: syn::ItemImpl =
+            parse_str(&method_code).context("Failed to parse method definition")?;
+
+        let new_method = dummy
+            .items
+            .first()
+            .context("No method found in definition")?
+            .clone();
+
+        // Get the method name for idempotency check
+        let method_name = match &new_method {
+            syn::ImplItem::Fn(f) => f.sig.ident.to_string(),
+            _ => anyhow::bail!("Only method definitions are supported"),
+        };
+
+        // `target` may also be written as `impl Trait for Type`
+        let (target, trait_name) = match op
+            .target
+            .strip_prefix("impl ")
+            .and_then(|rest| rest.split_once(" for "))
+        {
+            Some((trait_name, target)) => (target.trim(), Some(trait_name.trim())),
+            None => (op.target.as_str(), None),
+        };
+        let trait_name = op.trait_name.as_deref().or(trait_name);
+        let impl_path = self.find_impl_for_method(target, trait_name)?;
+
+        // Check if method already exists (idempotent)
+        let impl_block = match self.item_at(&impl_path) {
+            Item::Impl(i) => i,
+            _ => unreachable!(),
+        };
+
+        let method_exists = impl_block.items.iter().any(|item| {
             if let syn::ImplItem::Fn(f) = item {
                 f.sig.ident == method_name
             } else {
@@ -2124,7 +3357,7 @@ impl RustEditor {
         let backup_node = BackupNode {
             node_type: "ItemImpl".to_string(),
             identifier: op.target.clone(),
-            original_content: self.unparse_item(&self.syntax_tree.items[impl_index].clone()),
+            original_content: self.backup_item(self.item_at(&impl_path)),
             location: self.span_to_location(impl_block.span()),
         };
 
@@ -2132,7 +3365,7 @@ impl RustEditor {
         let impl_span = impl_block.span();
 
         // Add the method to the impl block
-        match &mut self.syntax_tree.items[impl_index] {
+        match self.item_at_mut(&impl_path) {
             Item::Impl(impl_block) => {
                 // Add based on position
                 match &op.position {
@@ -2176,7 +3409,176 @@ impl RustEditor {
         }
 
         // Use prettyplease to format just this impl block
-        self.replace_formatted_item(impl_index, impl_span)?;
+        self.replace_formatted_item(&impl_path, impl_span, op.edit_mode)?;
+
+        Ok(ModificationResult {
+            changed: true,
+            modified_nodes: vec![backup_node],
+            unmatched_qualified_paths: None,
+        })
+    }
+
+    /// Index path of the impl block of `target` a new method goes into: the one of
+    /// `trait_name` when given, otherwise the (first) inherent impl. Errors when there is
+    /// none, or when several impls of the trait match (e.g. `From<u8>` and `From<u16>` for
+    /// `--trait From`).
+    fn find_impl_for_method(&self, target: &str, trait_name: Option<&str>) -> Result<Vec<usize>> {
+        let candidates = self
+            .item_paths()
+            .into_iter()
+            .filter_map(|(path, module, item)| match item {
+                Item::Impl(impl_block) if Self::impl_of_type(impl_block, target) => {
+                    Some(((path, impl_block), module))
+                }
+                _ => None,
+            })
+            .collect();
+        let impls: Vec<(Vec<usize>, &syn::ItemImpl)> =
+            self.in_scope("impl block for", target, candidates)?;
+        let header = |impl_block: &syn::ItemImpl| {
+            let (_, path, _) = impl_block.trait_.as_ref()?;
+            Some(format!(
+                "`impl {} for {}` (line {})",
+                path.to_token_stream().to_string().replace(' ', ""),
+                target,
+                impl_block.span().start().line
+            ))
+        };
+
+        let Some(trait_name) = trait_name else {
+            if let Some((path, _)) = impls.iter().find(|(_, i)| i.trait_.is_none()) {
+                return Ok(path.clone());
+            }
+            let trait_impls: Vec<String> = impls.iter().filter_map(|(_, i)| header(i)).collect();
+            if trait_impls.is_empty() {
+                anyhow::bail!("impl block for '{}' not found", target);
+            }
+            anyhow::bail!(
+                "'{}' has no inherent impl block, only {}; pass --trait to add the method to one of them",
+                target,
+                trait_impls.join(", ")
+            );
+        };
+
+        let matching: Vec<&(Vec<usize>, &syn::ItemImpl)> = impls
+            .iter()
+            .filter(|(_, i)| Self::impl_of_trait(i, trait_name))
+            .collect();
+        match matching.as_slice() {
+            [] => anyhow::bail!("impl block of '{}' for '{}' not found", trait_name, target),
+            [(path, _)] => Ok(path.clone()),
+            several => anyhow::bail!(
+                "--trait {} is ambiguous for '{}': {}; give the trait's generic arguments too",
+                trait_name,
+                target,
+                several
+                    .iter()
+                    .filter_map(|(_, i)| header(i))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// Whether `impl_block` is for the type `target` (compared by last path segment).
+    fn impl_of_type(impl_block: &syn::ItemImpl, target: &str) -> bool {
+        matches!(
+            &*impl_block.self_ty,
+            syn::Type::Path(type_path)
+                if type_path.path.segments.last().is_some_and(|seg| seg.ident == target)
+        )
+    }
+
+    /// Whether `impl_block` implements `trait_name`. The last path segment is compared, so
+    /// `Display` and `std::fmt::Display` both match; generic arguments (`From<u8>`) only
+    /// count when given.
+    fn impl_of_trait(impl_block: &syn::ItemImpl, trait_name: &str) -> bool {
+        let Some((_, path, _)) = &impl_block.trait_ else {
+            return false;
+        };
+        let Some(segment) = path.segments.last() else {
+            return false;
+        };
+        let Ok(wanted) = parse_str::<syn::Path>(trait_name) else {
+            let last = trait_name.rsplit("::").next().unwrap_or(trait_name);
+            return segment.ident == last.trim();
+        };
+        let Some(wanted) = wanted.segments.last() else {
+            return false;
+        };
+        segment.ident == wanted.ident
+            && (wanted.arguments.is_none()
+                || segment.arguments.to_token_stream().to_string()
+                    == wanted.arguments.to_token_stream().to_string())
+    }
+
+    /// Remove a method from every impl block of `op.target` (or only from
+    /// `impl <trait_name> for <target>`), along with its doc comments and the blank line
+    /// after it. The rest of the file is left byte-for-byte as it was.
+    pub(crate) fn remove_impl_method(
+        &mut self,
+        op: &RemoveImplMethodOp,
+    ) -> Result<ModificationResult> {
+        let mut found_impl = false;
+        let mut ranges = Vec::new();
+        for item in self.scoped_items() {
+            let Item::Impl(impl_block) = item else {
+                continue;
+            };
+            let trait_matches = op
+                .trait_name
+                .as_deref()
+                .is_none_or(|name| Self::impl_of_trait(impl_block, name));
+            if !Self::impl_of_type(impl_block, &op.target) || !trait_matches {
+                continue;
+            }
+            found_impl = true;
+
+            for impl_item in &impl_block.items {
+                if let syn::ImplItem::Fn(f) = impl_item
+                    && f.sig.ident == op.method_name
+                {
+                    // The span of an impl item covers its attributes, doc comments included
+                    let start = self.span_to_byte_offset(f.span().start());
+                    let end = self.span_to_byte_offset(f.span().end());
+                    ranges.push(self.whole_lines(start, end));
+                }
+            }
+        }
+
+        if !found_impl {
+            match &op.trait_name {
+                Some(trait_name) => anyhow::bail!(
+                    "impl block of '{}' for '{}' not found",
+                    trait_name,
+                    op.target
+                ),
+                None => anyhow::bail!("impl block for '{}' not found", op.target),
+            }
+        }
+        if ranges.is_empty() {
+            return Ok(ModificationResult {
+                changed: false,
+                modified_nodes: vec![],
+                unmatched_qualified_paths: None,
+            });
+        }
+
+        // Impl blocks can't be told apart by name on revert, so back up the whole file
+        let backup_node = BackupNode {
+            node_type: "file".to_string(),
+            identifier: format!("{}::{}", op.target, op.method_name),
+            original_content: self.content.clone(),
+            location: self.span_to_location(self.syntax_tree.span()),
+        };
+
+        for (start, end) in ranges.into_iter().rev() {
+            self.content.replace_range(start..end, "");
+        }
+        self.syntax_tree = self
+            .parse_content()
+            .context("Failed to re-parse after removing impl method")?;
+        self.line_offsets = Self::compute_line_offsets(&self.content);
 
         Ok(ModificationResult {
             changed: true,
@@ -2185,6 +3587,42 @@ impl RustEditor {
         })
     }
 
+    /// Widen `start..end` to the full lines it sits on, plus one blank line next to it: the
+    /// one after, or failing that the one before (so removing the last item of a block
+    /// doesn't leave a gap above the closing brace). Bounds that share their line with other
+    /// code are left where they are.
+    fn whole_lines(&self, start: usize, end: usize) -> (usize, usize) {
+        let content = self.content.as_str();
+        let line_start = content[..start].rfind('\n').map_or(0, |i| i + 1);
+        let start = if content[line_start..start].trim().is_empty() {
+            line_start
+        } else {
+            start
+        };
+        let line_end = content[end..]
+            .find('\n')
+            .map_or(content.len(), |i| end + i + 1);
+        let end = if content[end..line_end].trim().is_empty() {
+            line_end
+        } else {
+            return (start, end);
+        };
+
+        let next_end = content[end..]
+            .find('\n')
+            .map_or(content.len(), |i| end + i + 1);
+        if end < content.len() && content[end..next_end].trim().is_empty() {
+            return (start, next_end);
+        }
+        if start > 0 {
+            let prev_start = content[..start - 1].rfind('\n').map_or(0, |i| i + 1);
+            if content[prev_start..start].trim().is_empty() {
+                return (prev_start, end);
+            }
+        }
+        (start, end)
+    }
+
     pub(crate) fn add_use_statement(
         &mut self,
         op: &AddUseStatementOp,
@@ -2314,35 +3752,110 @@ impl RustEditor {
         })
     }
 
-    pub(crate) fn add_derive(&mut self, op: &AddDeriveOp) -> Result<ModificationResult> {
-        // Find the target item (struct or enum)
-        let item_index = self
-            .syntax_tree
-            .items
-            .iter()
-            .position(|item| match (&op.target_type as &str, item) {
-                ("struct", Item::Struct(s)) => s.ident == op.target_name,
-                ("enum", Item::Enum(e)) => e.ident == op.target_name,
-                _ => false,
-            })
-            .ok_or_else(|| anyhow::anyhow!("{} '{}' not found", op.target_type, op.target_name))?;
+    /// Remove the imports `commands::unused_imports::analyze` finds unused. A `use` item left
+    /// with nothing goes with its line; otherwise just the unused members of its groups do,
+    /// and a group down to one member loses its braces.
+    pub(crate) fn remove_unused_imports(
+        &mut self,
+        op: &RemoveUnusedImportsOp,
+    ) -> Result<ModificationResult> {
+        use std::collections::HashSet;
+        use std::ops::Range;
+
+        use crate::commands::unused_imports::{analyze, checked_use_items, use_leaves};
+
+        enum Prune {
+            Keep,
+            All,
+            Edits(Vec<(Range<usize>, String)>),
+        }
+
+        fn prune(
+            editor: &RustEditor,
+            tree: &syn::UseTree,
+            parent: Option<&syn::Ident>,
+            unused: &HashSet<String>,
+        ) -> Prune {
+            let is_unused = |name: Option<String>| name.is_some_and(|n| unused.contains(&n));
+            let range = |span: Span| {
+                editor.span_to_byte_offset(span.start())..editor.span_to_byte_offset(span.end())
+            };
+            match tree {
+                syn::UseTree::Path(p) => prune(editor, &p.tree, Some(&p.ident), unused),
+                syn::UseTree::Name(n) => {
+                    let name = if n.ident == "self" {
+                        parent.map(ToString::to_string)
+                    } else {
+                        Some(n.ident.to_string())
+                    };
+                    if is_unused(name) {
+                        Prune::All
+                    } else {
+                        Prune::Keep
+                    }
+                }
+                syn::UseTree::Rename(r)
+                    if r.rename != "_" && is_unused(Some(r.rename.to_string())) =>
+                {
+                    Prune::All
+                }
+                syn::UseTree::Rename(_) | syn::UseTree::Glob(_) => Prune::Keep,
+                syn::UseTree::Group(g) => {
+                    let items: Vec<&syn::UseTree> = g.items.iter().collect();
+                    let pruned: Vec<Prune> = items
+                        .iter()
+                        .map(|item| prune(editor, item, parent, unused))
+                        .collect();
+                    let kept: Vec<usize> = (0..items.len())
+                        .filter(|&i| !matches!(pruned[i], Prune::All))
+                        .collect();
+                    let Some(&last_kept) = kept.last() else {
+                        return Prune::All;
+                    };
+                    if pruned.iter().all(|p| matches!(p, Prune::Keep)) {
+                        return Prune::Keep;
+                    }
 
-        // Get the item and check for existing derives
-        let (existing_derives, item_span, item_attrs) = match &self.syntax_tree.items[item_index] {
-            Item::Struct(s) => (Self::extract_derives(&s.attrs), s.span(), &s.attrs),
-            Item::Enum(e) => (Self::extract_derives(&e.attrs), e.span(), &e.attrs),
-            _ => (
-                Vec::new(),
-                proc_macro2::Span::call_site(),
-                &Vec::new() as &Vec<syn::Attribute>,
-            ),
-        };
+                    let is_self = |tree: &syn::UseTree| matches!(tree, syn::UseTree::Name(n) if n.ident == "self");
+                    if let [only] = kept[..]
+                        && matches!(pruned[only], Prune::Keep)
+                        && !is_self(items[only])
+                    {
+                        let braces =
+                            g.brace_token.span.open().start()..g.brace_token.span.close().end();
+                        let text = editor.content[range(items[only].span())].to_string();
+                        let start = editor.span_to_byte_offset(braces.start);
+                        let end = editor.span_to_byte_offset(braces.end);
+                        return Prune::Edits(vec![(start..end, text)]);
+                    }
 
-        // Check if the item matches the where filter (if specified)
-        if let Some(ref where_filter) = op.where_filter
-            && !self.matches_where_filter(item_attrs, where_filter)?
-        {
-            // Item doesn't match filter - skip without error
+                    let mut edits = Vec::new();
+                    for (i, pruned) in pruned.into_iter().enumerate() {
+                        match pruned {
+                            Prune::Edits(nested) => edits.extend(nested),
+                            // Up to the next member, taking the comma and spacing with it
+                            Prune::All if i < last_kept => edits.push((
+                                range(items[i].span()).start..range(items[i + 1].span()).start,
+                                String::new(),
+                            )),
+                            Prune::All | Prune::Keep => {}
+                        }
+                    }
+                    // Members after the last one kept go from the end of that one
+                    if last_kept + 1 < items.len() {
+                        edits.push((
+                            range(items[last_kept].span()).end
+                                ..range(items[items.len() - 1].span()).end,
+                            String::new(),
+                        ));
+                    }
+                    Prune::Edits(edits)
+                }
+            }
+        }
+
+        let unused = analyze(&self.syntax_tree, &op.traits).unused;
+        if unused.is_empty() {
             return Ok(ModificationResult {
                 changed: false,
                 modified_nodes: vec![],
@@ -2350,19 +3863,154 @@ impl RustEditor {
             });
         }
 
-        // Create backup of original item before modification
-        let backup_node = BackupNode {
-            node_type: if op.target_type == "struct" {
-                "struct"
-            } else {
-                "enum"
+        let mut edits = Vec::new();
+        let mut removed_items: Vec<Range<usize>> = Vec::new();
+        let mut modified_nodes = Vec::new();
+        for item in checked_use_items(&self.syntax_tree) {
+            let mut leaves = Vec::new();
+            use_leaves(&item.tree, &mut Vec::new(), None, &mut leaves);
+            let start = self.span_to_byte_offset(item.span().start());
+            let end = self.span_to_byte_offset(item.span().end());
+            for leaf in leaves {
+                if leaf.name.is_some_and(|name| unused.contains(&name)) {
+                    modified_nodes.push(BackupNode {
+                        node_type: "ItemUse".to_string(),
+                        identifier: leaf.path,
+                        original_content: self.content[start..end].to_string(),
+                        location: self.span_to_location(leaf.span),
+                    });
+                }
             }
-            .to_string(),
-            identifier: op.target_name.clone(),
-            original_content: self.unparse_item(&self.syntax_tree.items[item_index].clone()),
-            location: self.span_to_location(item_span),
-        };
-
+            match prune(self, &item.tree, None, &unused) {
+                Prune::All => removed_items.push(start..end),
+                Prune::Edits(nested) => edits.extend(nested),
+                Prune::Keep => {}
+            }
+        }
+        if modified_nodes.is_empty() {
+            return Ok(ModificationResult {
+                changed: false,
+                modified_nodes: vec![],
+                unmatched_qualified_paths: None,
+            });
+        }
+
+        // Whole items go with their lines. Neighbouring ones are merged first so that a
+        // block of imports removed together also takes one blank line next to it.
+        let content = self.content.as_str();
+        let line_of = |at: usize| {
+            let start = content[..at].rfind('\n').map_or(0, |i| i + 1);
+            let end = content[at..]
+                .find('\n')
+                .map_or(content.len(), |i| at + i + 1);
+            start..end
+        };
+        let mut merged: Vec<Range<usize>> = Vec::new();
+        removed_items.sort_by_key(|r| r.start);
+        for Range { mut start, mut end } in removed_items {
+            if content[line_of(start).start..start].trim().is_empty() {
+                start = line_of(start).start;
+            }
+            if end < content.len() && content[end..line_of(end).end].trim().is_empty() {
+                end = line_of(end).end;
+            }
+            match merged.last_mut() {
+                Some(last) if last.end == start => {
+                    last.end = end;
+                }
+                _ => merged.push(start..end),
+            }
+        }
+        for range in merged {
+            let blank_before =
+                range.start == 0 || content[line_of(range.start - 1)].trim().is_empty();
+            let blank_after =
+                range.end < content.len() && content[line_of(range.end)].trim().is_empty();
+            let end = if blank_before && blank_after {
+                line_of(range.end).end
+            } else {
+                range.end
+            };
+            edits.push((range.start..end, String::new()));
+        }
+
+        modified_nodes.insert(
+            0,
+            BackupNode {
+                node_type: "file".to_string(),
+                identifier: "unused imports".to_string(),
+                original_content: self.content.clone(),
+                location: NodeLocation {
+                    line: 0,
+                    column: 0,
+                    end_line: 0,
+                    end_column: 0,
+                },
+            },
+        );
+
+        edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+        for (range, text) in edits {
+            self.content.replace_range(range, &text);
+        }
+        self.syntax_tree = self
+            .parse_content()
+            .context("Failed to re-parse after removing unused imports")?;
+        self.line_offsets = Self::compute_line_offsets(&self.content);
+
+        Ok(ModificationResult {
+            changed: true,
+            modified_nodes,
+            unmatched_qualified_paths: None,
+        })
+    }
+
+    pub(crate) fn add_derive(&mut self, op: &AddDeriveOp) -> Result<ModificationResult> {
+        // Find the target item (struct or enum)
+        let item_path = self.locate_item(&op.target_type, &op.target_name, |item| {
+            match (&op.target_type as &str, item) {
+                ("struct", Item::Struct(s)) => s.ident == op.target_name,
+                ("enum", Item::Enum(e)) => e.ident == op.target_name,
+                _ => false,
+            }
+        })?;
+
+        // Get the item and check for existing derives
+        let (existing_derives, item_span, item_attrs) = match self.item_at(&item_path) {
+            Item::Struct(s) => (Self::extract_derives(&s.attrs), s.span(), &s.attrs),
+            Item::Enum(e) => (Self::extract_derives(&e.attrs), e.span(), &e.attrs),
+            _ => (
+                Vec::new(),
+                proc_macro2::Span::call_site(),
+                &Vec::new() as &Vec<syn::Attribute>,
+            ),
+        };
+
+        // Check if the item matches the where filter (if specified)
+        if let Some(ref where_filter) = op.where_filter
+            && !self.matches_where_filter(item_attrs, where_filter)?
+        {
+            // Item doesn't match filter - skip without error
+            return Ok(ModificationResult {
+                changed: false,
+                modified_nodes: vec![],
+                unmatched_qualified_paths: None,
+            });
+        }
+
+        // Create backup of original item before modification
+        let backup_node = BackupNode {
+            node_type: if op.target_type == "struct" {
+                "struct"
+            } else {
+                "enum"
+            }
+            .to_string(),
+            identifier: op.target_name.clone(),
+            original_content: self.backup_item(self.item_at(&item_path)),
+            location: self.span_to_location(item_span),
+        };
+
         // Filter out derives that already exist (idempotent)
         let new_derives: Vec<String> = op
             .derives
@@ -2388,18 +4036,94 @@ impl RustEditor {
         let all_derives_refs: Vec<&str> = all_derives.iter().map(|s| s.as_str()).collect();
 
         // Update the AST item's attributes
-        match &mut self.syntax_tree.items[item_index] {
+        match self.item_at_mut(&item_path) {
             Item::Struct(s) => {
-                Self::update_derive_attr(&mut s.attrs, &all_derives_refs)?;
+                Self::update_derive_attr(
+                    &mut s.attrs,
+                    &all_derives_refs,
+                    op.derive_order.as_deref(),
+                )?;
             }
             Item::Enum(e) => {
-                Self::update_derive_attr(&mut e.attrs, &all_derives_refs)?;
+                Self::update_derive_attr(
+                    &mut e.attrs,
+                    &all_derives_refs,
+                    op.derive_order.as_deref(),
+                )?;
             }
             _ => unreachable!(),
         }
 
         // Use prettyplease to format just this item
-        self.replace_formatted_item(item_index, item_span)?;
+        self.replace_formatted_item(&item_path, item_span, op.edit_mode)?;
+
+        Ok(ModificationResult {
+            changed: true,
+            modified_nodes: vec![backup_node],
+            unmatched_qualified_paths: None,
+        })
+    }
+
+    /// Replace the derive list of a struct or enum with `op.derives`, and/or sort it. Items
+    /// without a `#[derive]` only get one when `op.derives` is given.
+    pub(crate) fn update_derive(&mut self, op: &UpdateDeriveOp) -> Result<ModificationResult> {
+        let unchanged = ModificationResult {
+            changed: false,
+            modified_nodes: vec![],
+            unmatched_qualified_paths: None,
+        };
+        let item_path = self.locate_item(&op.target_type, &op.target_name, |item| {
+            match (&op.target_type as &str, item) {
+                ("struct", Item::Struct(s)) => s.ident == op.target_name,
+                ("enum", Item::Enum(e)) => e.ident == op.target_name,
+                _ => false,
+            }
+        })?;
+
+        let (item_span, item_attrs) = match self.item_at(&item_path) {
+            Item::Struct(s) => (s.span(), &s.attrs),
+            Item::Enum(e) => (e.span(), &e.attrs),
+            _ => unreachable!(),
+        };
+        if let Some(ref where_filter) = op.where_filter
+            && !self.matches_where_filter(item_attrs, where_filter)?
+        {
+            return Ok(unchanged);
+        }
+
+        // `extract_derives` yields an empty entry for a trailing comma
+        let existing: Vec<String> = Self::extract_derives(item_attrs)
+            .into_iter()
+            .filter(|d| !d.is_empty())
+            .collect();
+        let derives = match &op.derives {
+            Some(derives) => derives.clone(),
+            None if existing.is_empty() => return Ok(unchanged),
+            None => existing.clone(),
+        };
+        let derive_refs: Vec<&str> = derives.iter().map(|s| s.as_str()).collect();
+        let mut attrs = item_attrs.clone();
+        // An empty order sorts every derive alphabetically
+        Self::update_derive_attr(&mut attrs, &derive_refs, op.sort.then_some(&[][..]))?;
+
+        let normalize =
+            |list: &[String]| -> Vec<String> { list.iter().map(|d| d.replace(' ', "")).collect() };
+        if normalize(&Self::extract_derives(&attrs)) == normalize(&existing) {
+            return Ok(unchanged);
+        }
+
+        let backup_node = BackupNode {
+            node_type: op.target_type.clone(),
+            identifier: op.target_name.clone(),
+            original_content: self.backup_item(self.item_at(&item_path)),
+            location: self.span_to_location(item_span),
+        };
+        match self.item_at_mut(&item_path) {
+            Item::Struct(s) => s.attrs = attrs,
+            Item::Enum(e) => e.attrs = attrs,
+            _ => unreachable!(),
+        }
+        self.replace_formatted_item(&item_path, item_span, op.edit_mode)?;
 
         Ok(ModificationResult {
             changed: true,
@@ -2408,8 +4132,138 @@ impl RustEditor {
         })
     }
 
-    /// Replace an item in the content with a formatted version
-    fn replace_formatted_item(&mut self, item_index: usize, original_span: Span) -> Result<()> {
+    /// Give every definition `op` matches the visibility `op.visibility`. Ranges are all
+    /// found in the untouched source first, like `rename_signatures`.
+    pub(crate) fn update_visibility(
+        &mut self,
+        op: &UpdateVisibilityOp,
+    ) -> Result<ModificationResult> {
+        if let Some(node_type) = op
+            .node_types
+            .iter()
+            .find(|t| !VISIBILITY_NODE_TYPES.contains(&t.as_str()))
+        {
+            anyhow::bail!(
+                "--visibility doesn't apply to '{}'; it works on {}",
+                node_type,
+                VISIBILITY_NODE_TYPES.join(", ")
+            );
+        }
+        let visibility = op.visibility.trim();
+        if !visibility.is_empty() && syn::parse_str::<syn::Visibility>(visibility).is_err() {
+            anyhow::bail!(
+                "'{}' isn't a visibility (e.g. pub, pub(crate), pub(super))",
+                visibility
+            );
+        }
+        let compact = |text: &str| text.split_whitespace().collect::<String>();
+        let name_regex = op
+            .name_regex
+            .as_deref()
+            .map(compile_name_regex)
+            .transpose()?;
+
+        let mut edits = Vec::new();
+        for node_type in &op.node_types {
+            let mut matches = self.inspect(Some(node_type), op.name.as_deref(), None, false)?;
+            if let Some(regex) = &name_regex {
+                matches.retain(|m| name_matches(regex, &m.identifier));
+            }
+            for m in matches {
+                let start = self.line_column_to_byte_offset(m.location.line, m.location.column)?;
+                let end =
+                    self.line_column_to_byte_offset(m.location.end_line, m.location.end_column)?;
+                let Some((vis_start, vis_end)) = Self::visibility_range(&self.content[start..end])
+                else {
+                    continue;
+                };
+                let (vis_start, vis_end) = (start + vis_start, start + vis_end);
+                if compact(&self.content[vis_start..vis_end]) == compact(visibility) {
+                    continue;
+                }
+                edits.push((
+                    vis_start,
+                    vis_end,
+                    BackupNode {
+                        node_type: m.node_type.clone(),
+                        identifier: m.identifier.clone(),
+                        original_content: m.snippet.clone(),
+                        location: m.location.clone(),
+                    },
+                ));
+            }
+        }
+        edits.sort_by_key(|(start, _, _)| std::cmp::Reverse(*start));
+        edits.dedup_by_key(|(start, _, _)| *start);
+
+        let mut modified_nodes = Vec::new();
+        for (start, end, backup) in edits {
+            if start == end {
+                // A private item: the visibility goes before its keyword
+                self.content.insert_str(start, &format!("{} ", visibility));
+            } else if visibility.is_empty() {
+                // Going private: the visibility goes along with the space after it
+                let end = end
+                    + self.content[end..]
+                        .find(|c: char| !c.is_whitespace())
+                        .unwrap_or(0);
+                self.content.replace_range(start..end, "");
+            } else {
+                self.content.replace_range(start..end, visibility);
+            }
+            modified_nodes.push(backup);
+        }
+        if !modified_nodes.is_empty() {
+            self.line_offsets = Self::compute_line_offsets(&self.content);
+            self.syntax_tree = self
+                .parse_content()
+                .context("Failed to re-parse content after updating visibility")?;
+        }
+        Ok(ModificationResult {
+            changed: !modified_nodes.is_empty(),
+            modified_nodes,
+            unmatched_qualified_paths: None,
+        })
+    }
+
+    /// Byte range of the visibility in a definition's source, after its attributes: `pub`
+    /// with its `(..)` if any, or an empty range where the keyword starts for a private
+    /// item.
+    fn visibility_range(text: &str) -> Option<(usize, usize)> {
+        use proc_macro2::{Delimiter, TokenTree};
+
+        let src = crate::surgical::Source::new(text);
+        let tokens: proc_macro2::TokenStream = text.parse().ok()?;
+        let mut tokens = tokens.into_iter().peekable();
+        // Attributes, doc comments included, lex as `#` then a `[..]` group
+        while matches!(tokens.peek(), Some(TokenTree::Punct(p)) if p.as_char() == '#') {
+            tokens.next();
+            tokens.next();
+        }
+        let first = tokens.next()?;
+        let start = src.offset(first.span().start());
+        if !matches!(&first, TokenTree::Ident(i) if i == "pub") {
+            return Some((start, start));
+        }
+        let end = match tokens.peek() {
+            Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => {
+                src.offset(g.span().end())
+            }
+            _ => src.offset(first.span().end()),
+        };
+        Some((start, end))
+    }
+
+    /// Replace an item in the content with a formatted version (prettyplease, or the
+    /// project's rustfmt in `EditMode::Rustfmt`)
+    fn replace_formatted_item(
+        &mut self,
+        item_path: &[usize],
+        original_span: Span,
+        edit_mode: EditMode,
+    ) -> Result<()> {
+        self.ensure_nothing_pruned_in("the item", original_span)?;
+
         // Get the item start and end positions from the original source
         let item_start_pos = self.span_to_byte_offset(original_span.start());
         let item_end_pos = self.span_to_byte_offset(original_span.end());
@@ -2449,21 +4303,69 @@ impl RustEditor {
         }
 
         // Create a temporary file with just this item for pretty formatting
-        let item_clone = self.syntax_tree.items[item_index].clone();
+        let item_clone = self.item_at(item_path).clone();
         let temp_file = syn::File {
             shebang: None,
             attrs: Vec::new(),
             items: vec![item_clone],
         };
 
-        // Format the item using prettyplease
-        let formatted = prettyplease::unparse(&temp_file);
+        // Format the item using prettyplease, then rustfmt if asked for
+        let mut formatted = prettyplease::unparse(&temp_file);
+        if edit_mode == EditMode::Rustfmt {
+            formatted = crate::rustfmt::format_or_else(&formatted, || formatted.clone());
+        }
         let formatted = formatted.trim();
 
+        let at_line_start = actual_start == 0 || self.content.as_bytes()[actual_start - 1] == b'\n';
+        let formatted = self.reindent(formatted, item_start_pos, at_line_start);
+
         // Replace in content
         self.content
-            .replace_range(actual_start..item_end_pos, formatted);
+            .replace_range(actual_start..item_end_pos, &formatted);
+
+        Ok(())
+    }
+
+    /// `text`, formatted at column 0, indented like the line `item_start` is on, so an item
+    /// inside an inline module stays in place. The first line is only indented when it
+    /// goes at the start of that line.
+    fn reindent(&self, text: &str, item_start: usize, from_line_start: bool) -> String {
+        let line_start = self.content[..item_start].rfind('\n').map_or(0, |i| i + 1);
+        let indent: String = self.content[line_start..]
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect();
+        if indent.is_empty() {
+            return text.to_string();
+        }
+        text.lines()
+            .enumerate()
+            .map(|(i, line)| {
+                if line.is_empty() || (i == 0 && !from_line_start) {
+                    line.to_string()
+                } else {
+                    format!("{}{}", indent, line)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 
+    /// Run the whole file through the project's rustfmt (prettyplease if rustfmt can't be
+    /// used) and re-parse it.
+    fn rustfmt_content(&mut self) -> Result<()> {
+        self.content = crate::rustfmt::format_or_else(&self.content, || {
+            // A fresh parse, since code out of scope isn't in the syntax tree
+            syn::parse_str::<File>(&self.content).map_or_else(
+                |_| self.content.clone(),
+                |file| prettyplease::unparse(&file),
+            )
+        });
+        self.line_offsets = Self::compute_line_offsets(&self.content);
+        self.syntax_tree = self
+            .parse_content()
+            .context("Failed to re-parse after rustfmt")?;
         Ok(())
     }
 
@@ -2506,8 +4408,27 @@ impl RustEditor {
         Ok(true)
     }
 
-    /// Update or create derive attribute in the attribute list
-    fn update_derive_attr(attrs: &mut Vec<syn::Attribute>, derives: &[&str]) -> Result<()> {
+    /// Update or create derive attribute in the attribute list. With `order`, the list is
+    /// sorted into that order first; derives it doesn't mention go last, alphabetically.
+    fn update_derive_attr(
+        attrs: &mut Vec<syn::Attribute>,
+        derives: &[&str],
+        order: Option<&[String]>,
+    ) -> Result<()> {
+        let mut derives = derives.to_vec();
+        if let Some(order) = order {
+            // `serde::Serialize` is listed as `Serialize` too; paths come out of
+            // `extract_derives` with spaces around `::`
+            let rank = |derive: &str| {
+                let derive = derive.replace(' ', "");
+                let last = derive.rsplit("::").next().unwrap_or(&derive).to_string();
+                let position = order
+                    .iter()
+                    .position(|name| name.trim() == derive || name.trim() == last);
+                (position.unwrap_or(order.len()), last, derive)
+            };
+            derives.sort_by_cached_key(|derive| rank(derive));
+        }
         let derive_str = derives.join(", ");
 
         // Parse a dummy struct with the derive to extract the attribute
@@ -2539,21 +4460,21 @@ impl RustEditor {
         if modified_function.is_none() {
             // This case shouldn't happen in practice, but if it does, fall back to whole-file
             // format TODO: Track which functions were modified and format only those
-            self.content = prettyplease::unparse(&self.syntax_tree);
+            self.content = self.unparse_tree()?;
             return Ok(());
         }
 
         // Parse the ORIGINAL content to get the correct spans
-        let original_syntax_tree: File =
-            syn::parse_str(&self.content).context("Failed to re-parse original content")?;
+        let original_syntax_tree: File = self
+            .parse_content()
+            .context("Failed to re-parse original content")?;
 
         let function_name = modified_function.as_ref().unwrap();
 
         // Find the function in the ORIGINAL syntax tree to get correct byte positions
-        let original_fn = original_syntax_tree
-            .items
-            .iter()
-            .find_map(|item| {
+        let original_fn = Self::item_paths_in(&original_syntax_tree.items)
+            .into_iter()
+            .find_map(|(_, _, item)| {
                 if let Item::Fn(f) = item
                     && f.sig.ident == function_name
                 {
@@ -2562,6 +4483,10 @@ impl RustEditor {
                 None
             })
             .ok_or_else(|| anyhow::anyhow!("Function '{}' not found in original", function_name))?;
+        self.ensure_nothing_pruned_in(
+            &format!("function '{}'", function_name),
+            original_fn.span(),
+        )?;
 
         // Get the span of the original function (these are the correct byte positions)
         let start = self.span_to_byte_offset(original_fn.span().start());
@@ -2569,10 +4494,9 @@ impl RustEditor {
 
         // Find the MODIFIED function in the modified syntax tree
         let modified_fn = self
-            .syntax_tree
-            .items
-            .iter()
-            .find_map(|item| {
+            .item_paths()
+            .into_iter()
+            .find_map(|(_, _, item)| {
                 if let Item::Fn(f) = item
                     && f.sig.ident == function_name
                 {
@@ -2597,7 +4521,8 @@ impl RustEditor {
         let formatted_fn = formatted_fn.trim();
 
         // Replace the function in the original content using original spans
-        self.content.replace_range(start..end, formatted_fn);
+        let formatted_fn = self.reindent(formatted_fn, start, false);
+        self.content.replace_range(start..end, &formatted_fn);
 
         Ok(())
     }
@@ -2611,6 +4536,50 @@ impl RustEditor {
         }
     }
 
+    /// Inverse of `span_to_byte_offset`: 1-based line and byte column of `offset`.
+    fn byte_offset_to_line_column(&self, offset: usize) -> (usize, usize) {
+        let line_idx = self
+            .line_offsets
+            .partition_point(|&start| start <= offset)
+            .saturating_sub(1);
+        let line_start = self.line_offsets.get(line_idx).copied().unwrap_or(0);
+        (line_idx + 1, offset - line_start)
+    }
+
+    /// Every `<expr>.<field>` access in the file, macro arguments included, with the
+    /// receiver's type where `FieldAccessCollector` can tell it.
+    pub fn field_accesses(&self, field: &str) -> Vec<FieldAccess> {
+        use syn::visit::Visit;
+
+        let mut collector = FieldAccessCollector {
+            field: field.to_string(),
+            scopes: Vec::new(),
+            impl_stack: Vec::new(),
+            found: Vec::new(),
+        };
+        collector.visit_file(&self.syntax_tree);
+
+        collector
+            .found
+            .into_iter()
+            .map(|(start, member, receiver_type)| {
+                let code_start = self.span_to_byte_offset(start);
+                let code_end = self.span_to_byte_offset(member.end());
+                FieldAccess {
+                    location: self.span_to_location(member),
+                    receiver_type,
+                    code: self
+                        .content
+                        .get(code_start..code_end)
+                        .unwrap_or_default()
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                }
+            })
+            .collect()
+    }
+
     fn find_after_field_end(&self, pos: usize) -> usize {
         // Look for comma or newline after the field
         let mut i = pos;
@@ -2676,8 +4645,9 @@ impl RustEditor {
         self.content.replace_range(start..end, new_content);
 
         // Re-parse the syntax tree
-        self.syntax_tree =
-            syn::parse_str(&self.content).context("Failed to re-parse after replace_range")?;
+        self.syntax_tree = self
+            .parse_content()
+            .context("Failed to re-parse after replace_range")?;
         self.line_offsets = Self::compute_line_offsets(&self.content);
 
         Ok(())
@@ -2695,7 +4665,7 @@ impl RustEditor {
         let mut locations = Vec::new();
 
         // Search struct definitions
-        for item in &self.syntax_tree.items {
+        for item in self.scoped_items() {
             if let Item::Struct(s) = item
                 && let Fields::Named(ref fields) = s.fields
             {
@@ -2813,27 +4783,7 @@ impl RustEditor {
 
         // If node_type is None, search all node types
         if node_type.is_none() {
-            let all_types = vec![
-                "struct",
-                "enum",
-                "function",
-                "impl-method",
-                "trait",
-                "trait-impl",
-                "const",
-                "static",
-                "type-alias",
-                "mod",
-                "struct-literal",
-                "match-arm",
-                "enum-usage",
-                "function-call",
-                "method-call",
-                "macro-call",
-                "identifier",
-                "type-ref",
-            ];
-            for nt in all_types {
+            for nt in crate::commands::node_types::default_node_types() {
                 let mut type_results =
                     self.inspect(Some(nt), name_filter, variant_filter, include_comments)?;
                 results.append(&mut type_results);
@@ -2923,6 +4873,7 @@ impl RustEditor {
                                     location,
                                     snippet,
                                     preceding_comment,
+                                    arity: None,
                                 });
 
                                 syn::visit::visit_expr_struct(self, node);
@@ -3009,6 +4960,7 @@ impl RustEditor {
                             location,
                             snippet,
                             preceding_comment,
+                            arity: None,
                         });
 
                         // Continue visiting nested expressions
@@ -3081,6 +5033,7 @@ impl RustEditor {
                                 location,
                                 snippet,
                                 preceding_comment,
+                                arity: None,
                             });
                         }
 
@@ -3153,6 +5106,7 @@ impl RustEditor {
                             location,
                             snippet,
                             preceding_comment,
+                            arity: None,
                         });
 
                         // Continue visiting nested expressions
@@ -3230,6 +5184,7 @@ impl RustEditor {
                             location,
                             snippet,
                             preceding_comment,
+                            arity: None,
                         });
 
                         // Continue visiting nested expressions
@@ -3306,6 +5261,7 @@ impl RustEditor {
                             location,
                             snippet,
                             preceding_comment,
+                            arity: Some(Arity::of(&node.sig)),
                         });
 
                         syn::visit::visit_trait_item_fn(self, node);
@@ -3364,6 +5320,7 @@ impl RustEditor {
                             location,
                             snippet,
                             preceding_comment,
+                            arity: None,
                         });
 
                         // Continue visiting nested expressions
@@ -3423,6 +5380,7 @@ impl RustEditor {
                             location,
                             snippet,
                             preceding_comment,
+                            arity: None,
                         });
 
                         // Continue visiting
@@ -3491,6 +5449,7 @@ impl RustEditor {
                             location,
                             snippet,
                             preceding_comment,
+                            arity: None,
                         });
 
                         // Continue visiting
@@ -3556,6 +5515,7 @@ impl RustEditor {
                             location,
                             snippet,
                             preceding_comment,
+                            arity: None,
                         });
 
                         // Continue visiting nested expressions
@@ -3599,6 +5559,7 @@ impl RustEditor {
                                 location,
                                 snippet,
                                 preceding_comment,
+                                arity: None,
                             });
                         }
 
@@ -3619,52 +5580,101 @@ impl RustEditor {
                     syn::visit::visit_item(&mut visitor, item);
                 }
             }
-            "struct" => {
-                // Find all struct definitions
-                struct StructDefVisitor<'a> {
+            "extern-fn" => {
+                // Find functions declared in extern blocks (FFI signatures)
+                struct ExternFnVisitor<'a> {
                     results: &'a mut Vec<InspectResult>,
                     name_filter: Option<&'a str>,
                     editor: &'a RustEditor,
                     include_comments: bool,
                 }
 
-                impl<'ast, 'a> Visit<'ast> for StructDefVisitor<'a> {
-                    fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
-                        let struct_name = node.ident.to_string();
+                impl<'ast, 'a> Visit<'ast> for ExternFnVisitor<'a> {
+                    fn visit_foreign_item_fn(&mut self, node: &'ast syn::ForeignItemFn) {
+                        let fn_name = node.sig.ident.to_string();
+                        if self.name_filter.is_none_or(|filter| fn_name == filter) {
+                            let start = self.editor.span_to_byte_offset(node.span().start());
+                            let end = self.editor.span_to_byte_offset(node.span().end());
+                            let location = self.editor.span_to_location(node.span());
+                            let preceding_comment = if self.include_comments {
+                                extract_preceding_comment(&self.editor.content, location.line)
+                            } else {
+                                None
+                            };
 
-                        // Apply name filter if specified
-                        if let Some(filter) = self.name_filter
-                            && struct_name != filter
+                            self.results.push(InspectResult {
+                                file_path: String::new(), // Will be filled in by caller
+                                node_type: "extern-fn".to_string(),
+                                identifier: fn_name,
+                                location,
+                                snippet: self.editor.content[start..end].to_string(),
+                                preceding_comment,
+                                arity: None,
+                            });
+                        }
+                        syn::visit::visit_foreign_item_fn(self, node);
+                    }
+                }
+
+                let mut visitor = ExternFnVisitor {
+                    results: &mut results,
+                    name_filter,
+                    editor: self,
+                    include_comments,
+                };
+
+                for item in &self.syntax_tree.items {
+                    syn::visit::visit_item(&mut visitor, item);
+                }
+            }
+            "lifetime" => {
+                // Find every lifetime, where it's declared (`<'a>`, `for<'a>`) and where it's
+                // used; loop labels share the syntax but aren't included
+                struct LifetimeVisitor<'a> {
+                    results: &'a mut Vec<InspectResult>,
+                    name_filter: Option<&'a str>,
+                    editor: &'a RustEditor,
+                    include_comments: bool,
+                }
+
+                impl<'ast, 'a> Visit<'ast> for LifetimeVisitor<'a> {
+                    fn visit_lifetime(&mut self, node: &'ast syn::Lifetime) {
+                        // `--name a` and `--name "'a"` both work
+                        if self
+                            .name_filter
+                            .is_some_and(|filter| node.ident != filter.trim_start_matches('\''))
                         {
-                            syn::visit::visit_item_struct(self, node);
                             return;
                         }
-
-                        // Format the struct definition
-                        let snippet = self.editor.format_item_struct(node);
                         let location = self.editor.span_to_location(node.span());
-
-                        // Extract preceding comment if requested
                         let preceding_comment = if self.include_comments {
                             extract_preceding_comment(&self.editor.content, location.line)
                         } else {
                             None
                         };
-
                         self.results.push(InspectResult {
-                            file_path: String::new(),
-                            node_type: "struct".to_string(),
-                            identifier: struct_name,
+                            file_path: String::new(), // Will be filled in by caller
+                            node_type: "lifetime".to_string(),
+                            identifier: node.to_string(),
+                            snippet: self.editor.source_line(location.line),
                             location,
-                            snippet,
                             preceding_comment,
+                            arity: None,
                         });
+                    }
 
-                        syn::visit::visit_item_struct(self, node);
+                    fn visit_label(&mut self, _node: &'ast syn::Label) {}
+
+                    fn visit_expr_break(&mut self, node: &'ast syn::ExprBreak) {
+                        if let Some(expr) = &node.expr {
+                            self.visit_expr(expr);
+                        }
                     }
+
+                    fn visit_expr_continue(&mut self, _node: &'ast syn::ExprContinue) {}
                 }
 
-                let mut visitor = StructDefVisitor {
+                let mut visitor = LifetimeVisitor {
                     results: &mut results,
                     name_filter,
                     editor: self,
@@ -3675,17 +5685,330 @@ impl RustEditor {
                     syn::visit::visit_item(&mut visitor, item);
                 }
             }
-            "enum" => {
-                // Find all enum definitions
-                // Supports:
-                // 1. --variant Rectangle (all enums with Rectangle variant)
-                // 2. --name View --variant Rectangle (View enum, Rectangle variant only)
-                // 3. --name View::Rectangle (:: syntax auto-detects variant filter)
-                // 4. --name *::Rectangle (wildcard: any enum with Rectangle variant)
-                struct EnumDefVisitor<'a> {
+            "generic-param" => {
+                // Find type and const generic parameters where they're declared, and the
+                // uses of them within the item that declares them. Items nested in a body
+                // can't see the outer parameters, so each starts its own scope; impl and
+                // trait members add theirs to the block's.
+                struct GenericParamVisitor<'a> {
                     results: &'a mut Vec<InspectResult>,
                     name_filter: Option<&'a str>,
-                    variant_filter: Option<&'a str>,
+                    editor: &'a RustEditor,
+                    include_comments: bool,
+                    in_scope: Vec<String>,
+                }
+
+                impl GenericParamVisitor<'_> {
+                    fn report(&mut self, ident: &syn::Ident) {
+                        if self.name_filter.is_some_and(|filter| ident != filter) {
+                            return;
+                        }
+                        let location = self.editor.span_to_location(ident.span());
+                        let preceding_comment = if self.include_comments {
+                            extract_preceding_comment(&self.editor.content, location.line)
+                        } else {
+                            None
+                        };
+                        self.results.push(InspectResult {
+                            file_path: String::new(), // Will be filled in by caller
+                            node_type: "generic-param".to_string(),
+                            identifier: ident.to_string(),
+                            snippet: self.editor.source_line(location.line),
+                            location,
+                            preceding_comment,
+                            arity: None,
+                        });
+                    }
+
+                    /// Visit `node` with `generics` in scope, after those of enclosing items
+                    /// when `nested`.
+                    fn scoped(
+                        &mut self,
+                        generics: Option<&syn::Generics>,
+                        nested: bool,
+                        visit: impl FnOnce(&mut Self),
+                    ) {
+                        let saved = if nested {
+                            self.in_scope.clone()
+                        } else {
+                            std::mem::take(&mut self.in_scope)
+                        };
+                        for param in generics.into_iter().flat_map(|g| &g.params) {
+                            match param {
+                                syn::GenericParam::Type(t) => {
+                                    self.in_scope.push(t.ident.to_string())
+                                }
+                                syn::GenericParam::Const(c) => {
+                                    self.in_scope.push(c.ident.to_string())
+                                }
+                                syn::GenericParam::Lifetime(_) => {}
+                            }
+                        }
+                        visit(self);
+                        self.in_scope = saved;
+                    }
+
+                    fn in_scope(&self, path: &syn::Path) -> Option<syn::Ident> {
+                        let first = path.segments.first()?;
+                        (path.leading_colon.is_none()
+                            && self.in_scope.iter().any(|name| first.ident == name))
+                        .then(|| first.ident.clone())
+                    }
+                }
+
+                impl<'ast, 'a> Visit<'ast> for GenericParamVisitor<'a> {
+                    fn visit_item(&mut self, node: &'ast syn::Item) {
+                        let generics = match node {
+                            Item::Enum(i) => Some(&i.generics),
+                            Item::Fn(i) => Some(&i.sig.generics),
+                            Item::Impl(i) => Some(&i.generics),
+                            Item::Struct(i) => Some(&i.generics),
+                            Item::Trait(i) => Some(&i.generics),
+                            Item::TraitAlias(i) => Some(&i.generics),
+                            Item::Type(i) => Some(&i.generics),
+                            Item::Union(i) => Some(&i.generics),
+                            _ => None,
+                        };
+                        self.scoped(generics, false, |v| syn::visit::visit_item(v, node));
+                    }
+
+                    fn visit_impl_item(&mut self, node: &'ast syn::ImplItem) {
+                        let generics = match node {
+                            syn::ImplItem::Const(i) => Some(&i.generics),
+                            syn::ImplItem::Fn(i) => Some(&i.sig.generics),
+                            syn::ImplItem::Type(i) => Some(&i.generics),
+                            _ => None,
+                        };
+                        self.scoped(generics, true, |v| syn::visit::visit_impl_item(v, node));
+                    }
+
+                    fn visit_trait_item(&mut self, node: &'ast syn::TraitItem) {
+                        let generics = match node {
+                            syn::TraitItem::Const(i) => Some(&i.generics),
+                            syn::TraitItem::Fn(i) => Some(&i.sig.generics),
+                            syn::TraitItem::Type(i) => Some(&i.generics),
+                            _ => None,
+                        };
+                        self.scoped(generics, true, |v| syn::visit::visit_trait_item(v, node));
+                    }
+
+                    fn visit_foreign_item(&mut self, node: &'ast syn::ForeignItem) {
+                        let generics = match node {
+                            syn::ForeignItem::Fn(i) => Some(&i.sig.generics),
+                            _ => None,
+                        };
+                        self.scoped(generics, false, |v| syn::visit::visit_foreign_item(v, node));
+                    }
+
+                    fn visit_type_param(&mut self, node: &'ast syn::TypeParam) {
+                        self.report(&node.ident);
+                        syn::visit::visit_type_param(self, node);
+                    }
+
+                    fn visit_const_param(&mut self, node: &'ast syn::ConstParam) {
+                        self.report(&node.ident);
+                        syn::visit::visit_const_param(self, node);
+                    }
+
+                    // `T`, `T::Item` and `<T as Trait>::Item`
+                    fn visit_type_path(&mut self, node: &'ast syn::TypePath) {
+                        if node.qself.is_none()
+                            && let Some(ident) = self.in_scope(&node.path)
+                        {
+                            self.report(&ident);
+                        }
+                        syn::visit::visit_type_path(self, node);
+                    }
+
+                    // Const parameters in expressions (`[0; N]`) and `T::new()`
+                    fn visit_expr_path(&mut self, node: &'ast syn::ExprPath) {
+                        if node.qself.is_none()
+                            && let Some(ident) = self.in_scope(&node.path)
+                        {
+                            self.report(&ident);
+                        }
+                        syn::visit::visit_expr_path(self, node);
+                    }
+                }
+
+                let mut visitor = GenericParamVisitor {
+                    results: &mut results,
+                    name_filter,
+                    editor: self,
+                    include_comments,
+                    in_scope: Vec::new(),
+                };
+
+                for item in &self.syntax_tree.items {
+                    visitor.visit_item(item);
+                }
+            }
+            "closure" => {
+                // Find all closure expressions; the identifier is the first variable each one
+                // captures
+                struct ClosureVisitor<'a> {
+                    results: &'a mut Vec<InspectResult>,
+                    name_filter: Option<&'a str>,
+                    editor: &'a RustEditor,
+                    include_comments: bool,
+                }
+
+                impl<'ast, 'a> Visit<'ast> for ClosureVisitor<'a> {
+                    fn visit_expr_closure(&mut self, node: &'ast syn::ExprClosure) {
+                        let identifier =
+                            closure_first_capture(node).unwrap_or_else(|| "<closure>".to_string());
+
+                        if self.name_filter.is_none_or(|filter| identifier == filter) {
+                            // Keep the original source, line breaks included
+                            let start = self.editor.span_to_byte_offset(node.span().start());
+                            let end = self.editor.span_to_byte_offset(node.span().end());
+                            let location = self.editor.span_to_location(node.span());
+                            let preceding_comment = if self.include_comments {
+                                extract_preceding_comment(&self.editor.content, location.line)
+                            } else {
+                                None
+                            };
+
+                            self.results.push(InspectResult {
+                                file_path: String::new(), // Will be filled in by caller
+                                node_type: "closure".to_string(),
+                                identifier,
+                                location,
+                                snippet: self.editor.content[start..end].to_string(),
+                                preceding_comment,
+                                arity: None,
+                            });
+                        }
+
+                        // Closures nested in the body are reported too
+                        syn::visit::visit_expr_closure(self, node);
+                    }
+                }
+
+                let mut visitor = ClosureVisitor {
+                    results: &mut results,
+                    name_filter,
+                    editor: self,
+                    include_comments,
+                };
+
+                for item in &self.syntax_tree.items {
+                    syn::visit::visit_item(&mut visitor, item);
+                }
+            }
+            "struct" => {
+                // Find all struct definitions
+                struct StructDefVisitor<'a> {
+                    results: &'a mut Vec<InspectResult>,
+                    name_filter: Option<&'a str>,
+                    editor: &'a RustEditor,
+                    include_comments: bool,
+                }
+
+                impl<'ast, 'a> Visit<'ast> for StructDefVisitor<'a> {
+                    fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
+                        let struct_name = node.ident.to_string();
+
+                        // Apply name filter if specified
+                        if let Some(filter) = self.name_filter
+                            && struct_name != filter
+                        {
+                            syn::visit::visit_item_struct(self, node);
+                            return;
+                        }
+
+                        // Format the struct definition
+                        let snippet = self.editor.format_item_struct(node);
+                        let location = self.editor.span_to_location(node.span());
+
+                        // Extract preceding comment if requested
+                        let preceding_comment = if self.include_comments {
+                            extract_preceding_comment(&self.editor.content, location.line)
+                        } else {
+                            None
+                        };
+
+                        self.results.push(InspectResult {
+                            file_path: String::new(),
+                            node_type: "struct".to_string(),
+                            identifier: struct_name,
+                            location,
+                            snippet,
+                            preceding_comment,
+                            arity: None,
+                        });
+
+                        syn::visit::visit_item_struct(self, node);
+                    }
+                }
+
+                let mut visitor = StructDefVisitor {
+                    results: &mut results,
+                    name_filter,
+                    editor: self,
+                    include_comments,
+                };
+
+                for item in &self.syntax_tree.items {
+                    syn::visit::visit_item(&mut visitor, item);
+                }
+            }
+            "union" => {
+                struct UnionDefVisitor<'a> {
+                    results: &'a mut Vec<InspectResult>,
+                    name_filter: Option<&'a str>,
+                    editor: &'a RustEditor,
+                    include_comments: bool,
+                }
+
+                impl<'ast> Visit<'ast> for UnionDefVisitor<'_> {
+                    fn visit_item_union(&mut self, node: &'ast syn::ItemUnion) {
+                        if self.name_filter.is_none_or(|filter| node.ident == filter) {
+                            let location = self.editor.span_to_location(node.span());
+                            let start = self.editor.span_to_byte_offset(node.span().start());
+                            let end = self.editor.span_to_byte_offset(node.span().end());
+                            let preceding_comment = if self.include_comments {
+                                extract_preceding_comment(&self.editor.content, location.line)
+                            } else {
+                                None
+                            };
+
+                            self.results.push(InspectResult {
+                                file_path: String::new(),
+                                node_type: "union".to_string(),
+                                identifier: node.ident.to_string(),
+                                location,
+                                snippet: self.editor.content[start..end].to_string(),
+                                preceding_comment,
+                                arity: None,
+                            });
+                        }
+                        syn::visit::visit_item_union(self, node);
+                    }
+                }
+
+                let mut visitor = UnionDefVisitor {
+                    results: &mut results,
+                    name_filter,
+                    editor: self,
+                    include_comments,
+                };
+
+                for item in &self.syntax_tree.items {
+                    syn::visit::visit_item(&mut visitor, item);
+                }
+            }
+            "enum" => {
+                // Find all enum definitions
+                // Supports:
+                // 1. --variant Rectangle (all enums with Rectangle variant)
+                // 2. --name View --variant Rectangle (View enum, Rectangle variant only)
+                // 3. --name View::Rectangle (:: syntax auto-detects variant filter)
+                // 4. --name *::Rectangle (wildcard: any enum with Rectangle variant)
+                struct EnumDefVisitor<'a> {
+                    results: &'a mut Vec<InspectResult>,
+                    name_filter: Option<&'a str>,
+                    variant_filter: Option<&'a str>,
                     editor: &'a RustEditor,
                     include_comments: bool,
                 }
@@ -3760,6 +6083,7 @@ impl RustEditor {
                             location,
                             snippet,
                             preceding_comment,
+                            arity: None,
                         });
 
                         syn::visit::visit_item_enum(self, node);
@@ -3817,6 +6141,7 @@ impl RustEditor {
                             location,
                             snippet,
                             preceding_comment,
+                            arity: Some(Arity::of(&node.sig)),
                         });
 
                         syn::visit::visit_item_fn(self, node);
@@ -3901,6 +6226,7 @@ impl RustEditor {
                             location,
                             snippet,
                             preceding_comment,
+                            arity: Some(Arity::of(&node.sig)),
                         });
 
                         syn::visit::visit_impl_item_fn(self, node);
@@ -3958,6 +6284,7 @@ impl RustEditor {
                             location,
                             snippet,
                             preceding_comment,
+                            arity: None,
                         });
 
                         syn::visit::visit_item_trait(self, node);
@@ -4014,6 +6341,7 @@ impl RustEditor {
                             location,
                             snippet,
                             preceding_comment,
+                            arity: None,
                         });
 
                         syn::visit::visit_item_const(self, node);
@@ -4070,6 +6398,7 @@ impl RustEditor {
                             location,
                             snippet,
                             preceding_comment,
+                            arity: None,
                         });
 
                         syn::visit::visit_item_static(self, node);
@@ -4126,6 +6455,7 @@ impl RustEditor {
                             location,
                             snippet,
                             preceding_comment,
+                            arity: None,
                         });
 
                         syn::visit::visit_item_type(self, node);
@@ -4182,6 +6512,7 @@ impl RustEditor {
                             location,
                             snippet,
                             preceding_comment,
+                            arity: None,
                         });
 
                         syn::visit::visit_item_mod(self, node);
@@ -4199,490 +6530,2096 @@ impl RustEditor {
                     syn::visit::visit_item(&mut visitor, item);
                 }
             }
-            "trait-impl" => {
-                // Find all trait impl blocks (impl Trait for Type)
-                struct TraitImplVisitor<'a> {
+            "derive" => {
+                // Find every struct/enum with a #[derive(...)], one result per item; the name
+                // filter matches one of the derived traits
+                struct DeriveVisitor<'a> {
                     results: &'a mut Vec<InspectResult>,
                     name_filter: Option<&'a str>,
                     editor: &'a RustEditor,
+                    include_comments: bool,
                 }
 
-                impl<'ast, 'a> Visit<'ast> for TraitImplVisitor<'a> {
-                    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
-                        // Only emit when there's a trait clause
-                        if node.trait_.is_none() {
-                            syn::visit::visit_item_impl(self, node);
+                impl DeriveVisitor<'_> {
+                    fn push(&mut self, kind: &str, ident: &syn::Ident, attrs: &[syn::Attribute]) {
+                        let derive_attrs: Vec<&syn::Attribute> = attrs
+                            .iter()
+                            .filter(|attr| attr.path().is_ident("derive"))
+                            .collect();
+                        let (Some(first), Some(last)) = (derive_attrs.first(), derive_attrs.last())
+                        else {
                             return;
-                        }
-
-                        let trait_path = &node.trait_.as_ref().unwrap().1;
-                        let trait_name = trait_path
-                            .segments
-                            .last()
-                            .map(|seg| seg.ident.to_string())
-                            .unwrap_or_default();
+                        };
+                        let derives: Vec<String> = derive_attrs
+                            .iter()
+                            .flat_map(|attr| {
+                                RustEditor::extract_derives(std::slice::from_ref(*attr))
+                            })
+                            .map(|d| d.replace(' ', ""))
+                            .filter(|d| !d.is_empty())
+                            .collect();
 
-                        // Apply name filter to the trait name (same wildcard semantics as
-                        // struct-literal)
-                        if let Some(filter) = self.name_filter {
-                            #[allow(clippy::option_if_let_else)]
-                            let matches = if filter.contains("::") {
-                                if let Some(target) = filter.strip_prefix("*::") {
-                                    trait_path
-                                        .segments
-                                        .last()
-                                        .map(|seg| seg.ident == target)
-                                        .unwrap_or(false)
-                                } else {
-                                    let path_str = trait_path
-                                        .segments
-                                        .iter()
-                                        .map(|seg| seg.ident.to_string())
-                                        .collect::<Vec<_>>()
-                                        .join("::");
-                                    path_str == filter
-                                }
-                            } else {
-                                trait_path
-                                    .segments
-                                    .last()
-                                    .map(|seg| seg.ident == filter)
-                                    .unwrap_or(false)
-                            };
-                            if !matches {
-                                syn::visit::visit_item_impl(self, node);
-                                return;
-                            }
+                        if let Some(filter) = self.name_filter
+                            && !derives
+                                .iter()
+                                .any(|d| d == filter || d.rsplit("::").next() == Some(filter))
+                        {
+                            return;
                         }
 
-                        // Extract the implementing type name
-                        let type_name = match &*node.self_ty {
-                            syn::Type::Path(tp) => tp
-                                .path
-                                .segments
-                                .last()
-                                .map(|seg| seg.ident.to_string())
-                                .unwrap_or_else(|| quote::quote!(#tp).to_string()),
-                            other => quote::quote!(#other).to_string(),
+                        let start = self.editor.span_to_location(first.span());
+                        let end = self.editor.span_to_location(last.span());
+                        let location = NodeLocation {
+                            line: start.line,
+                            column: start.column,
+                            end_line: end.end_line,
+                            end_column: end.end_column,
+                        };
+                        let preceding_comment = if self.include_comments {
+                            extract_preceding_comment(&self.editor.content, location.line)
+                        } else {
+                            None
                         };
-
-                        let identifier = format!("{} for {}", trait_name, type_name);
-                        let snippet = format!("impl {} for {} {{ ... }}", trait_name, type_name);
-                        let location = self.editor.span_to_location(node.impl_token.span);
 
                         self.results.push(InspectResult {
                             file_path: String::new(),
-                            node_type: "trait-impl".to_string(),
-                            identifier,
+                            node_type: "derive".to_string(),
+                            identifier: ident.to_string(),
                             location,
-                            snippet,
-                            preceding_comment: None,
+                            snippet: format!(
+                                "#[derive({})] {} {}",
+                                derives.join(", "),
+                                kind,
+                                ident
+                            ),
+                            preceding_comment,
+                            arity: None,
                         });
+                    }
+                }
 
-                        syn::visit::visit_item_impl(self, node);
+                impl<'ast> Visit<'ast> for DeriveVisitor<'_> {
+                    fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
+                        self.push("struct", &node.ident, &node.attrs);
+                        syn::visit::visit_item_struct(self, node);
+                    }
+
+                    fn visit_item_enum(&mut self, node: &'ast ItemEnum) {
+                        self.push("enum", &node.ident, &node.attrs);
+                        syn::visit::visit_item_enum(self, node);
+                    }
+
+                    fn visit_item_union(&mut self, node: &'ast syn::ItemUnion) {
+                        self.push("union", &node.ident, &node.attrs);
+                        syn::visit::visit_item_union(self, node);
                     }
                 }
 
-                let mut visitor = TraitImplVisitor {
+                let mut visitor = DeriveVisitor {
                     results: &mut results,
                     name_filter,
                     editor: self,
+                    include_comments,
                 };
 
                 for item in &self.syntax_tree.items {
                     syn::visit::visit_item(&mut visitor, item);
                 }
             }
-            _ => anyhow::bail!("Unsupported node type: {}", node_type),
-        }
-
-        Ok(results)
-    }
+            "attribute" => {
+                // Find every outer attribute on an item, method, field or variant, one result
+                // per attribute; the name filter matches the attribute's path (`deprecated`,
+                // `cfg`, `tokio::test` or just `test`)
+                struct AttributeVisitor<'a> {
+                    results: &'a mut Vec<InspectResult>,
+                    name_filter: Option<&'a str>,
+                    editor: &'a RustEditor,
+                    include_comments: bool,
+                    /// The struct, enum, impl or trait being visited, to qualify its members
+                    container: Option<String>,
+                }
 
-    /// Format an ExprStruct node as a string - extracts original source
-    fn format_expr_struct(&self, expr: &syn::ExprStruct) -> String {
-        // Extract the original source code from the file content using the span
-        let start = self.span_to_byte_offset(expr.span().start());
-        let end = self.span_to_byte_offset(expr.span().end());
+                impl AttributeVisitor<'_> {
+                    fn push(&mut self, kind: &str, name: &str, attrs: &[syn::Attribute]) {
+                        for attr in attrs {
+                            if !matches!(attr.style, syn::AttrStyle::Outer) {
+                                continue;
+                            }
+                            let path = attr
+                                .path()
+                                .segments
+                                .iter()
+                                .map(|seg| seg.ident.to_string())
+                                .collect::<Vec<_>>()
+                                .join("::");
+                            if let Some(filter) = self.name_filter
+                                && path != filter
+                                && path.rsplit("::").next() != Some(filter)
+                            {
+                                continue;
+                            }
 
-        // Get the original text and collapse to single line
-        let original = &self.content[start..end];
+                            let span = attr.span();
+                            let start = self.editor.span_to_byte_offset(span.start());
+                            let end = self.editor.span_to_byte_offset(span.end());
+                            let text = self
+                                .editor
+                                .content
+                                .get(start..end)
+                                .unwrap_or_default()
+                                .split_whitespace()
+                                .collect::<Vec<_>>()
+                                .join(" ");
+                            let location = self.editor.span_to_location(span);
+                            let preceding_comment = if self.include_comments {
+                                extract_preceding_comment(&self.editor.content, location.line)
+                            } else {
+                                None
+                            };
 
-        // Replace multiple whitespace/newlines with single space for single-line format
-        original.split_whitespace().collect::<Vec<_>>().join(" ")
-    }
+                            self.results.push(InspectResult {
+                                file_path: String::new(),
+                                node_type: "attribute".to_string(),
+                                identifier: name.to_string(),
+                                location,
+                                snippet: format!("{} {} {}", text, kind, name),
+                                preceding_comment,
+                                arity: None,
+                            });
+                        }
+                    }
 
-    /// Format a match arm as a string - extracts original source
-    fn format_match_arm(&self, arm: &syn::Arm) -> String {
-        // Extract the original source code from the file content using the span
-        let start = self.span_to_byte_offset(arm.span().start());
-        let end = self.span_to_byte_offset(arm.span().end());
+                    fn member(&self, separator: &str, name: &str) -> String {
+                        self.container.as_ref().map_or_else(
+                            || name.to_string(),
+                            |container| format!("{}{}{}", container, separator, name),
+                        )
+                    }
 
-        // Get the original text and collapse to single line
-        let original = &self.content[start..end];
+                    fn visit_in<F: FnOnce(&mut Self)>(&mut self, container: String, visit: F) {
+                        let prev = self.container.replace(container);
+                        visit(self);
+                        self.container = prev;
+                    }
+                }
 
-        // Replace multiple whitespace/newlines with single space for single-line format
-        original.split_whitespace().collect::<Vec<_>>().join(" ")
-    }
+                impl<'ast> Visit<'ast> for AttributeVisitor<'_> {
+                    fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
+                        self.push("struct", &node.ident.to_string(), &node.attrs);
+                        self.visit_in(node.ident.to_string(), |v| {
+                            syn::visit::visit_item_struct(v, node);
+                        });
+                    }
 
-    /// Format an ExprPath node as a string - extracts original source
-    fn format_expr_path(&self, expr: &syn::ExprPath) -> String {
-        // Extract the original source code from the file content using the span
-        let start = self.span_to_byte_offset(expr.span().start());
-        let end = self.span_to_byte_offset(expr.span().end());
+                    fn visit_item_enum(&mut self, node: &'ast ItemEnum) {
+                        self.push("enum", &node.ident.to_string(), &node.attrs);
+                        self.visit_in(node.ident.to_string(), |v| {
+                            syn::visit::visit_item_enum(v, node);
+                        });
+                    }
 
-        // Get the original text and collapse to single line
-        let original = &self.content[start..end];
+                    fn visit_item_union(&mut self, node: &'ast syn::ItemUnion) {
+                        self.push("union", &node.ident.to_string(), &node.attrs);
+                        self.visit_in(node.ident.to_string(), |v| {
+                            syn::visit::visit_item_union(v, node);
+                        });
+                    }
 
-        // Replace multiple whitespace/newlines with single space for single-line format
-        original.split_whitespace().collect::<Vec<_>>().join(" ")
-    }
+                    fn visit_field(&mut self, node: &'ast syn::Field) {
+                        if let Some(ident) = &node.ident {
+                            let name = self.member(".", &ident.to_string());
+                            self.push("field", &name, &node.attrs);
+                        }
+                        syn::visit::visit_field(self, node);
+                    }
 
-    /// Format an ExprCall node as a string - extracts original source
-    fn format_expr_call(&self, expr: &syn::ExprCall) -> String {
-        // Extract the original source code from the file content using the span
-        let start = self.span_to_byte_offset(expr.span().start());
-        let end = self.span_to_byte_offset(expr.span().end());
+                    fn visit_variant(&mut self, node: &'ast syn::Variant) {
+                        let name = self.member("::", &node.ident.to_string());
+                        self.push("variant", &name, &node.attrs);
+                        syn::visit::visit_variant(self, node);
+                    }
 
-        // Get the original text and collapse to single line
-        let original = &self.content[start..end];
+                    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+                        self.push("fn", &node.sig.ident.to_string(), &node.attrs);
+                        syn::visit::visit_item_fn(self, node);
+                    }
 
-        // Replace multiple whitespace/newlines with single space for single-line format
-        original.split_whitespace().collect::<Vec<_>>().join(" ")
-    }
+                    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+                        let type_name = match &*node.self_ty {
+                            syn::Type::Path(type_path) => type_path
+                                .path
+                                .segments
+                                .last()
+                                .map(|seg| seg.ident.to_string())
+                                .unwrap_or_default(),
+                            other => other.to_token_stream().to_string(),
+                        };
+                        self.push("impl", &type_name, &node.attrs);
+                        self.visit_in(type_name, |v| syn::visit::visit_item_impl(v, node));
+                    }
 
-    /// Format an ExprMethodCall node as a string - extracts original source
-    fn format_expr_method_call(&self, expr: &syn::ExprMethodCall) -> String {
-        // Extract the original source code from the file content using the span
-        let start = self.span_to_byte_offset(expr.span().start());
-        let end = self.span_to_byte_offset(expr.span().end());
+                    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+                        let name = self.member("::", &node.sig.ident.to_string());
+                        self.push("fn", &name, &node.attrs);
+                        syn::visit::visit_impl_item_fn(self, node);
+                    }
 
-        // Get the original text and collapse to single line
-        let original = &self.content[start..end];
+                    fn visit_item_trait(&mut self, node: &'ast syn::ItemTrait) {
+                        self.push("trait", &node.ident.to_string(), &node.attrs);
+                        self.visit_in(node.ident.to_string(), |v| {
+                            syn::visit::visit_item_trait(v, node);
+                        });
+                    }
 
-        // Replace multiple whitespace/newlines with single space for single-line format
-        original.split_whitespace().collect::<Vec<_>>().join(" ")
-    }
+                    fn visit_trait_item_fn(&mut self, node: &'ast syn::TraitItemFn) {
+                        let name = self.member("::", &node.sig.ident.to_string());
+                        self.push("fn", &name, &node.attrs);
+                        syn::visit::visit_trait_item_fn(self, node);
+                    }
 
-    /// Format an Ident node as a string - just return the identifier
-    fn format_ident(&self, ident: &syn::Ident) -> String {
-        ident.to_string()
-    }
+                    fn visit_item_const(&mut self, node: &'ast syn::ItemConst) {
+                        self.push("const", &node.ident.to_string(), &node.attrs);
+                        syn::visit::visit_item_const(self, node);
+                    }
 
-    /// Format a TypePath node as a string - extracts original source
-    fn format_type_path(&self, ty: &syn::TypePath) -> String {
-        // Extract the original source code from the file content using the span
-        let start = self.span_to_byte_offset(ty.span().start());
-        let end = self.span_to_byte_offset(ty.span().end());
+                    fn visit_item_static(&mut self, node: &'ast syn::ItemStatic) {
+                        self.push("static", &node.ident.to_string(), &node.attrs);
+                        syn::visit::visit_item_static(self, node);
+                    }
 
-        // Get the original text and collapse to single line
-        let original = &self.content[start..end];
+                    fn visit_item_type(&mut self, node: &'ast syn::ItemType) {
+                        self.push("type", &node.ident.to_string(), &node.attrs);
+                        syn::visit::visit_item_type(self, node);
+                    }
 
-        // Replace multiple whitespace/newlines with single space for single-line format
-        original.split_whitespace().collect::<Vec<_>>().join(" ")
-    }
+                    fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+                        self.push("mod", &node.ident.to_string(), &node.attrs);
+                        // Items inside a module aren't its members
+                        let prev = self.container.take();
+                        syn::visit::visit_item_mod(self, node);
+                        self.container = prev;
+                    }
+                }
 
-    /// Format an ExprMacro node as a string - extracts original source
-    fn format_expr_macro(&self, expr: &syn::ExprMacro) -> String {
-        // Extract the original source code from the file content using the span
-        let start = self.span_to_byte_offset(expr.span().start());
-        let end = self.span_to_byte_offset(expr.span().end());
+                let mut visitor = AttributeVisitor {
+                    results: &mut results,
+                    name_filter,
+                    editor: self,
+                    include_comments,
+                    container: None,
+                };
 
-        // Get the original text and collapse to single line
-        let original = &self.content[start..end];
+                for item in &self.syntax_tree.items {
+                    syn::visit::visit_item(&mut visitor, item);
+                }
+            }
+            "where-clause" => {
+                // Find every item with trait bounds on its generics, inline (`<T: Send>`) or in
+                // a where clause; the name filter matches the bound's trait path
+                struct WhereClauseVisitor<'a> {
+                    results: &'a mut Vec<InspectResult>,
+                    name_filter: Option<&'a str>,
+                    editor: &'a RustEditor,
+                    include_comments: bool,
+                    impl_type: Option<String>,
+                }
 
-        // Replace multiple whitespace/newlines with single space for single-line format
-        original.split_whitespace().collect::<Vec<_>>().join(" ")
-    }
+                impl WhereClauseVisitor<'_> {
+                    /// Report the item `name` when one of `bounds` matches; its signature is the
+                    /// source from `start` up to `end`
+                    fn push(
+                        &mut self,
+                        name: &str,
+                        bounds: &[&syn::Path],
+                        start: LineColumn,
+                        end: LineColumn,
+                    ) {
+                        let matched = bounds.iter().any(|path| {
+                            self.name_filter.is_none_or(|filter| {
+                                let full = path
+                                    .segments
+                                    .iter()
+                                    .map(|seg| seg.ident.to_string())
+                                    .collect::<Vec<_>>()
+                                    .join("::");
+                                full == filter
+                                    || path.segments.last().is_some_and(|seg| seg.ident == filter)
+                            })
+                        });
+                        if !matched {
+                            return;
+                        }
 
-    /// Format a StmtMacro node as a string - extracts original source
-    fn format_stmt_macro(&self, stmt: &syn::StmtMacro) -> String {
-        // Extract the original source code from the file content using the span
-        let start = self.span_to_byte_offset(stmt.span().start());
-        let end = self.span_to_byte_offset(stmt.span().end());
+                        let (from, to) = (
+                            self.editor.span_to_byte_offset(start),
+                            self.editor.span_to_byte_offset(end),
+                        );
+                        let signature = self
+                            .editor
+                            .content
+                            .get(from..to)
+                            .unwrap_or_default()
+                            .split_whitespace()
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                        let location = NodeLocation {
+                            line: start.line,
+                            column: start.column,
+                            end_line: end.line,
+                            end_column: end.column,
+                        };
+                        let preceding_comment = if self.include_comments {
+                            extract_preceding_comment(&self.editor.content, location.line)
+                        } else {
+                            None
+                        };
 
-        // Get the original text and collapse to single line
-        let original = &self.content[start..end];
+                        self.results.push(InspectResult {
+                            file_path: String::new(),
+                            node_type: "where-clause".to_string(),
+                            identifier: name.to_string(),
+                            location,
+                            snippet: signature,
+                            preceding_comment,
+                            arity: None,
+                        });
+                    }
+                }
 
-        // Replace multiple whitespace/newlines with single space for single-line format
-        original.split_whitespace().collect::<Vec<_>>().join(" ")
-    }
+                /// Trait paths bounding the type parameters of `generics`
+                fn bound_paths(generics: &syn::Generics) -> Vec<&syn::Path> {
+                    let inline = generics.params.iter().filter_map(|param| match param {
+                        syn::GenericParam::Type(ty) => Some(&ty.bounds),
+                        _ => None,
+                    });
+                    let predicates = generics
+                        .where_clause
+                        .iter()
+                        .flat_map(|clause| &clause.predicates)
+                        .filter_map(|predicate| match predicate {
+                            syn::WherePredicate::Type(ty) => Some(&ty.bounds),
+                            _ => None,
+                        });
+                    inline
+                        .chain(predicates)
+                        .flatten()
+                        .filter_map(|bound| match bound {
+                            syn::TypeParamBound::Trait(t) => Some(&t.path),
+                            _ => None,
+                        })
+                        .collect()
+                }
 
-    /// Format an ItemStruct node as a string - extracts original source
-    fn format_item_struct(&self, item: &syn::ItemStruct) -> String {
-        let start = self.span_to_byte_offset(item.span().start());
-        let end = self.span_to_byte_offset(item.span().end());
-        let original = &self.content[start..end];
-        original.to_string()
-    }
+                impl<'ast> Visit<'ast> for WhereClauseVisitor<'_> {
+                    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+                        let span = node.sig.span();
+                        self.push(
+                            &node.sig.ident.to_string(),
+                            &bound_paths(&node.sig.generics),
+                            span.start(),
+                            span.end(),
+                        );
+                        syn::visit::visit_item_fn(self, node);
+                    }
 
-    /// Format an ItemEnum node as a string - extracts original source
-    fn format_item_enum(&self, item: &syn::ItemEnum) -> String {
-        let start = self.span_to_byte_offset(item.span().start());
-        let end = self.span_to_byte_offset(item.span().end());
-        let original = &self.content[start..end];
-        original.to_string()
+                    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+                        let type_name = match &*node.self_ty {
+                            syn::Type::Path(type_path) => type_path
+                                .path
+                                .segments
+                                .last()
+                                .map(|seg| seg.ident.to_string())
+                                .unwrap_or_default(),
+                            other => other.to_token_stream().to_string(),
+                        };
+                        let start = node.unsafety.map_or(node.impl_token.span, |u| u.span);
+                        self.push(
+                            &type_name,
+                            &bound_paths(&node.generics),
+                            start.start(),
+                            node.brace_token.span.open().start(),
+                        );
+                        let prev = self.impl_type.replace(type_name);
+                        syn::visit::visit_item_impl(self, node);
+                        self.impl_type = prev;
+                    }
+
+                    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+                        let name = self.impl_type.as_ref().map_or_else(
+                            || node.sig.ident.to_string(),
+                            |impl_type| format!("{}::{}", impl_type, node.sig.ident),
+                        );
+                        let span = node.sig.span();
+                        self.push(
+                            &name,
+                            &bound_paths(&node.sig.generics),
+                            span.start(),
+                            span.end(),
+                        );
+                        syn::visit::visit_impl_item_fn(self, node);
+                    }
+
+                    fn visit_item_trait(&mut self, node: &'ast syn::ItemTrait) {
+                        // Supertraits bound `Self`
+                        let mut bounds = bound_paths(&node.generics);
+                        bounds.extend(node.supertraits.iter().filter_map(|bound| match bound {
+                            syn::TypeParamBound::Trait(t) => Some(&t.path),
+                            _ => None,
+                        }));
+                        let start = node.unsafety.map_or(node.trait_token.span, |u| u.span);
+                        self.push(
+                            &node.ident.to_string(),
+                            &bounds,
+                            start.start(),
+                            node.brace_token.span.open().start(),
+                        );
+                        let prev = self.impl_type.replace(node.ident.to_string());
+                        syn::visit::visit_item_trait(self, node);
+                        self.impl_type = prev;
+                    }
+
+                    fn visit_trait_item_fn(&mut self, node: &'ast syn::TraitItemFn) {
+                        let name = self.impl_type.as_ref().map_or_else(
+                            || node.sig.ident.to_string(),
+                            |trait_name| format!("{}::{}", trait_name, node.sig.ident),
+                        );
+                        let span = node.sig.span();
+                        self.push(
+                            &name,
+                            &bound_paths(&node.sig.generics),
+                            span.start(),
+                            span.end(),
+                        );
+                        syn::visit::visit_trait_item_fn(self, node);
+                    }
+
+                    fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
+                        let end = match &node.fields {
+                            syn::Fields::Named(fields) => fields.brace_token.span.open().start(),
+                            _ => node
+                                .semi_token
+                                .map_or_else(|| node.span().end(), |semi| semi.span.start()),
+                        };
+                        self.push(
+                            &node.ident.to_string(),
+                            &bound_paths(&node.generics),
+                            node.struct_token.span.start(),
+                            end,
+                        );
+                        syn::visit::visit_item_struct(self, node);
+                    }
+
+                    fn visit_item_enum(&mut self, node: &'ast ItemEnum) {
+                        self.push(
+                            &node.ident.to_string(),
+                            &bound_paths(&node.generics),
+                            node.enum_token.span.start(),
+                            node.brace_token.span.open().start(),
+                        );
+                        syn::visit::visit_item_enum(self, node);
+                    }
+
+                    fn visit_item_union(&mut self, node: &'ast syn::ItemUnion) {
+                        self.push(
+                            &node.ident.to_string(),
+                            &bound_paths(&node.generics),
+                            node.union_token.span.start(),
+                            node.fields.brace_token.span.open().start(),
+                        );
+                        syn::visit::visit_item_union(self, node);
+                    }
+
+                    fn visit_item_type(&mut self, node: &'ast syn::ItemType) {
+                        self.push(
+                            &node.ident.to_string(),
+                            &bound_paths(&node.generics),
+                            node.type_token.span.start(),
+                            node.eq_token.span.start(),
+                        );
+                        syn::visit::visit_item_type(self, node);
+                    }
+                }
+
+                let mut visitor = WhereClauseVisitor {
+                    results: &mut results,
+                    name_filter,
+                    editor: self,
+                    include_comments,
+                    impl_type: None,
+                };
+
+                for item in &self.syntax_tree.items {
+                    syn::visit::visit_item(&mut visitor, item);
+                }
+            }
+            "trait-impl" => {
+                // Find all trait impl blocks (impl Trait for Type)
+                struct TraitImplVisitor<'a> {
+                    results: &'a mut Vec<InspectResult>,
+                    name_filter: Option<&'a str>,
+                    editor: &'a RustEditor,
+                }
+
+                impl<'ast, 'a> Visit<'ast> for TraitImplVisitor<'a> {
+                    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+                        // Only emit when there's a trait clause
+                        if node.trait_.is_none() {
+                            syn::visit::visit_item_impl(self, node);
+                            return;
+                        }
+
+                        let trait_path = &node.trait_.as_ref().unwrap().1;
+                        let trait_name = trait_path
+                            .segments
+                            .last()
+                            .map(|seg| seg.ident.to_string())
+                            .unwrap_or_default();
+
+                        // Apply name filter to the trait name (same wildcard semantics as
+                        // struct-literal)
+                        if let Some(filter) = self.name_filter {
+                            #[allow(clippy::option_if_let_else)]
+                            let matches = if filter.contains("::") {
+                                if let Some(target) = filter.strip_prefix("*::") {
+                                    trait_path
+                                        .segments
+                                        .last()
+                                        .map(|seg| seg.ident == target)
+                                        .unwrap_or(false)
+                                } else {
+                                    let path_str = trait_path
+                                        .segments
+                                        .iter()
+                                        .map(|seg| seg.ident.to_string())
+                                        .collect::<Vec<_>>()
+                                        .join("::");
+                                    path_str == filter
+                                }
+                            } else {
+                                trait_path
+                                    .segments
+                                    .last()
+                                    .map(|seg| seg.ident == filter)
+                                    .unwrap_or(false)
+                            };
+                            if !matches {
+                                syn::visit::visit_item_impl(self, node);
+                                return;
+                            }
+                        }
+
+                        // Extract the implementing type name
+                        let type_name = match &*node.self_ty {
+                            syn::Type::Path(tp) => tp
+                                .path
+                                .segments
+                                .last()
+                                .map(|seg| seg.ident.to_string())
+                                .unwrap_or_else(|| quote::quote!(#tp).to_string()),
+                            other => quote::quote!(#other).to_string(),
+                        };
+
+                        let identifier = format!("{} for {}", trait_name, type_name);
+                        let snippet = format!("impl {} for {} {{ ... }}", trait_name, type_name);
+                        let location = self.editor.span_to_location(node.impl_token.span);
+
+                        self.results.push(InspectResult {
+                            file_path: String::new(),
+                            node_type: "trait-impl".to_string(),
+                            identifier,
+                            location,
+                            snippet,
+                            preceding_comment: None,
+                            arity: None,
+                        });
+
+                        syn::visit::visit_item_impl(self, node);
+                    }
+                }
+
+                let mut visitor = TraitImplVisitor {
+                    results: &mut results,
+                    name_filter,
+                    editor: self,
+                };
+
+                for item in &self.syntax_tree.items {
+                    syn::visit::visit_item(&mut visitor, item);
+                }
+            }
+            "impl" => {
+                // One summary per impl block (inherent or trait): its header and method names.
+                // The name filter matches the implementing type.
+                struct ImplBlockVisitor<'a> {
+                    results: &'a mut Vec<InspectResult>,
+                    name_filter: Option<&'a str>,
+                    editor: &'a RustEditor,
+                    include_comments: bool,
+                }
+
+                impl<'ast> Visit<'ast> for ImplBlockVisitor<'_> {
+                    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+                        let type_name = match &*node.self_ty {
+                            syn::Type::Path(tp) => tp
+                                .path
+                                .segments
+                                .last()
+                                .map(|seg| seg.ident.to_string())
+                                .unwrap_or_default(),
+                            other => quote::quote!(#other).to_string(),
+                        };
+                        if self.name_filter.is_some_and(|filter| filter != type_name) {
+                            syn::visit::visit_item_impl(self, node);
+                            return;
+                        }
+
+                        let identifier = node.trait_.as_ref().map_or_else(
+                            || type_name.clone(),
+                            |(_, path, _)| {
+                                let trait_name = path
+                                    .segments
+                                    .last()
+                                    .map(|seg| seg.ident.to_string())
+                                    .unwrap_or_default();
+                                format!("{} for {}", trait_name, type_name)
+                            },
+                        );
+
+                        // Header as written (generics, trait path), minus attributes
+                        let start = self
+                            .editor
+                            .span_to_byte_offset(node.impl_token.span.start());
+                        let end = self
+                            .editor
+                            .span_to_byte_offset(node.brace_token.span.open().start());
+                        let header = self.editor.content[start..end]
+                            .split_whitespace()
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                        let methods: Vec<String> = node
+                            .items
+                            .iter()
+                            .filter_map(|item| match item {
+                                syn::ImplItem::Fn(method) => Some(method.sig.ident.to_string()),
+                                _ => None,
+                            })
+                            .collect();
+                        let snippet = if methods.is_empty() {
+                            format!("{} {{}}", header)
+                        } else {
+                            format!("{} {{ {} }}", header, methods.join(", "))
+                        };
+
+                        let location = self.editor.span_to_location(node.span());
+                        let preceding_comment = if self.include_comments {
+                            extract_preceding_comment(&self.editor.content, location.line)
+                        } else {
+                            None
+                        };
+
+                        self.results.push(InspectResult {
+                            file_path: String::new(),
+                            node_type: "impl".to_string(),
+                            identifier,
+                            location,
+                            snippet,
+                            preceding_comment,
+                            arity: None,
+                        });
+
+                        syn::visit::visit_item_impl(self, node);
+                    }
+                }
+
+                let mut visitor = ImplBlockVisitor {
+                    results: &mut results,
+                    name_filter,
+                    editor: self,
+                    include_comments,
+                };
+
+                for item in &self.syntax_tree.items {
+                    syn::visit::visit_item(&mut visitor, item);
+                }
+            }
+            _ => anyhow::bail!("Unsupported node type: {}", node_type),
+        }
+
+        Ok(results)
+    }
+
+    /// Format an ExprStruct node as a string - extracts original source
+    fn format_expr_struct(&self, expr: &syn::ExprStruct) -> String {
+        // Extract the original source code from the file content using the span
+        let start = self.span_to_byte_offset(expr.span().start());
+        let end = self.span_to_byte_offset(expr.span().end());
+
+        // Get the original text and collapse to single line
+        let original = &self.content[start..end];
+
+        // Replace multiple whitespace/newlines with single space for single-line format
+        original.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// Format a match arm as a string - extracts original source
+    fn format_match_arm(&self, arm: &syn::Arm) -> String {
+        // Extract the original source code from the file content using the span
+        let start = self.span_to_byte_offset(arm.span().start());
+        let end = self.span_to_byte_offset(arm.span().end());
+
+        // Get the original text and collapse to single line
+        let original = &self.content[start..end];
+
+        // Replace multiple whitespace/newlines with single space for single-line format
+        original.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// Format an ExprPath node as a string - extracts original source
+    fn format_expr_path(&self, expr: &syn::ExprPath) -> String {
+        // Extract the original source code from the file content using the span
+        let start = self.span_to_byte_offset(expr.span().start());
+        let end = self.span_to_byte_offset(expr.span().end());
+
+        // Get the original text and collapse to single line
+        let original = &self.content[start..end];
+
+        // Replace multiple whitespace/newlines with single space for single-line format
+        original.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// Format an ExprCall node as a string - extracts original source
+    fn format_expr_call(&self, expr: &syn::ExprCall) -> String {
+        // Extract the original source code from the file content using the span
+        let start = self.span_to_byte_offset(expr.span().start());
+        let end = self.span_to_byte_offset(expr.span().end());
+
+        // Get the original text and collapse to single line
+        let original = &self.content[start..end];
+
+        // Replace multiple whitespace/newlines with single space for single-line format
+        original.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// Format an ExprMethodCall node as a string - extracts original source
+    fn format_expr_method_call(&self, expr: &syn::ExprMethodCall) -> String {
+        // Extract the original source code from the file content using the span
+        let start = self.span_to_byte_offset(expr.span().start());
+        let end = self.span_to_byte_offset(expr.span().end());
+
+        // Get the original text and collapse to single line
+        let original = &self.content[start..end];
+
+        // Replace multiple whitespace/newlines with single space for single-line format
+        original.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// Format an Ident node as a string - just return the identifier
+    fn format_ident(&self, ident: &syn::Ident) -> String {
+        ident.to_string()
+    }
+
+    /// The text of 1-based `line`, trimmed.
+    fn source_line(&self, line: usize) -> String {
+        self.content
+            .lines()
+            .nth(line.saturating_sub(1))
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    }
+
+    /// Format a TypePath node as a string - extracts original source
+    fn format_type_path(&self, ty: &syn::TypePath) -> String {
+        // Extract the original source code from the file content using the span
+        let start = self.span_to_byte_offset(ty.span().start());
+        let end = self.span_to_byte_offset(ty.span().end());
+
+        // Get the original text and collapse to single line
+        let original = &self.content[start..end];
+
+        // Replace multiple whitespace/newlines with single space for single-line format
+        original.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// Format an ExprMacro node as a string - extracts original source
+    fn format_expr_macro(&self, expr: &syn::ExprMacro) -> String {
+        // Extract the original source code from the file content using the span
+        let start = self.span_to_byte_offset(expr.span().start());
+        let end = self.span_to_byte_offset(expr.span().end());
+
+        // Get the original text and collapse to single line
+        let original = &self.content[start..end];
+
+        // Replace multiple whitespace/newlines with single space for single-line format
+        original.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// Format a StmtMacro node as a string - extracts original source
+    fn format_stmt_macro(&self, stmt: &syn::StmtMacro) -> String {
+        // Extract the original source code from the file content using the span
+        let start = self.span_to_byte_offset(stmt.span().start());
+        let end = self.span_to_byte_offset(stmt.span().end());
+
+        // Get the original text and collapse to single line
+        let original = &self.content[start..end];
+
+        // Replace multiple whitespace/newlines with single space for single-line format
+        original.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// Format an ItemStruct node as a string - extracts original source
+    fn format_item_struct(&self, item: &syn::ItemStruct) -> String {
+        let start = self.span_to_byte_offset(item.span().start());
+        let end = self.span_to_byte_offset(item.span().end());
+        let original = &self.content[start..end];
+        original.to_string()
+    }
+
+    /// Format an ItemEnum node as a string - extracts original source
+    fn format_item_enum(&self, item: &syn::ItemEnum) -> String {
+        let start = self.span_to_byte_offset(item.span().start());
+        let end = self.span_to_byte_offset(item.span().end());
+        let original = &self.content[start..end];
+        original.to_string()
+    }
+
+    /// Format an ItemEnum showing only a specific variant
+    fn format_item_enum_variant_only(&self, item: &syn::ItemEnum, variant_name: &str) -> String {
+        // Find the matching variant
+        let variant = item.variants.iter().find(|v| v.ident == variant_name);
+
+        variant.map_or_else(
+            || self.format_item_enum(item),
+            |variant| {
+                // Extract the enum header (visibility, enum keyword, name, generics, where clause)
+                let enum_start = self.span_to_byte_offset(item.span().start());
+                let variants_start = if !item.variants.is_empty() {
+                    self.span_to_byte_offset(item.variants.first().unwrap().span().start())
+                } else {
+                    self.span_to_byte_offset(item.span().end())
+                };
+
+                // Get everything before the first variant (header)
+                let header = &self.content[enum_start..variants_start].trim_end();
+
+                // Extract the specific variant source
+                let variant_start = self.span_to_byte_offset(variant.span().start());
+                let variant_end = self.span_to_byte_offset(variant.span().end());
+                let variant_source = &self.content[variant_start..variant_end];
+
+                // Build the filtered output
+                format!(
+                    "{}\n    {},\n    // ... {} other variant{}\n}}",
+                    header,
+                    variant_source,
+                    item.variants.len() - 1,
+                    if item.variants.len() - 1 == 1 {
+                        ""
+                    } else {
+                        "s"
+                    }
+                )
+            },
+        )
+    }
+
+    /// Format an ItemFn node as a string - extracts original source
+    fn format_item_fn(&self, item: &syn::ItemFn) -> String {
+        let start = self.span_to_byte_offset(item.span().start());
+        let end = self.span_to_byte_offset(item.span().end());
+        let original = &self.content[start..end];
+        original.to_string()
+    }
+
+    /// Format an ImplItemFn node as a string - extracts original source
+    fn format_impl_item_fn(&self, item: &syn::ImplItemFn) -> String {
+        let start = self.span_to_byte_offset(item.span().start());
+        let end = self.span_to_byte_offset(item.span().end());
+        let original = &self.content[start..end];
+        original.to_string()
+    }
+
+    /// Format a TraitItemFn node as a string - extracts original source
+    fn format_trait_item_fn(&self, item: &syn::TraitItemFn) -> String {
+        let start = self.span_to_byte_offset(item.span().start());
+        let end = self.span_to_byte_offset(item.span().end());
+        let original = &self.content[start..end];
+        original.to_string()
+    }
+
+    /// Format an ItemTrait node as a string - extracts original source
+    fn format_item_trait(&self, item: &syn::ItemTrait) -> String {
+        let start = self.span_to_byte_offset(item.span().start());
+        let end = self.span_to_byte_offset(item.span().end());
+        let original = &self.content[start..end];
+        original.to_string()
+    }
+
+    /// Format an ItemConst node as a string - extracts original source
+    fn format_item_const(&self, item: &syn::ItemConst) -> String {
+        let start = self.span_to_byte_offset(item.span().start());
+        let end = self.span_to_byte_offset(item.span().end());
+        let original = &self.content[start..end];
+        original.to_string()
+    }
+
+    /// Format an ItemStatic node as a string - extracts original source
+    fn format_item_static(&self, item: &syn::ItemStatic) -> String {
+        let start = self.span_to_byte_offset(item.span().start());
+        let end = self.span_to_byte_offset(item.span().end());
+        let original = &self.content[start..end];
+        original.to_string()
+    }
+
+    /// Format an ItemType node as a string - extracts original source
+    fn format_item_type(&self, item: &syn::ItemType) -> String {
+        let start = self.span_to_byte_offset(item.span().start());
+        let end = self.span_to_byte_offset(item.span().end());
+        let original = &self.content[start..end];
+        original.to_string()
+    }
+
+    /// Format an ItemMod node as a string - extracts original source
+    fn format_item_mod(&self, item: &syn::ItemMod) -> String {
+        let start = self.span_to_byte_offset(item.span().start());
+        let end = self.span_to_byte_offset(item.span().end());
+        let original = &self.content[start..end];
+        original.to_string()
+    }
+
+    /// Find the index path of an item by type and name, inside inline modules too
+    #[allow(dead_code)]
+    pub(crate) fn find_item_path(&self, node_type: &str, name: &str) -> Result<Vec<usize>> {
+        for (path, _, item) in self.item_paths() {
+            match (node_type, item) {
+                ("struct", Item::Struct(s)) if s.ident == name => {
+                    return Ok(path);
+                }
+                ("enum", Item::Enum(e)) if e.ident == name => {
+                    return Ok(path);
+                }
+                ("union", Item::Union(u)) if u.ident == name => {
+                    return Ok(path);
+                }
+                ("fn", Item::Fn(f)) if f.sig.ident == name => {
+                    return Ok(path);
+                }
+                ("impl", Item::Impl(impl_block)) => {
+                    // For impl blocks, match on the self_ty
+                    if let syn::Type::Path(type_path) = &*impl_block.self_ty
+                        && let Some(segment) = type_path.path.segments.last()
+                        && segment.ident == name
+                    {
+                        return Ok(path);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        anyhow::bail!("Item '{}' of type '{}' not found", name, node_type)
+    }
+
+    /// Replace the item at an index path from `find_item_path` with a new item
+    #[allow(dead_code)]
+    pub(crate) fn replace_item_at_path(&mut self, path: &[usize], new_item: Item) -> Result<()> {
+        // Replace the item in the syntax tree
+        *self.item_at_mut(path) = new_item;
+
+        // Reformat the entire file using prettyplease
+        self.content = self.unparse_tree()?;
+
+        // Recompute line offsets
+        self.line_offsets = Self::compute_line_offsets(&self.content);
+
+        Ok(())
+    }
+
+    fn span_to_location(&self, span: Span) -> NodeLocation {
+        let start = span.start();
+        let end = span.end();
+
+        NodeLocation {
+            line: start.line,
+            column: start.column,
+            end_line: end.line,
+            end_column: end.column,
+        }
+    }
+
+    /// Generic transform operation - find matching nodes and apply action
+    pub(crate) fn transform(
+        &mut self,
+        op: &crate::operations::TransformOp,
+    ) -> Result<ModificationResult> {
+        use crate::operations::{InspectResult, TransformAction};
+
+        // First, use inspect to find all matching nodes (comments not needed for transform)
+        let matches = self.inspect(Some(&op.node_type), op.name_filter.as_deref(), None, false)?;
+
+        // Apply content filter if specified
+        let mut filtered_matches: Vec<InspectResult> =
+            if let Some(ref content_filter) = op.content_filter {
+                matches
+                    .into_iter()
+                    .filter(|m| m.snippet.contains(content_filter))
+                    .collect()
+            } else {
+                matches
+            };
+        if let Some(pattern) = &op.name_regex {
+            let regex = compile_name_regex(pattern)?;
+            filtered_matches.retain(|m| name_matches(&regex, &m.identifier));
+        }
+        self.retain_not_excluded(&mut filtered_matches, &op.exclude_names, &op.exclude_in);
+
+        if let TransformAction::RenameSignature { new_name } = &op.action {
+            return self.rename_signatures(&op.node_type, &filtered_matches, new_name);
+        }
+
+        if filtered_matches.is_empty() {
+            return Ok(ModificationResult {
+                changed: false,
+                modified_nodes: vec![],
+                unmatched_qualified_paths: None,
+            });
+        }
+
+        // Now apply the transformation action to each match
+        // We need to work backwards through the file to avoid offset issues
+        let mut sorted_matches = filtered_matches;
+        sorted_matches.sort_by(|a, b| {
+            b.location
+                .line
+                .cmp(&a.location.line)
+                .then(b.location.column.cmp(&a.location.column))
+        });
+
+        if let TransformAction::Wrap { with } = &op.action {
+            if !with.contains("{}") {
+                anyhow::bail!("Wrap template '{}' has no {{}} for the original code", with);
+            }
+            // `a.b().c()` matches as both `c` and `b`; wrapping the inner call too would
+            // shift the outer one's span, so only the outermost node is wrapped
+            retain_outermost(&mut sorted_matches, |m| {
+                WRAP_NODE_TYPES.contains(&m.node_type.as_str())
+            });
+        }
+
+        let replace_regex = match &op.action {
+            TransformAction::ReplaceRegex { pattern, .. } => {
+                let regex = regex::Regex::new(pattern)
+                    .map_err(|e| anyhow::anyhow!("Invalid --pattern '{}': {}", pattern, e))?;
+                // The outermost node's text already covers the nodes nested in it
+                retain_outermost(&mut sorted_matches, |_| true);
+                sorted_matches.dedup_by_key(|m| {
+                    let loc = &m.location;
+                    (loc.line, loc.column, loc.end_line, loc.end_column)
+                });
+                Some(regex)
+            }
+            _ => None,
+        };
+        #[cfg(feature = "script-hooks")]
+        let script_hook = match &op.action {
+            TransformAction::Script { script } => Some(crate::hooks::ScriptHook::load(script)?),
+            _ => None,
+        };
+
+        let statements = if matches!(op.action, TransformAction::CfgGuard { .. }) {
+            self.statement_extents()
+        } else {
+            Vec::new()
+        };
+
+        let mut modified_nodes = Vec::new();
+
+        for match_result in &sorted_matches {
+            // Create backup node
+            let backup_node = BackupNode {
+                node_type: match_result.node_type.clone(),
+                identifier: match_result.identifier.clone(),
+                original_content: match_result.snippet.clone(),
+                location: match_result.location.clone(),
+            };
+
+            // Find the byte offsets for this node
+            let start_offset = self.line_column_to_byte_offset(
+                match_result.location.line,
+                match_result.location.column,
+            )?;
+            let mut end_offset = self.line_column_to_byte_offset(
+                match_result.location.end_line,
+                match_result.location.end_column,
+            )?;
+
+            // Extract the original text
+            let original_text = &self.content[start_offset..end_offset];
+
+            // Apply the action
+            let replacement = match &op.action {
+                TransformAction::Comment => {
+                    // Comment out the code
+                    format!("// {}", original_text.replace("\n", "\n// "))
+                }
+                TransformAction::Remove => {
+                    // Remove the entire node
+                    String::new()
+                }
+                TransformAction::Replace { with } => {
+                    // Replace with provided code
+                    with.clone()
+                }
+                TransformAction::Wrap { with } => {
+                    if !WRAP_NODE_TYPES.contains(&match_result.node_type.as_str()) {
+                        continue;
+                    }
+                    with.replace("{}", original_text)
+                }
+                TransformAction::ToLetElse => {
+                    let rewritten = Self::rewrite_let_else(original_text);
+                    if rewritten == original_text {
+                        // Nothing with the recognized shape inside this node
+                        continue;
+                    }
+                    rewritten
+                }
+                TransformAction::SimplifyFormat => {
+                    let rewritten = Self::rewrite_simplify_format(original_text);
+                    if rewritten == original_text {
+                        continue;
+                    }
+                    rewritten
+                }
+                TransformAction::CfgGuard { cfg } => {
+                    let Some((guarded, guarded_end)) =
+                        self.cfg_guard(match_result, cfg, start_offset, end_offset, &statements)?
+                    else {
+                        // Already guarded, or an expression whose type a guard would change
+                        continue;
+                    };
+                    end_offset = guarded_end;
+                    guarded
+                }
+                TransformAction::ToConstructor {
+                    constructor,
+                    fields,
+                } => {
+                    if match_result.node_type != "struct-literal" {
+                        continue;
+                    }
+                    let fields = if fields.is_empty() {
+                        self.struct_field_names(&match_result.identifier)
+                            .with_context(|| {
+                                format!(
+                                    "Can't tell the field order of `{}` (line {}): its definition isn't in this file",
+                                    match_result.identifier, match_result.location.line
+                                )
+                            })?
+                    } else {
+                        fields.clone()
+                    };
+                    let line_start = self.content[..start_offset]
+                        .rfind('\n')
+                        .map_or(0, |i| i + 1);
+                    let indent: String = self.content[line_start..start_offset]
+                        .chars()
+                        .take_while(|c| c.is_whitespace())
+                        .collect();
+                    Self::struct_literal_to_constructor(
+                        original_text,
+                        constructor,
+                        &fields,
+                        &indent,
+                    )
+                    .with_context(|| {
+                        format!(
+                            "Can't convert the `{}` literal at line {} to a constructor call",
+                            match_result.identifier, match_result.location.line
+                        )
+                    })?
+                }
+                TransformAction::ToUfcs {
+                    trait_path,
+                    to_type,
+                    receiver,
+                } => {
+                    if match_result.node_type != "method-call" {
+                        continue;
+                    }
+                    // None for a call that doesn't pass the receiver filter, and for an
+                    // inner call already rewritten along with the call around it
+                    match Self::method_call_to_ufcs(
+                        original_text,
+                        &match_result.identifier,
+                        trait_path,
+                        to_type.as_deref(),
+                        receiver.as_deref(),
+                    )? {
+                        Some(rewritten) => rewritten,
+                        None => continue,
+                    }
+                }
+                // Handled by `rename_signatures` before the loop
+                TransformAction::RenameSignature { .. } => continue,
+                TransformAction::ReplaceRegex { with, .. } => {
+                    let Some(regex) = &replace_regex else {
+                        continue;
+                    };
+                    let rewritten = regex.replace_all(original_text, with.as_str()).into_owned();
+                    if rewritten == original_text {
+                        continue;
+                    }
+                    rewritten
+                }
+                #[cfg(feature = "script-hooks")]
+                TransformAction::Script { .. } => {
+                    let Some(hook) = &script_hook else {
+                        continue;
+                    };
+                    let rewritten = hook.run(original_text, match_result)?;
+                    if rewritten == original_text {
+                        continue;
+                    }
+                    rewritten
+                }
+                #[cfg(not(feature = "script-hooks"))]
+                TransformAction::Script { .. } => {
+                    anyhow::bail!("rs-hack was built without the `script-hooks` feature")
+                }
+            };
+
+            // Replace in content
+            self.content
+                .replace_range(start_offset..end_offset, &replacement);
+
+            // Recompute line offsets after each change
+            self.line_offsets = Self::compute_line_offsets(&self.content);
+
+            modified_nodes.push(backup_node);
+        }
+
+        // Re-parse the content if we made changes
+        if !modified_nodes.is_empty() {
+            // Don't reparse for now - we're doing text-level operations
+            // self.syntax_tree = syn::parse_str(&self.content)
+            //     .context("Failed to re-parse content after transformation")?;
+        }
+        // A pattern can match across token boundaries, so a substitution may break the syntax
+        if let TransformAction::ReplaceRegex { pattern, with } = &op.action
+            && !modified_nodes.is_empty()
+        {
+            self.syntax_tree = self.parse_content().with_context(|| {
+                format!(
+                    "Replacing '{}' with '{}' leaves the file unparseable",
+                    pattern, with
+                )
+            })?;
+        }
+
+        Ok(ModificationResult {
+            changed: !modified_nodes.is_empty(),
+            modified_nodes,
+            unmatched_qualified_paths: None,
+        })
+    }
+
+    /// `transform --action rename-signature`: the name in each matched definition's
+    /// signature becomes `new_name`. Every range is found in the untouched source before
+    /// anything is edited, so a definition nested in another match renames cleanly.
+    fn rename_signatures(
+        &mut self,
+        node_type: &str,
+        matches: &[crate::operations::InspectResult],
+        new_name: &str,
+    ) -> Result<ModificationResult> {
+        let Some(&(_, keyword)) = RENAME_SIGNATURE_NODE_TYPES
+            .iter()
+            .find(|(t, _)| *t == node_type)
+        else {
+            anyhow::bail!(
+                "--action rename-signature works on --node-type {}",
+                RENAME_SIGNATURE_NODE_TYPES
+                    .iter()
+                    .map(|(t, _)| *t)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        };
+        if syn::parse_str::<syn::Ident>(new_name).is_err() {
+            anyhow::bail!("'{}' isn't a valid name", new_name);
+        }
+
+        let mut edits = Vec::new();
+        for m in matches {
+            let start = self.line_column_to_byte_offset(m.location.line, m.location.column)?;
+            let end =
+                self.line_column_to_byte_offset(m.location.end_line, m.location.end_column)?;
+            let Some((name_start, name_end)) =
+                Self::signature_name_range(&self.content[start..end], keyword)
+            else {
+                continue;
+            };
+            edits.push((
+                start + name_start,
+                start + name_end,
+                BackupNode {
+                    node_type: m.node_type.clone(),
+                    identifier: m.identifier.clone(),
+                    original_content: m.snippet.clone(),
+                    location: m.location.clone(),
+                },
+            ));
+        }
+        edits.sort_by_key(|(start, _, _)| std::cmp::Reverse(*start));
+        edits.dedup_by_key(|(start, _, _)| *start);
+
+        let mut modified_nodes = Vec::new();
+        for (start, end, backup) in edits {
+            if self.content[start..end] == *new_name {
+                continue;
+            }
+            self.content.replace_range(start..end, new_name);
+            modified_nodes.push(backup);
+        }
+        if !modified_nodes.is_empty() {
+            self.line_offsets = Self::compute_line_offsets(&self.content);
+            self.syntax_tree = self
+                .parse_content()
+                .context("Failed to re-parse content after renaming signatures")?;
+        }
+        Ok(ModificationResult {
+            changed: !modified_nodes.is_empty(),
+            modified_nodes,
+            unmatched_qualified_paths: None,
+        })
+    }
+
+    /// Byte range of the name after `keyword` in a definition's source (attributes and
+    /// visibility come first as groups, so only the signature's own tokens are looked at).
+    fn signature_name_range(text: &str, keyword: &str) -> Option<(usize, usize)> {
+        use proc_macro2::TokenTree;
+
+        let tokens: proc_macro2::TokenStream = text.parse().ok()?;
+        let mut tokens = tokens.into_iter();
+        tokens.find(|t| matches!(t, TokenTree::Ident(i) if i == keyword))?;
+        let name = tokens.find_map(|t| match t {
+            TokenTree::Ident(i) if i != "mut" => Some(i),
+            _ => None,
+        })?;
+        if name == "_" {
+            return None;
+        }
+        // Spans of a freshly parsed string are relative to it
+        let pos = name.span().start();
+        let line_start: usize = text
+            .split_inclusive('\n')
+            .take(pos.line.saturating_sub(1))
+            .map(str::len)
+            .sum();
+        let start = text[line_start..]
+            .char_indices()
+            .nth(pos.column)
+            .map(|(i, _)| line_start + i)?;
+        Some((start, start + name.to_string().len()))
+    }
+
+    /// `recv.method(args)` as `Trait::<ToType>::method(recv, args)`, keeping the receiver's
+    /// and arguments' source text. `None` when `source` isn't a call to `method` (e.g. a
+    /// nested match whose text was already rewritten) or its receiver isn't `receiver_filter`.
+    fn method_call_to_ufcs(
+        source: &str,
+        method: &str,
+        trait_path: &str,
+        to_type: Option<&str>,
+        receiver_filter: Option<&str>,
+    ) -> Result<Option<String>> {
+        syn::parse_str::<syn::Path>(trait_path)
+            .with_context(|| format!("Invalid trait path '{}'", trait_path))?;
+        if let Some(to_type) = to_type {
+            syn::parse_str::<syn::Type>(to_type)
+                .with_context(|| format!("Invalid type '{}'", to_type))?;
+        }
+        let Ok(Expr::MethodCall(call)) = syn::parse_str::<Expr>(source) else {
+            return Ok(None);
+        };
+        if call.method != method {
+            return Ok(None);
+        }
+
+        let line_offsets = Self::compute_line_offsets(source);
+        let text = |node: &dyn Spanned| {
+            let span = node.span();
+            let offset = |pos: LineColumn| line_offsets[pos.line - 1] + pos.column;
+            &source[offset(span.start())..offset(span.end())]
+        };
+
+        // `(a + b).into()` passes `a + b`; any other receiver goes in as written
+        let receiver = match &*call.receiver {
+            Expr::Paren(paren) => text(&paren.expr),
+            other => text(other),
+        };
+        if receiver_filter.is_some_and(|filter| filter.trim() != receiver.trim()) {
+            return Ok(None);
+        }
+
+        let mut path = trait_path.trim().to_string();
+        if let Some(to_type) = to_type {
+            path.push_str(&format!("::<{}>", to_type.trim()));
+        }
+        let turbofish = call.turbofish.as_ref().map_or("", |t| text(t));
+        let args: Vec<&str> = std::iter::once(receiver)
+            .chain(call.args.iter().map(|arg| text(arg)))
+            .collect();
+        Ok(Some(format!(
+            "{}::{}{}({})",
+            path,
+            call.method,
+            turbofish,
+            args.join(", ")
+        )))
+    }
+
+    /// Named fields of the struct called `name` (last path segment) defined in this file,
+    /// in declaration order. `None` when there's no such definition, or it isn't unique, or
+    /// it's a tuple or unit struct.
+    pub fn struct_field_names(&self, name: &str) -> Option<Vec<String>> {
+        let name = name.rsplit("::").next().unwrap_or(name).trim();
+        let def = self.find_struct_def(name, None).ok()?;
+        match &def.fields {
+            Fields::Named(named) => Some(
+                named
+                    .named
+                    .iter()
+                    .filter_map(|f| f.ident.as_ref().map(|i| i.to_string()))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Rewrite the struct literal `source` as `Path::constructor(args)`, with the field
+    /// values as arguments in `fields` order.
+    ///
+    /// Fails on literals that can't be mapped: `..rest`, tuple-style members, fields missing
+    /// from or unknown to `fields`, and fields written out of order when moving a value
+    /// could change when its side effects run. A multi-line literal that doesn't fit on one
+    /// line gets one argument per line, indented from `indent`.
+    fn struct_literal_to_constructor(
+        source: &str,
+        constructor: &str,
+        fields: &[String],
+        indent: &str,
+    ) -> Result<String> {
+        let literal: syn::ExprStruct =
+            syn::parse_str(source).context("Failed to parse struct literal")?;
+        let path = source[..source.find('{').unwrap_or(source.len())].trim();
+        if literal.rest.is_some() || literal.dot2_token.is_some() {
+            anyhow::bail!(
+                "`{} {{ .. }}` uses `..` to fill the remaining fields, which `{}::{}` can't take",
+                path,
+                path,
+                constructor
+            );
+        }
+
+        let line_offsets = Self::compute_line_offsets(source);
+        let offset = |pos: LineColumn| {
+            line_offsets
+                .get(pos.line.saturating_sub(1))
+                .map_or(source.len(), |line_start| line_start + pos.column)
+        };
+
+        // (declaration index, value source, value is free of side effects)
+        let mut values: Vec<(usize, &str, bool)> = Vec::new();
+        for field in &literal.fields {
+            let syn::Member::Named(ident) = &field.member else {
+                anyhow::bail!(
+                    "`{}` sets a field by position; only named fields can be mapped",
+                    path
+                );
+            };
+            let name = ident.to_string();
+            let Some(index) = fields.iter().position(|f| *f == name) else {
+                anyhow::bail!(
+                    "`{}` sets `{}`, which isn't a field of its definition",
+                    path,
+                    name
+                );
+            };
+            let text = &source[offset(field.expr.span().start())..offset(field.expr.span().end())];
+            values.push((index, text, Self::is_pure_expr(&field.expr)));
+        }
+        if let Some(missing) = fields
+            .iter()
+            .enumerate()
+            .find(|(i, _)| !values.iter().any(|(index, _, _)| index == i))
+        {
+            anyhow::bail!("`{}` doesn't set `{}`", path, missing.1);
+        }
+        let in_order = values.windows(2).all(|pair| pair[0].0 < pair[1].0);
+        if !in_order && let Some((index, _, _)) = values.iter().find(|(_, _, pure)| !pure) {
+            anyhow::bail!(
+                "`{}` lists its fields out of declaration order and `{}` may have side effects; reorder the literal first",
+                path,
+                fields[*index]
+            );
+        }
+        values.sort_by_key(|(index, _, _)| *index);
+
+        let args: Vec<&str> = values.iter().map(|(_, text, _)| *text).collect();
+        let call = format!("{}::{}({})", path, constructor, args.join(", "));
+        if !source.contains('\n') || (indent.len() + call.len() <= 100 && !call.contains('\n')) {
+            return Ok(call);
+        }
+        let mut out = format!("{}::{}(\n", path, constructor);
+        for arg in args {
+            out.push_str(&format!("{}    {},\n", indent, arg));
+        }
+        out.push_str(&format!("{})", indent));
+        Ok(out)
+    }
+
+    /// Whether evaluating `expr` can't have side effects (so moving it is safe).
+    fn is_pure_expr(expr: &Expr) -> bool {
+        match expr {
+            Expr::Path(_) | Expr::Lit(_) | Expr::Closure(_) => true,
+            Expr::Field(f) => Self::is_pure_expr(&f.base),
+            Expr::Reference(r) => Self::is_pure_expr(&r.expr),
+            Expr::Paren(p) => Self::is_pure_expr(&p.expr),
+            _ => false,
+        }
     }
 
-    /// Format an ItemEnum showing only a specific variant
-    fn format_item_enum_variant_only(&self, item: &syn::ItemEnum, variant_name: &str) -> String {
-        // Find the matching variant
-        let variant = item.variants.iter().find(|v| v.ident == variant_name);
+    /// Drop matches whose own name matches one of `exclude_names`, or that sit inside a
+    /// function, impl/trait/type, or module whose name matches one of `exclude_in`.
+    /// Patterns are globs (`test_*`); qualified names also match on their last segment.
+    pub fn retain_not_excluded(
+        &self,
+        results: &mut Vec<crate::operations::InspectResult>,
+        exclude_names: &[String],
+        exclude_in: &[String],
+    ) {
+        use syn::visit::Visit;
+
+        if exclude_names.is_empty() && exclude_in.is_empty() {
+            return;
+        }
+
+        fn name_matches(name: &str, patterns: &[String]) -> bool {
+            let last = name.rsplit("::").next().unwrap_or(name);
+            patterns.iter().any(|pattern| {
+                glob::Pattern::new(pattern).map_or(pattern == name || pattern == last, |p| {
+                    p.matches(name) || p.matches(last)
+                })
+            })
+        }
+
+        /// (start, end, name) of every named scope in the file
+        struct ScopeCollector {
+            scopes: Vec<(LineColumn, LineColumn, String)>,
+        }
+
+        impl ScopeCollector {
+            fn push(&mut self, span: Span, name: String) {
+                self.scopes.push((span.start(), span.end(), name));
+            }
+        }
+
+        impl<'ast> Visit<'ast> for ScopeCollector {
+            fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+                self.push(node.span(), node.sig.ident.to_string());
+                syn::visit::visit_item_fn(self, node);
+            }
+
+            fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+                self.push(node.span(), node.sig.ident.to_string());
+                syn::visit::visit_impl_item_fn(self, node);
+            }
+
+            fn visit_trait_item_fn(&mut self, node: &'ast syn::TraitItemFn) {
+                self.push(node.span(), node.sig.ident.to_string());
+                syn::visit::visit_trait_item_fn(self, node);
+            }
+
+            fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+                if let syn::Type::Path(type_path) = node.self_ty.as_ref()
+                    && let Some(seg) = type_path.path.segments.last()
+                {
+                    self.push(node.span(), seg.ident.to_string());
+                }
+                syn::visit::visit_item_impl(self, node);
+            }
+
+            fn visit_item_trait(&mut self, node: &'ast syn::ItemTrait) {
+                self.push(node.span(), node.ident.to_string());
+                syn::visit::visit_item_trait(self, node);
+            }
 
-        variant.map_or_else(
-            || self.format_item_enum(item),
-            |variant| {
-                // Extract the enum header (visibility, enum keyword, name, generics, where clause)
-                let enum_start = self.span_to_byte_offset(item.span().start());
-                let variants_start = if !item.variants.is_empty() {
-                    self.span_to_byte_offset(item.variants.first().unwrap().span().start())
-                } else {
-                    self.span_to_byte_offset(item.span().end())
-                };
+            fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
+                self.push(node.span(), node.ident.to_string());
+                syn::visit::visit_item_struct(self, node);
+            }
 
-                // Get everything before the first variant (header)
-                let header = &self.content[enum_start..variants_start].trim_end();
+            fn visit_item_enum(&mut self, node: &'ast ItemEnum) {
+                self.push(node.span(), node.ident.to_string());
+                syn::visit::visit_item_enum(self, node);
+            }
 
-                // Extract the specific variant source
-                let variant_start = self.span_to_byte_offset(variant.span().start());
-                let variant_end = self.span_to_byte_offset(variant.span().end());
-                let variant_source = &self.content[variant_start..variant_end];
+            fn visit_item_mod(&mut self, node: &'ast syn::ItemMod) {
+                self.push(node.span(), node.ident.to_string());
+                syn::visit::visit_item_mod(self, node);
+            }
+        }
 
-                // Build the filtered output
-                format!(
-                    "{}\n    {},\n    // ... {} other variant{}\n}}",
-                    header,
-                    variant_source,
-                    item.variants.len() - 1,
-                    if item.variants.len() - 1 == 1 {
-                        ""
-                    } else {
-                        "s"
-                    }
-                )
-            },
-        )
-    }
+        let mut collector = ScopeCollector { scopes: Vec::new() };
+        if !exclude_in.is_empty() {
+            collector.visit_file(&self.syntax_tree);
+        }
+        let excluded_scopes: Vec<_> = collector
+            .scopes
+            .into_iter()
+            .filter(|(_, _, name)| name_matches(name, exclude_in))
+            .map(|(start, end, _)| ((start.line, start.column), (end.line, end.column)))
+            .collect();
 
-    /// Format an ItemFn node as a string - extracts original source
-    fn format_item_fn(&self, item: &syn::ItemFn) -> String {
-        let start = self.span_to_byte_offset(item.span().start());
-        let end = self.span_to_byte_offset(item.span().end());
-        let original = &self.content[start..end];
-        original.to_string()
+        results.retain(|result| {
+            if name_matches(&result.identifier, exclude_names) {
+                return false;
+            }
+            let loc = &result.location;
+            let node = ((loc.line, loc.column), (loc.end_line, loc.end_column));
+            // Strictly inside: a definition isn't "in" itself (that's --exclude-name)
+            !excluded_scopes
+                .iter()
+                .any(|&(start, end)| start <= node.0 && node.1 <= end && (start, end) != node)
+        });
     }
 
-    /// Format an ImplItemFn node as a string - extracts original source
-    fn format_impl_item_fn(&self, item: &syn::ImplItemFn) -> String {
-        let start = self.span_to_byte_offset(item.span().start());
-        let end = self.span_to_byte_offset(item.span().end());
-        let original = &self.content[start..end];
-        original.to_string()
-    }
+    /// Rewrite every `let x = if let P(v) = e { v } else { .. };` in `source` (an item's text)
+    /// into `let P(x) = e else { .. };`.
+    ///
+    /// Only the exact shape is touched: the then-branch must be just the single binding of `P`,
+    /// the else-branch must end in `return`/`break`/`continue` or a panicking macro, and the
+    /// `let` must have no type annotation. Anything else is left as is, as is source that
+    /// doesn't parse on its own.
+    fn rewrite_let_else(source: &str) -> String {
+        use syn::visit::Visit;
 
-    /// Format a TraitItemFn node as a string - extracts original source
-    fn format_trait_item_fn(&self, item: &syn::TraitItemFn) -> String {
-        let start = self.span_to_byte_offset(item.span().start());
-        let end = self.span_to_byte_offset(item.span().end());
-        let original = &self.content[start..end];
-        original.to_string()
-    }
+        struct LetElseCollector<'a> {
+            editor: &'a RustEditor,
+            edits: Vec<(usize, usize, String)>,
+        }
 
-    /// Format an ItemTrait node as a string - extracts original source
-    fn format_item_trait(&self, item: &syn::ItemTrait) -> String {
-        let start = self.span_to_byte_offset(item.span().start());
-        let end = self.span_to_byte_offset(item.span().end());
-        let original = &self.content[start..end];
-        original.to_string()
-    }
+        /// Names bound by a pattern (lowercase idents; `None`-style unit paths are skipped)
+        fn pattern_bindings(pat: &syn::Pat) -> Vec<&syn::PatIdent> {
+            struct Bindings<'ast>(Vec<&'ast syn::PatIdent>);
+            impl<'ast> Visit<'ast> for Bindings<'ast> {
+                fn visit_pat_ident(&mut self, node: &'ast syn::PatIdent) {
+                    if !node.ident.to_string().starts_with(char::is_uppercase) {
+                        self.0.push(node);
+                    }
+                    syn::visit::visit_pat_ident(self, node);
+                }
+            }
+            let mut bindings = Bindings(Vec::new());
+            bindings.visit_pat(pat);
+            bindings.0
+        }
 
-    /// Format an ItemConst node as a string - extracts original source
-    fn format_item_const(&self, item: &syn::ItemConst) -> String {
-        let start = self.span_to_byte_offset(item.span().start());
-        let end = self.span_to_byte_offset(item.span().end());
-        let original = &self.content[start..end];
-        original.to_string()
-    }
+        fn diverges(block: &syn::Block) -> bool {
+            let expr = match block.stmts.last() {
+                Some(syn::Stmt::Expr(expr, _)) => expr,
+                Some(syn::Stmt::Macro(stmt_macro)) => {
+                    return is_panicking_macro(&stmt_macro.mac);
+                }
+                _ => return false,
+            };
+            match expr {
+                Expr::Return(_) | Expr::Break(_) | Expr::Continue(_) => true,
+                Expr::Macro(expr_macro) => is_panicking_macro(&expr_macro.mac),
+                _ => false,
+            }
+        }
 
-    /// Format an ItemStatic node as a string - extracts original source
-    fn format_item_static(&self, item: &syn::ItemStatic) -> String {
-        let start = self.span_to_byte_offset(item.span().start());
-        let end = self.span_to_byte_offset(item.span().end());
-        let original = &self.content[start..end];
-        original.to_string()
-    }
+        fn is_panicking_macro(mac: &syn::Macro) -> bool {
+            mac.path.segments.last().is_some_and(|seg| {
+                matches!(
+                    seg.ident.to_string().as_str(),
+                    "panic" | "unreachable" | "todo" | "unimplemented"
+                )
+            })
+        }
 
-    /// Format an ItemType node as a string - extracts original source
-    fn format_item_type(&self, item: &syn::ItemType) -> String {
-        let start = self.span_to_byte_offset(item.span().start());
-        let end = self.span_to_byte_offset(item.span().end());
-        let original = &self.content[start..end];
-        original.to_string()
-    }
+        impl LetElseCollector<'_> {
+            fn text(&self, span: Span) -> &str {
+                let start = self.editor.span_to_byte_offset(span.start());
+                let end = self.editor.span_to_byte_offset(span.end());
+                &self.editor.content[start..end]
+            }
 
-    /// Format an ItemMod node as a string - extracts original source
-    fn format_item_mod(&self, item: &syn::ItemMod) -> String {
-        let start = self.span_to_byte_offset(item.span().start());
-        let end = self.span_to_byte_offset(item.span().end());
-        let original = &self.content[start..end];
-        original.to_string()
-    }
+            fn rewrite(&self, local: &syn::Local) -> Option<String> {
+                // `let x = ...` / `let mut x = ...`, no type annotation, no existing let-else
+                let syn::Pat::Ident(target) = &local.pat else {
+                    return None;
+                };
+                if target.subpat.is_some() || target.by_ref.is_some() {
+                    return None;
+                }
+                let init = local.init.as_ref()?;
+                if init.diverge.is_some() {
+                    return None;
+                }
 
-    /// Find the index of an item by type and name
-    #[allow(dead_code)]
-    pub(crate) fn find_item_index(&self, node_type: &str, name: &str) -> Result<usize> {
-        for (index, item) in self.syntax_tree.items.iter().enumerate() {
-            match (node_type, item) {
-                ("struct", Item::Struct(s)) if s.ident == name => {
-                    return Ok(index);
+                let Expr::If(expr_if) = init.expr.as_ref() else {
+                    return None;
+                };
+                let Expr::Let(cond) = expr_if.cond.as_ref() else {
+                    return None;
+                };
+                let (_, else_branch) = expr_if.else_branch.as_ref()?;
+                let Expr::Block(else_block) = else_branch.as_ref() else {
+                    return None;
+                };
+                if !else_block.attrs.is_empty()
+                    || else_block.label.is_some()
+                    || !diverges(&else_block.block)
+                {
+                    return None;
                 }
-                ("enum", Item::Enum(e)) if e.ident == name => {
-                    return Ok(index);
+
+                // The then-branch must yield exactly the pattern's single binding
+                let [binding] = pattern_bindings(&cond.pat)[..] else {
+                    return None;
+                };
+                if binding.by_ref.is_some() || binding.mutability.is_some() {
+                    return None;
                 }
-                ("fn", Item::Fn(f)) if f.sig.ident == name => {
-                    return Ok(index);
+                let [syn::Stmt::Expr(Expr::Path(yielded), None)] = &expr_if.then_branch.stmts[..]
+                else {
+                    return None;
+                };
+                if !yielded.path.is_ident(&binding.ident) {
+                    return None;
                 }
-                ("impl", Item::Impl(impl_block)) => {
-                    // For impl blocks, match on the self_ty
-                    if let syn::Type::Path(type_path) = &*impl_block.self_ty
-                        && let Some(segment) = type_path.path.segments.last()
-                        && segment.ident == name
-                    {
-                        return Ok(index);
-                    }
+
+                // `let P = e else` is ambiguous when `e` ends in `}` or is a lazy boolean
+                let scrutinee = self.text(cond.expr.span()).trim();
+                if scrutinee.ends_with('}')
+                    || matches!(
+                        cond.expr.as_ref(),
+                        Expr::Binary(syn::ExprBinary {
+                            op: syn::BinOp::And(_) | syn::BinOp::Or(_),
+                            ..
+                        })
+                    )
+                {
+                    return None;
                 }
-                _ => {}
-            }
-        }
 
-        anyhow::bail!("Item '{}' of type '{}' not found", name, node_type)
-    }
+                // Splice the outer binding (`x` / `mut x`) into the pattern in place of `v`
+                let pat_span = cond.pat.span();
+                let pat_start = self.editor.span_to_byte_offset(pat_span.start());
+                let pat_end = self.editor.span_to_byte_offset(pat_span.end());
+                let binding_start = self
+                    .editor
+                    .span_to_byte_offset(binding.ident.span().start());
+                let binding_end = self.editor.span_to_byte_offset(binding.ident.span().end());
+                let content = &self.editor.content;
+                let pattern = format!(
+                    "{}{}{}",
+                    &content[pat_start..binding_start],
+                    self.text(target.span()),
+                    &content[binding_end..pat_end]
+                );
 
-    /// Replace an item at a specific index with a new item
-    #[allow(dead_code)]
-    pub(crate) fn replace_item_at_index(&mut self, index: usize, new_item: Item) -> Result<()> {
-        if index >= self.syntax_tree.items.len() {
-            anyhow::bail!("Index {} out of bounds", index);
+                Some(format!(
+                    "let {} = {} else {};",
+                    pattern,
+                    scrutinee,
+                    self.text(else_block.block.span())
+                ))
+            }
         }
 
-        // Replace the item in the syntax tree
-        self.syntax_tree.items[index] = new_item;
-
-        // Reformat the entire file using prettyplease
-        self.content = prettyplease::unparse(&self.syntax_tree);
+        impl<'ast> Visit<'ast> for LetElseCollector<'_> {
+            fn visit_local(&mut self, node: &'ast syn::Local) {
+                if node.attrs.is_empty()
+                    && let Some(replacement) = self.rewrite(node)
+                {
+                    let start = self.editor.span_to_byte_offset(node.let_token.span.start());
+                    let end = self.editor.span_to_byte_offset(node.semi_token.span.end());
+                    self.edits.push((start, end, replacement));
+                    return;
+                }
+                syn::visit::visit_local(self, node);
+            }
+        }
 
-        // Recompute line offsets
-        self.line_offsets = Self::compute_line_offsets(&self.content);
+        let Ok(editor) = Self::new(source) else {
+            return source.to_string();
+        };
+        let mut collector = LetElseCollector {
+            editor: &editor,
+            edits: Vec::new(),
+        };
+        collector.visit_file(&editor.syntax_tree);
 
-        Ok(())
+        let mut edits = collector.edits;
+        edits.sort_by_key(|(start, _, _)| std::cmp::Reverse(*start));
+        let mut output = source.to_string();
+        for (start, end, replacement) in edits {
+            output.replace_range(start..end, &replacement);
+        }
+        output
     }
 
-    fn span_to_location(&self, span: Span) -> NodeLocation {
-        let start = span.start();
-        let end = span.end();
+    /// `(expression, statement)` locations of every expression statement (`expr;`, and
+    /// macro statements, where the two are the same), so a match can tell whether it
+    /// stands on its own.
+    fn statement_extents(&self) -> Vec<(NodeLocation, NodeLocation)> {
+        use syn::visit::Visit;
 
-        NodeLocation {
-            line: start.line,
-            column: start.column,
-            end_line: end.line,
-            end_column: end.column,
+        struct StatementCollector<'a> {
+            editor: &'a RustEditor,
+            extents: Vec<(NodeLocation, NodeLocation)>,
         }
-    }
 
-    /// Generic transform operation - find matching nodes and apply action
-    pub(crate) fn transform(
-        &mut self,
-        op: &crate::operations::TransformOp,
-    ) -> Result<ModificationResult> {
-        use crate::operations::{InspectResult, TransformAction};
+        impl<'ast> Visit<'ast> for StatementCollector<'_> {
+            fn visit_stmt(&mut self, node: &'ast syn::Stmt) {
+                match node {
+                    syn::Stmt::Macro(mac) => {
+                        let location = self.editor.span_to_location(mac.span());
+                        self.extents.push((location.clone(), location));
+                    }
+                    syn::Stmt::Expr(expr, Some(_)) => {
+                        self.extents.push((
+                            self.editor.span_to_location(expr.span()),
+                            self.editor.span_to_location(node.span()),
+                        ));
+                    }
+                    _ => {}
+                }
+                syn::visit::visit_stmt(self, node);
+            }
+        }
 
-        // First, use inspect to find all matching nodes (comments not needed for transform)
-        let matches = self.inspect(Some(&op.node_type), op.name_filter.as_deref(), None, false)?;
+        let mut collector = StatementCollector {
+            editor: self,
+            extents: Vec::new(),
+        };
+        collector.visit_file(&self.syntax_tree);
+        collector.extents
+    }
 
-        // Apply content filter if specified
-        let filtered_matches: Vec<InspectResult> =
-            if let Some(ref content_filter) = op.content_filter {
-                matches
-                    .into_iter()
-                    .filter(|m| m.snippet.contains(content_filter))
-                    .collect()
-            } else {
-                matches
-            };
+    /// Replacement text and end offset for `--action cfg-guard` on one match, or `None`
+    /// when it is left alone.
+    ///
+    /// A definition gets `#[cfg(<cfg>)]`; a statement (with its `;`) is wrapped in
+    /// `if cfg!(<cfg>) { ... }`; a print or log macro in expression position (a match arm
+    /// or closure body) becomes `if cfg!(<cfg>) { mac!(...) }`. Other expressions would
+    /// change type and are skipped, as are sites already guarded by the same condition.
+    fn cfg_guard(
+        &self,
+        node: &crate::operations::InspectResult,
+        cfg: &str,
+        start: usize,
+        end: usize,
+        statements: &[(NodeLocation, NodeLocation)],
+    ) -> Result<Option<(String, usize)>> {
+        let before = self.content[..start].trim_end();
+        let line_start = self.content[..start].rfind('\n').map_or(0, |i| i + 1);
+        let indent: String = self.content[line_start..start]
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .collect();
 
-        if filtered_matches.is_empty() {
-            return Ok(ModificationResult {
-                changed: false,
-                modified_nodes: vec![],
-                unmatched_qualified_paths: None,
-            });
+        let is_definition = crate::commands::node_types::NODE_TYPES
+            .iter()
+            .any(|info| info.name == node.node_type && info.level == "definition");
+        if is_definition {
+            let attribute = format!("#[cfg({})]", cfg);
+            if before.ends_with(&attribute) || self.content[start..end].contains(&attribute) {
+                return Ok(None);
+            }
+            let guarded = format!("{}\n{}{}", attribute, indent, &self.content[start..end]);
+            return Ok(Some((guarded, end)));
         }
 
-        // Now apply the transformation action to each match
-        // We need to work backwards through the file to avoid offset issues
-        let mut sorted_matches = filtered_matches;
-        sorted_matches.sort_by(|a, b| {
-            b.location
-                .line
-                .cmp(&a.location.line)
-                .then(b.location.column.cmp(&a.location.column))
+        if before.ends_with(&format!("if cfg!({}) {{", cfg)) {
+            return Ok(None);
+        }
+
+        let location = &node.location;
+        let statement = statements.iter().find(|(expr, _)| {
+            (expr.line, expr.column, expr.end_line, expr.end_column)
+                == (
+                    location.line,
+                    location.column,
+                    location.end_line,
+                    location.end_column,
+                )
         });
+        if let Some((_, stmt)) = statement {
+            let stmt_end = self.line_column_to_byte_offset(stmt.end_line, stmt.end_column)?;
+            let body = self.content[start..stmt_end]
+                .lines()
+                .map(|line| {
+                    if line.trim().is_empty() {
+                        String::new()
+                    } else {
+                        format!("    {}", line)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            let guarded = format!("if cfg!({}) {{\n{}{}\n{}}}", cfg, indent, body, indent);
+            return Ok(Some((guarded, stmt_end)));
+        }
 
-        let mut modified_nodes = Vec::new();
+        // Macros that expand to `()`, so `if cfg!(..) { mac!(..) }` keeps the type
+        const UNIT_MACROS: &[&str] = &[
+            "print", "println", "eprint", "eprintln", "trace", "debug", "info", "warn", "error",
+        ];
+        if node.node_type == "macro-call" && UNIT_MACROS.contains(&node.identifier.as_str()) {
+            let guarded = format!("if cfg!({}) {{ {} }}", cfg, &self.content[start..end]);
+            return Ok(Some((guarded, end)));
+        }
+        Ok(None)
+    }
 
-        for match_result in &sorted_matches {
-            // Create backup node
-            let backup_node = BackupNode {
-                node_type: match_result.node_type.clone(),
-                identifier: match_result.identifier.clone(),
-                original_content: match_result.snippet.clone(),
-                location: match_result.location.clone(),
-            };
+    /// Rewrite every `format!("{}", x)` in `source` into `x.to_string()`.
+    ///
+    /// Only the trivial form is touched: a single `{}` placeholder and one positional
+    /// argument. `source` may be a whole item or a lone expression (a `macro-call` match).
+    /// `format!` calls nested inside other macro invocations aren't visible to syn and are
+    /// left alone.
+    fn rewrite_simplify_format(source: &str) -> String {
+        use syn::punctuated::Punctuated;
+        use syn::visit::Visit;
 
-            // Find the byte offsets for this node
-            let start_offset = self.line_column_to_byte_offset(
-                match_result.location.line,
-                match_result.location.column,
-            )?;
-            let end_offset = self.line_column_to_byte_offset(
-                match_result.location.end_line,
-                match_result.location.end_column,
-            )?;
+        struct FormatCollector<'a> {
+            source: &'a str,
+            line_offsets: Vec<usize>,
+            edits: Vec<(usize, usize, String)>,
+        }
 
-            // Extract the original text
-            let original_text = &self.content[start_offset..end_offset];
+        impl FormatCollector<'_> {
+            fn offset(&self, pos: LineColumn) -> usize {
+                self.line_offsets
+                    .get(pos.line.saturating_sub(1))
+                    .map_or(self.source.len(), |line_start| line_start + pos.column)
+            }
 
-            // Apply the action
-            let replacement = match &op.action {
-                TransformAction::Comment => {
-                    // Comment out the code
-                    format!("// {}", original_text.replace("\n", "\n// "))
-                }
-                TransformAction::Remove => {
-                    // Remove the entire node
-                    String::new()
+            fn rewrite(&self, mac: &syn::Macro) -> Option<String> {
+                let segments: Vec<String> = mac
+                    .path
+                    .segments
+                    .iter()
+                    .map(|seg| seg.ident.to_string())
+                    .collect();
+                let is_format = match segments.as_slice() {
+                    [name] => name == "format",
+                    [krate, name] => (krate == "std" || krate == "alloc") && name == "format",
+                    _ => false,
+                };
+                if !is_format {
+                    return None;
                 }
-                TransformAction::Replace { with } => {
-                    // Replace with provided code
-                    with.clone()
+
+                let args = mac
+                    .parse_body_with(Punctuated::<Expr, syn::Token![,]>::parse_terminated)
+                    .ok()?;
+                let [template, arg] = args.iter().collect::<Vec<_>>()[..] else {
+                    return None;
+                };
+                let Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(template),
+                    ..
+                }) = template
+                else {
+                    return None;
+                };
+                // `name = value` is a named argument, not a positional one
+                if template.value() != "{}" || matches!(arg, Expr::Assign(_)) {
+                    return None;
                 }
-            };
 
-            // Replace in content
-            self.content
-                .replace_range(start_offset..end_offset, &replacement);
+                let arg_text =
+                    &self.source[self.offset(arg.span().start())..self.offset(arg.span().end())];
+                let arg_text = RustEditor::rewrite_simplify_format(arg_text);
+                let receiver = match arg {
+                    Expr::Path(_)
+                    | Expr::Field(_)
+                    | Expr::MethodCall(_)
+                    | Expr::Call(_)
+                    | Expr::Index(_)
+                    | Expr::Lit(_)
+                    | Expr::Paren(_)
+                    | Expr::Macro(_)
+                    | Expr::Try(_) => arg_text,
+                    _ => format!("({})", arg_text),
+                };
+                Some(format!("{}.to_string()", receiver))
+            }
+        }
 
-            // Recompute line offsets after each change
-            self.line_offsets = Self::compute_line_offsets(&self.content);
+        impl<'ast> Visit<'ast> for FormatCollector<'_> {
+            fn visit_macro(&mut self, node: &'ast syn::Macro) {
+                if let Some(replacement) = self.rewrite(node) {
+                    let start = self.offset(node.span().start());
+                    let end = self.offset(node.span().end());
+                    self.edits.push((start, end, replacement));
+                }
+            }
+        }
 
-            modified_nodes.push(backup_node);
+        let mut collector = FormatCollector {
+            source,
+            line_offsets: Self::compute_line_offsets(source),
+            edits: Vec::new(),
+        };
+        if let Ok(file) = syn::parse_file(source) {
+            collector.visit_file(&file);
+        } else if let Ok(expr) = syn::parse_str::<Expr>(source) {
+            collector.visit_expr(&expr);
+        } else {
+            return source.to_string();
         }
 
-        // Re-parse the content if we made changes
-        if !modified_nodes.is_empty() {
-            // Don't reparse for now - we're doing text-level operations
-            // self.syntax_tree = syn::parse_str(&self.content)
-            //     .context("Failed to re-parse content after transformation")?;
+        let mut edits = collector.edits;
+        edits.sort_by_key(|(start, _, _)| std::cmp::Reverse(*start));
+        let mut output = source.to_string();
+        for (start, end, replacement) in edits {
+            output.replace_range(start..end, &replacement);
+        }
+        output
+    }
+
+    /// Apply several transforms in sequence. The syntax tree is re-parsed between steps so
+    /// each transform sees the output of the previous one.
+    pub(crate) fn multi_transform(
+        &mut self,
+        op: &crate::operations::MultiTransformOp,
+    ) -> Result<ModificationResult> {
+        let mut modified_nodes = Vec::new();
+
+        for (index, transform) in op.transforms.iter().enumerate() {
+            let result = self.transform(transform)?;
+            if !result.changed {
+                continue;
+            }
+
+            self.syntax_tree = self.parse_content().with_context(|| {
+                format!(
+                    "Transform #{} ({} {}) produced code that no longer parses",
+                    index + 1,
+                    transform.node_type,
+                    transform.name_filter.as_deref().unwrap_or("*")
+                )
+            })?;
+            self.line_offsets = Self::compute_line_offsets(&self.content);
+            modified_nodes.extend(result.modified_nodes);
         }
 
         Ok(ModificationResult {
@@ -5322,6 +9259,361 @@ impl RustEditor {
         anyhow::bail!("Argument index {} not found", arg_index)
     }
 
+    /// Rename field `old_name` of `struct_name`: in the definition (when it's in this file)
+    /// and in every `StructName { .. }` literal, where shorthand `{ old }` becomes
+    /// `{ new: old }`. Qualified literal paths are reported as unmatched, as in the other
+    /// field operations. See `RenameStructFieldOp` for the serde options.
+    pub(crate) fn rename_struct_field(
+        &mut self,
+        op: &RenameStructFieldOp,
+    ) -> Result<ModificationResult> {
+        use syn::visit::Visit;
+
+        // (start, end, replacement)
+        let mut edits: Vec<(usize, usize, String)> = Vec::new();
+        let mut modified_nodes = Vec::new();
+
+        if let Ok(item_struct) = self.find_struct_def(&op.struct_name, None) {
+            let Fields::Named(fields) = &item_struct.fields else {
+                anyhow::bail!("Struct '{}' does not have named fields", op.struct_name);
+            };
+            let field_names: Vec<String> = fields
+                .named
+                .iter()
+                .filter_map(|f| f.ident.as_ref().map(|i| i.to_string()))
+                .collect();
+            match fields
+                .named
+                .iter()
+                .find(|f| f.ident.as_ref().is_some_and(|i| *i == op.old_name))
+            {
+                Some(field) => {
+                    let ident = field.ident.as_ref().expect("named field");
+                    edits.push((
+                        self.span_to_byte_offset(ident.span().start()),
+                        self.span_to_byte_offset(ident.span().end()),
+                        op.new_name.clone(),
+                    ));
+                    if op.serde_aware && Self::uses_serde(&item_struct.attrs) {
+                        edits.extend(self.serde_rename_edits(&item_struct, field, op)?);
+                    }
+                    modified_nodes.push(self.struct_def_backup(&item_struct, &op.struct_name));
+                }
+                // Already renamed: only literals may be left to update
+                None if field_names.contains(&op.new_name) => {}
+                None => anyhow::bail!(
+                    "Field '{}' not found in struct '{}'\n\nAvailable fields: {}",
+                    op.old_name,
+                    op.struct_name,
+                    field_names.join(", ")
+                ),
+            }
+        }
+
+        struct LiteralFieldFinder<'a> {
+            op: &'a RenameStructFieldOp,
+            editor: &'a RustEditor,
+            edits: Vec<(usize, usize, String)>,
+            /// `StructName { old, .. }` patterns: (start, end, replacement)
+            pattern_edits: Vec<(usize, usize, String)>,
+            unmatched: std::collections::HashMap<String, usize>,
+        }
+
+        impl<'ast> Visit<'ast> for LiteralFieldFinder<'_> {
+            fn visit_expr_macro(&mut self, node: &'ast syn::ExprMacro) {
+                // Literals inside vec![...] and the like
+                use syn::parse::Parser;
+                if let Ok(exprs) =
+                    syn::punctuated::Punctuated::<Expr, syn::Token![,]>::parse_terminated
+                        .parse2(node.mac.tokens.clone())
+                {
+                    for expr in &exprs {
+                        self.visit_expr(expr);
+                    }
+                }
+                syn::visit::visit_expr_macro(self, node);
+            }
+
+            fn visit_expr_struct(&mut self, node: &'ast syn::ExprStruct) {
+                let segments = &node.path.segments;
+                let is_target = segments.len() == 1 && segments[0].ident == self.op.struct_name;
+                if !is_target
+                    && segments.len() > 1
+                    && segments
+                        .last()
+                        .is_some_and(|seg| seg.ident == self.op.struct_name)
+                {
+                    let path = segments
+                        .iter()
+                        .map(|seg| seg.ident.to_string())
+                        .collect::<Vec<_>>()
+                        .join("::");
+                    *self.unmatched.entry(path).or_insert(0) += 1;
+                }
+                if is_target
+                    && let Some(field) = node.fields.iter().find(|f| {
+                        matches!(&f.member, syn::Member::Named(ident) if *ident == self.op.old_name)
+                    })
+                {
+                    let start = self.editor.span_to_byte_offset(field.member.span().start());
+                    let end = self.editor.span_to_byte_offset(field.member.span().end());
+                    let replacement = if field.colon_token.is_some() {
+                        self.op.new_name.clone()
+                    } else {
+                        format!("{}: {}", self.op.new_name, self.op.old_name)
+                    };
+                    self.edits.push((start, end, replacement));
+                }
+                syn::visit::visit_expr_struct(self, node);
+            }
+
+            fn visit_pat_struct(&mut self, node: &'ast syn::PatStruct) {
+                let segments = &node.path.segments;
+                if segments.len() == 1
+                    && segments[0].ident == self.op.struct_name
+                    && let Some(field) = node.fields.iter().find(|f| {
+                        matches!(&f.member, syn::Member::Named(ident) if *ident == self.op.old_name)
+                    })
+                {
+                    let start = self.editor.span_to_byte_offset(field.member.span().start());
+                    let end = self.editor.span_to_byte_offset(field.member.span().end());
+                    let replacement = if field.colon_token.is_some() {
+                        self.op.new_name.clone()
+                    } else {
+                        // `{ old }` binds `old`; keep the binding, rename the field
+                        format!("{}: {}", self.op.new_name, self.op.old_name)
+                    };
+                    self.pattern_edits.push((start, end, replacement));
+                }
+                syn::visit::visit_pat_struct(self, node);
+            }
+        }
+
+        let mut finder = LiteralFieldFinder {
+            op,
+            editor: self,
+            edits: Vec::new(),
+            pattern_edits: Vec::new(),
+            unmatched: std::collections::HashMap::new(),
+        };
+        finder.visit_file(&self.syntax_tree);
+        let unmatched = finder.unmatched;
+        if !finder.edits.is_empty() {
+            modified_nodes.extend(self.collect_struct_literal_backups(&op.struct_name, None));
+            edits.extend(finder.edits);
+        }
+
+        // Patterns and `x.old` accesses: the receiver's type is often unknown, so every access
+        // is renamed unless its receiver is known to be another type (`--validate` lists the
+        // guesses). Each edit gets its own backup, located once all edits are in.
+        let mut reference_edits = finder.pattern_edits;
+        for access in self.field_accesses(&op.old_name) {
+            if access
+                .receiver_type
+                .as_ref()
+                .is_some_and(|ty| *ty != op.struct_name)
+            {
+                continue;
+            }
+            let start = self.span_to_byte_offset(LineColumn {
+                line: access.location.line,
+                column: access.location.column,
+            });
+            let end = self.span_to_byte_offset(LineColumn {
+                line: access.location.end_line,
+                column: access.location.end_column,
+            });
+            reference_edits.push((start, end, op.new_name.clone()));
+        }
+        let reference_starts: Vec<(usize, String)> = reference_edits
+            .iter()
+            .map(|(start, end, _)| (*start, self.content[*start..*end].to_string()))
+            .collect();
+        edits.extend(reference_edits);
+
+        if edits.is_empty() {
+            return Ok(ModificationResult {
+                changed: false,
+                modified_nodes: vec![],
+                unmatched_qualified_paths: (!unmatched.is_empty()).then_some(unmatched),
+            });
+        }
+
+        // Last first, and at one offset the replacement before the insertion (a private
+        // field's serde attribute goes where its name starts). A literal inside a macro can be
+        // reached twice, so identical edits are dropped.
+        edits.sort_by(|a, b| b.cmp(a));
+        edits.dedup();
+
+        // Where each renamed pattern field or access ends up once the edits before it shift it
+        let mut moved_references = Vec::new();
+        for (start, original) in reference_starts {
+            let Some((_, _, replacement)) = edits.iter().find(|edit| edit.0 == start) else {
+                continue;
+            };
+            let shift: isize = edits
+                .iter()
+                .filter(|edit| edit.0 < start)
+                .map(|(s, e, r)| r.len() as isize - (e - s) as isize)
+                .sum();
+            let new_start = start.saturating_add_signed(shift);
+            moved_references.push((new_start, new_start + replacement.len(), original));
+        }
+
+        for (start, end, replacement) in edits {
+            self.content.replace_range(start..end, &replacement);
+        }
+        self.syntax_tree = self
+            .parse_content()
+            .context("Failed to re-parse content after renaming field")?;
+        self.line_offsets = Self::compute_line_offsets(&self.content);
+
+        for (start, end, original) in moved_references {
+            let (line, column) = self.byte_offset_to_line_column(start);
+            let (end_line, end_column) = self.byte_offset_to_line_column(end);
+            modified_nodes.push(BackupNode {
+                node_type: "field-reference".to_string(),
+                identifier: format!("{}.{}", op.struct_name, op.new_name),
+                original_content: original,
+                location: NodeLocation {
+                    line,
+                    column,
+                    end_line,
+                    end_column,
+                },
+            });
+        }
+
+        Ok(ModificationResult {
+            changed: true,
+            modified_nodes,
+            unmatched_qualified_paths: (!unmatched.is_empty()).then_some(unmatched),
+        })
+    }
+
+    /// Whether a type's attributes show it takes part in serde: a `Serialize`/`Deserialize`
+    /// derive or a `#[serde(...)]` attribute.
+    fn uses_serde(attrs: &[syn::Attribute]) -> bool {
+        attrs.iter().any(|attr| attr.path().is_ident("serde"))
+            || Self::extract_derives(attrs).iter().any(|derive| {
+                let last = derive.rsplit("::").next().unwrap_or(derive).trim();
+                last == "Serialize" || last == "Deserialize"
+            })
+    }
+
+    /// Edits that keep (or, with `update_serde_name`, move) the serde wire name of `field`
+    /// as it's renamed.
+    fn serde_rename_edits(
+        &self,
+        item_struct: &ItemStruct,
+        field: &syn::Field,
+        op: &RenameStructFieldOp,
+    ) -> Result<Vec<(usize, usize, String)>> {
+        // The `rename` value of a `#[serde(...)]` on the field: `Some(Some(lit))` for
+        // `rename = "x"`, `Some(None)` for the `rename(serialize = .., ..)` form
+        let mut rename: Option<Option<syn::LitStr>> = None;
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("serde")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    rename = Some(if meta.input.peek(syn::Token![=]) {
+                        Some(meta.value()?.parse()?)
+                    } else {
+                        None
+                    });
+                } else if meta.input.peek(syn::Token![=]) {
+                    meta.value()?.parse::<syn::Expr>()?;
+                } else if meta.input.peek(syn::token::Paren) {
+                    meta.input.parse::<proc_macro2::Group>()?;
+                }
+                Ok(())
+            })
+            .context("Failed to parse #[serde(...)] on the field")?;
+        }
+
+        match (rename, op.update_serde_name) {
+            // The attribute already pins the wire name
+            (Some(_), false) => Ok(Vec::new()),
+            (Some(Some(lit)), true) => Ok(vec![(
+                self.span_to_byte_offset(lit.span().start()),
+                self.span_to_byte_offset(lit.span().end()),
+                format!("{:?}", op.new_name.trim_start_matches("r#")),
+            )]),
+            (Some(None), true) => anyhow::bail!(
+                "Field '{}' uses `#[serde(rename(serialize = .., deserialize = ..))]`; update it by hand",
+                op.old_name
+            ),
+            // Without an attribute the wire name follows the field name anyway
+            (None, true) => Ok(Vec::new()),
+            (None, false) => {
+                let wire_name = Self::serde_wire_name(&item_struct.attrs, &op.old_name)?;
+                let start = match &field.vis {
+                    syn::Visibility::Inherited => field.ident.as_ref().map(|i| i.span().start()),
+                    vis => Some(vis.span().start()),
+                }
+                .map(|pos| self.span_to_byte_offset(pos))
+                .context("Field has no name")?;
+                let line_start = self.content[..start].rfind('\n').map_or(0, |i| i + 1);
+                let before = &self.content[line_start..start];
+                let attr = format!("#[serde(rename = {:?})]", wire_name);
+                let text = if before.trim().is_empty() {
+                    format!("{}\n{}", attr, before)
+                } else {
+                    format!("{} ", attr)
+                };
+                Ok(vec![(start, start, text)])
+            }
+        }
+    }
+
+    /// The name serde uses on the wire for `field` given the container's `rename_all`.
+    fn serde_wire_name(container_attrs: &[syn::Attribute], field: &str) -> Result<String> {
+        let field = field.trim_start_matches("r#");
+        let mut rule: Option<String> = None;
+        for attr in container_attrs
+            .iter()
+            .filter(|a| a.path().is_ident("serde"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename_all") {
+                    if !meta.input.peek(syn::Token![=]) {
+                        return Err(meta.error(
+                            "`rename_all(serialize = .., deserialize = ..)` gives the field two wire names",
+                        ));
+                    }
+                    rule = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+                } else if meta.input.peek(syn::Token![=]) {
+                    meta.value()?.parse::<syn::Expr>()?;
+                } else if meta.input.peek(syn::token::Paren) {
+                    meta.input.parse::<proc_macro2::Group>()?;
+                }
+                Ok(())
+            })
+            .context("Can't tell the field's serde wire name")?;
+        }
+
+        let words: Vec<&str> = field.split('_').filter(|w| !w.is_empty()).collect();
+        let capitalize = |word: &str| {
+            let mut chars = word.chars();
+            chars.next().map_or_else(String::new, |first| {
+                first.to_uppercase().chain(chars).collect()
+            })
+        };
+        Ok(match rule.as_deref() {
+            None | Some("snake_case") => field.to_string(),
+            Some("lowercase") => field.to_lowercase(),
+            Some("UPPERCASE" | "SCREAMING_SNAKE_CASE") => field.to_uppercase(),
+            Some("PascalCase") => words.iter().map(|w| capitalize(w)).collect(),
+            Some("camelCase") => words
+                .iter()
+                .enumerate()
+                .map(|(i, w)| if i == 0 { w.to_string() } else { capitalize(w) })
+                .collect(),
+            Some("kebab-case") => field.replace('_', "-"),
+            Some("SCREAMING-KEBAB-CASE") => field.replace('_', "-").to_uppercase(),
+            Some(other) => anyhow::bail!("Unknown serde rename_all rule \"{}\"", other),
+        })
+    }
+
     /// Rename an enum variant across the entire file
     pub(crate) fn rename_enum_variant(
         &mut self,
@@ -5341,8 +9633,16 @@ impl RustEditor {
             None
         };
 
+        // Bare `OldVariant` only refers to the variant where the file imports it
+        // (`use Enum::OldVariant;` or `use Enum::*;`)
+        let bare_in_scope = crate::path_resolver::imports_member_unqualified(
+            &self.syntax_tree,
+            &op.old_variant,
+            |prefix| is_enum_path(prefix, &op.enum_name, path_resolver.as_ref()),
+        );
+
         match op.edit_mode {
-            EditMode::Surgical => {
+            EditMode::Surgical | EditMode::Rustfmt => {
                 // Use non-mutating visitor to collect replacement locations
                 use syn::visit::Visit;
 
@@ -5351,6 +9651,7 @@ impl RustEditor {
                     old_variant: op.old_variant.clone(),
                     new_variant: op.new_variant.clone(),
                     path_resolver,
+                    bare_in_scope,
                     replacements: Vec::new(),
                 };
 
@@ -5372,9 +9673,14 @@ impl RustEditor {
                 self.line_offsets = Self::compute_line_offsets(&self.content);
 
                 // Re-parse the modified content
-                self.syntax_tree = syn::parse_str(&self.content)
+                self.syntax_tree = self
+                    .parse_content()
                     .context("Failed to re-parse after surgical edit")?;
 
+                if op.edit_mode == EditMode::Rustfmt {
+                    self.rustfmt_content()?;
+                }
+
                 let backup_node = BackupNode {
                     node_type: "EnumVariantRename".to_string(),
                     identifier: format!(
@@ -5406,6 +9712,7 @@ impl RustEditor {
                     old_variant: op.old_variant.clone(),
                     new_variant: op.new_variant.clone(),
                     path_resolver,
+                    bare_in_scope,
                     modified: false,
                 };
 
@@ -5421,7 +9728,7 @@ impl RustEditor {
                 }
 
                 // Reformat the entire file using prettyplease
-                self.content = prettyplease::unparse(&self.syntax_tree);
+                self.content = self.unparse_tree()?;
 
                 // Recompute line offsets
                 self.line_offsets = Self::compute_line_offsets(&self.content);
@@ -5461,6 +9768,10 @@ impl RustEditor {
     ) -> Result<ModificationResult> {
         use crate::operations::EditMode;
 
+        if let Some(receiver_type) = &op.receiver_type {
+            return self.rename_method_on_type(op, receiver_type);
+        }
+
         // Create a path resolver if a canonical path was provided
         let path_resolver = if let Some(function_path) = &op.function_path {
             let mut resolver = PathResolver::new(function_path)
@@ -5474,7 +9785,7 @@ impl RustEditor {
         };
 
         match op.edit_mode {
-            EditMode::Surgical => {
+            EditMode::Surgical | EditMode::Rustfmt => {
                 // Use non-mutating visitor to collect replacement locations
                 use syn::visit::Visit;
 
@@ -5482,6 +9793,7 @@ impl RustEditor {
                     old_name: op.old_name.clone(),
                     new_name: op.new_name.clone(),
                     path_resolver,
+                    keep_link_name: op.keep_link_name,
                     replacements: Vec::new(),
                 };
 
@@ -5503,9 +9815,14 @@ impl RustEditor {
                 self.line_offsets = Self::compute_line_offsets(&self.content);
 
                 // Re-parse the modified content
-                self.syntax_tree = syn::parse_str(&self.content)
+                self.syntax_tree = self
+                    .parse_content()
                     .context("Failed to re-parse after surgical edit")?;
 
+                if op.edit_mode == EditMode::Rustfmt {
+                    self.rustfmt_content()?;
+                }
+
                 let backup_node = BackupNode {
                     node_type: "FunctionRename".to_string(),
                     identifier: format!("{} -> {} (surgical)", op.old_name, op.new_name),
@@ -5533,6 +9850,7 @@ impl RustEditor {
                     old_name: op.old_name.clone(),
                     new_name: op.new_name.clone(),
                     path_resolver,
+                    keep_link_name: op.keep_link_name,
                     modified: false,
                 };
 
@@ -5548,7 +9866,7 @@ impl RustEditor {
                 }
 
                 // Reformat the entire file using prettyplease
-                self.content = prettyplease::unparse(&self.syntax_tree);
+                self.content = self.unparse_tree()?;
 
                 // Recompute line offsets
                 self.line_offsets = Self::compute_line_offsets(&self.content);
@@ -5575,6 +9893,77 @@ impl RustEditor {
         }
     }
 
+    /// Rename an inherent method of `receiver_type` and the calls that can be traced to it.
+    /// Receivers are resolved by `MethodReplacementCollector`'s local inference; anything it
+    /// can't pin to `receiver_type` keeps its old name.
+    fn rename_method_on_type(
+        &mut self,
+        op: &crate::operations::RenameFunctionOp,
+        receiver_type: &str,
+    ) -> Result<ModificationResult> {
+        use crate::operations::EditMode;
+        use syn::visit::Visit;
+
+        let receiver_type = receiver_type
+            .rsplit("::")
+            .next()
+            .unwrap_or(receiver_type)
+            .to_string();
+        let mut collector = MethodReplacementCollector {
+            old_name: op.old_name.clone(),
+            new_name: op.new_name.clone(),
+            receiver_type: receiver_type.clone(),
+            scopes: Vec::new(),
+            impl_stack: Vec::new(),
+            replacements: Vec::new(),
+        };
+        collector.visit_file(&self.syntax_tree);
+
+        if collector.replacements.is_empty() {
+            return Ok(ModificationResult {
+                changed: false,
+                modified_nodes: vec![],
+                unmatched_qualified_paths: None,
+            });
+        }
+
+        self.content = crate::surgical::apply_surgical_edits(&self.content, collector.replacements);
+        self.syntax_tree = self
+            .parse_content()
+            .context("Failed to re-parse after method rename")?;
+        match op.edit_mode {
+            EditMode::Surgical => {}
+            EditMode::Reformat => {
+                let file: File = syn::parse_str(&self.content)
+                    .context("Failed to re-parse after method rename")?;
+                self.content = prettyplease::unparse(&file);
+            }
+            EditMode::Rustfmt => self.rustfmt_content()?,
+        }
+        self.line_offsets = Self::compute_line_offsets(&self.content);
+
+        let backup_node = BackupNode {
+            node_type: "MethodRename".to_string(),
+            identifier: format!("{}::{} -> {}", receiver_type, op.old_name, op.new_name),
+            original_content: format!(
+                "Renamed {}::{} to {}",
+                receiver_type, op.old_name, op.new_name
+            ),
+            location: NodeLocation {
+                line: 1,
+                column: 0,
+                end_line: 1,
+                end_column: 0,
+            },
+        };
+
+        Ok(ModificationResult {
+            changed: true,
+            modified_nodes: vec![backup_node],
+            unmatched_qualified_paths: None,
+        })
+    }
+
     /// Convert line/column to byte offset
     fn line_column_to_byte_offset(&self, line: usize, column: usize) -> Result<usize> {
         if line == 0 || line > self.line_offsets.len() {
@@ -5908,6 +10297,8 @@ struct EnumVariantRenamer {
     old_variant: String,
     new_variant: String,
     path_resolver: Option<PathResolver>,
+    /// The file imports the variant, so a bare `OldVariant` refers to it
+    bare_in_scope: bool,
     modified: bool,
 }
 
@@ -5917,7 +10308,8 @@ impl EnumVariantRenamer {
     /// This method handles various path forms:
     /// - Simple paths: `EnumName::Variant`
     /// - Qualified paths: `crate::module::EnumName::Variant`
-    /// - Imported paths: `Variant` (when enum is imported via use statement)
+    /// - Imported paths: `Variant` (when the variant is imported via `use Enum::Variant`
+    ///   or `use Enum::*`)
     ///
     /// When a PathResolver is configured, it validates that paths refer to
     /// the correct enum before renaming.
@@ -5960,16 +10352,11 @@ impl EnumVariantRenamer {
                 }
             }
         } else if len == 1 {
-            // Single segment path - check if it's an imported variant
-            if segments[0].ident == self.old_variant {
-                // When using path resolver, we don't rename single-segment paths
-                // unless they're explicitly imported (which would be unusual for variants)
-                // For backward compatibility, we still rename them when no resolver is present
-                if self.path_resolver.is_none() {
-                    path.segments[0].ident =
-                        syn::Ident::new(&self.new_variant, path.segments[0].ident.span());
-                    self.modified = true;
-                }
+            // Single segment path - only the variant if the file imports it
+            if segments[0].ident == self.old_variant && self.bare_in_scope {
+                path.segments[0].ident =
+                    syn::Ident::new(&self.new_variant, path.segments[0].ident.span());
+                self.modified = true;
             }
         }
     }
@@ -5991,6 +10378,27 @@ impl VisitMut for EnumVariantRenamer {
         syn::visit_mut::visit_item_enum_mut(self, node);
     }
 
+    /// Rename in `use Enum::OldVariant;` imports
+    fn visit_item_use_mut(&mut self, node: &mut syn::ItemUse) {
+        let mut prefix = syn::Path {
+            leading_colon: node.leading_colon,
+            segments: syn::punctuated::Punctuated::new(),
+        };
+        let (enum_name, resolver) = (&self.enum_name, self.path_resolver.as_ref());
+        let mut renamed = false;
+        for_each_variant_import(
+            &mut node.tree,
+            &mut prefix,
+            &self.old_variant,
+            &|p| is_enum_path(p, enum_name, resolver),
+            &mut |ident| {
+                *ident = syn::Ident::new(&self.new_variant, ident.span());
+                renamed = true;
+            },
+        );
+        self.modified |= renamed;
+    }
+
     /// Rename in patterns (match arms, let bindings, function parameters, etc.)
     fn visit_pat_mut(&mut self, pat: &mut syn::Pat) {
         match pat {
@@ -6003,6 +10411,13 @@ impl VisitMut for EnumVariantRenamer {
             syn::Pat::Path(path_pat) => {
                 self.rename_path(&mut path_pat.path);
             }
+            // An imported unit variant in a pattern parses as a binding
+            syn::Pat::Ident(pat_ident)
+                if self.bare_in_scope && is_bare_variant_pat(pat_ident, &self.old_variant) =>
+            {
+                pat_ident.ident = syn::Ident::new(&self.new_variant, pat_ident.ident.span());
+                self.modified = true;
+            }
             _ => {}
         }
 
@@ -6030,6 +10445,34 @@ impl VisitMut for EnumVariantRenamer {
         // Continue visiting nested expressions
         syn::visit_mut::visit_expr_mut(self, expr);
     }
+
+    /// Rename inside `matches!(x, Variant { .. })`, `assert_eq!(s, Variant)` and the like
+    fn visit_macro_mut(&mut self, node: &mut syn::Macro) {
+        let Some(mut args) = VariantMacroArgs::parse(node) else {
+            return;
+        };
+        let was_modified = std::mem::replace(&mut self.modified, false);
+        match &mut args {
+            VariantMacroArgs::Exprs(exprs) => {
+                for expr in exprs.iter_mut() {
+                    self.visit_expr_mut(expr);
+                }
+            }
+            VariantMacroArgs::Matches {
+                expr, pat, guard, ..
+            } => {
+                self.visit_expr_mut(expr);
+                self.visit_pat_mut(pat);
+                if let Some(guard) = guard {
+                    self.visit_expr_mut(guard);
+                }
+            }
+        }
+        if self.modified {
+            node.tokens = args.into_token_stream();
+        }
+        self.modified |= was_modified;
+    }
 }
 
 // Non-mutating visitor for collecting replacement locations (surgical mode)
@@ -6038,6 +10481,8 @@ struct EnumVariantReplacementCollector {
     old_variant: String,
     new_variant: String,
     path_resolver: Option<PathResolver>,
+    /// The file imports the variant, so a bare `OldVariant` refers to it
+    bare_in_scope: bool,
     replacements: Vec<crate::surgical::Replacement>,
 }
 
@@ -6062,35 +10507,29 @@ impl EnumVariantReplacementCollector {
                         segments: path.segments.iter().take(len - 1).cloned().collect(),
                     };
                     resolver.matches_target(&enum_path)
-                });
-
-                if should_rename {
-                    let span = potential_variant.ident.span();
-                    let start = span.start();
-                    let end = span.end();
-
-                    self.replacements.push(crate::surgical::Replacement::new(
-                        start,
-                        end,
-                        self.new_variant.clone(),
-                    ));
+                });
+
+                if should_rename {
+                    self.push_replacement(potential_variant.ident.span());
                 }
             }
-        } else if len == 1 && self.path_resolver.is_none() {
-            // Single segment - only without path resolver (backward compat)
+        } else if len == 1 && self.bare_in_scope {
+            // Single segment - only the variant if the file imports it
             if segments[0].ident == self.old_variant {
-                let span = segments[0].ident.span();
-                let start = span.start();
-                let end = span.end();
-
-                self.replacements.push(crate::surgical::Replacement::new(
-                    start,
-                    end,
-                    self.new_variant.clone(),
-                ));
+                self.push_replacement(segments[0].ident.span());
             }
         }
     }
+
+    /// Replace the identifier at `span` with the new variant name
+    fn push_replacement(&mut self, span: Span) {
+        let replacement =
+            crate::surgical::Replacement::new(span.start(), span.end(), self.new_variant.clone());
+        // A call's callee is both collected directly and visited as a path
+        if !self.replacements.contains(&replacement) {
+            self.replacements.push(replacement);
+        }
+    }
 }
 
 impl<'ast> syn::visit::Visit<'ast> for EnumVariantReplacementCollector {
@@ -6113,6 +10552,24 @@ impl<'ast> syn::visit::Visit<'ast> for EnumVariantReplacementCollector {
         syn::visit::visit_item_enum(self, node);
     }
 
+    fn visit_item_use(&mut self, node: &'ast syn::ItemUse) {
+        let mut prefix = syn::Path {
+            leading_colon: node.leading_colon,
+            segments: syn::punctuated::Punctuated::new(),
+        };
+        let mut spans = Vec::new();
+        for_each_variant_import(
+            &mut node.tree.clone(),
+            &mut prefix,
+            &self.old_variant,
+            &|p| is_enum_path(p, &self.enum_name, self.path_resolver.as_ref()),
+            &mut |ident| spans.push(ident.span()),
+        );
+        for span in spans {
+            self.push_replacement(span);
+        }
+    }
+
     fn visit_pat(&mut self, pat: &'ast syn::Pat) {
         match pat {
             syn::Pat::TupleStruct(tuple_struct) => {
@@ -6124,6 +10581,11 @@ impl<'ast> syn::visit::Visit<'ast> for EnumVariantReplacementCollector {
             syn::Pat::Path(path_pat) => {
                 self.collect_path_replacement(&path_pat.path);
             }
+            syn::Pat::Ident(pat_ident)
+                if self.bare_in_scope && is_bare_variant_pat(pat_ident, &self.old_variant) =>
+            {
+                self.push_replacement(pat_ident.ident.span());
+            }
             _ => {}
         }
         syn::visit::visit_pat(self, pat);
@@ -6146,6 +10608,137 @@ impl<'ast> syn::visit::Visit<'ast> for EnumVariantReplacementCollector {
         }
         syn::visit::visit_expr(self, expr);
     }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        match VariantMacroArgs::parse(node) {
+            Some(VariantMacroArgs::Exprs(exprs)) => {
+                for expr in &exprs {
+                    self.visit_expr(expr);
+                }
+            }
+            Some(VariantMacroArgs::Matches {
+                expr, pat, guard, ..
+            }) => {
+                self.visit_expr(&expr);
+                self.visit_pat(&pat);
+                if let Some(guard) = &guard {
+                    self.visit_expr(guard);
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+/// Whether a path names the enum whose variant is being renamed: checked against the
+/// canonical path when there's a resolver, otherwise by the enum's name alone.
+fn is_enum_path(path: &syn::Path, enum_name: &str, resolver: Option<&PathResolver>) -> bool {
+    resolver.map_or_else(
+        || {
+            path.segments
+                .last()
+                .is_some_and(|seg| seg.ident == enum_name)
+        },
+        |resolver| resolver.matches_target(path),
+    )
+}
+
+/// Whether a binding pattern is really a bare unit variant (`Draft => ...`).
+fn is_bare_variant_pat(pat: &syn::PatIdent, variant: &str) -> bool {
+    pat.by_ref.is_none() && pat.mutability.is_none() && pat.subpat.is_none() && pat.ident == variant
+}
+
+/// Call `f` on the `variant` leaf of every `use Enum::variant` (plain or `as` renamed,
+/// possibly inside a group) whose enum prefix satisfies `is_enum`.
+fn for_each_variant_import(
+    tree: &mut syn::UseTree,
+    prefix: &mut syn::Path,
+    variant: &str,
+    is_enum: &dyn Fn(&syn::Path) -> bool,
+    f: &mut dyn FnMut(&mut syn::Ident),
+) {
+    match tree {
+        syn::UseTree::Path(path) => {
+            prefix.segments.push(path.ident.clone().into());
+            for_each_variant_import(&mut path.tree, prefix, variant, is_enum, f);
+            prefix.segments.pop();
+        }
+        syn::UseTree::Name(syn::UseName { ident })
+        | syn::UseTree::Rename(syn::UseRename { ident, .. }) => {
+            if *ident == variant && !prefix.segments.is_empty() && is_enum(prefix) {
+                f(ident);
+            }
+        }
+        syn::UseTree::Group(group) => {
+            for tree in &mut group.items {
+                for_each_variant_import(tree, prefix, variant, is_enum, f);
+            }
+        }
+        syn::UseTree::Glob(_) => {}
+    }
+}
+
+/// Macro arguments an enum variant can appear in: a comma-separated expression list
+/// (`assert_eq!`, `vec!`, `println!`, ...) or the `expr, pattern` of `matches!`.
+enum VariantMacroArgs {
+    Exprs(syn::punctuated::Punctuated<Expr, syn::Token![,]>),
+    Matches {
+        expr: Box<Expr>,
+        pat: Box<syn::Pat>,
+        guard: Option<Box<Expr>>,
+        /// A trailing comma or the message of `assert_matches!`, kept as is
+        rest: proc_macro2::TokenStream,
+    },
+}
+
+impl VariantMacroArgs {
+    fn parse(mac: &syn::Macro) -> Option<Self> {
+        use syn::parse::{ParseStream, Parser};
+
+        let name = mac.path.segments.last()?.ident.to_string();
+        if matches!(
+            name.as_str(),
+            "matches" | "assert_matches" | "debug_assert_matches"
+        ) {
+            let parser = |input: ParseStream| {
+                let expr: Box<Expr> = input.parse()?;
+                input.parse::<syn::Token![,]>()?;
+                let pat = Box::new(syn::Pat::parse_multi_with_leading_vert(input)?);
+                let guard = if input.parse::<Option<syn::Token![if]>>()?.is_some() {
+                    Some(input.parse()?)
+                } else {
+                    None
+                };
+                let rest = input.parse()?;
+                Ok(Self::Matches {
+                    expr,
+                    pat,
+                    guard,
+                    rest,
+                })
+            };
+            return parser.parse2(mac.tokens.clone()).ok();
+        }
+        syn::punctuated::Punctuated::parse_terminated
+            .parse2(mac.tokens.clone())
+            .ok()
+            .map(Self::Exprs)
+    }
+
+    fn into_token_stream(self) -> proc_macro2::TokenStream {
+        match self {
+            Self::Exprs(exprs) => exprs.into_token_stream(),
+            Self::Matches {
+                expr,
+                pat,
+                guard,
+                rest,
+            } => {
+                let guard = guard.map(|guard| quote::quote!(if #guard));
+                quote::quote!(#expr, #pat #guard #rest)
+            }
+        }
+    }
 }
 
 // Mutating visitor for renaming functions (reformat mode)
@@ -6153,6 +10746,8 @@ struct FunctionRenamer {
     old_name: String,
     new_name: String,
     path_resolver: Option<PathResolver>,
+    /// Add `#[link_name = "old"]` to renamed extern-block declarations
+    keep_link_name: bool,
     modified: bool,
 }
 
@@ -6199,6 +10794,16 @@ impl VisitMut for FunctionRenamer {
         syn::visit_mut::visit_trait_item_fn_mut(self, node);
     }
 
+    fn visit_foreign_item_fn_mut(&mut self, node: &mut syn::ForeignItemFn) {
+        // Rename function declared in an extern block, optionally still linking the old symbol
+        if self.keep_link_name && node.sig.ident == self.old_name && !has_link_name(&node.attrs) {
+            let old_name = &self.old_name;
+            node.attrs.push(syn::parse_quote!(#[link_name = #old_name]));
+        }
+        self.rename_ident(&mut node.sig.ident);
+        syn::visit_mut::visit_foreign_item_fn_mut(self, node);
+    }
+
     fn visit_expr_mut(&mut self, expr: &mut syn::Expr) {
         match expr {
             syn::Expr::Call(call) => {
@@ -6229,6 +10834,8 @@ struct FunctionReplacementCollector {
     old_name: String,
     new_name: String,
     path_resolver: Option<PathResolver>,
+    /// Add `#[link_name = "old"]` to renamed extern-block declarations
+    keep_link_name: bool,
     replacements: Vec<crate::surgical::Replacement>,
 }
 
@@ -6282,6 +10889,28 @@ impl<'ast> syn::visit::Visit<'ast> for FunctionReplacementCollector {
         syn::visit::visit_trait_item_fn(self, node);
     }
 
+    fn visit_foreign_item_fn(&mut self, node: &'ast syn::ForeignItemFn) {
+        // Collect extern-block declaration rename, optionally still linking the old symbol
+        if self.keep_link_name && node.sig.ident == self.old_name && !has_link_name(&node.attrs) {
+            // Right after any existing attributes, at the declaration's indentation
+            let start = match &node.vis {
+                syn::Visibility::Inherited => node.sig.span().start(),
+                vis => vis.span().start(),
+            };
+            self.replacements.push(crate::surgical::Replacement::new(
+                start,
+                start,
+                format!(
+                    "#[link_name = \"{}\"]\n{}",
+                    self.old_name,
+                    " ".repeat(start.column)
+                ),
+            ));
+        }
+        self.collect_replacement(&node.sig.ident);
+        syn::visit::visit_foreign_item_fn(self, node);
+    }
+
     fn visit_expr(&mut self, expr: &'ast syn::Expr) {
         match expr {
             syn::Expr::Call(call) => {
@@ -6313,6 +10942,464 @@ impl<'ast> syn::visit::Visit<'ast> for FunctionReplacementCollector {
     }
 }
 
+/// Collects renames for a method on one type: definitions in inherent `impl Type` blocks,
+/// `Type::method` / `Self::method` paths, and `.method()` calls whose receiver is known to be
+/// a `Type`. Receivers are tracked per scope from type annotations, `Type::new()` /
+/// `Type::default()` initializers, struct literals, and `self` inside `impl Type`. Any other
+/// binding is recorded as unknown so it shadows outer knowledge instead of inheriting it.
+struct MethodReplacementCollector {
+    old_name: String,
+    new_name: String,
+    receiver_type: String,
+    /// Innermost scope last: binding name -> whether it is known to hold a `receiver_type`
+    scopes: Vec<std::collections::HashMap<String, bool>>,
+    /// Self type of each enclosing impl block (`None` inside a free function)
+    impl_stack: Vec<Option<String>>,
+    replacements: Vec<crate::surgical::Replacement>,
+}
+
+impl MethodReplacementCollector {
+    fn collect_replacement(&mut self, ident: &syn::Ident) {
+        let span = ident.span();
+        self.replacements.push(crate::surgical::Replacement::new(
+            span.start(),
+            span.end(),
+            self.new_name.clone(),
+        ));
+    }
+
+    fn in_receiver_impl(&self) -> bool {
+        self.impl_stack
+            .last()
+            .is_some_and(|ty| ty.as_deref() == Some(self.receiver_type.as_str()))
+    }
+
+    /// Whether a path segment names the receiver type (`Self` counts inside `impl Type`)
+    fn names_receiver(&self, ident: &syn::Ident) -> bool {
+        ident == &self.receiver_type || (ident == "Self" && self.in_receiver_impl())
+    }
+
+    fn type_is_receiver(&self, ty: &syn::Type) -> bool {
+        match ty {
+            syn::Type::Reference(r) => self.type_is_receiver(&r.elem),
+            syn::Type::Paren(p) => self.type_is_receiver(&p.elem),
+            syn::Type::Group(g) => self.type_is_receiver(&g.elem),
+            syn::Type::Path(p) => p
+                .path
+                .segments
+                .last()
+                .is_some_and(|seg| seg.arguments.is_none() && self.names_receiver(&seg.ident)),
+            _ => false,
+        }
+    }
+
+    fn expr_is_receiver(&self, expr: &syn::Expr) -> bool {
+        match expr {
+            syn::Expr::Path(p) => p.path.get_ident().is_some_and(|ident| {
+                if ident == "self" {
+                    return self.in_receiver_impl();
+                }
+                let name = ident.to_string();
+                self.scopes
+                    .iter()
+                    .rev()
+                    .find_map(|scope| scope.get(&name).copied())
+                    .unwrap_or(false)
+            }),
+            syn::Expr::Call(call) => match call.func.as_ref() {
+                syn::Expr::Path(p) => {
+                    let segments: Vec<_> = p.path.segments.iter().collect();
+                    matches!(
+                        segments.as_slice(),
+                        [.., ty, ctor] if self.names_receiver(&ty.ident)
+                            && (ctor.ident == "new" || ctor.ident == "default")
+                    )
+                }
+                _ => false,
+            },
+            syn::Expr::Struct(s) => s
+                .path
+                .segments
+                .last()
+                .is_some_and(|seg| self.names_receiver(&seg.ident)),
+            syn::Expr::Reference(r) => self.expr_is_receiver(&r.expr),
+            syn::Expr::Paren(p) => self.expr_is_receiver(&p.expr),
+            syn::Expr::Group(g) => self.expr_is_receiver(&g.expr),
+            _ => false,
+        }
+    }
+
+    fn bind(&mut self, name: String, known: bool) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name, known);
+        }
+    }
+
+    /// Record every binding in `pat`; a plain `name: Type` or `name` pattern takes `known`,
+    /// anything destructured is unknown.
+    fn bind_pat(&mut self, pat: &syn::Pat, known: bool) {
+        match pat {
+            syn::Pat::Type(pt) => {
+                let typed = self.type_is_receiver(&pt.ty);
+                self.bind_pat(&pt.pat, typed);
+            }
+            syn::Pat::Ident(pi) if pi.subpat.is_none() => self.bind(pi.ident.to_string(), known),
+            _ => {
+                struct Idents(Vec<String>);
+                impl<'ast> syn::visit::Visit<'ast> for Idents {
+                    fn visit_pat_ident(&mut self, node: &'ast syn::PatIdent) {
+                        self.0.push(node.ident.to_string());
+                        syn::visit::visit_pat_ident(self, node);
+                    }
+                }
+                let mut idents = Idents(Vec::new());
+                syn::visit::Visit::visit_pat(&mut idents, pat);
+                for name in idents.0 {
+                    self.bind(name, false);
+                }
+            }
+        }
+    }
+
+    fn bind_inputs<'a>(&mut self, inputs: impl IntoIterator<Item = &'a syn::FnArg>) {
+        for input in inputs {
+            if let syn::FnArg::Typed(pt) = input {
+                let typed = self.type_is_receiver(&pt.ty);
+                self.bind_pat(&pt.pat, typed);
+            }
+        }
+    }
+}
+
+impl<'ast> syn::visit::Visit<'ast> for MethodReplacementCollector {
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        let self_ty = match node.self_ty.as_ref() {
+            syn::Type::Path(p) => p.path.segments.last().map(|seg| seg.ident.to_string()),
+            _ => None,
+        };
+        let inherent = node.trait_.is_none();
+        self.impl_stack.push(self_ty);
+        if inherent && self.in_receiver_impl() {
+            for item in &node.items {
+                if let syn::ImplItem::Fn(f) = item
+                    && f.sig.ident == self.old_name
+                {
+                    self.collect_replacement(&f.sig.ident);
+                }
+            }
+        }
+        syn::visit::visit_item_impl(self, node);
+        self.impl_stack.pop();
+    }
+
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        self.impl_stack.push(None);
+        self.scopes.push(Default::default());
+        self.bind_inputs(&node.sig.inputs);
+        self.visit_block(&node.block);
+        self.scopes.pop();
+        self.impl_stack.pop();
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        self.scopes.push(Default::default());
+        self.bind_inputs(&node.sig.inputs);
+        self.visit_block(&node.block);
+        self.scopes.pop();
+    }
+
+    fn visit_block(&mut self, node: &'ast syn::Block) {
+        self.scopes.push(Default::default());
+        syn::visit::visit_block(self, node);
+        self.scopes.pop();
+    }
+
+    fn visit_local(&mut self, node: &'ast syn::Local) {
+        let known = node.init.as_ref().is_some_and(|init| {
+            self.visit_expr(&init.expr);
+            if let Some((_, diverge)) = &init.diverge {
+                self.visit_expr(diverge);
+            }
+            self.expr_is_receiver(&init.expr)
+        });
+        self.bind_pat(&node.pat, known);
+    }
+
+    fn visit_expr_closure(&mut self, node: &'ast syn::ExprClosure) {
+        self.scopes.push(Default::default());
+        for input in &node.inputs {
+            self.bind_pat(input, false);
+        }
+        self.visit_expr(&node.body);
+        self.scopes.pop();
+    }
+
+    fn visit_expr_for_loop(&mut self, node: &'ast syn::ExprForLoop) {
+        self.visit_expr(&node.expr);
+        self.scopes.push(Default::default());
+        self.bind_pat(&node.pat, false);
+        self.visit_block(&node.body);
+        self.scopes.pop();
+    }
+
+    fn visit_arm(&mut self, node: &'ast syn::Arm) {
+        self.scopes.push(Default::default());
+        self.bind_pat(&node.pat, false);
+        if let Some((_, guard)) = &node.guard {
+            self.visit_expr(guard);
+        }
+        self.visit_expr(&node.body);
+        self.scopes.pop();
+    }
+
+    fn visit_expr_let(&mut self, node: &'ast syn::ExprLet) {
+        // `if let` / `while let` bindings: visible in the following block, but the enclosing
+        // scope is the closest one we track, so mark them unknown there
+        self.visit_expr(&node.expr);
+        self.bind_pat(&node.pat, false);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
+        if node.method == self.old_name && self.expr_is_receiver(&node.receiver) {
+            self.collect_replacement(&node.method);
+        }
+        syn::visit::visit_expr_method_call(self, node);
+    }
+
+    fn visit_expr_path(&mut self, node: &'ast syn::ExprPath) {
+        let segments: Vec<_> = node.path.segments.iter().collect();
+        if let [.., ty, method] = segments.as_slice()
+            && method.ident == self.old_name
+            && self.names_receiver(&ty.ident)
+        {
+            self.collect_replacement(&method.ident);
+        }
+        syn::visit::visit_expr_path(self, node);
+    }
+}
+
+/// Finds `.field` accesses and, in the style of `MethodReplacementCollector`, tracks what
+/// type each binding is known to hold: from type annotations (through references and
+/// `Box`/`Rc`/`Arc`), `Type::new()` / `Type::default()` initializers, struct literals, and
+/// `self` inside an impl. Any other binding is recorded as unknown so it shadows outer
+/// knowledge instead of inheriting it.
+struct FieldAccessCollector {
+    field: String,
+    /// Innermost scope last: binding name -> the type it's known to hold, if any
+    scopes: Vec<std::collections::HashMap<String, Option<String>>>,
+    /// Self type of each enclosing impl block (`None` inside a free function)
+    impl_stack: Vec<Option<String>>,
+    /// (start of the access expression, span of the field name, receiver type)
+    found: Vec<(LineColumn, Span, Option<String>)>,
+}
+
+impl FieldAccessCollector {
+    /// The type a path segment names, with `Self` resolved inside an impl
+    fn named_type(&self, ident: &syn::Ident) -> Option<String> {
+        if ident == "Self" {
+            self.impl_stack.last().cloned().flatten()
+        } else {
+            Some(ident.to_string())
+        }
+    }
+
+    fn type_name(&self, ty: &syn::Type) -> Option<String> {
+        match ty {
+            syn::Type::Reference(r) => self.type_name(&r.elem),
+            syn::Type::Paren(p) => self.type_name(&p.elem),
+            syn::Type::Group(g) => self.type_name(&g.elem),
+            syn::Type::Path(p) => {
+                let seg = p.path.segments.last()?;
+                // Field access auto-derefs through smart pointers
+                if matches!(seg.ident.to_string().as_str(), "Box" | "Rc" | "Arc")
+                    && let syn::PathArguments::AngleBracketed(args) = &seg.arguments
+                    && let [syn::GenericArgument::Type(inner)] =
+                        args.args.iter().collect::<Vec<_>>().as_slice()
+                {
+                    return self.type_name(inner);
+                }
+                self.named_type(&seg.ident)
+            }
+            _ => None,
+        }
+    }
+
+    fn expr_type(&self, expr: &syn::Expr) -> Option<String> {
+        match expr {
+            syn::Expr::Path(p) => {
+                let ident = p.path.get_ident()?;
+                if ident == "self" {
+                    return self.impl_stack.last().cloned().flatten();
+                }
+                let name = ident.to_string();
+                self.scopes
+                    .iter()
+                    .rev()
+                    .find_map(|scope| scope.get(&name).cloned())
+                    .flatten()
+            }
+            syn::Expr::Call(call) => match call.func.as_ref() {
+                syn::Expr::Path(p) => {
+                    let segments: Vec<_> = p.path.segments.iter().collect();
+                    match segments.as_slice() {
+                        [.., ty, ctor] if ctor.ident == "new" || ctor.ident == "default" => {
+                            self.named_type(&ty.ident)
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            },
+            syn::Expr::Struct(s) => self.named_type(&s.path.segments.last()?.ident),
+            syn::Expr::Reference(r) => self.expr_type(&r.expr),
+            syn::Expr::Paren(p) => self.expr_type(&p.expr),
+            syn::Expr::Group(g) => self.expr_type(&g.expr),
+            _ => None,
+        }
+    }
+
+    fn bind(&mut self, name: String, ty: Option<String>) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name, ty);
+        }
+    }
+
+    /// Record every binding in `pat`; a plain `name: Type` or `name` pattern takes `ty`,
+    /// anything destructured is unknown.
+    fn bind_pat(&mut self, pat: &syn::Pat, ty: Option<String>) {
+        match pat {
+            syn::Pat::Type(pt) => {
+                let typed = self.type_name(&pt.ty);
+                self.bind_pat(&pt.pat, typed);
+            }
+            syn::Pat::Ident(pi) if pi.subpat.is_none() => self.bind(pi.ident.to_string(), ty),
+            _ => {
+                struct Idents(Vec<String>);
+                impl<'ast> syn::visit::Visit<'ast> for Idents {
+                    fn visit_pat_ident(&mut self, node: &'ast syn::PatIdent) {
+                        self.0.push(node.ident.to_string());
+                        syn::visit::visit_pat_ident(self, node);
+                    }
+                }
+                let mut idents = Idents(Vec::new());
+                syn::visit::Visit::visit_pat(&mut idents, pat);
+                for name in idents.0 {
+                    self.bind(name, None);
+                }
+            }
+        }
+    }
+
+    fn bind_inputs<'a>(&mut self, inputs: impl IntoIterator<Item = &'a syn::FnArg>) {
+        for input in inputs {
+            if let syn::FnArg::Typed(pt) = input {
+                let typed = self.type_name(&pt.ty);
+                self.bind_pat(&pt.pat, typed);
+            }
+        }
+    }
+}
+
+impl<'ast> syn::visit::Visit<'ast> for FieldAccessCollector {
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        let self_ty = match node.self_ty.as_ref() {
+            syn::Type::Path(p) => p.path.segments.last().map(|seg| seg.ident.to_string()),
+            _ => None,
+        };
+        self.impl_stack.push(self_ty);
+        syn::visit::visit_item_impl(self, node);
+        self.impl_stack.pop();
+    }
+
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        self.impl_stack.push(None);
+        self.scopes.push(Default::default());
+        self.bind_inputs(&node.sig.inputs);
+        self.visit_block(&node.block);
+        self.scopes.pop();
+        self.impl_stack.pop();
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        self.scopes.push(Default::default());
+        self.bind_inputs(&node.sig.inputs);
+        self.visit_block(&node.block);
+        self.scopes.pop();
+    }
+
+    fn visit_block(&mut self, node: &'ast syn::Block) {
+        self.scopes.push(Default::default());
+        syn::visit::visit_block(self, node);
+        self.scopes.pop();
+    }
+
+    fn visit_local(&mut self, node: &'ast syn::Local) {
+        let ty = node.init.as_ref().and_then(|init| {
+            self.visit_expr(&init.expr);
+            if let Some((_, diverge)) = &init.diverge {
+                self.visit_expr(diverge);
+            }
+            self.expr_type(&init.expr)
+        });
+        self.bind_pat(&node.pat, ty);
+    }
+
+    fn visit_expr_closure(&mut self, node: &'ast syn::ExprClosure) {
+        self.scopes.push(Default::default());
+        for input in &node.inputs {
+            self.bind_pat(input, None);
+        }
+        self.visit_expr(&node.body);
+        self.scopes.pop();
+    }
+
+    fn visit_expr_for_loop(&mut self, node: &'ast syn::ExprForLoop) {
+        self.visit_expr(&node.expr);
+        self.scopes.push(Default::default());
+        self.bind_pat(&node.pat, None);
+        self.visit_block(&node.body);
+        self.scopes.pop();
+    }
+
+    fn visit_arm(&mut self, node: &'ast syn::Arm) {
+        self.scopes.push(Default::default());
+        self.bind_pat(&node.pat, None);
+        if let Some((_, guard)) = &node.guard {
+            self.visit_expr(guard);
+        }
+        self.visit_expr(&node.body);
+        self.scopes.pop();
+    }
+
+    fn visit_expr_let(&mut self, node: &'ast syn::ExprLet) {
+        self.visit_expr(&node.expr);
+        self.bind_pat(&node.pat, None);
+    }
+
+    fn visit_expr_field(&mut self, node: &'ast syn::ExprField) {
+        if let syn::Member::Named(ident) = &node.member
+            && *ident == self.field
+        {
+            let receiver_type = self.expr_type(&node.base);
+            self.found
+                .push((node.base.span().start(), ident.span(), receiver_type));
+        }
+        syn::visit::visit_expr_field(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        // Accesses in `println!("{}", cfg.timeout)` and the like
+        use syn::parse::Parser;
+        if let Ok(exprs) = syn::punctuated::Punctuated::<Expr, syn::Token![,]>::parse_terminated
+            .parse2(node.tokens.clone())
+        {
+            for expr in &exprs {
+                self.visit_expr(expr);
+            }
+        }
+    }
+}
+
 // ============================================================================
 // Doc Comment Operations
 // ============================================================================
@@ -6351,6 +11438,62 @@ fn generate_doc_comment(text: &str, style: &DocCommentStyle) -> String {
     }
 }
 
+/// Whether an extern-block declaration already names its symbol with `#[link_name]`.
+fn has_link_name(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| attr.path().is_ident("link_name"))
+}
+
+/// First variable a closure takes from its environment, in source order: a single-segment
+/// lowercase path (or `self`) that isn't bound by the closure's parameters or inside its
+/// body, and isn't the function being called. Uses inside macro arguments aren't seen.
+fn closure_first_capture(closure: &syn::ExprClosure) -> Option<String> {
+    use syn::visit::Visit;
+
+    #[derive(Default)]
+    struct Captures {
+        bound: std::collections::HashSet<String>,
+        first: Option<String>,
+    }
+
+    impl<'ast> Visit<'ast> for Captures {
+        fn visit_pat_ident(&mut self, node: &'ast syn::PatIdent) {
+            self.bound.insert(node.ident.to_string());
+            syn::visit::visit_pat_ident(self, node);
+        }
+
+        fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+            // `helper(x)` calls an item; only its arguments can capture
+            if !matches!(&*node.func, syn::Expr::Path(p) if p.path.segments.len() == 1) {
+                self.visit_expr(&node.func);
+            }
+            for arg in &node.args {
+                self.visit_expr(arg);
+            }
+        }
+
+        fn visit_expr_path(&mut self, node: &'ast syn::ExprPath) {
+            if self.first.is_none()
+                && node.qself.is_none()
+                && let Some(ident) = node.path.get_ident()
+            {
+                let name = ident.to_string();
+                let is_variable =
+                    name == "self" || name.starts_with(|c: char| c.is_lowercase() || c == '_');
+                if is_variable && !self.bound.contains(&name) {
+                    self.first = Some(name);
+                }
+            }
+        }
+    }
+
+    let mut captures = Captures::default();
+    for input in &closure.inputs {
+        captures.visit_pat(input);
+    }
+    captures.visit_expr(&closure.body);
+    captures.first
+}
+
 /// Extract preceding comments (both doc and regular) before a given line
 /// Returns None if no comments found, Some(comment_text) if comments exist
 fn extract_preceding_comment(content: &str, start_line: usize) -> Option<String> {
@@ -6456,6 +11599,80 @@ impl<'ast> syn::visit::Visit<'ast> for TargetFinder {
         }
         syn::visit::visit_item_fn(self, node);
     }
+
+    /// `impl-method` targets are named `Type::method`, matched against inherent and trait
+    /// impls of `Type`; the first match wins.
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        if self.target_type == "impl-method"
+            && self.found_position.is_none()
+            && let Some((type_name, method_name)) = self.target_name.rsplit_once("::")
+            && let syn::Type::Path(type_path) = node.self_ty.as_ref()
+            && type_path
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == type_name)
+        {
+            for item in &node.items {
+                if let syn::ImplItem::Fn(method) = item
+                    && method.sig.ident == method_name
+                {
+                    let line = method_start_line(&method.attrs, &method.sig);
+                    self.found_position = Some((line, String::new()));
+                    break;
+                }
+            }
+        }
+        syn::visit::visit_item_impl(self, node);
+    }
+
+    /// `trait-method` targets are named `Trait::method`, with or without a default body.
+    fn visit_item_trait(&mut self, node: &'ast syn::ItemTrait) {
+        if self.target_type == "trait-method"
+            && self.found_position.is_none()
+            && let Some((trait_name, method_name)) = self.target_name.rsplit_once("::")
+            && node.ident == trait_name
+        {
+            for item in &node.items {
+                if let syn::TraitItem::Fn(method) = item
+                    && method.sig.ident == method_name
+                {
+                    let line = method_start_line(&method.attrs, &method.sig);
+                    self.found_position = Some((line, String::new()));
+                    break;
+                }
+            }
+        }
+        syn::visit::visit_item_trait(self, node);
+    }
+}
+
+/// Drop the matches nested inside another match that `encloses` accepts. Matches with the
+/// same span as that match are kept.
+fn retain_outermost(matches: &mut Vec<InspectResult>, encloses: impl Fn(&InspectResult) -> bool) {
+    let span = |m: &InspectResult| {
+        let loc = &m.location;
+        ((loc.line, loc.column), (loc.end_line, loc.end_column))
+    };
+    let outer: Vec<_> = matches.iter().filter(|m| encloses(m)).map(span).collect();
+    matches.retain(|m| {
+        let (start, end) = span(m);
+        !outer
+            .iter()
+            .any(|&other| other != (start, end) && other.0 <= start && end <= other.1)
+    });
+}
+
+/// Line a method's doc comment goes above: its first attribute other than a doc comment
+/// (`#[inline]`, `#[must_use]`), or else its `fn` line.
+fn method_start_line(attrs: &[syn::Attribute], sig: &syn::Signature) -> usize {
+    attrs
+        .iter()
+        .find(|attr| !attr.path().is_ident("doc"))
+        .map_or_else(
+            || sig.fn_token.span.start().line,
+            |attr| attr.pound_token.span.start().line,
+        )
 }
 
 impl RustEditor {
@@ -6510,11 +11727,16 @@ impl RustEditor {
             }
 
             // Update content
+            let trailing_newline = self.content.ends_with('\n');
             self.content = new_lines.join("\n");
+            if trailing_newline {
+                self.content.push('\n');
+            }
 
             // Re-parse to update syntax tree
-            self.syntax_tree =
-                syn::parse_str(&self.content).context("Failed to re-parse after adding comment")?;
+            self.syntax_tree = self
+                .parse_content()
+                .context("Failed to re-parse after adding comment")?;
 
             Ok(ModificationResult {
                 changed: true,
@@ -6612,10 +11834,15 @@ impl RustEditor {
             new_lines.extend(lines.iter().skip(line_idx).map(|line| (*line).to_string()));
 
             // Update content
+            let trailing_newline = self.content.ends_with('\n');
             self.content = new_lines.join("\n");
+            if trailing_newline {
+                self.content.push('\n');
+            }
 
             // Re-parse to update syntax tree
-            self.syntax_tree = syn::parse_str(&self.content)
+            self.syntax_tree = self
+                .parse_content()
                 .context("Failed to re-parse after updating comment")?;
 
             Ok(ModificationResult {
@@ -6697,10 +11924,15 @@ impl RustEditor {
             new_lines.extend(lines.iter().skip(line_idx).map(|line| (*line).to_string()));
 
             // Update content
+            let trailing_newline = self.content.ends_with('\n');
             self.content = new_lines.join("\n");
+            if trailing_newline {
+                self.content.push('\n');
+            }
 
             // Re-parse to update syntax tree
-            self.syntax_tree = syn::parse_str(&self.content)
+            self.syntax_tree = self
+                .parse_content()
                 .context("Failed to re-parse after removing comment")?;
 
             Ok(ModificationResult {
@@ -2,20 +2,115 @@
 //!
 //! Returns a structured `ExecuteResult` with everything a caller needs to render output, make
 //! decisions, or surface errors. No `println!`/`eprintln!` — embedders (MCP server, yah, tests)
-//! decide what to display; the CLI in `main.rs` wraps these calls with its own renderer.
+//! decide what to display; the CLI in `main.rs` wraps these calls with its own renderer. The
+//! one exception is the per-file `--verbose` log, which is off unless `ExecuteOpts::verbose` turns
+//! it on.
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
+use crate::cfg::Scope;
 use crate::editor::RustEditor;
-use crate::operations::{BackupNode, Operation};
+use crate::operations::{BackupNode, BatchCondition, BatchOperation, Operation};
 use crate::state::{
     FileModification, RunMetadata, RunStatus, generate_run_id, get_state_dir, hash_file,
     save_backup_nodes, save_run_metadata,
 };
+use crate::verbose::Verbose;
+
+/// Where the original of each file is copied before it's overwritten (`--backups`,
+/// `--backup-dir`). Independent of the revertible run state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackupLocation {
+    /// `file.rs.bak` next to the file
+    NextToFile,
+    /// `<dir>/<path of the file>.bak`, mirroring the file's path under the directory
+    Dir(PathBuf),
+}
+
+/// Path of the `.bak` copy of `file_path` under `location`. With a directory, the file's
+/// path is mirrored below it, keeping only its normal components (no root, `.` or `..`).
+pub fn backup_path(file_path: &Path, location: &BackupLocation) -> PathBuf {
+    let mut name = file_path.as_os_str().to_owned();
+    name.push(".bak");
+    match location {
+        BackupLocation::NextToFile => PathBuf::from(name),
+        BackupLocation::Dir(dir) => {
+            let mut path = dir.clone();
+            path.extend(file_path.components().filter_map(|c| match c {
+                std::path::Component::Normal(part) => Some(part),
+                _ => None,
+            }));
+            let mut name = path.into_os_string();
+            name.push(".bak");
+            PathBuf::from(name)
+        }
+    }
+}
+
+/// Write `content` to `path`, first copying what's there to its `.bak` under `backups`.
+fn write_file(path: &Path, content: &str, backups: Option<&BackupLocation>) -> Result<()> {
+    if let Some(location) = backups
+        && path.exists()
+    {
+        let backup = backup_path(path, location);
+        if let Some(parent) = backup.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::copy(path, &backup).with_context(|| {
+            format!(
+                "Failed to back up {} to {}",
+                path.display(),
+                backup.display()
+            )
+        })?;
+    }
+    std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Check that every change still parses as Rust, so an edit that breaks a file aborts the
+/// whole run before anything is written.
+fn validate_changes(changes: &[FileChange]) -> Result<()> {
+    for change in changes {
+        if let Err(e) = syn::parse_file(&change.new_content) {
+            let start = e.span().start();
+            anyhow::bail!(
+                "{}:{}:{}: the change would leave this file unparseable ({}); no files were written",
+                change.path.display(),
+                start.line,
+                start.column + 1,
+                e
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Write every change (to `output` instead, when set), after `validate_changes`. If a write
+/// fails, the files already written get their old content back, so the run lands whole or
+/// not at all.
+fn write_all(
+    changes: &[FileChange],
+    output: Option<&Path>,
+    backups: Option<&BackupLocation>,
+) -> Result<()> {
+    for (written, change) in changes.iter().enumerate() {
+        let path = output.unwrap_or(&change.path);
+        if let Err(e) = write_file(path, &change.new_content, backups) {
+            for done in &changes[..written] {
+                let _ = std::fs::write(output.unwrap_or(&done.path), &done.old_content);
+            }
+            return Err(e);
+        }
+    }
+    Ok(())
+}
 
 #[derive(Debug, Clone, Default)]
 pub struct ExecuteOpts {
@@ -25,6 +120,18 @@ pub struct ExecuteOpts {
     pub output: Option<PathBuf>,
     /// Stop after this many modifications across all files.
     pub limit: Option<usize>,
+    /// Leave a file untouched when the operation would modify more than this many nodes in
+    /// it (see `ExecuteResult::capped_files`).
+    pub max_per_file: Option<usize>,
+    /// Copy each file to its `.bak` before overwriting it (`--backups`, `--backup-dir`).
+    pub backups: Option<BackupLocation>,
+    /// The per-file `--verbose` log.
+    pub verbose: Verbose,
+    /// `--strict`: a file that doesn't parse fails the run instead of being skipped.
+    pub strict: bool,
+    /// The code the operation sees (`--cfg`, `--skip-tests`, `--skip-attr`, `--in-module`);
+    /// code outside it is left as it was.
+    pub scope: Scope,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,82 +147,288 @@ pub struct ExecuteResult {
     pub changes: Vec<FileChange>,
     pub total_modifications: usize,
     pub unmatched_qualified_paths: HashMap<String, usize>,
+    /// Files skipped because they don't parse, however many files there were (with
+    /// `ExecuteOpts::strict`, the first one is an error instead)
     pub parse_errors: Vec<(PathBuf, String)>,
     /// Last per-file apply error from a multi-file run (single-file errors bubble up).
     pub last_error: Option<String>,
     pub limit_hit: bool,
+    /// Files skipped by `ExecuteOpts::max_per_file`, with the number of modifications the
+    /// operation would have made in each.
+    #[serde(default)]
+    pub capped_files: Vec<(PathBuf, usize)>,
     /// Set when `execute_with_state` applied changes successfully.
     pub run_id: Option<String>,
     /// Per-file metadata captured for state tracking. Empty for `execute()`.
     pub files_modified: Vec<FileModification>,
+    /// `#[cfg]`-gated items the changes touch (counted only without `--cfg`)
+    #[serde(default)]
+    pub cfg_gated_items: usize,
+    /// Matches left alone inside code `--skip-tests`/`--skip-attr` skips, or outside the
+    /// `--in-module` module
+    #[serde(default)]
+    pub skipped_matches: usize,
 }
 
-/// Apply `op` across `files` without printing anything. When `opts.apply` is
-/// true, writes modified files in place (or to `opts.output` if set);
-/// otherwise performs a dry run and only fills the result.
-pub fn execute(files: &[PathBuf], op: &Operation, opts: &ExecuteOpts) -> Result<ExecuteResult> {
-    let mut result = ExecuteResult::default();
+/// What applying the operation to one file produced, before anything is written.
+enum FileOutcome {
+    ParseError(anyhow::Error),
+    ApplyError(anyhow::Error),
+    Unchanged {
+        unmatched: Option<HashMap<String, usize>>,
+        skipped_matches: usize,
+    },
+    Changed {
+        content: String,
+        new_content: String,
+        modified_nodes: Vec<BackupNode>,
+        unmatched: Option<HashMap<String, usize>>,
+        cfg_gated_items: usize,
+        skipped_matches: usize,
+    },
+}
+
+/// Read, parse and apply `op` to every file in parallel (rayon's pool; the CLI sizes it
+/// with `--jobs`). Outcomes come back in `files` order, so everything done with them
+/// afterwards (writes, state, output) is as deterministic as a serial run. Each comes with
+/// the time spent on its file, for `--verbose`.
+fn analyze_files(
+    files: &[PathBuf],
+    op: &Operation,
+    scope: &Scope,
+) -> Result<Vec<(FileOutcome, Duration)>> {
+    use rayon::prelude::*;
+
+    files
+        .par_iter()
+        .map(|file_path| {
+            let started = Instant::now();
+            let content = std::fs::read_to_string(file_path)
+                .with_context(|| format!("Failed to read {}", file_path.display()))?;
+            let outcome = analyze_content(content, op, scope);
+            Ok((outcome, started.elapsed()))
+        })
+        .collect()
+}
+
+/// The `--verbose` line for one file's outcome.
+fn log_outcome(verbose: &Verbose, file_path: &Path, outcome: &FileOutcome, elapsed: Duration) {
+    match outcome {
+        FileOutcome::ParseError(e) => verbose.skipped(file_path, e),
+        FileOutcome::ApplyError(e) => verbose.file_failed(file_path, e, elapsed),
+        FileOutcome::Unchanged { .. } => verbose.file_done(file_path, 0, elapsed),
+        FileOutcome::Changed { modified_nodes, .. } => {
+            verbose.file_done(file_path, modified_nodes.len(), elapsed);
+        }
+    }
+}
 
-    for file_path in files {
-        let content = std::fs::read_to_string(file_path)
-            .with_context(|| format!("Failed to read {}", file_path.display()))?;
+/// Parse `content` afresh and apply `op` to the code in `scope`.
+fn analyze_content(content: String, op: &Operation, scope: &Scope) -> FileOutcome {
+    let mut editor = match RustEditor::with_scope(&content, scope.clone()) {
+        Ok(editor) => editor,
+        Err(e) => return FileOutcome::ParseError(e),
+    };
+    match editor.apply_operation(op) {
+        Ok(op_result) if op_result.changed => {
+            let new_content = editor.to_string();
+            let skipped_matches = skipped_matches(
+                &content,
+                op,
+                scope,
+                &new_content,
+                op_result.modified_nodes.len(),
+            );
+            if new_content == content {
+                return FileOutcome::Unchanged {
+                    unmatched: op_result.unmatched_qualified_paths,
+                    skipped_matches,
+                };
+            }
+            let cfg_gated_items = if scope.cfg().is_none() {
+                crate::cfg::gated_items_touched(&content, &new_content)
+            } else {
+                0
+            };
+            FileOutcome::Changed {
+                content,
+                new_content,
+                modified_nodes: op_result.modified_nodes,
+                unmatched: op_result.unmatched_qualified_paths,
+                cfg_gated_items,
+                skipped_matches,
+            }
+        }
+        Ok(op_result) => FileOutcome::Unchanged {
+            skipped_matches: skipped_matches(&content, op, scope, &content, 0),
+            unmatched: op_result.unmatched_qualified_paths,
+        },
+        Err(e) => FileOutcome::ApplyError(e),
+    }
+}
 
-        let mut editor = match RustEditor::new(&content) {
-            Ok(editor) => editor,
-            Err(e) => {
-                if files.len() == 1 {
+/// How many matches `--skip-tests`/`--skip-attr`/`--in-module` left alone: what `op` does to `content`
+/// without them beyond what it did with them (`new_content`, `modified_nodes` nodes). Counts
+/// changed regions too, for edits that don't record where they were.
+fn skipped_matches(
+    content: &str,
+    op: &Operation,
+    scope: &Scope,
+    new_content: &str,
+    modified_nodes: usize,
+) -> usize {
+    if !scope.skips_code() {
+        return 0;
+    }
+    let Ok(mut editor) = RustEditor::with_scope(content, scope.unskipped()) else {
+        return 0;
+    };
+    match editor.apply_operation(op) {
+        Ok(result) if result.changed => {
+            let nodes = result.modified_nodes.len().saturating_sub(modified_nodes);
+            let regions = crate::cfg::changed_regions(content, &editor.to_string())
+                .len()
+                .saturating_sub(crate::cfg::changed_regions(content, new_content).len());
+            nodes.max(regions)
+        }
+        _ => 0,
+    }
+}
+
+/// Apply `op` to `source` in memory (`--stdin`): the edited source, or `None` when the
+/// operation matched nothing. Nothing is read from or written to disk, and no state is kept.
+pub fn apply_to_source(source: &str, op: &Operation, scope: &Scope) -> Result<Option<String>> {
+    match analyze_content(source.to_string(), op, scope) {
+        FileOutcome::ParseError(e) => Err(e).context("Failed to parse the code on stdin"),
+        FileOutcome::ApplyError(e) => Err(e),
+        FileOutcome::Unchanged { .. } => Ok(None),
+        FileOutcome::Changed { new_content, .. } => Ok(Some(new_content)),
+    }
+}
+
+/// Fold one file's outcome into `result`. Returns the change to write, if any: `None` for
+/// files that failed, didn't change, or went over `max_per_file`. Files that don't parse are
+/// skipped (an error under `--strict`); errors applying the operation to the only file of a
+/// run bubble up, as they would when the operation is applied directly.
+fn record_outcome(
+    result: &mut ExecuteResult,
+    file_path: &Path,
+    outcome: FileOutcome,
+    single_file: bool,
+    opts: &ExecuteOpts,
+) -> Result<Option<FileChange>> {
+    let (content, new_content, modified_nodes, unmatched, cfg_gated_items, skipped_matches) =
+        match outcome {
+            FileOutcome::ParseError(e) => {
+                if opts.strict {
                     return Err(e)
                         .with_context(|| format!("Failed to parse {}", file_path.display()));
                 }
                 result
                     .parse_errors
-                    .push((file_path.clone(), format!("{}", e)));
-                continue;
+                    .push((file_path.to_path_buf(), format!("{}", e)));
+                return Ok(None);
             }
-        };
-
-        match editor.apply_operation(op) {
-            Ok(op_result) => {
-                if let Some(unmatched) = op_result.unmatched_qualified_paths {
-                    for (path, count) in unmatched {
-                        *result.unmatched_qualified_paths.entry(path).or_insert(0) += count;
-                    }
-                }
-
-                if op_result.changed {
-                    result.total_modifications += op_result.modified_nodes.len();
-                    let new_content = editor.to_string();
-
-                    if opts.apply {
-                        let write_path = opts.output.as_ref().unwrap_or(file_path);
-                        std::fs::write(write_path, &new_content)
-                            .with_context(|| format!("Failed to write {}", write_path.display()))?;
-                    }
-
-                    result.changes.push(FileChange {
-                        path: file_path.clone(),
-                        old_content: content,
-                        new_content,
-                        modified_nodes: op_result.modified_nodes,
-                    });
-
-                    if let Some(limit) = opts.limit
-                        && result.total_modifications >= limit
-                    {
-                        result.limit_hit = true;
-                        break;
-                    }
-                }
-            }
-            Err(e) => {
-                if files.len() == 1 {
+            FileOutcome::ApplyError(e) => {
+                if single_file {
                     return Err(e);
                 }
                 result.last_error = Some(format!("{}", e));
+                return Ok(None);
             }
+            FileOutcome::Unchanged {
+                unmatched,
+                skipped_matches,
+            } => (None, None, Vec::new(), unmatched, 0, skipped_matches),
+            FileOutcome::Changed {
+                content,
+                new_content,
+                modified_nodes,
+                unmatched,
+                cfg_gated_items,
+                skipped_matches,
+            } => (
+                Some(content),
+                Some(new_content),
+                modified_nodes,
+                unmatched,
+                cfg_gated_items,
+                skipped_matches,
+            ),
+        };
+
+    result.skipped_matches += skipped_matches;
+
+    if let Some(unmatched) = unmatched {
+        for (path, count) in unmatched {
+            *result.unmatched_qualified_paths.entry(path).or_insert(0) += count;
         }
     }
+    let (Some(old_content), Some(new_content)) = (content, new_content) else {
+        return Ok(None);
+    };
+
+    if let Some(max) = opts.max_per_file
+        && modified_nodes.len() > max
+    {
+        result
+            .capped_files
+            .push((file_path.to_path_buf(), modified_nodes.len()));
+        return Ok(None);
+    }
+    result.total_modifications += modified_nodes.len();
+    result.cfg_gated_items += cfg_gated_items;
+    Ok(Some(FileChange {
+        path: file_path.to_path_buf(),
+        old_content,
+        new_content,
+        modified_nodes,
+    }))
+}
+
+/// Whether `--limit` has been reached; marks the result when it has.
+const fn limit_reached(result: &mut ExecuteResult, opts: &ExecuteOpts) -> bool {
+    if let Some(limit) = opts.limit
+        && result.total_modifications >= limit
+    {
+        result.limit_hit = true;
+        return true;
+    }
+    false
+}
 
+/// Apply `op` across `files` without printing anything. When `opts.apply` is
+/// true, writes modified files in place (or to `opts.output` if set);
+/// otherwise performs a dry run and only fills the result.
+///
+/// Files are parsed and matched in parallel; results and `--limit` are then handled one
+/// file at a time in `files` order. Nothing is written until every changed file has been
+/// checked to still parse (see `validate_changes`).
+pub fn execute(files: &[PathBuf], op: &Operation, opts: &ExecuteOpts) -> Result<ExecuteResult> {
+    let mut result = ExecuteResult::default();
+    let outcomes = analyze_files(files, op, &opts.scope)?;
+
+    for (file_path, (outcome, elapsed)) in files.iter().zip(outcomes) {
+        log_outcome(&opts.verbose, file_path, &outcome, elapsed);
+        let Some(change) = record_outcome(&mut result, file_path, outcome, files.len() == 1, opts)?
+        else {
+            continue;
+        };
+        result.changes.push(change);
+
+        if limit_reached(&mut result, opts) {
+            break;
+        }
+    }
+
+    if opts.apply {
+        validate_changes(&result.changes)?;
+        write_all(
+            &result.changes,
+            opts.output.as_deref(),
+            opts.backups.as_ref(),
+        )?;
+    }
     Ok(result)
 }
 
@@ -137,77 +450,256 @@ pub fn execute_with_state(
     if !opts.apply || opts.output.is_some() {
         return execute(files, op, opts);
     }
+    write_with_state(files, op, opts, local_state, command_line, None)
+}
 
-    let run_id = generate_run_id();
-    let state_dir = get_state_dir(local_state)?;
-    let mut result = ExecuteResult::default();
+/// Like `execute_with_state`, but each changed region is offered to `review` first and only
+/// the accepted ones are written (`--interactive`).
+///
+/// Always applies (`opts.apply` and `opts.output` are ignored). A file with only some of its
+/// regions accepted is backed up whole, so reverting the run restores exactly what was
+/// written. After `Review::Quit` nothing more is offered or written, but what was accepted
+/// before it is kept.
+pub fn execute_reviewed(
+    files: &[PathBuf],
+    op: &Operation,
+    opts: &ExecuteOpts,
+    local_state: bool,
+    command_line: String,
+    review: &mut Reviewer<'_>,
+) -> Result<ExecuteResult> {
+    write_with_state(files, op, opts, local_state, command_line, Some(review))
+}
 
-    for file_path in files {
-        let content = std::fs::read_to_string(file_path)
-            .with_context(|| format!("Failed to read {}", file_path.display()))?;
+/// One contiguous changed region of a file, as offered by `execute_reviewed`.
+#[derive(Debug, Clone)]
+pub struct ChangeHunk {
+    pub path: PathBuf,
+    /// Line in the original file where the region starts (1-based)
+    pub line: usize,
+    /// The region as unified-diff lines: ` ` context, `-` removed, `+` added
+    pub diff: Vec<String>,
+}
 
-        let mut editor = match RustEditor::new(&content) {
-            Ok(editor) => editor,
-            Err(e) => {
-                if files.len() == 1 {
-                    return Err(e)
-                        .with_context(|| format!("Failed to parse {}", file_path.display()));
-                }
-                result
-                    .parse_errors
-                    .push((file_path.clone(), format!("{}", e)));
-                continue;
-            }
-        };
+/// Callback deciding on each `ChangeHunk` in `execute_reviewed` (the CLI prompts).
+pub type Reviewer<'a> = dyn FnMut(&ChangeHunk) -> Result<Review> + 'a;
 
-        match editor.apply_operation(op) {
-            Ok(op_result) => {
-                if let Some(unmatched) = op_result.unmatched_qualified_paths {
-                    for (path, count) in unmatched {
-                        *result.unmatched_qualified_paths.entry(path).or_insert(0) += count;
-                    }
-                }
+/// The reviewer's answer for one `ChangeHunk`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Review {
+    Accept,
+    Skip,
+    /// Accept this region and every one after it without asking
+    AcceptAll,
+    /// Skip this region and stop reviewing
+    Quit,
+}
+
+/// Lines of unchanged code shown around each reviewed region.
+const REVIEW_CONTEXT: usize = 3;
+
+#[derive(Default)]
+pub(crate) struct ReviewState {
+    pub(crate) accept_all: bool,
+    pub(crate) quit: bool,
+}
+
+/// Offer each changed region of `change` to `review` and keep only the accepted ones, with
+/// the number of modifications they count for. `None` when nothing was accepted.
+pub(crate) fn review_change(
+    mut change: FileChange,
+    review: &mut Reviewer<'_>,
+    state: &mut ReviewState,
+) -> Result<Option<(FileChange, usize)>> {
+    let diff = similar::TextDiff::from_lines(&change.old_content, &change.new_content);
+    let (old, new) = (diff.old_slices(), diff.new_slices());
+    let line = |text: &str, prefix: char| format!("{}{}", prefix, text.trim_end_matches('\n'));
+
+    let ranges: Vec<(Range<usize>, Range<usize>)> = diff
+        .grouped_ops(0)
+        .iter()
+        .filter_map(|group| {
+            let (first, last) = (group.first()?, group.last()?);
+            Some((
+                first.old_range().start..last.old_range().end,
+                first.new_range().start..last.new_range().end,
+            ))
+        })
+        .flat_map(|(old_range, new_range)| {
+            split_by_nodes(old_range, new_range, &change.modified_nodes)
+        })
+        .collect();
 
-                if op_result.changed {
-                    result.total_modifications += op_result.modified_nodes.len();
-                    let new_content = editor.to_string();
-
-                    let hash_before = hash_file(file_path)?;
-                    save_backup_nodes(file_path, &op_result.modified_nodes, &run_id, &state_dir)?;
-
-                    std::fs::write(file_path, &new_content)
-                        .with_context(|| format!("Failed to write {}", file_path.display()))?;
-
-                    let hash_after = hash_file(file_path)?;
-
-                    result.files_modified.push(FileModification {
-                        path: file_path.clone(),
-                        hash_before,
-                        hash_after,
-                        backup_nodes: op_result.modified_nodes.clone(),
-                    });
-                    result.changes.push(FileChange {
-                        path: file_path.clone(),
-                        old_content: content,
-                        new_content,
-                        modified_nodes: op_result.modified_nodes,
-                    });
-
-                    if let Some(limit) = opts.limit
-                        && result.total_modifications >= limit
-                    {
-                        result.limit_hit = true;
-                        break;
-                    }
+    // (old range, new range, accepted) of each region
+    let mut regions = Vec::new();
+    for (old_range, new_range) in ranges {
+        let accepted = if state.quit {
+            false
+        } else if state.accept_all {
+            true
+        } else {
+            let before = old_range.start.saturating_sub(REVIEW_CONTEXT)..old_range.start;
+            let after = old_range.end..(old_range.end + REVIEW_CONTEXT).min(old.len());
+            let hunk = ChangeHunk {
+                path: change.path.clone(),
+                line: old_range.start + 1,
+                diff: old[before]
+                    .iter()
+                    .map(|l| line(l, ' '))
+                    .chain(old[old_range.clone()].iter().map(|l| line(l, '-')))
+                    .chain(new[new_range.clone()].iter().map(|l| line(l, '+')))
+                    .chain(old[after].iter().map(|l| line(l, ' ')))
+                    .collect(),
+            };
+            match review(&hunk)? {
+                Review::Accept => true,
+                Review::Skip => false,
+                Review::AcceptAll => {
+                    state.accept_all = true;
+                    true
                 }
-            }
-            Err(e) => {
-                if files.len() == 1 {
-                    return Err(e);
+                Review::Quit => {
+                    state.quit = true;
+                    false
                 }
-                result.last_error = Some(format!("{}", e));
             }
+        };
+        regions.push((old_range, new_range, accepted));
+    }
+
+    let (total, accepted) = (
+        regions.len(),
+        regions.iter().filter(|(_, _, accepted)| *accepted).count(),
+    );
+    if accepted == 0 {
+        return Ok(None);
+    }
+    if accepted == total {
+        let count = change.modified_nodes.len();
+        return Ok(Some((change, count)));
+    }
+
+    let mut content = String::with_capacity(change.old_content.len());
+    let mut pos = 0;
+    for (old_range, new_range, accepted) in regions {
+        content.extend(old[pos..old_range.start].iter().copied());
+        if accepted {
+            content.extend(new[new_range].iter().copied());
+        } else {
+            content.extend(old[old_range.clone()].iter().copied());
         }
+        pos = old_range.end;
+    }
+    content.extend(old[pos..].iter().copied());
+
+    // The operation's own backups describe every region; a partial file is restored whole
+    let lines = change.old_content.lines().count().max(1);
+    change.modified_nodes = vec![BackupNode {
+        node_type: "file".to_string(),
+        identifier: format!("{} of {} changes", accepted, total),
+        original_content: change.old_content.clone(),
+        location: crate::operations::NodeLocation {
+            line: 1,
+            column: 0,
+            end_line: lines,
+            end_column: 0,
+        },
+    }];
+    change.new_content = content;
+    Ok(Some((change, accepted)))
+}
+
+/// Split a region whose lines were rewritten one for one (`a.unwrap();` on three adjacent
+/// lines) where one modified node ends and the next begins, so each node is reviewed on
+/// its own. Lines of a multi-line node stay together.
+fn split_by_nodes(
+    old_range: Range<usize>,
+    new_range: Range<usize>,
+    nodes: &[BackupNode],
+) -> Vec<(Range<usize>, Range<usize>)> {
+    // 0-based line spans of the nodes starting in the region
+    let spans: Vec<Range<usize>> = nodes
+        .iter()
+        .map(|node| node.location.line.saturating_sub(1)..node.location.end_line)
+        .filter(|span| old_range.contains(&span.start))
+        .collect();
+    if old_range.len() != new_range.len() || spans.len() < 2 {
+        return vec![(old_range, new_range)];
+    }
+
+    let to_new = |range: Range<usize>| {
+        new_range.start + (range.start - old_range.start)
+            ..new_range.start + (range.end - old_range.start)
+    };
+    let mut regions = Vec::new();
+    let mut start = old_range.start;
+    for line in old_range.start + 1..old_range.end {
+        let joined = spans
+            .iter()
+            .any(|span| span.contains(&(line - 1)) && span.contains(&line));
+        if !joined {
+            regions.push((start..line, to_new(start..line)));
+            start = line;
+        }
+    }
+    regions.push((start..old_range.end, to_new(start..old_range.end)));
+    regions
+}
+
+fn write_with_state(
+    files: &[PathBuf],
+    op: &Operation,
+    opts: &ExecuteOpts,
+    local_state: bool,
+    command_line: String,
+    mut review: Option<&mut Reviewer<'_>>,
+) -> Result<ExecuteResult> {
+    let run_id = generate_run_id();
+    let state_dir = get_state_dir(local_state)?;
+    let mut result = ExecuteResult::default();
+    let mut review_state = ReviewState::default();
+    let outcomes = analyze_files(files, op, &opts.scope)?;
+
+    for (file_path, (outcome, elapsed)) in files.iter().zip(outcomes) {
+        if review_state.quit {
+            break;
+        }
+        log_outcome(&opts.verbose, file_path, &outcome, elapsed);
+        let Some(mut change) =
+            record_outcome(&mut result, file_path, outcome, files.len() == 1, opts)?
+        else {
+            continue;
+        };
+        if let Some(review) = review.as_deref_mut() {
+            // record_outcome counted every proposed modification
+            result.total_modifications -= change.modified_nodes.len();
+            let Some((reviewed, count)) = review_change(change, review, &mut review_state)? else {
+                continue;
+            };
+            result.total_modifications += count;
+            change = reviewed;
+        }
+        result.changes.push(change);
+
+        if limit_reached(&mut result, opts) {
+            break;
+        }
+    }
+
+    validate_changes(&result.changes)?;
+    let mut hashes_before = Vec::with_capacity(result.changes.len());
+    for change in &result.changes {
+        hashes_before.push(hash_file(&change.path)?);
+        save_backup_nodes(&change.path, &change.modified_nodes, &run_id, &state_dir)?;
+    }
+    write_all(&result.changes, None, opts.backups.as_ref())?;
+    for (change, hash_before) in result.changes.iter().zip(hashes_before) {
+        result.files_modified.push(FileModification {
+            path: change.path.clone(),
+            hash_before,
+            hash_after: hash_file(&change.path)?,
+            backup_nodes: change.modified_nodes.clone(),
+        });
     }
 
     if !result.files_modified.is_empty() {
@@ -226,3 +718,349 @@ pub fn execute_with_state(
 
     Ok(result)
 }
+
+/// Several operations of a batch modifying the same node of one file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchConflict {
+    pub path: PathBuf,
+    pub node_type: String,
+    pub identifier: String,
+    /// Indexes into the batch's operations, in order
+    pub operations: Vec<usize>,
+}
+
+/// A batch operation that didn't run because its `when` condition didn't hold.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedOperation {
+    /// Index into the batch's operations
+    pub index: usize,
+    pub reason: String,
+}
+
+#[derive(Debug, Default)]
+pub struct BatchPlan {
+    /// One result per operation (empty for skipped ones). A change's `old_content` is the
+    /// file as the operations before it left it.
+    pub operations: Vec<ExecuteResult>,
+    /// Net change to each file over the whole batch, in `files` order
+    pub changes: Vec<FileChange>,
+    pub conflicts: Vec<BatchConflict>,
+    pub skipped: Vec<SkippedOperation>,
+}
+
+/// Whether `condition` holds for the in-memory `contents`: its query matches in the `scope`
+/// of some file (files that don't parse are left out) exactly when it should exist.
+fn condition_holds(contents: &[String], condition: &BatchCondition, scope: &Scope) -> Result<bool> {
+    use rayon::prelude::*;
+
+    let found = contents
+        .par_iter()
+        .map(|content| {
+            let Ok(editor) = RustEditor::with_scope(content, scope.clone()) else {
+                return Ok(false);
+            };
+            let matches = editor.inspect(
+                condition.node_type.as_deref(),
+                condition.name.as_deref(),
+                None,
+                false,
+            )?;
+            Ok(!matches.is_empty())
+        })
+        .collect::<Result<Vec<bool>>>()?;
+    Ok(found.into_iter().any(|found| found) == condition.exists)
+}
+
+/// Apply `ops` in order to in-memory copies of `files`, without writing anything.
+///
+/// Each operation re-parses what the ones before it produced, so edits to the same file build
+/// on each other rather than on a stale syntax tree; its `when` condition is checked against
+/// the same contents, and `skipped` lists the operations whose condition didn't hold.
+/// `conflicts` lists the nodes that more than one operation modified, for the caller to
+/// report before writing `changes` (e.g. with `write_changes`). Of `opts`, only
+/// `max_per_file`, `verbose`, `strict` and `scope` apply.
+pub fn plan_batch(
+    files: &[PathBuf],
+    ops: &[BatchOperation],
+    opts: &ExecuteOpts,
+) -> Result<BatchPlan> {
+    use rayon::prelude::*;
+
+    let originals = files
+        .iter()
+        .map(|file_path| {
+            std::fs::read_to_string(file_path)
+                .with_context(|| format!("Failed to read {}", file_path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut contents = originals.clone();
+    let mut modified_nodes: Vec<Vec<BackupNode>> = vec![Vec::new(); files.len()];
+    let mut touched: std::collections::BTreeMap<(usize, String, String), Vec<usize>> =
+        std::collections::BTreeMap::new();
+    let mut plan = BatchPlan::default();
+    for (op_index, BatchOperation { op, when }) in ops.iter().enumerate() {
+        if let Some(condition) = when
+            && !condition_holds(&contents, condition, &opts.scope)
+                .with_context(|| format!("Invalid `when` of operation #{}", op_index + 1))?
+        {
+            plan.skipped.push(SkippedOperation {
+                index: op_index,
+                reason: condition.failure_reason(),
+            });
+            plan.operations.push(ExecuteResult::default());
+            continue;
+        }
+        let outcomes: Vec<(FileOutcome, Duration)> = contents
+            .par_iter()
+            .map(|content| {
+                let started = Instant::now();
+                let outcome = analyze_content(content.clone(), op, &opts.scope);
+                (outcome, started.elapsed())
+            })
+            .collect();
+
+        let mut result = ExecuteResult::default();
+        for (file_index, (outcome, elapsed)) in outcomes.into_iter().enumerate() {
+            log_outcome(&opts.verbose, &files[file_index], &outcome, elapsed);
+            let Some(change) = record_outcome(
+                &mut result,
+                &files[file_index],
+                outcome,
+                files.len() == 1,
+                opts,
+            )?
+            else {
+                continue;
+            };
+            for node in &change.modified_nodes {
+                let ops = touched
+                    .entry((file_index, node.node_type.clone(), node.identifier.clone()))
+                    .or_default();
+                if !ops.contains(&op_index) {
+                    ops.push(op_index);
+                }
+            }
+            contents[file_index].clone_from(&change.new_content);
+            modified_nodes[file_index].extend(change.modified_nodes.iter().cloned());
+            result.changes.push(change);
+        }
+        plan.operations.push(result);
+    }
+
+    plan.conflicts = touched
+        .into_iter()
+        .filter(|(_, ops)| ops.len() > 1)
+        .map(
+            |((file_index, node_type, identifier), operations)| BatchConflict {
+                path: files[file_index].clone(),
+                node_type,
+                identifier,
+                operations,
+            },
+        )
+        .collect();
+    plan.changes = files
+        .iter()
+        .zip(originals)
+        .zip(contents)
+        .zip(modified_nodes)
+        .filter(|(((_, old_content), new_content), _)| old_content != new_content)
+        .map(
+            |(((path, old_content), new_content), modified_nodes)| FileChange {
+                path: path.clone(),
+                old_content,
+                new_content,
+                modified_nodes,
+            },
+        )
+        .collect();
+    Ok(plan)
+}
+
+/// Write changes computed up front by a multi-file command such as `move`.
+///
+/// Records a revertible run, backing each file up whole since the edits don't map onto
+/// single AST nodes. With `local_state` as for `execute_with_state`; `None` writes
+/// without recording anything. `backups` as for `ExecuteOpts::backups`.
+pub fn write_changes(
+    changes: Vec<FileChange>,
+    operation: &str,
+    local_state: Option<bool>,
+    command_line: String,
+    backups: Option<&BackupLocation>,
+) -> Result<ExecuteResult> {
+    let mut result = ExecuteResult::default();
+    validate_changes(&changes)?;
+    let Some(local_state) = local_state else {
+        write_all(&changes, None, backups)?;
+        result.total_modifications = changes.iter().map(|c| c.modified_nodes.len()).sum();
+        result.changes = changes;
+        return Ok(result);
+    };
+
+    let run_id = generate_run_id();
+    let state_dir = get_state_dir(local_state)?;
+    let mut file_backups = Vec::with_capacity(changes.len());
+    for change in &changes {
+        let backup = BackupNode {
+            node_type: "file".to_string(),
+            identifier: change.path.display().to_string(),
+            original_content: change.old_content.clone(),
+            location: crate::operations::NodeLocation {
+                line: 1,
+                column: 0,
+                end_line: change.old_content.lines().count().max(1),
+                end_column: 0,
+            },
+        };
+        let hash_before = hash_file(&change.path)?;
+        save_backup_nodes(
+            &change.path,
+            std::slice::from_ref(&backup),
+            &run_id,
+            &state_dir,
+        )?;
+        file_backups.push((backup, hash_before));
+    }
+    write_all(&changes, None, backups)?;
+    for (change, (backup, hash_before)) in changes.into_iter().zip(file_backups) {
+        result.files_modified.push(FileModification {
+            path: change.path.clone(),
+            hash_before,
+            hash_after: hash_file(&change.path)?,
+            backup_nodes: vec![backup],
+        });
+        result.total_modifications += change.modified_nodes.len();
+        result.changes.push(change);
+    }
+
+    if !result.files_modified.is_empty() {
+        save_run_metadata(
+            &RunMetadata {
+                run_id: run_id.clone(),
+                timestamp: chrono::Utc::now(),
+                command: command_line,
+                operation: operation.to_string(),
+                files_modified: result.files_modified.clone(),
+                status: RunStatus::Applied,
+                can_revert: true,
+            },
+            &state_dir,
+        )?;
+        result.run_id = Some(run_id);
+    }
+    Ok(result)
+}
+
+/// Per-file entry of an `OperationReport`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+    pub modified_nodes: usize,
+}
+
+/// Serializable summary of one executed operation, derived from an `ExecuteResult`.
+///
+/// Drops file contents and keeps only what a CI artifact needs: what ran, how many files were
+/// looked at, what changed per file, and why the run ended the way it did.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationReport {
+    /// `Operation::kind_name()` of the executed operation.
+    pub operation: String,
+    /// The full operation, in the same shape batch specs use.
+    pub spec: Operation,
+    pub applied: bool,
+    pub files_scanned: usize,
+    pub files_changed: usize,
+    pub total_modifications: usize,
+    pub files: Vec<FileReport>,
+    pub parse_errors: Vec<(PathBuf, String)>,
+    pub last_error: Option<String>,
+    pub limit_hit: bool,
+    pub run_id: Option<String>,
+    /// One of `applied`, `would_change`, or `no_changes`.
+    pub outcome: String,
+    /// Human-readable explanation of `outcome`.
+    pub reason: String,
+}
+
+impl OperationReport {
+    pub fn from_result(
+        files_scanned: usize,
+        op: &Operation,
+        applied: bool,
+        result: &ExecuteResult,
+    ) -> Self {
+        let files: Vec<FileReport> = result
+            .changes
+            .iter()
+            .map(|change| {
+                let (_, stats) = crate::diff::generate_unified_diff(
+                    &change.path,
+                    &change.old_content,
+                    &change.new_content,
+                    0,
+                );
+                FileReport {
+                    path: change.path.clone(),
+                    lines_added: stats.lines_added,
+                    lines_removed: stats.lines_removed,
+                    modified_nodes: change.modified_nodes.len(),
+                }
+            })
+            .collect();
+
+        let (outcome, mut reason) = if result.changes.is_empty() {
+            let reason = result.last_error.as_ref().map_or_else(
+                || "target not found in any scanned file".to_string(),
+                |err| format!("target not found in any scanned file (last error: {})", err),
+            );
+            ("no_changes", reason)
+        } else if applied {
+            (
+                "applied",
+                format!(
+                    "{} modification(s) written to {} file(s)",
+                    result.total_modifications,
+                    files.len()
+                ),
+            )
+        } else {
+            (
+                "would_change",
+                format!(
+                    "dry run: {} modification(s) in {} file(s) not written (pass --apply)",
+                    result.total_modifications,
+                    files.len()
+                ),
+            )
+        };
+        if result.limit_hit {
+            reason.push_str("; stopped early because --limit was reached");
+        }
+        if !result.parse_errors.is_empty() {
+            reason.push_str(&format!(
+                "; {} file(s) skipped due to parse errors",
+                result.parse_errors.len()
+            ));
+        }
+
+        Self {
+            operation: op.kind_name().to_string(),
+            spec: op.clone(),
+            applied,
+            files_scanned,
+            files_changed: files.len(),
+            total_modifications: result.total_modifications,
+            files,
+            parse_errors: result.parse_errors.clone(),
+            last_error: result.last_error.clone(),
+            limit_hit: result.limit_hit,
+            run_id: result.run_id.clone(),
+            outcome: outcome.to_string(),
+            reason,
+        }
+    }
+}
@@ -1,11 +1,97 @@
-//! File discovery: glob/dir traversal and exclusion filtering for `.rs` files,
-//! plus the kind→node-type expansion used by `find` and friends.
+//! File discovery: glob/dir traversal and exclusion filtering for `.rs` files.
 
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use glob::glob;
-use walkdir::WalkDir;
+use ignore::WalkBuilder;
+
+/// How directory walks find files and which of them are kept, from the global file-selection
+/// flags.
+#[derive(Debug, Clone)]
+pub struct CollectOptions {
+    /// Follow symlinked directories (`--follow-symlinks`). Off by default: a symlink
+    /// pointing back up the tree would otherwise loop, and one pointing out of it would let
+    /// edits escape the paths that were asked for.
+    pub follow_symlinks: bool,
+    /// Skip what `.gitignore`/`.ignore` files and hidden-file rules leave out. On by
+    /// default; `--no-ignore` turns it off.
+    pub use_ignore_files: bool,
+    /// How deep walks go below each directory (`--max-depth`): 1 is only the files directly
+    /// in it.
+    pub max_depth: Option<usize>,
+    /// `--only-files`: keep only files whose raw text contains this (see `retain_containing`)
+    pub only_files: Option<String>,
+    /// `--since`: keep only files in this set of canonical paths (see `changed_since`)
+    pub changed_files: Option<HashSet<PathBuf>>,
+}
+
+impl Default for CollectOptions {
+    fn default() -> Self {
+        Self {
+            follow_symlinks: false,
+            use_ignore_files: true,
+            max_depth: None,
+            only_files: None,
+            changed_files: None,
+        }
+    }
+}
+
+/// Canonical paths of the files changed since `git_ref`, for `--since`.
+///
+/// That's `git diff --name-only <ref>` (committed and uncommitted changes alike) plus
+/// untracked files git doesn't ignore; deleted files are left out. `dir` must be inside a
+/// git repository.
+pub fn changed_since(dir: &Path, git_ref: &str) -> Result<HashSet<PathBuf>> {
+    let git = |args: &[&str]| -> Result<String> {
+        let output = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .context("--since needs git, which couldn't be run")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "--since {}: `git {}` failed: {}",
+                git_ref,
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+
+    let root = match git(&["rev-parse", "--show-toplevel"]) {
+        Ok(root) => PathBuf::from(root.trim()),
+        Err(_) => {
+            let dir = std::fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+            anyhow::bail!(
+                "--since needs a git repository, and {} isn't in one",
+                dir.display()
+            );
+        }
+    };
+    let changed = git(&["diff", "--name-only", "-z", git_ref, "--"])?;
+    let untracked = git(&[
+        "ls-files",
+        "--others",
+        "--exclude-standard",
+        "-z",
+        "--full-name",
+    ])?;
+    Ok(changed
+        .split('\0')
+        .chain(untracked.split('\0'))
+        .filter(|name| !name.is_empty())
+        .filter_map(|name| std::fs::canonicalize(root.join(name)).ok())
+        .collect())
+}
+
+/// Keep the files in `changed` (canonical paths, as from `changed_since`).
+pub fn retain_changed(files: &mut Vec<PathBuf>, changed: &HashSet<PathBuf>) {
+    files.retain(|file| std::fs::canonicalize(file).is_ok_and(|file| changed.contains(&file)));
+}
 
 pub fn collect_rust_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
     collect_rust_files_with_exclusions(paths, &[])
@@ -14,8 +100,43 @@ pub fn collect_rust_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
 pub fn collect_rust_files_with_exclusions(
     paths: &[PathBuf],
     exclude_patterns: &[String],
+) -> Result<Vec<PathBuf>> {
+    collect_rust_files_with_options(paths, exclude_patterns, &CollectOptions::default())
+}
+
+/// Keep the files whose text contains `needle`, a plain substring checked before any parsing.
+///
+/// A cheap gate for localized edits in big trees, not a guarantee: code that only reaches
+/// the target through a glob import, alias or macro is dropped with the rest. Files that
+/// can't be read are kept so the error surfaces where they're parsed.
+pub fn retain_containing(files: &mut Vec<PathBuf>, needle: &str) {
+    files.retain(|file| {
+        std::fs::read_to_string(file).map_or(true, |content| content.contains(needle))
+    });
+}
+
+/// Like `collect_rust_files_with_exclusions`, walking and filtering as `options` say.
+///
+/// With `use_ignore_files`, directory walks skip hidden entries and whatever `.gitignore`,
+/// `.ignore` and git's exclude files rule out (so `target/` usually stays out). Whatever a
+/// `.rs-hackignore` in the walked directory or above it rules out is skipped either way.
+/// With `max_depth`, walks stop that many levels below each directory: 1 is only the files
+/// directly in it. Files and glob patterns named in `paths` are taken as asked for. Each
+/// file is returned once, under the first path it was reached by, even when several paths
+/// (symlinks, overlapping globs) lead to it.
+pub fn collect_rust_files_with_options(
+    paths: &[PathBuf],
+    exclude_patterns: &[String],
+    options: &CollectOptions,
 ) -> Result<Vec<PathBuf>> {
     let mut files = Vec::new();
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+    let mut push = |file: PathBuf| {
+        let key = std::fs::canonicalize(&file).unwrap_or_else(|_| file.clone());
+        if seen.insert(key) {
+            files.push(file);
+        }
+    };
 
     for path in paths {
         let path_str = path.to_string_lossy();
@@ -27,7 +148,7 @@ pub fn collect_rust_files_with_exclusions(
                         if file_path.is_file()
                             && file_path.extension().and_then(|s| s.to_str()) == Some("rs")
                         {
-                            files.push(file_path);
+                            push(file_path);
                         }
                     }
                     Err(e) => eprintln!("Warning: Error reading glob entry: {}", e),
@@ -35,15 +156,24 @@ pub fn collect_rust_files_with_exclusions(
             }
         } else if path.is_file() {
             if path.extension().and_then(|s| s.to_str()) == Some("rs") {
-                files.push(path.clone());
+                push(path.clone());
             }
         } else if path.is_dir() {
-            for entry in WalkDir::new(path)
-                .into_iter()
+            // Symlink loops are reported as errors when following links; those are skipped.
+            // `.gitignore` applies even outside a git checkout.
+            for entry in WalkBuilder::new(path)
+                .standard_filters(options.use_ignore_files)
+                .add_custom_ignore_filename(IGNORE_FILE)
+                .require_git(false)
+                .follow_links(options.follow_symlinks)
+                .max_depth(options.max_depth)
+                .sort_by_file_name(|a, b| a.cmp(b))
+                .build()
                 .filter_map(|e| e.ok())
                 .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("rs"))
+                .filter(|e| e.path().is_file())
             {
-                files.push(entry.path().to_path_buf());
+                push(entry.into_path());
             }
         }
     }
@@ -63,28 +193,66 @@ pub fn collect_rust_files_with_exclusions(
         });
     }
 
+    if let Some(needle) = &options.only_files {
+        retain_containing(&mut files, needle);
+    }
+    if let Some(changed) = &options.changed_files {
+        retain_changed(&mut files, changed);
+    }
     Ok(files)
 }
 
-pub fn expand_kind_to_node_types(kind: &str) -> Vec<&'static str> {
-    match kind {
-        "struct" => vec!["struct", "struct-literal"],
-        "function" => vec![
-            "function",
-            "function-call",
-            "method-call",
-            "impl-method",
-            "trait-method",
-        ],
-        "enum" => vec!["enum", "enum-usage"],
-        "match" => vec!["match-arm"],
-        "identifier" => vec!["identifier"],
-        "type" => vec!["type-ref", "type-alias"],
-        "macro" => vec!["macro-call"],
-        "const" => vec!["const", "static"],
-        "trait" => vec!["trait", "trait-impl"],
-        "mod" => vec!["mod"],
-        "use" => vec!["use"],
-        _ => vec![],
+/// File name of the per-project ignore list (gitignore syntax).
+pub const IGNORE_FILE: &str = ".rs-hackignore";
+
+/// The `src` directory of the crate enclosing `start`: walks up to the nearest directory
+/// with a `Cargo.toml` and returns its `src`, if it has one.
+pub fn find_crate_src(start: &Path) -> Option<PathBuf> {
+    let manifest_dir = start
+        .ancestors()
+        .find(|dir| dir.join("Cargo.toml").is_file())?;
+    let src = manifest_dir.join("src");
+    src.is_dir().then_some(src)
+}
+
+/// Module path of a source file, from its place under the crate's `src`: `src/lib.rs` is
+/// `crate`, `src/net/mod.rs` is `crate::net`, `src/net/tcp.rs` is `crate::net::tcp`.
+pub fn module_path(file: &Path) -> Option<Vec<String>> {
+    let file = std::fs::canonicalize(file).ok()?;
+    let src = find_crate_src(file.parent()?)?;
+    let relative = file.strip_prefix(&src).ok()?.with_extension("");
+    let mut segments = vec!["crate".to_string()];
+    segments.extend(
+        relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+    );
+    // A crate root or a `mod.rs` is the module of its directory
+    let last = segments.last().map(String::as_str);
+    let is_root = segments.len() == 2 && matches!(last, Some("lib" | "main"));
+    if is_root || (segments.len() > 2 && last == Some("mod")) {
+        segments.pop();
+    }
+    Some(segments)
+}
+
+/// Path used when a command is run without `--paths`.
+///
+/// Read-only commands look at the current directory; commands that edit default to the
+/// enclosing crate's `src` (see `find_crate_src`), so a stray run can't touch `target/`,
+/// examples or vendored code. Shown relative to the current directory when it's below it.
+pub fn default_path(read_only: bool) -> Result<PathBuf> {
+    if read_only {
+        return Ok(PathBuf::from("."));
     }
+    let cwd = std::env::current_dir().context("Failed to read the current directory")?;
+    let src = find_crate_src(&cwd).with_context(|| {
+        format!(
+            "No --paths given and no crate with a src/ directory found above {}",
+            cwd.display()
+        )
+    })?;
+    Ok(src
+        .strip_prefix(&cwd)
+        .map_or_else(|_| src.clone(), Path::to_path_buf))
 }
@@ -0,0 +1,147 @@
+//! GitHub Actions workflow commands for `--format github`.
+//!
+//! Each match (for `find`) or node a dry run would change (for mutating commands) becomes a
+//! `::warning file=...,line=...,col=...::message` line, which the Actions runner shows as an
+//! inline annotation on the PR diff.
+
+use std::fmt;
+
+use crate::execute::FileChange;
+use crate::operations::{BackupNode, InspectResult};
+
+/// One `::warning` workflow command. Lines and columns are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    pub file: String,
+    pub line: usize,
+    pub col: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    pub title: String,
+    pub message: String,
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "::warning file={},line={},col={},endLine={},endColumn={},title={}::{}",
+            escape_property(&self.file),
+            self.line,
+            self.col,
+            self.end_line,
+            self.end_column,
+            escape_property(&self.title),
+            escape_data(&self.message)
+        )
+    }
+}
+
+/// One annotation per match. `query` is the `--name` filter, if any; the title carries the
+/// same rule id as the SARIF output.
+pub fn from_find_results(results: &[InspectResult], query: Option<&str>) -> Vec<Annotation> {
+    results
+        .iter()
+        .map(|result| {
+            let first_line = result.snippet.lines().next().unwrap_or_default().trim();
+            Annotation {
+                file: crate::sarif::artifact_uri(&result.file_path),
+                line: result.location.line,
+                col: result.location.column + 1,
+                end_line: result.location.end_line,
+                end_column: result.location.end_column + 1,
+                title: format!(
+                    "rs-hack find: {}",
+                    crate::sarif::rule_id(&result.node_type, query)
+                ),
+                message: format!(
+                    "{} `{}`: {}",
+                    result.node_type, result.identifier, first_line
+                ),
+            }
+        })
+        .collect()
+}
+
+/// One annotation per node a dry run of `operation` (e.g. "AddStructField") would change.
+/// Operations that don't record node positions get one per changed region instead.
+pub fn from_changes(changes: &[FileChange], operation: &str) -> Vec<Annotation> {
+    let mut annotations = Vec::new();
+    for change in changes {
+        let file = crate::sarif::artifact_uri(&change.path.to_string_lossy());
+        let title = format!("rs-hack {}", operation);
+        let describe = |node: &BackupNode| {
+            format!(
+                "{} would change {} `{}`",
+                operation, node.node_type, node.identifier
+            )
+        };
+        let placed: Vec<&BackupNode> = change
+            .modified_nodes
+            .iter()
+            .filter(|node| !node.location.is_placeholder())
+            .collect();
+        if placed.is_empty() {
+            let message = change
+                .modified_nodes
+                .first()
+                .map_or_else(|| format!("{} would change this code", operation), describe);
+            for (line, end_line) in changed_regions(&change.old_content, &change.new_content) {
+                annotations.push(Annotation {
+                    file: file.clone(),
+                    line,
+                    col: 1,
+                    end_line,
+                    end_column: 1,
+                    title: title.clone(),
+                    message: message.clone(),
+                });
+            }
+            continue;
+        }
+        for node in placed {
+            let annotation = Annotation {
+                file: file.clone(),
+                line: node.location.line,
+                col: node.location.column + 1,
+                end_line: node.location.end_line,
+                end_column: node.location.end_column + 1,
+                title: title.clone(),
+                message: describe(node),
+            };
+            if !annotations.contains(&annotation) {
+                annotations.push(annotation);
+            }
+        }
+    }
+    annotations
+}
+
+/// 1-based first and last lines of each run of changed lines in `old` (an insertion points
+/// at the line it goes before).
+fn changed_regions(old: &str, new: &str) -> Vec<(usize, usize)> {
+    let last_line = old.lines().count().max(1);
+    similar::TextDiff::from_lines(old, new)
+        .grouped_ops(0)
+        .iter()
+        .filter_map(|group| {
+            let start = group.first()?.old_range().start;
+            let end = group.last()?.old_range().end;
+            let line = (start + 1).min(last_line);
+            Some((line, end.clamp(line, last_line)))
+        })
+        .collect()
+}
+
+/// The message after `::`, escaped so it stays on one line.
+fn escape_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// A `key=value` property, where `:` and `,` would also end the value early.
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
@@ -0,0 +1,65 @@
+//! `transform --script`: rewrite each matched node with an embedded
+//! [Rhai](https://rhai.rs) script.
+//!
+//! The script runs once per node with `source` (the node's text), `node_type`, `identifier`
+//! and `line` in scope, and evaluates to the replacement. Evaluating to `source` unchanged
+//! leaves the node alone; `throw` stops the transform with the thrown message.
+//!
+//! ```rhai
+//! if identifier == "unwrap" {
+//!     source.replace("unwrap()", "expect(\"checked\")");
+//! }
+//! source
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+
+use crate::operations::InspectResult;
+
+/// A compiled `--script`, run on every node a transform matches.
+pub struct ScriptHook {
+    path: PathBuf,
+    engine: rhai::Engine,
+    ast: rhai::AST,
+}
+
+impl ScriptHook {
+    /// Read and compile the script at `path`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read transform script {}", path.display()))?;
+        let engine = rhai::Engine::new();
+        let ast = engine
+            .compile(&text)
+            .map_err(|e| anyhow!("Invalid transform script {}: {}", path.display(), e))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            engine,
+            ast,
+        })
+    }
+
+    /// Run the script on one matched node and return its replacement text.
+    pub fn run(&self, source: &str, node: &InspectResult) -> Result<String> {
+        let mut scope = rhai::Scope::new();
+        scope.push("source", source.to_string());
+        scope.push_constant("node_type", node.node_type.clone());
+        scope.push_constant("identifier", node.identifier.clone());
+        scope.push_constant("line", node.location.line as i64);
+
+        self.engine
+            .eval_ast_with_scope::<String>(&mut scope, &self.ast)
+            .map_err(|e| {
+                anyhow!(
+                    "Transform script {} failed on {} `{}` (line {}): {}",
+                    self.path.display(),
+                    node.node_type,
+                    node.identifier,
+                    node.location.line,
+                    e
+                )
+            })
+    }
+}
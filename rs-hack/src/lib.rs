@@ -1,16 +1,28 @@
 //! Core library for AST-aware Rust refactoring.
 //! Re-exports operations, editor, diff, surgical edits, and state management.
 
+pub mod cfg;
+pub mod color;
 pub mod commands;
+pub mod config;
 pub mod diff;
 pub mod editor;
 pub mod execute;
 pub mod files;
+pub mod github;
+#[cfg(feature = "script-hooks")]
+pub mod hooks;
+pub mod lsp;
 pub mod operations;
 pub mod path_resolver;
+pub mod rustfmt;
+pub mod sarif;
 pub mod state;
 pub mod surgical;
+pub mod verbose;
 pub mod visitor;
+#[cfg(feature = "watch")]
+pub mod watch;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,145 @@
+//! LSP `TextEdit`s for `--format lsp`.
+//!
+//! A dry run's changes become one `{ "uri", "edits" }` object per file, each edit a
+//! `{ "range", "newText" }` against the file as it is on disk. Lines are 0-based and
+//! characters are UTF-16 code units, the LSP default, so an editor adapter can pass them
+//! straight to `workspace/applyEdit`.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::execute::FileChange;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Position {
+    pub line: usize,
+    pub character: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextEdit {
+    pub range: Range,
+    pub new_text: String,
+}
+
+/// The edits to one file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileEdits {
+    pub uri: String,
+    pub edits: Vec<TextEdit>,
+}
+
+/// One `FileEdits` per change, its edits in file order and not overlapping.
+pub fn from_changes(changes: &[FileChange]) -> Vec<FileEdits> {
+    changes
+        .iter()
+        .map(|change| FileEdits {
+            uri: file_uri(&change.path),
+            edits: text_edits(&change.old_content, &change.new_content),
+        })
+        .collect()
+}
+
+/// The edits turning `old` into `new`: one per run of changed lines, trimmed to the
+/// characters that actually differ.
+pub fn text_edits(old: &str, new: &str) -> Vec<TextEdit> {
+    let line_starts = |text: &str| -> Vec<usize> {
+        std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect()
+    };
+    let (old_lines, new_lines) = (line_starts(old), line_starts(new));
+    let offset = |starts: &[usize], text: &str, line: usize| {
+        starts
+            .get(line)
+            .copied()
+            .unwrap_or(text.len())
+            .min(text.len())
+    };
+
+    similar::TextDiff::from_lines(old, new)
+        .grouped_ops(0)
+        .iter()
+        .filter_map(|group| {
+            let (first, last) = (group.first()?, group.last()?);
+            let mut old_range = offset(&old_lines, old, first.old_range().start)
+                ..offset(&old_lines, old, last.old_range().end);
+            let mut new_range = offset(&new_lines, new, first.new_range().start)
+                ..offset(&new_lines, new, last.new_range().end);
+
+            let prefix = common_prefix(&old[old_range.clone()], &new[new_range.clone()]);
+            old_range.start += prefix;
+            new_range.start += prefix;
+            let suffix = common_suffix(&old[old_range.clone()], &new[new_range.clone()]);
+            old_range.end -= suffix;
+            new_range.end -= suffix;
+
+            Some(TextEdit {
+                range: Range {
+                    start: position(old, &old_lines, old_range.start),
+                    end: position(old, &old_lines, old_range.end),
+                },
+                new_text: new[new_range].to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Length in bytes of the longest common prefix, on a char boundary.
+fn common_prefix(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, x), y)| x != y)
+        .map_or_else(|| a.len().min(b.len()), |((i, _), _)| i)
+}
+
+/// Length in bytes of the longest common suffix, on a char boundary.
+fn common_suffix(a: &str, b: &str) -> usize {
+    a.chars()
+        .rev()
+        .zip(b.chars().rev())
+        .take_while(|(x, y)| x == y)
+        .map(|(x, _)| x.len_utf8())
+        .sum()
+}
+
+/// LSP position of byte `offset` in `text`: 0-based line, UTF-16 character.
+fn position(text: &str, line_starts: &[usize], offset: usize) -> Position {
+    let line = line_starts.partition_point(|&start| start <= offset) - 1;
+    Position {
+        line,
+        character: text[line_starts[line]..offset].encode_utf16().count(),
+    }
+}
+
+/// `file://` URI of `path`, made absolute, with anything outside the unreserved set
+/// percent-encoded.
+pub fn file_uri(path: &Path) -> String {
+    let absolute = std::fs::canonicalize(path).unwrap_or_else(|_| {
+        std::env::current_dir()
+            .map(|cwd| cwd.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    });
+    let path = absolute.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
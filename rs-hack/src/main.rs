@@ -1,13 +1,15 @@
 //! CLI frontend for rs-hack. Parses clap commands and dispatches
 //! to core library operations.
 
-use std::path::PathBuf;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use rs_hack::commands::node_types::{KINDS, expand_kind_to_node_types};
 use rs_hack::diff::{DiffStats, print_diff, print_summary_diff};
 use rs_hack::editor::RustEditor;
-use rs_hack::files::{collect_rust_files_with_exclusions, expand_kind_to_node_types};
+use rs_hack::files::{CollectOptions, collect_rust_files_with_options};
 use rs_hack::operations::{self, *};
 use rs_hack::state::*;
 
@@ -49,10 +51,31 @@ struct Cli {
     #[arg(long, global = true)]
     local_state: bool,
 
-    /// Output format: "default", "diff", or "summary"
+    /// Don't record backups or run metadata when applying (revert won't be available)
+    #[arg(long, global = true)]
+    no_state: bool,
+
+    /// Before overwriting a file, copy the original to `<file>.bak` next to it (plain copies
+    /// for manual diffing, kept apart from the state `revert` uses; none in dry runs)
+    #[arg(long, global = true, conflicts_with = "backup_dir")]
+    backups: bool,
+
+    /// Like --backups, but put the `.bak` copies under this directory, mirroring each
+    /// file's path
+    #[arg(long, global = true, value_name = "PATH")]
+    backup_dir: Option<PathBuf>,
+
+    /// Output format: "default", "diff", "summary", "github" (dry runs as Actions
+    /// `::warning` annotations) or "lsp" (dry runs as LSP text edits); "json" for history
+    /// and revert
     #[arg(long, default_value = "default", global = true)]
     format: String,
 
+    /// When to color diffs and find output: "auto" (a terminal without NO_COLOR set),
+    /// "always" or "never". JSON and locations output is never colored
+    #[arg(long, default_value = "auto", global = true, value_name = "WHEN")]
+    color: String,
+
     /// Show summary statistics after diff output
     #[arg(long, global = true)]
     summary: bool,
@@ -70,6 +93,96 @@ struct Cli {
     #[arg(long, global = true)]
     limit: Option<usize>,
 
+    /// Leave a file unchanged (with a warning) when the operation would modify more than N
+    /// nodes in it; other files are still processed
+    #[arg(long, global = true, value_name = "N")]
+    max_matches_per_file: Option<std::num::NonZeroUsize>,
+
+    /// Number of threads used to parse and match files (default: one per CPU)
+    #[arg(long, global = true, value_name = "N")]
+    jobs: Option<std::num::NonZeroUsize>,
+
+    /// Write a JSON report of the run (operations, files scanned/changed, per-file
+    /// line counts, run ID, exit status) to this path, independent of --format
+    #[arg(long, global = true, value_name = "PATH")]
+    report_file: Option<PathBuf>,
+
+    /// In a dry run, also write every change as one patch for `git apply -p1`, with paths
+    /// relative to the current directory
+    #[arg(long, global = true, value_name = "PATH")]
+    output_patch: Option<PathBuf>,
+
+    /// Run as a dry run and exit nonzero if the operation would still change anything
+    /// (turns an operation into a CI invariant, e.g. "all structs derive Debug")
+    #[arg(long, global = true)]
+    verify_idempotent: bool,
+
+    /// Descend into symlinked directories when walking --paths (off by default to avoid
+    /// loops and edits outside the tree; files reached twice are still processed once)
+    #[arg(long, global = true)]
+    follow_symlinks: bool,
+
+    /// Walk directories without honoring .gitignore/.ignore files or skipping hidden
+    /// entries (.rs-hackignore still applies)
+    #[arg(long, global = true)]
+    no_ignore: bool,
+
+    /// Walk at most N directory levels below each --paths directory (1: only the files
+    /// directly in it). Explicit files and glob patterns aren't limited
+    #[arg(long, global = true, value_name = "N")]
+    max_depth: Option<std::num::NonZeroUsize>,
+
+    /// Log each file to stderr: whether it parsed (and where it didn't), how many nodes
+    /// matched, and the time spent on it
+    #[arg(long, global = true)]
+    verbose: bool,
+
+    /// Fail on the first file that doesn't parse, instead of skipping it and listing the
+    /// skipped files at the end (a run where no file parses fails either way)
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Show each changed region and ask [y]es / [n]o / [a]ll / [q]uit before writing it;
+    /// only accepted changes are applied (and reverted by `revert`). Needs a terminal
+    #[arg(long, global = true, conflicts_with_all = ["no_state", "verify_idempotent"])]
+    interactive: bool,
+
+    /// Only parse files whose text contains this substring (a grep-style pre-filter that
+    /// speeds up localized edits in big trees; files that only reach the target through an
+    /// alias, glob import or macro are skipped too)
+    #[arg(long, global = true, value_name = "SUBSTRING")]
+    only_files: Option<String>,
+
+    /// Only process files changed since this git ref (`git diff --name-only <REF>`, plus
+    /// untracked files), e.g. `--since main`. Needs a git repository
+    #[arg(long, global = true, value_name = "REF")]
+    since: Option<String>,
+
+    /// Treat this cfg option as set (repeatable; `unix`, `feature=serde`): items whose
+    /// #[cfg] is false under the given options are left untouched and out of `find`
+    #[arg(long = "cfg", global = true, value_name = "KEY[=VALUE]")]
+    cfg_options: Vec<String>,
+
+    /// Leave test code alone: items marked #[cfg(test)] or #[test] are untouched and out of
+    /// `find` (same as --skip-attr 'cfg(test)' --skip-attr test)
+    #[arg(long, global = true)]
+    skip_tests: bool,
+
+    /// Leave code carrying this attribute alone (repeatable; `cfg(test)`, `tokio::test`); a
+    /// bare path also matches the attribute with arguments
+    #[arg(long = "skip-attr", global = true, value_name = "ATTR")]
+    skip_attrs: Vec<String>,
+
+    /// Only touch code inside this inline module of each file (`a`, `a::b`), so a name
+    /// defined in several `mod` blocks picks one; `find` only reports matches inside it
+    #[arg(long, global = true, value_name = "PATH")]
+    in_module: Option<String>,
+
+    /// Read the code to edit from stdin and print the result to stdout (the diff with
+    /// --format diff) instead of collecting --paths; nothing is written and no state is kept
+    #[arg(long, global = true, conflicts_with_all = ["interactive", "verify_idempotent"])]
+    stdin: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -396,7 +509,8 @@ EXAMPLES:
         #[arg(long)]
         enum_path: Option<String>,
 
-        /// Edit mode: 'surgical' (default, preserves formatting) or 'reformat' (uses prettyplease)
+        /// Edit mode: 'surgical' (default, preserves formatting), 'reformat' (uses prettyplease)
+        /// or 'rustfmt' (surgical, then the project's rustfmt and its rustfmt.toml)
         #[arg(long, default_value = "surgical")]
         edit_mode: String,
 
@@ -441,7 +555,8 @@ EXAMPLES:
         #[arg(long)]
         function_path: Option<String>,
 
-        /// Edit mode: 'surgical' (default, preserves formatting) or 'reformat' (uses prettyplease)
+        /// Edit mode: 'surgical' (default, preserves formatting), 'reformat' (uses prettyplease)
+        /// or 'rustfmt' (surgical, then the project's rustfmt and its rustfmt.toml)
         #[arg(long, default_value = "surgical")]
         edit_mode: String,
 
@@ -484,11 +599,46 @@ ENUM VARIANT SYNTAX:
 
     This works for both the target name (--name) specification.
 
+METHODS ON ONE TYPE:
+    Use --receiver-type to rename a method on a single type without touching
+    same-named methods elsewhere:
+      rs-hack rename --name process --to handle --receiver-type Parser --paths src --apply
+
+    This renames `fn process` in inherent `impl Parser` blocks, `Parser::process` and
+    `Self::process` paths, and `.process()` calls whose receiver is known to be a Parser:
+    `self` in an `impl Parser`, a binding typed `Parser` (`let p: Parser`, `p: &Parser`
+    parameters), or one initialized from `Parser::new()`, `Parser::default()` or a
+    `Parser { .. }` literal. This is a local heuristic, not type checking: calls whose
+    receiver can't be inferred are left alone, so review the diff and the build.
+    Trait methods are not covered.
+
 QUALIFIED PATHS:
     Use --enum-path or --function-path to provide fully-qualified paths for disambiguation:
       --enum-path \"crate::types::Status\"
       --function-path \"crate::utils::process_v2\"
 
+    Add --require-qualified to make the qualified path mandatory: the rename refuses to
+    run on a bare name, which keeps scripted renames of common names from matching
+    unrelated items:
+      rs-hack rename --name process --to handle --require-qualified \\
+        --function-path \"crate::jobs::process\" --paths src --apply
+
+FIELD RENAMES:
+    Struct::field renames a named field in the definition, in `Struct { .. }`
+    literals and patterns (shorthand `{ timeout }` becomes `{ timeout_secs: timeout }`),
+    and in `x.timeout` accesses:
+      rs-hack rename --name Config::timeout --to timeout_secs --paths src --apply
+
+    An access is left alone only when its receiver is known to be another type (self in
+    an impl, typed parameters and lets, Type::new()/literal bindings). --validate lists
+    the accesses on receivers of unknown type, which are renamed on a guess:
+      rs-hack rename --name Config::timeout --to timeout_secs --validate --paths src
+
+    Add --serde-aware to keep the serialized name unchanged: a field without
+    `#[serde(rename = \"...\")]` gets one carrying the old wire name (following the
+    container's rename_all), and an existing one is left as is. With
+    --update-serde-name the wire name follows the rename instead.
+
 EDIT MODES:
     - surgical (default): Preserves formatting precisely, makes minimal changes
     - reformat: Uses prettyplease to reformat modified code
@@ -501,13 +651,16 @@ NOTES:
     - Use --name <NAME> to specify the target to rename
     - Use --to <NEW_NAME> to specify the new name
     - For enum variants, use :: syntax (EnumName::VariantName)
+    - For struct fields, use the same syntax (StructName::field); the definition,
+      `StructName { .. }` literals and patterns, and field accesses are renamed
     - The command performs renames across definitions and all usages")]
     Rename {
         /// Path to the Rust file or directory (supports multiple paths and glob patterns)
         #[arg(short, long, num_args = 1..)]
         paths: Vec<PathBuf>,
 
-        /// Target to rename (function name or EnumName::VariantName for enum variants)
+        /// Target to rename (function name, EnumName::VariantName for enum variants, or a
+        /// module with --kind mod)
         #[arg(short, long)]
         name: String,
 
@@ -524,6 +677,11 @@ NOTES:
         #[arg(long)]
         function_path: Option<String>,
 
+        /// Rename the method on this type only: its definition in `impl <Type>` and calls
+        /// whose receiver is inferred to be a <Type> (heuristic, see --help)
+        #[arg(long, conflicts_with_all = ["validate", "kind", "node_type", "enum_path"])]
+        receiver_type: Option<String>,
+
         /// Semantic kind for grouping related node types (struct, function, enum, match,
         /// identifier, type, macro, const, trait, mod, use)
         #[arg(short = 'k', long, conflicts_with = "node_type")]
@@ -533,7 +691,8 @@ NOTES:
         #[arg(short = 'T', long, conflicts_with = "kind")]
         node_type: Option<String>,
 
-        /// Edit mode: 'surgical' (default, preserves formatting) or 'reformat' (uses prettyplease)
+        /// Edit mode: 'surgical' (default, preserves formatting), 'reformat' (uses prettyplease)
+        /// or 'rustfmt' (surgical, then the project's rustfmt and its rustfmt.toml)
         #[arg(long, default_value = "surgical")]
         edit_mode: String,
 
@@ -541,6 +700,41 @@ NOTES:
         #[arg(long)]
         validate: bool,
 
+        /// In a dry run, flag matches that look like false positives (common names, bare
+        /// identifiers, unchecked method receivers, mentions inside macros)
+        #[arg(long, conflicts_with = "apply")]
+        flag_risky: bool,
+
+        /// Refuse to rename unless --function-path / --enum-path gives a qualified path
+        /// (guardrail against bare-name matches across large codebases)
+        #[arg(long, conflicts_with_all = ["receiver_type", "node_type", "kind"])]
+        require_qualified: bool,
+
+        /// For a field rename (Struct::field): keep the serde wire name stable, adding
+        /// `#[serde(rename = "<old>")]` when the field has no rename attribute
+        #[arg(long, conflicts_with_all = ["receiver_type", "node_type", "kind"])]
+        serde_aware: bool,
+
+        /// With --serde-aware: let the wire name follow the rename instead, updating an
+        /// existing `#[serde(rename = "...")]`
+        #[arg(long, requires = "serde_aware")]
+        update_serde_name: bool,
+
+        /// For functions declared in `extern` blocks: add #[link_name = "<old>"] so the
+        /// declaration still links the old symbol
+        #[arg(long, conflicts_with_all = ["receiver_type", "node_type", "kind"])]
+        keep_link_name: bool,
+
+        /// Also replace the name, as a whole word, inside string literals; shown as a
+        /// separate diff after the rename so it can be reviewed on its own
+        #[arg(long, conflicts_with = "validate")]
+        include_strings: bool,
+
+        /// Also replace the name, as a whole word, inside comments (doc comments included);
+        /// shown with --include-strings' separate diff
+        #[arg(long, conflicts_with = "validate")]
+        include_comments_text: bool,
+
         /// Apply changes (default is dry-run)
         #[arg(long)]
         apply: bool,
@@ -647,10 +841,28 @@ MIGRATION:
     },
 
     /// Batch operation from JSON or YAML specification
+    #[command(after_help = "STDIN MODE (--stdin-batch):
+    Reads one JSON request per line from stdin and writes one JSON response per line to
+    stdout, staying up until stdin closes:
+
+    {\"id\": 1, \"paths\": [\"src\"], \"apply\": true,
+     \"operation\": {\"type\": \"AddDerive\", \"target_type\": \"struct\", \"target_name\": \"User\",
+                   \"derives\": [\"Debug\"]}}
+
+    {\"id\":1,\"ok\":true,\"run_id\":\"a1b2c3d\",\"total_modifications\":1,
+     \"files\":[{\"path\":\"src/user.rs\",\"modified_nodes\":1,\"diff\":\"...\"}],\"limit_hit\":false}
+
+    `operation` has the same shape as an entry in a batch spec. `apply` defaults to false
+    (dry run; the diff shows what would change). Failed requests get {\"ok\": false,
+    \"error\": ...} and the loop keeps going.")]
     Batch {
         /// Path to JSON or YAML file with batch operations
-        #[arg(short, long)]
-        spec: PathBuf,
+        #[arg(short, long, required_unless_present = "stdin_batch")]
+        spec: Option<PathBuf>,
+
+        /// Serve JSON-lines operation requests from stdin until it closes
+        #[arg(long, conflicts_with_all = ["spec", "apply"])]
+        stdin_batch: bool,
 
         /// Apply changes (default is dry-run)
         #[arg(long)]
@@ -686,6 +898,199 @@ MIGRATION:
         r#enum: String,
     },
 
+    /// Add missing arms and flag (or prune) stale arms in matches over an enum
+    #[command(after_help = "EXAMPLES:
+    # Report what would change in every match over Status
+    rs-hack sync-match --enum Status --paths src
+
+    # Add arms for new variants in one function
+    rs-hack sync-match --enum Status --function handle --paths src --apply
+
+    # Also remove arms for variants that were deleted from the enum
+    rs-hack sync-match --enum Status --paths src --prune --apply
+
+NOTES:
+    Arms that reference a removed variant are only reported unless --prune is given.
+    An arm is pruned only when every variant it names is gone; mixed or-patterns are
+    left for manual cleanup. Matches with a wildcard arm get no new arms.")]
+    SyncMatch {
+        /// Path(s) to Rust file(s) or directories
+        #[arg(short, long, num_args = 1..)]
+        paths: Vec<PathBuf>,
+
+        /// Enum whose matches should be synchronized
+        #[arg(long = "enum")]
+        r#enum: String,
+
+        /// Only touch matches inside this function
+        #[arg(short, long)]
+        function: Option<String>,
+
+        /// Body for added arms
+        #[arg(short, long, default_value = "todo!()")]
+        body: String,
+
+        /// Remove arms that reference variants that no longer exist
+        #[arg(long)]
+        prune: bool,
+
+        /// Apply changes (default is dry-run)
+        #[arg(long)]
+        apply: bool,
+    },
+
+    /// Move an item (with a type's impl blocks) to another module file and update `use` paths
+    #[command(after_help = "EXAMPLES:
+    # Preview: prints the removal from old.rs, the insertion into new.rs and every use fix
+    rs-hack move --name Config --from src/old.rs --to src/new.rs --paths src
+
+    # Apply (revertible like any run)
+    rs-hack move --name Config --from src/old.rs --to src/new.rs --paths src --apply
+
+WHAT MOVES:
+    The top-level struct/enum/union/type/trait/fn/const/static named --name, and for a
+    type, every impl block for it in --from. The items are appended to --to, which must
+    already exist and be declared as a module.
+
+IMPORTS AND PATHS:
+    Module paths come from file locations: src/new.rs is crate::new, src/a/mod.rs is
+    crate::a. The destination gets the `use` lines the moved code relied on, plus imports
+    of items it uses that stay behind (private ones are reported). In every scanned file,
+    `use` declarations naming the old path and `crate::`/`self::`/`super::` paths through
+    it are pointed at the new module; --from imports the item back if it still uses it.
+    Trait imports needed only for method calls can't be detected and may need adding.")]
+    Move {
+        /// Files whose `use` declarations and paths are updated (--from and --to are always
+        /// included)
+        #[arg(short, long, num_args = 1..)]
+        paths: Vec<PathBuf>,
+
+        /// Name of the item to move
+        #[arg(short, long)]
+        name: String,
+
+        /// File the item is defined in
+        #[arg(long)]
+        from: PathBuf,
+
+        /// File to move it to
+        #[arg(long)]
+        to: PathBuf,
+
+        /// Apply changes (default is dry-run)
+        #[arg(long)]
+        apply: bool,
+    },
+
+    /// Replace the one call of a function with its body and delete the definition
+    #[command(after_help = "EXAMPLES:
+    # Preview: prints the removed definition and the expanded call
+    rs-hack inline-function --name helper --paths src
+
+    # Apply (revertible like any run)
+    rs-hack inline-function --name helper --paths src --apply
+
+WHEN IT INLINES:
+    A free function (not a method) with exactly one call site in --paths, no type or const
+    parameters, plain `name: Type` parameters, and no early `return` or `?`. Otherwise it
+    stops and says why, listing every call site when there is more than one.
+
+ARGUMENTS:
+    Each argument is written in place of its parameter when that can't change what runs:
+    a path or literal, or an argument used once, unconditionally, in a one-expression
+    body. The rest are bound with `let` at the top of an inlined block:
+
+        let total = add(base, compute());      fn add(a: u32, b: u32) -> u32 { a + b }
+        let total = base + compute();          (one use each: substituted)
+
+    A `use` that only imports the function is removed; one that imports other names too is
+    reported. When the call is in another file, check that what the body uses is in scope
+    there.")]
+    InlineFunction {
+        /// Files searched for the definition and its call site
+        #[arg(short, long, num_args = 1..)]
+        paths: Vec<PathBuf>,
+
+        /// Name of the function to inline
+        #[arg(short, long)]
+        name: String,
+
+        /// Apply changes (default is dry-run)
+        #[arg(long)]
+        apply: bool,
+    },
+
+    /// Align a struct's field types into one column (cosmetic; revertible like any run)
+    #[command(after_help = "EXAMPLES:
+    # Preview, then apply
+    rs-hack align-fields --name Config --paths src
+    rs-hack align-fields --name Config --paths src --apply
+
+    # A struct defined inside a function body
+    rs-hack align-fields --name Row --in-fn load --paths src/db.rs --apply
+
+RESULT:
+    pub struct Config {
+        pub name:    String,
+        /// Seconds
+        timeout:     u64, // comments stay put
+        pub retries: u8,
+    }
+
+NOTES:
+    Only the spaces between each field's `:` and its type change. Fields that share a line
+    with another field, or whose type starts on a later line, are left as they are.
+    rustfmt removes the padding again; run this after formatting, or undo it with
+    'rs-hack revert <run-id>'.")]
+    AlignFields {
+        /// Path(s) to Rust file(s) or directories
+        #[arg(short, long, num_args = 1..)]
+        paths: Vec<PathBuf>,
+
+        /// Struct whose fields to align
+        #[arg(short, long)]
+        name: String,
+
+        /// Target a struct defined inside this function's body
+        #[arg(long)]
+        in_fn: Option<String>,
+
+        /// Apply changes (default is dry-run)
+        #[arg(long)]
+        apply: bool,
+    },
+
+    /// Report everything that references a type, to judge the blast radius of removing it
+    #[command(after_help = "EXAMPLES:
+    # What would break if Config went away?
+    rs-hack impact --type Config --paths src
+
+    # Machine-readable, with every location
+    rs-hack impact --type Config --paths src --format json
+
+CATEGORIES:
+    Struct literals, fields embedding the type (Vec<Config> included), function
+    signatures, impl blocks, associated items and constructors (Config::new()),
+    imports, and any other type reference (let annotations, aliases, generics).
+    Each site is counted once, under the most specific category.
+
+NOTES:
+    Matching is by name: same-named types in different modules are counted
+    together, and code inside macro invocations isn't seen.")]
+    Impact {
+        /// Path(s) to Rust file(s) or directories
+        #[arg(short, long, num_args = 1..)]
+        paths: Vec<PathBuf>,
+
+        /// Type to report on (the last segment of a path is used)
+        #[arg(long = "type", value_name = "NAME")]
+        type_name: String,
+
+        /// Output format: "text" or "json"
+        #[arg(short = 'f', long, default_value = "text")]
+        format: String,
+    },
+
     /// Report doc-comment coverage for public items
     DocCoverage {
         /// Path(s) to Rust file(s) or directories
@@ -697,6 +1102,59 @@ MIGRATION:
         fields: bool,
     },
 
+    /// Report functions whose cyclomatic complexity exceeds a threshold
+    #[command(after_help = "EXAMPLES:
+    # Functions scoring above 10, most complex first
+    rs-hack complexity --paths src
+
+    # Stricter threshold, JSON for dashboards
+    rs-hack complexity --paths src --threshold 5 --format json
+
+SCORING:
+    Each function starts at 1 and gains a point for every if / if let / else if,
+    let-else, match arm after the first, match guard, && and ||, and for / while /
+    loop. Closures count toward their enclosing function; nested fn items are
+    scored separately. Methods are reported as Type::method.")]
+    Complexity {
+        /// Path(s) to Rust file(s) or directories
+        #[arg(short, long, num_args = 1..)]
+        paths: Vec<PathBuf>,
+
+        /// Report functions scoring above this
+        #[arg(long, default_value_t = 10)]
+        threshold: usize,
+
+        /// Order: "score" (highest first), "name", or "location"
+        #[arg(long, default_value = "score")]
+        sort: String,
+
+        /// Output format: "text" or "json"
+        #[arg(short = 'f', long, default_value = "text")]
+        format: String,
+    },
+
+    /// Summarize a codebase: item counts, unwraps and todos per file, largest functions
+    #[command(after_help = "EXAMPLES:
+    # Per-file table with totals and the 10 largest functions
+    rs-hack stats --paths src
+
+    # JSON for tooling
+    rs-hack stats --paths src --format json
+
+COLUMNS:
+    lines/code: all lines / lines that aren't blank or only a // comment
+    fns: free functions and impl methods; impls: inherent and trait impl blocks
+    macros: macro invocations; todos: todo!() and unimplemented!()")]
+    Stats {
+        /// Path(s) to Rust file(s) or directories
+        #[arg(short, long, num_args = 1..)]
+        paths: Vec<PathBuf>,
+
+        /// Output format: "text" or "json"
+        #[arg(short = 'f', long, default_value = "text")]
+        format: String,
+    },
+
     /// Print a module inventory for a single .rs file
     Summary {
         /// Path to a single Rust source file
@@ -704,6 +1162,46 @@ MIGRATION:
         path: PathBuf,
     },
 
+    /// Show the syntax nodes covering one line, with their syn types and node types
+    #[command(after_help = "EXAMPLES:
+    # Which node types could a find/transform on line 42 use?
+    rs-hack debug --file src/config.rs --line 42
+
+    # Machine-readable
+    rs-hack debug --file src/config.rs --line 42 --format json
+
+OUTPUT:
+    One row per node whose span covers the line, outermost first and indented by
+    nesting: the syn type (Item::Impl, Expr::MethodCall, ...), the --node-type
+    that finds it (- when none does), its lines and its source.")]
+    Debug {
+        /// Rust source file to parse
+        #[arg(long)]
+        file: PathBuf,
+
+        /// Line to explain (1-based)
+        #[arg(long)]
+        line: usize,
+
+        /// Output format: "text" or "json"
+        #[arg(short = 'f', long, default_value = "text")]
+        format: String,
+    },
+
+    /// List every supported --node-type and --kind, what each matches, and which commands
+    /// accept it
+    #[command(after_help = "EXAMPLES:
+    # Print the vocabulary as text
+    rs-hack node-types
+
+    # Machine-readable, for editor integrations and scripts
+    rs-hack node-types --format json")]
+    NodeTypes {
+        /// Output format: "text" or "json"
+        #[arg(short = 'f', long, default_value = "text")]
+        format: String,
+    },
+
     /// Find definitions AND all usages across files (see: rs-hack find --help)
     #[command(display_order = 1)]
     #[command(after_help = "EXAMPLES:
@@ -738,19 +1236,67 @@ MIGRATION:
     # Get JSON output (useful for scripting)
     rs-hack find --paths src --node-type function --name process --format json
 
+    # Counts only ({by_type, by_file, total}) for dashboards
+    rs-hack find --paths src --node-type method-call --name unwrap --format json --summary
+
+    # Who calls process? Call sites grouped by the enclosing function, with counts
+    rs-hack find --paths src --callers process
+
+    # Private fns/structs/consts never referenced outside their own definition
+    rs-hack find --paths src --unused
+
+    # Fields named like a method of the same type (e.g. field `len` and fn len())
+    rs-hack find --paths src --field-method-collisions --name Config
+
+    # Names spelled out inside macro invocations (token scan; macros aren't expanded)
+    rs-hack find --paths src --in-macro-call bitflags --name-regex \"^[A-Z_]+$\"
+
+    # Names by regex instead of exact --name
+    rs-hack find --paths src --node-type function --name-regex \"^test_\"
+
     # Get just file locations (grep-like output)
     rs-hack find --paths src --node-type method-call --name unwrap --format locations
 
+    # SARIF 2.1.0 report for GitHub code scanning (rule id: <node-type>/<name>)
+    rs-hack find --paths src --node-type method-call --name unwrap --format sarif > find.sarif
+
+    # Inline PR annotations from a GitHub Actions step
+    rs-hack find --paths src --node-type method-call --name unwrap --format github
+
     # Search multiple files with glob patterns
     rs-hack find --paths \"src/**/*.rs\" --node-type struct --name Config
 
     # Include documentation comments in output
     rs-hack find --paths src --node-type function --name main --include-comments true
 
+    # Filter by parameter count (zero-arg functions, or methods taking more than five args)
+    rs-hack find --paths src --node-type function --arity 0
+    rs-hack find --paths src --node-type impl-method --min-arity 6 --exclude-self
+
+    # Review a trait's contract: required methods vs. provided defaults
+    rs-hack find --paths src --node-type trait-method --name Handler --required-only
+    rs-hack find --paths src --node-type trait-method --name Handler --default-only
+
+    # Carve out matches by name or by enclosing function/type/module
+    rs-hack find --paths src --node-type method-call --name unwrap --exclude-in \"test_*\" --exclude-in tests
+
+    # Every struct/enum deriving Serialize, with its full derive list
+    rs-hack find --paths src --node-type derive --name Serialize
+
+    # Variants of Status with identical fields (e.g. left behind by a merge)
+    rs-hack find --paths src --duplicate-variants --name Status
+
+    # How many unwrap() calls, and where (--format json for {\"total\", \"by_file\"})
+    rs-hack find --paths src --node-type method-call --name unwrap --count
+
+    # Color the matched name in snippets (when colors are on; see --color)
+    rs-hack find --paths src --node-type method-call --name unwrap --highlight
+
 OUTPUT FORMATS:
     snippets    Show full code snippets with file locations (default, most readable)
     locations   Show only file:line:column (grep-style, good for scripting)
-    json        JSON output with all metadata (for programmatic use)")]
+    json        JSON output with all metadata (for programmatic use)
+    jsonl       One JSON object per match per line, streamed file by file (pipe into jq)")]
     Find {
         /// Path to Rust file(s) - supports multiple paths and glob patterns (e.g., "tests/*.rs")
         #[arg(short, long, num_args = 1..)]
@@ -762,9 +1308,16 @@ OUTPUT FORMATS:
         kind: Option<String>,
 
         /// Type of node: Expression-level: "struct-literal", "match-arm", "enum-usage",
-        /// "function-call", "method-call", "macro-call", "identifier", "type-ref".
-        /// Definition-level: "struct", "enum", "function", "impl-method", "trait", "const",
-        /// "static", "type-alias", "mod". Omit to search all types.
+        /// "function-call", "method-call", "macro-call", "closure" (--name filters by the
+        /// first captured variable), "identifier", "type-ref", "lifetime" and
+        /// "generic-param" (declarations and uses).
+        /// Definition-level: "struct", "enum", "function", "impl-method", "trait-method",
+        /// "extern-fn" (functions declared in extern blocks),
+        /// "trait", "impl" (impl blocks of the --name type, with their methods), "const",
+        /// "static", "type-alias", "mod", "derive" (--name filters by the derived trait),
+        /// "attribute" (--name filters by the attribute, e.g. deprecated, cfg, test),
+        /// "where-clause" (items whose generics have a bound; --name filters by the bound).
+        /// Omit to search all types.
         #[arg(short = 't', long, conflicts_with = "kind")]
         node_type: Option<String>,
 
@@ -790,13 +1343,160 @@ OUTPUT FORMATS:
         #[arg(long, default_value = "true", action = clap::ArgAction::Set)]
         include_comments: bool,
 
-        /// Output format: "json", "locations", "snippets"
+        /// Output format: "json", "jsonl" (one compact object per line, streamed as files are
+        /// searched), "locations", "snippets", "sarif" (SARIF 2.1.0 for code scanning),
+        /// "github" (Actions `::warning` annotations), "count-by-type" (how many nodes of each
+        /// type, e.g. without --node-type for a crate's makeup); with --summary, "json" prints
+        /// only counts
         #[arg(short = 'f', long, default_value = "snippets")]
         format: String,
 
-        /// Show N raw lines of context before each snippet match (like grep -B N)
+        /// Show N raw source lines before and after each snippet match (like grep -C N;
+        /// snippets format only)
         #[arg(long)]
         context: Option<usize>,
+
+        /// Only match functions/methods with exactly N parameters
+        #[arg(long)]
+        arity: Option<usize>,
+
+        /// Only match functions/methods with at least N parameters
+        #[arg(long)]
+        min_arity: Option<usize>,
+
+        /// Only match functions/methods with at most N parameters
+        #[arg(long)]
+        max_arity: Option<usize>,
+
+        /// Don't count a `self` receiver toward --arity/--min-arity/--max-arity
+        #[arg(long)]
+        exclude_self: bool,
+
+        /// Only trait methods without a default body (the trait's required contract)
+        #[arg(long, conflicts_with = "default_only")]
+        required_only: bool,
+
+        /// Only trait methods that provide a default body
+        #[arg(long)]
+        default_only: bool,
+
+        /// Skip nodes whose own name matches this glob (e.g. "test_*"; repeatable)
+        #[arg(long = "exclude-name", value_name = "PATTERN")]
+        exclude_names: Vec<String>,
+
+        /// Skip nodes inside a function, impl/trait/type, or module whose name matches this
+        /// glob (repeatable)
+        #[arg(long, value_name = "PATTERN")]
+        exclude_in: Vec<String>,
+
+        /// Report enum variants whose fields are identical (--name limits it to one enum)
+        #[arg(long, conflicts_with_all = ["field_name", "node_type", "kind", "variant"])]
+        duplicate_variants: bool,
+
+        /// Report struct fields named like a method in an impl of the same type (--name
+        /// limits it to one struct)
+        #[arg(
+            long,
+            conflicts_with_all = ["field_name", "node_type", "kind", "variant", "duplicate_variants"]
+        )]
+        field_method_collisions: bool,
+
+        /// Scan the token streams of this macro's invocations (e.g. "bitflags") for
+        /// identifiers; matches are token-based, not AST-based
+        #[arg(
+            long,
+            value_name = "MACRO",
+            conflicts_with_all = [
+                "field_name",
+                "node_type",
+                "kind",
+                "variant",
+                "duplicate_variants",
+                "field_method_collisions"
+            ]
+        )]
+        in_macro_call: Option<String>,
+
+        /// Filter by a regex on the name instead of --name (unanchored; use ^...$ for whole
+        /// names). A path like Foo::bar matches on the whole or on its last segment
+        #[arg(long, value_name = "PATTERN", conflicts_with = "name")]
+        name_regex: Option<String>,
+
+        /// List every call to this function or method, grouped by the function or method
+        /// each call is made from (e.g. "process", or "Worker::new" for path calls only)
+        #[arg(
+            long,
+            value_name = "FUNCTION",
+            conflicts_with_all = [
+                "name",
+                "name_regex",
+                "field_name",
+                "node_type",
+                "kind",
+                "variant",
+                "duplicate_variants",
+                "field_method_collisions",
+                "in_macro_call",
+                "count"
+            ]
+        )]
+        callers: Option<String>,
+
+        /// Report private fns, structs and consts whose name never appears outside their own
+        /// definition across the scanned files (dead-code candidates; misses uses through
+        /// macro expansion, trait dispatch and other crates). Skips main, tests and
+        /// #[allow(dead_code)] items
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "name",
+                "name_regex",
+                "field_name",
+                "node_type",
+                "kind",
+                "variant",
+                "duplicate_variants",
+                "field_method_collisions",
+                "in_macro_call",
+                "callers",
+                "count"
+            ]
+        )]
+        unused: bool,
+
+        /// Report functions and methods with identical bodies, ignoring whitespace, comments
+        /// and the names of parameters and locals (--node-type function, impl-method or
+        /// trait-method limits it to one kind)
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "name",
+                "name_regex",
+                "field_name",
+                "kind",
+                "variant",
+                "duplicate_variants",
+                "field_method_collisions",
+                "in_macro_call",
+                "callers",
+                "unused",
+                "count"
+            ]
+        )]
+        duplicates: bool,
+
+        /// Highlight the matched name in snippets (ANSI colors; off whenever --color turns
+        /// colors off)
+        #[arg(long)]
+        highlight: bool,
+
+        /// Print match counts per file instead of the matches ({"total", "by_file", ...}
+        /// with --format json)
+        #[arg(
+            long,
+            conflicts_with_all = ["field_name", "duplicate_variants", "field_method_collisions"]
+        )]
+        count: bool,
     },
 
     /// [LEGACY] Add derive macros - use 'rs-hack add' instead
@@ -838,6 +1538,10 @@ OUTPUT FORMATS:
         #[arg(short, long)]
         method: String,
 
+        /// Add the method to `impl <TRAIT> for <target>` instead of the inherent impl
+        #[arg(long = "trait", value_name = "TRAIT")]
+        trait_name: Option<String>,
+
         /// Where to insert: "first", "last", "after:method_name", or "before:method_name"
         #[arg(short = 'P', long, default_value = "last")]
         position: String,
@@ -890,6 +1594,16 @@ EXAMPLES:
     rs-hack add --name Config --field-name timeout --field-value \"Duration::from_secs(30)\" \\
                --literal-only --paths src --apply
 
+    # Derive the new field from the literal's other fields ({name} is that field's value)
+    rs-hack add --name Rect --field-name area --field-type u32 \\
+               --field-value \"{width} * {height}\" --paths src --apply
+
+    # Set a field in a literal nested inside Config { inner: Inner { .. }, .. }
+    rs-hack add --name Config --field-path inner.retries --field-value 3 --paths src --apply
+
+    # Add a field to tuple struct Point(i32, i32) and 0.0 to every Point(x, y) literal
+    rs-hack add --name Point --field-type f32 --field-value 0.0 --position last --paths src --apply
+
     # Add enum variant
     rs-hack add --name Status --variant \"Archived\" --paths src --apply
 
@@ -913,6 +1627,7 @@ AUTO-DETECTION:
     - --field-name + --field-type: Add to struct definition
     - --field-name + --field-value: Add to all struct literals
     - --field-name + --field-type + --field-value: Add to both
+    - --field-path + --field-value: Add to struct literals nested inside the target's literals
     - --variant: Add enum variant
     - --method: Add impl method
     - --derive: Add derive macro
@@ -956,13 +1671,23 @@ NOTES:
         field_name: Option<String>,
 
         /// Field type (e.g., \"String\", \"Option<i32>\"). Adds to struct definition.
-        #[arg(long, requires = "field_name")]
+        /// Without --field-name, adds a field to a tuple struct; --position after:/before:
+        /// then take a field index
+        #[arg(long)]
         field_type: Option<String>,
 
         /// Field value (e.g., \"None\", \"0\", \"vec![]\"). Adds to struct literals.
-        #[arg(long, requires = "field_name")]
+        /// Use with --field-name or --field-path. `{field}` (no spaces) stands for the value
+        /// the literal already gives `field`, e.g. \"{width} * 2\"
+        #[arg(long)]
         field_value: Option<String>,
 
+        /// Dotted path to a field in a nested struct literal (e.g., \"inner.retries\" sets
+        /// `retries` in the `inner: Inner { .. }` literal of each --name literal). Literals
+        /// whose intermediate fields are missing or aren't struct literals are left alone
+        #[arg(long, requires = "field_value", conflicts_with_all = ["field_name", "field_type"])]
+        field_path: Option<String>,
+
         /// Variant definition to add (e.g., \"Archived\" or \"Draft { id: u32 }\")
         #[arg(short = 'v', long)]
         variant: Option<String>,
@@ -971,10 +1696,25 @@ NOTES:
         #[arg(short, long)]
         method: Option<String>,
 
+        /// Add the method to `impl <TRAIT> for <name>` (with --method; without it, the
+        /// inherent impl is used)
+        #[arg(long = "trait", value_name = "TRAIT", requires = "method")]
+        trait_name: Option<String>,
+
         /// Derive macros to add (comma-separated, e.g., \"Clone,Debug,Serialize\")
         #[arg(short = 'd', long)]
         derive: Option<String>,
 
+        /// Reorder the whole derive list after adding (e.g., \"Debug,Clone,PartialEq\");
+        /// derives not listed go last, alphabetically
+        #[arg(long, value_name = "TRAITS", requires = "derive")]
+        derive_order: Option<String>,
+
+        /// How --method and --derive re-render the item: 'surgical' (default, prettyplease)
+        /// or 'rustfmt' (the project's rustfmt and its rustfmt.toml)
+        #[arg(long, default_value = "surgical")]
+        edit_mode: String,
+
         /// Use statement path (e.g., \"std::collections::HashMap\")
         #[arg(short = 'u', long)]
         r#use: Option<String>,
@@ -1058,6 +1798,11 @@ NOTES:
         #[arg(short = 'P', long, default_value = "last")]
         position: String,
 
+        /// Target a struct/enum declared inside this function's body (disambiguates
+        /// function-local types that share a name)
+        #[arg(long)]
+        in_fn: Option<String>,
+
         /// Apply changes (default is dry-run)
         #[arg(long)]
         apply: bool,
@@ -1085,12 +1830,19 @@ NOTES:
     # Remove impl method
     rs-hack remove --name User --method get_email --paths src --apply
 
+    # Remove a method only from one trait impl
+    rs-hack remove --name User --method fmt --trait Display --paths src --apply
+
+    # Remove use imports nothing in the file refers to
+    rs-hack remove --unused-imports --paths src --apply
+
 AUTO-DETECTION:
     The command auto-detects what to remove based on which flag you provide:
     - --field-name: Remove struct field (or enum variant field with :: syntax)
     - --variant: Remove enum variant
-    - --method: Remove impl method
+    - --method: Remove impl method (from all impl blocks of the type; --trait narrows it)
     - --derive: Remove derive macro
+    - --unused-imports: Remove unreferenced `use` imports (no --name)
 
     If the target (--name) is not found, the command will search the codebase
     and show hints about what exists and how to fix the command.
@@ -1127,6 +1879,11 @@ NOTES:
         #[arg(short, long)]
         method: Option<String>,
 
+        /// Only remove the method from `impl <TRAIT> for <name>` (with --method; without it,
+        /// every impl block of the type is searched)
+        #[arg(long = "trait", value_name = "TRAIT", requires = "method")]
+        trait_name: Option<String>,
+
         /// Derive macro to remove (use with struct/enum)
         #[arg(short = 'd', long)]
         derive: Option<String>,
@@ -1174,6 +1931,17 @@ NOTES:
         #[arg(long)]
         content_filter: Option<String>,
 
+        /// Target a struct/enum declared inside this function's body (disambiguates
+        /// function-local types that share a name)
+        #[arg(long)]
+        in_fn: Option<String>,
+
+        /// Remove `use` imports each file never refers to, pruning just the unused members
+        /// of grouped imports. Globs, and names only used in macros or that may be traits,
+        /// are left in place with a warning
+        #[arg(long, conflicts_with_all = ["name", "field_name", "variant", "method", "derive", "match_arm", "call", "doc_comment"])]
+        unused_imports: bool,
+
         /// Apply changes (default is dry-run)
         #[arg(long)]
         apply: bool,
@@ -1205,12 +1973,14 @@ AUTO-DETECTION:
 WHAT IT DOES:
     - For struct fields: Updates the field definition (type, visibility, etc.)
     - For enum variants: Updates the variant definition (changes structure)
+    - For derives: Replaces the derive list with exactly the given traits, in that order
 
 NOTES:
     - Use --name <NAME> to specify the target struct/enum
     - For --field, provide the new field definition (e.g., \"pub email: String\")
     - For --variant, provide the new variant definition (e.g., \"Draft { created_at: u64 }\")
-    - The field/variant name is parsed from the definition you provide")]
+    - The field/variant name is parsed from the definition you provide
+    - --sort alphabetizes the derive list (the existing one, or the --derive list)")]
     Update {
         /// Path to the Rust file or directory (supports multiple paths and glob patterns)
         #[arg(short, long, num_args = 1..)]
@@ -1285,19 +2055,47 @@ NOTES:
         #[arg(long)]
         content_filter: Option<String>,
 
+        /// Target a struct/enum declared inside this function's body (disambiguates
+        /// function-local types that share a name)
+        #[arg(long)]
+        in_fn: Option<String>,
+
+        /// New derive list, replacing the existing one (e.g., \"Debug,Clone,Serialize\")
+        #[arg(long)]
+        derive: Option<String>,
+
+        /// Sort the derive list alphabetically (alone, or with --derive)
+        #[arg(long)]
+        sort: bool,
+
+        /// New visibility for the matching definitions (e.g. "pub(crate)", "pub"; "private"
+        /// removes it). Works on struct, union, enum, function, const, static, trait,
+        /// type-alias and mod; all of them unless --kind/--node-type narrows it
+        #[arg(long, value_name = "VIS")]
+        visibility: Option<String>,
+
+        /// With --visibility: match names by regex instead of --name (e.g. '.*' for all)
+        #[arg(
+            long,
+            value_name = "PATTERN",
+            requires = "visibility",
+            conflicts_with = "name"
+        )]
+        name_regex: Option<String>,
+
         /// Apply changes (default is dry-run)
         #[arg(long)]
         apply: bool,
     },
 
-    /// Show history of rs-hack runs
+    /// Show history of rs-hack runs (--format json for an array of runs)
     History {
         /// Number of recent runs to show
         #[arg(short, long, default_value = "10")]
         limit: usize,
     },
 
-    /// Revert a specific run
+    /// Revert a specific run (--format json for a report of restored and skipped files)
     Revert {
         /// Run ID to revert (from history)
         run_id: String,
@@ -1338,29 +2136,74 @@ ACTIONS:
     comment     Wrap code in /* ... */ (preserves it for reference)
     remove      Delete code entirely
     replace     Swap with new code (use --with to specify replacement)
+    to-let-else Rewrite `let x = if let Some(v) = e { v } else { return; };`
+                into `let Some(x) = e else { return; };` inside matched functions
+    simplify-format
+                Rewrite `format!(\"{}\", x)` into `x.to_string()` inside matched nodes
+                (only a single `{}` placeholder with one positional argument)
+    cfg-guard   Keep matches only in builds where --guard holds (default debug_assertions):
+                statements become `if cfg!(debug_assertions) { ... }`, definitions get
+                `#[cfg(debug_assertions)]`
+    to-constructor
+                Turn struct literals into constructor calls: `Config { a, b }` becomes
+                `Config::new(a, b)` (--constructor picks the function). Fields are passed
+                in declaration order, read from the struct definition in --paths; literals
+                using `..rest`, or out of order where a value has side effects, are errors
+    to-ufcs     Qualify method calls with a trait (--trait, optional --to-type): `x.into()`
+                becomes `Into::<Foo>::into(x)`. --receiver limits it to one receiver
+                expression. The receiver is passed as written, so add `&` by hand where
+                the method took `&self` through auto-ref
+    replace-regex
+                Regex substitution scoped to each matched node's source (--pattern, --with;
+                $1 or $name in --with are capture groups). The file must still parse
+                afterwards, or nothing is changed
+    rename-signature
+                Rename a definition in its signature only (--with the new name): `fn old`
+                becomes `fn new`; its body, attributes and callers are left alone. Use
+                `rename` to update uses as well
+
+ACTIONS BY NODE TYPE:
+    any node type       comment, remove, replace, replace-regex, to-let-else,
+                        simplify-format, cfg-guard, --script
+    function-call, method-call, struct-literal
+                        wrap
+    struct-literal      to-constructor
+    method-call         to-ufcs
+    function, impl-method, trait-method, extern-fn, struct, union, enum, trait,
+    const, static, type-alias, mod
+                        rename-signature
+
+SCRIPT HOOKS (--script, instead of --action; needs the `script-hooks` feature):
+    A Rhai script run on each matched node, with `source`, `node_type`, `identifier` and
+    `line` in scope. What it evaluates to replaces the node; evaluating to `source`
+    unchanged skips the node, and `throw` aborts with the thrown message.
 
 SUPPORTED NODE TYPES:
 
-Expression-level nodes (8 types):
+Expression-level nodes (9 types):
     struct-literal      Struct initialization (e.g., Config { field: value })
     match-arm           Match arm pattern and body
     enum-usage          Enum variant usage (e.g., Status::Active)
     function-call       Function call (e.g., process_data())
     method-call         Method call (e.g., value.unwrap())
     macro-call          Macro invocation (e.g., println!(), vec![])
+    closure             Closure (--name filters by the first captured variable)
     identifier          Variable or type identifier
     type-ref            Type reference in annotations
 
-Definition-level nodes (9 types):
+Definition-level nodes (12 types):
     struct              Struct definition
+    union               Union definition
     enum                Enum definition
     function            Function definition
     impl-method         Method in impl block
+    extern-fn           Function declared in an extern block (FFI signature)
     trait               Trait definition
     const               Const item
     static              Static item
     type-alias          Type alias
     mod                 Module definition
+    derive              #[derive(...)] on a struct/enum (--name filters by derived trait)
 
 EXAMPLES:
     # Comment out all unwrap() calls
@@ -1372,44 +2215,203 @@ EXAMPLES:
     # Replace a specific function call
     rs-hack transform --paths src --node-type function-call --name old_func --action replace --with new_func --apply
 
+    # Wrap every call to compute in instrument(...) ({} is the original call)
+    rs-hack transform --paths src --node-type function-call --name compute --action wrap --with \"instrument({})\" --apply
+
+    # Comment out every function whose name starts with debug_
+    rs-hack transform --paths src --node-type function --name-regex \"^debug_\" --action comment
+
     # Remove all struct literals containing a specific value
     rs-hack transform --paths src --node-type struct-literal --content-filter \"[SHADOW RENDER]\" --action remove --apply
 
     # Comment out all TODO match arms
     rs-hack transform --paths src --node-type match-arm --content-filter \"todo!()\" --action comment --apply
 
+    # Closures that unwrap (find --node-type closure lists them all)
+    rs-hack transform --paths src --node-type closure --content-filter unwrap --action comment
+
     # Preview changes before applying (default dry-run)
-    rs-hack transform --paths src --node-type method-call --name unwrap --action comment")]
+    rs-hack transform --paths src --node-type method-call --name unwrap --action comment
+
+    # Comment out unwrap() everywhere except inside tests and main
+    rs-hack transform --paths src --node-type method-call --name unwrap --action comment \\
+        --exclude-in \"test_*\" --exclude-in tests --exclude-in main
+
+    # Adopt let-else in one function (use --node-type impl-method for methods)
+    rs-hack transform --paths src --action to-let-else --in parse_config --apply
+
+    # Turn format!(\"{}\", x) into x.to_string() across every format! call
+    rs-hack transform --paths src --node-type macro-call --name format --action simplify-format --apply
+
+    # Keep eprintln! debugging in debug builds only
+    rs-hack transform --paths src --node-type macro-call --name eprintln --action cfg-guard --apply
+
+    # Migrate Config literals to Config::new now that it exists
+    rs-hack transform --paths src --node-type struct-literal --name Config --action to-constructor --apply
+
+    # Rename a function's definition only, leaving its callers for a later step
+    rs-hack transform --paths src --node-type function --name old_name --action rename-signature \\
+        --with new_name --apply
+
+    # Spell out which conversion an ambiguous .into() means
+    rs-hack transform --paths src --node-type method-call --name into --action to-ufcs \\
+        --trait Into --to-type Foo --apply
+
+    # Retag debug output in println! calls only, leaving other strings alone
+    rs-hack transform --paths src --node-type macro-call --name println --action replace-regex \\
+        --pattern 'DEBUG:' --with 'TRACE:' --apply
+
+    # Rewrite matches with a Rhai script (built with --features script-hooks)
+    rs-hack transform --paths src --node-type macro-call --name log --script hooks/log.rhai
+
+    # Apply several transforms in one pass (single run id, one revert)
+    rs-hack transform --paths src --spec cleanup.yaml --apply
+
+TRANSFORM SPEC (JSON or YAML):
+    transforms:
+      - node_type: macro-call
+        name_filter: eprintln
+        action: { type: Remove }
+      - node_type: method-call
+        name_filter: unwrap
+        action: { type: Comment }
+      - node_type: function-call
+        name_filter: old_func
+        action: { type: Replace, with: new_func }")]
     Transform {
         /// Path to Rust file(s) - supports multiple paths and glob patterns (e.g., "src/**/*.rs")
         #[arg(short, long, num_args = 1..)]
         paths: Vec<PathBuf>,
 
         /// Type of node (see SUPPORTED NODE TYPES above for full list)
-        #[arg(short = 't', long)]
-        node_type: String,
+        #[arg(short = 't', long, required_unless_present_any = ["spec", "in_fn"])]
+        node_type: Option<String>,
 
         /// Filter by name (e.g., "eprintln", "unwrap", "Config")
         #[arg(short, long)]
         name: Option<String>,
 
+        /// Filter by a regex on the name instead of --name (e.g. "^debug_"; unanchored)
+        #[arg(long, value_name = "PATTERN", conflicts_with_all = ["name", "in_fn"])]
+        name_regex: Option<String>,
+
         /// Filter by content - only transform nodes containing this string
         #[arg(short = 'c', long)]
         content_filter: Option<String>,
 
-        /// Action to perform: "comment", "remove", or "replace"
-        #[arg(short, long)]
-        action: String,
+        /// Action to perform: "comment", "remove", "replace", "wrap", "to-let-else",
+        /// "simplify-format", "cfg-guard", "to-constructor", "to-ufcs", or "replace-regex"
+        #[arg(short, long, required_unless_present_any = ["spec", "script"])]
+        action: Option<String>,
 
-        /// Replacement code (required if action is "replace")
+        /// Replacement code (required if action is "replace"), the template for "wrap"
+        /// with {} standing for the matched code (e.g. "instrument({})"), or the
+        /// substitution for each --pattern match with "replace-regex"
         #[arg(short = 'w', long)]
         with: Option<String>,
 
+        /// Condition for --action cfg-guard (e.g. "debug_assertions", "feature = \"trace\"")
+        #[arg(
+            long = "guard",
+            value_name = "PREDICATE",
+            default_value = "debug_assertions"
+        )]
+        guard: String,
+
+        /// Regex to substitute inside each matched node with --action replace-regex
+        #[arg(long, value_name = "REGEX")]
+        pattern: Option<String>,
+
+        /// Associated function to call with --action to-constructor
+        #[arg(long, value_name = "NAME", default_value = "new")]
+        constructor: String,
+
+        /// Trait to qualify calls with, for --action to-ufcs (e.g. "Into", "std::fmt::Display")
+        #[arg(long = "trait", value_name = "PATH")]
+        trait_path: Option<String>,
+
+        /// Type argument for the trait with --action to-ufcs: `--trait Into --to-type Foo`
+        /// gives `Into::<Foo>::into(x)`
+        #[arg(long, value_name = "TYPE", requires = "trait_path")]
+        to_type: Option<String>,
+
+        /// Only rewrite calls on this receiver expression with --action to-ufcs (e.g. "self.id")
+        #[arg(long, value_name = "EXPR", requires = "trait_path")]
+        receiver: Option<String>,
+
+        /// Function to rewrite with --action to-let-else or simplify-format (shorthand for
+        /// --name with --node-type defaulting to "function"; pass --node-type impl-method
+        /// for methods)
+        #[arg(long = "in", value_name = "FUNCTION", conflicts_with = "name")]
+        in_fn: Option<String>,
+
+        /// Skip nodes whose own name matches this glob (e.g. "test_*"; repeatable)
+        #[arg(long = "exclude-name", value_name = "PATTERN")]
+        exclude_names: Vec<String>,
+
+        /// Skip nodes inside a function, impl/trait/type, or module whose name matches this
+        /// glob (repeatable)
+        #[arg(long, value_name = "PATTERN")]
+        exclude_in: Vec<String>,
+
+        /// JSON/YAML file listing several transforms to apply in order
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "node_type",
+                "name",
+                "name_regex",
+                "content_filter",
+                "action",
+                "with",
+                "in_fn",
+                "exclude_names",
+                "exclude_in",
+                "flag_risky",
+                "script",
+                "trait_path",
+                "pattern"
+            ]
+        )]
+        spec: Option<PathBuf>,
+
+        /// Rhai script that rewrites each matched node: it gets the node's `source` and
+        /// evaluates to the replacement (used instead of --action)
+        #[arg(long, value_name = "SCRIPT", conflicts_with_all = ["action", "with", "in_fn"])]
+        script: Option<PathBuf>,
+
+        /// In a dry run, flag matches that look like false positives (common names, bare
+        /// identifiers, unchecked method receivers, mentions inside macros)
+        #[arg(long, conflicts_with = "apply")]
+        flag_risky: bool,
+
         /// Apply changes (default is dry-run)
         #[arg(long)]
         apply: bool,
     },
 
+    /// Re-run a find or dry-run transform whenever a .rs file under its paths changes
+    #[command(after_help = "EXAMPLES:
+    # Keep the unwrap() calls on screen while you fix them
+    rs-hack watch find --node-type method-call --name unwrap --paths src
+
+    # Preview a transform after every save
+    rs-hack watch transform --node-type macro-call --name dbg --action remove --paths src
+
+NOTES:
+    - The screen is cleared before each run; Ctrl-C stops watching
+    - Changes less than --debounce apart trigger a single run
+    - Transforms are never applied: --apply is rejected")]
+    Watch {
+        /// Milliseconds without changes to wait before re-running
+        #[arg(long, default_value_t = 200)]
+        debounce: u64,
+
+        /// The find or transform command to run, with its arguments
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+
     #[command(hide = true)]
     /// [DEPRECATED] Add documentation comment to an item - use 'rs-hack add' instead
     #[command(
@@ -1543,20 +2545,82 @@ WHAT IT DOES:
     },
 }
 
-/// Validate that an enum variant rename would catch all references
-fn validate_enum_variant_rename(
+/// `rename --name Struct::field --validate`: sort the `.field` accesses a rename would
+/// touch by what's known about their receivers, and list the ones it can only guess at.
+fn validate_field_rename(
+    ctx: &RunContext,
     files: &[PathBuf],
-    enum_name: &str,
-    old_variant: &str,
-    enum_path: Option<&str>,
+    struct_name: &str,
+    field: &str,
 ) -> Result<()> {
-    use syn::File;
-    use syn::visit::Visit;
-
-    struct VariantFinder<'a> {
-        enum_name: &'a str,
-        variant_name: &'a str,
-        _enum_path: Option<&'a str>,
+    let mut known = 0;
+    let mut other = 0;
+    let mut unknown = Vec::new();
+    for file_path in files {
+        let content = ctx
+            .read_source(file_path)
+            .with_context(|| format!("Failed to read {}", file_path.display()))?;
+        let editor = match RustEditor::with_scope(&content, ctx.scope.clone()) {
+            Ok(editor) => editor,
+            Err(e) => {
+                ctx.verbose.skipped(file_path, &e);
+                continue;
+            }
+        };
+        for access in editor.field_accesses(field) {
+            match access.receiver_type.as_deref() {
+                Some(ty) if ty == struct_name => known += 1,
+                Some(_) => other += 1,
+                None => unknown.push((file_path.display().to_string(), access)),
+            }
+        }
+    }
+
+    println!("Accesses to .{} for {}::{}:", field, struct_name, field);
+    println!(
+        "  ✓ {} on receivers known to be {} (renamed)",
+        known, struct_name
+    );
+    println!("  - {} on receivers of other types (left alone)", other);
+    if unknown.is_empty() {
+        println!("  ✓ No accesses on receivers of unknown type.");
+        return Ok(());
+    }
+    println!(
+        "  ⚠️  {} on receivers of unknown type (renamed too; review these):",
+        unknown.len()
+    );
+    for (file, access) in &unknown {
+        println!(
+            "    - {}:{}:{}  {}",
+            file, access.location.line, access.location.column, access.code
+        );
+    }
+    println!();
+    println!(
+        "💡 If any of these belong to another type with a `{}` field,",
+        field
+    );
+    println!("   annotate the binding's type so the rename can tell them apart.");
+
+    Ok(())
+}
+
+/// Validate that an enum variant rename would catch all references
+fn validate_enum_variant_rename(
+    ctx: &RunContext,
+    files: &[PathBuf],
+    enum_name: &str,
+    old_variant: &str,
+    enum_path: Option<&str>,
+) -> Result<()> {
+    use syn::File;
+    use syn::visit::Visit;
+
+    struct VariantFinder<'a> {
+        enum_name: &'a str,
+        variant_name: &'a str,
+        _enum_path: Option<&'a str>,
         references: Vec<(String, usize, usize, String)>, // (file, line, col, code)
     }
 
@@ -1601,7 +2665,8 @@ fn validate_enum_variant_rename(
     };
 
     for file_path in files {
-        let content = std::fs::read_to_string(file_path)
+        let content = ctx
+            .read_source(file_path)
             .with_context(|| format!("Failed to read {}", file_path.display()))?;
 
         let _syntax_tree: File = syn::parse_str(&content)
@@ -1656,8 +2721,36 @@ fn validate_enum_variant_rename(
     Ok(())
 }
 
+/// `rename --require-qualified`: refuse to rename by bare name unless the matching
+/// `--enum-path` / `--function-path` is given and actually qualified
+fn require_qualified_path(
+    required: bool,
+    path: Option<&str>,
+    flag: &str,
+    example: &str,
+) -> Result<()> {
+    if !required {
+        return Ok(());
+    }
+    match path {
+        Some(path) if path.contains("::") => Ok(()),
+        Some(path) => anyhow::bail!(
+            "--require-qualified: {} '{}' is not a qualified path (e.g. {})",
+            flag,
+            path,
+            example
+        ),
+        None => anyhow::bail!(
+            "--require-qualified: pass {} (e.g. {}) so only references resolving to that path are renamed",
+            flag,
+            example
+        ),
+    }
+}
+
 /// Validate that a function rename would catch all references
 fn validate_function_rename(
+    ctx: &RunContext,
     files: &[PathBuf],
     old_name: &str,
     function_path: Option<&str>,
@@ -1665,7 +2758,8 @@ fn validate_function_rename(
     let mut references = Vec::new();
 
     for file_path in files {
-        let content = std::fs::read_to_string(file_path)
+        let content = ctx
+            .read_source(file_path)
             .with_context(|| format!("Failed to read {}", file_path.display()))?;
 
         // Search for simple text matches
@@ -1723,14 +2817,24 @@ fn validate_function_rename(
 }
 
 /// Check if a target exists in the files
-fn target_exists(files: &[PathBuf], name: &str, node_type: Option<&str>) -> Result<bool> {
+fn target_exists(
+    ctx: &RunContext,
+    files: &[PathBuf],
+    name: &str,
+    node_type: Option<&str>,
+) -> Result<bool> {
     for file in files {
-        let content =
-            std::fs::read_to_string(file).context(format!("Failed to read file: {:?}", file))?;
+        let content = ctx
+            .read_source(file)
+            .context(format!("Failed to read file: {:?}", file))?;
 
-        let editor = match RustEditor::new(&content) {
+        let editor = match RustEditor::with_scope(&content, ctx.scope.clone()) {
             Ok(e) => e,
-            Err(_) => continue, // Skip unparseable files during discovery
+            Err(e) => {
+                // Skip unparseable files during discovery
+                ctx.verbose.skipped(file, &e);
+                continue;
+            }
         };
         let results = editor.inspect(node_type, Some(name), None, false)?;
 
@@ -1742,14 +2846,19 @@ fn target_exists(files: &[PathBuf], name: &str, node_type: Option<&str>) -> Resu
 }
 
 /// Detect the type of a target (struct or enum) for derive operations
-fn detect_target_type(files: &[PathBuf], name: &str) -> Result<Option<String>> {
+fn detect_target_type(ctx: &RunContext, files: &[PathBuf], name: &str) -> Result<Option<String>> {
     for file in files {
-        let content =
-            std::fs::read_to_string(file).context(format!("Failed to read file: {:?}", file))?;
+        let content = ctx
+            .read_source(file)
+            .context(format!("Failed to read file: {:?}", file))?;
 
-        let editor = match RustEditor::new(&content) {
+        let editor = match RustEditor::with_scope(&content, ctx.scope.clone()) {
             Ok(e) => e,
-            Err(_) => continue, // Skip unparseable files during discovery
+            Err(e) => {
+                // Skip unparseable files during discovery
+                ctx.verbose.skipped(file, &e);
+                continue;
+            }
         };
 
         // Try struct first
@@ -1767,8 +2876,52 @@ fn detect_target_type(files: &[PathBuf], name: &str) -> Result<Option<String>> {
     Ok(None)
 }
 
+/// Field count of the tuple struct `name`, read from its definition in `files`. `None` when
+/// there's no such struct; an error when it has named fields or its definitions disagree.
+fn tuple_struct_arity(
+    ctx: &RunContext,
+    files: &[PathBuf],
+    name: &str,
+    in_fn: Option<&str>,
+) -> Result<Option<usize>> {
+    let mut arities = std::collections::BTreeSet::new();
+    for file in files {
+        let content = ctx
+            .read_source(file)
+            .context(format!("Failed to read file: {:?}", file))?;
+
+        let editor = match RustEditor::with_scope(&content, ctx.scope.clone()) {
+            Ok(e) => e,
+            Err(e) => {
+                // Skip unparseable files during discovery
+                ctx.verbose.skipped(file, &e);
+                continue;
+            }
+        };
+        match editor.tuple_field_count(name, in_fn) {
+            Ok(Some(arity)) => {
+                arities.insert(arity);
+            }
+            Ok(None) => anyhow::bail!(
+                "Struct '{}' has named fields: use --field-name to add one",
+                name
+            ),
+            Err(_) => continue,
+        }
+    }
+    if arities.len() > 1 {
+        anyhow::bail!(
+            "Found tuple structs named '{}' with different field counts ({:?}); narrow --paths to one",
+            name,
+            arities
+        );
+    }
+    Ok(arities.pop_first())
+}
+
 /// Show helpful hints when target is not found
 fn show_target_hints(
+    ctx: &RunContext,
     files: &[PathBuf],
     name: &str,
     expected_type: &str,
@@ -1780,14 +2933,24 @@ fn show_target_hints(
 
     // Search across all node types to find near-misses
     let mut hint_results: Vec<InspectResult> = Vec::new();
+    let mut parse_errors = Vec::new();
 
     for file in files {
-        let content =
-            std::fs::read_to_string(file).context(format!("Failed to read file: {:?}", file))?;
+        let content = ctx
+            .read_source(file)
+            .context(format!("Failed to read file: {:?}", file))?;
 
-        let editor = match RustEditor::new(&content) {
+        let editor = match RustEditor::with_scope(&content, ctx.scope.clone()) {
             Ok(e) => e,
-            Err(_) => continue, // Skip unparseable files during discovery
+            Err(e) if ctx.strict => {
+                return Err(e).with_context(|| format!("Failed to parse {}", file.display()));
+            }
+            Err(e) => {
+                // Skip unparseable files during discovery
+                ctx.verbose.skipped(file, &e);
+                parse_errors.push((file.clone(), e.to_string()));
+                continue;
+            }
         };
         let mut results = editor.inspect(None, Some(name), None, false)?;
 
@@ -1797,6 +2960,8 @@ fn show_target_hints(
 
         hint_results.extend(results);
     }
+    // Not finding the target because nothing parsed fails like the operation itself would
+    ensure_some_parsed(files, &parse_errors)?;
 
     if hint_results.is_empty() {
         eprintln!("No {} found named \"{}\"", expected_type, name);
@@ -1855,12 +3020,556 @@ fn show_target_hints(
         );
     }
 
+    // Printing nothing and succeeding would let a `--stdin` filter wipe the caller's buffer
+    if ctx.stdin_mode() {
+        anyhow::bail!("No {} named \"{}\" on stdin", expected_type, name);
+    }
+    Ok(())
+}
+
+/// What a run carries beyond each command's own arguments: the global flags the command
+/// helpers read, the code read by `--stdin`, and what `--report-file`/`--output-patch`
+/// collect as operations execute.
+#[derive(Default)]
+struct RunContext {
+    /// The code read by `--stdin`; operations edit it instead of files.
+    stdin_source: Option<String>,
+    /// `--interactive`: changes are reviewed one region at a time before writing.
+    interactive: bool,
+    /// `--max-matches-per-file`
+    max_matches_per_file: Option<usize>,
+    /// `--verify-idempotent`: every operation runs as a dry run.
+    verify_idempotent: bool,
+    /// `--backups`/`--backup-dir`
+    backups: Option<rs_hack::execute::BackupLocation>,
+    /// The `--verbose` log, shared by the lookups and the operation of a run.
+    verbose: rs_hack::verbose::Verbose,
+    /// `--strict`: a file that doesn't parse fails the run instead of being skipped.
+    strict: bool,
+    /// The code operations and lookups see (`--cfg`, `--skip-tests`, `--skip-attr`,
+    /// `--in-module`)
+    scope: rs_hack::cfg::Scope,
+    /// Operation reports for `--report-file` and `--verify-idempotent`. `None` unless one of
+    /// them was requested, so regular runs don't pay for the per-file diff stats.
+    reports: RefCell<Option<Vec<rs_hack::execute::OperationReport>>>,
+    /// Dry-run changes for `--output-patch`, one entry per file. `None` unless a patch was
+    /// requested.
+    patch: RefCell<Option<Vec<rs_hack::execute::FileChange>>>,
+}
+
+/// Stand-in file name for the code on stdin.
+const STDIN_PATH: &str = "<stdin>";
+
+impl RunContext {
+    const fn stdin_mode(&self) -> bool {
+        self.stdin_source.is_some()
+    }
+
+    /// The files an operation runs on: `--paths` expanded, or the stand-in for stdin.
+    fn input_files(
+        &self,
+        paths: &[PathBuf],
+        exclude: &[String],
+        collect: &CollectOptions,
+    ) -> Result<Vec<PathBuf>> {
+        if self.stdin_mode() {
+            return Ok(vec![PathBuf::from(STDIN_PATH)]);
+        }
+        collect_rust_files_with_options(paths, exclude, collect)
+    }
+
+    /// How to execute an operation of this run.
+    fn execute_opts(
+        &self,
+        apply: bool,
+        output: Option<&PathBuf>,
+        limit: Option<usize>,
+    ) -> rs_hack::execute::ExecuteOpts {
+        rs_hack::execute::ExecuteOpts {
+            apply,
+            output: output.cloned(),
+            limit,
+            max_per_file: self.max_matches_per_file,
+            backups: self.backups.clone(),
+            verbose: self.verbose.clone(),
+            strict: self.strict,
+            scope: self.scope.clone(),
+        }
+    }
+
+    /// Contents of a file from `input_files`.
+    fn read_source(&self, path: &Path) -> std::io::Result<String> {
+        match &self.stdin_source {
+            Some(source) if path == Path::new(STDIN_PATH) => Ok(source.clone()),
+            _ => std::fs::read_to_string(path),
+        }
+    }
+
+    fn record_operation_report(
+        &self,
+        files: &[PathBuf],
+        op: &Operation,
+        apply: bool,
+        result: &rs_hack::execute::ExecuteResult,
+    ) {
+        if !apply {
+            self.record_patch(&result.changes);
+        }
+        if let Some(reports) = self.reports.borrow_mut().as_mut() {
+            reports.push(rs_hack::execute::OperationReport::from_result(
+                files.len(),
+                op,
+                apply,
+                result,
+            ));
+        }
+    }
+
+    /// Add dry-run changes to the `--output-patch` collection. A file changed again by a
+    /// later operation of the same run (a batch) keeps its original contents as the patch's
+    /// base.
+    fn record_patch(&self, changes: &[rs_hack::execute::FileChange]) {
+        if let Some(recorded) = self.patch.borrow_mut().as_mut() {
+            for change in changes {
+                match recorded.iter_mut().find(|c| c.path == change.path) {
+                    Some(existing) if existing.new_content == change.old_content => {
+                        existing.new_content = change.new_content.clone();
+                    }
+                    Some(existing) => {
+                        eprintln!(
+                            "⚠️  {} is changed by more than one operation; --output-patch keeps the last",
+                            change.path.display()
+                        );
+                        *existing = change.clone();
+                    }
+                    None => recorded.push(change.clone()),
+                }
+            }
+        }
+    }
+}
+
+/// Top-level document written by `--report-file`.
+#[derive(serde::Serialize)]
+struct RunReport {
+    command: String,
+    /// `ok` or `error`
+    status: &'static str,
+    exit_code: i32,
+    /// Why the run ended with `exit_code`
+    reason: String,
+    operations: Vec<rs_hack::execute::OperationReport>,
+}
+
+/// Write the changes collected by `record_patch` to `path` as one patch, file sections in
+/// path order.
+fn write_output_patch(ctx: &RunContext, path: &Path) -> Result<()> {
+    let changes = ctx.patch.take().unwrap_or_default();
+    if changes.is_empty() {
+        eprintln!(
+            "No changes to write; {} not written (--output-patch only records dry runs)",
+            path.display()
+        );
+        return Ok(());
+    }
+    let cwd = std::env::current_dir()
+        .and_then(std::fs::canonicalize)
+        .context("Failed to read the current directory")?;
+    let mut sections = Vec::new();
+    for change in &changes {
+        let absolute = std::fs::canonicalize(&change.path)
+            .with_context(|| format!("Failed to resolve {}", change.path.display()))?;
+        let relative = absolute.strip_prefix(&cwd).map_err(|_| {
+            anyhow::anyhow!(
+                "{} is outside the current directory; run from a directory containing it so the patch can name it",
+                change.path.display()
+            )
+        })?;
+        let relative = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let section =
+            rs_hack::diff::git_file_patch(&relative, &change.old_content, &change.new_content);
+        sections.push((relative, section));
+    }
+    sections.sort();
+    let patch: String = sections.into_iter().map(|(_, section)| section).collect();
+    std::fs::write(path, patch)
+        .with_context(|| format!("Failed to write patch {}", path.display()))?;
+    eprintln!(
+        "📝 Wrote {} file(s) to {} (apply with: git apply -p1 {})",
+        changes.len(),
+        path.display(),
+        path.display()
+    );
     Ok(())
 }
 
+fn write_run_report(ctx: &RunContext, path: &Path, outcome: &Result<()>) -> Result<()> {
+    let operations = ctx.reports.take().unwrap_or_default();
+
+    let (status, exit_code, reason) = match outcome {
+        Ok(()) if operations.is_empty() => (
+            "ok",
+            0,
+            "command completed without executing a modifying operation".to_string(),
+        ),
+        Ok(()) => {
+            let reasons: Vec<&str> = operations.iter().map(|r| r.reason.as_str()).collect();
+            ("ok", 0, reasons.join("\n"))
+        }
+        Err(e) => ("error", 1, format!("{:#}", e)),
+    };
+
+    let report = RunReport {
+        command: std::env::args().collect::<Vec<_>>().join(" "),
+        status,
+        exit_code,
+        reason,
+        operations,
+    };
+
+    let json = serde_json::to_string_pretty(&report)?;
+    std::fs::write(path, json)
+        .with_context(|| format!("Failed to write report file {}", path.display()))
+}
+
+/// `--verify-idempotent` verdict: fail if any executed operation would have changed files.
+fn check_idempotent(ctx: &RunContext) -> Result<()> {
+    let pending: Vec<rs_hack::execute::FileReport> = ctx
+        .reports
+        .borrow()
+        .iter()
+        .flatten()
+        .flat_map(|report| report.files.clone())
+        .collect();
+
+    if pending.is_empty() {
+        println!("\n✓ Idempotent: no changes would be made");
+        return Ok(());
+    }
+
+    let modifications: usize = pending.iter().map(|f| f.modified_nodes).sum();
+    eprintln!(
+        "\n✗ Not idempotent: {} modification(s) would still be made in {} file(s):",
+        modifications,
+        pending.len()
+    );
+    for file in &pending {
+        eprintln!(
+            "   {} (+{} -{})",
+            file.path.display(),
+            file.lines_added,
+            file.lines_removed
+        );
+    }
+    anyhow::bail!(
+        "--verify-idempotent: operation would still change {} file(s)",
+        pending.len()
+    )
+}
+
 fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let (cli, config_paths) = parse_cli()?;
+    run_reported(cli, &config_paths)
+}
+
+/// `run`, then what `--output-patch`, `--verify-idempotent` and `--report-file` ask for
+/// once it's done.
+fn run_reported(cli: Cli, config_paths: &[PathBuf]) -> Result<()> {
+    let report_file = cli.report_file.clone();
+    let output_patch = cli.output_patch.clone();
+    let verify = cli.verify_idempotent;
+
+    if verify
+        && matches!(
+            cli.command,
+            Commands::Revert { .. } | Commands::Clean { .. }
+        )
+    {
+        anyhow::bail!("--verify-idempotent only applies to modifying operations");
+    }
+
+    let mut ctx = RunContext {
+        verify_idempotent: verify,
+        reports: RefCell::new((report_file.is_some() || verify).then(Vec::new)),
+        patch: RefCell::new(output_patch.is_some().then(Vec::new)),
+        ..RunContext::default()
+    };
+    let mut outcome = run(cli, config_paths, &mut ctx);
+    if let Some(path) = &output_patch
+        && outcome.is_ok()
+    {
+        outcome = write_output_patch(&ctx, path);
+    }
+    if verify && outcome.is_ok() {
+        outcome = check_idempotent(&ctx);
+    }
+    if let Some(report_file) = report_file {
+        write_run_report(&ctx, &report_file, &outcome)?;
+    }
+    outcome
+}
+
+/// Parse the command line on top of the defaults from `.rs-hack.toml`, if there is one.
+/// The config only replaces clap's defaults, so flags given explicitly always win. Also
+/// returns the config's `paths`, which `fill_default_paths` tries before the built-in default.
+fn parse_cli() -> Result<(Cli, Vec<PathBuf>)> {
+    let cwd = std::env::current_dir().context("Failed to read the current directory")?;
+    let Some(config) = rs_hack::config::Config::find(&cwd)? else {
+        return Ok((Cli::parse(), Vec::new()));
+    };
+
+    let mut command = Cli::command();
+    if !config.exclude.is_empty() {
+        command = command.mut_arg("exclude", |arg| arg.default_values(config.exclude.clone()));
+    }
+    if let Some(format) = &config.format {
+        command = command.mut_arg("format", |arg| arg.default_value(format.clone()));
+    }
+    if config.local_state {
+        command = command.mut_arg("local_state", |arg| arg.default_value("true"));
+    }
+    if let Some(edit_mode) = config.edit_mode {
+        let takes_edit_mode: Vec<String> = command
+            .get_subcommands()
+            .filter(|sub| sub.get_arguments().any(|arg| arg.get_id() == "edit_mode"))
+            .map(|sub| sub.get_name().to_string())
+            .collect();
+        for name in takes_edit_mode {
+            command = command.mut_subcommand(name, |sub| {
+                sub.mut_arg("edit_mode", |arg| arg.default_value(edit_mode.to_string()))
+            });
+        }
+    }
+
+    let cli = Cli::from_arg_matches(&command.get_matches()).map_err(|e| e.exit())?;
+    Ok((cli, config.paths))
+}
+
+/// `watch`: run `command` (a find or transform) as a child rs-hack, then again after each
+/// burst of `.rs` changes under its paths. Global flags given around `watch` pass through.
+#[cfg(feature = "watch")]
+fn run_watch(
+    debounce: std::time::Duration,
+    command: &[String],
+    config_paths: &[PathBuf],
+) -> Result<()> {
+    use std::ffi::OsString;
+    use std::io::{IsTerminal, Write};
+
+    // Our own arguments are `[globals] watch [--debounce N | globals] <command...>`
+    let args: Vec<OsString> = std::env::args_os().skip(1).collect();
+    let prefix = &args[..args.len().saturating_sub(command.len())];
+    let watch_at = prefix
+        .iter()
+        .rposition(|arg| arg == "watch")
+        .context("Failed to find `watch` in the command line")?;
+    let mut child_args = prefix[..watch_at].to_vec();
+    let mut rest = prefix[watch_at + 1..].iter();
+    while let Some(arg) = rest.next() {
+        if arg == "--debounce" {
+            rest.next();
+        } else if !arg.to_string_lossy().starts_with("--debounce=") {
+            child_args.push(arg.clone());
+        }
+    }
+    child_args.extend(command.iter().map(OsString::from));
+
+    let mut inner = Cli::try_parse_from(
+        std::iter::once(OsString::from("rs-hack")).chain(child_args.iter().cloned()),
+    )
+    .unwrap_or_else(|e| e.exit());
+    if inner.stdin {
+        anyhow::bail!("watch re-reads files on change; drop --stdin");
+    }
+    fill_default_paths(&mut inner.command, false, config_paths)?;
+    let paths = match &inner.command {
+        Commands::Find { paths, .. } => paths.clone(),
+        Commands::Transform { apply: true, .. } => anyhow::bail!(
+            "watch only previews transforms; drop --apply, and apply once the diff looks right"
+        ),
+        Commands::Transform { paths, .. } => paths.clone(),
+        _ => anyhow::bail!("watch re-runs `find` or `transform`, not `{}`", command[0]),
+    };
+
+    let exe = std::env::current_exe().context("Failed to find the rs-hack executable")?;
+    let title = format!("rs-hack {}", command.join(" "));
+    rs_hack::watch::watch(&paths, debounce, || {
+        if std::io::stdout().is_terminal() {
+            print!("\x1b[2J\x1b[H");
+        }
+        println!(
+            "{}",
+            rs_hack::color::paint(
+                rs_hack::color::DIM,
+                &format!(
+                    "[{}] {} (Ctrl-C to stop)",
+                    chrono::Local::now().format("%H:%M:%S"),
+                    title
+                )
+            )
+        );
+        std::io::stdout().flush()?;
+        // A failing run has printed its error; keep watching for the fix
+        std::process::Command::new(&exe)
+            .args(&child_args)
+            .status()
+            .context("Failed to run rs-hack")?;
+        Ok(())
+    })?;
+    eprintln!("Stopped watching.");
+    Ok(())
+}
 
+#[cfg(not(feature = "watch"))]
+fn run_watch(
+    _debounce: std::time::Duration,
+    _command: &[String],
+    _config_paths: &[PathBuf],
+) -> Result<()> {
+    anyhow::bail!("rs-hack was built without the `watch` feature")
+}
+
+/// Fill in `--paths` when it was left out, from `config_paths` or else
+/// `rs_hack::files::default_path` (saying which path was picked). Explicit paths are used as
+/// given.
+#[allow(deprecated)]
+fn fill_default_paths(command: &mut Commands, stdin: bool, config_paths: &[PathBuf]) -> Result<()> {
+    let (paths, read_only) = match command {
+        Commands::Find { paths, .. }
+        | Commands::FindField { paths, .. }
+        | Commands::Impls { paths, .. }
+        | Commands::MatchAudit { paths, .. }
+        | Commands::DocCoverage { paths, .. }
+        | Commands::Impact { paths, .. }
+        | Commands::Complexity { paths, .. }
+        | Commands::Stats { paths, .. } => (paths, true),
+        Commands::AddStructField { paths, .. }
+        | Commands::UpdateStructField { paths, .. }
+        | Commands::RemoveStructField { paths, .. }
+        | Commands::AddStructLiteralField { paths, .. }
+        | Commands::AddEnumVariant { paths, .. }
+        | Commands::UpdateEnumVariant { paths, .. }
+        | Commands::RemoveEnumVariant { paths, .. }
+        | Commands::RenameEnumVariant { paths, .. }
+        | Commands::RenameFunction { paths, .. }
+        | Commands::Rename { paths, .. }
+        | Commands::AddMatchArm { paths, .. }
+        | Commands::UpdateMatchArm { paths, .. }
+        | Commands::RemoveMatchArm { paths, .. }
+        | Commands::SyncMatch { paths, .. }
+        | Commands::Move { paths, .. }
+        | Commands::InlineFunction { paths, .. }
+        | Commands::AlignFields { paths, .. }
+        | Commands::AddDerive { paths, .. }
+        | Commands::AddImplMethod { paths, .. }
+        | Commands::AddUse { paths, .. }
+        | Commands::Add { paths, .. }
+        | Commands::Remove { paths, .. }
+        | Commands::Update { paths, .. }
+        | Commands::Transform { paths, .. }
+        | Commands::AddDocComment { paths, .. }
+        | Commands::UpdateDocComment { paths, .. }
+        | Commands::RemoveDocComment { paths, .. } => (paths, false),
+        _ if stdin => anyhow::bail!("--stdin only applies to commands that edit a single file"),
+        _ => return Ok(()),
+    };
+    if stdin {
+        if read_only {
+            anyhow::bail!("--stdin only applies to commands that edit a single file");
+        }
+        if !paths.is_empty() {
+            anyhow::bail!("--stdin reads the code from stdin; drop --paths");
+        }
+        return Ok(());
+    }
+    if paths.is_empty() && !config_paths.is_empty() {
+        paths.extend(config_paths.iter().cloned());
+        return Ok(());
+    }
+    if paths.is_empty() {
+        let path = rs_hack::files::default_path(read_only)?;
+        eprintln!("No --paths given; using {}", path.display());
+        paths.push(path);
+    }
+    Ok(())
+}
+
+fn run(mut cli: Cli, config_paths: &[PathBuf], ctx: &mut RunContext) -> Result<()> {
+    ctx.verbose = rs_hack::verbose::Verbose::new(cli.verbose);
+    ctx.strict = cli.strict;
+    rs_hack::color::set_color_choice(
+        cli.color
+            .parse::<rs_hack::color::ColorChoice>()
+            .map_err(|e| anyhow::anyhow!(e))?,
+    );
+    let collect = rs_hack::files::CollectOptions {
+        follow_symlinks: cli.follow_symlinks,
+        use_ignore_files: !cli.no_ignore,
+        max_depth: cli.max_depth.map(std::num::NonZeroUsize::get),
+        only_files: cli.only_files.clone(),
+        changed_files: cli
+            .since
+            .as_deref()
+            .map(|git_ref| rs_hack::files::changed_since(Path::new("."), git_ref))
+            .transpose()?,
+    };
+    let test_attrs: &[&str] = if cli.skip_tests {
+        rs_hack::cfg::TEST_ATTRS
+    } else {
+        &[]
+    };
+    ctx.scope = rs_hack::cfg::Scope::default()
+        .with_cfg(if cli.cfg_options.is_empty() {
+            None
+        } else {
+            Some(rs_hack::cfg::CfgSet::parse(
+                cli.cfg_options.iter().map(String::as_str),
+            )?)
+        })
+        .with_skip_attrs(
+            test_attrs
+                .iter()
+                .copied()
+                .chain(cli.skip_attrs.iter().map(String::as_str)),
+        )
+        .with_in_module(cli.in_module.as_deref());
+    ctx.backups = match &cli.backup_dir {
+        Some(dir) => Some(rs_hack::execute::BackupLocation::Dir(dir.clone())),
+        None if cli.backups => Some(rs_hack::execute::BackupLocation::NextToFile),
+        None => None,
+    };
+    if cli.interactive {
+        use std::io::IsTerminal;
+        if !std::io::stdin().is_terminal() {
+            anyhow::bail!("--interactive needs a terminal to prompt on (stdin isn't one)");
+        }
+        ctx.interactive = true;
+    }
+    ctx.max_matches_per_file = cli.max_matches_per_file.map(std::num::NonZeroUsize::get);
+    if let Some(jobs) = cli.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs.get())
+            .build_global()
+            .context("Failed to set up the --jobs thread pool")?;
+    }
+    fill_default_paths(&mut cli.command, cli.stdin, config_paths)?;
+    if cli.stdin {
+        use std::io::Read;
+
+        let mut source = String::new();
+        std::io::stdin()
+            .read_to_string(&mut source)
+            .context("Failed to read stdin")?;
+        ctx.stdin_source = Some(source);
+    }
+    let ctx = &*ctx;
+
+    // Run after the AST rename, as its own diff
+    let mut rename_text = None;
     match cli.command {
         Commands::AddStructField {
             paths,
@@ -1871,21 +3580,24 @@ fn main() -> Result<()> {
             output,
             apply,
         } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
             let op = Operation::AddStructField(AddStructFieldOp {
                 struct_name,
                 field_def: field,
                 position: parse_position(&position)?,
                 literal_default,
                 where_filter: cli.r#where.clone(),
+                in_fn: None,
             });
 
             execute_operation_with_state(
+                ctx,
                 &files,
                 &op,
                 apply,
                 output.as_ref(),
                 &cli.local_state,
+                cli.no_state,
                 &cli.format,
                 cli.summary,
                 cli.limit,
@@ -1899,19 +3611,22 @@ fn main() -> Result<()> {
             output,
             apply,
         } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
             let op = Operation::UpdateStructField(UpdateStructFieldOp {
                 struct_name,
                 field_def: field,
                 where_filter: cli.r#where.clone(),
+                in_fn: None,
             });
 
             execute_operation_with_state(
+                ctx,
                 &files,
                 &op,
                 apply,
                 output.as_ref(),
                 &cli.local_state,
+                cli.no_state,
                 &cli.format,
                 cli.summary,
                 cli.limit,
@@ -1926,20 +3641,23 @@ fn main() -> Result<()> {
             output,
             apply,
         } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
             let op = Operation::RemoveStructField(RemoveStructFieldOp {
                 struct_name,
                 field_name,
                 literal_only,
                 where_filter: cli.r#where.clone(),
+                in_fn: None,
             });
 
             execute_operation_with_state(
+                ctx,
                 &files,
                 &op,
                 apply,
                 output.as_ref(),
                 &cli.local_state,
+                cli.no_state,
                 &cli.format,
                 cli.summary,
                 cli.limit,
@@ -1954,20 +3672,23 @@ fn main() -> Result<()> {
             position,
             apply,
         } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
             let op = Operation::AddStructLiteralField(AddStructLiteralFieldOp {
                 struct_name,
                 field_def: field,
                 position: parse_position(&position)?,
                 struct_path: None, // Deprecated command doesn't support path resolution
+                field_path: Vec::new(),
             });
 
             execute_operation_with_state(
+                ctx,
                 &files,
                 &op,
                 apply,
                 None,
                 &cli.local_state,
+                cli.no_state,
                 &cli.format,
                 cli.summary,
                 cli.limit,
@@ -1982,20 +3703,23 @@ fn main() -> Result<()> {
             output,
             apply,
         } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
             let op = Operation::AddEnumVariant(AddEnumVariantOp {
                 enum_name,
                 variant_def: variant,
                 position: parse_position(&position)?,
                 where_filter: cli.r#where.clone(),
+                in_fn: None,
             });
 
             execute_operation_with_state(
+                ctx,
                 &files,
                 &op,
                 apply,
                 output.as_ref(),
                 &cli.local_state,
+                cli.no_state,
                 &cli.format,
                 cli.summary,
                 cli.limit,
@@ -2009,19 +3733,22 @@ fn main() -> Result<()> {
             output,
             apply,
         } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
             let op = Operation::UpdateEnumVariant(UpdateEnumVariantOp {
                 enum_name,
                 variant_def: variant,
                 where_filter: cli.r#where.clone(),
+                in_fn: None,
             });
 
             execute_operation_with_state(
+                ctx,
                 &files,
                 &op,
                 apply,
                 output.as_ref(),
                 &cli.local_state,
+                cli.no_state,
                 &cli.format,
                 cli.summary,
                 cli.limit,
@@ -2035,19 +3762,22 @@ fn main() -> Result<()> {
             output,
             apply,
         } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
             let op = Operation::RemoveEnumVariant(RemoveEnumVariantOp {
                 enum_name,
                 variant_name,
                 where_filter: cli.r#where.clone(),
+                in_fn: None,
             });
 
             execute_operation_with_state(
+                ctx,
                 &files,
                 &op,
                 apply,
                 output.as_ref(),
                 &cli.local_state,
+                cli.no_state,
                 &cli.format,
                 cli.summary,
                 cli.limit,
@@ -2064,11 +3794,12 @@ fn main() -> Result<()> {
             validate,
             apply,
         } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
 
             // If validate mode, run validation instead of rename
             if validate {
                 validate_enum_variant_rename(
+                    ctx,
                     &files,
                     &enum_name,
                     &old_variant,
@@ -2089,11 +3820,13 @@ fn main() -> Result<()> {
                 });
 
                 execute_operation_with_state(
+                    ctx,
                     &files,
                     &op,
                     apply,
                     None,
                     &cli.local_state,
+                    cli.no_state,
                     &cli.format,
                     cli.summary,
                     cli.limit,
@@ -2110,11 +3843,11 @@ fn main() -> Result<()> {
             validate,
             apply,
         } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
 
             // If validate mode, run validation instead of rename
             if validate {
-                validate_function_rename(&files, &old_name, function_path.as_deref())?;
+                validate_function_rename(ctx, &files, &old_name, function_path.as_deref())?;
             } else {
                 // Parse edit mode
                 let edit_mode = edit_mode
@@ -2126,14 +3859,18 @@ fn main() -> Result<()> {
                     new_name,
                     function_path,
                     edit_mode,
+                    receiver_type: None,
+                    keep_link_name: false,
                 });
 
                 execute_operation_with_state(
+                    ctx,
                     &files,
                     &op,
                     apply,
                     None,
                     &cli.local_state,
+                    cli.no_state,
                     &cli.format,
                     cli.summary,
                     cli.limit,
@@ -2147,19 +3884,89 @@ fn main() -> Result<()> {
             to,
             enum_path,
             function_path,
+            receiver_type,
             kind,
             node_type,
             edit_mode,
             validate,
+            flag_risky,
+            require_qualified,
+            serde_aware,
+            update_serde_name,
+            keep_link_name,
+            include_strings,
+            include_comments_text,
             apply,
-        } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+        } => 'rename: {
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
+            if include_strings || include_comments_text {
+                if ctx.stdin_mode() {
+                    anyhow::bail!(
+                        "--include-strings and --include-comments-text don't work with --stdin"
+                    );
+                }
+                rename_text = Some((
+                    rs_hack::commands::rename_text::RenameTextArgs {
+                        name: name.clone(),
+                        to: to.clone(),
+                        files: files.clone(),
+                        strings: include_strings,
+                        comments: include_comments_text,
+                    },
+                    apply,
+                ));
+            }
+
+            if flag_risky {
+                // What each rename path rewrites: call sites for functions/methods, paths for
+                // enum variants, or the granular node type itself
+                let risk_node_type = match (node_type.as_deref(), kind.as_deref()) {
+                    (Some(nt), _) => nt,
+                    (None, Some("identifier")) => "identifier",
+                    _ if name.contains("::") => "enum-usage",
+                    _ => "function-call",
+                };
+                let report = rs_hack::commands::risk::run(&rs_hack::commands::risk::RiskArgs {
+                    files: files.clone(),
+                    node_types: vec![risk_node_type.to_string()],
+                    name: Some(name.clone()),
+                    content_filter: None,
+                    exclude_names: Vec::new(),
+                    exclude_in: Vec::new(),
+                    verbose: ctx.verbose.clone(),
+                })?;
+                rs_hack::commands::risk::render(&report);
+            }
 
             // Parse edit mode
             let edit_mode = edit_mode
                 .parse::<EditMode>()
                 .map_err(|e| anyhow::anyhow!("{}", e))?;
 
+            if receiver_type.is_some() {
+                let op = Operation::RenameFunction(RenameFunctionOp {
+                    old_name: name,
+                    new_name: to,
+                    function_path,
+                    edit_mode,
+                    receiver_type,
+                    keep_link_name: false,
+                });
+                execute_operation_with_state(
+                    ctx,
+                    &files,
+                    &op,
+                    apply,
+                    None,
+                    &cli.local_state,
+                    cli.no_state,
+                    &cli.format,
+                    cli.summary,
+                    cli.limit,
+                )?;
+                break 'rename;
+            }
+
             // Handle granular renaming with --node-type (for expression-level nodes)
             // For these, delegate to Transform with Replace action
             let granular_types = [
@@ -2181,18 +3988,23 @@ fn main() -> Result<()> {
                     name_filter: Some(name),
                     content_filter: None,
                     action: TransformAction::Replace { with: to },
+                    exclude_names: Vec::new(),
+                    exclude_in: Vec::new(),
+                    name_regex: None,
                 });
                 execute_operation_with_state(
+                    ctx,
                     &files,
                     &op,
                     apply,
                     None,
                     &cli.local_state,
+                    cli.no_state,
                     &cli.format,
                     cli.summary,
                     cli.limit,
                 )?;
-                return Ok(());
+                break 'rename;
             }
 
             // Handle --kind expansion for semantic grouping
@@ -2200,8 +4012,9 @@ fn main() -> Result<()> {
                 let expanded = expand_kind_to_node_types(k);
                 if expanded.is_empty() {
                     anyhow::bail!(
-                        "Unknown kind '{}'. Valid kinds: struct, function, enum, match, identifier, type, macro, const, trait, mod, use",
-                        k
+                        "Unknown kind '{}'. Valid kinds: {} (see: rs-hack node-types)",
+                        k,
+                        KINDS.join(", ")
                     );
                 }
 
@@ -2216,25 +4029,51 @@ fn main() -> Result<()> {
                         name_filter: Some(name),
                         content_filter: None,
                         action: TransformAction::Replace { with: to },
+                        exclude_names: Vec::new(),
+                        exclude_in: Vec::new(),
+                        name_regex: None,
                     });
                     execute_operation_with_state(
+                        ctx,
                         &files,
                         &op,
                         apply,
                         None,
                         &cli.local_state,
+                        cli.no_state,
                         &cli.format,
                         cli.summary,
                         cli.limit,
                     )?;
-                    return Ok(());
+                    break 'rename;
+                } else if k == "mod" {
+                    let result = rs_hack::commands::rename_mod::run(
+                        &rs_hack::commands::rename_mod::RenameModArgs {
+                            name,
+                            to: to.clone(),
+                            files,
+                        },
+                    )?;
+                    render_rename_mod_result(
+                        ctx,
+                        result,
+                        &to,
+                        apply && !ctx.verify_idempotent,
+                        (!cli.no_state).then_some(cli.local_state),
+                        &cli.format,
+                    )?;
+                    break 'rename;
                 } else {
                     anyhow::bail!(
-                        "Rename with --kind is only supported for 'function' and 'identifier' kinds. For other kinds, use --node-type."
+                        "Rename with --kind is only supported for 'function', 'identifier' and 'mod' kinds. For other kinds, use --node-type."
                     );
                 }
             }
 
+            if serde_aware && !name.contains("::") {
+                anyhow::bail!("--serde-aware only applies to field renames (Struct::field)");
+            }
+
             // Auto-detect: Check if name contains :: for enum variant syntax
             if name.contains("::") {
                 // Parse as enum variant (EnumName::VariantName)
@@ -2245,16 +4084,56 @@ fn main() -> Result<()> {
 
                 let enum_name = parts[0];
                 let old_variant = parts[1];
+                require_qualified_path(
+                    require_qualified,
+                    enum_path.as_deref(),
+                    "--enum-path",
+                    &format!("crate::types::{}", enum_name),
+                )?;
+
+                // Struct::field (no enum by that name) renames a field
+                if !target_exists(ctx, &files, enum_name, Some("enum"))?
+                    && (target_exists(ctx, &files, enum_name, Some("struct"))?
+                        || target_exists(ctx, &files, enum_name, Some("union"))?)
+                {
+                    if validate {
+                        return validate_field_rename(ctx, &files, enum_name, old_variant);
+                    }
+                    let op = Operation::RenameStructField(RenameStructFieldOp {
+                        struct_name: enum_name.to_string(),
+                        old_name: old_variant.to_string(),
+                        new_name: to,
+                        serde_aware,
+                        update_serde_name,
+                    });
+                    execute_operation_with_state(
+                        ctx,
+                        &files,
+                        &op,
+                        apply,
+                        None,
+                        &cli.local_state,
+                        cli.no_state,
+                        &cli.format,
+                        cli.summary,
+                        cli.limit,
+                    )?;
+                    break 'rename;
+                }
+                if serde_aware {
+                    anyhow::bail!("--serde-aware only applies to field renames (Struct::field)");
+                }
 
                 // Check if the enum exists
-                if !target_exists(&files, enum_name, Some("enum"))? {
-                    show_target_hints(&files, enum_name, "enum", &paths)?;
-                    return Ok(());
+                if !target_exists(ctx, &files, enum_name, Some("enum"))? {
+                    show_target_hints(ctx, &files, enum_name, "enum", &paths)?;
+                    break 'rename;
                 }
 
                 // If validate mode, run validation instead of rename
                 if validate {
                     validate_enum_variant_rename(
+                        ctx,
                         &files,
                         enum_name,
                         old_variant,
@@ -2270,11 +4149,13 @@ fn main() -> Result<()> {
                     });
 
                     execute_operation_with_state(
+                        ctx,
                         &files,
                         &op,
                         apply,
                         None,
                         &cli.local_state,
+                        cli.no_state,
                         &cli.format,
                         cli.summary,
                         cli.limit,
@@ -2284,21 +4165,27 @@ fn main() -> Result<()> {
                 // No :: syntax - need to discover if it's a function or enum variant
                 // First check if it exists as any kind of function (standalone, impl-method,
                 // trait-method)
-                let is_function = target_exists(&files, &name, Some("function"))?
-                    || target_exists(&files, &name, Some("impl-method"))?
-                    || target_exists(&files, &name, Some("trait-method"))?;
+                let is_function = target_exists(ctx, &files, &name, Some("function"))?
+                    || target_exists(ctx, &files, &name, Some("impl-method"))?
+                    || target_exists(ctx, &files, &name, Some("trait-method"))?
+                    || target_exists(ctx, &files, &name, Some("extern-fn"))?;
 
                 // Check if any enum has a variant with this name
                 let mut found_as_enum_variant = false;
                 let mut enum_candidates: Vec<String> = Vec::new();
 
                 for file in &files {
-                    let content = std::fs::read_to_string(file)
+                    let content = ctx
+                        .read_source(file)
                         .context(format!("Failed to read file: {:?}", file))?;
 
-                    let editor = match RustEditor::new(&content) {
+                    let editor = match RustEditor::with_scope(&content, ctx.scope.clone()) {
                         Ok(e) => e,
-                        Err(_) => continue, // Skip unparseable files during discovery
+                        Err(e) => {
+                            // Skip unparseable files during discovery
+                            ctx.verbose.skipped(file, &e);
+                            continue;
+                        }
                     };
                     // Search for enums
                     let enum_results = editor.inspect(Some("enum"), None, None, false)?;
@@ -2334,22 +4221,32 @@ fn main() -> Result<()> {
                     );
                 } else if is_function {
                     // Rename function
+                    require_qualified_path(
+                        require_qualified,
+                        function_path.as_deref(),
+                        "--function-path",
+                        &format!("crate::utils::{}", name),
+                    )?;
                     if validate {
-                        validate_function_rename(&files, &name, function_path.as_deref())?;
+                        validate_function_rename(ctx, &files, &name, function_path.as_deref())?;
                     } else {
                         let op = Operation::RenameFunction(RenameFunctionOp {
                             old_name: name,
                             new_name: to,
                             function_path,
                             edit_mode,
+                            receiver_type: None,
+                            keep_link_name,
                         });
 
                         execute_operation_with_state(
+                            ctx,
                             &files,
                             &op,
                             apply,
                             None,
                             &cli.local_state,
+                            cli.no_state,
                             &cli.format,
                             cli.summary,
                             cli.limit,
@@ -2360,9 +4257,16 @@ fn main() -> Result<()> {
                     if enum_candidates.len() == 1 {
                         // Only one enum has this variant - can proceed
                         let enum_name = &enum_candidates[0];
+                        require_qualified_path(
+                            require_qualified,
+                            enum_path.as_deref(),
+                            "--enum-path",
+                            &format!("crate::types::{}", enum_name),
+                        )?;
 
                         if validate {
                             validate_enum_variant_rename(
+                                ctx,
                                 &files,
                                 enum_name,
                                 &name,
@@ -2378,11 +4282,13 @@ fn main() -> Result<()> {
                             });
 
                             execute_operation_with_state(
+                                ctx,
                                 &files,
                                 &op,
                                 apply,
                                 None,
                                 &cli.local_state,
+                                cli.no_state,
                                 &cli.format,
                                 cli.summary,
                                 cli.limit,
@@ -2414,6 +4320,9 @@ fn main() -> Result<()> {
                             .join(" "),
                         name
                     );
+                    if ctx.stdin_mode() {
+                        anyhow::bail!("No function or enum variant named \"{}\" on stdin", name);
+                    }
                 }
             }
         }
@@ -2437,7 +4346,7 @@ fn main() -> Result<()> {
                 anyhow::bail!("--pattern is required when not using --auto-detect");
             }
 
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
             let op = Operation::AddMatchArm(AddMatchArmOp {
                 pattern: pattern.unwrap_or_default(),
                 body,
@@ -2447,6 +4356,7 @@ fn main() -> Result<()> {
             });
 
             execute_operation(
+                ctx,
                 &files,
                 &op,
                 apply,
@@ -2464,7 +4374,7 @@ fn main() -> Result<()> {
             function,
             apply,
         } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
             let op = Operation::UpdateMatchArm(UpdateMatchArmOp {
                 pattern,
                 new_body: body,
@@ -2472,6 +4382,7 @@ fn main() -> Result<()> {
             });
 
             execute_operation(
+                ctx,
                 &files,
                 &op,
                 apply,
@@ -2488,13 +4399,14 @@ fn main() -> Result<()> {
             function,
             apply,
         } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
             let op = Operation::RemoveMatchArm(RemoveMatchArmOp {
                 pattern,
                 function_name: function,
             });
 
             execute_operation(
+                ctx,
                 &files,
                 &op,
                 apply,
@@ -2505,7 +4417,29 @@ fn main() -> Result<()> {
             )?;
         }
 
-        Commands::Batch { spec, apply } => {
+        Commands::Batch {
+            spec,
+            stdin_batch,
+            apply,
+        } => {
+            if stdin_batch {
+                let opts = rs_hack::commands::stdin_batch::ServeOpts {
+                    exclude: cli.exclude.clone(),
+                    collect,
+                    local_state: cli.local_state,
+                    no_state: cli.no_state,
+                    limit: cli.limit,
+                    max_matches_per_file: ctx.max_matches_per_file,
+                };
+                rs_hack::commands::stdin_batch::serve(
+                    std::io::stdin().lock(),
+                    std::io::stdout().lock(),
+                    &opts,
+                )?;
+                return Ok(());
+            }
+
+            let spec = spec.expect("--spec is required without --stdin-batch");
             let content =
                 std::fs::read_to_string(&spec).context("Failed to read batch spec file")?;
 
@@ -2521,7 +4455,16 @@ fn main() -> Result<()> {
                     .context("Failed to parse batch spec (tried both JSON and YAML)")?
             };
 
-            execute_batch(&batch, apply, &cli.exclude)?;
+            execute_batch(
+                ctx,
+                &batch,
+                apply,
+                &cli.exclude,
+                &collect,
+                &cli.format,
+                cli.summary,
+                (!cli.no_state).then_some(cli.local_state),
+            )?;
         }
 
         Commands::Find {
@@ -2535,12 +4478,46 @@ fn main() -> Result<()> {
             include_comments,
             format,
             context,
+            arity,
+            min_arity,
+            max_arity,
+            exclude_self,
+            required_only,
+            default_only,
+            exclude_names,
+            exclude_in,
+            duplicate_variants,
+            field_method_collisions,
+            in_macro_call,
+            name_regex,
+            callers,
+            unused,
+            duplicates,
+            highlight,
+            count,
         } => {
             use operations::InspectResult;
 
-            let args = rs_hack::commands::find::FindArgs {
+            let highlight = highlight && rs_hack::color::enabled();
+            let paint = |result: &InspectResult| {
+                if highlight {
+                    let word = rs_hack::commands::find::highlight_target(
+                        &result.identifier,
+                        name.as_deref(),
+                    );
+                    rs_hack::commands::find::highlight_name(&result.snippet, word)
+                } else {
+                    result.snippet.clone()
+                }
+            };
+
+            let args = rs_hack::commands::find::FindArgs {
                 paths: paths.clone(),
                 exclude: cli.exclude.clone(),
+                collect: collect.clone(),
+                verbose: ctx.verbose.clone(),
+                strict: ctx.strict,
+                scope: ctx.scope.clone(),
                 kind: kind.clone(),
                 node_type: node_type.clone(),
                 name: name.clone(),
@@ -2549,9 +4526,239 @@ fn main() -> Result<()> {
                 field_name: field_name.clone(),
                 include_comments,
                 context,
+                arity,
+                min_arity,
+                max_arity,
+                exclude_self,
+                required_only,
+                default_only,
+                exclude_names,
+                exclude_in,
+                duplicate_variants,
+                field_method_collisions,
+                in_macro_call: in_macro_call.clone(),
+                name_regex,
+                callers: callers.clone(),
+                unused,
+                duplicates,
             };
 
-            let result = rs_hack::commands::find::run(&args)?;
+            let jsonl = format == "jsonl";
+            if jsonl && count {
+                anyhow::bail!("--count prints totals; use --format json with it, not jsonl");
+            }
+            if count && (format == "sarif" || format == "github") {
+                anyhow::bail!("--format {} is not supported with --count", format);
+            }
+
+            let node_mode = field_name.is_none()
+                && !duplicate_variants
+                && !field_method_collisions
+                && callers.is_none()
+                && !unused
+                && !duplicates;
+            let result = if jsonl && node_mode {
+                // One compact object per match, flushed file by file, so consumers can start
+                // before the scan ends
+                use std::io::Write;
+                let mut stdout = std::io::stdout().lock();
+                let mut streamed = 0;
+                let skipped = rs_hack::commands::find::stream_nodes(&args, |results| {
+                    streamed += results.len();
+                    for result in &results {
+                        serde_json::to_writer(&mut stdout, result)?;
+                        writeln!(stdout)?;
+                    }
+                    stdout.flush()?;
+                    Ok(())
+                })?;
+                if streamed > 0 {
+                    render_skipped_matches(skipped);
+                    return Ok(());
+                }
+                // Nothing matched: fall through to the hints, which only go to stderr
+                rs_hack::commands::find::FindResult::Nodes {
+                    matches: Vec::new(),
+                    skipped,
+                }
+            } else {
+                rs_hack::commands::find::run(&args)?
+            };
+
+            if let rs_hack::commands::find::FindResult::DuplicateVariants { groups } = &result {
+                match format.as_str() {
+                    "json" => println!("{}", serde_json::to_string_pretty(groups)?),
+                    "jsonl" => print_jsonl(groups)?,
+                    "sarif" | "github" => anyhow::bail!(
+                        "--format {} is not supported with --duplicate-variants",
+                        format
+                    ),
+                    _ if groups.is_empty() => println!("No duplicate variants found."),
+                    _ => {
+                        for group in groups {
+                            let sites: Vec<String> = group
+                                .variants
+                                .iter()
+                                .map(|v| format!("{} (line {})", v.name, v.line))
+                                .collect();
+                            println!(
+                                "{}: {} has identical variants {}",
+                                group.file_path,
+                                group.enum_name,
+                                sites.join(", ")
+                            );
+                            println!("    fields: {}", group.fields);
+                        }
+                    }
+                }
+                return Ok(());
+            }
+
+            if let rs_hack::commands::find::FindResult::DuplicateFunctions { groups } = &result {
+                match format.as_str() {
+                    "json" => println!("{}", serde_json::to_string_pretty(groups)?),
+                    "jsonl" => print_jsonl(groups)?,
+                    "sarif" | "github" => {
+                        anyhow::bail!("--format {} is not supported with --duplicates", format)
+                    }
+                    _ if groups.is_empty() => println!("No duplicate functions found."),
+                    _ => {
+                        for group in groups {
+                            println!(
+                                "{} functions with the same {}-token body:",
+                                group.functions.len(),
+                                group.tokens
+                            );
+                            for site in &group.functions {
+                                println!(
+                                    "  {}:{}  {} ({})",
+                                    site.file_path, site.line, site.name, site.node_type
+                                );
+                            }
+                        }
+                        let total: usize = groups.iter().map(|g| g.functions.len()).sum();
+                        println!(
+                            "\n{} duplicate group{} ({} functions)",
+                            groups.len(),
+                            if groups.len() == 1 { "" } else { "s" },
+                            total
+                        );
+                    }
+                }
+                return Ok(());
+            }
+
+            if let rs_hack::commands::find::FindResult::Callers { groups } = &result {
+                let callee = callers.as_deref().unwrap_or_default();
+                match format.as_str() {
+                    "json" => println!("{}", serde_json::to_string_pretty(groups)?),
+                    "jsonl" => print_jsonl(groups)?,
+                    "sarif" | "github" => {
+                        anyhow::bail!("--format {} is not supported with --callers", format)
+                    }
+                    _ if groups.is_empty() => println!("No calls to '{}' found.", callee),
+                    _ => {
+                        for group in groups {
+                            println!(
+                                "{} ({} call{})",
+                                group.caller,
+                                group.count,
+                                if group.count == 1 { "" } else { "s" }
+                            );
+                            for call in &group.calls {
+                                println!(
+                                    "  {}:{} (in {})  {}",
+                                    group.file_path, call.line, group.caller, call.code
+                                );
+                            }
+                        }
+                        let total: usize = groups.iter().map(|g| g.count).sum();
+                        println!(
+                            "\n{} call{} to '{}' from {} caller{}",
+                            total,
+                            if total == 1 { "" } else { "s" },
+                            callee,
+                            groups.len(),
+                            if groups.len() == 1 { "" } else { "s" }
+                        );
+                    }
+                }
+                return Ok(());
+            }
+
+            if let rs_hack::commands::find::FindResult::Unused { candidates } = &result {
+                match format.as_str() {
+                    "json" => println!("{}", serde_json::to_string_pretty(candidates)?),
+                    "jsonl" => print_jsonl(candidates)?,
+                    "sarif" | "github" => {
+                        anyhow::bail!("--format {} is not supported with --unused", format)
+                    }
+                    _ if candidates.is_empty() => println!("No unused private items found."),
+                    _ => {
+                        for candidate in candidates {
+                            println!(
+                                "{}:{}: {} {} (candidate)",
+                                candidate.file_path,
+                                candidate.line,
+                                candidate.item_kind,
+                                candidate.name
+                            );
+                        }
+                        println!(
+                            "\n{} candidate{} never referenced by name outside their definition",
+                            candidates.len(),
+                            if candidates.len() == 1 { "" } else { "s" }
+                        );
+                        println!(
+                            "   Check before deleting: uses through macros, trait dispatch or other \
+                             crates aren't seen."
+                        );
+                    }
+                }
+                return Ok(());
+            }
+
+            if let rs_hack::commands::find::FindResult::FieldMethodCollisions { collisions } =
+                &result
+            {
+                match format.as_str() {
+                    "json" => println!("{}", serde_json::to_string_pretty(collisions)?),
+                    "jsonl" => print_jsonl(collisions)?,
+                    "sarif" | "github" => anyhow::bail!(
+                        "--format {} is not supported with --field-method-collisions",
+                        format
+                    ),
+                    _ if collisions.is_empty() => {
+                        println!("No field/method name collisions found.")
+                    }
+                    _ => {
+                        for collision in collisions {
+                            println!(
+                                "{}:{}: field {}.{} shares its name with:",
+                                collision.file_path,
+                                collision.line,
+                                collision.type_name,
+                                collision.field
+                            );
+                            for method in &collision.methods {
+                                let owner = method.trait_name.as_ref().map_or_else(
+                                    || collision.type_name.clone(),
+                                    |t| format!("<{} as {}>", collision.type_name, t),
+                                );
+                                println!(
+                                    "    {}::{} ({}:{}){}",
+                                    owner,
+                                    collision.field,
+                                    method.file_path,
+                                    method.line,
+                                    if method.is_getter { " - getter" } else { "" }
+                                );
+                            }
+                        }
+                    }
+                }
+                return Ok(());
+            }
 
             // Field-mode rendering
             if let rs_hack::commands::find::FindResult::Field {
@@ -2566,6 +4773,13 @@ fn main() -> Result<()> {
                     .as_ref()
                     .expect("field_name set when FindResult::Field");
 
+                if format == "sarif" || format == "github" {
+                    anyhow::bail!("--format {} is not supported with --field-name", format);
+                }
+                if jsonl {
+                    return print_jsonl(all_locations);
+                }
+
                 if all_locations.is_empty() {
                     println!("No occurrences of field '{}' found.", field);
                     return Ok(());
@@ -2669,11 +4883,99 @@ fn main() -> Result<()> {
             }
 
             // Node-mode rendering
-            let all_results: Vec<InspectResult> = match result {
-                rs_hack::commands::find::FindResult::Nodes { matches } => matches,
-                rs_hack::commands::find::FindResult::Field { .. } => unreachable!("handled above"),
+            let (all_results, skipped): (Vec<InspectResult>, usize) = match result {
+                rs_hack::commands::find::FindResult::Nodes { matches, skipped } => {
+                    render_skipped_matches(skipped);
+                    (matches, skipped)
+                }
+                rs_hack::commands::find::FindResult::Field { .. }
+                | rs_hack::commands::find::FindResult::DuplicateVariants { .. }
+                | rs_hack::commands::find::FindResult::FieldMethodCollisions { .. }
+                | rs_hack::commands::find::FindResult::Callers { .. }
+                | rs_hack::commands::find::FindResult::Unused { .. }
+                | rs_hack::commands::find::FindResult::DuplicateFunctions { .. } => {
+                    unreachable!("handled above")
+                }
             };
 
+            // Counts only: always emitted (zero included), without snippets or hints
+            if count {
+                let summary = rs_hack::commands::find::FindSummary::from_results(&all_results);
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&summary)?);
+                } else {
+                    let label = name
+                        .as_deref()
+                        .or(node_type.as_deref())
+                        .or(kind.as_deref())
+                        .unwrap_or("all nodes");
+                    println!(
+                        "{}: {} match{} across {} file{}",
+                        label,
+                        summary.total,
+                        if summary.total == 1 { "" } else { "es" },
+                        summary.by_file.len(),
+                        if summary.by_file.len() == 1 { "" } else { "s" }
+                    );
+                    let mut by_file: Vec<(&String, &usize)> = summary.by_file.iter().collect();
+                    by_file.sort_by_key(|&(_, n)| std::cmp::Reverse(*n));
+                    for (file, n) in by_file {
+                        println!("  {:>6}  {}", n, file);
+                    }
+                }
+                return Ok(());
+            }
+
+            // How many nodes of each type: a quick picture of an unfamiliar crate, most common
+            // type first
+            if format == "count-by-type" {
+                let summary = rs_hack::commands::find::FindSummary::from_results(&all_results);
+                println!(
+                    "{} node{} of {} type{} across {} file{}",
+                    summary.total,
+                    if summary.total == 1 { "" } else { "s" },
+                    summary.by_type.len(),
+                    if summary.by_type.len() == 1 { "" } else { "s" },
+                    summary.by_file.len(),
+                    if summary.by_file.len() == 1 { "" } else { "s" }
+                );
+                for (node_type, n) in summary.types_by_count() {
+                    println!("  {:>6}  {}", n, node_type);
+                }
+                return Ok(());
+            }
+
+            // SARIF goes to code-scanning tools: always emit a log, even an empty one, and skip
+            // the human-oriented hints below
+            if format == "sarif" {
+                let log = rs_hack::sarif::from_find_results(&all_results, name.as_deref());
+                println!("{}", serde_json::to_string_pretty(&log)?);
+                return Ok(());
+            }
+            if format == "github" {
+                for annotation in rs_hack::github::from_find_results(&all_results, name.as_deref())
+                {
+                    println!("{}", annotation);
+                }
+                return Ok(());
+            }
+
+            if all_results.is_empty()
+                && format == "snippets"
+                && let Some(macro_name) = &in_macro_call
+            {
+                println!("No matching identifiers in {}! invocations.", macro_name);
+                return Ok(());
+            }
+
+            // Counts for dashboards: like SARIF, always emitted (zero counts included) and
+            // without the hints
+            if format == "json" && cli.summary {
+                let summary = rs_hack::commands::find::FindSummary::from_results(&all_results);
+                println!("{}", serde_json::to_string_pretty(&summary)?);
+                return Ok(());
+            }
+
             // Hints system: If we found nothing with a specific node-type, check if other types
             // have matches
             if all_results.is_empty()
@@ -2733,15 +5035,18 @@ fn main() -> Result<()> {
                 }
             }
 
-            // Fallback: If we still found nothing with a name filter, do a text search
+            // Fallback: If we still found nothing with a name filter, do a text search. Skipped
+            // matches were already reported, and their lines aren't non-AST text.
             if all_results.is_empty()
+                && skipped == 0
                 && let Some(search_name) = name.as_deref()
             {
                 let mut text_matches: Vec<(String, usize)> = Vec::new();
-                let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+                let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
 
                 for file in &files {
-                    let content = std::fs::read_to_string(file)
+                    let content = ctx
+                        .read_source(file)
                         .context(format!("Failed to read file: {:?}", file))?;
 
                     let count = content
@@ -2799,6 +5104,7 @@ fn main() -> Result<()> {
                 "json" => {
                     println!("{}", serde_json::to_string_pretty(&all_results)?);
                 }
+                "jsonl" => print_jsonl(&all_results)?,
                 "locations" => {
                     for result in &all_results {
                         println!(
@@ -2808,6 +5114,9 @@ fn main() -> Result<()> {
                     }
                 }
                 "snippets" => {
+                    // Sources read for --context, by path
+                    let mut sources = std::collections::HashMap::new();
+
                     // If searching all types (node_type is None), group results by type
                     if node_type.is_none() && !all_results.is_empty() {
                         use std::collections::HashMap;
@@ -2850,7 +5159,7 @@ fn main() -> Result<()> {
 
                             println!(
                                 "{}{}:",
-                                type_name,
+                                rs_hack::color::paint(rs_hack::color::BOLD, &type_name),
                                 if count > 1 {
                                     format!(
                                         " ({} match{})",
@@ -2863,13 +5172,8 @@ fn main() -> Result<()> {
                             );
 
                             for result in results {
-                                println!(
-                                    "  // {}:{}:{} - {}",
-                                    result.file_path,
-                                    result.location.line,
-                                    result.location.column,
-                                    result.identifier
-                                );
+                                print_context(result, context, true, "  ", &mut sources);
+                                println!("  {}", match_header(result));
                                 // Show preceding comment if present
                                 if let Some(ref comment) = result.preceding_comment {
                                     // Indent comment
@@ -2878,9 +5182,10 @@ fn main() -> Result<()> {
                                     }
                                 }
                                 // Indent snippet
-                                for line in result.snippet.lines() {
+                                for line in paint(result).lines() {
                                     println!("  {}", line);
                                 }
+                                print_context(result, context, false, "  ", &mut sources);
                                 println!();
                             }
                         }
@@ -2941,43 +5246,26 @@ fn main() -> Result<()> {
                         // Standard non-grouped output
                         let mut prev_file: Option<&str> = None;
                         for result in &all_results {
-                            // Context lines (--context N): show N raw lines before the snippet
-                            if let Some(n) = context
-                                && n > 0
-                            {
-                                // Separator between matches (grep-B style)
-                                if prev_file.is_some() {
-                                    println!("--");
-                                }
-                                // Read the file and print N lines before the match
-                                if let Ok(content) = std::fs::read_to_string(&result.file_path) {
-                                    let lines: Vec<&str> = content.lines().collect();
-                                    let match_line = result.location.line; // already 0-indexed
-                                    let start = match_line.saturating_sub(n);
-                                    for (i, line) in lines[start..match_line].iter().enumerate() {
-                                        println!("{}: {}", start + i + 1, line);
-                                    }
-                                }
+                            // Separator between matches (grep -C style)
+                            if context.is_some_and(|n| n > 0) && prev_file.is_some() {
+                                println!("--");
                             }
-                            println!(
-                                "// {}:{}:{} - {}",
-                                result.file_path,
-                                result.location.line,
-                                result.location.column,
-                                result.identifier
-                            );
+                            print_context(result, context, true, "", &mut sources);
+                            println!("{}", match_header(result));
                             // Show preceding comment if present
                             if let Some(ref comment) = result.preceding_comment {
                                 println!("{}", comment);
                             }
-                            println!("{}\n", result.snippet);
+                            println!("{}", paint(result));
+                            print_context(result, context, false, "", &mut sources);
+                            println!();
                             prev_file = Some(&result.file_path);
                         }
                     }
                 }
                 _ => {
                     anyhow::bail!(
-                        "Unknown format: {}. Use 'json', 'locations', or 'snippets'",
+                        "Unknown format: {}. Use 'json', 'jsonl', 'locations', 'snippets', 'sarif', 'github' or 'count-by-type'",
                         format
                     );
                 }
@@ -3024,6 +5312,10 @@ fn main() -> Result<()> {
             let args = FindArgs {
                 paths,
                 exclude: cli.exclude.clone(),
+                collect,
+                verbose: ctx.verbose.clone(),
+                strict: ctx.strict,
+                scope: ctx.scope.clone(),
                 node_type: Some("trait-impl".to_string()),
                 name: Some(r#trait.clone()),
                 include_comments: false,
@@ -3032,7 +5324,7 @@ fn main() -> Result<()> {
 
             let result = rs_hack::commands::find::run(&args)?;
             let matches = match result {
-                rs_hack::commands::find::FindResult::Nodes { matches } => matches,
+                rs_hack::commands::find::FindResult::Nodes { matches, .. } => matches,
                 _ => vec![],
             };
 
@@ -3053,20 +5345,195 @@ fn main() -> Result<()> {
         }
 
         Commands::MatchAudit { paths, r#enum } => {
-            let result = rs_hack::commands::match_audit::run(&paths, &r#enum, &cli.exclude)?;
+            let result =
+                rs_hack::commands::match_audit::run(&paths, &r#enum, &cli.exclude, &collect)?;
             rs_hack::commands::match_audit::render(&result);
         }
 
+        Commands::SyncMatch {
+            paths,
+            r#enum,
+            function,
+            body,
+            prune,
+            apply,
+        } => {
+            let report = rs_hack::commands::sync_match::run(
+                &paths,
+                &r#enum,
+                function.as_deref(),
+                &cli.exclude,
+                &collect,
+            )?;
+            rs_hack::commands::sync_match::render(&report, prune);
+
+            if rs_hack::commands::sync_match::has_changes(&report, prune) {
+                println!();
+                let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
+                let op = rs_hack::commands::sync_match::operation(&report, function, body, prune);
+                execute_operation_with_state(
+                    ctx,
+                    &files,
+                    &op,
+                    apply,
+                    None,
+                    &cli.local_state,
+                    cli.no_state,
+                    &cli.format,
+                    cli.summary,
+                    cli.limit,
+                )?;
+            }
+        }
+
+        Commands::Move {
+            paths,
+            name,
+            from,
+            to,
+            apply,
+        } => {
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
+            let result =
+                rs_hack::commands::move_item::run(&rs_hack::commands::move_item::MoveArgs {
+                    name,
+                    from,
+                    to,
+                    files,
+                })?;
+            render_move_result(
+                ctx,
+                result,
+                apply && !ctx.verify_idempotent,
+                (!cli.no_state).then_some(cli.local_state),
+                &cli.format,
+            )?;
+        }
+
+        Commands::InlineFunction { paths, name, apply } => {
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
+            let result =
+                rs_hack::commands::inline_fn::run(&rs_hack::commands::inline_fn::InlineArgs {
+                    name: name.clone(),
+                    files,
+                })?;
+            render_inline_result(
+                ctx,
+                result,
+                &name,
+                apply && !ctx.verify_idempotent,
+                (!cli.no_state).then_some(cli.local_state),
+                &cli.format,
+            )?;
+        }
+
+        Commands::AlignFields {
+            paths,
+            name,
+            in_fn,
+            apply,
+        } => {
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
+            let op = Operation::AlignStructFields(operations::AlignStructFieldsOp {
+                struct_name: name,
+                in_fn,
+            });
+
+            execute_operation_with_state(
+                ctx,
+                &files,
+                &op,
+                apply,
+                None,
+                &cli.local_state,
+                cli.no_state,
+                &cli.format,
+                cli.summary,
+                cli.limit,
+            )?;
+        }
+
         Commands::DocCoverage { paths, fields } => {
-            let result = rs_hack::commands::doc_coverage::run(&paths, fields, &cli.exclude)?;
+            let result =
+                rs_hack::commands::doc_coverage::run(&paths, fields, &cli.exclude, &collect)?;
             rs_hack::commands::doc_coverage::render(&result);
         }
 
+        Commands::Impact {
+            paths,
+            type_name,
+            format,
+        } => {
+            let report =
+                rs_hack::commands::impact::run(&paths, &type_name, &cli.exclude, &collect)?;
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+                "text" => rs_hack::commands::impact::render(&report),
+                other => anyhow::bail!("Unknown format '{}'. Use \"text\" or \"json\"", other),
+            }
+        }
+
+        Commands::Complexity {
+            paths,
+            threshold,
+            sort,
+            format,
+        } => {
+            let report = rs_hack::commands::complexity::run(
+                &paths,
+                threshold,
+                &sort,
+                &cli.exclude,
+                &collect,
+            )?;
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+                "text" => rs_hack::commands::complexity::render(&report),
+                other => anyhow::bail!("Unknown format '{}'. Use \"text\" or \"json\"", other),
+            }
+        }
+
+        Commands::Stats { paths, format } => {
+            let report = rs_hack::commands::stats::run(&paths, &cli.exclude, &collect)?;
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+                "text" => rs_hack::commands::stats::render(&report),
+                other => anyhow::bail!("Unknown format '{}'. Use \"text\" or \"json\"", other),
+            }
+        }
+
         Commands::Summary { path } => {
             let result = rs_hack::commands::summary::run(&path)?;
             rs_hack::commands::summary::render(&result);
         }
 
+        Commands::Watch { debounce, command } => {
+            run_watch(
+                std::time::Duration::from_millis(debounce),
+                &command,
+                config_paths,
+            )?;
+        }
+
+        Commands::Debug { file, line, format } => {
+            let report =
+                rs_hack::commands::debug::run(&rs_hack::commands::debug::DebugArgs { file, line })?;
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+                "text" => rs_hack::commands::debug::render(&report),
+                other => anyhow::bail!("Unknown format '{}'. Use \"text\" or \"json\"", other),
+            }
+        }
+
+        Commands::NodeTypes { format } => {
+            let report = rs_hack::commands::node_types::run();
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+                "text" => rs_hack::commands::node_types::render(&report),
+                other => anyhow::bail!("Unknown format '{}'. Use \"text\" or \"json\"", other),
+            }
+        }
+
         Commands::AddDerive {
             paths,
             target_type,
@@ -3074,7 +5541,7 @@ fn main() -> Result<()> {
             derives,
             apply,
         } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
             let derive_vec: Vec<String> =
                 derives.split(',').map(|s| s.trim().to_string()).collect();
 
@@ -3083,9 +5550,12 @@ fn main() -> Result<()> {
                 target_type,
                 derives: derive_vec,
                 where_filter: cli.r#where.clone(),
+                derive_order: None,
+                edit_mode: EditMode::default(),
             });
 
             execute_operation(
+                ctx,
                 &files,
                 &op,
                 apply,
@@ -3100,18 +5570,22 @@ fn main() -> Result<()> {
             paths,
             target,
             method,
+            trait_name,
             position,
             apply,
         } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
 
             let op = Operation::AddImplMethod(AddImplMethodOp {
                 target,
                 method_def: method,
                 position: parse_position(&position)?,
+                trait_name,
+                edit_mode: EditMode::default(),
             });
 
             execute_operation(
+                ctx,
                 &files,
                 &op,
                 apply,
@@ -3128,7 +5602,7 @@ fn main() -> Result<()> {
             position,
             apply,
         } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
 
             let op = Operation::AddUseStatement(AddUseStatementOp {
                 use_path,
@@ -3136,11 +5610,13 @@ fn main() -> Result<()> {
             });
 
             execute_operation_with_state(
+                ctx,
                 &files,
                 &op,
                 apply,
                 None,
                 &cli.local_state,
+                cli.no_state,
                 &cli.format,
                 cli.summary,
                 cli.limit,
@@ -3154,9 +5630,13 @@ fn main() -> Result<()> {
             field_name,
             field_type,
             field_value,
+            field_path,
             variant,
             method,
+            trait_name,
             derive,
+            derive_order,
+            edit_mode,
             r#use,
             match_arm,
             body,
@@ -3176,9 +5656,10 @@ fn main() -> Result<()> {
             call_type,
             content_filter,
             position,
+            in_fn,
             apply,
         } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
 
             // Handle --call operations first (add argument to function/method calls)
             if let Some(call_name) = call {
@@ -3196,11 +5677,13 @@ fn main() -> Result<()> {
                     content_filter,
                 });
                 execute_operation_with_state(
+                    ctx,
                     &files,
                     &op,
                     apply,
                     None,
                     &cli.local_state,
+                    cli.no_state,
                     &cli.format,
                     cli.summary,
                     cli.limit,
@@ -3226,11 +5709,13 @@ fn main() -> Result<()> {
                     struct_path: None,
                 });
                 execute_operation_with_state(
+                    ctx,
                     &files,
                     &op,
                     apply,
                     None,
                     &cli.local_state,
+                    cli.no_state,
                     &cli.format,
                     cli.summary,
                     cli.limit,
@@ -3241,7 +5726,8 @@ fn main() -> Result<()> {
             // Count how many operation flags are set
             let op_count = [
                 field.is_some(),
-                field_name.is_some(),
+                field_name.is_some() || field_type.is_some(),
+                field_path.is_some(),
                 variant.is_some(),
                 method.is_some(),
                 derive.is_some(),
@@ -3255,7 +5741,7 @@ fn main() -> Result<()> {
 
             if op_count == 0 {
                 anyhow::bail!(
-                    "Must specify one of: --field/--field-name, --variant, --method, --derive, --use, --match-arm, --default-rest, --base, --call, or --doc-comment"
+                    "Must specify one of: --field/--field-name/--field-type/--field-path, --variant, --method, --derive, --use, --match-arm, --default-rest, --base, --call, or --doc-comment"
                 );
             }
 
@@ -3303,11 +5789,13 @@ fn main() -> Result<()> {
                         enum_name,
                     });
                     execute_operation_with_state(
+                        ctx,
                         &files,
                         &op,
                         apply,
                         None,
                         &cli.local_state,
+                        cli.no_state,
                         &cli.format,
                         cli.summary,
                         cli.limit,
@@ -3321,11 +5809,13 @@ fn main() -> Result<()> {
                         enum_name: None,
                     });
                     execute_operation_with_state(
+                        ctx,
                         &files,
                         &op,
                         apply,
                         None,
                         &cli.local_state,
+                        cli.no_state,
                         &cli.format,
                         cli.summary,
                         cli.limit,
@@ -3345,8 +5835,9 @@ fn main() -> Result<()> {
                     let expanded = expand_kind_to_node_types(k);
                     if expanded.is_empty() {
                         anyhow::bail!(
-                            "Unknown kind '{}'. Valid kinds: struct, function, enum, match, identifier, type, macro, const, trait, mod, use",
-                            k
+                            "Unknown kind '{}'. Valid kinds: {} (see: rs-hack node-types)",
+                            k,
+                            KINDS.join(", ")
                         );
                     }
                     if expanded.len() > 1 {
@@ -3369,11 +5860,13 @@ fn main() -> Result<()> {
                     style: DocCommentStyle::Line,
                 });
                 execute_operation_with_state(
+                    ctx,
                     &files,
                     &op,
                     apply,
                     None,
                     &cli.local_state,
+                    cli.no_state,
                     &cli.format,
                     cli.summary,
                     cli.limit,
@@ -3388,11 +5881,13 @@ fn main() -> Result<()> {
                     position: parse_position(&position)?,
                 });
                 execute_operation_with_state(
+                    ctx,
                     &files,
                     &op,
                     apply,
                     None,
                     &cli.local_state,
+                    cli.no_state,
                     &cli.format,
                     cli.summary,
                     cli.limit,
@@ -3406,8 +5901,73 @@ fn main() -> Result<()> {
                 .ok_or_else(|| anyhow::anyhow!("--name is required for this operation"))?;
 
             // Auto-detect operation type and execute
-            // Handle both old --field API and new --field-name API
-            if field.is_some() || field_name.is_some() {
+            if let Some(path) = field_path {
+                // --field-path: literals only, inserted into the nested literal
+                let mut segments: Vec<String> =
+                    path.split('.').map(|s| s.trim().to_string()).collect();
+                if segments.len() < 2 || segments.iter().any(String::is_empty) {
+                    anyhow::bail!(
+                        "--field-path must name a nested field like \"inner.retries\" (got \"{}\")",
+                        path
+                    );
+                }
+                let leaf = segments.pop().unwrap_or_default();
+                let value = field_value.unwrap_or_default();
+
+                let op = Operation::AddStructLiteralField(AddStructLiteralFieldOp {
+                    struct_name: target_name.clone(),
+                    field_def: format!("{}: {}", leaf, value),
+                    position: parse_position(&position)?,
+                    struct_path: None,
+                    field_path: segments,
+                });
+                execute_operation_with_state(
+                    ctx,
+                    &files,
+                    &op,
+                    apply,
+                    None,
+                    &cli.local_state,
+                    cli.no_state,
+                    &cli.format,
+                    cli.summary,
+                    cli.limit,
+                )?;
+            } else if field_name.is_none()
+                && let Some(ftype) = &field_type
+            {
+                // --field-type alone: a tuple struct field, placed by index
+                let Some(arity) = tuple_struct_arity(ctx, &files, target_name, in_fn.as_deref())?
+                else {
+                    show_target_hints(ctx, &files, target_name, "struct", &paths)?;
+                    return Ok(());
+                };
+
+                let op = Operation::AddTupleField(AddTupleFieldOp {
+                    struct_name: target_name.clone(),
+                    field_type: ftype.clone(),
+                    position: parse_position(&position)?,
+                    literal_default: field_value,
+                    arity: Some(arity),
+                    where_filter: cli.r#where.clone(),
+                    in_fn,
+                });
+                execute_operation_with_state(
+                    ctx,
+                    &files,
+                    &op,
+                    apply,
+                    None,
+                    &cli.local_state,
+                    cli.no_state,
+                    &cli.format,
+                    cli.summary,
+                    cli.limit,
+                )?;
+            } else if field_value.is_some() && field_name.is_none() {
+                anyhow::bail!("--field-value requires --field-name, --field-type or --field-path");
+            } else if field.is_some() || field_name.is_some() {
+                // Handle both old --field API and new --field-name API
                 // Convert new API to internal format
                 let (final_field_def, final_literal_default) = if let Some(fname) = field_name {
                     // New unified API: --field-name + --field-type + --field-value
@@ -3447,7 +6007,7 @@ fn main() -> Result<()> {
                         let node_types = expand_kind_to_node_types(k);
                         let mut found = false;
                         for nt in node_types {
-                            if target_exists(&files, target_name, Some(nt))? {
+                            if target_exists(ctx, &files, target_name, Some(nt))? {
                                 found = true;
                                 break;
                             }
@@ -3455,14 +6015,15 @@ fn main() -> Result<()> {
                         found
                     } else if let Some(nt) = &node_type {
                         // Use specific node type
-                        target_exists(&files, target_name, Some(nt))?
+                        target_exists(ctx, &files, target_name, Some(nt))?
                     } else {
-                        // Default to struct
-                        target_exists(&files, target_name, Some("struct"))?
+                        // Default to struct (or union)
+                        target_exists(ctx, &files, target_name, Some("struct"))?
+                            || target_exists(ctx, &files, target_name, Some("union"))?
                     };
 
                     if !exists {
-                        show_target_hints(&files, target_name, "struct", &paths)?;
+                        show_target_hints(ctx, &files, target_name, "struct", &paths)?;
                         return Ok(());
                     }
                 }
@@ -3473,21 +6034,24 @@ fn main() -> Result<()> {
                     position: parse_position(&position)?,
                     literal_default: final_literal_default,
                     where_filter: cli.r#where.clone(),
+                    in_fn,
                 });
                 execute_operation_with_state(
+                    ctx,
                     &files,
                     &op,
                     apply,
                     None,
                     &cli.local_state,
+                    cli.no_state,
                     &cli.format,
                     cli.summary,
                     cli.limit,
                 )?;
             } else if let Some(variant_def) = variant {
                 // Adding enum variant
-                if !target_exists(&files, target_name, Some("enum"))? {
-                    show_target_hints(&files, target_name, "enum", &paths)?;
+                if !target_exists(ctx, &files, target_name, Some("enum"))? {
+                    show_target_hints(ctx, &files, target_name, "enum", &paths)?;
                     return Ok(());
                 }
 
@@ -3496,13 +6060,16 @@ fn main() -> Result<()> {
                     variant_def,
                     position: parse_position(&position)?,
                     where_filter: cli.r#where.clone(),
+                    in_fn,
                 });
                 execute_operation_with_state(
+                    ctx,
                     &files,
                     &op,
                     apply,
                     None,
                     &cli.local_state,
+                    cli.no_state,
                     &cli.format,
                     cli.summary,
                     cli.limit,
@@ -3510,8 +6077,8 @@ fn main() -> Result<()> {
             } else if let Some(method_def) = method {
                 // Adding impl method
                 // Note: impl methods target the type name, not "impl TypeName"
-                if !target_exists(&files, target_name, None)? {
-                    show_target_hints(&files, target_name, "impl", &paths)?;
+                if !target_exists(ctx, &files, target_name, None)? {
+                    show_target_hints(ctx, &files, target_name, "impl", &paths)?;
                     return Ok(());
                 }
 
@@ -3519,8 +6086,13 @@ fn main() -> Result<()> {
                     target: target_name.clone(),
                     method_def,
                     position: parse_position(&position)?,
+                    trait_name,
+                    edit_mode: edit_mode
+                        .parse::<EditMode>()
+                        .map_err(|e| anyhow::anyhow!(e))?,
                 });
                 execute_operation(
+                    ctx,
                     &files,
                     &op,
                     apply,
@@ -3532,10 +6104,10 @@ fn main() -> Result<()> {
             } else if let Some(derives) = derive {
                 // Adding derive macros
                 // Need to detect if target is struct or enum
-                let target_type = detect_target_type(&files, target_name)?;
+                let target_type = detect_target_type(ctx, &files, target_name)?;
 
                 if target_type.is_none() {
-                    show_target_hints(&files, target_name, "struct or enum", &paths)?;
+                    show_target_hints(ctx, &files, target_name, "struct or enum", &paths)?;
                     return Ok(());
                 }
 
@@ -3547,8 +6119,14 @@ fn main() -> Result<()> {
                     target_type: target_type.unwrap(),
                     derives: derive_vec,
                     where_filter: cli.r#where.clone(),
+                    derive_order: derive_order
+                        .map(|order| order.split(',').map(|s| s.trim().to_string()).collect()),
+                    edit_mode: edit_mode
+                        .parse::<EditMode>()
+                        .map_err(|e| anyhow::anyhow!(e))?,
                 });
                 execute_operation(
+                    ctx,
                     &files,
                     &op,
                     apply,
@@ -3566,6 +6144,7 @@ fn main() -> Result<()> {
             field_name,
             variant,
             method,
+            trait_name,
             derive,
             match_arm,
             function,
@@ -3577,27 +6156,50 @@ fn main() -> Result<()> {
             arg_index,
             call_type,
             content_filter,
+            in_fn,
+            unused_imports,
             apply,
         } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
 
-            // Handle --call operations first (remove argument from function/method calls)
-            if let Some(call_name) = call {
-                let idx = arg_index
-                    .ok_or_else(|| anyhow::anyhow!("--arg-index is required when using --call"))?;
-
-                let op = Operation::RemoveCallArg(RemoveCallArgOp {
-                    call_name,
-                    arg_index: idx,
+            if unused_imports {
+                let report = rs_hack::commands::unused_imports::run(&files)?;
+                rs_hack::commands::unused_imports::render_skipped(&report);
+                let op = rs_hack::commands::unused_imports::operation(&report);
+                execute_operation_with_state(
+                    ctx,
+                    &files,
+                    &op,
+                    apply,
+                    None,
+                    &cli.local_state,
+                    cli.no_state,
+                    &cli.format,
+                    cli.summary,
+                    cli.limit,
+                )?;
+                return Ok(());
+            }
+
+            // Handle --call operations first (remove argument from function/method calls)
+            if let Some(call_name) = call {
+                let idx = arg_index
+                    .ok_or_else(|| anyhow::anyhow!("--arg-index is required when using --call"))?;
+
+                let op = Operation::RemoveCallArg(RemoveCallArgOp {
+                    call_name,
+                    arg_index: idx,
                     call_type,
                     content_filter,
                 });
                 execute_operation_with_state(
+                    ctx,
                     &files,
                     &op,
                     apply,
                     None,
                     &cli.local_state,
+                    cli.no_state,
                     &cli.format,
                     cli.summary,
                     cli.limit,
@@ -3620,7 +6222,7 @@ fn main() -> Result<()> {
 
             if op_count == 0 {
                 anyhow::bail!(
-                    "Must specify one of: --field-name, --variant, --method, --derive, --match-arm, --call, or --doc-comment"
+                    "Must specify one of: --field-name, --variant, --method, --derive, --match-arm, --call, --doc-comment, or --unused-imports"
                 );
             }
 
@@ -3637,11 +6239,13 @@ fn main() -> Result<()> {
                     function_name: function,
                 });
                 execute_operation_with_state(
+                    ctx,
                     &files,
                     &op,
                     apply,
                     None,
                     &cli.local_state,
+                    cli.no_state,
                     &cli.format,
                     cli.summary,
                     cli.limit,
@@ -3660,8 +6264,9 @@ fn main() -> Result<()> {
                     let expanded = expand_kind_to_node_types(k);
                     if expanded.is_empty() {
                         anyhow::bail!(
-                            "Unknown kind '{}'. Valid kinds: struct, function, enum, match, identifier, type, macro, const, trait, mod, use",
-                            k
+                            "Unknown kind '{}'. Valid kinds: {} (see: rs-hack node-types)",
+                            k,
+                            KINDS.join(", ")
                         );
                     }
                     if expanded.len() > 1 {
@@ -3682,11 +6287,13 @@ fn main() -> Result<()> {
                     name: name.unwrap(),
                 });
                 execute_operation_with_state(
+                    ctx,
                     &files,
                     &op,
                     apply,
                     None,
                     &cli.local_state,
+                    cli.no_state,
                     &cli.format,
                     cli.summary,
                     cli.limit,
@@ -3712,7 +6319,7 @@ fn main() -> Result<()> {
                     let node_types = expand_kind_to_node_types(k);
                     let mut found = false;
                     for nt in node_types {
-                        if target_exists(&files, target_name, Some(nt))? {
+                        if target_exists(ctx, &files, target_name, Some(nt))? {
                             found = true;
                             break;
                         }
@@ -3720,7 +6327,7 @@ fn main() -> Result<()> {
                     found
                 } else if let Some(nt) = &node_type {
                     // Use specific node type
-                    target_exists(&files, target_name, Some(nt))?
+                    target_exists(ctx, &files, target_name, Some(nt))?
                 } else {
                     // Legacy detection: check for :: for enum variants
                     if target_name.contains("::") {
@@ -3728,18 +6335,19 @@ fn main() -> Result<()> {
                         let parts: Vec<&str> = target_name.split("::").collect();
                         if parts.len() == 2 {
                             let enum_name = parts[0];
-                            target_exists(&files, enum_name, Some("enum"))?
+                            target_exists(ctx, &files, enum_name, Some("enum"))?
                         } else {
                             anyhow::bail!("Invalid enum variant syntax. Use EnumName::VariantName");
                         }
                     } else {
-                        // Default to struct
-                        target_exists(&files, target_name, Some("struct"))?
+                        // Default to struct (or union)
+                        target_exists(ctx, &files, target_name, Some("struct"))?
+                            || target_exists(ctx, &files, target_name, Some("union"))?
                     }
                 };
 
                 if !exists {
-                    show_target_hints(&files, target_name, "struct", &paths)?;
+                    show_target_hints(ctx, &files, target_name, "struct", &paths)?;
                     return Ok(());
                 }
 
@@ -3748,21 +6356,24 @@ fn main() -> Result<()> {
                     field_name: field,
                     literal_only,
                     where_filter: cli.r#where.clone(),
+                    in_fn,
                 });
                 execute_operation_with_state(
+                    ctx,
                     &files,
                     &op,
                     apply,
                     None,
                     &cli.local_state,
+                    cli.no_state,
                     &cli.format,
                     cli.summary,
                     cli.limit,
                 )?;
             } else if let Some(variant_name) = variant {
                 // Removing enum variant
-                if !target_exists(&files, target_name, Some("enum"))? {
-                    show_target_hints(&files, target_name, "enum", &paths)?;
+                if !target_exists(ctx, &files, target_name, Some("enum"))? {
+                    show_target_hints(ctx, &files, target_name, "enum", &paths)?;
                     return Ok(());
                 }
 
@@ -3770,24 +6381,44 @@ fn main() -> Result<()> {
                     enum_name: target_name.clone(),
                     variant_name,
                     where_filter: cli.r#where.clone(),
+                    in_fn,
                 });
                 execute_operation_with_state(
+                    ctx,
                     &files,
                     &op,
                     apply,
                     None,
                     &cli.local_state,
+                    cli.no_state,
                     &cli.format,
                     cli.summary,
                     cli.limit,
                 )?;
             } else if let Some(method_name) = method {
-                // Removing impl method
-                // Note: We don't have RemoveImplMethod operation yet, so bail with helpful message
-                anyhow::bail!(
-                    "Remove impl method is not yet implemented. Use the transform command to comment out methods:\n  rs-hack transform --paths src --node-type impl-method --name {} --action comment --apply",
-                    method_name
-                );
+                // Removing impl method (from every impl block of the type unless --trait)
+                if !target_exists(ctx, &files, target_name, None)? {
+                    show_target_hints(ctx, &files, target_name, "impl", &paths)?;
+                    return Ok(());
+                }
+
+                let op = Operation::RemoveImplMethod(RemoveImplMethodOp {
+                    target: target_name.clone(),
+                    method_name,
+                    trait_name,
+                });
+                execute_operation_with_state(
+                    ctx,
+                    &files,
+                    &op,
+                    apply,
+                    None,
+                    &cli.local_state,
+                    cli.no_state,
+                    &cli.format,
+                    cli.summary,
+                    cli.limit,
+                )?;
             } else if let Some(_derive_macro) = derive {
                 // Removing derive macro
                 // Note: We don't have RemoveDerive operation yet, so bail with helpful message
@@ -3815,9 +6446,14 @@ fn main() -> Result<()> {
             arg,
             call_type,
             content_filter,
+            in_fn,
+            derive,
+            sort,
+            visibility,
+            name_regex,
             apply,
         } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
 
             // Handle --call operations first (update argument in function/method calls)
             if let Some(call_name) = call {
@@ -3834,11 +6470,13 @@ fn main() -> Result<()> {
                     content_filter,
                 });
                 execute_operation_with_state(
+                    ctx,
                     &files,
                     &op,
                     apply,
                     None,
                     &cli.local_state,
+                    cli.no_state,
                     &cli.format,
                     cli.summary,
                     cli.limit,
@@ -3853,6 +6491,8 @@ fn main() -> Result<()> {
                 variant.is_some(),
                 match_arm.is_some(),
                 doc_comment.is_some(),
+                derive.is_some() || sort,
+                visibility.is_some(),
             ]
             .iter()
             .filter(|&&x| x)
@@ -3860,16 +6500,71 @@ fn main() -> Result<()> {
 
             if op_count == 0 {
                 anyhow::bail!(
-                    "Must specify one of: --field, --variant, --match-arm, --call, or --doc-comment"
+                    "Must specify one of: --field, --variant, --match-arm, --call, --doc-comment, --derive/--sort, or --visibility"
                 );
             }
 
             if op_count > 1 {
                 anyhow::bail!(
-                    "Can only specify one operation flag at a time (--field, --variant, --match-arm, --call, or --doc-comment)"
+                    "Can only specify one operation flag at a time (--field, --variant, --match-arm, --call, --doc-comment, --derive/--sort, or --visibility)"
                 );
             }
 
+            // Handle --visibility operations
+            if let Some(visibility) = visibility {
+                if name.is_none() && name_regex.is_none() {
+                    anyhow::bail!("--name or --name-regex is required when using --visibility");
+                }
+                let node_types: Vec<String> = if let Some(k) = &kind {
+                    let expanded: Vec<String> = expand_kind_to_node_types(k)
+                        .into_iter()
+                        .filter(|t| rs_hack::editor::VISIBILITY_NODE_TYPES.contains(t))
+                        .map(str::to_string)
+                        .collect();
+                    if expanded.is_empty() {
+                        anyhow::bail!(
+                            "Kind '{}' has no definitions --visibility applies to ({})",
+                            k,
+                            rs_hack::editor::VISIBILITY_NODE_TYPES.join(", ")
+                        );
+                    }
+                    expanded
+                } else if let Some(nt) = node_type {
+                    vec![nt]
+                } else {
+                    rs_hack::editor::VISIBILITY_NODE_TYPES
+                        .iter()
+                        .map(|t| t.to_string())
+                        .collect()
+                };
+
+                let op = Operation::UpdateVisibility(UpdateVisibilityOp {
+                    node_types,
+                    name,
+                    name_regex: name_regex
+                        .map(|pattern| compile_name_regex(&pattern).map(|_| pattern))
+                        .transpose()?,
+                    visibility: if visibility.trim() == "private" {
+                        String::new()
+                    } else {
+                        visibility
+                    },
+                });
+                execute_operation_with_state(
+                    ctx,
+                    &files,
+                    &op,
+                    apply,
+                    None,
+                    &cli.local_state,
+                    cli.no_state,
+                    &cli.format,
+                    cli.summary,
+                    cli.limit,
+                )?;
+                return Ok(());
+            }
+
             // Handle --match-arm operations
             if let Some(pattern) = match_arm {
                 if body.is_none() {
@@ -3882,11 +6577,13 @@ fn main() -> Result<()> {
                     function_name: function,
                 });
                 execute_operation_with_state(
+                    ctx,
                     &files,
                     &op,
                     apply,
                     None,
                     &cli.local_state,
+                    cli.no_state,
                     &cli.format,
                     cli.summary,
                     cli.limit,
@@ -3905,8 +6602,9 @@ fn main() -> Result<()> {
                     let expanded = expand_kind_to_node_types(k);
                     if expanded.is_empty() {
                         anyhow::bail!(
-                            "Unknown kind '{}'. Valid kinds: struct, function, enum, match, identifier, type, macro, const, trait, mod, use",
-                            k
+                            "Unknown kind '{}'. Valid kinds: {} (see: rs-hack node-types)",
+                            k,
+                            KINDS.join(", ")
                         );
                     }
                     if expanded.len() > 1 {
@@ -3928,11 +6626,13 @@ fn main() -> Result<()> {
                     doc_comment: doc_text,
                 });
                 execute_operation_with_state(
+                    ctx,
                     &files,
                     &op,
                     apply,
                     None,
                     &cli.local_state,
+                    cli.no_state,
                     &cli.format,
                     cli.summary,
                     cli.limit,
@@ -3968,7 +6668,7 @@ fn main() -> Result<()> {
                     let node_types = expand_kind_to_node_types(k);
                     let mut found = false;
                     for nt in node_types {
-                        if target_exists(&files, target_name, Some(nt))? {
+                        if target_exists(ctx, &files, target_name, Some(nt))? {
                             found = true;
                             break;
                         }
@@ -3976,14 +6676,15 @@ fn main() -> Result<()> {
                     found
                 } else if let Some(nt) = &node_type {
                     // Use specific node type
-                    target_exists(&files, target_name, Some(nt))?
+                    target_exists(ctx, &files, target_name, Some(nt))?
                 } else {
-                    // Default to struct
-                    target_exists(&files, target_name, Some("struct"))?
+                    // Default to struct (or union)
+                    target_exists(ctx, &files, target_name, Some("struct"))?
+                        || target_exists(ctx, &files, target_name, Some("union"))?
                 };
 
                 if !exists {
-                    show_target_hints(&files, target_name, "struct", &paths)?;
+                    show_target_hints(ctx, &files, target_name, "struct", &paths)?;
                     return Ok(());
                 }
 
@@ -3991,21 +6692,24 @@ fn main() -> Result<()> {
                     struct_name: target_name.clone(),
                     field_def: final_field_def,
                     where_filter: cli.r#where.clone(),
+                    in_fn,
                 });
                 execute_operation_with_state(
+                    ctx,
                     &files,
                     &op,
                     apply,
                     None,
                     &cli.local_state,
+                    cli.no_state,
                     &cli.format,
                     cli.summary,
                     cli.limit,
                 )?;
             } else if let Some(variant_def) = variant {
                 // Updating enum variant
-                if !target_exists(&files, target_name, Some("enum"))? {
-                    show_target_hints(&files, target_name, "enum", &paths)?;
+                if !target_exists(ctx, &files, target_name, Some("enum"))? {
+                    show_target_hints(ctx, &files, target_name, "enum", &paths)?;
                     return Ok(());
                 }
 
@@ -4013,13 +6717,53 @@ fn main() -> Result<()> {
                     enum_name: target_name.clone(),
                     variant_def,
                     where_filter: cli.r#where.clone(),
+                    in_fn,
                 });
                 execute_operation_with_state(
+                    ctx,
                     &files,
                     &op,
                     apply,
                     None,
                     &cli.local_state,
+                    cli.no_state,
+                    &cli.format,
+                    cli.summary,
+                    cli.limit,
+                )?;
+            } else {
+                // Replacing and/or sorting the derive list
+                let Some(target_type) = detect_target_type(ctx, &files, target_name)? else {
+                    show_target_hints(ctx, &files, target_name, "struct or enum", &paths)?;
+                    return Ok(());
+                };
+
+                let derives = derive.map(|list| {
+                    list.split(',')
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                        .collect::<Vec<_>>()
+                });
+                if derives.as_ref().is_some_and(Vec::is_empty) {
+                    anyhow::bail!("--derive needs at least one trait (e.g., \"Debug,Clone\")");
+                }
+
+                let op = Operation::UpdateDerive(UpdateDeriveOp {
+                    target_name: target_name.clone(),
+                    target_type,
+                    derives,
+                    sort,
+                    where_filter: cli.r#where.clone(),
+                    edit_mode: EditMode::default(),
+                });
+                execute_operation_with_state(
+                    ctx,
+                    &files,
+                    &op,
+                    apply,
+                    None,
+                    &cli.local_state,
+                    cli.no_state,
                     &cli.format,
                     cli.summary,
                     cli.limit,
@@ -4029,12 +6773,29 @@ fn main() -> Result<()> {
 
         Commands::History { limit } => {
             let state_dir = get_state_dir(cli.local_state)?;
-            show_history(limit, &state_dir)?;
+            if cli.format == "json" {
+                let runs = recent_runs(limit, &state_dir)?;
+                println!("{}", serde_json::to_string_pretty(&runs)?);
+            } else {
+                show_history(limit, &state_dir)?;
+            }
         }
 
         Commands::Revert { run_id, force } => {
             let state_dir = get_state_dir(cli.local_state)?;
-            revert_run(&run_id, force, &state_dir)?;
+            if cli.format == "json" {
+                let report = revert_run_report(&run_id, force, &state_dir)?;
+                println!("{}", serde_json::to_string_pretty(&report)?);
+                if !report.skipped.is_empty() {
+                    anyhow::bail!(
+                        "Run {} not reverted: {} file(s) changed since (use --force to ignore)",
+                        run_id,
+                        report.skipped.len()
+                    );
+                }
+            } else {
+                revert_run(&run_id, force, &state_dir)?;
+            }
         }
 
         Commands::Clean { keep_days } => {
@@ -4046,43 +6807,210 @@ fn main() -> Result<()> {
             paths,
             node_type,
             name,
+            name_regex,
             content_filter,
             action,
             with,
+            in_fn,
+            guard,
+            pattern,
+            constructor,
+            trait_path,
+            to_type,
+            receiver,
+            exclude_names,
+            exclude_in,
+            spec,
+            script,
+            flag_risky,
             apply,
         } => {
-            use operations::{TransformAction, TransformOp};
+            use operations::{MultiTransformOp, TransformAction, TransformOp};
+
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
+
+            if let Some(spec) = spec {
+                let content =
+                    std::fs::read_to_string(&spec).context("Failed to read transform spec file")?;
+                let multi: MultiTransformOp = if matches!(
+                    spec.extension().and_then(|s| s.to_str()),
+                    Some("yaml" | "yml")
+                ) {
+                    serde_yaml::from_str(&content).context("Failed to parse transform spec YAML")?
+                } else {
+                    serde_json::from_str(&content)
+                        .or_else(|_| serde_yaml::from_str(&content))
+                        .context("Failed to parse transform spec (tried both JSON and YAML)")?
+                };
+
+                if multi.transforms.is_empty() {
+                    anyhow::bail!("Transform spec {} lists no transforms", spec.display());
+                }
+
+                execute_operation_with_state(
+                    ctx,
+                    &files,
+                    &Operation::MultiTransform(multi),
+                    apply,
+                    None,
+                    &cli.local_state,
+                    cli.no_state,
+                    &cli.format,
+                    cli.summary,
+                    cli.limit,
+                )?;
+                return Ok(());
+            }
+
+            // clap enforces --action without --spec or --script, and --node-type unless --in
+            // stands in for it
+            if in_fn.is_some()
+                && !matches!(action.as_deref(), Some("to-let-else" | "simplify-format"))
+            {
+                anyhow::bail!(
+                    "--in is only supported with --action to-let-else or simplify-format"
+                );
+            }
+            let node_type = node_type.unwrap_or_else(|| "function".to_string());
+            let name = name.or(in_fn);
 
             // Parse the action
-            let transform_action = match action.as_str() {
-                "comment" => TransformAction::Comment,
-                "remove" => TransformAction::Remove,
-                "replace" => {
-                    let replacement = with.ok_or_else(|| {
-                        anyhow::anyhow!("--with is required when action is 'replace'")
-                    })?;
-                    TransformAction::Replace { with: replacement }
+            let transform_action = if let Some(script) = script {
+                TransformAction::Script { script }
+            } else {
+                let action = action.expect("--action is required without --spec or --script");
+                match action.as_str() {
+                    "comment" => TransformAction::Comment,
+                    "remove" => TransformAction::Remove,
+                    "replace" => {
+                        let replacement = with.ok_or_else(|| {
+                            anyhow::anyhow!("--with is required when action is 'replace'")
+                        })?;
+                        TransformAction::Replace { with: replacement }
+                    }
+                    "wrap" => {
+                        if !rs_hack::editor::WRAP_NODE_TYPES.contains(&node_type.as_str()) {
+                            anyhow::bail!(
+                                "--action wrap works on --node-type {}",
+                                rs_hack::editor::WRAP_NODE_TYPES.join(", ")
+                            );
+                        }
+                        let template = with.ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "--action wrap needs --with, a template with {{}} for the original code (e.g. \"instrument({{}})\")"
+                            )
+                        })?;
+                        if !template.contains("{}") {
+                            anyhow::bail!("--with for --action wrap must contain {{}}");
+                        }
+                        TransformAction::Wrap { with: template }
+                    }
+                    "to-let-else" => TransformAction::ToLetElse,
+                    "simplify-format" => TransformAction::SimplifyFormat,
+                    "cfg-guard" => TransformAction::CfgGuard { cfg: guard },
+                    "to-constructor" => {
+                        if node_type != "struct-literal" {
+                            anyhow::bail!(
+                                "--action to-constructor works on --node-type struct-literal"
+                            );
+                        }
+                        let name = name.as_deref().ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "--action to-constructor needs --name (the struct whose literals to convert)"
+                            )
+                        })?;
+                        TransformAction::ToConstructor {
+                            constructor,
+                            fields: struct_field_order(ctx, &files, name)?,
+                        }
+                    }
+                    "to-ufcs" => {
+                        if node_type != "method-call" {
+                            anyhow::bail!("--action to-ufcs works on --node-type method-call");
+                        }
+                        let trait_path = trait_path.ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "--action to-ufcs needs --trait (the trait to qualify calls with)"
+                            )
+                        })?;
+                        TransformAction::ToUfcs {
+                            trait_path,
+                            to_type,
+                            receiver,
+                        }
+                    }
+                    "replace-regex" => {
+                        let pattern = pattern.ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "--action replace-regex needs --pattern (the regex to replace)"
+                            )
+                        })?;
+                        let with = with.ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "--action replace-regex needs --with (the substitution; may be empty)"
+                            )
+                        })?;
+                        TransformAction::ReplaceRegex { pattern, with }
+                    }
+                    "rename-signature" => {
+                        if !rs_hack::editor::RENAME_SIGNATURE_NODE_TYPES
+                            .iter()
+                            .any(|(t, _)| *t == node_type)
+                        {
+                            anyhow::bail!(
+                                "--action rename-signature works on --node-type {}",
+                                rs_hack::editor::RENAME_SIGNATURE_NODE_TYPES
+                                    .iter()
+                                    .map(|(t, _)| *t)
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            );
+                        }
+                        let new_name = with.ok_or_else(|| {
+                            anyhow::anyhow!("--action rename-signature needs --with (the new name)")
+                        })?;
+                        TransformAction::RenameSignature { new_name }
+                    }
+                    _ => anyhow::bail!(
+                        "Invalid action: {}. Use 'comment', 'remove', 'replace', 'wrap', 'to-let-else', 'simplify-format', 'cfg-guard', 'to-constructor', 'to-ufcs', 'replace-regex', or 'rename-signature'",
+                        action
+                    ),
                 }
-                _ => anyhow::bail!(
-                    "Invalid action: {}. Use 'comment', 'remove', or 'replace'",
-                    action
-                ),
             };
 
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            if flag_risky {
+                let report = rs_hack::commands::risk::run(&rs_hack::commands::risk::RiskArgs {
+                    files: files.clone(),
+                    node_types: vec![node_type.clone()],
+                    name: name.clone(),
+                    content_filter: content_filter.clone(),
+                    exclude_names: exclude_names.clone(),
+                    exclude_in: exclude_in.clone(),
+                    verbose: ctx.verbose.clone(),
+                })?;
+                rs_hack::commands::risk::render(&report);
+            }
+
             let op = Operation::Transform(TransformOp {
                 node_type,
                 name_filter: name,
                 content_filter,
                 action: transform_action,
+                exclude_names,
+                exclude_in,
+                name_regex: name_regex
+                    .map(|pattern| compile_name_regex(&pattern).map(|_| pattern))
+                    .transpose()?,
             });
 
             execute_operation_with_state(
+                ctx,
                 &files,
                 &op,
                 apply,
                 None,
                 &cli.local_state,
+                cli.no_state,
                 &cli.format,
                 cli.summary,
                 cli.limit,
@@ -4097,7 +7025,7 @@ fn main() -> Result<()> {
             style,
             apply,
         } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
 
             // Parse style
             let doc_style = style
@@ -4112,11 +7040,13 @@ fn main() -> Result<()> {
             });
 
             execute_operation_with_state(
+                ctx,
                 &files,
                 &op,
                 apply,
                 None,
                 &cli.local_state,
+                cli.no_state,
                 &cli.format,
                 cli.summary,
                 cli.limit,
@@ -4130,7 +7060,7 @@ fn main() -> Result<()> {
             doc_comment,
             apply,
         } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
 
             let op = Operation::UpdateDocComment(UpdateDocCommentOp {
                 target_type,
@@ -4139,11 +7069,13 @@ fn main() -> Result<()> {
             });
 
             execute_operation_with_state(
+                ctx,
                 &files,
                 &op,
                 apply,
                 None,
                 &cli.local_state,
+                cli.no_state,
                 &cli.format,
                 cli.summary,
                 cli.limit,
@@ -4156,16 +7088,18 @@ fn main() -> Result<()> {
             name,
             apply,
         } => {
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
 
             let op = Operation::RemoveDocComment(RemoveDocCommentOp { target_type, name });
 
             execute_operation_with_state(
+                ctx,
                 &files,
                 &op,
                 apply,
                 None,
                 &cli.local_state,
+                cli.no_state,
                 &cli.format,
                 cli.summary,
                 cli.limit,
@@ -4179,7 +7113,7 @@ fn main() -> Result<()> {
         } => {
             use operations::FieldContext;
 
-            let files = collect_rust_files_with_exclusions(&paths, &cli.exclude)?;
+            let files = ctx.input_files(&paths, &cli.exclude, &collect)?;
 
             let mut all_struct_defs = Vec::new();
             let mut all_enum_variants = Vec::new();
@@ -4187,7 +7121,7 @@ fn main() -> Result<()> {
 
             for file in files {
                 let content = std::fs::read_to_string(&file)?;
-                let editor = match RustEditor::new(&content) {
+                let editor = match RustEditor::with_scope(&content, ctx.scope.clone()) {
                     Ok(e) => e,
                     Err(e) => {
                         eprintln!("⚠️  Skipping {}: {}", file.display(), e);
@@ -4304,6 +7238,17 @@ fn main() -> Result<()> {
         }
     }
 
+    if let Some((args, apply)) = rename_text {
+        let result = rs_hack::commands::rename_text::run(&args)?;
+        render_rename_text_result(
+            ctx,
+            result,
+            apply && !ctx.verify_idempotent,
+            (!cli.no_state).then_some(cli.local_state),
+            &cli.format,
+        )?;
+    }
+
     Ok(())
 }
 
@@ -4377,7 +7322,9 @@ fn print_operation_hints(op: &Operation) {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn execute_operation(
+    ctx: &RunContext,
     files: &[PathBuf],
     op: &Operation,
     apply: bool,
@@ -4386,17 +7333,111 @@ fn execute_operation(
     show_summary: bool,
     limit: Option<usize>,
 ) -> Result<()> {
-    let opts = rs_hack::execute::ExecuteOpts {
-        apply,
-        output: output.cloned(),
-        limit,
-    };
+    if ctx.stdin_mode() {
+        return execute_on_stdin(ctx, op, format);
+    }
+    if ctx.interactive {
+        anyhow::bail!("--interactive isn't supported by this command yet");
+    }
+    let apply = apply && !ctx.verify_idempotent;
+    let opts = ctx.execute_opts(apply, output, limit);
 
     let result = rs_hack::execute::execute(files, op, &opts)?;
+    ctx.record_operation_report(files, op, apply, &result);
+    ensure_some_parsed(files, &result.parse_errors)?;
     render_execute_result(&result, op, format, show_summary, apply, output);
     Ok(())
 }
 
+/// Unparseable files are skipped and listed, but a run where none of the files parsed has
+/// processed nothing and fails, before anything about the run is printed.
+fn ensure_some_parsed(files: &[PathBuf], parse_errors: &[(PathBuf, String)]) -> Result<()> {
+    if !files.is_empty() && parse_errors.len() == files.len() {
+        let errors: Vec<String> = parse_errors
+            .iter()
+            .map(|(path, err)| format!("{} — {}", path.display(), err))
+            .collect();
+        anyhow::bail!(
+            "None of the {} file(s) could be parsed; nothing was processed:\n   {}",
+            files.len(),
+            errors.join("\n   ")
+        );
+    }
+    Ok(())
+}
+
+/// `--stdin`: apply `op` to the code on stdin and print the edited code (or, with
+/// `--format diff`, the diff) to stdout. Errors when the operation matches nothing.
+fn execute_on_stdin(ctx: &RunContext, op: &Operation, format: &str) -> Result<()> {
+    let source = ctx.stdin_source.as_deref().unwrap_or_default();
+    let Some(edited) = rs_hack::execute::apply_to_source(source, op, &ctx.scope)? else {
+        anyhow::bail!("No changes: {} matched nothing on stdin", op.kind_name());
+    };
+    if format == "diff" {
+        print_diff(Path::new(STDIN_PATH), source, &edited);
+    } else {
+        print!("{}", edited);
+    }
+    Ok(())
+}
+
+/// Field order of the struct `name` for `transform --action to-constructor`, taken from its
+/// definition in `files`. Literals can be anywhere, so the definition is looked up across
+/// the whole run rather than per file.
+fn struct_field_order(ctx: &RunContext, files: &[PathBuf], name: &str) -> Result<Vec<String>> {
+    let mut orders: Vec<(PathBuf, Vec<String>)> = Vec::new();
+    for file in files {
+        let Ok(content) = ctx.read_source(file) else {
+            continue;
+        };
+        let editor = match RustEditor::with_scope(&content, ctx.scope.clone()) {
+            Ok(editor) => editor,
+            Err(e) => {
+                ctx.verbose.skipped(file, &e);
+                continue;
+            }
+        };
+        if let Some(fields) = editor.struct_field_names(name)
+            && !orders.iter().any(|(_, seen)| *seen == fields)
+        {
+            orders.push((file.clone(), fields));
+        }
+    }
+    match orders.len() {
+        0 => anyhow::bail!(
+            "No definition of struct `{}` with named fields in the given paths; the field order can't be determined",
+            name
+        ),
+        1 => Ok(orders.remove(0).1),
+        _ => anyhow::bail!(
+            "Struct `{}` is defined with different fields in {} and {}; narrow --paths",
+            name,
+            orders[0].0.display(),
+            orders[1].0.display()
+        ),
+    }
+}
+
+/// One line per rewritten literal for `transform --action to-constructor` (not in diff
+/// output, which has to stay a valid patch).
+fn report_constructor_conversions(change: &rs_hack::execute::FileChange, op: &Operation) {
+    let Operation::Transform(operations::TransformOp {
+        action: operations::TransformAction::ToConstructor { constructor, .. },
+        ..
+    }) = op
+    else {
+        return;
+    };
+    let mut nodes: Vec<_> = change.modified_nodes.iter().collect();
+    nodes.sort_by_key(|node| node.location.line);
+    for node in nodes {
+        println!(
+            "    line {}: {} {{ .. }} → {}::{}(..)",
+            node.location.line, node.identifier, node.identifier, constructor
+        );
+    }
+}
+
 /// CLI-side rendering of an `ExecuteResult`. Reproduces the original
 /// `execute_operation` stdout/stderr output from structured fields.
 fn render_execute_result(
@@ -4407,6 +7448,21 @@ fn render_execute_result(
     apply: bool,
     output: Option<&PathBuf>,
 ) {
+    // Only the edits go to stdout, so an editor can parse it whole
+    if format == "lsp" && !apply {
+        print_lsp_edits(&result.changes);
+        render_parse_errors(&result.parse_errors);
+        render_capped_files(&result.capped_files);
+        render_cfg_gated(result.cfg_gated_items);
+        render_skipped_matches(result.skipped_matches);
+        if result.changes.is_empty()
+            && let Some(err) = &result.last_error
+        {
+            eprintln!("📋 Diagnostic: {}", err);
+        }
+        return;
+    }
+
     let mut total_stats = DiffStats::default();
 
     for change in &result.changes {
@@ -4416,6 +7472,12 @@ fn render_execute_result(
         } else if format == "summary" {
             let stats = print_summary_diff(&change.path, &change.old_content, &change.new_content);
             total_stats.add(&stats);
+        } else if format == "github" && !apply {
+            for annotation in
+                rs_hack::github::from_changes(std::slice::from_ref(change), op.kind_name())
+            {
+                println!("{}", annotation);
+            }
         } else if apply {
             if let Some(out) = output {
                 println!("✓ Written to: {}", out.display());
@@ -4427,10 +7489,9 @@ fn render_execute_result(
         } else {
             println!("Would modify: {}", change.path.display());
         }
-    }
-
-    for (path, err) in &result.parse_errors {
-        eprintln!("⚠️  Skipping {}: {}", path.display(), err);
+        if format != "diff" {
+            report_constructor_conversions(change, op);
+        }
     }
 
     if result.limit_hit {
@@ -4440,22 +7501,23 @@ fn render_execute_result(
         );
     }
 
-    if !result.parse_errors.is_empty() {
-        eprintln!(
-            "\n⚠️  {} file(s) skipped due to parse errors:",
-            result.parse_errors.len()
-        );
-        for (path, err) in &result.parse_errors {
-            eprintln!("   {} — {}", path.display(), err);
-        }
-    }
+    render_capped_files(&result.capped_files);
+    render_cfg_gated(result.cfg_gated_items);
+    render_skipped_matches(result.skipped_matches);
+
+    render_parse_errors(&result.parse_errors);
 
     if !result.unmatched_qualified_paths.is_empty() {
         if !result.changes.is_empty() {
             println!("\n⚠️  Note: Some instances were not matched:");
         }
         render_unmatched_paths(&result.unmatched_qualified_paths);
-    } else if result.changes.is_empty() {
+    } else if result.changes.is_empty()
+        && result.capped_files.is_empty()
+        && matches!(op, Operation::RemoveUnusedImports(_))
+    {
+        println!("No unused imports found");
+    } else if result.changes.is_empty() && result.capped_files.is_empty() {
         println!("No changes made - target not found in any files");
         if let Some(err) = &result.last_error {
             eprintln!("\n📋 Diagnostic: {}", err);
@@ -4476,6 +7538,344 @@ fn render_execute_result(
     }
 }
 
+/// The files left out because they don't parse, listed once after everything else.
+fn render_parse_errors(errors: &[(PathBuf, String)]) {
+    if !errors.is_empty() {
+        eprintln!(
+            "\n⚠️  {} file(s) skipped due to parse errors:",
+            errors.len()
+        );
+        for (path, err) in errors {
+            eprintln!("   {} — {}", path.display(), err);
+        }
+    }
+}
+
+/// Without `--cfg`, changes inside `#[cfg]`-gated code may be for a configuration that isn't
+/// built; say how many items that was.
+fn render_cfg_gated(count: usize) {
+    if count > 0 {
+        eprintln!(
+            "\n⚠️  {} #[cfg]-gated item{} touched; check the change suits every configuration (--cfg KEY=VALUE skips inactive code)",
+            count,
+            if count == 1 { "" } else { "s" }
+        );
+    }
+}
+
+/// Matches `--skip-tests`/`--skip-attr` left out, so a smaller result isn't a surprise.
+fn render_skipped_matches(count: usize) {
+    if count > 0 {
+        eprintln!(
+            "\nℹ️  {} match{} left out by --skip-tests/--skip-attr/--in-module",
+            count,
+            if count == 1 { "" } else { "es" }
+        );
+    }
+}
+
+/// Output for `move`. The dry run always shows the diffs (the deletion from --from and the
+/// insertion into --to are the point of reviewing a move); applying writes the files.
+fn render_move_result(
+    ctx: &RunContext,
+    result: rs_hack::commands::move_item::MoveResult,
+    apply: bool,
+    local_state: Option<bool>,
+    format: &str,
+) -> Result<()> {
+    // --format lsp keeps stdout to the edits
+    if apply || format != "lsp" {
+        println!(
+            "{} {} from {} to {}",
+            if apply { "Moving" } else { "Would move" },
+            result.moved.join(", "),
+            result.from_module,
+            result.to_module
+        );
+        println!();
+    }
+    render_planned_changes(
+        ctx,
+        result.changes,
+        &result.warnings,
+        "MoveItem",
+        apply,
+        local_state,
+        format,
+    )
+}
+
+/// Output for `rename --kind mod`: the edits, then the module's files renamed once the
+/// edits are written (they're written at the old paths).
+fn render_rename_mod_result(
+    ctx: &RunContext,
+    result: rs_hack::commands::rename_mod::RenameModResult,
+    to: &str,
+    apply: bool,
+    local_state: Option<bool>,
+    format: &str,
+) -> Result<()> {
+    // --format lsp keeps stdout to the edits
+    let chatty = apply || format != "lsp";
+    if chatty {
+        println!(
+            "{} module {} to {}",
+            if apply { "Renaming" } else { "Would rename" },
+            result.module,
+            to
+        );
+        if !apply {
+            for (from, to) in &result.renames {
+                println!("  {} → {}", from.display(), to.display());
+            }
+        }
+        println!();
+    }
+    render_planned_changes(
+        ctx,
+        result.changes,
+        &result.warnings,
+        "RenameModule",
+        apply,
+        local_state,
+        format,
+    )?;
+    if apply {
+        for (from, to) in &result.renames {
+            std::fs::rename(from, to).with_context(|| {
+                format!("Failed to rename {} to {}", from.display(), to.display())
+            })?;
+            println!("✓ Renamed: {} → {}", from.display(), to.display());
+        }
+    }
+    Ok(())
+}
+
+/// Output for `rename --include-strings/--include-comments-text`: the textual replacements,
+/// under their own heading after the rename's output so they're reviewed separately.
+fn render_rename_text_result(
+    ctx: &RunContext,
+    result: rs_hack::commands::rename_text::RenameTextResult,
+    apply: bool,
+    local_state: Option<bool>,
+    format: &str,
+) -> Result<()> {
+    // --format lsp keeps stdout to the edits
+    if apply || format != "lsp" {
+        println!(
+            "\n{} in strings and comments: {} in string literals, {} in comments",
+            if apply { "Replacing" } else { "Would replace" },
+            result.in_strings,
+            result.in_comments
+        );
+        println!("(textual matches of the whole name; review them before relying on them)\n");
+    }
+    if result.changes.is_empty() {
+        return Ok(());
+    }
+    render_planned_changes(
+        ctx,
+        result.changes,
+        &[],
+        "RenameText",
+        apply,
+        local_state,
+        format,
+    )
+}
+
+/// Output for `inline-function`, shown like `move`: the removed definition and the expanded
+/// call are what to review.
+fn render_inline_result(
+    ctx: &RunContext,
+    result: rs_hack::commands::inline_fn::InlineResult,
+    name: &str,
+    apply: bool,
+    local_state: Option<bool>,
+    format: &str,
+) -> Result<()> {
+    // --format lsp keeps stdout to the edits
+    if apply || format != "lsp" {
+        println!(
+            "{} `{}` ({}) into its call at {}",
+            if apply { "Inlining" } else { "Would inline" },
+            name,
+            result.definition,
+            result.call_site
+        );
+        println!();
+    }
+    render_planned_changes(
+        ctx,
+        result.changes,
+        &result.warnings,
+        "InlineFunction",
+        apply,
+        local_state,
+        format,
+    )
+}
+
+/// Write (or diff, on a dry run) the file changes a multi-file command computed, then its
+/// warnings.
+fn render_planned_changes(
+    ctx: &RunContext,
+    changes: Vec<rs_hack::execute::FileChange>,
+    warnings: &[String],
+    operation: &str,
+    apply: bool,
+    local_state: Option<bool>,
+    format: &str,
+) -> Result<()> {
+    if !apply {
+        ctx.record_patch(&changes);
+    }
+    if apply {
+        let command = std::env::args().collect::<Vec<_>>().join(" ");
+        let written = rs_hack::execute::write_changes(
+            changes,
+            operation,
+            local_state,
+            command,
+            ctx.backups.as_ref(),
+        )?;
+        for change in &written.changes {
+            println!("✓ Modified: {}", change.path.display());
+        }
+        if let Some(run_id) = &written.run_id {
+            println!(
+                "\n📝 Run ID: {} (use 'rs-hack revert {}' to undo)",
+                run_id, run_id
+            );
+        }
+    } else if format == "github" {
+        for annotation in rs_hack::github::from_changes(&changes, operation) {
+            println!("{}", annotation);
+        }
+    } else if format == "lsp" {
+        print_lsp_edits(&changes);
+    } else {
+        let mut total_stats = DiffStats::default();
+        for change in &changes {
+            let stats = if format == "summary" {
+                print_summary_diff(&change.path, &change.old_content, &change.new_content)
+            } else {
+                print_diff(&change.path, &change.old_content, &change.new_content)
+            };
+            total_stats.add(&stats);
+        }
+        total_stats.print_summary();
+    }
+
+    if !warnings.is_empty() {
+        eprintln!();
+        for warning in warnings {
+            eprintln!("⚠️  {}", warning);
+        }
+    }
+    if !apply && format != "diff" && format != "github" && format != "lsp" {
+        println!("\n🔍 Dry run complete. Use --apply to make changes.");
+    }
+    Ok(())
+}
+
+/// `--format lsp`: the dry run's changes as a JSON array of `{ uri, edits }`.
+fn print_lsp_edits(changes: &[rs_hack::execute::FileChange]) {
+    let edits = rs_hack::lsp::from_changes(changes);
+    match serde_json::to_string_pretty(&edits) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("⚠️  Failed to serialize edits: {}", e),
+    }
+}
+
+/// `find --context N` lines above (`before`) or below a snippet match, numbered with a
+/// grep-style `N-` and dimmed on a terminal so they stand apart from the match. Lines above
+/// start over the match's preceding comment, since that is printed with the match.
+fn print_context(
+    result: &operations::InspectResult,
+    context: Option<usize>,
+    before: bool,
+    indent: &str,
+    sources: &mut std::collections::HashMap<String, String>,
+) {
+    let Some(n) = context.filter(|&n| n > 0) else {
+        return;
+    };
+    let content = sources
+        .entry(result.file_path.clone())
+        .or_insert_with(|| std::fs::read_to_string(&result.file_path).unwrap_or_default());
+    let comment_lines = result
+        .preceding_comment
+        .as_ref()
+        .map_or(0, |comment| comment.lines().count());
+    let (above, below) = rs_hack::commands::find::context_lines(
+        content,
+        result.location.line.saturating_sub(comment_lines),
+        result.location.end_line,
+        n,
+    );
+    for (number, line) in if before { above } else { below } {
+        println!(
+            "{}{}",
+            indent,
+            rs_hack::color::paint(rs_hack::color::DIM, &format!("{}- {}", number, line))
+        );
+    }
+}
+
+/// Print each item as one line of compact JSON (`--format jsonl`).
+fn print_jsonl<T: serde::Serialize>(items: &[T]) -> Result<()> {
+    use std::io::Write;
+    let mut stdout = std::io::stdout().lock();
+    for item in items {
+        serde_json::to_writer(&mut stdout, item)?;
+        writeln!(stdout)?;
+    }
+    Ok(())
+}
+
+/// " (required)" / " (default)" suffix for trait-method matches in find output, and a
+/// reminder that `--in-macro-call` matches come from raw tokens
+fn match_tag(result: &operations::InspectResult) -> &'static str {
+    if result.node_type == "macro-token" {
+        return " (macro tokens, not AST)";
+    }
+    match rs_hack::commands::find::trait_method_has_default(&result.node_type, &result.snippet) {
+        Some(true) => " (default)",
+        Some(false) => " (required)",
+        None => "",
+    }
+}
+
+/// The `// file:line:col - identifier` line above each snippet in find output, cyan when
+/// colors are on.
+fn match_header(result: &operations::InspectResult) -> String {
+    rs_hack::color::paint(
+        rs_hack::color::CYAN,
+        &format!(
+            "// {}:{}:{} - {}{}",
+            result.file_path,
+            result.location.line,
+            result.location.column,
+            result.identifier,
+            match_tag(result)
+        ),
+    )
+}
+
+fn render_capped_files(capped: &[(PathBuf, usize)]) {
+    if capped.is_empty() {
+        return;
+    }
+    eprintln!(
+        "\n⚠️  {} file(s) left unchanged, over --max-matches-per-file:",
+        capped.len()
+    );
+    for (path, count) in capped {
+        eprintln!("   {} — {} matches", path.display(), count);
+    }
+}
+
 fn render_unmatched_paths(unmatched: &std::collections::HashMap<String, usize>) {
     println!(
         "\n💡 Hint: Found {} struct literal(s) with fully qualified paths that didn't match:",
@@ -4510,40 +7910,214 @@ fn render_unmatched_paths(unmatched: &std::collections::HashMap<String, usize>)
     }
 }
 
-fn execute_batch(batch: &BatchSpec, apply: bool, exclude_patterns: &[String]) -> Result<()> {
-    for op in &batch.operations {
-        let files = collect_rust_files_with_exclusions(
-            std::slice::from_ref(&batch.base_path),
-            exclude_patterns,
+/// Run a batch spec: plan every operation in memory, report nodes that several of them
+/// modify, then show or write the combined result as a single run.
+#[allow(clippy::too_many_arguments)]
+fn execute_batch(
+    ctx: &RunContext,
+    batch: &BatchSpec,
+    apply: bool,
+    exclude_patterns: &[String],
+    collect: &CollectOptions,
+    format: &str,
+    show_summary: bool,
+    local_state: Option<bool>,
+) -> Result<()> {
+    let apply = apply && !ctx.verify_idempotent;
+    let files = collect_rust_files_with_options(
+        std::slice::from_ref(&batch.base_path),
+        exclude_patterns,
+        collect,
+    )?;
+    let plan = rs_hack::execute::plan_batch(
+        &files,
+        &batch.operations,
+        &ctx.execute_opts(false, None, None),
+    )?;
+    // Every operation that ran saw the same files
+    if let Some(first) = plan.operations.iter().find(|r| !r.parse_errors.is_empty()) {
+        ensure_some_parsed(&files, &first.parse_errors)?;
+    }
+
+    if !plan.conflicts.is_empty() {
+        eprintln!(
+            "⚠️  {} node(s) modified by more than one operation:",
+            plan.conflicts.len()
+        );
+        for conflict in &plan.conflicts {
+            let ops: Vec<String> = conflict
+                .operations
+                .iter()
+                .map(|&i| format!("#{} {}", i + 1, batch.operations[i].op.kind_name()))
+                .collect();
+            eprintln!(
+                "   {} {} `{}`: {}",
+                conflict.path.display(),
+                conflict.node_type,
+                conflict.identifier,
+                ops.join(", ")
+            );
+        }
+        eprintln!(
+            "   Each operation runs on the output of the ones before it; check the result.\n"
+        );
+    }
+
+    if format == "lsp" && !apply {
+        print_lsp_edits(&plan.changes);
+        for skipped in &plan.skipped {
+            let op = &batch.operations[skipped.index].op;
+            eprintln!(
+                "#{} {}: skipped ({})",
+                skipped.index + 1,
+                op.kind_name(),
+                skipped.reason
+            );
+        }
+        if let Some(first) = plan.operations.iter().find(|r| !r.parse_errors.is_empty()) {
+            render_parse_errors(&first.parse_errors);
+        }
+        render_cfg_gated(plan.operations.iter().map(|r| r.cfg_gated_items).sum());
+        render_skipped_matches(plan.operations.iter().map(|r| r.skipped_matches).sum());
+        return Ok(());
+    }
+
+    for (index, (op, result)) in batch.operations.iter().zip(&plan.operations).enumerate() {
+        let op = &op.op;
+        if let Some(skipped) = plan.skipped.iter().find(|s| s.index == index) {
+            println!(
+                "#{} {}: skipped ({})",
+                index + 1,
+                op.kind_name(),
+                skipped.reason
+            );
+            continue;
+        }
+        ctx.record_operation_report(&files, op, apply, result);
+        if result.changes.is_empty() && result.capped_files.is_empty() {
+            println!(
+                "#{} {}: target not found in any files",
+                index + 1,
+                op.kind_name()
+            );
+            if let Some(err) = &result.last_error {
+                eprintln!("   📋 Diagnostic: {}", err);
+            }
+        } else {
+            println!(
+                "#{} {}: {} modification(s) in {} file(s)",
+                index + 1,
+                op.kind_name(),
+                result.total_modifications,
+                result.changes.len()
+            );
+        }
+        render_capped_files(&result.capped_files);
+    }
+    // Every operation that ran saw the same files; a skipped one has no results
+    if let Some(first) = plan.operations.iter().find(|r| !r.parse_errors.is_empty()) {
+        render_parse_errors(&first.parse_errors);
+    }
+    render_cfg_gated(plan.operations.iter().map(|r| r.cfg_gated_items).sum());
+    render_skipped_matches(plan.operations.iter().map(|r| r.skipped_matches).sum());
+    println!();
+
+    let mut total_stats = DiffStats::default();
+    for change in &plan.changes {
+        if format == "diff" {
+            total_stats.add(&print_diff(
+                &change.path,
+                &change.old_content,
+                &change.new_content,
+            ));
+        } else if format == "summary" {
+            total_stats.add(&print_summary_diff(
+                &change.path,
+                &change.old_content,
+                &change.new_content,
+            ));
+        } else if !apply {
+            println!("Would modify: {}", change.path.display());
+        }
+    }
+
+    if apply {
+        let command = std::env::args().collect::<Vec<_>>().join(" ");
+        let written = rs_hack::execute::write_changes(
+            plan.changes,
+            "Batch",
+            local_state,
+            command,
+            ctx.backups.as_ref(),
         )?;
-        execute_operation(&files, op, apply, None, "default", false, None)?;
+        if format != "diff" && format != "summary" {
+            for change in &written.changes {
+                println!("✓ Modified: {}", change.path.display());
+            }
+        }
+        if let Some(run_id) = &written.run_id {
+            println!(
+                "\n📝 Run ID: {} (use 'rs-hack revert {}' to undo)",
+                run_id, run_id
+            );
+        }
+    }
+    if format == "diff" && show_summary {
+        total_stats.print_summary();
     }
     Ok(())
 }
 
 #[allow(clippy::too_many_arguments)]
 fn execute_operation_with_state(
+    ctx: &RunContext,
     files: &[PathBuf],
     op: &Operation,
     apply: bool,
     output: Option<&PathBuf>,
     local_state: &bool,
+    no_state: bool,
     format: &str,
     show_summary: bool,
     limit: Option<usize>,
 ) -> Result<()> {
-    let opts = rs_hack::execute::ExecuteOpts {
-        apply,
-        output: output.cloned(),
-        limit,
-    };
+    let apply = apply && !ctx.verify_idempotent;
+
+    // `--no-state` skips backup nodes and run metadata entirely; so does `--stdin`
+    if no_state || ctx.stdin_mode() {
+        return execute_operation(ctx, files, op, apply, output, format, show_summary, limit);
+    }
+
+    let opts = ctx.execute_opts(apply, output, limit);
 
     let command = std::env::args().collect::<Vec<_>>().join(" ");
+    if ctx.interactive {
+        if output.is_some() {
+            anyhow::bail!("--interactive edits files in place and can't be combined with --output");
+        }
+        let result = rs_hack::execute::execute_reviewed(
+            files,
+            op,
+            &opts,
+            *local_state,
+            command,
+            &mut review_hunk,
+        )?;
+        ctx.record_operation_report(files, op, true, &result);
+        ensure_some_parsed(files, &result.parse_errors)?;
+        if result.changes.is_empty() {
+            println!("\nNo changes applied");
+        }
+        render_execute_with_state_result(&result, op, format, show_summary);
+        return Ok(());
+    }
     let result = rs_hack::execute::execute_with_state(files, op, &opts, *local_state, command)?;
+    ctx.record_operation_report(files, op, apply, &result);
 
     // The lib falls back to plain `execute` (no state tracking) when the call
     // would not have written: dry runs and `--output` overrides. Match the
     // renderer to the path that actually ran so dry runs say "Would modify".
+    ensure_some_parsed(files, &result.parse_errors)?;
     if !apply || output.is_some() {
         render_execute_result(&result, op, format, show_summary, apply, output);
     } else {
@@ -4552,6 +8126,37 @@ fn execute_operation_with_state(
     Ok(())
 }
 
+/// `--interactive` prompt for one changed region.
+fn review_hunk(hunk: &rs_hack::execute::ChangeHunk) -> Result<rs_hack::execute::Review> {
+    use rs_hack::execute::Review;
+    use std::io::Write;
+
+    println!("\n{}:{}", hunk.path.display(), hunk.line);
+    for line in &hunk.diff {
+        match line.chars().next() {
+            Some('-') => println!("{}", rs_hack::color::paint(rs_hack::color::RED, line)),
+            Some('+') => println!("{}", rs_hack::color::paint(rs_hack::color::GREEN, line)),
+            _ => println!("{}", line),
+        }
+    }
+    loop {
+        print!("Apply this change? [y]es / [n]o / [a]ll / [q]uit: ");
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        if std::io::stdin().read_line(&mut answer)? == 0 {
+            // End of input: keep what was accepted so far
+            return Ok(Review::Quit);
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "y" | "yes" => return Ok(Review::Accept),
+            "n" | "no" => return Ok(Review::Skip),
+            "a" | "all" => return Ok(Review::AcceptAll),
+            "q" | "quit" => return Ok(Review::Quit),
+            _ => println!("Please answer y, n, a or q"),
+        }
+    }
+}
+
 fn render_execute_with_state_result(
     result: &rs_hack::execute::ExecuteResult,
     op: &Operation,
@@ -4570,10 +8175,9 @@ fn render_execute_with_state_result(
         } else {
             println!("✓ Modified: {}", change.path.display());
         }
-    }
-
-    for (path, err) in &result.parse_errors {
-        eprintln!("⚠️  Skipping {}: {}", path.display(), err);
+        if format != "diff" {
+            report_constructor_conversions(change, op);
+        }
     }
 
     if result.limit_hit {
@@ -4583,15 +8187,11 @@ fn render_execute_with_state_result(
         );
     }
 
-    if !result.parse_errors.is_empty() {
-        eprintln!(
-            "\n⚠️  {} file(s) skipped due to parse errors:",
-            result.parse_errors.len()
-        );
-        for (path, err) in &result.parse_errors {
-            eprintln!("   {} — {}", path.display(), err);
-        }
-    }
+    render_capped_files(&result.capped_files);
+    render_cfg_gated(result.cfg_gated_items);
+    render_skipped_matches(result.skipped_matches);
+
+    render_parse_errors(&result.parse_errors);
 
     if let Some(run_id) = &result.run_id {
         if format == "diff" && show_summary {
@@ -4601,7 +8201,12 @@ fn render_execute_with_state_result(
             "\n📝 Run ID: {} (use 'rs-hack revert {}' to undo)",
             run_id, run_id
         );
-    } else if result.changes.is_empty() {
+    } else if result.changes.is_empty()
+        && result.capped_files.is_empty()
+        && matches!(op, Operation::RemoveUnusedImports(_))
+    {
+        println!("No unused imports found");
+    } else if result.changes.is_empty() && result.capped_files.is_empty() {
         println!("No changes made - target not found in any files");
         if let Some(err) = &result.last_error {
             eprintln!("\n📋 Diagnostic: {}", err);
@@ -4616,3 +8221,109 @@ fn render_execute_with_state_result(
         render_unmatched_paths(&result.unmatched_qualified_paths);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Run a command line as `main` does.
+    fn run_cli(args: &[&std::ffi::OsStr]) -> Result<()> {
+        let args: Vec<std::ffi::OsString> = std::iter::once("rs-hack".as_ref())
+            .chain(args.iter().copied())
+            .map(std::ffi::OsStr::to_os_string)
+            .collect();
+        // The command line and `run` need more stack in a debug build than a test thread gets
+        std::thread::Builder::new()
+            .stack_size(64 << 20)
+            .spawn(move || run_reported(Cli::try_parse_from(args).unwrap(), &[]))
+            .unwrap()
+            .join()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_report_file() {
+        let temp_dir = TempDir::new().unwrap();
+        // SAFETY: no other test in this binary reads the environment
+        unsafe { std::env::set_var("HACK_STATE_DIR", temp_dir.path().join(".hack")) };
+        let file = temp_dir.path().join("config.rs");
+        std::fs::write(&file, "struct Config {\n    port: u16,\n}\n").unwrap();
+        let report = temp_dir.path().join("report.json");
+
+        let run_with = |field: &str| {
+            let outcome = run_cli(&[
+                "--report-file".as_ref(),
+                report.as_os_str(),
+                "add".as_ref(),
+                "--paths".as_ref(),
+                file.as_os_str(),
+                "--name".as_ref(),
+                "Config".as_ref(),
+                "--field".as_ref(),
+                field.as_ref(),
+                "--apply".as_ref(),
+            ]);
+            let text = std::fs::read_to_string(&report).unwrap();
+            (
+                outcome,
+                serde_json::from_str::<serde_json::Value>(&text).unwrap(),
+            )
+        };
+
+        let (outcome, json) = run_with("debug: bool");
+        outcome.unwrap();
+        assert_eq!(json["status"], "ok");
+        assert_eq!(json["exit_code"], 0);
+        assert_eq!(json["reason"], "1 modification(s) written to 1 file(s)");
+        let operation = &json["operations"][0];
+        assert_eq!(operation["operation"], "AddStructField");
+        assert_eq!(operation["outcome"], "applied");
+        assert_eq!(operation["files_scanned"], 1);
+        assert_eq!(operation["files_changed"], 1);
+        assert_eq!(operation["files"][0]["lines_added"], 1);
+        assert!(operation["run_id"].is_string());
+
+        // A failed run is reported with the error as its reason
+        let (outcome, json) = run_with("verbose: Vec<");
+        assert!(outcome.is_err());
+        assert_eq!(json["status"], "error");
+        assert_eq!(json["exit_code"], 1);
+        assert!(
+            json["reason"]
+                .as_str()
+                .unwrap()
+                .contains("Failed to parse field definition"),
+            "{}",
+            json["reason"]
+        );
+    }
+
+    #[test]
+    fn test_cfg_picks_the_active_definition() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("config.rs");
+        let source = "#[cfg(windows)]\nstruct Config {\n    a: u32,\n}\n\n#[cfg(unix)]\nstruct Config {\n    a: u32,\n}\n";
+        std::fs::write(&file, source).unwrap();
+
+        run_cli(&[
+            "--cfg".as_ref(),
+            "unix".as_ref(),
+            "--no-state".as_ref(),
+            "add".as_ref(),
+            "--paths".as_ref(),
+            file.as_os_str(),
+            "--name".as_ref(),
+            "Config".as_ref(),
+            "--field".as_ref(),
+            "b: u16".as_ref(),
+            "--apply".as_ref(),
+        ])
+        .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "#[cfg(windows)]\nstruct Config {\n    a: u32,\n}\n\n#[cfg(unix)]\nstruct Config {\n    a: u32,\n    b: u16,\n}\n"
+        );
+    }
+}
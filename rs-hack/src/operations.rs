@@ -18,6 +18,9 @@ pub enum EditMode {
     /// Reformat mode: use prettyplease to reformat the entire file
     /// Use this if you want consistent formatting across the file
     Reformat,
+    /// Rustfmt mode: format with the project's `rustfmt` (honoring its `rustfmt.toml`),
+    /// falling back to prettyplease when rustfmt isn't available
+    Rustfmt,
 }
 
 impl std::fmt::Display for EditMode {
@@ -25,6 +28,7 @@ impl std::fmt::Display for EditMode {
         match self {
             Self::Surgical => write!(f, "surgical"),
             Self::Reformat => write!(f, "reformat"),
+            Self::Rustfmt => write!(f, "rustfmt"),
         }
     }
 }
@@ -36,8 +40,9 @@ impl std::str::FromStr for EditMode {
         match s.to_lowercase().as_str() {
             "surgical" => Ok(Self::Surgical),
             "reformat" => Ok(Self::Reformat),
+            "rustfmt" => Ok(Self::Rustfmt),
             _ => Err(format!(
-                "Invalid edit mode: {}. Valid values are 'surgical' or 'reformat'",
+                "Invalid edit mode: {}. Valid values are 'surgical', 'reformat' or 'rustfmt'",
                 s
             )),
         }
@@ -48,6 +53,7 @@ impl std::str::FromStr for EditMode {
 #[serde(tag = "type")]
 pub enum Operation {
     AddStructField(AddStructFieldOp),
+    AddTupleField(AddTupleFieldOp),
     UpdateStructField(UpdateStructFieldOp),
     RemoveStructField(RemoveStructFieldOp),
     AddStructLiteralField(AddStructLiteralFieldOp),
@@ -58,11 +64,14 @@ pub enum Operation {
     UpdateMatchArm(UpdateMatchArmOp),
     RemoveMatchArm(RemoveMatchArmOp),
     AddImplMethod(AddImplMethodOp),
+    RemoveImplMethod(RemoveImplMethodOp),
     AddUseStatement(AddUseStatementOp),
     AddDerive(AddDeriveOp),
+    UpdateDerive(UpdateDeriveOp),
     Transform(TransformOp),
     RenameEnumVariant(RenameEnumVariantOp),
     RenameFunction(RenameFunctionOp),
+    RenameStructField(RenameStructFieldOp),
     AddDocComment(AddDocCommentOp),
     UpdateDocComment(UpdateDocCommentOp),
     RemoveDocComment(RemoveDocCommentOp),
@@ -70,6 +79,11 @@ pub enum Operation {
     AddCallArg(AddCallArgOp),
     UpdateCallArg(UpdateCallArgOp),
     RemoveCallArg(RemoveCallArgOp),
+    SyncMatchArms(SyncMatchArmsOp),
+    MultiTransform(MultiTransformOp),
+    AlignStructFields(AlignStructFieldsOp),
+    RemoveUnusedImports(RemoveUnusedImportsOp),
+    UpdateVisibility(UpdateVisibilityOp),
 }
 
 impl Operation {
@@ -78,6 +92,7 @@ impl Operation {
     pub const fn kind_name(&self) -> &'static str {
         match self {
             Self::AddStructField(_) => "AddStructField",
+            Self::AddTupleField(_) => "AddTupleField",
             Self::UpdateStructField(_) => "UpdateStructField",
             Self::RemoveStructField(_) => "RemoveStructField",
             Self::AddStructLiteralField(_) => "AddStructLiteralField",
@@ -89,10 +104,13 @@ impl Operation {
             Self::UpdateMatchArm(_) => "UpdateMatchArm",
             Self::RemoveMatchArm(_) => "RemoveMatchArm",
             Self::AddImplMethod(_) => "AddImplMethod",
+            Self::RemoveImplMethod(_) => "RemoveImplMethod",
             Self::AddUseStatement(_) => "AddUseStatement",
             Self::AddDerive(_) => "AddDerive",
+            Self::UpdateDerive(_) => "UpdateDerive",
             Self::Transform(_) => "Transform",
             Self::RenameFunction(_) => "RenameFunction",
+            Self::RenameStructField(_) => "RenameStructField",
             Self::AddDocComment(_) => "AddDocComment",
             Self::UpdateDocComment(_) => "UpdateDocComment",
             Self::RemoveDocComment(_) => "RemoveDocComment",
@@ -100,6 +118,11 @@ impl Operation {
             Self::AddCallArg(_) => "AddCallArg",
             Self::UpdateCallArg(_) => "UpdateCallArg",
             Self::RemoveCallArg(_) => "RemoveCallArg",
+            Self::SyncMatchArms(_) => "SyncMatchArms",
+            Self::MultiTransform(_) => "MultiTransform",
+            Self::AlignStructFields(_) => "AlignStructFields",
+            Self::RemoveUnusedImports(_) => "RemoveUnusedImports",
+            Self::UpdateVisibility(_) => "UpdateVisibility",
         }
     }
 }
@@ -115,6 +138,28 @@ pub struct AddStructFieldOp {
                                           * (idempotent), always updates literals */
     #[serde(default)]
     pub where_filter: Option<String>, // Optional: filter targets (e.g., "derives_trait:Clone")
+    #[serde(default)]
+    pub in_fn: Option<String>, // Optional: target a type local to this function's body
+}
+
+/// Add a field to a tuple struct (`struct Point(i32, i32)`), and a value to its
+/// `Point(x, y)` literals. Tuple fields have no names, so positions are field indexes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddTupleFieldOp {
+    pub struct_name: String,
+    pub field_type: String,       // e.g., "f32" or "pub String"
+    pub position: InsertPosition, // After/Before take a field index (e.g., "1")
+    #[serde(default)]
+    pub literal_default: Option<String>, // If provided: also added to each literal
+    /// How many fields the struct has before the change. Only a definition and literals with
+    /// that many are changed, so a second run doesn't add the field again. Read from the
+    /// definition in the file when not given
+    #[serde(default)]
+    pub arity: Option<usize>,
+    #[serde(default)]
+    pub where_filter: Option<String>, // Optional: filter targets (e.g., "derives_trait:Clone")
+    #[serde(default)]
+    pub in_fn: Option<String>, // Optional: target a type local to this function's body
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -123,6 +168,8 @@ pub struct UpdateStructFieldOp {
     pub field_def: String, // e.g., "field_name: NewType" (field name is parsed from this)
     #[serde(default)]
     pub where_filter: Option<String>, // Optional: filter targets (e.g., "derives_trait:Clone")
+    #[serde(default)]
+    pub in_fn: Option<String>, // Optional: target a type local to this function's body
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -133,6 +180,8 @@ pub struct RemoveStructFieldOp {
     pub literal_only: bool, // If true, only remove from struct literals, not the definition
     #[serde(default)]
     pub where_filter: Option<String>, // Optional: filter targets (e.g., "derives_trait:Clone")
+    #[serde(default)]
+    pub in_fn: Option<String>, // Optional: target a type local to this function's body
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -142,6 +191,10 @@ pub struct AddStructLiteralFieldOp {
     pub position: InsertPosition,
     #[serde(default)]
     pub struct_path: Option<String>, // Optional canonical path (e.g., "crate::types::Rectangle")
+    /// Fields to descend through before inserting: `["inner"]` puts the field into the
+    /// `inner: Inner { .. }` literal nested inside each matched literal
+    #[serde(default)]
+    pub field_path: Vec<String>,
 }
 
 /// Add or set the base expression (..expr) on struct literals
@@ -163,6 +216,8 @@ pub struct AddEnumVariantOp {
     pub position: InsertPosition,
     #[serde(default)]
     pub where_filter: Option<String>, // Optional: filter targets (e.g., "derives_trait:Clone")
+    #[serde(default)]
+    pub in_fn: Option<String>, // Optional: target a type local to this function's body
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -172,6 +227,8 @@ pub struct UpdateEnumVariantOp {
                               * from this) */
     #[serde(default)]
     pub where_filter: Option<String>, // Optional: filter targets (e.g., "derives_trait:Clone")
+    #[serde(default)]
+    pub in_fn: Option<String>, // Optional: target a type local to this function's body
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -180,6 +237,8 @@ pub struct RemoveEnumVariantOp {
     pub variant_name: String, // Name of the variant to remove
     #[serde(default)]
     pub where_filter: Option<String>, // Optional: filter targets (e.g., "derives_trait:Clone")
+    #[serde(default)]
+    pub in_fn: Option<String>, // Optional: target a type local to this function's body
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -192,6 +251,36 @@ pub struct AddMatchArmOp {
     pub enum_name: Option<String>,     // Enum name for auto-detection
 }
 
+/// Reconcile match expressions over an enum with its current variants: add arms for missing
+/// variants and (with `prune`) drop arms for variants that no longer exist.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncMatchArmsOp {
+    pub enum_name: String,
+    pub body: String,                  // Body for added arms, e.g., "todo!()"
+    pub function_name: Option<String>, // Optional: specific function containing match
+    #[serde(default)]
+    pub variants: Vec<String>, // Current variants; looked up in the file when empty
+    #[serde(default)]
+    pub prune: bool, // Remove arms that only reference non-existent variants
+}
+
+/// Pad the space after each field's `:` so a struct's field types line up in one column.
+/// Only whitespace between the colon and the type changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlignStructFieldsOp {
+    pub struct_name: String,
+    #[serde(default)]
+    pub in_fn: Option<String>, // Optional: target a type local to this function's body
+}
+
+/// Remove the private `use` imports each file never refers to, pruning just the unused
+/// members of a group. Globs, and names only seen in macros or that may be traits, are kept.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RemoveUnusedImportsOp {
+    #[serde(default)]
+    pub traits: Vec<String>, // Traits defined across the scanned files; imports of these are kept
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateMatchArmOp {
     pub pattern: String,               // Pattern to find (e.g., "MyEnum::Variant")
@@ -210,6 +299,18 @@ pub struct AddImplMethodOp {
     pub target: String,     // e.g., "MyStruct" or "impl MyTrait for MyStruct"
     pub method_def: String, // Full method definition
     pub position: InsertPosition,
+    #[serde(default)]
+    pub trait_name: Option<String>, // Optional: `impl <trait_name> for <target>` (default: inherent impl)
+    #[serde(default)]
+    pub edit_mode: EditMode, // How the impl block is re-rendered (surgical = prettyplease)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoveImplMethodOp {
+    pub target: String,      // Type whose impl blocks are searched (e.g., "MyStruct")
+    pub method_name: String, // Method to remove
+    #[serde(default)]
+    pub trait_name: Option<String>, // Optional: only `impl <trait_name> for <target>`
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -225,6 +326,38 @@ pub struct AddDeriveOp {
     pub derives: Vec<String>, // e.g., ["Clone", "Debug", "Serialize"]
     #[serde(default)]
     pub where_filter: Option<String>, // Optional: filter targets (e.g., "derives_trait:Clone")
+    #[serde(default)]
+    pub derive_order: Option<Vec<String>>, // Optional: sort the whole list (unlisted go last, A-Z)
+    #[serde(default)]
+    pub edit_mode: EditMode, // How the item is re-rendered (surgical = prettyplease)
+}
+
+/// Replace a struct's or enum's derive list with exactly `derives` (in that order), and/or
+/// sort it alphabetically.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateDeriveOp {
+    pub target_name: String, // Name of struct or enum
+    pub target_type: String, // "struct" or "enum"
+    #[serde(default)]
+    pub derives: Option<Vec<String>>, // Optional: the new list; keeps the existing one if None
+    #[serde(default)]
+    pub sort: bool, // Sort the list alphabetically (by last path segment)
+    #[serde(default)]
+    pub where_filter: Option<String>, // Optional: filter targets (e.g., "derives_trait:Clone")
+    #[serde(default)]
+    pub edit_mode: EditMode, // How the item is re-rendered (surgical = prettyplease)
+}
+
+/// Set the visibility of matching definitions: the existing `pub`/`pub(..)` is replaced, or
+/// the new one inserted before the keyword of a private item.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateVisibilityOp {
+    pub node_types: Vec<String>, // Definition node types to match (see VISIBILITY_NODE_TYPES)
+    #[serde(default)]
+    pub name: Option<String>, // Exact name to match
+    #[serde(default)]
+    pub name_regex: Option<String>, // Or a regex on the name (see name_matches)
+    pub visibility: String,      // e.g. "pub", "pub(crate)"; empty makes the items private
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -238,7 +371,59 @@ pub enum InsertPosition {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BatchSpec {
     pub base_path: PathBuf,
-    pub operations: Vec<Operation>,
+    pub operations: Vec<BatchOperation>,
+}
+
+/// One operation of a `BatchSpec`: the operation's own fields (with its `type`), plus an
+/// optional `when` condition.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchOperation {
+    #[serde(flatten)]
+    pub op: Operation,
+    /// Only run the operation if this holds, checked against the files as the operations
+    /// before it left them. Otherwise it is skipped and reported with the reason
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<BatchCondition>,
+}
+
+impl From<Operation> for BatchOperation {
+    fn from(op: Operation) -> Self {
+        Self { op, when: None }
+    }
+}
+
+/// `when` clause of a batch operation, a `find` query:
+/// `when: { node_type: struct, name: Config, exists: true }`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchCondition {
+    /// Node type as for `find --node-type` (any type when left out)
+    #[serde(default)]
+    pub node_type: Option<String>,
+    /// Name as for `find --name` (any name when left out)
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Whether the query must match something (`true`, the default) or nothing (`false`)
+    #[serde(default = "default_exists")]
+    pub exists: bool,
+}
+
+const fn default_exists() -> bool {
+    true
+}
+
+impl BatchCondition {
+    /// Why the condition doesn't hold, e.g. "no struct `Config` found".
+    pub fn failure_reason(&self) -> String {
+        let mut query = self.node_type.as_deref().unwrap_or("node").to_string();
+        if let Some(name) = &self.name {
+            query = format!("{} `{}`", query, name);
+        }
+        if self.exists {
+            format!("no {} found", query)
+        } else {
+            format!("{} already exists", query)
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -249,6 +434,14 @@ pub struct NodeLocation {
     pub end_column: usize,
 }
 
+impl NodeLocation {
+    /// Some operations record a placeholder (line 0, or an empty span at 1:0) for nodes they
+    /// changed in many places.
+    pub fn is_placeholder(&self) -> bool {
+        self.line == 0 || (self.line, self.column, self.end_line, self.end_column) == (1, 0, 1, 0)
+    }
+}
+
 /// Backup of a single AST node before modification
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackupNode {
@@ -278,6 +471,31 @@ pub struct InspectResult {
     pub snippet: String, // Formatted code snippet
     #[serde(skip_serializing_if = "Option::is_none")]
     pub preceding_comment: Option<String>, // Doc comments + regular comments before the node
+    /// Parameters of a `function`, `impl-method` or `trait-method` match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arity: Option<Arity>,
+}
+
+/// How many parameters a function or method signature takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Arity {
+    /// Every parameter, a `self` receiver included
+    pub params: usize,
+    pub has_receiver: bool,
+}
+
+impl Arity {
+    pub fn of(sig: &syn::Signature) -> Self {
+        Self {
+            params: sig.inputs.len(),
+            has_receiver: sig.receiver().is_some(),
+        }
+    }
+
+    /// The parameter count, leaving out the `self` receiver with `exclude_self`.
+    pub const fn count(&self, exclude_self: bool) -> usize {
+        self.params - (exclude_self && self.has_receiver) as usize
+    }
 }
 
 /// Generic transformation operation
@@ -287,15 +505,91 @@ pub struct TransformOp {
     pub name_filter: Option<String>,    // Filter by name (e.g., "eprintln")
     pub content_filter: Option<String>, // Filter by content (e.g., "[SHADOW RENDER]")
     pub action: TransformAction,        // What to do with matching nodes
+    /// Skip nodes whose own name matches one of these globs
+    #[serde(default)]
+    pub exclude_names: Vec<String>,
+    /// Skip nodes inside a function/type/module whose name matches one of these globs
+    #[serde(default)]
+    pub exclude_in: Vec<String>,
+    /// Only nodes whose name matches this regex (alternative to `name_filter`; see
+    /// `name_matches`)
+    #[serde(default)]
+    pub name_regex: Option<String>,
+}
+
+/// Compile a `--name-regex` pattern. Unanchored, like grep: use `^...$` for whole names.
+pub fn compile_name_regex(pattern: &str) -> anyhow::Result<regex::Regex> {
+    regex::Regex::new(pattern)
+        .map_err(|e| anyhow::anyhow!("Invalid --name-regex '{}': {}", pattern, e))
+}
+
+/// Whether `identifier` or its last `::` segment matches `regex`, so `^debug_` picks
+/// `Foo::debug_dump` the way `--name debug_dump` would.
+pub fn name_matches(regex: &regex::Regex, identifier: &str) -> bool {
+    let last = identifier.rsplit("::").next().unwrap_or(identifier);
+    regex.is_match(identifier) || regex.is_match(last)
+}
+
+/// Several transforms applied in order to each file as a single operation (one run id).
+/// This is also the format of a `transform --spec` file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiTransformOp {
+    pub transforms: Vec<TransformOp>,
 }
 
 /// Actions that can be performed on AST nodes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum TransformAction {
-    Comment,                  // Wrap in // comment
-    Remove,                   // Delete the node entirely
-    Replace { with: String }, // Replace with provided code
+    /// Wrap in a `//` comment
+    Comment,
+    /// Delete the node entirely
+    Remove,
+    /// Replace with the provided code
+    Replace { with: String },
+    /// Wrap each matched function call, method call or struct literal in `with`, where `{}`
+    /// stands for the node's source: `instrument({})` turns `compute()` into
+    /// `instrument(compute())`. Only the outermost of nested matches is wrapped
+    Wrap { with: String },
+    /// Rewrite `let x = if let P(v) = e { v } else { <diverge> };` into
+    /// `let P(x) = e else { <diverge> };` inside the matched nodes
+    ToLetElse,
+    /// Rewrite `format!("{}", x)` into `x.to_string()` inside the matched nodes
+    SimplifyFormat,
+    /// Keep the matched code in builds where `cfg` holds: statements are wrapped in
+    /// `if cfg!(<cfg>) { ... }`, definitions get a `#[cfg(<cfg>)]` attribute
+    CfgGuard { cfg: String },
+    /// Replace struct literals with a call to an associated constructor that takes the
+    /// fields positionally: `Config { a, b }` becomes `Config::new(a, b)`. `fields` is the
+    /// declaration order; when empty, it's read from the struct definition in the same file
+    ToConstructor {
+        constructor: String,
+        #[serde(default)]
+        fields: Vec<String>,
+    },
+    /// Rewrite method calls into trait-qualified calls: `x.into()` becomes
+    /// `Into::<Foo>::into(x)` with `trait_path: "Into"`, `to_type: Some("Foo")`. The
+    /// receiver is passed as written, so a call relying on auto-ref needs it added by hand.
+    /// `receiver` limits the rewrite to calls whose receiver is exactly that expression
+    ToUfcs {
+        trait_path: String,
+        #[serde(default)]
+        to_type: Option<String>,
+        #[serde(default)]
+        receiver: Option<String>,
+    },
+    /// Regex substitution inside each matched node's source only: every match of `pattern`
+    /// becomes `with`, where `$1` or `$name` stand for capture groups. A node nested in
+    /// another match is rewritten once, as part of the outer one, and the file must still
+    /// parse afterwards
+    ReplaceRegex { pattern: String, with: String },
+    /// Rename each matched definition in its signature only: `fn old(..)` becomes
+    /// `fn new_name(..)`, leaving its body, attributes and every use as they are. Works on
+    /// the node types in `editor::RENAME_SIGNATURE_NODE_TYPES`
+    RenameSignature { new_name: String },
+    /// Replace each node with what a Rhai script evaluates to, given the node's source
+    /// (requires the `script-hooks` feature)
+    Script { script: std::path::PathBuf },
 }
 
 /// Rename an enum variant across the codebase
@@ -311,6 +605,22 @@ pub struct RenameEnumVariantOp {
     pub edit_mode: EditMode, // How to apply changes (surgical vs reformat)
 }
 
+/// Rename a named struct field in its definition and in struct literals
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenameStructFieldOp {
+    pub struct_name: String, // Struct (or union) that owns the field (e.g., "Config")
+    pub old_name: String,    // Current field name (e.g., "timeout")
+    pub new_name: String,    // New field name (e.g., "timeout_secs")
+    /// Keep the serde wire name stable: an existing `#[serde(rename = "...")]` is left as
+    /// is, and a field without one gets `#[serde(rename = "<old wire name>")]`
+    #[serde(default)]
+    pub serde_aware: bool,
+    /// With `serde_aware`: let the wire name follow the rename instead (an existing
+    /// `rename = "..."` is set to the new field name, and no attribute is added)
+    #[serde(default)]
+    pub update_serde_name: bool,
+}
+
 /// Rename a function across the codebase
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RenameFunctionOp {
@@ -320,13 +630,19 @@ pub struct RenameFunctionOp {
     pub function_path: Option<String>, // Optional canonical path (e.g., "crate::utils::process_v2")
     #[serde(default)]
     pub edit_mode: EditMode, // How to apply changes (surgical vs reformat)
+    #[serde(default)]
+    pub receiver_type: Option<String>, /* Optional: only rename the inherent method on this type
+                           * and calls whose receiver is inferred to be it */
+    #[serde(default)]
+    pub keep_link_name: bool, /* Extern-block declarations get #[link_name = "<old>"], so they
+                               * still link the old symbol */
 }
 
 /// Add documentation comment to an item
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AddDocCommentOp {
-    pub target_type: String, // "struct", "enum", "function", "field", "variant"
-    pub name: String,        // Name of the target (e.g., "User", "Status::Draft")
+    pub target_type: String, // "struct", "enum", "function", "impl-method", "trait-method"
+    pub name: String,        // Name of the target (e.g., "User", "Config::new")
     pub doc_comment: String, // Documentation text (without /// prefix)
     #[serde(default)]
     pub style: DocCommentStyle, // Line (///) or Block (/** */)
@@ -335,7 +651,7 @@ pub struct AddDocCommentOp {
 /// Update existing documentation comment
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateDocCommentOp {
-    pub target_type: String, // "struct", "enum", "function", "field", "variant"
+    pub target_type: String, // "struct", "enum", "function", "impl-method", "trait-method"
     pub name: String,        // Name of the target
     pub doc_comment: String, // New documentation text
 }
@@ -343,7 +659,7 @@ pub struct UpdateDocCommentOp {
 /// Remove documentation comment from an item
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RemoveDocCommentOp {
-    pub target_type: String, // "struct", "enum", "function", "field", "variant"
+    pub target_type: String, // "struct", "enum", "function", "impl-method", "trait-method"
     pub name: String,        // Name of the target
 }
 
@@ -380,6 +696,15 @@ pub struct FieldLocation {
     pub context: FieldContext,
 }
 
+/// A `receiver.field` access, as found by `RustEditor::field_accesses`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldAccess {
+    pub location: NodeLocation, // Span of the field name
+    /// Type the receiver is known to have, when that's clear without type inference
+    pub receiver_type: Option<String>,
+    pub code: String, // The access expression on one line (e.g., "cfg.timeout")
+}
+
 /// Insert position for call arguments (numeric since args are positional)
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub enum ArgPosition {
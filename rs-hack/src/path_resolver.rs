@@ -203,6 +203,72 @@ impl PathResolver {
     }
 }
 
+/// Check if `file` brings `member` of a parent item into scope under its own name, via
+/// `use Parent::member;` (or a group containing it) or `use Parent::*;`.
+///
+/// `is_parent` decides whether a use-path prefix names the parent, so the caller picks
+/// how strict that is (a bare name, or a `PathResolver` check). Renamed imports
+/// (`use Parent::member as m;`) don't count: the bare name in scope is the alias.
+///
+/// Used for enum variants, which `use Enum::*;` lets code write unqualified.
+pub fn imports_member_unqualified(
+    file: &File,
+    member: &str,
+    is_parent: impl Fn(&Path) -> bool,
+) -> bool {
+    struct MemberImportScanner<'a, F> {
+        member: &'a str,
+        is_parent: F,
+        found: bool,
+    }
+
+    impl<F: Fn(&Path) -> bool> MemberImportScanner<'_, F> {
+        fn process_use_tree(&mut self, tree: &UseTree, prefix: &mut Path) {
+            match tree {
+                UseTree::Path(path) => {
+                    prefix.segments.push(path.ident.clone().into());
+                    self.process_use_tree(&path.tree, prefix);
+                    prefix.segments.pop();
+                }
+                UseTree::Name(name) => {
+                    if name.ident == self.member && (self.is_parent)(prefix) {
+                        self.found = true;
+                    }
+                }
+                UseTree::Glob(_) => {
+                    if !prefix.segments.is_empty() && (self.is_parent)(prefix) {
+                        self.found = true;
+                    }
+                }
+                UseTree::Group(group) => {
+                    for tree in &group.items {
+                        self.process_use_tree(tree, prefix);
+                    }
+                }
+                UseTree::Rename(_) => {}
+            }
+        }
+    }
+
+    impl<'ast, F: Fn(&Path) -> bool> Visit<'ast> for MemberImportScanner<'_, F> {
+        fn visit_item_use(&mut self, node: &'ast ItemUse) {
+            let mut prefix = Path {
+                leading_colon: node.leading_colon,
+                segments: syn::punctuated::Punctuated::new(),
+            };
+            self.process_use_tree(&node.tree, &mut prefix);
+        }
+    }
+
+    let mut scanner = MemberImportScanner {
+        member,
+        is_parent,
+        found: false,
+    };
+    scanner.visit_file(file);
+    scanner.found
+}
+
 /// Visitor that scans use statements to build the alias map.
 struct UseStatementScanner<'a> {
     target_canonical_segments: &'a [String],
@@ -404,4 +470,22 @@ mod tests {
         let path: Path = parse_quote!(IRValue);
         assert!(resolver.matches_target(&path));
     }
+
+    #[test]
+    fn test_imports_member_unqualified() {
+        let file: File = parse_quote! {
+            use crate::model::Status::*;
+            use crate::other::Kind::{Open, Closed as Shut};
+        };
+        let status = PathResolver::new("crate::model::Status").unwrap();
+        assert!(imports_member_unqualified(&file, "Draft", |p| status.matches_target(p)));
+
+        let kind = PathResolver::new("crate::other::Kind").unwrap();
+        assert!(imports_member_unqualified(&file, "Open", |p| kind.matches_target(p)));
+        // Only reachable through its alias
+        assert!(!imports_member_unqualified(&file, "Closed", |p| kind.matches_target(p)));
+        // Neither import is from this parent
+        let other = PathResolver::new("crate::model::Kind").unwrap();
+        assert!(!imports_member_unqualified(&file, "Open", |p| other.matches_target(p)));
+    }
 }
@@ -0,0 +1,72 @@
+//! `--edit-mode rustfmt`: format edited code with the project's own `rustfmt`, so its
+//! `rustfmt.toml` applies, falling back to prettyplease when rustfmt can't be used.
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::Once;
+
+use anyhow::{Context, Result, bail};
+
+static FALLBACK_WARNING: Once = Once::new();
+
+/// Format `source` with `rustfmt --emit stdout`.
+///
+/// The code is piped in, so rustfmt picks up `rustfmt.toml` the way it does for stdin: from
+/// the current directory and its parents. The edition comes from the nearest `Cargo.toml`
+/// (2021 when there is none).
+pub fn format_source(source: &str) -> Result<String> {
+    let edition = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| manifest_edition(&cwd))
+        .unwrap_or_else(|| "2021".to_string());
+
+    let mut child = Command::new("rustfmt")
+        .args(["--emit", "stdout", "--edition", &edition])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run rustfmt (is it on PATH?)")?;
+    child
+        .stdin
+        .take()
+        .context("Failed to open rustfmt's stdin")?
+        .write_all(source.as_bytes())
+        .context("Failed to write to rustfmt")?;
+
+    let output = child
+        .wait_with_output()
+        .context("Failed to wait for rustfmt")?;
+    if !output.status.success() {
+        bail!(
+            "rustfmt failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    String::from_utf8(output.stdout).context("rustfmt produced invalid UTF-8")
+}
+
+/// `format_source`, or `fallback()` (prettyplease) when rustfmt can't be run or rejects the
+/// code. The fallback is warned about once per run.
+pub fn format_or_else(source: &str, fallback: impl FnOnce() -> String) -> String {
+    format_source(source).unwrap_or_else(|e| {
+        FALLBACK_WARNING.call_once(|| {
+            eprintln!("Warning: {:#}; formatting with prettyplease instead", e);
+        });
+        fallback()
+    })
+}
+
+/// The `edition = "..."` of the nearest `Cargo.toml` at or above `start` that sets one, so
+/// a member using `edition.workspace = true` gets the workspace's.
+fn manifest_edition(start: &Path) -> Option<String> {
+    start.ancestors().find_map(|dir| {
+        let manifest = std::fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+        manifest.lines().find_map(|line| {
+            let value = line.trim().strip_prefix("edition")?.trim_start();
+            let value = value.strip_prefix('=')?.trim();
+            Some(value.strip_prefix('"')?.split('"').next()?.to_string())
+        })
+    })
+}
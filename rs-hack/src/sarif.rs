@@ -0,0 +1,171 @@
+//! Minimal SARIF 2.1.0 serializer for `find --format sarif`.
+//!
+//! Lets find audits (unwrap usage, panics, ...) be uploaded to GitHub code scanning and show
+//! up as PR annotations. Only the parts code scanning reads are emitted: the tool's rules, and one result per
+//! match with a message and a physical location.
+
+use serde::Serialize;
+
+use crate::operations::InspectResult;
+
+pub const SARIF_VERSION: &str = "2.1.0";
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+#[derive(Debug, Serialize)]
+pub struct SarifLog {
+    #[serde(rename = "$schema")]
+    pub schema: &'static str,
+    pub version: &'static str,
+    pub runs: Vec<SarifRun>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SarifRun {
+    pub tool: SarifTool,
+    pub results: Vec<SarifResult>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SarifTool {
+    pub driver: SarifDriver,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifDriver {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub information_uri: &'static str,
+    pub rules: Vec<SarifRule>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifRule {
+    pub id: String,
+    pub short_description: SarifMessage,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SarifMessage {
+    pub text: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifResult {
+    pub rule_id: String,
+    pub level: &'static str,
+    pub message: SarifMessage,
+    pub locations: Vec<SarifLocation>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifLocation {
+    pub physical_location: SarifPhysicalLocation,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifPhysicalLocation {
+    pub artifact_location: SarifArtifactLocation,
+    pub region: SarifRegion,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SarifArtifactLocation {
+    pub uri: String,
+}
+
+/// Lines and columns are 1-based in SARIF.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifRegion {
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+/// Rule id for a match: the node type, plus the `--name` query when there is one
+/// (e.g. `method-call/unwrap`).
+pub fn rule_id(node_type: &str, query: Option<&str>) -> String {
+    query.map_or_else(
+        || node_type.to_string(),
+        |query| format!("{}/{}", node_type, query),
+    )
+}
+
+/// Build a SARIF log with one result per match. `query` is the `--name` filter, if any.
+pub fn from_find_results(results: &[InspectResult], query: Option<&str>) -> SarifLog {
+    let mut rules: Vec<SarifRule> = Vec::new();
+    let mut sarif_results = Vec::with_capacity(results.len());
+
+    for result in results {
+        let id = rule_id(&result.node_type, query);
+        if !rules.iter().any(|rule| rule.id == id) {
+            let text = query.map_or_else(
+                || format!("rs-hack find: {} nodes", result.node_type),
+                |query| {
+                    format!(
+                        "rs-hack find: {} nodes matching `{}`",
+                        result.node_type, query
+                    )
+                },
+            );
+            rules.push(SarifRule {
+                id: id.clone(),
+                short_description: SarifMessage { text },
+            });
+        }
+
+        let first_line = result.snippet.lines().next().unwrap_or_default().trim();
+        sarif_results.push(SarifResult {
+            rule_id: id,
+            level: "warning",
+            message: SarifMessage {
+                text: format!(
+                    "{} `{}`: {}",
+                    result.node_type, result.identifier, first_line
+                ),
+            },
+            locations: vec![SarifLocation {
+                physical_location: SarifPhysicalLocation {
+                    artifact_location: SarifArtifactLocation {
+                        uri: artifact_uri(&result.file_path),
+                    },
+                    region: SarifRegion {
+                        start_line: result.location.line,
+                        start_column: result.location.column + 1,
+                        end_line: result.location.end_line,
+                        end_column: result.location.end_column + 1,
+                    },
+                },
+            }],
+        });
+    }
+
+    SarifLog {
+        schema: SARIF_SCHEMA,
+        version: SARIF_VERSION,
+        runs: vec![SarifRun {
+            tool: SarifTool {
+                driver: SarifDriver {
+                    name: "rs-hack",
+                    version: env!("CARGO_PKG_VERSION"),
+                    information_uri: "https://github.com/1e1f/rs-hack",
+                    rules,
+                },
+            },
+            results: sarif_results,
+        }],
+    }
+}
+
+/// Relative, forward-slash URI as code scanning expects (paths are taken as given, relative
+/// to where `find` ran).
+pub(crate) fn artifact_uri(file_path: &str) -> String {
+    let uri = file_path.replace('\\', "/");
+    uri.trim_start_matches("./").to_string()
+}
@@ -215,6 +215,12 @@ pub fn restore_from_nodes(file_path: &Path, nodes: &[BackupNode], _state_dir: &P
         return Ok(());
     }
 
+    // Whole-file backups (commands that rewrite several files at once, like `move`)
+    if let Some(backup) = nodes.iter().find(|b| b.node_type == "file") {
+        return fs::write(file_path, &backup.original_content)
+            .with_context(|| format!("Failed to write restored file: {}", file_path.display()));
+    }
+
     // Read current file content
     let content = fs::read_to_string(file_path)
         .with_context(|| format!("Failed to read file for revert: {}", file_path.display()))?;
@@ -222,9 +228,19 @@ pub fn restore_from_nodes(file_path: &Path, nodes: &[BackupNode], _state_dir: &P
     // Parse into AST
     let mut editor = RustEditor::new(&content)?;
 
+    // Renamed field accesses and patterns are located as they are after the operation, so
+    // they go first, from the end of the file back, while those locations still hold
+    let (mut field_references, nodes): (Vec<&BackupNode>, Vec<&BackupNode>) =
+        nodes.iter().partition(|b| b.node_type == "field-reference");
+    field_references.sort_by_key(|b| std::cmp::Reverse((b.location.line, b.location.column)));
+    for backup in field_references {
+        restore_field_reference(&mut editor, backup)?;
+    }
+
     // Separate struct-literal backups from others (they need special ordering)
-    let (mut struct_literal_backups, other_backups): (Vec<_>, Vec<_>) =
-        nodes.iter().partition(|b| b.node_type == "struct-literal");
+    let (mut struct_literal_backups, other_backups): (Vec<_>, Vec<_>) = nodes
+        .into_iter()
+        .partition(|b| b.node_type == "struct-literal");
 
     // Sort struct-literal backups by counter in REVERSE order (process from end of file to
     // beginning) This ensures byte offsets remain valid as we restore
@@ -258,6 +274,9 @@ pub fn restore_from_nodes(file_path: &Path, nodes: &[BackupNode], _state_dir: &P
             "ItemEnum" | "enum" => {
                 restore_enum(&mut editor, backup)?;
             }
+            "union" => {
+                restore_union(&mut editor, backup)?;
+            }
             "ItemImpl" => {
                 restore_impl(&mut editor, backup)?;
             }
@@ -302,12 +321,25 @@ fn restore_struct(editor: &mut crate::editor::RustEditor, backup: &BackupNode) -
         parse_str(&backup.original_content).context("Failed to parse backup struct content")?;
 
     // Find the struct in the current AST by name
-    let struct_index = editor
-        .find_item_index("struct", &backup.identifier)
+    let struct_path = editor
+        .find_item_path("struct", &backup.identifier)
         .with_context(|| format!("Struct '{}' not found for revert", backup.identifier))?;
 
     // Replace with the backup using the editor's method
-    editor.replace_item_at_index(struct_index, backup_item)?;
+    editor.replace_item_at_path(&struct_path, backup_item)?;
+
+    Ok(())
+}
+
+fn restore_union(editor: &mut crate::editor::RustEditor, backup: &BackupNode) -> Result<()> {
+    use syn::{Item, parse_str};
+
+    let backup_item: Item =
+        parse_str(&backup.original_content).context("Failed to parse backup union content")?;
+    let union_path = editor
+        .find_item_path("union", &backup.identifier)
+        .with_context(|| format!("Union '{}' not found for revert", backup.identifier))?;
+    editor.replace_item_at_path(&union_path, backup_item)?;
 
     Ok(())
 }
@@ -320,12 +352,12 @@ fn restore_enum(editor: &mut crate::editor::RustEditor, backup: &BackupNode) ->
         parse_str(&backup.original_content).context("Failed to parse backup enum content")?;
 
     // Find the enum in the current AST by name
-    let enum_index = editor
-        .find_item_index("enum", &backup.identifier)
+    let enum_path = editor
+        .find_item_path("enum", &backup.identifier)
         .with_context(|| format!("Enum '{}' not found for revert", backup.identifier))?;
 
     // Replace with the backup
-    editor.replace_item_at_index(enum_index, backup_item)?;
+    editor.replace_item_at_path(&enum_path, backup_item)?;
 
     Ok(())
 }
@@ -338,8 +370,8 @@ fn restore_impl(editor: &mut crate::editor::RustEditor, backup: &BackupNode) ->
         parse_str(&backup.original_content).context("Failed to parse backup impl content")?;
 
     // Find impl block by matching on the self_ty
-    let impl_index = editor
-        .find_item_index("impl", &backup.identifier)
+    let impl_path = editor
+        .find_item_path("impl", &backup.identifier)
         .with_context(|| {
             format!(
                 "Impl block for '{}' not found for revert",
@@ -348,7 +380,7 @@ fn restore_impl(editor: &mut crate::editor::RustEditor, backup: &BackupNode) ->
         })?;
 
     // Replace with the backup
-    editor.replace_item_at_index(impl_index, backup_item)?;
+    editor.replace_item_at_path(&impl_path, backup_item)?;
 
     Ok(())
 }
@@ -361,16 +393,53 @@ fn restore_function(editor: &mut crate::editor::RustEditor, backup: &BackupNode)
         parse_str(&backup.original_content).context("Failed to parse backup function content")?;
 
     // Find the function in the current AST by name
-    let fn_index = editor
-        .find_item_index("fn", &backup.identifier)
+    let fn_path = editor
+        .find_item_path("fn", &backup.identifier)
         .with_context(|| format!("Function '{}' not found for revert", backup.identifier))?;
 
     // Replace with the backup
-    editor.replace_item_at_index(fn_index, backup_item)?;
+    editor.replace_item_at_path(&fn_path, backup_item)?;
 
     Ok(())
 }
 
+/// Put back the original text of a renamed field access or pattern field. The identifier is
+/// `Struct.new_name`; the text at the recorded location must still start with the new name.
+fn restore_field_reference(
+    editor: &mut crate::editor::RustEditor,
+    backup: &BackupNode,
+) -> Result<()> {
+    use proc_macro2::LineColumn;
+
+    let new_name = backup
+        .identifier
+        .rsplit('.')
+        .next()
+        .context("Invalid field reference identifier")?;
+    let start = editor.span_to_byte_offset(LineColumn {
+        line: backup.location.line,
+        column: backup.location.column,
+    });
+    let end = editor.span_to_byte_offset(LineColumn {
+        line: backup.location.end_line,
+        column: backup.location.end_column,
+    });
+    let current = editor.to_string();
+    match current.get(start..end) {
+        Some(text) if text.starts_with(new_name) => {
+            editor.replace_range(start, end, &backup.original_content)
+        }
+        // Edited since; leave it rather than guess
+        _ => {
+            eprintln!(
+                "Warning: {} at line {} changed since the rename; not reverted",
+                backup.identifier, backup.location.line
+            );
+            Ok(())
+        }
+    }
+}
+
 fn restore_struct_literal(
     editor: &mut crate::editor::RustEditor,
     backup: &BackupNode,
@@ -496,8 +565,31 @@ pub fn load_run_metadata(run_id: &str, state_dir: &Path) -> Result<RunMetadata>
     Ok(metadata)
 }
 
-/// Revert a run
-pub fn revert_run(run_id: &str, force: bool, state_dir: &Path) -> Result<()> {
+/// A file left untouched by a revert because it no longer matches the run's result
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedFile {
+    pub path: PathBuf,
+    /// "missing" or "hash-mismatch"
+    pub reason: String,
+    pub expected_hash: Option<String>,
+    pub current_hash: Option<String>,
+}
+
+/// Outcome of a revert, for `revert --format json`
+#[derive(Debug, Clone, Serialize)]
+pub struct RevertReport {
+    pub run_id: String,
+    /// "reverted", or "aborted" when files were skipped and nothing was restored
+    pub status: String,
+    pub reverted: Vec<PathBuf>,
+    pub skipped: Vec<SkippedFile>,
+}
+
+/// Revert a run, reporting what was restored instead of printing it.
+///
+/// Without `force`, every file is checked first; if any is missing or has changed since the
+/// run, nothing is restored and the report lists those files as skipped.
+pub fn revert_run_report(run_id: &str, force: bool, state_dir: &Path) -> Result<RevertReport> {
     // Load run metadata
     let run = load_run_metadata(run_id, state_dir)?;
 
@@ -511,33 +603,45 @@ pub fn revert_run(run_id: &str, force: bool, state_dir: &Path) -> Result<()> {
     }
 
     // Verify files haven't changed (unless --force)
+    let mut skipped = Vec::new();
     if !force {
         for file in &run.files_modified {
             if !file.path.exists() {
-                bail!(
-                    "File {} no longer exists (use --force to ignore)",
-                    file.path.display()
-                );
+                skipped.push(SkippedFile {
+                    path: file.path.clone(),
+                    reason: "missing".to_string(),
+                    expected_hash: Some(file.hash_after.clone()),
+                    current_hash: None,
+                });
+                continue;
             }
 
             let current_hash = hash_file(&file.path)?;
             if current_hash != file.hash_after {
-                bail!(
-                    "File {} has changed since run {} (use --force to ignore)\nExpected hash: {}\nCurrent hash: {}",
-                    file.path.display(),
-                    run_id,
-                    file.hash_after,
-                    current_hash
-                );
+                skipped.push(SkippedFile {
+                    path: file.path.clone(),
+                    reason: "hash-mismatch".to_string(),
+                    expected_hash: Some(file.hash_after.clone()),
+                    current_hash: Some(current_hash),
+                });
             }
         }
     }
 
+    if !skipped.is_empty() {
+        return Ok(RevertReport {
+            run_id: run_id.to_string(),
+            status: "aborted".to_string(),
+            reverted: Vec::new(),
+            skipped,
+        });
+    }
+
     // Restore from backups
-    println!("Reverting {} file(s)...", run.files_modified.len());
+    let mut reverted = Vec::new();
     for file in &run.files_modified {
         restore_from_nodes(&file.path, &file.backup_nodes, state_dir)?;
-        println!("  ✓ Restored: {}", file.path.display());
+        reverted.push(file.path.clone());
     }
 
     // Mark run as reverted
@@ -554,14 +658,57 @@ pub fn revert_run(run_id: &str, force: bool, state_dir: &Path) -> Result<()> {
     run.can_revert = false;
     save_run_metadata(&run, state_dir)?;
 
+    Ok(RevertReport {
+        run_id: run_id.to_string(),
+        status: "reverted".to_string(),
+        reverted,
+        skipped: Vec::new(),
+    })
+}
+
+/// Revert a run
+pub fn revert_run(run_id: &str, force: bool, state_dir: &Path) -> Result<()> {
+    let report = revert_run_report(run_id, force, state_dir)?;
+
+    if let Some(file) = report.skipped.first() {
+        match (&file.expected_hash, &file.current_hash) {
+            (Some(expected), Some(current)) => bail!(
+                "File {} has changed since run {} (use --force to ignore)\nExpected hash: {}\nCurrent hash: {}",
+                file.path.display(),
+                run_id,
+                expected,
+                current
+            ),
+            _ => bail!(
+                "File {} no longer exists (use --force to ignore)",
+                file.path.display()
+            ),
+        }
+    }
+
+    println!("Reverting {} file(s)...", report.reverted.len());
+    for path in &report.reverted {
+        println!("  ✓ Restored: {}", path.display());
+    }
+
     println!("✓ Run {} reverted successfully", run_id);
     Ok(())
 }
 
+/// The most recent runs, newest first
+pub fn recent_runs(limit: usize, state_dir: &Path) -> Result<Vec<RunMetadata>> {
+    let index = RunsIndex::load_or_reset(state_dir)?;
+    Ok(index
+        .get_sorted_runs()
+        .into_iter()
+        .take(limit)
+        .cloned()
+        .collect())
+}
+
 /// Display run history
 pub fn show_history(limit: usize, state_dir: &Path) -> Result<()> {
-    let index = RunsIndex::load_or_reset(state_dir)?;
-    let runs = index.get_sorted_runs();
+    let runs = recent_runs(limit, state_dir)?;
 
     if runs.is_empty() {
         println!("No runs found");
@@ -570,7 +717,7 @@ pub fn show_history(limit: usize, state_dir: &Path) -> Result<()> {
 
     println!("Recent runs (showing up to {}):\n", limit);
 
-    for run in runs.iter().take(limit) {
+    for run in &runs {
         let status_str = match run.status {
             RunStatus::Applied => {
                 if run.can_revert {
@@ -1,9 +1,12 @@
 //! Surgical edit engine: applies precise, minimal text replacements
 //! to source code while preserving all formatting, comments, and whitespace.
+//!
+//! `Source` maps `proc_macro2` span positions to byte offsets for text edits.
 
 use std::cmp::Ordering;
 
 use proc_macro2::LineColumn;
+use syn::spanned::Spanned;
 
 /// Represents a single textual replacement in the source code.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -42,6 +45,53 @@ impl PartialOrd for Replacement {
     }
 }
 
+/// Byte offsets for `proc_macro2` line/column positions (columns count chars).
+pub(crate) struct Source<'a> {
+    pub(crate) text: &'a str,
+    line_offsets: Vec<usize>,
+}
+
+impl<'a> Source<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        let mut line_offsets = vec![0];
+        line_offsets.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+        Self { text, line_offsets }
+    }
+
+    pub(crate) fn offset(&self, pos: LineColumn) -> usize {
+        let Some(&line_start) = self.line_offsets.get(pos.line.saturating_sub(1)) else {
+            return self.text.len();
+        };
+        self.text[line_start..]
+            .char_indices()
+            .nth(pos.column)
+            .map_or(self.text.len(), |(i, _)| line_start + i)
+    }
+
+    pub(crate) fn range(&self, node: &impl Spanned) -> (usize, usize) {
+        let span = node.span();
+        (self.offset(span.start()), self.offset(span.end()))
+    }
+
+    /// `start..end` grown to whole lines, with plain `//` comments directly above it, so
+    /// removing it leaves no stray indentation or orphaned comments.
+    pub(crate) fn item_lines(&self, start: usize, end: usize) -> (usize, usize) {
+        let mut start = self.text[..start].rfind('\n').map_or(0, |i| i + 1);
+        while start > 0 {
+            let prev = self.text[..start - 1].rfind('\n').map_or(0, |i| i + 1);
+            let line = self.text[prev..start].trim();
+            if !line.starts_with("//") || line.starts_with("///") || line.starts_with("//!") {
+                break;
+            }
+            start = prev;
+        }
+        let end = self.text[end..]
+            .find('\n')
+            .map_or(self.text.len(), |i| end + i + 1);
+        (start, end)
+    }
+}
+
 /// Apply surgical edits to source code, preserving all formatting.
 ///
 /// This function takes the original source code and a list of replacements,
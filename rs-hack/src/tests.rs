@@ -36,6 +36,7 @@ impl User {
             position: InsertPosition::Last,
             literal_default: None,
             where_filter: None,
+            in_fn: None,
         };
 
         let result = editor.add_struct_field(&op);
@@ -46,6 +47,217 @@ impl User {
         assert!(output.contains("email: String"));
     }
 
+    #[test]
+    fn test_inspect_impl_blocks() {
+        let code = "struct Config;\n\nimpl Config {\n    pub fn new() -> Self { Config }\n    const N: u8 = 1;\n    fn load(&self) {}\n}\n\nimpl std::fmt::Debug for Config {\n    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { Ok(()) }\n}\n\nimpl Other {}\n";
+        let editor = RustEditor::new(code).unwrap();
+
+        let found = editor
+            .inspect(Some("impl"), Some("Config"), None, false)
+            .unwrap();
+        let summary: Vec<(&str, &str)> = found
+            .iter()
+            .map(|r| (r.identifier.as_str(), r.snippet.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Config", "impl Config { new, load }"),
+                (
+                    "Debug for Config",
+                    "impl std::fmt::Debug for Config { fmt }"
+                ),
+            ]
+        );
+        assert_eq!(found[1].location.line, 9);
+
+        let all = editor.inspect(Some("impl"), None, None, false).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[2].snippet, "impl Other {}");
+    }
+
+    #[test]
+    fn test_rename_struct_field_serde_aware() {
+        let code = "#[derive(Serialize, Deserialize)]\n#[serde(rename_all = \"camelCase\")]\npub struct Config {\n    pub read_timeout: u64,\n    #[serde(rename = \"lvl\")]\n    level: u8,\n}\n\nfn make(read_timeout: u64) -> Config {\n    Config { read_timeout, level: 1 }\n}\n";
+        let rename = |old: &str, new: &str, update_serde_name: bool| RenameStructFieldOp {
+            struct_name: "Config".to_string(),
+            old_name: old.to_string(),
+            new_name: new.to_string(),
+            serde_aware: true,
+            update_serde_name,
+        };
+
+        let mut editor = RustEditor::new(code).unwrap();
+        let result = editor
+            .rename_struct_field(&rename("read_timeout", "timeout_secs", false))
+            .unwrap();
+        assert!(result.changed);
+        // The wire name follows the container's rename_all
+        let output = editor.to_string();
+        assert!(
+            output.contains("    #[serde(rename = \"readTimeout\")]\n    pub timeout_secs: u64,")
+        );
+        assert!(output.contains("Config { timeout_secs: read_timeout, level: 1 }"));
+
+        // An existing rename attribute already pins the wire name...
+        let mut kept = RustEditor::new(&output).unwrap();
+        kept.rename_struct_field(&rename("level", "verbosity", false))
+            .unwrap();
+        assert!(
+            kept.to_string()
+                .contains("#[serde(rename = \"lvl\")]\n    verbosity: u8,")
+        );
+
+        // ...unless it's asked to follow the rename
+        editor
+            .rename_struct_field(&rename("level", "verbosity", true))
+            .unwrap();
+        let output = editor.to_string();
+        assert!(output.contains("#[serde(rename = \"verbosity\")]\n    verbosity: u8,"));
+        assert!(output.contains("Config { timeout_secs: read_timeout, verbosity: 1 }"));
+
+        assert!(
+            editor
+                .rename_struct_field(&rename("missing", "other", false))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_rename_struct_field_serde_aware_private() {
+        let mut editor =
+            RustEditor::new("#[derive(Serialize)]\nstruct S {\n    timeout: u32,\n}\n").unwrap();
+        let result = editor
+            .rename_struct_field(&RenameStructFieldOp {
+                struct_name: "S".to_string(),
+                old_name: "timeout".to_string(),
+                new_name: "timeout_secs".to_string(),
+                serde_aware: true,
+                update_serde_name: false,
+            })
+            .unwrap();
+        assert!(result.changed);
+        assert_eq!(
+            editor.to_string(),
+            "#[derive(Serialize)]\nstruct S {\n    #[serde(rename = \"timeout\")]\n    timeout_secs: u32,\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_rename_struct_field_accesses() {
+        let code = r#"pub struct Config {
+    pub timeout: u64,
+}
+
+pub struct Other {
+    pub timeout: u64,
+}
+
+impl Other {
+    fn get(&self) -> u64 { self.timeout }
+}
+
+fn run(c: &Config, o: Other, boxed: Box<Config>) -> u64 {
+    let Config { timeout } = Config { timeout: 1 };
+    let unknown = make();
+    println!("{}", unknown.timeout);
+    c.timeout + o.timeout + boxed.timeout + timeout
+}
+"#;
+        let editor = RustEditor::new(code).unwrap();
+        let receivers: Vec<(String, Option<String>)> = editor
+            .field_accesses("timeout")
+            .into_iter()
+            .map(|a| (a.code, a.receiver_type))
+            .collect();
+        let known = |ty: &str| Some(ty.to_string());
+        assert_eq!(
+            receivers,
+            vec![
+                ("self.timeout".to_string(), known("Other")),
+                ("unknown.timeout".to_string(), None),
+                ("c.timeout".to_string(), known("Config")),
+                ("o.timeout".to_string(), known("Other")),
+                ("boxed.timeout".to_string(), known("Config")),
+            ]
+        );
+
+        let mut editor = RustEditor::new(code).unwrap();
+        let result = editor
+            .rename_struct_field(&RenameStructFieldOp {
+                struct_name: "Config".to_string(),
+                old_name: "timeout".to_string(),
+                new_name: "timeout_secs".to_string(),
+                serde_aware: false,
+                update_serde_name: false,
+            })
+            .unwrap();
+        let output = editor.to_string();
+        assert!(output.contains("fn get(&self) -> u64 { self.timeout }"));
+        assert!(
+            output.contains("let Config { timeout_secs: timeout } = Config { timeout_secs: 1 };")
+        );
+        assert!(output.contains("println!(\"{}\", unknown.timeout_secs);"));
+        assert!(output.contains("c.timeout_secs + o.timeout + boxed.timeout_secs + timeout"));
+
+        // One backup per renamed access or pattern field, pointing at the new text
+        let references: Vec<&BackupNode> = result
+            .modified_nodes
+            .iter()
+            .filter(|n| n.node_type == "field-reference")
+            .collect();
+        assert_eq!(references.len(), 4);
+        for node in &references {
+            let line = output.lines().nth(node.location.line - 1).unwrap();
+            let text = &line[node.location.column..node.location.end_column];
+            // A shorthand pattern field covers `new: binding`
+            assert!(text.starts_with("timeout_secs"), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_union_field_operations() {
+        let code = "#[repr(C)]\npub union Value {\n    pub int: i64,\n    pub float: f64,\n}\n\nfn make() -> Value {\n    Value { int: 1 }\n}\n";
+        let mut editor = RustEditor::new(code).unwrap();
+
+        let found = editor
+            .inspect(Some("union"), Some("Value"), None, false)
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(found[0].snippet.contains("pub union Value {"));
+
+        let add = |field_def: &str, literal_default: Option<&str>| AddStructFieldOp {
+            struct_name: "Value".to_string(),
+            field_def: field_def.to_string(),
+            position: InsertPosition::Last,
+            literal_default: literal_default.map(str::to_string),
+            where_filter: None,
+            in_fn: None,
+        };
+        let result = editor
+            .add_struct_field(&add("pub bits: u64", None))
+            .unwrap();
+        assert_eq!(result.modified_nodes[0].node_type, "union");
+        // A union literal sets exactly one field, so there's nothing to default
+        assert!(editor.add_struct_field(&add("tag: u8", Some("0"))).is_err());
+
+        let mut editor = RustEditor::new(&editor.to_string()).unwrap();
+        let result = editor
+            .remove_struct_field(&RemoveStructFieldOp {
+                struct_name: "Value".to_string(),
+                field_name: "float".to_string(),
+                where_filter: None,
+                in_fn: None,
+                literal_only: false,
+            })
+            .unwrap();
+        assert_eq!(result.modified_nodes.len(), 1);
+
+        let output = editor.to_string();
+        assert!(output.contains("pub union Value {\n    pub int: i64,\n    pub bits: u64,\n}"));
+        assert!(output.contains("Value { int: 1 }"));
+    }
+
     #[test]
     fn test_add_struct_field_idempotent() {
         let mut editor = RustEditor::new(SAMPLE_STRUCT).unwrap();
@@ -55,6 +267,7 @@ impl User {
             position: InsertPosition::Last,
             literal_default: None,
             where_filter: None,
+            in_fn: None,
         };
 
         let result = editor.add_struct_field(&op);
@@ -62,6 +275,96 @@ impl User {
         assert!(!result.unwrap().changed); // Should return false (no change)
     }
 
+    #[test]
+    fn test_add_tuple_field_to_definition() {
+        let source = "struct Point(pub i32, pub i32);\n\nstruct Wide(\n    i32,\n    String,\n);\n";
+        let op = AddTupleFieldOp {
+            struct_name: "Point".to_string(),
+            field_type: "f32".to_string(),
+            position: InsertPosition::Last,
+            literal_default: None,
+            arity: None,
+            where_filter: None,
+            in_fn: None,
+        };
+
+        let mut editor = RustEditor::new(source).unwrap();
+        assert!(editor.add_tuple_field(&op).unwrap().changed);
+        assert!(
+            editor
+                .to_string()
+                .contains("struct Point(pub i32, pub i32, f32);")
+        );
+
+        // Positions are field indexes; a multi-line list gets the field on its own line
+        let op = AddTupleFieldOp {
+            struct_name: "Wide".to_string(),
+            position: InsertPosition::After("0".to_string()),
+            ..op
+        };
+        let mut editor = RustEditor::new(source).unwrap();
+        assert!(editor.add_tuple_field(&op).unwrap().changed);
+        assert!(
+            editor
+                .to_string()
+                .contains("struct Wide(\n    i32,\n    f32,\n    String,\n);")
+        );
+
+        let out_of_range = AddTupleFieldOp {
+            position: InsertPosition::Before("2".to_string()),
+            ..op
+        };
+        let mut editor = RustEditor::new(source).unwrap();
+        assert!(editor.add_tuple_field(&out_of_range).is_err());
+    }
+
+    #[test]
+    fn test_add_tuple_field_to_literals() {
+        let source = r#"struct Point(i32, i32);
+
+impl Point {
+    fn origin() -> Self {
+        Self(0, 0)
+    }
+}
+
+fn points() -> Vec<Point> {
+    let _ = Shape::Point(1, 2);
+    vec![Point(1, 2), geo::Point(3, 4), Point(5, 6, 7)]
+}
+"#;
+        let op = AddTupleFieldOp {
+            struct_name: "Point".to_string(),
+            field_type: "f32".to_string(),
+            position: InsertPosition::First,
+            literal_default: Some("0.0".to_string()),
+            arity: None,
+            where_filter: None,
+            in_fn: None,
+        };
+
+        let mut editor = RustEditor::new(source).unwrap();
+        let result = editor.add_tuple_field(&op).unwrap();
+        assert!(result.changed);
+        let output = editor.to_string();
+        assert!(output.contains("struct Point(f32, i32, i32);"));
+        assert!(output.contains("Self(0.0, 0, 0)"));
+        assert!(output.contains("vec![Point(0.0, 1, 2), geo::Point(0.0, 3, 4), Point(5, 6, 7)]"));
+        // An enum variant of the same name isn't a literal of the struct
+        assert!(output.contains("Shape::Point(1, 2)"));
+
+        // Without the definition, the given arity picks the literals
+        let literals_only = "fn f() -> Point {\n    Point(1, 2)\n}\n";
+        let op = AddTupleFieldOp {
+            arity: Some(2),
+            position: InsertPosition::Last,
+            ..op
+        };
+        let mut editor = RustEditor::new(literals_only).unwrap();
+        assert!(editor.add_tuple_field(&op).unwrap().changed);
+        assert!(editor.to_string().contains("Point(1, 2, 0.0)"));
+    }
+
     #[test]
     fn test_update_struct_field() {
         let mut editor = RustEditor::new(SAMPLE_STRUCT).unwrap();
@@ -69,6 +372,7 @@ impl User {
             struct_name: "User".to_string(),
             field_def: "id: i64".to_string(), // Change type from u64 to i64
             where_filter: None,
+            in_fn: None,
         };
 
         let result = editor.update_struct_field(&op);
@@ -87,6 +391,7 @@ impl User {
             struct_name: "User".to_string(),
             field_name: "name".to_string(),
             where_filter: None,
+            in_fn: None,
             literal_only: false,
         };
 
@@ -106,6 +411,7 @@ impl User {
             variant_def: "Archived".to_string(),
             position: InsertPosition::Last,
             where_filter: None,
+            in_fn: None,
         };
 
         let result = editor.add_enum_variant(&op);
@@ -124,6 +430,7 @@ impl User {
             variant_def: "Draft".to_string(), // Already exists
             position: InsertPosition::Last,
             where_filter: None,
+            in_fn: None,
         };
 
         let result = editor.add_enum_variant(&op);
@@ -138,6 +445,7 @@ impl User {
             enum_name: "Status".to_string(),
             variant_name: "Draft".to_string(),
             where_filter: None,
+            in_fn: None,
         };
 
         let result = editor.remove_enum_variant(&op);
@@ -149,6 +457,78 @@ impl User {
         assert!(output.contains("Published")); // Other variant still there
     }
 
+    #[test]
+    fn test_remove_struct_field_blank_line_spacing() {
+        let code =
+            "pub struct Config {\n    host: String,\n\n    port: u16,\n\n    debug: bool,\n}\n";
+        let remove = |field: &str| {
+            let mut editor = RustEditor::new(code).unwrap();
+            let op = RemoveStructFieldOp {
+                struct_name: "Config".to_string(),
+                field_name: field.to_string(),
+                where_filter: None,
+                in_fn: None,
+                literal_only: false,
+            };
+            assert!(editor.remove_struct_field(&op).unwrap().changed);
+            editor.to_string()
+        };
+
+        assert_eq!(
+            remove("host"),
+            "pub struct Config {\n    port: u16,\n\n    debug: bool,\n}\n"
+        );
+        assert_eq!(
+            remove("port"),
+            "pub struct Config {\n    host: String,\n\n    debug: bool,\n}\n"
+        );
+        assert_eq!(
+            remove("debug"),
+            "pub struct Config {\n    host: String,\n\n    port: u16,\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_remove_enum_variant_blank_line_spacing() {
+        let code =
+            "enum Status {\n    Draft,\n\n    Published,\n\n    Archived,\n}\n\nfn main() {}\n";
+        let remove = |variant: &str| {
+            let mut editor = RustEditor::new(code).unwrap();
+            let op = RemoveEnumVariantOp {
+                enum_name: "Status".to_string(),
+                variant_name: variant.to_string(),
+                where_filter: None,
+                in_fn: None,
+            };
+            assert!(editor.remove_enum_variant(&op).unwrap().changed);
+            editor.to_string()
+        };
+
+        assert_eq!(
+            remove("Draft"),
+            "enum Status {\n    Published,\n\n    Archived,\n}\n\nfn main() {}\n"
+        );
+        assert_eq!(
+            remove("Published"),
+            "enum Status {\n    Draft,\n\n    Archived,\n}\n\nfn main() {}\n"
+        );
+        assert_eq!(
+            remove("Archived"),
+            "enum Status {\n    Draft,\n\n    Published,\n}\n\nfn main() {}\n"
+        );
+
+        // Densely packed variants stay densely packed
+        let mut editor = RustEditor::new("enum E {\n    A,\n    B,\n    C,\n}\n").unwrap();
+        let op = RemoveEnumVariantOp {
+            enum_name: "E".to_string(),
+            variant_name: "B".to_string(),
+            where_filter: None,
+            in_fn: None,
+        };
+        editor.remove_enum_variant(&op).unwrap();
+        assert_eq!(editor.to_string(), "enum E {\n    A,\n    C,\n}\n");
+    }
+
     #[test]
     fn test_add_derive() {
         let mut editor = RustEditor::new(SAMPLE_STRUCT).unwrap();
@@ -157,6 +537,8 @@ impl User {
             target_type: "struct".to_string(),
             derives: vec!["Clone".to_string(), "Serialize".to_string()],
             where_filter: None,
+            derive_order: None,
+            edit_mode: EditMode::default(),
         };
 
         let result = editor.add_derive(&op);
@@ -177,6 +559,8 @@ impl User {
             target_type: "struct".to_string(),
             derives: vec!["Debug".to_string()], // Already has Debug
             where_filter: None,
+            derive_order: None,
+            edit_mode: EditMode::default(),
         };
 
         let result = editor.add_derive(&op);
@@ -184,6 +568,80 @@ impl User {
         assert!(!result.unwrap().changed); // Should return false (no change)
     }
 
+    #[test]
+    fn test_update_derive_replace_and_sort() {
+        let source = "#[derive(Serialize, Clone, Debug)]\nstruct Config {\n    id: u64,\n}\n\n#[derive(PartialEq, serde::Deserialize, Clone,)]\nenum Mode {\n    A,\n}\n";
+        let replace = UpdateDeriveOp {
+            target_name: "Config".to_string(),
+            target_type: "struct".to_string(),
+            derives: Some(vec!["Debug".to_string(), "Clone".to_string()]),
+            sort: false,
+            where_filter: None,
+            edit_mode: EditMode::default(),
+        };
+
+        let mut editor = RustEditor::new(source).unwrap();
+        assert!(editor.update_derive(&replace).unwrap().changed);
+        assert!(
+            editor
+                .to_string()
+                .contains("#[derive(Debug, Clone)]\nstruct Config")
+        );
+        // Same list again: no change
+        let mut editor = RustEditor::new(&editor.to_string()).unwrap();
+        assert!(!editor.update_derive(&replace).unwrap().changed);
+
+        // --sort alone orders the existing list by last path segment
+        let sort = UpdateDeriveOp {
+            target_name: "Mode".to_string(),
+            target_type: "enum".to_string(),
+            derives: None,
+            sort: true,
+            ..replace
+        };
+        assert!(editor.update_derive(&sort).unwrap().changed);
+        assert!(
+            editor
+                .to_string()
+                .contains("#[derive(Clone, serde::Deserialize, PartialEq)]\nenum Mode"),
+            "{}",
+            editor
+        );
+    }
+
+    #[test]
+    fn test_add_derive_with_order() {
+        let mut editor =
+            RustEditor::new("#[derive(Serialize, Debug)]\nstruct User {\n    id: u64,\n}\n")
+                .unwrap();
+        let op = AddDeriveOp {
+            target_name: "User".to_string(),
+            target_type: "struct".to_string(),
+            derives: vec![
+                "Clone".to_string(),
+                "Hash".to_string(),
+                "serde::Deserialize".to_string(),
+            ],
+            where_filter: None,
+            derive_order: Some(vec![
+                "Debug".to_string(),
+                "Clone".to_string(),
+                "Deserialize".to_string(),
+            ]),
+            edit_mode: EditMode::default(),
+        };
+
+        assert!(editor.add_derive(&op).unwrap().changed);
+        // Listed derives first, in the given order; the rest alphabetically
+        assert!(
+            editor
+                .to_string()
+                .contains("#[derive(Debug, Clone, serde::Deserialize, Hash, Serialize)]"),
+            "{}",
+            editor
+        );
+    }
+
     #[test]
     fn test_add_impl_method() {
         let mut editor = RustEditor::new(&format!("{}\n{}", SAMPLE_STRUCT, SAMPLE_IMPL)).unwrap();
@@ -191,6 +649,8 @@ impl User {
             target: "User".to_string(),
             method_def: "pub fn get_id(&self) -> u64 { self.id }".to_string(),
             position: InsertPosition::Last,
+            trait_name: None,
+            edit_mode: EditMode::default(),
         };
 
         let result = editor.add_impl_method(&op);
@@ -210,6 +670,8 @@ impl User {
             method_def: "pub fn new(id: u64, name: String) -> Self { Self { id, name } }"
                 .to_string(),
             position: InsertPosition::Last,
+            trait_name: None,
+            edit_mode: EditMode::default(),
         };
 
         let result = editor.add_impl_method(&op);
@@ -439,6 +901,7 @@ pub fn handle_status(status: Status) -> String {
             position: InsertPosition::First,
             literal_default: None,
             where_filter: None,
+            in_fn: None,
         };
 
         let result = editor.add_struct_field(&op);
@@ -461,6 +924,7 @@ pub fn handle_status(status: Status) -> String {
             position: InsertPosition::After("id".to_string()),
             literal_default: None,
             where_filter: None,
+            in_fn: None,
         };
 
         let result = editor.add_struct_field(&op);
@@ -483,6 +947,7 @@ pub fn handle_status(status: Status) -> String {
             variant_def: "Error { code: i32, message: String }".to_string(),
             position: InsertPosition::Last,
             where_filter: None,
+            in_fn: None,
         };
 
         let result = editor.add_enum_variant(&op);
@@ -504,6 +969,7 @@ pub fn handle_status(status: Status) -> String {
             position: InsertPosition::Last,
             literal_default: None,
             where_filter: None,
+            in_fn: None,
         };
 
         let result = editor.add_struct_field(&op);
@@ -519,6 +985,7 @@ pub fn handle_status(status: Status) -> String {
             variant_def: "Variant".to_string(),
             position: InsertPosition::Last,
             where_filter: None,
+            in_fn: None,
         };
 
         let result = editor.add_enum_variant(&op);
@@ -549,6 +1016,7 @@ fn create_ctx() -> IRCtx {
             field_def: "return_type: None".to_string(),
             position: InsertPosition::After("current_function_frame".to_string()),
             struct_path: None,
+            field_path: Vec::new(),
         };
 
         let result = editor.add_struct_literal_field(&op);
@@ -596,6 +1064,7 @@ fn new_user() -> User {
             field_def: "name: \"Bob\".to_string()".to_string(),
             position: InsertPosition::Last,
             struct_path: None,
+            field_path: Vec::new(),
         };
 
         let result = editor.add_struct_literal_field(&op);
@@ -634,6 +1103,7 @@ fn create_point_2() -> Point {
             field_def: "z: 0".to_string(),
             position: InsertPosition::Last,
             struct_path: None,
+            field_path: Vec::new(),
         };
 
         let result = editor.add_struct_literal_field(&op);
@@ -666,6 +1136,7 @@ fn default_config() -> Config {
             field_def: "timeout: 30".to_string(),
             position: InsertPosition::Last,
             struct_path: None,
+            field_path: Vec::new(),
         };
 
         let result = editor.add_struct_literal_field(&op);
@@ -701,6 +1172,7 @@ fn create() -> types::Data {
             field_def: "timestamp: 0".to_string(),
             position: InsertPosition::Last,
             struct_path: Some("types::Data".to_string()), // Use path resolver to match types::Data
+            field_path: Vec::new(),
         };
 
         let result = editor.add_struct_literal_field(&op);
@@ -733,6 +1205,7 @@ fn new_ctx() -> IRCtx {
             position: InsertPosition::After("current_function_frame".to_string()),
             literal_default: Some("None".to_string()),
             where_filter: None,
+            in_fn: None,
         };
 
         let result = editor.add_struct_field(&op);
@@ -756,6 +1229,210 @@ fn new_ctx() -> IRCtx {
         assert!(rt_def_pos > cfr_def_pos);
     }
 
+    #[test]
+    fn test_add_struct_literal_field_cfg_gated() {
+        let code = r#"
+pub struct Options {
+    verbose: bool,
+    #[cfg(feature = "trace")]
+    trace: bool,
+}
+
+fn make() -> Options {
+    Options {
+        verbose: false,
+    }
+}
+"#;
+        // The definition gates `trace`, so the literal must be gated the same way
+        let mut editor = RustEditor::new(code).unwrap();
+        let op = AddStructLiteralFieldOp {
+            struct_name: "Options".to_string(),
+            field_def: "trace: false".to_string(),
+            position: InsertPosition::Last,
+            struct_path: None,
+            field_path: Vec::new(),
+        };
+        assert!(editor.add_struct_literal_field(&op).unwrap().changed);
+        let output = editor.to_string();
+        assert!(output.contains(
+            "        verbose: false,\n        #[cfg(feature = \"trace\")]\n        trace: false"
+        ));
+        assert!(syn::parse_file(&output).is_ok());
+
+        // A cfg-gated field added through `add` keeps its attribute on both sides
+        let mut editor = RustEditor::new(code).unwrap();
+        let op = AddStructFieldOp {
+            struct_name: "Options".to_string(),
+            field_def: "#[cfg(feature = \"metrics\")] metrics: bool".to_string(),
+            position: InsertPosition::Last,
+            literal_default: Some("true".to_string()),
+            where_filter: None,
+            in_fn: None,
+        };
+        assert!(editor.add_struct_field(&op).unwrap().changed);
+        let output = editor.to_string();
+        assert_eq!(output.matches("#[cfg(feature = \"metrics\")]").count(), 2);
+        assert!(output.contains("#[cfg(feature = \"metrics\")]\n        metrics: true"));
+        assert!(syn::parse_file(&output).is_ok());
+    }
+
+    #[test]
+    fn test_add_struct_literal_field_nested_path() {
+        let code = r#"
+fn configs() -> Vec<Config> {
+    vec![
+        Config {
+            name: "a",
+            inner: Inner {
+                timeout: 5,
+            },
+        },
+        Config {
+            name: "b",
+            inner: Inner {
+                timeout: 5,
+                retries: 1,
+            },
+        },
+        Config {
+            name: "c",
+            inner: Inner::default(),
+        },
+    ]
+}
+"#;
+        let mut editor = RustEditor::new(code).unwrap();
+        let op = AddStructLiteralFieldOp {
+            struct_name: "Config".to_string(),
+            field_def: "retries: 3".to_string(),
+            position: InsertPosition::Last,
+            struct_path: None,
+            field_path: vec!["inner".to_string()],
+        };
+        assert!(editor.add_struct_literal_field(&op).unwrap().changed);
+
+        let output = editor.to_string();
+        assert!(output.contains("                timeout: 5,\n                retries: 3,\n"));
+        // Already set, or not a nested literal: left alone
+        assert!(output.contains("retries: 1,"));
+        assert!(output.contains("inner: Inner::default(),"));
+        assert_eq!(output.matches("retries").count(), 2);
+        // The outer literal itself doesn't get the field
+        assert!(!output.contains("},\n            retries"));
+        assert!(syn::parse_file(&output).is_ok());
+    }
+
+    #[test]
+    fn test_add_struct_literal_field_copies_sibling_values() {
+        let code = r#"
+fn rects(width: u32) -> Vec<Rect> {
+    vec![
+        Rect {
+            width: 3 + 1,
+            height: 2,
+            label: "{width}",
+        },
+        Rect {
+            width,
+            height: 5,
+            label: "b",
+        },
+    ]
+}
+"#;
+        let op = AddStructLiteralFieldOp {
+            struct_name: "Rect".to_string(),
+            field_def: "area: {width} * {height}".to_string(),
+            position: InsertPosition::Last,
+            struct_path: None,
+            field_path: Vec::new(),
+        };
+        let mut editor = RustEditor::new(code).unwrap();
+        assert!(editor.add_struct_literal_field(&op).unwrap().changed);
+        let output = editor.to_string();
+        // Compound values are parenthesized; shorthand fields copy their name
+        assert!(output.contains("area: (3 + 1) * 2,"), "{}", output);
+        assert!(output.contains("area: width * 5,"), "{}", output);
+
+        // A lone reference is copied as is; braces inside strings aren't references
+        let op = AddStructLiteralFieldOp {
+            field_def: r#"caption: format!("{width}: {}", {label})"#.to_string(),
+            ..op
+        };
+        let mut editor = RustEditor::new(code).unwrap();
+        assert!(editor.add_struct_literal_field(&op).unwrap().changed);
+        let output = editor.to_string();
+        assert!(output.contains(r#"caption: format!("{width}: {}", "{width}"),"#));
+        assert!(output.contains(r#"caption: format!("{width}: {}", "b"),"#));
+
+        let missing = AddStructLiteralFieldOp {
+            field_def: "depth: {depth}".to_string(),
+            ..op
+        };
+        let mut editor = RustEditor::new(code).unwrap();
+        let err = editor.add_struct_literal_field(&missing).unwrap_err();
+        assert!(err.to_string().contains("no `depth` field"), "{}", err);
+    }
+
+    #[test]
+    fn test_function_local_types_with_in_fn() {
+        let code = r#"
+fn parse() {
+    struct Token {
+        text: String,
+    }
+    enum Kind {
+        Word,
+    }
+}
+
+fn render() {
+    struct Token {
+        width: usize,
+    }
+}
+"#;
+        // A name declared in a single function body is found without a qualifier
+        let mut editor = RustEditor::new(code).unwrap();
+        let op = AddEnumVariantOp {
+            enum_name: "Kind".to_string(),
+            variant_def: "Number".to_string(),
+            position: InsertPosition::Last,
+            where_filter: None,
+            in_fn: None,
+        };
+        assert!(editor.add_enum_variant(&op).unwrap().changed);
+        assert!(
+            editor
+                .to_string()
+                .contains("        Word,\n        Number,")
+        );
+
+        // Two functions declare `Token`: unqualified is ambiguous, --in-fn picks one
+        let mut op = AddStructFieldOp {
+            struct_name: "Token".to_string(),
+            field_def: "line: u32".to_string(),
+            position: InsertPosition::Last,
+            literal_default: None,
+            where_filter: None,
+            in_fn: None,
+        };
+        let err = editor.add_struct_field(&op).unwrap_err().to_string();
+        assert!(err.contains("--in-fn"), "{}", err);
+
+        op.in_fn = Some("render".to_string());
+        assert!(editor.add_struct_field(&op).unwrap().changed);
+        let output = editor.to_string();
+        assert!(output.contains("        width: usize,\n        line: u32,"));
+        assert!(output.contains("        text: String,\n    }"));
+        assert!(syn::parse_file(&output).is_ok());
+
+        op.in_fn = Some("missing".to_string());
+        let err = editor.add_struct_field(&op).unwrap_err().to_string();
+        assert!(err.contains("not found in function 'missing'"));
+    }
+
     #[test]
     #[ignore] // TODO: Fix idempotent behavior when field exists but literal_default is provided
     fn test_add_struct_field_with_literal_default_idempotent() {
@@ -779,6 +1456,7 @@ fn default_config() -> Config {
             position: InsertPosition::Last,
             literal_default: Some("30".to_string()),
             where_filter: None,
+            in_fn: None,
         };
 
         let result = editor.add_struct_field(&op);
@@ -813,6 +1491,7 @@ fn new_user() -> User {
             position: InsertPosition::Last,
             literal_default: None, // No literal default
             where_filter: None,
+            in_fn: None,
         };
 
         let result = editor.add_struct_field(&op);
@@ -866,6 +1545,7 @@ pub struct Config {
             position: InsertPosition::Last,
             literal_default: None,
             where_filter: Some("derives_trait:Clone".to_string()),
+            in_fn: None,
         };
 
         let result = editor.add_struct_field(&op).unwrap();
@@ -890,6 +1570,7 @@ pub struct Config {
             position: InsertPosition::Last,
             literal_default: None,
             where_filter: Some("derives_trait:Clone".to_string()),
+            in_fn: None,
         };
 
         let result = editor.add_struct_field(&op).unwrap();
@@ -914,6 +1595,7 @@ pub struct Config {
             position: InsertPosition::Last,
             literal_default: None,
             where_filter: Some("derives_trait:Clone,Debug".to_string()),
+            in_fn: None,
         };
 
         let result = editor.add_struct_field(&op).unwrap();
@@ -1097,4 +1779,3937 @@ fn main() {
                 .contains("Unsupported node type")
         );
     }
+
+    #[test]
+    fn test_node_types_match_dispatch() {
+        // Every listed node type must be one `inspect` actually dispatches on
+        let editor = RustEditor::new("fn main() {}").unwrap();
+        for info in crate::commands::node_types::NODE_TYPES {
+            assert!(
+                editor.inspect(Some(info.name), None, None, false).is_ok(),
+                "node-types lists '{}' but inspect rejects it",
+                info.name
+            );
+        }
+        assert!(
+            editor
+                .inspect(Some("not-a-node"), None, None, false)
+                .is_err()
+        );
+
+        let report = crate::commands::node_types::run();
+        assert_eq!(report.kinds.len(), crate::commands::node_types::KINDS.len());
+        assert!(report.kinds.iter().all(|k| !k.node_types.is_empty()));
+        for info in crate::commands::node_types::NODE_TYPES {
+            if let Some(kind) = info.kind {
+                assert!(
+                    crate::commands::node_types::KINDS.contains(&kind),
+                    "'{}' belongs to unlisted kind '{}'",
+                    info.name,
+                    kind
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_inspect_without_node_type_searches_default_types() {
+        let code = r#"
+trait Runner {
+    fn run(&self);
+}
+"#;
+        let editor = RustEditor::new(code).unwrap();
+        let results = editor.inspect(None, Some("run"), None, false).unwrap();
+        assert!(results.iter().any(|r| r.node_type == "trait-method"));
+    }
+
+    #[test]
+    fn test_inspect_arity() {
+        use crate::operations::Arity;
+
+        let code = r#"
+fn f() {}
+fn g(a: u8, b: &str) -> u8 { a }
+struct S;
+impl S {
+    fn get(&self, key: &str) {}
+}
+trait T {
+    fn run(&mut self);
+}
+"#;
+        let editor = RustEditor::new(code).unwrap();
+        let arity = |node_type: &str, name: &str| {
+            editor
+                .inspect(Some(node_type), Some(name), None, false)
+                .unwrap()[0]
+                .arity
+        };
+
+        assert_eq!(arity("function", "f").map(|a| a.count(false)), Some(0));
+        assert_eq!(arity("function", "g").map(|a| a.count(false)), Some(2));
+        assert_eq!(
+            arity("impl-method", "get"),
+            Some(Arity {
+                params: 2,
+                has_receiver: true
+            })
+        );
+        assert_eq!(arity("impl-method", "get").map(|a| a.count(true)), Some(1));
+        assert_eq!(arity("trait-method", "run").map(|a| a.count(true)), Some(0));
+        assert_eq!(arity("struct", "S"), None);
+    }
+
+    #[test]
+    fn test_trait_method_required_vs_default() {
+        use crate::commands::find::trait_method_has_default;
+
+        let code = r#"
+pub trait Handler {
+    fn handle(&self, req: u32) -> u32;
+    fn name(&self) -> &str {
+        "handler"
+    }
+}
+"#;
+        let editor = RustEditor::new(code).unwrap();
+        let results = editor
+            .inspect(Some("trait-method"), Some("Handler"), None, false)
+            .unwrap();
+        let kinds: Vec<(&str, Option<bool>)> = results
+            .iter()
+            .map(|r| {
+                (
+                    r.identifier.as_str(),
+                    trait_method_has_default(&r.node_type, &r.snippet),
+                )
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("Handler::handle", Some(false)),
+                ("Handler::name", Some(true)),
+            ]
+        );
+        assert_eq!(trait_method_has_default("function", "fn f() {}"), None);
+    }
+
+    #[test]
+    fn test_sync_match_arms() {
+        let code = r#"
+pub enum Status {
+    Active,
+    Done,
+}
+
+pub fn handle(status: Status) -> u8 {
+    match status {
+        Status::Active => 1,
+        Status::Removed => 2,
+    }
+}
+"#;
+        let op = |prune| {
+            Operation::SyncMatchArms(SyncMatchArmsOp {
+                enum_name: "Status".to_string(),
+                body: "todo!()".to_string(),
+                function_name: Some("handle".to_string()),
+                variants: vec![],
+                prune,
+            })
+        };
+
+        let mut editor = RustEditor::new(code).unwrap();
+        let result = editor.apply_operation(&op(false)).unwrap();
+        assert!(result.changed);
+        let output = editor.to_string();
+        assert!(output.contains("Status::Done => todo!(),"));
+        assert!(output.contains("Status::Removed => 2,"));
+
+        let mut editor = RustEditor::new(code).unwrap();
+        editor.apply_operation(&op(true)).unwrap();
+        let output = editor.to_string();
+        assert!(output.contains("Status::Done => todo!(),"));
+        assert!(!output.contains("Status::Removed"));
+        assert!(output.contains("        Status::Active => 1,\n        Status::Done"));
+    }
+
+    #[test]
+    fn test_multi_transform() {
+        let code = r#"
+fn main() {
+    eprintln!("debug");
+    let value = compute().unwrap();
+    old_func(value);
+}
+"#;
+        let spec = r#"
+transforms:
+  - node_type: macro-call
+    name_filter: eprintln
+    action: { type: Comment }
+  - node_type: function-call
+    name_filter: old_func
+    action: { type: Replace, with: "new_func(value)" }
+"#;
+        let op: MultiTransformOp = serde_yaml::from_str(spec).unwrap();
+        let mut editor = RustEditor::new(code).unwrap();
+        let result = editor
+            .apply_operation(&Operation::MultiTransform(op))
+            .unwrap();
+
+        assert!(result.changed);
+        assert_eq!(result.modified_nodes.len(), 2);
+        let output = editor.to_string();
+        assert!(output.contains("// eprintln!(\"debug\");"));
+        assert!(output.contains("new_func(value);"));
+        assert!(!output.contains("old_func"));
+    }
+
+    #[test]
+    fn test_transform_to_let_else() {
+        let code = r#"
+fn load(opt: Option<u32>, res: Result<String, ()>) -> u32 {
+    let x = if let Some(v) = opt { v } else { return 0; };
+    let mut s = if let Ok(inner) = res {
+        inner
+    } else {
+        panic!("bad input");
+    };
+    let typed: u32 = if let Some(v) = opt { v } else { return 1; };
+    let computed = if let Some(v) = opt { v + 1 } else { return 2; };
+    let fallback = if let Some(v) = opt { v } else { 3 };
+    s.push('!');
+    x + typed + computed + fallback
+}
+"#;
+        let mut editor = RustEditor::new(code).unwrap();
+        let result = editor
+            .transform(&TransformOp {
+                node_type: "function".to_string(),
+                name_filter: Some("load".to_string()),
+                content_filter: None,
+                action: TransformAction::ToLetElse,
+                exclude_names: Vec::new(),
+                exclude_in: Vec::new(),
+                name_regex: None,
+            })
+            .unwrap();
+        assert!(result.changed);
+
+        let output = editor.to_string();
+        assert!(output.contains("let Some(x) = opt else { return 0; };"));
+        assert!(
+            output.contains("let Ok(mut s) = res else {\n        panic!(\"bad input\");\n    };")
+        );
+        // Shapes outside the narrow pattern are left alone
+        assert!(output.contains("let typed: u32 = if let Some(v) = opt { v } else { return 1; };"));
+        assert!(
+            output.contains("let computed = if let Some(v) = opt { v + 1 } else { return 2; };")
+        );
+        assert!(output.contains("let fallback = if let Some(v) = opt { v } else { 3 };"));
+        assert!(syn::parse_file(&output).is_ok());
+
+        // Nothing left to rewrite: no change reported
+        let mut editor = RustEditor::new(&output).unwrap();
+        let result = editor
+            .transform(&TransformOp {
+                node_type: "function".to_string(),
+                name_filter: Some("load".to_string()),
+                content_filter: None,
+                action: TransformAction::ToLetElse,
+                exclude_names: Vec::new(),
+                exclude_in: Vec::new(),
+                name_regex: None,
+            })
+            .unwrap();
+        assert!(!result.changed);
+    }
+
+    #[test]
+    fn test_transform_simplify_format() {
+        let code = r#"
+fn render(id: u32, user: &User, a: i32, b: i32) -> Vec<String> {
+    vec![]
+        .into_iter()
+        .chain([
+            format!("{}", id),
+            format!("{}", user.name),
+            format!("{}", a + b),
+            std::format!("{}", format!("{}", id)),
+            format!("{}!", id),
+            format!("{} {}", a, b),
+            format!("id: {}", id),
+            format!("{id}"),
+        ])
+        .collect()
+}
+"#;
+        let mut editor = RustEditor::new(code).unwrap();
+        let result = editor
+            .transform(&TransformOp {
+                node_type: "function".to_string(),
+                name_filter: Some("render".to_string()),
+                content_filter: None,
+                action: TransformAction::SimplifyFormat,
+                exclude_names: Vec::new(),
+                exclude_in: Vec::new(),
+                name_regex: None,
+            })
+            .unwrap();
+        assert!(result.changed);
+
+        let output = editor.to_string();
+        assert!(output.contains("            id.to_string(),"));
+        assert!(output.contains("            user.name.to_string(),"));
+        assert!(output.contains("            (a + b).to_string(),"));
+        assert!(output.contains("            id.to_string().to_string(),"));
+        // Anything beyond one bare `{}` and one argument is left alone
+        assert!(output.contains("format!(\"{}!\", id)"));
+        assert!(output.contains("format!(\"{} {}\", a, b)"));
+        assert!(output.contains("format!(\"id: {}\", id)"));
+        assert!(output.contains("format!(\"{id}\")"));
+        assert!(syn::parse_file(&output).is_ok());
+
+        // A lone macro-call match is rewritten too
+        let mut editor = RustEditor::new("fn f(x: u8) -> String { format!(\"{}\", x) }").unwrap();
+        let result = editor
+            .transform(&TransformOp {
+                node_type: "macro-call".to_string(),
+                name_filter: Some("format".to_string()),
+                content_filter: None,
+                action: TransformAction::SimplifyFormat,
+                exclude_names: Vec::new(),
+                exclude_in: Vec::new(),
+                name_regex: None,
+            })
+            .unwrap();
+        assert!(result.changed);
+        assert_eq!(
+            editor.to_string(),
+            "fn f(x: u8) -> String { x.to_string() }"
+        );
+    }
+
+    #[test]
+    fn test_transform_cfg_guard() {
+        let code = r#"fn run(x: u32) -> u32 {
+    eprintln!("start {}", x);
+    match x {
+        0 => eprintln!("zero"),
+        _ => {}
+    }
+    let n = dbg!(x);
+    n
+}
+
+fn dump() {}
+"#;
+        let guard = |editor: &mut RustEditor, node_type: &str, name: &str| {
+            editor
+                .transform(&TransformOp {
+                    node_type: node_type.to_string(),
+                    name_filter: Some(name.to_string()),
+                    content_filter: None,
+                    action: TransformAction::CfgGuard {
+                        cfg: "debug_assertions".to_string(),
+                    },
+                    exclude_names: Vec::new(),
+                    exclude_in: Vec::new(),
+                    name_regex: None,
+                })
+                .unwrap()
+        };
+
+        let mut editor = RustEditor::new(code).unwrap();
+        let result = guard(&mut editor, "macro-call", "eprintln");
+        assert_eq!(result.modified_nodes.len(), 2);
+        let mut editor = RustEditor::new(&editor.to_string()).unwrap();
+        let result = guard(&mut editor, "function", "dump");
+        assert_eq!(result.modified_nodes.len(), 1);
+        // dbg! yields a value, so wrapping it would change the type; it's left alone
+        let mut editor = RustEditor::new(&editor.to_string()).unwrap();
+        let result = guard(&mut editor, "macro-call", "dbg");
+        assert!(!result.changed);
+
+        let output = editor.to_string();
+        assert!(output.contains(
+            "    if cfg!(debug_assertions) {\n        eprintln!(\"start {}\", x);\n    }\n"
+        ));
+        assert!(output.contains("0 => if cfg!(debug_assertions) { eprintln!(\"zero\") },"));
+        assert!(output.contains("#[cfg(debug_assertions)]\nfn dump() {}"));
+        assert!(output.contains("let n = dbg!(x);"));
+        assert!(syn::parse_file(&output).is_ok());
+
+        // Guarded sites aren't wrapped twice
+        let mut editor = RustEditor::new(&output).unwrap();
+        assert!(!guard(&mut editor, "macro-call", "eprintln").changed);
+        assert!(!guard(&mut editor, "function", "dump").changed);
+    }
+
+    #[test]
+    fn test_transform_to_constructor() {
+        let code = r#"
+struct Config {
+    name: String,
+    retries: u32,
+}
+
+fn defaults(name: String) -> Config {
+    let a = Config { name, retries: 3 };
+    let b = Config { retries: 1, name: "b".into() };
+    a
+}
+"#;
+        let to_constructor = |code: &str| {
+            let mut editor = RustEditor::new(code).unwrap();
+            editor
+                .transform(&TransformOp {
+                    node_type: "struct-literal".to_string(),
+                    name_filter: Some("Config".to_string()),
+                    content_filter: None,
+                    action: TransformAction::ToConstructor {
+                        constructor: "new".to_string(),
+                        fields: Vec::new(),
+                    },
+                    exclude_names: Vec::new(),
+                    exclude_in: Vec::new(),
+                    name_regex: None,
+                })
+                .map(|result| (result.modified_nodes.len(), editor.to_string()))
+        };
+
+        // `"b".into()` would run before `1` after reordering, which isn't what was written
+        let err = to_constructor(code).unwrap_err();
+        assert!(format!("{:#}", err).contains("out of declaration order"));
+
+        // Out of order but side-effect free is fine; the order comes from the definition
+        let code = code.replace(r#"name: "b".into()"#, "name: String::new");
+        let (converted, output) = to_constructor(&code).unwrap();
+        assert_eq!(converted, 2);
+        assert!(output.contains("let a = Config::new(name, 3);"));
+        assert!(output.contains("let b = Config::new(String::new, 1);"));
+
+        // `..rest` leaves fields the constructor can't be given
+        let err = to_constructor(&code.replace("retries: 3 }", "..base() }")).unwrap_err();
+        assert!(format!("{:#}", err).contains("uses `..`"));
+    }
+
+    #[test]
+    fn test_transform_to_ufcs() {
+        let code = r#"
+fn convert(x: Bar, y: u8) -> Foo {
+    let a: Foo = x.into();
+    let b: Foo = (y + 1).into();
+    let v = items.iter().collect::<Vec<_>>();
+    a
+}
+"#;
+        let to_ufcs =
+            |method: &str, trait_path: &str, to_type: Option<&str>, receiver: Option<&str>| {
+                let mut editor = RustEditor::new(code).unwrap();
+                let result = editor
+                    .transform(&TransformOp {
+                        node_type: "method-call".to_string(),
+                        name_filter: Some(method.to_string()),
+                        content_filter: None,
+                        action: TransformAction::ToUfcs {
+                            trait_path: trait_path.to_string(),
+                            to_type: to_type.map(str::to_string),
+                            receiver: receiver.map(str::to_string),
+                        },
+                        exclude_names: Vec::new(),
+                        exclude_in: Vec::new(),
+                        name_regex: None,
+                    })
+                    .unwrap();
+                (result.modified_nodes.len(), editor.to_string())
+            };
+
+        let (converted, output) = to_ufcs("into", "Into", Some("Foo"), None);
+        assert_eq!(converted, 2);
+        assert!(output.contains("let a: Foo = Into::<Foo>::into(x);"));
+        assert!(output.contains("let b: Foo = Into::<Foo>::into(y + 1);"));
+
+        let (converted, output) = to_ufcs("into", "Into", Some("Foo"), Some("x"));
+        assert_eq!(converted, 1);
+        assert!(output.contains("let b: Foo = (y + 1).into();"));
+
+        // A method turbofish stays on the method
+        let (_, output) = to_ufcs("collect", "Iterator", None, None);
+        assert!(output.contains("let v = Iterator::collect::<Vec<_>>(items.iter());"));
+    }
+
+    #[test]
+    fn test_transform_wrap() {
+        let code = r#"
+fn run() {
+    let x = a.b().c();
+    let y = compute(1);
+    let z = Config { a: 1 };
+}
+"#;
+        let wrap = |node_type: &str, name: Option<&str>, with: &str| {
+            let mut editor = RustEditor::new(code).unwrap();
+            let result = editor.transform(&TransformOp {
+                node_type: node_type.to_string(),
+                name_filter: name.map(str::to_string),
+                content_filter: None,
+                action: TransformAction::Wrap {
+                    with: with.to_string(),
+                },
+                exclude_names: Vec::new(),
+                exclude_in: Vec::new(),
+                name_regex: None,
+            });
+            result.map(|r| (r.modified_nodes.len(), editor.to_string()))
+        };
+
+        let (wrapped, output) = wrap("function-call", None, "instrument({})").unwrap();
+        assert_eq!(wrapped, 1);
+        assert!(output.contains("let y = instrument(compute(1));"));
+
+        // The whole chain is wrapped once, not the inner `a.b()` as well
+        let (wrapped, output) = wrap("method-call", None, "instrument({})").unwrap();
+        assert_eq!(wrapped, 1);
+        assert!(
+            output.contains("let x = instrument(a.b().c());"),
+            "{}",
+            output
+        );
+        let (_, output) = wrap("method-call", Some("b"), "instrument({})").unwrap();
+        assert!(output.contains("let x = instrument(a.b()).c();"));
+
+        let (_, output) = wrap("struct-literal", None, "Box::new({})").unwrap();
+        assert!(output.contains("let z = Box::new(Config { a: 1 });"));
+
+        assert!(wrap("function-call", None, "instrument()").is_err());
+    }
+
+    #[test]
+    fn test_transform_replace_regex() {
+        let code = r#"fn main() {
+    println!("DEBUG: start {}", id);
+    eprintln!("DEBUG: untouched");
+    println!("{}", format!("DEBUG: {}", 1));
+    let label = "DEBUG: kept";
+}
+"#;
+        let replace = |pattern: &str, with: &str| {
+            let mut editor = RustEditor::new(code).unwrap();
+            let result = editor.transform(&TransformOp {
+                node_type: "macro-call".to_string(),
+                name_filter: Some("println".to_string()),
+                content_filter: None,
+                action: TransformAction::ReplaceRegex {
+                    pattern: pattern.to_string(),
+                    with: with.to_string(),
+                },
+                exclude_names: Vec::new(),
+                exclude_in: Vec::new(),
+                name_regex: None,
+            });
+            result.map(|r| (r.modified_nodes.len(), editor.to_string()))
+        };
+
+        let (changed, output) = replace("DEBUG:", "TRACE:").unwrap();
+        assert_eq!(changed, 2);
+        assert!(output.contains(r#"println!("TRACE: start {}", id);"#));
+        // Only inside the matched nodes
+        assert!(output.contains(r#"eprintln!("DEBUG: untouched");"#));
+        assert!(output.contains(r#"let label = "DEBUG: kept";"#));
+        // Text inside a matched node is rewritten, nested macro calls included
+        assert!(output.contains(r#"println!("{}", format!("TRACE: {}", 1));"#));
+
+        let (_, output) = replace(r"DEBUG: (\w+)", "[$1]").unwrap();
+        assert!(output.contains(r#"println!("[start] {}", id);"#));
+
+        // Substitutions that break the syntax are refused
+        assert!(replace(r"\)", "").is_err());
+        assert!(replace("(", "x").is_err());
+    }
+
+    #[test]
+    fn test_transform_rename_signature() {
+        let code = r#"
+/// Loads the config
+#[inline]
+pub fn load(path: &str) -> u8 {
+    fn load() {}
+    load();
+    path.len() as u8
+}
+
+struct Store;
+impl Store {
+    fn load(&self) -> u8 { load("x") }
+}
+"#;
+        let rename = |node_type: &str, new_name: &str| {
+            let mut editor = RustEditor::new(code).unwrap();
+            let result = editor.transform(&TransformOp {
+                node_type: node_type.to_string(),
+                name_filter: Some("load".to_string()),
+                content_filter: None,
+                action: TransformAction::RenameSignature {
+                    new_name: new_name.to_string(),
+                },
+                exclude_names: Vec::new(),
+                exclude_in: Vec::new(),
+                name_regex: None,
+            });
+            result.map(|r| (r.modified_nodes.len(), editor.to_string()))
+        };
+
+        let (changed, output) = rename("function", "read").unwrap();
+        assert_eq!(changed, 2);
+        assert!(
+            output.contains("/// Loads the config\n#[inline]\npub fn read(path: &str) -> u8 {")
+        );
+        assert!(output.contains("    fn read() {}\n    load();"));
+        // Methods and calls are other nodes
+        assert!(output.contains(r#"fn load(&self) -> u8 { load("x") }"#));
+
+        let (changed, output) = rename("impl-method", "fetch").unwrap();
+        assert_eq!(changed, 1);
+        assert!(output.contains(r#"fn fetch(&self) -> u8 { load("x") }"#));
+
+        assert!(rename("function", "not a name").is_err());
+        assert!(rename("method-call", "read").is_err());
+    }
+
+    #[test]
+    fn test_update_visibility() {
+        let code = "/// Settings\n#[derive(Debug)]\nstruct Config;\n\npub(super) enum Mode {\n    A,\n}\n\nasync fn fetch() {}\n\nmod inner {\n    pub(in crate) fn helper() {}\n}\n";
+        let op = |node_types: &[&str], name: Option<&str>, regex: Option<&str>, vis: &str| {
+            Operation::UpdateVisibility(UpdateVisibilityOp {
+                node_types: node_types.iter().map(|t| t.to_string()).collect(),
+                name: name.map(str::to_string),
+                name_regex: regex.map(str::to_string),
+                visibility: vis.to_string(),
+            })
+        };
+        let apply = |op: Operation| {
+            let mut editor = RustEditor::new(code).unwrap();
+            let result = editor.apply_operation(&op).unwrap();
+            (editor.to_string(), result.modified_nodes.len())
+        };
+
+        // Inserted after the attributes of a private item, replaced where there is one
+        let (content, changed) = apply(op(&["struct"], Some("Config"), None, "pub(crate)"));
+        assert_eq!(changed, 1);
+        assert!(content.contains("#[derive(Debug)]\npub(crate) struct Config;"));
+        let (content, changed) = apply(op(&["function"], None, Some(".*"), "pub"));
+        assert_eq!(changed, 2);
+        assert!(content.contains("pub async fn fetch() {}"));
+        assert!(content.contains("    pub fn helper() {}"));
+
+        // Empty makes it private; an item already at the visibility is left alone
+        let (content, _) = apply(op(&["enum"], Some("Mode"), None, ""));
+        assert!(content.contains("\nenum Mode {"));
+        let (_, changed) = apply(op(&["enum"], Some("Mode"), None, "pub(super)"));
+        assert_eq!(changed, 0);
+
+        let mut editor = RustEditor::new(code).unwrap();
+        assert!(
+            editor
+                .apply_operation(&op(&["impl-method"], Some("x"), None, "pub"))
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "script-hooks")]
+    #[test]
+    fn test_transform_script_hook() {
+        let dir = std::env::temp_dir().join(format!(
+            "rs-hack-script-{}",
+            crate::state::generate_run_id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("hook.rhai");
+        // Wrap unwrap() calls in a named expect, leaving other method calls as they are
+        std::fs::write(
+            &script,
+            "if identifier == \"unwrap\" {\n    source.replace(\"unwrap()\", \"expect(\\\"checked\\\")\");\n}\nsource\n",
+        )
+        .unwrap();
+
+        let code = "fn main() {\n    let a = load().unwrap();\n    let b = a.len();\n}\n";
+        let transform = |name: Option<&str>| {
+            let mut editor = RustEditor::new(code).unwrap();
+            editor
+                .transform(&TransformOp {
+                    node_type: "method-call".to_string(),
+                    name_filter: name.map(str::to_string),
+                    content_filter: None,
+                    action: TransformAction::Script {
+                        script: script.clone(),
+                    },
+                    exclude_names: Vec::new(),
+                    exclude_in: Vec::new(),
+                    name_regex: None,
+                })
+                .map(|result| (result.modified_nodes.len(), editor.to_string()))
+        };
+        let (modified, output) = transform(None).unwrap();
+        assert_eq!(modified, 1);
+        assert_eq!(
+            output,
+            "fn main() {\n    let a = load().expect(\"checked\");\n    let b = a.len();\n}\n"
+        );
+
+        // A throw stops the transform with its message
+        std::fs::write(&script, "throw \"cannot rewrite \" + identifier;\n").unwrap();
+        let err = transform(Some("len")).unwrap_err();
+        assert!(err.to_string().contains("cannot rewrite len"));
+
+        // So does a script that doesn't compile, before any node is touched
+        std::fs::write(&script, "source +\n").unwrap();
+        let err = transform(None).unwrap_err();
+        assert!(err.to_string().contains("Invalid transform script"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_inspect_derive_sites() {
+        let code = r#"
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize)]
+pub struct Config {
+    name: String,
+}
+
+#[derive(Serialize, PartialEq)]
+enum Status {
+    Active,
+}
+
+#[derive(Debug)]
+struct Plain;
+
+struct NoDerives;
+"#;
+        let editor = RustEditor::new(code).unwrap();
+
+        let results = editor
+            .inspect(Some("derive"), Some("Serialize"), None, false)
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].identifier, "Config");
+        assert_eq!(
+            results[0].snippet,
+            "#[derive(Debug, Clone, serde::Serialize)] struct Config"
+        );
+        // Spans both derive attributes
+        assert_eq!(results[0].location.line, 2);
+        assert_eq!(results[0].location.end_line, 3);
+        assert_eq!(results[1].identifier, "Status");
+        assert_eq!(
+            results[1].snippet,
+            "#[derive(Serialize, PartialEq)] enum Status"
+        );
+
+        // Without a filter every item with a derive is reported
+        let all = editor.inspect(Some("derive"), None, None, false).unwrap();
+        let names: Vec<&str> = all.iter().map(|r| r.identifier.as_str()).collect();
+        assert_eq!(names, ["Config", "Status", "Plain"]);
+    }
+
+    #[test]
+    fn test_inspect_attributes() {
+        let code = r#"#[deprecated]
+pub struct Legacy {
+    #[deprecated(since = "1.2",
+                 note = "use id")]
+    old_id: u32,
+}
+
+pub enum Mode {
+    #[deprecated]
+    Fast,
+    Slow,
+}
+
+impl Legacy {
+    #[deprecated(note = "use new")]
+    pub fn create() -> Self {
+        todo!()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn works() {}
+
+    #[tokio::test]
+    async fn works_async() {}
+}
+"#;
+        let editor = RustEditor::new(code).unwrap();
+
+        let deprecated = editor
+            .inspect(Some("attribute"), Some("deprecated"), None, false)
+            .unwrap();
+        let names: Vec<&str> = deprecated.iter().map(|r| r.identifier.as_str()).collect();
+        assert_eq!(
+            names,
+            ["Legacy", "Legacy.old_id", "Mode::Fast", "Legacy::create"]
+        );
+        assert_eq!(deprecated[0].snippet, "#[deprecated] struct Legacy");
+        assert_eq!(deprecated[0].location.line, 1);
+        // Multi-line attributes are collapsed onto one line in the snippet
+        assert_eq!(
+            deprecated[1].snippet,
+            r#"#[deprecated(since = "1.2", note = "use id")] field Legacy.old_id"#
+        );
+        assert_eq!(deprecated[1].location.line, 3);
+        assert_eq!(deprecated[1].location.end_line, 4);
+
+        // Matches the last path segment as well as the full path
+        let tests = editor
+            .inspect(Some("attribute"), Some("test"), None, false)
+            .unwrap();
+        let names: Vec<&str> = tests.iter().map(|r| r.identifier.as_str()).collect();
+        assert_eq!(names, ["works", "works_async"]);
+
+        let cfg = editor
+            .inspect(Some("attribute"), Some("cfg"), None, false)
+            .unwrap();
+        assert_eq!(cfg.len(), 1);
+        assert_eq!(cfg[0].snippet, "#[cfg(test)] mod tests");
+    }
+
+    #[test]
+    fn test_inspect_where_clause_bounds() {
+        let code = r#"fn spawn<F: FnOnce() + Send + 'static>(f: F) {}
+
+fn plain(x: u32) {}
+
+pub struct Pool<T>
+where
+    T: std::marker::Send,
+{
+    items: Vec<T>,
+}
+
+impl<T: Clone> Pool<T> {
+    fn run<U>(&self, u: U) -> U
+    where
+        U: Send + Sync,
+    {
+        u
+    }
+}
+
+trait Job: Send {}
+"#;
+        let editor = RustEditor::new(code).unwrap();
+
+        let send = editor
+            .inspect(Some("where-clause"), Some("Send"), None, false)
+            .unwrap();
+        let names: Vec<&str> = send.iter().map(|r| r.identifier.as_str()).collect();
+        assert_eq!(names, ["spawn", "Pool", "Pool::run", "Job"]);
+        assert_eq!(
+            send[0].snippet,
+            "fn spawn<F: FnOnce() + Send + 'static>(f: F)"
+        );
+        // The signature runs through the where clause, on one line
+        assert_eq!(
+            send[1].snippet,
+            "struct Pool<T> where T: std::marker::Send,"
+        );
+        assert_eq!(send[1].location.line, 5);
+        assert_eq!(
+            send[2].snippet,
+            "fn run<U>(&self, u: U) -> U where U: Send + Sync,"
+        );
+
+        // Full paths match too; without a filter, every bounded item is listed
+        let full = editor
+            .inspect(Some("where-clause"), Some("std::marker::Send"), None, false)
+            .unwrap();
+        assert_eq!(full.len(), 1);
+        let all = editor
+            .inspect(Some("where-clause"), None, None, false)
+            .unwrap();
+        let names: Vec<&str> = all.iter().map(|r| r.identifier.as_str()).collect();
+        assert_eq!(names, ["spawn", "Pool", "Pool", "Pool::run", "Job"]);
+        assert_eq!(all[2].snippet, "impl<T: Clone> Pool<T>");
+    }
+
+    #[test]
+    fn test_align_struct_fields() {
+        let code = r#"pub struct Config {
+    pub name: String,
+    /// Seconds
+    #[serde(default)]
+    timeout: u64, // kept
+    pub retries:   u8,
+    a: u8, b: u8,
+    long_one:
+        Vec<u8>,
+}
+"#;
+        let op = Operation::AlignStructFields(AlignStructFieldsOp {
+            struct_name: "Config".to_string(),
+            in_fn: None,
+        });
+        let mut editor = RustEditor::new(code).unwrap();
+        assert!(editor.apply_operation(&op).unwrap().changed);
+        assert_eq!(
+            editor.to_string(),
+            r#"pub struct Config {
+    pub name:    String,
+    /// Seconds
+    #[serde(default)]
+    timeout:     u64, // kept
+    pub retries: u8,
+    a: u8, b: u8,
+    long_one:
+        Vec<u8>,
+}
+"#
+        );
+
+        // Already aligned: nothing to do
+        let mut editor = RustEditor::new(&editor.to_string()).unwrap();
+        assert!(!editor.apply_operation(&op).unwrap().changed);
+    }
+
+    #[test]
+    fn test_remove_unused_imports() {
+        use crate::commands::unused_imports::{SkipReason, analyze};
+
+        let code = r#"use std::collections::HashMap;
+use std::rc::Rc;
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::io::{self, Write};
+use std::path::{
+    Path,
+    PathBuf,
+    Component,
+};
+use super::*;
+use serde::Serialize;
+pub use std::fmt;
+
+fn main() {
+    let _: HashMap<u8, HashSet<u8>> = HashMap::new();
+    let _ = (PathBuf::new(), Option::<Component>::None);
+    println!("{:?}", VecDeque::<u8>::new());
+}
+
+#[derive(Serialize)]
+struct A;
+"#;
+        let syntax = syn::parse_file(code).unwrap();
+        let imports = analyze(&syntax, &[]);
+        let mut unused: Vec<_> = imports.unused.iter().map(String::as_str).collect();
+        unused.sort();
+        assert_eq!(unused, vec!["BTreeMap", "Path", "Rc", "io"]);
+        let skipped: Vec<_> = imports
+            .skipped
+            .iter()
+            .map(|(line, _, reason)| (*line, *reason))
+            .collect();
+        assert_eq!(
+            skipped,
+            vec![
+                (4, SkipReason::MacroOnly),
+                (5, SkipReason::MaybeTrait),
+                (11, SkipReason::Glob)
+            ]
+        );
+
+        let op = Operation::RemoveUnusedImports(RemoveUnusedImportsOp::default());
+        let mut editor = RustEditor::new(code).unwrap();
+        let result = editor.apply_operation(&op).unwrap();
+        assert!(result.changed);
+        // A whole-file backup for revert, then one node per removed import
+        assert_eq!(result.modified_nodes[0].node_type, "file");
+        assert_eq!(result.modified_nodes.len(), 5);
+        assert!(editor.to_string().starts_with(
+            r#"use std::collections::HashMap;
+
+use std::collections::{HashSet, VecDeque};
+use std::io::Write;
+use std::path::{
+    PathBuf,
+    Component,
+};
+use super::*;
+"#
+        ));
+
+        // Nothing left to remove
+        let mut editor = RustEditor::new(&editor.to_string()).unwrap();
+        assert!(!editor.apply_operation(&op).unwrap().changed);
+    }
+
+    #[test]
+    fn test_doc_comments_on_methods() {
+        let code = r#"impl Config {
+    #[inline]
+    pub fn new() -> Self {
+        Config
+    }
+}
+
+trait Shape {
+    /// Old doc
+    fn area(&self) -> f64;
+}
+"#;
+        let mut editor = RustEditor::new(code).unwrap();
+        editor
+            .apply_operation(&Operation::AddDocComment(AddDocCommentOp {
+                target_type: "impl-method".to_string(),
+                name: "Config::new".to_string(),
+                doc_comment: "Constructs a default Config".to_string(),
+                style: DocCommentStyle::Line,
+            }))
+            .unwrap();
+        editor
+            .apply_operation(&Operation::UpdateDocComment(UpdateDocCommentOp {
+                target_type: "trait-method".to_string(),
+                name: "Shape::area".to_string(),
+                doc_comment: "Area in square units".to_string(),
+            }))
+            .unwrap();
+        assert_eq!(
+            editor.to_string(),
+            r#"impl Config {
+    /// Constructs a default Config
+    #[inline]
+    pub fn new() -> Self {
+        Config
+    }
+}
+
+trait Shape {
+    /// Area in square units
+    fn area(&self) -> f64;
+}
+"#
+        );
+
+        // Methods are named through their type or trait
+        let err = editor
+            .apply_operation(&Operation::RemoveDocComment(RemoveDocCommentOp {
+                target_type: "impl-method".to_string(),
+                name: "Shape::area".to_string(),
+            }))
+            .unwrap_err();
+        assert!(err.to_string().contains("not found"));
+    }
+
+    #[test]
+    fn test_execute_parallel_keeps_file_order_and_limit() {
+        use crate::execute::{ExecuteOpts, execute};
+
+        let dir = std::env::temp_dir().join(format!(
+            "rs-hack-parallel-{}",
+            crate::state::generate_run_id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let files: Vec<_> = (0..24)
+            .map(|i| {
+                let path = dir.join(format!("f{:02}.rs", i));
+                std::fs::write(&path, "fn f() {\n    x.unwrap();\n    y.unwrap();\n}\n").unwrap();
+                path
+            })
+            .collect();
+        std::fs::write(&files[3], "fn broken( {").unwrap();
+
+        let op = Operation::Transform(TransformOp {
+            node_type: "method-call".to_string(),
+            name_filter: Some("unwrap".to_string()),
+            content_filter: None,
+            action: TransformAction::Comment,
+            exclude_names: Vec::new(),
+            exclude_in: Vec::new(),
+            name_regex: None,
+        });
+        let opts = ExecuteOpts {
+            limit: Some(9),
+            ..ExecuteOpts::default()
+        };
+        let result = execute(&files, &op, &opts).unwrap();
+
+        // Stops at the first file that reaches the limit, counting in the order given
+        let changed: Vec<_> = result.changes.iter().map(|c| c.path.clone()).collect();
+        let expected: Vec<_> = files[..6]
+            .iter()
+            .filter(|f| **f != files[3])
+            .cloned()
+            .collect();
+        assert_eq!(changed, expected);
+        assert_eq!(result.total_modifications, 10);
+        assert!(result.limit_hit);
+        assert_eq!(result.parse_errors.len(), 1);
+        assert_eq!(result.parse_errors[0].0, files[3]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_execute_skips_unparseable_single_file() {
+        use crate::execute::{ExecuteOpts, execute};
+
+        let dir = std::env::temp_dir().join(format!(
+            "rs-hack-skip-broken-{}",
+            crate::state::generate_run_id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let broken = dir.join("fixture.rs");
+        std::fs::write(&broken, "fn broken( {").unwrap();
+
+        let op = Operation::Transform(TransformOp {
+            node_type: "method-call".to_string(),
+            name_filter: Some("unwrap".to_string()),
+            content_filter: None,
+            action: TransformAction::Comment,
+            exclude_names: Vec::new(),
+            exclude_in: Vec::new(),
+            name_regex: None,
+        });
+
+        // One file is skipped like any other, rather than aborting the run
+        let result = execute(std::slice::from_ref(&broken), &op, &ExecuteOpts::default()).unwrap();
+        assert!(result.changes.is_empty());
+        assert_eq!(result.parse_errors.len(), 1);
+        assert_eq!(result.parse_errors[0].0, broken);
+        assert!(result.parse_errors[0].1.contains("line 1"));
+
+        // With `strict` it fails the run instead
+        let strict = ExecuteOpts {
+            strict: true,
+            ..ExecuteOpts::default()
+        };
+        assert!(execute(std::slice::from_ref(&broken), &op, &strict).is_err());
+
+        // A search where nothing parsed has searched nothing and fails
+        let args = crate::commands::find::FindArgs {
+            paths: vec![broken],
+            node_type: Some("method-call".to_string()),
+            ..Default::default()
+        };
+        let err = crate::commands::find::run(&args).unwrap_err().to_string();
+        assert!(
+            err.contains("None of the 1 file(s) could be parsed"),
+            "{}",
+            err
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_execute_writes_nothing_when_a_change_breaks_a_file() {
+        use crate::execute::{ExecuteOpts, execute};
+
+        let dir = std::env::temp_dir().join(format!(
+            "rs-hack-all-or-nothing-{}",
+            crate::state::generate_run_id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let fine = dir.join("fine.rs");
+        let broken = dir.join("broken.rs");
+        std::fs::write(&fine, "fn a() {\n    old(1);\n}\n").unwrap();
+        std::fs::write(&broken, "fn b() {\n    old(2);\n}\n").unwrap();
+
+        let replace = |content_filter: Option<&str>, with: &str| {
+            Operation::Transform(TransformOp {
+                node_type: "function-call".to_string(),
+                name_filter: Some("old".to_string()),
+                content_filter: content_filter.map(str::to_string),
+                action: TransformAction::Replace {
+                    with: with.to_string(),
+                },
+                exclude_names: Vec::new(),
+                exclude_in: Vec::new(),
+                name_regex: None,
+            })
+        };
+        // Only the replacement in broken.rs leaves a dangling paren
+        let op = replace(Some("2"), "new(2");
+        let rename = replace(None, "new()");
+        let opts = ExecuteOpts {
+            apply: true,
+            ..ExecuteOpts::default()
+        };
+        let files = [fine.clone(), broken.clone()];
+        let dry_run = execute(&files, &op, &ExecuteOpts::default()).unwrap();
+        let err = execute(&files, &op, &opts).unwrap_err().to_string();
+        let fine_after = std::fs::read_to_string(&fine).unwrap();
+        let broken_after = std::fs::read_to_string(&broken).unwrap();
+        let renamed = execute(&files, &rename, &opts).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(dry_run.changes.len(), 1);
+        assert!(err.contains("broken.rs:"), "{}", err);
+        assert!(err.contains("no files were written"), "{}", err);
+        assert_eq!(fine_after, "fn a() {\n    old(1);\n}\n");
+        assert_eq!(broken_after, "fn b() {\n    old(2);\n}\n");
+        assert_eq!(renamed.changes.len(), 2);
+    }
+
+    #[test]
+    fn test_execute_max_per_file() {
+        use crate::execute::{ExecuteOpts, execute};
+
+        let dir = std::env::temp_dir().join(format!(
+            "rs-hack-max-per-file-{}",
+            crate::state::generate_run_id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let generated = dir.join("generated.rs");
+        let handwritten = dir.join("handwritten.rs");
+        let generated_src = "fn a() {
+    x.unwrap();
+    y.unwrap();
+    z.unwrap();
+}
+";
+        std::fs::write(&generated, generated_src).unwrap();
+        std::fs::write(
+            &handwritten,
+            "fn b() {
+    x.unwrap();
+}
+",
+        )
+        .unwrap();
+
+        let op = Operation::Transform(TransformOp {
+            node_type: "method-call".to_string(),
+            name_filter: Some("unwrap".to_string()),
+            content_filter: None,
+            action: TransformAction::Comment,
+            exclude_names: Vec::new(),
+            exclude_in: Vec::new(),
+            name_regex: None,
+        });
+        let opts = ExecuteOpts {
+            apply: true,
+            max_per_file: Some(2),
+            ..ExecuteOpts::default()
+        };
+        let result = execute(&[generated.clone(), handwritten.clone()], &op, &opts).unwrap();
+
+        // The file over the cap is left alone and reported; the other one is changed
+        assert_eq!(result.capped_files, [(generated.clone(), 3)]);
+        assert_eq!(result.total_modifications, 1);
+        assert_eq!(std::fs::read_to_string(&generated).unwrap(), generated_src);
+        assert!(
+            std::fs::read_to_string(&handwritten)
+                .unwrap()
+                .contains("// x.unwrap()")
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_stdin_batch_serve() {
+        use crate::commands::stdin_batch::{ServeOpts, serve};
+
+        let dir = std::env::temp_dir().join(format!(
+            "rs-hack-stdin-batch-{}",
+            crate::state::generate_run_id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("user.rs");
+        std::fs::write(&file, "pub struct User {\n    id: u32,\n}\n").unwrap();
+
+        let request = |id: u32, name: &str, apply: bool| {
+            serde_json::json!({
+                "id": id,
+                "paths": [dir],
+                "apply": apply,
+                "operation": {
+                    "type": "AddDerive",
+                    "target_type": "struct",
+                    "target_name": name,
+                    "derives": ["Debug"],
+                },
+            })
+            .to_string()
+        };
+        let input = [
+            request(1, "User", false),
+            "not json".to_string(),
+            String::new(),
+            r#"{"id": 3, "paths": [], "operation": {"type": "AddDerive"}}"#.to_string(),
+            request(4, "User", true),
+        ]
+        .join("\n");
+
+        let mut output = Vec::new();
+        let opts = ServeOpts {
+            no_state: true,
+            ..ServeOpts::default()
+        };
+        let handled = serve(input.as_bytes(), &mut output, &opts).unwrap();
+        assert_eq!(handled, 4);
+
+        let responses: Vec<serde_json::Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        // Dry run: the diff shows the change, the file is untouched until request 4
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["ok"], true);
+        assert!(
+            responses[0]["files"][0]["diff"]
+                .as_str()
+                .unwrap()
+                .contains("+#[derive(Debug)]")
+        );
+        // Bad lines get an error response and the loop carries on
+        assert_eq!(responses[1]["ok"], false);
+        assert!(responses[1]["id"].is_null());
+        assert_eq!(responses[2]["id"], 3);
+        assert_eq!(responses[2]["ok"], false);
+        assert_eq!(responses[3]["id"], 4);
+        assert_eq!(responses[3]["total_modifications"], 1);
+        assert!(
+            std::fs::read_to_string(&file)
+                .unwrap()
+                .starts_with("#[derive(Debug)]")
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_find_field_method_collisions() {
+        let types = r#"
+pub struct Config {
+    name: String,
+    len: usize,
+    retries: u8,
+}
+
+pub struct Other {
+    name: String,
+}
+"#;
+        let impls = r#"
+impl Config {
+    pub fn name(&self) -> &str { &self.name }
+    pub fn retries(&mut self) -> u8 { self.retries += 1; self.retries }
+    pub fn other(&self) {}
+}
+
+impl Len for Config {
+    fn len(&self) -> usize { self.len.clone() }
+}
+"#;
+        let sources = vec![
+            ("types.rs".to_string(), syn::parse_file(types).unwrap()),
+            ("impls.rs".to_string(), syn::parse_file(impls).unwrap()),
+        ];
+
+        let collisions = crate::commands::find::field_method_collisions_in(&sources, None);
+        let found: Vec<(&str, &str, bool)> = collisions
+            .iter()
+            .map(|c| {
+                (
+                    c.field.as_str(),
+                    c.methods[0].file_path.as_str(),
+                    c.methods[0].is_getter,
+                )
+            })
+            .collect();
+        // Other has no impl with a `name` method, so its field doesn't collide
+        assert_eq!(
+            found,
+            [
+                ("name", "impls.rs", true),
+                ("len", "impls.rs", true),
+                ("retries", "impls.rs", false),
+            ]
+        );
+        assert_eq!(collisions[1].methods[0].trait_name.as_deref(), Some("Len"));
+        assert_eq!(collisions[0].line, 3);
+
+        assert!(
+            crate::commands::find::field_method_collisions_in(&sources, Some("Other")).is_empty()
+        );
+    }
+
+    #[test]
+    fn test_find_duplicate_variants() {
+        let code = r#"
+enum Status {
+    Active { since: u64 },
+    Idle,
+    Enabled { since: u64 },
+    Paused(String),
+    Stopped,
+    Halted(String),
+    Renamed { until: u64 },
+}
+
+mod other {
+    enum Event {
+        Click(u32),
+        Tap(u32),
+    }
+}
+"#;
+        let syntax = syn::parse_file(code).unwrap();
+
+        let groups = crate::commands::find::duplicate_variants_in(&syntax, Some("Status"));
+        let names: Vec<Vec<&str>> = groups
+            .iter()
+            .map(|g| g.variants.iter().map(|v| v.name.as_str()).collect())
+            .collect();
+        // Unit variants and same-typed fields under another name don't count
+        assert_eq!(names, [vec!["Active", "Enabled"], vec!["Paused", "Halted"]]);
+        assert_eq!(groups[0].enum_name, "Status");
+        assert_eq!(groups[0].variants[1].line, 5);
+
+        // Without a name every enum is checked, including ones in nested modules
+        let groups = crate::commands::find::duplicate_variants_in(&syntax, None);
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[2].enum_name, "Event");
+    }
+
+    #[test]
+    fn test_find_macro_tokens() {
+        let code = r#"
+bitflags! {
+    pub struct Perms: u32 {
+        const READ = 0b001;
+        const WRITE = 0b010;
+    }
+}
+
+fn route() {
+    my_dsl!(GET "/users" => list_users);
+    other::bitflags!(r#EXEC);
+}
+"#;
+        let syntax = syn::parse_file(code).unwrap();
+        let names = |macro_name: &str, filter: &dyn Fn(&str) -> bool| -> Vec<String> {
+            crate::commands::find::macro_tokens_in(&syntax, code, macro_name, filter)
+                .into_iter()
+                .map(|r| format!("{}:{}", r.identifier, r.location.line))
+                .collect()
+        };
+
+        let upper = |name: &str| name.chars().all(|c| c.is_ascii_uppercase());
+        // Path-qualified invocations count; raw identifiers are reported without `r#`
+        assert_eq!(names("bitflags", &upper), ["READ:4", "WRITE:5", "EXEC:11"]);
+        assert_eq!(names("my_dsl!", &|n| n == "list_users"), ["list_users:10"]);
+
+        let results =
+            crate::commands::find::macro_tokens_in(&syntax, code, "my_dsl", &|n| n == "GET");
+        assert_eq!(results[0].node_type, "macro-token");
+        assert_eq!(results[0].snippet, "my_dsl!(GET \"/users\" => list_users);");
+    }
+
+    #[test]
+    fn test_find_summary_counts() {
+        use crate::commands::find::FindSummary;
+
+        let editor = RustEditor::new(
+            "fn a() { x.unwrap(); y.unwrap(); }\nfn b() { z.unwrap(); format!(\"{}\", 1); }",
+        )
+        .unwrap();
+        let mut results = editor
+            .inspect(Some("method-call"), Some("unwrap"), None, false)
+            .unwrap();
+        results.extend(
+            editor
+                .inspect(Some("macro-call"), Some("format"), None, false)
+                .unwrap(),
+        );
+        for (i, result) in results.iter_mut().enumerate() {
+            result.file_path = if i == 0 { "a.rs" } else { "b.rs" }.to_string();
+        }
+
+        let summary = FindSummary::from_results(&results);
+        assert_eq!(summary.total, 4);
+        assert_eq!(summary.by_type["method-call"], 3);
+        assert_eq!(summary.by_type["macro-call"], 1);
+        assert_eq!(summary.by_file["a.rs"], 1);
+        assert_eq!(summary.by_file["b.rs"], 3);
+
+        let json = serde_json::to_value(FindSummary::from_results(&[])).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "by_type": {}, "by_file": {}, "total": 0 })
+        );
+    }
+
+    #[test]
+    fn test_find_count_by_type() {
+        use crate::commands::find::FindSummary;
+
+        // All node types at once, as `find --format count-by-type` without --node-type
+        let editor = RustEditor::new(
+            "struct A;
+struct B;
+enum E { X }
+fn f() {}
+fn g() {}
+fn h() { f(); }
+",
+        )
+        .unwrap();
+        let results = editor.inspect(None, None, None, false).unwrap();
+        let summary = FindSummary::from_results(&results);
+        let types = summary.types_by_count();
+        assert_eq!(summary.by_type["function"], 3);
+        assert_eq!(summary.by_type["struct"], 2);
+        assert_eq!(summary.by_type["enum"], 1);
+        assert_eq!(summary.by_type["function-call"], 1);
+        // Most common first
+        assert!(types.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        assert_eq!(types.iter().map(|(_, n)| n).sum::<usize>(), summary.total);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_rust_files_symlinked_dirs() {
+        use crate::files::{CollectOptions, collect_rust_files_with_options};
+
+        let dir = std::env::temp_dir().join(format!(
+            "rs-hack-symlinks-{}",
+            crate::state::generate_run_id()
+        ));
+        let src = dir.join("src");
+        let shared = dir.join("shared");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::create_dir_all(&shared).unwrap();
+        std::fs::write(src.join("lib.rs"), "fn a() {}\n").unwrap();
+        std::fs::write(shared.join("util.rs"), "fn b() {}\n").unwrap();
+        // One link out of the tree, and one back up to the root (a loop)
+        std::os::unix::fs::symlink(&shared, src.join("shared")).unwrap();
+        std::os::unix::fs::symlink(&src, src.join("again")).unwrap();
+
+        let names = |files: Vec<std::path::PathBuf>| -> Vec<String> {
+            let mut names: Vec<String> = files
+                .iter()
+                .map(|f| f.strip_prefix(&src).unwrap().display().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        // Default: symlinked directories aren't entered
+        let files = collect_rust_files_with_options(
+            std::slice::from_ref(&src),
+            &[],
+            &CollectOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(names(files), ["lib.rs"]);
+
+        // Opt in: the outside directory is walked, the loop ends, and lib.rs (also reachable
+        // as again/lib.rs) is only listed once
+        let follow = CollectOptions {
+            follow_symlinks: true,
+            ..CollectOptions::default()
+        };
+        let files =
+            collect_rust_files_with_options(std::slice::from_ref(&src), &[], &follow).unwrap();
+        assert_eq!(names(files), ["lib.rs", "shared/util.rs"]);
+
+        // The same file named twice is processed once
+        let files = collect_rust_files_with_options(
+            &[src.join("lib.rs"), src.clone()],
+            &[],
+            &CollectOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(files.len(), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_collect_rust_files_honors_gitignore() {
+        use crate::files::{CollectOptions, collect_rust_files_with_options};
+
+        let dir = std::env::temp_dir().join(format!(
+            "rs-hack-gitignore-{}",
+            crate::state::generate_run_id()
+        ));
+        for sub in ["src", "target/debug", "vendor", ".hidden"] {
+            std::fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        for file in [
+            "src/lib.rs",
+            "target/debug/build.rs",
+            "vendor/dep.rs",
+            ".hidden/x.rs",
+        ] {
+            std::fs::write(dir.join(file), "fn f() {}\n").unwrap();
+        }
+        std::fs::write(dir.join(".gitignore"), "/target\n").unwrap();
+        std::fs::write(dir.join(".ignore"), "vendor/\n").unwrap();
+
+        let names = |files: Vec<std::path::PathBuf>| -> Vec<String> {
+            let mut names: Vec<String> = files
+                .iter()
+                .map(|f| f.strip_prefix(&dir).unwrap().display().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        let walked = collect_rust_files_with_options(
+            std::slice::from_ref(&dir),
+            &[],
+            &CollectOptions::default(),
+        );
+        assert_eq!(names(walked.unwrap()), ["src/lib.rs"]);
+
+        // --no-ignore: everything, as a plain directory walk
+        let no_ignore = |max_depth| CollectOptions {
+            use_ignore_files: false,
+            max_depth,
+            ..CollectOptions::default()
+        };
+        let walked =
+            collect_rust_files_with_options(std::slice::from_ref(&dir), &[], &no_ignore(None));
+        assert_eq!(
+            names(walked.unwrap()),
+            [
+                ".hidden/x.rs",
+                "src/lib.rs",
+                "target/debug/build.rs",
+                "vendor/dep.rs"
+            ]
+        );
+
+        // Explicit globs and files are taken as asked for
+        let pattern = dir.join("target/**/*.rs");
+        let globbed = collect_rust_files_with_options(
+            &[pattern, dir.join("vendor/dep.rs")],
+            &[],
+            &CollectOptions::default(),
+        );
+        assert_eq!(
+            names(globbed.unwrap()),
+            ["target/debug/build.rs", "vendor/dep.rs"]
+        );
+
+        // --max-depth: 1 is only the directory's own files; globs aren't limited
+        std::fs::write(dir.join("build.rs"), "fn main() {}\n").unwrap();
+        let walk = |depth| {
+            collect_rust_files_with_options(std::slice::from_ref(&dir), &[], &no_ignore(depth))
+        };
+        assert_eq!(names(walk(Some(1)).unwrap()), ["build.rs"]);
+        assert_eq!(
+            names(walk(Some(2)).unwrap()),
+            [".hidden/x.rs", "build.rs", "src/lib.rs", "vendor/dep.rs"]
+        );
+        let globbed = collect_rust_files_with_options(
+            &[dir.join("target/**/*.rs")],
+            &[],
+            &no_ignore(Some(1)),
+        );
+        assert_eq!(names(globbed.unwrap()), ["target/debug/build.rs"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_find_crate_src() {
+        use crate::files::find_crate_src;
+
+        let dir = std::env::temp_dir().join(format!(
+            "rs-hack-crate-src-{}",
+            crate::state::generate_run_id()
+        ));
+        let nested = dir.join("src").join("commands");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir_all(dir.join("docs")).unwrap();
+        std::fs::write(dir.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+
+        // From the crate root, from inside src/, and from a sibling directory
+        assert_eq!(find_crate_src(&dir), Some(dir.join("src")));
+        assert_eq!(find_crate_src(&nested), Some(dir.join("src")));
+        assert_eq!(find_crate_src(&dir.join("docs")), Some(dir.join("src")));
+
+        // A manifest without src/ (e.g. a virtual workspace) gives nothing to default to
+        std::fs::remove_dir_all(dir.join("src")).unwrap();
+        assert_eq!(find_crate_src(&dir.join("docs")), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rs_hackignore_rules() {
+        use crate::files::{CollectOptions, collect_rust_files_with_options};
+
+        let dir = std::env::temp_dir().join(format!(
+            "rs-hack-ignore-{}",
+            crate::state::generate_run_id()
+        ));
+        for sub in ["src/generated", "src/proto", "vendor/src"] {
+            std::fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        for file in [
+            "src/lib.rs",
+            "src/generated/api.rs",
+            "src/proto/msg_pb.rs",
+            "src/proto/keep_pb.rs",
+            "vendor/src/lib.rs",
+        ] {
+            std::fs::write(dir.join(file), "fn f() {}\n").unwrap();
+        }
+        std::fs::write(
+            dir.join(".rs-hackignore"),
+            "# generated code\nsrc/generated/\n*_pb.rs\n!keep_pb.rs\n/vendor\n!vendor/src/lib.rs\n",
+        )
+        .unwrap();
+
+        let walk = |path: &str, use_ignore_files| {
+            let options = CollectOptions {
+                use_ignore_files,
+                ..CollectOptions::default()
+            };
+            let mut names: Vec<String> =
+                collect_rust_files_with_options(&[dir.join(path)], &[], &options)
+                    .unwrap()
+                    .iter()
+                    .map(|f| f.strip_prefix(&dir).unwrap().display().to_string())
+                    .collect();
+            names.sort();
+            names
+        };
+
+        // An ignored directory's files can't be re-included, and --no-ignore keeps the rules
+        let expected = ["src/lib.rs", "src/proto/keep_pb.rs"];
+        assert_eq!(walk("", true), expected);
+        assert_eq!(walk("", false), expected);
+        // Found above the walked directory too
+        assert_eq!(walk("src/proto", true), ["src/proto/keep_pb.rs"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rename_method_with_receiver_type() {
+        let code = r#"
+struct Parser;
+impl Parser {
+    fn new() -> Self { Parser }
+    fn process(&self) {}
+    fn run(&self) { self.process(); }
+}
+struct Other;
+impl Other {
+    fn process(&self) {}
+}
+fn process() {}
+fn main(o: &Other, given: &Parser) {
+    let typed: Parser = Parser::new();
+    typed.process();
+    let built = Parser::new();
+    built.process();
+    given.process();
+    Parser::new().process();
+    Parser::process(&built);
+    o.process();
+    let given = o;
+    given.process();
+    let unknown = make();
+    unknown.process();
+    process();
+}
+"#;
+        let mut editor = RustEditor::new(code).unwrap();
+        let result = editor
+            .rename_function(&RenameFunctionOp {
+                old_name: "process".to_string(),
+                new_name: "handle".to_string(),
+                function_path: None,
+                edit_mode: EditMode::Surgical,
+                receiver_type: Some("Parser".to_string()),
+                keep_link_name: false,
+            })
+            .unwrap();
+        assert!(result.changed);
+
+        let output = editor.to_string();
+        assert!(output.contains("    fn handle(&self) {}\n    fn run(&self) { self.handle(); }"));
+        assert!(output.contains("typed.handle();"));
+        assert!(output.contains("built.handle();"));
+        assert!(output.contains("Parser::new().handle();"));
+        assert!(output.contains("Parser::handle(&built);"));
+        // Other types, shadowed or uninferable receivers, and the free fn keep their names
+        assert!(output.contains("impl Other {\n    fn process(&self) {}"));
+        assert!(output.contains("o.process();"));
+        assert!(output.contains("let given = o;\n    given.process();"));
+        assert!(output.contains("unknown.process();"));
+        assert!(output.contains("fn process() {}"));
+        assert!(output.contains("    process();\n}"));
+        assert_eq!(output.matches("handle").count(), 7);
+    }
+
+    #[test]
+    fn test_rename_enum_variant_glob_imported() {
+        let code = r#"
+pub enum Status {
+    Draft { id: u32 },
+    Published,
+}
+use Status::*;
+fn make() -> Status {
+    Draft { id: 1 }
+}
+fn check(s: &Status) -> bool {
+    assert_matches!(s, Draft { .. }, "not a draft");
+    matches!(s, Draft { .. } | Published)
+}
+fn name(s: Status) -> &'static str {
+    match s {
+        Draft { .. } => "draft",
+        Status::Published => "published",
+    }
+}
+"#;
+        let op = |edit_mode| RenameEnumVariantOp {
+            enum_name: "Status".to_string(),
+            old_variant: "Draft".to_string(),
+            new_variant: "Pending".to_string(),
+            enum_path: None,
+            edit_mode,
+        };
+
+        let mut editor = RustEditor::new(code).unwrap();
+        assert!(
+            editor
+                .rename_enum_variant(&op(EditMode::Surgical))
+                .unwrap()
+                .changed
+        );
+        let output = editor.to_string();
+        assert!(!output.contains("Draft"), "{}", output);
+        assert!(output.contains("    Pending { id: 1 }\n"));
+        assert!(output.contains("assert_matches!(s, Pending { .. }, \"not a draft\");"));
+        assert!(output.contains("matches!(s, Pending { .. } | Published)"));
+        assert!(output.contains("        Pending { .. } => \"draft\","));
+
+        // The reformat visitor rewrites the same places, macro arguments included
+        let mut editor = RustEditor::new(code).unwrap();
+        assert!(
+            editor
+                .rename_enum_variant(&op(EditMode::Reformat))
+                .unwrap()
+                .changed
+        );
+        let output = editor.to_string();
+        assert!(!output.contains("Draft"), "{}", output);
+        assert_eq!(output.matches("Pending").count(), 5);
+    }
+
+    #[test]
+    fn test_rename_enum_variant_imported_by_name() {
+        let code = r#"
+use crate::model::Status::{self, Draft, Published as Live};
+use crate::model::Other::*;
+fn f(s: Status) -> bool {
+    let fresh = Draft;
+    let Draft = s else { return false };
+    matches!(s, Draft | Live) && fresh == Draft
+}
+"#;
+        let op = |enum_path: Option<&str>| RenameEnumVariantOp {
+            enum_name: "Status".to_string(),
+            old_variant: "Draft".to_string(),
+            new_variant: "Pending".to_string(),
+            enum_path: enum_path.map(str::to_string),
+            edit_mode: EditMode::Surgical,
+        };
+
+        for enum_path in [None, Some("crate::model::Status")] {
+            let mut editor = RustEditor::new(code).unwrap();
+            assert!(editor.rename_enum_variant(&op(enum_path)).unwrap().changed);
+            let output = editor.to_string();
+            assert!(!output.contains("Draft"), "{}", output);
+            assert!(
+                output.contains("use crate::model::Status::{self, Pending, Published as Live};")
+            );
+            assert!(output.contains("matches!(s, Pending | Live) && fresh == Pending"));
+        }
+
+        // Another enum's glob import doesn't bring a bare `Draft` into scope
+        let code = "use crate::model::Other::*;\nfn f() { let _ = Draft; }\n";
+        let mut editor = RustEditor::new(code).unwrap();
+        let result = editor
+            .rename_enum_variant(&op(Some("crate::model::Status")))
+            .unwrap();
+        assert!(!result.changed);
+        let mut editor = RustEditor::new(code).unwrap();
+        assert!(!editor.rename_enum_variant(&op(None)).unwrap().changed);
+    }
+
+    #[test]
+    fn test_flag_risky_scoring() {
+        use crate::commands::risk::{RISK_THRESHOLD, macro_mention_lines, score_match};
+
+        let (score, reasons) = score_match("function-call", "process", "process()");
+        assert!(score >= RISK_THRESHOLD);
+        assert_eq!(reasons, ["common name `process`", "unqualified path"]);
+
+        // A distinctive, qualified call is not flagged
+        let (score, reasons) = score_match("function-call", "process_v2", "util::process_v2()");
+        assert_eq!(score, 0);
+        assert!(reasons.is_empty());
+
+        let (score, _) = score_match("method-call", "len", "v.len()");
+        assert!(score >= RISK_THRESHOLD);
+        let (score, _) = score_match("method-call", "recompute_layout", "v.recompute_layout()");
+        assert!(score < RISK_THRESHOLD);
+
+        let code = "fn main() {\n    process();\n    log!(process, 1);\n    println!(\"{}\", processed);\n}\n";
+        assert_eq!(macro_mention_lines(code, "process"), [3]);
+    }
+
+    #[test]
+    fn test_find_sarif_output() {
+        let code = "fn main() {\n    let v = load().unwrap();\n    other.unwrap();\n}\n";
+        let editor = RustEditor::new(code).unwrap();
+        let mut results = editor
+            .inspect(Some("method-call"), Some("unwrap"), None, false)
+            .unwrap();
+        for result in &mut results {
+            result.file_path = "./src/main.rs".to_string();
+        }
+
+        let log = crate::sarif::from_find_results(&results, Some("unwrap"));
+        let json = serde_json::to_value(&log).unwrap();
+        assert_eq!(json["version"], "2.1.0");
+        let run = &json["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "rs-hack");
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            run["tool"]["driver"]["rules"][0]["id"],
+            "method-call/unwrap"
+        );
+
+        let sarif_results = run["results"].as_array().unwrap();
+        assert_eq!(sarif_results.len(), 2);
+        assert_eq!(sarif_results[0]["ruleId"], "method-call/unwrap");
+        assert_eq!(
+            sarif_results[0]["message"]["text"],
+            "method-call `unwrap`: load().unwrap()"
+        );
+        let location = &sarif_results[0]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "src/main.rs");
+        // SARIF columns are 1-based
+        assert_eq!(location["region"]["startLine"], 2);
+        assert_eq!(location["region"]["startColumn"], 13);
+    }
+
+    #[test]
+    fn test_cfg_evaluation_and_filtering() {
+        use crate::cfg::{CfgSet, Scope, gated_items_touched, gated_lines};
+
+        let cfg = CfgSet::parse(["unix", "feature=\"serde\"", "target_os=linux"]).unwrap();
+        let eval = |predicate: &str| cfg.evaluate(&syn::parse_str(predicate).unwrap());
+        assert_eq!(eval("unix"), Some(true));
+        assert_eq!(eval("windows"), Some(false));
+        assert_eq!(eval("feature = \"serde\""), Some(true));
+        assert_eq!(
+            eval("all(unix, not(test), target_os = \"linux\")"),
+            Some(true)
+        );
+        assert_eq!(eval("any(windows, feature = \"json\")"), Some(false));
+        assert_eq!(eval("not(unix, windows)"), None);
+        assert!(CfgSet::parse(["=x"]).is_err());
+
+        let old = "struct A;\n\n#[cfg(unix)]\nfn a() {\n    old();\n}\n\n#[cfg(windows)]\nfn b() {\n    old();\n}\n\nfn c() {\n    old();\n}\n";
+        let new = old.replace("old()", "new()");
+        let file = syn::parse_file(old).unwrap();
+        assert_eq!(gated_lines(&file, Some(&cfg)), vec![7..11]);
+        assert_eq!(gated_lines(&file, None), vec![2..6, 7..11]);
+
+        // Only the compiled-out fn is pruned
+        let mut pruned = file;
+        assert!(Scope::default().prune(&mut pruned).is_empty());
+        let scope = Scope::default().with_cfg(Some(cfg));
+        assert_eq!(scope.prune(&mut pruned), vec![7..11]);
+        assert_eq!(pruned.items.len(), 3);
+
+        // Without --cfg both gated fns the edit touches are counted
+        assert_eq!(gated_items_touched(old, &new), 2);
+
+        // Of two same-named definitions, the one compiled in is edited
+        let source = "#[cfg(windows)]\nstruct Config {\n    a: u32,\n}\n\n#[cfg(unix)]\nstruct Config {\n    a: u32,\n    #[cfg(windows)]\n    handle: u64,\n}\n";
+        let cfg = CfgSet::parse(["unix"]).unwrap();
+        let mut editor =
+            RustEditor::with_scope(source, Scope::default().with_cfg(Some(cfg))).unwrap();
+        let result = editor
+            .add_struct_field(&AddStructFieldOp {
+                struct_name: "Config".to_string(),
+                field_def: "b: u16".to_string(),
+                position: InsertPosition::Last,
+                literal_default: None,
+                where_filter: None,
+                in_fn: None,
+            })
+            .unwrap();
+        assert!(result.changed);
+        assert_eq!(
+            editor.to_string(),
+            "#[cfg(windows)]\nstruct Config {\n    a: u32,\n}\n\n#[cfg(unix)]\nstruct Config {\n    a: u32,\n    b: u16,\n    #[cfg(windows)]\n    handle: u64,\n}\n"
+        );
+        // The backup has the field the scope leaves out
+        assert!(
+            result.modified_nodes[0]
+                .original_content
+                .contains("handle: u64")
+        );
+    }
+
+    #[test]
+    fn test_skip_test_code() {
+        use crate::cfg::{Scope, TEST_ATTRS};
+        use crate::execute::{ExecuteOpts, execute};
+        use crate::operations::{Operation, RenameFunctionOp};
+
+        let old = "fn a() {\n    old();\n}\n\n#[test]\nfn b() {\n    old();\n}\n\n#[cfg(test)]\nmod tests {\n    fn c() {\n        old();\n    }\n}\n\n#[tokio::test(flavor = \"multi_thread\")]\nasync fn d() {\n    old();\n}\n";
+        let tests = Scope::default().with_skip_attrs(TEST_ATTRS.iter().copied());
+        let mut file = syn::parse_file(old).unwrap();
+        assert_eq!(tests.prune(&mut file.clone()), vec![4..8, 9..15]);
+        // A bare path matches the attribute with arguments too
+        let tokio = Scope::default().with_skip_attrs(["#[tokio::test]"]);
+        assert_eq!(tokio.prune(&mut file), vec![16..20]);
+
+        // Only the calls outside test code are renamed; the two left alone are counted
+        let rename = Operation::RenameFunction(RenameFunctionOp {
+            old_name: "old".to_string(),
+            new_name: "new".to_string(),
+            function_path: None,
+            edit_mode: Default::default(),
+            receiver_type: None,
+            keep_link_name: false,
+        });
+        let dir = std::env::temp_dir().join(format!("rs_hack_skip_tests_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("lib.rs");
+        std::fs::write(&path, old).unwrap();
+        let opts = ExecuteOpts {
+            scope: tests,
+            ..ExecuteOpts::default()
+        };
+        let result = execute(std::slice::from_ref(&path), &rename, &opts).unwrap();
+        assert_eq!(result.skipped_matches, 2);
+        let content = &result.changes[0].new_content;
+        assert!(content.contains("fn a() {\n    new();"));
+        assert!(content.contains("fn b() {\n    old();"));
+        assert!(content.contains("        old();\n    }\n}"));
+        assert!(content.contains("async fn d() {\n    new();"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_inline_module_scope() {
+        use crate::cfg::Scope;
+
+        let old = "mod a {\n    struct Config {\n        port: u16,\n    }\n}\n\nmod b {\n    fn f() {}\n\n    mod c {\n        struct Config {\n            name: String,\n        }\n    }\n}\n";
+        let module = |path: &str| Scope::default().with_in_module(Some(path));
+        // Everything off the path to the module goes
+        let mut file = syn::parse_file(old).unwrap();
+        assert_eq!(module("crate::b::c").prune(&mut file), vec![0..5, 7..8]);
+        let mut file = syn::parse_file(old).unwrap();
+        assert_eq!(module("c").prune(&mut file), vec![0..5, 6..15]);
+        assert!(file.items.is_empty());
+
+        // Only the definition inside the module is edited
+        let op = |name: &str| AddStructFieldOp {
+            struct_name: name.to_string(),
+            field_def: "debug: bool".to_string(),
+            position: InsertPosition::Last,
+            literal_default: None,
+            where_filter: None,
+            in_fn: None,
+        };
+        let mut editor = RustEditor::with_scope(old, module("b::c")).unwrap();
+        assert!(editor.add_struct_field(&op("Config")).unwrap().changed);
+        let content = editor.to_string();
+        assert!(content.contains("        port: u16,\n    }"));
+        assert!(content.contains("            name: String,\n            debug: bool,\n"));
+
+        // Definitions nested in inline modules are found; a name several modules define
+        // needs --in-module
+        let mut editor =
+            RustEditor::new("mod a {\n    struct Solo {\n        x: u8,\n    }\n}\n").unwrap();
+        assert!(editor.add_struct_field(&op("Solo")).unwrap().changed);
+        assert!(
+            editor
+                .to_string()
+                .contains("        debug: bool,\n    }\n}")
+        );
+
+        let mut editor = RustEditor::new(old).unwrap();
+        let err = editor
+            .add_struct_field(&op("Config"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("several modules (a, b::c)"), "{}", err);
+    }
+
+    #[test]
+    fn test_operations_on_nested_module_items() {
+        let code = "mod outer {\n    mod inner {\n        #[derive(Debug)]\n        pub struct Point {\n            pub x: i32,\n        }\n\n        pub enum Shape {\n            Circle,\n        }\n\n        impl Point {\n            pub fn new() -> Self {\n                Point { x: 0 }\n            }\n        }\n    }\n}\n";
+        // Each edit keeps the nested item's indentation
+        let check = |op: Operation, expected: &str| {
+            let mut editor = RustEditor::new(code).unwrap();
+            assert!(editor.apply_operation(&op).unwrap().changed);
+            let content = editor.to_string();
+            assert!(content.contains(expected), "{}", content);
+            syn::parse_file(&content).unwrap();
+        };
+
+        check(
+            Operation::AddStructField(AddStructFieldOp {
+                struct_name: "Point".to_string(),
+                field_def: "y: i32".to_string(),
+                position: InsertPosition::Last,
+                literal_default: None,
+                where_filter: None,
+                in_fn: None,
+            }),
+            "            pub x: i32,\n            y: i32,\n        }",
+        );
+        check(
+            Operation::AddEnumVariant(AddEnumVariantOp {
+                enum_name: "Shape".to_string(),
+                variant_def: "Square".to_string(),
+                position: InsertPosition::Last,
+                where_filter: None,
+                in_fn: None,
+            }),
+            "            Circle,\n            Square,\n        }",
+        );
+        check(
+            Operation::AddDerive(AddDeriveOp {
+                target_name: "Point".to_string(),
+                target_type: "struct".to_string(),
+                derives: vec!["Clone".to_string()],
+                where_filter: None,
+                derive_order: None,
+                edit_mode: EditMode::default(),
+            }),
+            "        #[derive(Debug, Clone)]\n        pub struct Point {",
+        );
+        check(
+            Operation::AddImplMethod(AddImplMethodOp {
+                target: "Point".to_string(),
+                method_def: "pub fn x(&self) -> i32 { self.x }".to_string(),
+                position: InsertPosition::Last,
+                trait_name: None,
+                edit_mode: EditMode::default(),
+            }),
+            "            pub fn x(&self) -> i32 {\n                self.x\n            }\n        }\n    }\n}",
+        );
+
+        let editor = RustEditor::new(code).unwrap();
+        assert_eq!(
+            editor.find_item_path("enum", "Shape").unwrap(),
+            vec![0, 0, 1]
+        );
+    }
+
+    #[test]
+    fn test_github_annotations() {
+        use crate::execute::FileChange;
+        use crate::operations::{BackupNode, NodeLocation};
+
+        let code = "fn main() {\n    let v = load().unwrap();\n}\n";
+        let editor = RustEditor::new(code).unwrap();
+        let mut results = editor
+            .inspect(Some("method-call"), Some("unwrap"), None, false)
+            .unwrap();
+        results[0].file_path = "./src/main.rs".to_string();
+        let annotations = crate::github::from_find_results(&results, Some("unwrap"));
+        assert_eq!(
+            annotations[0].to_string(),
+            "::warning file=src/main.rs,line=2,col=13,endLine=2,endColumn=28,title=rs-hack find%3A method-call/unwrap::method-call `unwrap`: load().unwrap()"
+        );
+
+        // Nodes with a real position are annotated there; placeholder positions fall back to
+        // each changed region
+        let node = |line, column, end_line, end_column| BackupNode {
+            node_type: "ItemStruct".to_string(),
+            identifier: "User".to_string(),
+            original_content: String::new(),
+            location: NodeLocation {
+                line,
+                column,
+                end_line,
+                end_column,
+            },
+        };
+        let change = |modified_nodes| FileChange {
+            path: std::path::PathBuf::from("src/lib.rs"),
+            old_content: "a\nb\nc\nd\n".to_string(),
+            new_content: "a\nB\nc\nD\n".to_string(),
+            modified_nodes,
+        };
+        let placed =
+            crate::github::from_changes(&[change(vec![node(3, 0, 5, 1)])], "AddStructField");
+        assert_eq!(placed.len(), 1);
+        assert_eq!(
+            (placed[0].line, placed[0].col, placed[0].end_column),
+            (3, 1, 2)
+        );
+        assert_eq!(
+            placed[0].message,
+            "AddStructField would change ItemStruct `User`"
+        );
+
+        let regions = crate::github::from_changes(&[change(vec![node(1, 0, 1, 0)])], "Rename");
+        let lines: Vec<usize> = regions.iter().map(|a| a.line).collect();
+        assert_eq!(lines, vec![2, 4]);
+        assert!(
+            regions[0]
+                .to_string()
+                .ends_with("::Rename would change ItemStruct `User`")
+        );
+    }
+
+    #[test]
+    fn test_lsp_text_edits() {
+        use crate::execute::FileChange;
+        use crate::lsp::{Position, text_edits};
+
+        let old = "struct A {\n    x: u32,\n}\n\nlet é = \"café\"; // old\n";
+        let new = "struct A {\n    x: u32,\n    y: u32,\n}\n\nlet é = \"café\"; // new\n";
+        let edits = text_edits(old, new);
+        assert_eq!(edits.len(), 2);
+
+        // A whole-line insertion is an empty range at the start of the next line
+        assert_eq!(
+            edits[0].range.start,
+            Position {
+                line: 2,
+                character: 0
+            }
+        );
+        assert_eq!(edits[0].range.end, edits[0].range.start);
+        assert_eq!(edits[0].new_text, "    y: u32,\n");
+
+        // Characters count UTF-16 units and the edit covers only what differs
+        assert_eq!(
+            edits[1].range.start,
+            Position {
+                line: 4,
+                character: 19
+            }
+        );
+        assert_eq!(
+            edits[1].range.end,
+            Position {
+                line: 4,
+                character: 22
+            }
+        );
+        assert_eq!(edits[1].new_text, "new");
+
+        let json = serde_json::to_value(&edits[1]).unwrap();
+        assert_eq!(json["newText"], "new");
+        assert_eq!(json["range"]["end"]["character"], 22);
+
+        let files = crate::lsp::from_changes(&[FileChange {
+            path: std::path::PathBuf::from("src/my lib.rs"),
+            old_content: old.to_string(),
+            new_content: new.to_string(),
+            modified_nodes: Vec::new(),
+        }]);
+        assert!(files[0].uri.starts_with("file:///"));
+        assert!(files[0].uri.ends_with("/src/my%20lib.rs"));
+        assert_eq!(files[0].edits, edits);
+    }
+
+    #[test]
+    fn test_complexity_score() {
+        use crate::commands::complexity::score_block;
+
+        let score = |body: &str| score_block(&syn::parse_str::<syn::Block>(body).unwrap());
+
+        assert_eq!(score("{ run(); }"), 1);
+        // if + else if, && and ||
+        assert_eq!(
+            score("{ if a && b { x() } else if c || d { y() } else { z() } }"),
+            5
+        );
+        // 3 arms (+2), one guard (+1), for loop (+1)
+        assert_eq!(
+            score("{ for i in v { match i { 0 => {} n if n > 9 => {} _ => {} } } }"),
+            5
+        );
+        // Closures count toward the enclosing function; while and loop each add one
+        assert_eq!(
+            score("{ let f = |x| if x { 1 } else { 2 }; while go() {} loop { break; } }"),
+            4
+        );
+        // Nested fn items are scored separately
+        assert_eq!(score("{ fn inner() { if a {} } inner(); }"), 1);
+    }
+
+    #[test]
+    fn test_transform_exclude_name_and_in() {
+        let code = r#"
+fn main() {
+    let a = load().unwrap();
+    let b = parse().expect("parse");
+}
+
+mod tests {
+    fn test_load() {
+        let a = load().unwrap();
+    }
+}
+
+fn test_helper() {
+    let c = other().unwrap();
+}
+"#;
+        let op = |exclude_names: Vec<&str>, exclude_in: Vec<&str>| TransformOp {
+            node_type: "method-call".to_string(),
+            name_filter: None,
+            content_filter: None,
+            action: TransformAction::Comment,
+            exclude_names: exclude_names.into_iter().map(String::from).collect(),
+            exclude_in: exclude_in.into_iter().map(String::from).collect(),
+            name_regex: None,
+        };
+
+        // --exclude-in skips everything inside `mod tests` and any `test_*` function
+        let mut editor = RustEditor::new(code).unwrap();
+        let result = editor
+            .transform(&op(vec![], vec!["tests", "test_*"]))
+            .unwrap();
+        assert_eq!(result.modified_nodes.len(), 2);
+        let output = editor.to_string();
+        assert!(output.contains("// load().unwrap()"));
+        assert!(output.contains("// parse().expect(\"parse\")"));
+        assert!(output.contains("        let a = load().unwrap();"));
+        assert!(output.contains("    let c = other().unwrap();"));
+
+        // --exclude-name skips nodes by their own name
+        let mut editor = RustEditor::new(code).unwrap();
+        let result = editor.transform(&op(vec!["expect"], vec![])).unwrap();
+        assert_eq!(result.modified_nodes.len(), 3);
+        assert!(editor.to_string().contains("parse().expect(\"parse\");"));
+    }
+
+    #[test]
+    fn test_revert_report_skips_changed_files() {
+        use crate::state::*;
+
+        let dir = std::env::temp_dir().join(format!("rs-hack-revert-{}", generate_run_id()));
+        let state_dir = dir.join("state");
+        std::fs::create_dir_all(&state_dir).unwrap();
+        let file = dir.join("lib.rs");
+        std::fs::write(&file, "struct User;\n").unwrap();
+
+        let run = RunMetadata {
+            run_id: "abc1234".to_string(),
+            timestamp: chrono::Utc::now(),
+            command: "add".to_string(),
+            operation: "AddStructField".to_string(),
+            files_modified: vec![FileModification {
+                path: file.clone(),
+                hash_before: String::new(),
+                hash_after: "not-the-current-hash".to_string(),
+                backup_nodes: Vec::new(),
+            }],
+            status: RunStatus::Applied,
+            can_revert: true,
+        };
+        save_run_metadata(&run, &state_dir).unwrap();
+
+        let report = revert_run_report("abc1234", false, &state_dir).unwrap();
+        assert_eq!(report.status, "aborted");
+        assert!(report.reverted.is_empty());
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].reason, "hash-mismatch");
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["skipped"][0]["expected_hash"], "not-the-current-hash");
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "struct User;\n");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_move_item_between_modules() {
+        use crate::commands::move_item::{MoveArgs, run};
+
+        let dir =
+            std::env::temp_dir().join(format!("rs-hack-move-{}", crate::state::generate_run_id()));
+        let src = dir.join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(dir.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+        std::fs::write(
+            src.join("lib.rs"),
+            "pub mod old;\npub mod new;\n\nuse crate::old::{Config, helper};\n\npub fn main_fn() -> Config {\n    helper();\n    crate::old::Config::default()\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            src.join("old.rs"),
+            "use std::collections::HashMap;\n\npub fn helper() {}\n\n#[derive(Default)]\npub struct Config {\n    pub map: HashMap<String, u8>,\n}\n\nimpl Config {\n    pub fn new() -> Self {\n        helper();\n        Self::default()\n    }\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            src.join("new.rs"),
+            "use crate::old::Config;\n\npub fn uses_config(_c: &Config) {}\n",
+        )
+        .unwrap();
+
+        let files = ["lib.rs", "old.rs", "new.rs"].map(|f| src.join(f)).to_vec();
+        let result = run(&MoveArgs {
+            name: "Config".to_string(),
+            from: src.join("old.rs"),
+            to: src.join("new.rs"),
+            files,
+        })
+        .unwrap();
+        assert_eq!(result.from_module, "crate::old");
+        assert_eq!(result.to_module, "crate::new");
+        assert_eq!(result.moved, vec!["struct Config", "impl Config"]);
+
+        let content = |name: &str| {
+            result
+                .changes
+                .iter()
+                .find(|c| c.path.ends_with(name))
+                .map(|c| c.new_content.clone())
+                .unwrap()
+        };
+        // The struct, its impl and the import only they used are gone, with no blank lines
+        // stacked where they were
+        assert_eq!(content("old.rs"), "pub fn helper() {}\n");
+
+        let new = content("new.rs");
+        assert!(!new.contains("use crate::old::Config;"));
+        assert!(new.contains("use std::collections::HashMap;"));
+        assert!(new.contains("use crate::old::helper;"));
+        assert!(new.contains("pub struct Config {"));
+        assert!(new.contains("impl Config {"));
+
+        assert_eq!(
+            content("lib.rs"),
+            "pub mod old;\npub mod new;\n\nuse crate::old::helper;\nuse crate::new::Config;\n\npub fn main_fn() -> Config {\n    helper();\n    crate::new::Config::default()\n}\n"
+        );
+
+        // Nothing is written until the caller does
+        assert!(
+            std::fs::read_to_string(src.join("old.rs"))
+                .unwrap()
+                .contains("struct Config")
+        );
+
+        // Cut from between two items, one blank line stays
+        std::fs::write(
+            src.join("old.rs"),
+            "pub fn a() {}\n\npub fn b() {}\n\npub fn c() {}\n",
+        )
+        .unwrap();
+        let result = run(&MoveArgs {
+            name: "b".to_string(),
+            from: src.join("old.rs"),
+            to: src.join("new.rs"),
+            files: Vec::new(),
+        })
+        .unwrap();
+        assert_eq!(
+            result.changes[0].new_content,
+            "pub fn a() {}\n\npub fn c() {}\n"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rename_module_with_paths() {
+        use crate::commands::rename_mod::{RenameModArgs, run};
+
+        let dir = std::env::temp_dir().join(format!(
+            "rs-hack-rename-mod-{}",
+            crate::state::generate_run_id()
+        ));
+        let src = dir.join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(dir.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+        std::fs::write(
+            src.join("lib.rs"),
+            "pub mod util;\nmod shapes {\n    pub mod util {}\n}\n\nuse crate::util::{self, Helper};\n\npub fn run() {\n    let util = 1;\n    util::helper(util);\n}\n\n#[cfg(test)]\nmod tests {\n    use super::*;\n    fn t() { util::helper(0); }\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            src.join("util.rs"),
+            "pub struct Helper;\npub fn helper(_: u8) { crate::util::other(); }\nfn other() {}\n",
+        )
+        .unwrap();
+
+        let files = ["lib.rs", "util.rs"].map(|f| src.join(f)).to_vec();
+        let args = |name: &str| RenameModArgs {
+            name: name.to_string(),
+            to: "helpers".to_string(),
+            files: files.clone(),
+        };
+        let err = run(&args("util")).unwrap_err().to_string();
+        assert!(err.contains("crate::shapes::util"), "{}", err);
+
+        let result = run(&args("crate::util")).unwrap();
+        assert_eq!(result.module, "crate::util");
+        assert_eq!(
+            result.renames,
+            vec![(src.join("util.rs"), src.join("helpers.rs"))]
+        );
+        let content = |name: &str| {
+            result
+                .changes
+                .iter()
+                .find(|c| c.path.ends_with(name))
+                .map(|c| c.new_content.clone())
+                .unwrap()
+        };
+        let lib = content("lib.rs");
+        assert!(lib.contains("pub mod helpers;"));
+        assert!(lib.contains("pub mod util {}"));
+        assert!(lib.contains("use crate::helpers::{self, Helper};"));
+        assert!(lib.contains("let util = 1;\n    helpers::helper(util);"));
+        assert!(lib.contains("fn t() { helpers::helper(0); }"));
+        assert!(content("util.rs").contains("crate::helpers::other();"));
+
+        // Nothing is written or renamed until the caller does
+        assert!(src.join("util.rs").is_file());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rename_text_in_strings_and_comments() {
+        use crate::commands::rename_text::{RenameTextArgs, run};
+
+        let dir = std::env::temp_dir().join(format!(
+            "rs-hack-rename-text-{}",
+            crate::state::generate_run_id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let lib = dir.join("lib.rs");
+        std::fs::write(
+            &lib,
+            "/// Wraps `load`; see load_all.\npub fn load() {\n    // load is cheap\n    log(\"load started\", r#\"load\"#);\n    let load = 'l';\n    /* preload */\n}\n",
+        )
+        .unwrap();
+        let rename = |strings: bool, comments: bool| {
+            run(&RenameTextArgs {
+                name: "load".to_string(),
+                to: "fetch".to_string(),
+                files: vec![lib.clone()],
+                strings,
+                comments,
+            })
+            .unwrap()
+        };
+
+        let result = rename(true, true);
+        assert_eq!((result.in_strings, result.in_comments), (2, 2));
+        assert_eq!(
+            result.changes[0].new_content,
+            "/// Wraps `fetch`; see load_all.\npub fn load() {\n    // fetch is cheap\n    log(\"fetch started\", r#\"fetch\"#);\n    let load = 'l';\n    /* preload */\n}\n"
+        );
+
+        // Comments only: the strings and all code are left as they were
+        let result = rename(false, true);
+        assert_eq!((result.in_strings, result.in_comments), (0, 2));
+        assert!(
+            result.changes[0]
+                .new_content
+                .contains("log(\"load started\", r#\"load\"#);")
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_inline_function_single_caller() {
+        use crate::commands::inline_fn::{InlineArgs, run};
+
+        let dir = std::env::temp_dir().join(format!(
+            "rs-hack-inline-{}",
+            crate::state::generate_run_id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let lib = dir.join("lib.rs");
+        std::fs::write(
+            &lib,
+            "fn add(a: u32, b: u32) -> u32 {\n    a * 2 + b\n}\n\nfn greet(name: &str, times: usize) {\n    let line = format!(\"hi {name}\");\n    for _ in 0..times {\n        println!(\"{}\", line);\n    }\n}\n\nfn twice(v: u32) -> u32 {\n    v + v\n}\n\nfn early(x: u32) -> u32 {\n    if x > 1 {\n        return 0;\n    }\n    x\n}\n\npub fn main_fn(base: u32) -> u32 {\n    let total = add(base, compute());\n    greet(\"bob\", total as usize);\n    early(twice(1)) + twice(2)\n}\n\nfn compute() -> u32 {\n    4\n}\n",
+        )
+        .unwrap();
+        let inline = |name: &str| {
+            run(&InlineArgs {
+                name: name.to_string(),
+                files: vec![lib.clone()],
+            })
+        };
+
+        // Simple arguments and a single unconditional use are substituted in place
+        let result = inline("add").unwrap();
+        assert_eq!(result.definition, format!("{}:1", lib.display()));
+        let content = &result.changes[0].new_content;
+        assert!(content.contains("    let total = base * 2 + compute();\n"));
+        assert!(content.starts_with("fn greet("));
+
+        // Parameters read by a format string or inside a loop are bound with `let`
+        let content = &inline("greet").unwrap().changes[0].new_content;
+        assert!(content.contains(
+            "    {\n        let name: &str = \"bob\";\n        let times: usize = total as usize;\n        let line = format!(\"hi {name}\");\n        for _ in 0..times {\n            println!(\"{}\", line);\n        }\n    };\n"
+        ));
+        assert!(!content.contains("fn greet"));
+
+        // Removing the last item doesn't leave a trailing blank line
+        let content = &inline("compute").unwrap().changes[0].new_content;
+        assert!(content.contains("add(base, 4)"));
+        assert!(content.ends_with("twice(2)\n}\n"));
+
+        let err = inline("twice").unwrap_err().to_string();
+        assert!(err.contains("has 2 call sites"), "{}", err);
+        let err = inline("early").unwrap_err().to_string();
+        assert!(err.contains("early `return`"), "{}", err);
+        let err = inline("missing").unwrap_err().to_string();
+        assert!(err.contains("No function `missing`"), "{}", err);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_plan_batch_chains_operations_and_reports_conflicts() {
+        use crate::execute::{ExecuteOpts, plan_batch};
+        use crate::operations::BatchSpec;
+
+        let dir =
+            std::env::temp_dir().join(format!("rs-hack-batch-{}", crate::state::generate_run_id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let lib = dir.join("lib.rs");
+        let original = "#[derive(Debug)]\npub struct User {\n    pub name: String,\n}\n\npub enum Status {\n    Draft,\n}\n";
+        std::fs::write(&lib, original).unwrap();
+
+        let batch: BatchSpec = serde_yaml::from_str(
+            r#"
+base_path: src/
+operations:
+  - type: AddDerive
+    target_name: User
+    target_type: struct
+    derives: [Clone]
+  - type: AddStructField
+    struct_name: User
+    field_def: "age: u32"
+    position: Last
+  - type: AddEnumVariant
+    enum_name: Status
+    variant_def: Archived
+    position: Last
+"#,
+        )
+        .unwrap();
+
+        let plan = plan_batch(
+            std::slice::from_ref(&lib),
+            &batch.operations,
+            &ExecuteOpts::default(),
+        )
+        .unwrap();
+        assert_eq!(plan.operations.len(), 3);
+        assert!(plan.operations.iter().all(|r| r.total_modifications == 1));
+
+        // Both struct edits survive: the second operation saw the first one's output
+        assert_eq!(plan.changes.len(), 1);
+        let content = &plan.changes[0].new_content;
+        assert!(content.contains("#[derive(Debug, Clone)]"));
+        assert!(content.contains("age: u32"));
+        assert!(content.contains("Archived"));
+        assert_eq!(plan.changes[0].old_content, original);
+
+        assert_eq!(plan.conflicts.len(), 1);
+        assert_eq!(plan.conflicts[0].identifier, "User");
+        assert_eq!(plan.conflicts[0].operations, vec![0, 1]);
+
+        // Planning never writes
+        assert_eq!(std::fs::read_to_string(&lib).unwrap(), original);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_batch_chained_operations_see_earlier_renames() {
+        use crate::execute::{ExecuteOpts, plan_batch, write_changes};
+        use crate::operations::BatchSpec;
+
+        let dir = std::env::temp_dir().join(format!(
+            "rs-hack-batch-chain-{}",
+            crate::state::generate_run_id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let model = dir.join("model.rs");
+        let view = dir.join("view.rs");
+        std::fs::write(
+            &model,
+            "pub enum Status {\n    Draft,\n    Published,\n}\n\npub fn process_v2(s: &Status) -> u8 {\n    0\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            &view,
+            "pub fn label(s: &Status) -> u8 {\n    match s {\n        Status::Draft => process_v2(s),\n        Status::Published => 1,\n    }\n}\n",
+        )
+        .unwrap();
+
+        // Operations 3 and 5 only find their targets under the names 1, 2 and 4 gave them
+        let batch: BatchSpec = serde_yaml::from_str(
+            r#"
+base_path: src/
+operations:
+  - type: RenameFunction
+    old_name: process_v2
+    new_name: process
+  - type: RenameEnumVariant
+    enum_name: Status
+    old_variant: Draft
+    new_variant: Pending
+  - type: UpdateMatchArm
+    pattern: Status::Pending
+    new_body: "process(s) + 1"
+    function_name: label
+  - type: AddEnumVariant
+    enum_name: Status
+    variant_def: Archived
+    position: Last
+  - type: AddMatchArm
+    pattern: Status::Archived
+    body: "process(s)"
+    function_name: label
+"#,
+        )
+        .unwrap();
+
+        let files = vec![model.clone(), view.clone()];
+        let plan = plan_batch(&files, &batch.operations, &ExecuteOpts::default()).unwrap();
+        assert!(
+            plan.operations.iter().all(|r| r.total_modifications > 0),
+            "every operation should find its target"
+        );
+        write_changes(plan.changes, "Batch", None, String::new(), None).unwrap();
+
+        let model = std::fs::read_to_string(&model).unwrap();
+        let view = std::fs::read_to_string(&view).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(model.contains("    Pending,\n    Published,\n    Archived,\n"));
+        assert!(model.contains("pub fn process(s: &Status)"));
+        assert!(view.contains("Status::Pending => process(s) + 1,"));
+        assert!(view.contains("Status::Archived => process(s),"));
+    }
+
+    #[test]
+    fn test_plan_batch_when_conditions() {
+        use crate::execute::{ExecuteOpts, plan_batch};
+        use crate::operations::BatchSpec;
+
+        let dir = std::env::temp_dir().join(format!(
+            "rs-hack-batch-when-{}",
+            crate::state::generate_run_id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let lib = dir.join("lib.rs");
+        std::fs::write(&lib, "pub struct User {\n    pub name: String,\n}\n").unwrap();
+
+        let batch: BatchSpec = serde_yaml::from_str(
+            r#"
+base_path: src/
+operations:
+  - type: AddStructField
+    struct_name: User
+    field_def: "age: u32"
+    position: Last
+    when: { node_type: struct, name: User }
+  - type: AddStructField
+    struct_name: Config
+    field_def: "timeout: u64"
+    position: Last
+    when: { node_type: struct, name: Config, exists: true }
+  - type: AddEnumVariant
+    enum_name: Status
+    variant_def: Archived
+    position: Last
+    when: { node_type: struct, name: User, exists: false }
+  - type: AddDerive
+    target_name: User
+    target_type: struct
+    derives: [Clone]
+"#,
+        )
+        .unwrap();
+        assert!(batch.operations[3].when.is_none());
+
+        let plan = plan_batch(
+            std::slice::from_ref(&lib),
+            &batch.operations,
+            &ExecuteOpts::default(),
+        )
+        .unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let skipped: Vec<(usize, &str)> = plan
+            .skipped
+            .iter()
+            .map(|s| (s.index, s.reason.as_str()))
+            .collect();
+        assert_eq!(
+            skipped,
+            vec![
+                (1, "no struct `Config` found"),
+                (2, "struct `User` already exists"),
+            ]
+        );
+        // Results stay aligned with the operations
+        assert_eq!(plan.operations.len(), 4);
+        assert_eq!(plan.operations[0].total_modifications, 1);
+        assert_eq!(plan.operations[3].total_modifications, 1);
+        let content = &plan.changes[0].new_content;
+        assert!(content.contains("age: u32") && content.contains("derive(Clone)"));
+        assert!(!content.contains("timeout") && !content.contains("Archived"));
+    }
+
+    #[test]
+    fn test_find_highlight_name() {
+        use crate::commands::find::{highlight_name, highlight_target};
+
+        let snippet = "let user = User::new(); // Users\nfn make_User() -> User {}";
+        assert_eq!(
+            highlight_name(snippet, "User"),
+            "let user = \x1b[1;33mUser\x1b[0m::new(); // Users\nfn make_User() -> \x1b[1;33mUser\x1b[0m {}"
+        );
+        assert_eq!(highlight_name(snippet, "Nope"), snippet);
+
+        assert_eq!(
+            highlight_target("User", Some("crate::models::User")),
+            "User"
+        );
+        assert_eq!(highlight_target("Display for User", Some("*User")), "User");
+        assert_eq!(highlight_target("Status::Draft", None), "Draft");
+    }
+
+    #[test]
+    fn test_inspect_lifetimes_and_generic_params() {
+        let code = r#"
+struct Wrap<'a, T, const N: usize> {
+    items: &'a [T; N],
+}
+
+impl<'a, T: Clone, const N: usize> Wrap<'a, T, N> {
+    fn first<U: From<T>>(&self) -> U {
+        'outer: loop {
+            break 'outer;
+        }
+        fn inner<T>(t: T) -> T {
+            t
+        }
+        U::from(self.items[0].clone())
+    }
+}
+"#;
+        let editor = RustEditor::new(code).unwrap();
+        let lines = |node_type: &str, name: &str| -> Vec<usize> {
+            editor
+                .inspect(Some(node_type), Some(name), None, false)
+                .unwrap()
+                .iter()
+                .map(|r| r.location.line)
+                .collect()
+        };
+
+        // Declarations and uses; the loop label isn't a lifetime
+        assert_eq!(lines("lifetime", "'a"), vec![2, 3, 6, 6]);
+        assert_eq!(lines("lifetime", "a"), lines("lifetime", "'a"));
+        assert!(lines("lifetime", "outer").is_empty());
+        let results = editor
+            .inspect(Some("lifetime"), Some("a"), None, false)
+            .unwrap();
+        assert_eq!(results[0].identifier, "'a");
+        assert_eq!(results[0].snippet, "struct Wrap<'a, T, const N: usize> {");
+
+        // `inner` declares its own T: three matches of that one, none of the impl's
+        assert_eq!(lines("generic-param", "T"), vec![2, 3, 6, 6, 7, 11, 11, 11]);
+        assert_eq!(lines("generic-param", "N"), vec![2, 3, 6, 6]);
+        assert_eq!(lines("generic-param", "U"), vec![7, 7, 14]);
+    }
+
+    #[test]
+    fn test_inspect_closures() {
+        let code = r#"
+fn f(items: Vec<Option<u32>>, offset: u32) -> Vec<u32> {
+    let add = |x: u32| x + offset;
+    let log = || helper(items.len());
+    items.into_iter().map(|v| {
+        let y = v.unwrap();
+        y * 2
+    }).collect()
+}
+"#;
+        let editor = RustEditor::new(code).unwrap();
+        let results = editor.inspect(Some("closure"), None, None, false).unwrap();
+        let identifiers: Vec<&str> = results.iter().map(|r| r.identifier.as_str()).collect();
+        assert_eq!(identifiers, vec!["offset", "items", "<closure>"]);
+        assert_eq!(results[0].snippet, "|x: u32| x + offset");
+        assert!(results[2].snippet.contains("\n        let y = v.unwrap();"));
+
+        let results = editor
+            .inspect(Some("closure"), Some("items"), None, false)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].location.line, 4);
+
+        // Transform goes through inspect, so content filters work on closures too
+        let mut editor = RustEditor::new(code).unwrap();
+        let result = editor
+            .transform(&TransformOp {
+                node_type: "closure".to_string(),
+                name_filter: None,
+                content_filter: Some("unwrap".to_string()),
+                action: TransformAction::Replace {
+                    with: "|v| v.unwrap_or(0) * 2".to_string(),
+                },
+                exclude_names: Vec::new(),
+                exclude_in: Vec::new(),
+                name_regex: None,
+            })
+            .unwrap();
+        assert_eq!(result.modified_nodes.len(), 1);
+        assert!(
+            editor
+                .to_string()
+                .contains("items.into_iter().map(|v| v.unwrap_or(0) * 2).collect()")
+        );
+    }
+
+    #[test]
+    fn test_extern_fn_find_and_rename() {
+        let code = r#"unsafe extern "C" {
+    /// Adds two numbers
+    pub fn ffi_add(a: i32, b: i32) -> i32;
+    #[link_name = "raw_free"]
+    fn ffi_free(p: *mut u8);
+}
+
+pub fn add(a: i32, b: i32) -> i32 {
+    unsafe { ffi_add(a, b) }
+}
+"#;
+        let editor = RustEditor::new(code).unwrap();
+        let results = editor
+            .inspect(Some("extern-fn"), None, None, false)
+            .unwrap();
+        let names: Vec<&str> = results.iter().map(|r| r.identifier.as_str()).collect();
+        assert_eq!(names, vec!["ffi_add", "ffi_free"]);
+        assert_eq!(
+            results[0].snippet,
+            "/// Adds two numbers\n    pub fn ffi_add(a: i32, b: i32) -> i32;"
+        );
+
+        let rename = |old: &str, new: &str, keep_link_name: bool| {
+            let mut editor = RustEditor::new(code).unwrap();
+            editor
+                .rename_function(&RenameFunctionOp {
+                    old_name: old.to_string(),
+                    new_name: new.to_string(),
+                    function_path: None,
+                    edit_mode: EditMode::Surgical,
+                    receiver_type: None,
+                    keep_link_name,
+                })
+                .unwrap();
+            editor.to_string()
+        };
+
+        // The declaration and its call sites are renamed; the symbol follows by default
+        let output = rename("ffi_add", "add_ints", false);
+        assert!(output.contains("    pub fn add_ints(a: i32, b: i32) -> i32;"));
+        assert!(output.contains("unsafe { add_ints(a, b) }"));
+        assert!(!output.contains("link_name = \"ffi_add\""));
+
+        let output = rename("ffi_add", "add_ints", true);
+        assert!(output.contains(
+            "    /// Adds two numbers\n    #[link_name = \"ffi_add\"]\n    pub fn add_ints("
+        ));
+
+        // An existing link_name already pins the symbol
+        let output = rename("ffi_free", "free_buf", true);
+        assert_eq!(output.matches("link_name").count(), 1);
+        assert!(output.contains("    fn free_buf(p: *mut u8);"));
+    }
+
+    #[test]
+    fn test_impact_report_categories() {
+        let dir = std::env::temp_dir().join(format!(
+            "rs-hack-impact-{}",
+            crate::state::generate_run_id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("lib.rs"),
+            r#"use crate::config::Config;
+
+pub struct Server {
+    pub config: std::sync::Arc<Config>,
+    pub name: String,
+}
+
+impl From<Config> for Server {
+    fn from(config: Config) -> Self {
+        Server { config: std::sync::Arc::new(config), name: String::new() }
+    }
+}
+
+pub fn load() -> Config {
+    let c: Vec<Config> = Vec::new();
+    let _ = c;
+    Config { port: 1, ..Config::default() }
+}
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("config.rs"),
+            "#[derive(Default)]\npub struct Config {\n    pub port: u16,\n}\n",
+        )
+        .unwrap();
+
+        let report = crate::commands::impact::run(
+            std::slice::from_ref(&dir),
+            "crate::config::Config",
+            &[],
+            &Default::default(),
+        )
+        .unwrap();
+        assert_eq!(report.type_name, "Config");
+        assert_eq!(report.definitions.len(), 1);
+        assert!(report.definitions[0].file_path.ends_with("config.rs"));
+
+        let codes = |category: &str| -> Vec<String> {
+            report
+                .categories
+                .iter()
+                .find(|c| c.category == category)
+                .unwrap()
+                .sites
+                .iter()
+                .map(|s| s.code.clone())
+                .collect()
+        };
+        assert_eq!(codes("struct-literal"), vec!["Config { .. }"]);
+        assert_eq!(
+            codes("field"),
+            vec!["Server::config: std::sync::Arc<Config>"]
+        );
+        assert_eq!(
+            codes("signature"),
+            vec!["fn from(config: Config) -> Self", "fn load() -> Config"]
+        );
+        assert_eq!(codes("impl"), vec!["impl From<Config> for Server"]);
+        assert_eq!(codes("path"), vec!["Config::default"]);
+        assert_eq!(codes("use"), vec!["use crate::config::Config;"]);
+        // `Server { config: .. }` is a different type; the field mention is not counted twice
+        assert_eq!(codes("type-ref"), vec!["Vec<Config>"]);
+        assert_eq!(report.blast_radius, 8);
+        assert_eq!(report.files, 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_describe_type() {
+        let dir = std::env::temp_dir().join(format!(
+            "rs-hack-describe-{}",
+            crate::state::generate_run_id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("lib.rs"),
+            r#"pub struct Counter {
+    pub count: u32,
+    step: u32,
+}
+
+impl Counter {
+    pub fn new() -> Self {
+        Counter { count: 0, step: 1 }
+    }
+
+    fn bump(&mut self) {
+        self.count += self.step;
+    }
+
+    pub fn bump_twice(&mut self) {
+        self.bump();
+        Self::bump(self);
+    }
+}
+
+impl Default for Counter {
+    fn default() -> Self {
+        Counter::new()
+    }
+}
+
+fn main() {
+    let mut c = Counter::new();
+    c.bump_twice();
+}
+"#,
+        )
+        .unwrap();
+
+        let description = crate::commands::describe::run(
+            std::slice::from_ref(&dir),
+            "Counter",
+            &[],
+            &Default::default(),
+        )
+        .unwrap();
+        assert_eq!(description.definitions.len(), 1);
+        assert_eq!(description.definitions[0].kind, "struct");
+        assert_eq!(
+            description.definitions[0].fields,
+            vec!["count: u32", "step: u32"]
+        );
+
+        assert_eq!(description.impls.len(), 2);
+        assert_eq!(description.impls[0].trait_name, None);
+        assert_eq!(description.impls[1].trait_name.as_deref(), Some("Default"));
+        let methods = &description.impls[0].methods;
+        let summary: Vec<(&str, &str, usize, usize)> = methods
+            .iter()
+            .map(|m| {
+                (
+                    m.name.as_str(),
+                    m.signature.as_str(),
+                    m.qualified_calls,
+                    m.method_calls,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("new", "pub fn new() -> Self", 2, 0),
+                ("bump", "fn bump(&mut self)", 2, 1),
+                ("bump_twice", "pub fn bump_twice(&mut self)", 0, 1),
+            ]
+        );
+        assert!(
+            description
+                .references
+                .iter()
+                .any(|r| r.category == "struct-literal" && r.count == 1)
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_remove_impl_method() {
+        let code = r#"struct User;
+
+impl User {
+    /// Old accessor.
+    #[inline]
+    pub fn legacy(&self) {}
+
+    pub fn name(&self) {}
+}
+
+impl User {
+    pub fn other(&self) {}
+
+    pub fn legacy(&self) {}
+}
+
+impl Display for User {
+    fn legacy(&self) {}
+}
+"#;
+        let only_trait = RemoveImplMethodOp {
+            target: "User".to_string(),
+            method_name: "legacy".to_string(),
+            trait_name: Some("std::fmt::Display".to_string()),
+        };
+        let mut editor = RustEditor::new(code).unwrap();
+        let result = editor.remove_impl_method(&only_trait).unwrap();
+        assert!(result.changed);
+        assert_eq!(result.modified_nodes[0].original_content, code);
+        assert_eq!(editor.to_string().matches("fn legacy").count(), 2);
+        assert!(editor.to_string().contains("impl Display for User {\n}"));
+
+        let everywhere = RemoveImplMethodOp {
+            trait_name: None,
+            ..only_trait
+        };
+        let mut editor = RustEditor::new(code).unwrap();
+        assert!(editor.remove_impl_method(&everywhere).unwrap().changed);
+        assert_eq!(
+            editor.to_string(),
+            r#"struct User;
+
+impl User {
+    pub fn name(&self) {}
+}
+
+impl User {
+    pub fn other(&self) {}
+}
+
+impl Display for User {
+}
+"#
+        );
+
+        // Already gone: nothing to do
+        assert!(!editor.remove_impl_method(&everywhere).unwrap().changed);
+    }
+
+    #[test]
+    fn test_add_impl_method_picks_impl_by_trait() {
+        let code = r#"struct User;
+
+impl From<u8> for User {
+    fn from(_: u8) -> Self {
+        User
+    }
+}
+
+impl From<u16> for User {
+    fn from(_: u16) -> Self {
+        User
+    }
+}
+
+impl User {
+    fn id(&self) {}
+}
+"#;
+        let inherent = AddImplMethodOp {
+            target: "User".to_string(),
+            method_def: "fn name(&self) {}".to_string(),
+            position: InsertPosition::Last,
+            trait_name: None,
+            edit_mode: EditMode::default(),
+        };
+        // The inherent impl wins over trait impls that come first
+        let mut editor = RustEditor::new(code).unwrap();
+        assert!(editor.add_impl_method(&inherent).unwrap().changed);
+        assert!(
+            editor
+                .to_string()
+                .contains("impl User {\n    fn id(&self) {}\n    fn name(&self) {}\n}"),
+            "{}",
+            editor
+        );
+
+        // Several impls of the trait: generic arguments pick one
+        let ambiguous = AddImplMethodOp {
+            method_def: "fn extra() {}".to_string(),
+            trait_name: Some("From".to_string()),
+            ..inherent.clone()
+        };
+        let mut editor = RustEditor::new(code).unwrap();
+        let err = editor.add_impl_method(&ambiguous).unwrap_err().to_string();
+        assert!(err.contains("ambiguous"), "{}", err);
+        assert!(err.contains("impl From<u16> for User"), "{}", err);
+
+        let exact = AddImplMethodOp {
+            trait_name: Some("From<u16>".to_string()),
+            ..ambiguous
+        };
+        let mut editor = RustEditor::new(code).unwrap();
+        assert!(editor.add_impl_method(&exact).unwrap().changed);
+        let output = editor.to_string();
+        let u16_impl = output.find("impl From<u16>").unwrap();
+        assert!(output.find("fn extra").unwrap() > u16_impl);
+        assert!(output.find("fn extra").unwrap() < output.find("impl User").unwrap());
+
+        // Only trait impls and no --trait: an error naming them
+        let mut editor = RustEditor::new(&code.replace("impl User {", "impl Other {")).unwrap();
+        let err = editor.add_impl_method(&inherent).unwrap_err().to_string();
+        assert!(err.contains("no inherent impl"), "{}", err);
+        assert!(err.contains("impl From<u8> for User"), "{}", err);
+    }
+
+    #[test]
+    fn test_name_regex_filter() {
+        let code = r#"
+fn debug_dump() {}
+fn run() {}
+struct Foo;
+impl Foo {
+    fn debug_state(&self) {}
+    fn undebug_me(&self) {}
+}
+"#;
+        let transform = |node_type: &str, pattern: &str| {
+            let mut editor = RustEditor::new(code).unwrap();
+            let result = editor
+                .transform(&TransformOp {
+                    node_type: node_type.to_string(),
+                    name_filter: None,
+                    content_filter: None,
+                    action: TransformAction::Remove,
+                    exclude_names: Vec::new(),
+                    exclude_in: Vec::new(),
+                    name_regex: Some(pattern.to_string()),
+                })
+                .unwrap();
+            (result.modified_nodes.len(), editor.to_string())
+        };
+
+        let (removed, output) = transform("function", "^debug_");
+        assert_eq!(removed, 1);
+        assert!(!output.contains("debug_dump") && output.contains("fn run()"));
+
+        // Methods match on their own name as well as on `Foo::debug_state`
+        let (removed, output) = transform("impl-method", "^debug_");
+        assert_eq!(removed, 1);
+        assert!(!output.contains("debug_state") && output.contains("undebug_me"));
+
+        // Bad patterns fail with the regex crate's explanation
+        let err = crate::operations::compile_name_regex("debug_(")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Invalid --name-regex 'debug_('"), "{}", err);
+        assert!(err.contains("unclosed group"), "{}", err);
+    }
+
+    #[test]
+    fn test_find_stream_nodes_per_file() {
+        use crate::commands::find::{FindArgs, FindResult, run, stream_nodes};
+
+        let dir = std::env::temp_dir().join(format!(
+            "rs-hack-stream-{}",
+            crate::state::generate_run_id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.rs"), "fn main() { x.unwrap(); y.unwrap(); }\n").unwrap();
+        std::fs::write(dir.join("b.rs"), "fn other() {}\n").unwrap();
+
+        let args = FindArgs {
+            paths: vec![dir.join("a.rs"), dir.join("b.rs")],
+            node_type: Some("method-call".to_string()),
+            name: Some("unwrap".to_string()),
+            ..Default::default()
+        };
+        let mut per_file = Vec::new();
+        stream_nodes(&args, |results| {
+            per_file.push(results);
+            Ok(())
+        })
+        .unwrap();
+        // Called once per file, matches or not, in the same order as a collected run
+        assert_eq!(
+            per_file.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![2, 0]
+        );
+        let FindResult::Nodes { matches, .. } = run(&args).unwrap() else {
+            panic!("expected node matches");
+        };
+        assert_eq!(
+            serde_json::to_string(&per_file.into_iter().flatten().collect::<Vec<_>>()).unwrap(),
+            serde_json::to_string(&matches).unwrap()
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_find_context_lines() {
+        use crate::commands::find::context_lines;
+
+        let content = "a\nb\nc\nd\ne\nf\n";
+        // Match on lines 3..=4
+        let (before, after) = context_lines(content, 3, 4, 1);
+        assert_eq!(before, vec![(2, "b")]);
+        assert_eq!(after, vec![(5, "e")]);
+        // Clipped at both ends of the file
+        let (before, after) = context_lines(content, 2, 5, 3);
+        assert_eq!(before, vec![(1, "a")]);
+        assert_eq!(after, vec![(6, "f")]);
+    }
+
+    #[test]
+    fn test_rename_function_rustfmt_edit_mode() {
+        // Needs rustfmt on PATH; without it the mode falls back to prettyplease
+        if crate::rustfmt::format_source("").is_err() {
+            return;
+        }
+        let mut editor = RustEditor::new(
+            "// entry point
+fn  process( x:u32 )->u32{ x }
+
+fn main() { process(1); }
+",
+        )
+        .unwrap();
+        let result = editor
+            .rename_function(&RenameFunctionOp {
+                old_name: "process".to_string(),
+                new_name: "handle".to_string(),
+                function_path: None,
+                edit_mode: EditMode::Rustfmt,
+                receiver_type: None,
+                keep_link_name: false,
+            })
+            .unwrap();
+        assert!(result.changed);
+        // Renamed surgically (comments survive), then laid out by rustfmt
+        let output = editor.to_string();
+        assert!(
+            output.starts_with("// entry point\nfn handle(x: u32) -> u32 {\n"),
+            "{}",
+            output
+        );
+        assert!(output.contains("handle(1);"), "{}", output);
+        assert_eq!("rustfmt".parse::<EditMode>(), Ok(EditMode::Rustfmt));
+    }
+
+    #[test]
+    fn test_review_change_applies_accepted_regions() {
+        use crate::execute::{FileChange, Review, ReviewState, review_change};
+
+        let old = "fn main() {\n    a.unwrap();\n    b.unwrap();\n    c.unwrap();\n}\n";
+        let mut editor = RustEditor::new(old).unwrap();
+        let result = editor
+            .transform(&TransformOp {
+                node_type: "method-call".to_string(),
+                name_filter: Some("unwrap".to_string()),
+                content_filter: None,
+                action: TransformAction::Comment,
+                exclude_names: Vec::new(),
+                exclude_in: Vec::new(),
+                name_regex: None,
+            })
+            .unwrap();
+        let change = FileChange {
+            path: "main.rs".into(),
+            old_content: old.to_string(),
+            new_content: editor.to_string(),
+            modified_nodes: result.modified_nodes,
+        };
+
+        let review = |answers: &[Review]| {
+            let mut answers = answers.iter().copied();
+            let mut offered = Vec::new();
+            let mut state = ReviewState::default();
+            let reviewed = review_change(
+                change.clone(),
+                &mut |hunk| {
+                    offered.push(hunk.line);
+                    Ok(answers.next().unwrap())
+                },
+                &mut state,
+            )
+            .unwrap();
+            (reviewed, offered)
+        };
+
+        // Adjacent one-line changes are offered one node at a time
+        let (reviewed, offered) = review(&[Review::Accept, Review::Skip, Review::Accept]);
+        assert_eq!(offered, vec![2, 3, 4]);
+        let (partial, count) = reviewed.unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(
+            partial.new_content,
+            "fn main() {\n    // a.unwrap();\n    b.unwrap();\n    // c.unwrap();\n}\n"
+        );
+        // A partly applied file is backed up whole, so revert restores exactly the original
+        assert_eq!(partial.modified_nodes.len(), 1);
+        assert_eq!(partial.modified_nodes[0].node_type, "file");
+        assert_eq!(partial.modified_nodes[0].original_content, old);
+
+        // "all" accepts the rest without asking; "quit" stops asking and skips the rest
+        let (reviewed, offered) = review(&[Review::Skip, Review::AcceptAll]);
+        assert_eq!(offered, vec![2, 3]);
+        assert_eq!(
+            reviewed.unwrap().0.new_content,
+            "fn main() {\n    a.unwrap();\n    // b.unwrap();\n    // c.unwrap();\n}\n"
+        );
+        let (reviewed, offered) = review(&[Review::Quit]);
+        assert_eq!(offered, vec![2]);
+        assert!(reviewed.is_none());
+    }
+
+    #[test]
+    fn test_find_callers_grouped_by_enclosing_fn() {
+        use crate::commands::find::callers_in;
+
+        let code = r#"
+struct AppState;
+impl AppState {
+    fn update(&mut self) {
+        process(1);
+        let retry = || process(2);
+        self.process();
+        Self::process(self);
+    }
+    fn process(&self) {}
+}
+fn process(x: u32) -> u32 { x }
+fn main() {
+    crate::process(3);
+}
+"#;
+        let syntax = syn::parse_file(code).unwrap();
+        let summary = |callee: &str| {
+            callers_in(&syntax, code, callee)
+                .into_iter()
+                .map(|g| {
+                    let lines: Vec<usize> = g.calls.iter().map(|c| c.line).collect();
+                    (g.caller, g.count, lines)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Closures count toward the function they're in; method calls match by name
+        assert_eq!(
+            summary("process"),
+            vec![
+                ("AppState::update".to_string(), 4, vec![5, 6, 7, 8]),
+                ("main".to_string(), 1, vec![14]),
+            ]
+        );
+        // Qualified: path calls, plus `self.`/`Self::` calls inside the type's impls
+        assert_eq!(
+            summary("AppState::process"),
+            vec![("AppState::update".to_string(), 2, vec![7, 8])]
+        );
+        let groups = callers_in(&syntax, code, "process");
+        assert_eq!(groups[1].calls[0].code, "crate::process(3)");
+    }
+
+    #[test]
+    fn test_find_unused_candidates() {
+        use crate::commands::find::unused_in;
+
+        let lib = r#"
+const LIMIT: u32 = 3;
+const STALE: u32 = 4;
+pub const PUBLIC: u32 = 1;
+struct Helper;
+struct Orphan { x: u32 }
+fn countdown(n: u32) -> u32 { if n == 0 { 0 } else { countdown(n - 1) } }
+#[allow(dead_code)]
+fn kept() {}
+#[derive(serde::Deserialize)]
+struct Settings { #[serde(default = "default_port")] port: u16 }
+fn default_port() -> u16 { 80 }
+impl Helper {
+    fn unused_method(&self) {}
+    pub fn api(&self) {}
+}
+impl Clone for Helper {
+    fn clone(&self) -> Self { Helper }
+}
+fn main() {}
+#[test]
+fn checks() {}
+"#;
+        let other = r#"
+pub fn entry() -> u32 {
+    let _ = Settings { port: 1 };
+    println!("{}", in_macro());
+    LIMIT
+}
+fn in_macro() -> u32 { 1 }
+"#;
+        let sources = vec![
+            ("lib.rs".to_string(), syn::parse_file(lib).unwrap()),
+            ("other.rs".to_string(), syn::parse_file(other).unwrap()),
+        ];
+        let found: Vec<(String, String, usize)> = unused_in(&sources)
+            .into_iter()
+            .map(|c| (c.item_kind, c.name, c.line))
+            .collect();
+        // Uses in another file, in a macro or in a serde attribute count; recursion doesn't
+        assert_eq!(
+            found,
+            vec![
+                ("const".to_string(), "STALE".to_string(), 3),
+                ("struct".to_string(), "Orphan".to_string(), 6),
+                ("fn".to_string(), "countdown".to_string(), 7),
+                ("fn".to_string(), "Helper::unused_method".to_string(), 14),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_duplicate_functions() {
+        use crate::commands::find::duplicate_functions_in;
+
+        let lib = r#"
+fn total(items: &[u32]) -> u32 {
+    // add them up
+    let mut sum = 0;
+    for item in items { sum += *item; }
+    sum
+}
+fn other_total(items: &[u32]) -> u32 {
+    let mut sum = 1;
+    for item in items { sum += *item; }
+    sum
+}
+struct Cart { n: u32 }
+impl Cart {
+    fn get(&self) -> u32 { self.n }
+}
+"#;
+        let other = r#"
+struct Basket { n: u32 }
+impl Basket {
+    fn get(&self) -> u32 { self.n }
+    fn add_all(&self, values: &[u32]) -> u32 {
+        let mut acc = 0;
+        for v in values {
+            acc += *v;
+        }
+        acc
+    }
+}
+"#;
+        let sources = vec![
+            ("lib.rs".to_string(), syn::parse_file(lib).unwrap()),
+            ("other.rs".to_string(), syn::parse_file(other).unwrap()),
+        ];
+        let sites = |node_type| -> Vec<Vec<(String, String, usize)>> {
+            duplicate_functions_in(&sources, node_type)
+                .into_iter()
+                .map(|group| {
+                    group
+                        .functions
+                        .into_iter()
+                        .map(|site| (site.file_path, site.name, site.line))
+                        .collect()
+                })
+                .collect()
+        };
+        // Local names and comments don't count, a different literal does, and getters are
+        // too short to report
+        assert_eq!(
+            sites(None),
+            vec![vec![
+                ("lib.rs".to_string(), "total".to_string(), 2),
+                ("other.rs".to_string(), "Basket::add_all".to_string(), 5),
+            ]]
+        );
+        assert!(sites(Some("function")).is_empty());
+    }
+
+    #[test]
+    fn test_stats_counts_and_largest_functions() {
+        let dir =
+            std::env::temp_dir().join(format!("rs-hack-stats-{}", crate::state::generate_run_id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("lib.rs"),
+            r#"// Widgets
+pub struct Widget;
+pub enum Kind { A }
+pub trait Render { fn render(&self); }
+
+impl Widget {
+    pub fn build(&self) -> u32 {
+        let v: Option<u32> = None;
+        v.unwrap()
+    }
+}
+
+fn stub() {
+    todo!()
+}
+"#,
+        )
+        .unwrap();
+
+        let report =
+            crate::commands::stats::run(std::slice::from_ref(&dir), &[], &Default::default())
+                .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let c = &report.totals;
+        assert_eq!(report.files.len(), 1);
+        assert_eq!((c.lines, c.code_lines), (15, 12));
+        assert_eq!((c.structs, c.enums, c.traits, c.impls), (1, 1, 1, 1));
+        assert_eq!(c.functions, 2);
+        assert_eq!((c.macro_calls, c.unwraps, c.todos), (1, 1, 1));
+
+        let largest: Vec<(&str, usize)> = report
+            .largest_functions
+            .iter()
+            .map(|f| (f.name.as_str(), f.lines))
+            .collect();
+        assert_eq!(largest, vec![("Widget::build", 4), ("stub", 3)]);
+    }
+
+    #[test]
+    fn test_debug_nodes_on_line() {
+        use crate::commands::debug::{DebugArgs, run};
+
+        let dir =
+            std::env::temp_dir().join(format!("rs-hack-debug-{}", crate::state::generate_run_id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("lib.rs");
+        std::fs::write(
+            &file,
+            "struct Config;\n\nimpl Config {\n    fn load(&self) -> usize {\n        self.items().len()\n    }\n}\n",
+        )
+        .unwrap();
+
+        let report = run(&DebugArgs {
+            file: file.clone(),
+            line: 5,
+        })
+        .unwrap();
+        let nodes: Vec<(usize, &str, Option<&str>)> = report
+            .nodes
+            .iter()
+            .map(|n| (n.depth, n.syn_type.as_str(), n.node_type))
+            .collect();
+        assert_eq!(
+            nodes,
+            vec![
+                (0, "Item::Impl", Some("impl")),
+                (1, "ImplItem::Fn", Some("impl-method")),
+                (2, "Expr::MethodCall", Some("method-call")),
+                (3, "Expr::MethodCall", Some("method-call")),
+                (4, "Expr::Path", Some("identifier")),
+            ]
+        );
+        assert_eq!(report.nodes[2].snippet, "self.items().len()");
+
+        assert!(run(&DebugArgs { file, line: 2 }).unwrap().nodes.is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_apply_to_source_for_stdin() {
+        use crate::execute::apply_to_source;
+
+        let add = |name: &str| {
+            Operation::AddStructField(AddStructFieldOp {
+                struct_name: name.to_string(),
+                field_def: "x: i32".to_string(),
+                position: InsertPosition::Last,
+                literal_default: None,
+                where_filter: None,
+                in_fn: None,
+            })
+        };
+        let source = "struct Config {\n    a: u32,\n}\n";
+
+        let edited = apply_to_source(source, &add("Config"), &Default::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(edited, "struct Config {\n    a: u32,\n    x: i32,\n}\n");
+
+        // A missing target is reported rather than echoing the code back
+        assert!(apply_to_source(source, &add("Other"), &Default::default()).is_err());
+        let again = apply_to_source(&edited, &add("Config"), &Default::default()).unwrap();
+        assert!(again.is_none(), "field already present: {:?}", again);
+        assert!(apply_to_source("struct {", &add("Config"), &Default::default()).is_err());
+    }
+
+    #[test]
+    fn test_only_files_prefilter() {
+        use crate::files::{CollectOptions, collect_rust_files_with_options};
+
+        let dir = std::env::temp_dir().join(format!(
+            "rs-hack-only-files-{}",
+            crate::state::generate_run_id()
+        ));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/a.rs"), "fn old_name() {}\n").unwrap();
+        std::fs::write(dir.join("src/b.rs"), "fn other() {}\n").unwrap();
+        std::fs::write(dir.join("src/c.rs"), "fn c() { old_name() }\n").unwrap();
+
+        let only = CollectOptions {
+            only_files: Some("old_name".to_string()),
+            ..CollectOptions::default()
+        };
+        let files = collect_rust_files_with_options(
+            std::slice::from_ref(&dir),
+            &["c.rs".to_string()],
+            &only,
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Composes with --exclude: c.rs mentions it but was excluded
+        assert_eq!(files, vec![dir.join("src/a.rs")]);
+    }
+
+    #[test]
+    fn test_since_keeps_changed_files() {
+        use crate::files::{CollectOptions, changed_since, collect_rust_files_with_options};
+
+        let dir =
+            std::env::temp_dir().join(format!("rs-hack-since-{}", crate::state::generate_run_id()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .current_dir(&dir)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?}", args);
+        };
+        std::fs::write(dir.join("src/a.rs"), "fn a() {}\n").unwrap();
+        std::fs::write(dir.join("src/b.rs"), "fn b() {}\n").unwrap();
+        std::fs::write(dir.join("src/c.rs"), "fn c() {}\n").unwrap();
+        git(&["init", "-q"]);
+        git(&["add", "-A"]);
+        git(&["commit", "-q", "-m", "init"]);
+        std::fs::write(dir.join("src/a.rs"), "fn a() { b() }\n").unwrap();
+        std::fs::write(dir.join("src/c.rs"), "fn c() { b() }\n").unwrap();
+        std::fs::write(dir.join("src/new.rs"), "fn new() {}\n").unwrap();
+
+        let since = CollectOptions {
+            changed_files: Some(changed_since(&dir.join("src"), "HEAD").unwrap()),
+            ..CollectOptions::default()
+        };
+        let files = collect_rust_files_with_options(
+            std::slice::from_ref(&dir),
+            &["c.rs".to_string()],
+            &since,
+        )
+        .unwrap();
+        let bad_ref = changed_since(&dir, "no-such-ref").unwrap_err().to_string();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Modified and untracked files; c.rs changed but was excluded
+        assert_eq!(files, vec![dir.join("src/a.rs"), dir.join("src/new.rs")]);
+        assert!(bad_ref.contains("no-such-ref"), "{}", bad_ref);
+
+        let outside = std::env::temp_dir().join(format!(
+            "rs-hack-since-none-{}",
+            crate::state::generate_run_id()
+        ));
+        std::fs::create_dir_all(&outside).unwrap();
+        let not_a_repo = changed_since(&outside, "HEAD");
+        std::fs::remove_dir_all(&outside).unwrap();
+        assert!(
+            not_a_repo
+                .unwrap_err()
+                .to_string()
+                .contains("needs a git repository")
+        );
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn test_watch_root() {
+        use std::path::{Path, PathBuf};
+
+        use crate::watch::watch_root;
+
+        let dir =
+            std::env::temp_dir().join(format!("rs-hack-watch-{}", crate::state::generate_run_id()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        let existing = watch_root(&dir.join("src"));
+        // A glob is watched from the deepest directory that exists above it
+        let glob = watch_root(&dir.join("src/**/*.rs"));
+        let missing = watch_root(&dir.join("gen/*.rs"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(existing, dir.join("src"));
+        assert_eq!(glob, dir.join("src"));
+        assert_eq!(missing, dir);
+        assert_eq!(watch_root(Path::new("**/*.rs")), PathBuf::from("."));
+    }
+
+    #[test]
+    fn test_backup_path() {
+        use std::path::{Path, PathBuf};
+
+        use crate::execute::{BackupLocation, backup_path};
+
+        assert_eq!(
+            backup_path(Path::new("src/lib.rs"), &BackupLocation::NextToFile),
+            PathBuf::from("src/lib.rs.bak")
+        );
+        let dir = BackupLocation::Dir(PathBuf::from("/tmp/bak"));
+        assert_eq!(
+            backup_path(Path::new("./src/net/mod.rs"), &dir),
+            PathBuf::from("/tmp/bak/src/net/mod.rs.bak")
+        );
+        // Absolute and parent-relative paths stay inside the directory
+        assert_eq!(
+            backup_path(Path::new("/home/me/crate/src/lib.rs"), &dir),
+            PathBuf::from("/tmp/bak/home/me/crate/src/lib.rs.bak")
+        );
+        assert_eq!(
+            backup_path(Path::new("../other/src/lib.rs"), &dir),
+            PathBuf::from("/tmp/bak/other/src/lib.rs.bak")
+        );
+    }
+
+    #[test]
+    fn test_config_file_parse_and_find() {
+        use crate::config::{CONFIG_FILE, Config};
+
+        let config = Config::parse(
+            r#"
+# project defaults
+paths = ["src"]  # relative to this file
+exclude = [
+    "target",
+    'vendor/#keep',
+]
+format = "diff"
+edit_mode = "rustfmt"
+local_state = true
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.paths, vec![std::path::PathBuf::from("src")]);
+        assert_eq!(config.exclude, vec!["target", "vendor/#keep"]);
+        assert_eq!(config.format.as_deref(), Some("diff"));
+        assert_eq!(config.edit_mode, Some(EditMode::Rustfmt));
+        assert!(config.local_state);
+
+        let err = |text: &str| format!("{:#}", Config::parse(text).unwrap_err());
+        assert!(err("pahts = [\"src\"]").contains("unknown field `pahts`"));
+        assert!(err("edit_mode = \"fancy\"").contains("line 1"));
+        assert!(err("[defaults]").contains("unknown field `defaults`"));
+
+        // Found from a subdirectory; paths resolve against the config's directory
+        let dir = std::env::temp_dir().join(format!(
+            "rs-hack-config-{}",
+            crate::state::generate_run_id()
+        ));
+        std::fs::create_dir_all(dir.join("src/net")).unwrap();
+        std::fs::write(dir.join(CONFIG_FILE), "paths = [\"src\"]\n").unwrap();
+        let found = Config::find(&dir.join("src/net")).unwrap().unwrap();
+        let found_at_root = Config::find(&dir).unwrap().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(found.paths, vec![dir.join("src")]);
+        assert_eq!(found.source, dir.join(CONFIG_FILE));
+        assert_eq!(found_at_root.paths, vec![std::path::PathBuf::from("src")]);
+    }
 }
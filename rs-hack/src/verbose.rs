@@ -0,0 +1,70 @@
+//! `--verbose`: per-file diagnostics for slow or surprising runs.
+//!
+//! Each file searched or edited gets a line saying whether it parsed, how many nodes
+//! matched and how long it took, and files a lookup skipped because they don't parse are
+//! named instead of silently left out. Everything goes to stderr, so `--format json` and
+//! other machine-readable stdout stays intact.
+
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The `--verbose` log, off by default. Clones share the files already reported by
+/// `skipped`, so one run's lookups and its operation name each file once.
+#[derive(Debug, Clone, Default)]
+pub struct Verbose {
+    /// Files already reported by `skipped`; `None` when the log is off
+    skipped: Option<Arc<Mutex<HashSet<PathBuf>>>>,
+}
+
+impl Verbose {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            skipped: enabled.then(Arc::default),
+        }
+    }
+
+    pub const fn enabled(&self) -> bool {
+        self.skipped.is_some()
+    }
+
+    /// `file` parsed and was searched: `matched` nodes in `elapsed` (reading included).
+    pub fn file_done(&self, file: &Path, matched: usize, elapsed: Duration) {
+        if self.enabled() {
+            eprintln!(
+                "[verbose] {}: parsed, {} node(s) matched in {:.1?}",
+                file.display(),
+                matched,
+                elapsed
+            );
+        }
+    }
+
+    /// `file` parsed, but the operation failed on it.
+    pub fn file_failed(&self, file: &Path, error: &dyn Display, elapsed: Duration) {
+        if self.enabled() {
+            eprintln!(
+                "[verbose] {}: parsed, failed after {:.1?}: {}",
+                file.display(),
+                elapsed,
+                error
+            );
+        }
+    }
+
+    /// `file` was left out because it doesn't parse; `error` says where. Reported once.
+    pub fn skipped(&self, file: &Path, error: &dyn Display) {
+        let Some(skipped) = &self.skipped else {
+            return;
+        };
+        if skipped
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(file.to_path_buf())
+        {
+            eprintln!("[verbose] skipped {}: {}", file.display(), error);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! `watch`: re-run a command whenever a `.rs` file under the watched paths changes.
+//!
+//! Editors often write a file several times per save (temp file, rename, metadata), so
+//! changes are debounced: a run starts once no change has come in for the debounce window.
+//! Ctrl-C stops the loop instead of killing the process mid-output.
+
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use notify::{EventKind, RecursiveMode, Watcher};
+
+enum Signal {
+    Changed,
+    Stop,
+}
+
+/// Call `run` now, then again after each burst of `.rs` changes under `paths`, until
+/// Ctrl-C. Paths that don't exist yet (glob patterns, say) are watched from their nearest
+/// existing directory.
+pub fn watch(
+    paths: &[PathBuf],
+    debounce: Duration,
+    mut run: impl FnMut() -> Result<()>,
+) -> Result<()> {
+    let (tx, rx) = mpsc::channel();
+
+    let changes = tx.clone();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event
+            && !matches!(event.kind, EventKind::Access(_))
+            && event
+                .paths
+                .iter()
+                .any(|path| path.extension().is_some_and(|ext| ext == "rs"))
+        {
+            let _ = changes.send(Signal::Changed);
+        }
+    })
+    .context("Failed to start the file watcher")?;
+    for path in paths {
+        let root = watch_root(path);
+        let mode = if root.is_dir() {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        watcher
+            .watch(&root, mode)
+            .with_context(|| format!("Failed to watch {}", root.display()))?;
+    }
+
+    ctrlc::set_handler(move || {
+        let _ = tx.send(Signal::Stop);
+    })
+    .context("Failed to set the Ctrl-C handler")?;
+
+    run()?;
+    while matches!(rx.recv(), Ok(Signal::Changed)) {
+        loop {
+            match rx.recv_timeout(debounce) {
+                Ok(Signal::Changed) => {}
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Ok(Signal::Stop) | Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+        run()?;
+    }
+    Ok(())
+}
+
+/// The path itself when it exists; otherwise the deepest existing directory above its
+/// first glob component (or `.`).
+pub(crate) fn watch_root(path: &Path) -> PathBuf {
+    if path.exists() {
+        return path.to_path_buf();
+    }
+    let mut root = PathBuf::new();
+    for component in path.components() {
+        if let Component::Normal(part) = component
+            && part.to_string_lossy().contains(['*', '?', '['])
+        {
+            break;
+        }
+        root.push(component);
+    }
+    while !root.as_os_str().is_empty() && !root.is_dir() {
+        root.pop();
+    }
+    if root.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        root
+    }
+}